@@ -0,0 +1,36 @@
+//! Compare the parse time with and without the `trace` feature:
+//!
+//! ```text
+//! cargo bench --bench parse -- --save-baseline lean
+//! cargo bench --bench parse --features trace -- --baseline lean
+//! ```
+#[macro_use]
+extern crate criterion;
+
+use claybrick::{build::PdfBuilder, simple_encode::SimpleEncoder};
+use criterion::Criterion;
+
+/// Pages of the generated document, about 1 MB.
+const PAGES: usize = 2000;
+
+fn document() -> Vec<u8> {
+    let mut builder = PdfBuilder::new();
+    for i in 0..PAGES {
+        let page = builder.add_page(595.0, 842.0);
+        let content = format!("BT /F1 12 Tf 72 770 Td (Page {}) Tj ET 0 0 m 595 842 l S", i);
+        builder.set_page_content(page, content);
+    }
+    let mut out = Vec::new();
+    SimpleEncoder::write_with(&builder.finish().unwrap(), &mut out, Default::default()).unwrap();
+    out
+}
+
+pub fn bench_parse(c: &mut Criterion) {
+    let input = document();
+    c.bench_function("parse-mid-size", |b| {
+        b.iter(|| claybrick::read_bytes(input.clone()).unwrap())
+    });
+}
+
+criterion_group!(group_parse, bench_parse);
+criterion_main!(group_parse);
@@ -1,3 +1,4 @@
+use claybrick::pdf::Reference;
 use std::path::PathBuf;
 use structopt::StructOpt;
 
@@ -8,6 +9,14 @@ struct Opt {
     /// Output file
     #[structopt(short, long, parse(from_os_str))]
     input: PathBuf,
+
+    /// Additionally print the object with this object number.
+    #[structopt(short, long)]
+    object: Option<u32>,
+
+    /// Generation number of the additional object.
+    #[structopt(short, long, default_value = "0")]
+    generation: u32,
 }
 
 pub fn main() {
@@ -23,5 +32,16 @@ pub fn main() {
         }
     };
 
-    println!("Catalog: {:#?}", pdf.catalog());
+    match pdf.catalog() {
+        Ok(catalog) => println!("Catalog: {:#?}", catalog),
+        Err(e) => println!("Invalid catalog: {}", e),
+    }
+
+    if let Some(index) = opt.object {
+        let reference = Reference::new(index, opt.generation);
+        match pdf.dereference(&reference) {
+            Some(object) => println!("Object {} {}: {}", reference.index(), reference.generation(), object),
+            None => println!("Object {} {} not found", reference.index(), reference.generation()),
+        }
+    }
 }
@@ -0,0 +1,77 @@
+use claybrick::pdf::{DumpOptions, Object};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// List all objects of the given PDF file with their type and references.
+#[derive(StructOpt, Debug)]
+#[structopt(name = "dump")]
+struct Opt {
+    /// Output file
+    #[structopt(short, long, parse(from_os_str))]
+    input: PathBuf,
+
+    /// List the objects of each section instead of the newest versions only.
+    #[structopt(short, long)]
+    sections: bool,
+
+    /// Expand the references from the trailer instead of listing the objects.
+    #[structopt(short, long)]
+    tree: bool,
+
+    /// How many levels of references are expanded with `--tree`.
+    #[structopt(short, long)]
+    depth: Option<usize>,
+
+    /// Don't show the length of the stream data.
+    #[structopt(long)]
+    no_stream_lengths: bool,
+
+    /// Also list free objects and objects that are not reachable with `--tree`.
+    #[structopt(short, long)]
+    unused: bool,
+}
+
+fn print_object(number: usize, generation: u32, obj: &Object) {
+    let dict_type = match obj {
+        Object::Dictionary(dict) => dict.get(&b"Type"[..]),
+        Object::Stream(stream) => stream.dictionary.get(&b"Type"[..]),
+        _ => None,
+    };
+    match dict_type.and_then(Object::name) {
+        Some(name) => println!("{} {} {} /{}", number, generation, obj.type_name(), name),
+        None => println!("{} {} {}", number, generation, obj.type_name()),
+    }
+}
+
+pub fn main() {
+    env_logger::init();
+    let opt = Opt::from_args();
+
+    let pdf = match claybrick::read_file(opt.input.as_path()) {
+        Ok(pdf) => pdf,
+        Err(e) => {
+            log::error!("Error while parsing: {:?}", e);
+            return;
+        }
+    };
+
+    if opt.sections {
+        for (index, section) in pdf.sections().iter().enumerate() {
+            println!("Section {}: {} objects", index, section.len());
+            for (number, generation, obj) in section.objects() {
+                print_object(number, generation, obj);
+            }
+        }
+    } else {
+        let options = DumpOptions {
+            tree: opt.tree,
+            depth: opt.depth.unwrap_or(usize::MAX),
+            stream_lengths: !opt.no_stream_lengths,
+            unused_objects: opt.unused,
+        };
+        let mut out = String::new();
+        pdf.dump(&mut out, options).expect("writing to a string can't fail");
+        println!("{} objects", pdf.object_count());
+        print!("{}", out);
+    }
+}
@@ -0,0 +1,51 @@
+use claybrick::pdf::serialize::{with_options, SerializeOptions, StreamData};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Print the parsed objects of the given PDF file as JSON.
+#[derive(StructOpt, Debug)]
+#[structopt(name = "claybrick-dump-json")]
+struct Opt {
+    /// Input file
+    #[structopt(short, long, parse(from_os_str))]
+    input: PathBuf,
+
+    /// Write stream data as hex instead of base64.
+    #[structopt(long)]
+    hex: bool,
+
+    /// Don't write stream data.
+    #[structopt(long)]
+    omit_data: bool,
+
+    /// Only write the first bytes of each stream.
+    #[structopt(long)]
+    max_stream_bytes: Option<usize>,
+}
+
+pub fn main() {
+    env_logger::init();
+    let opt = Opt::from_args();
+
+    let pdf = match claybrick::read_file(opt.input.as_path()) {
+        Ok(pdf) => pdf,
+        Err(e) => {
+            log::error!("Error while parsing: {:?}", e);
+            return;
+        }
+    };
+
+    let stream_data = if opt.omit_data {
+        StreamData::Omit
+    } else if opt.hex {
+        StreamData::Hex
+    } else {
+        StreamData::Base64
+    };
+    let options = SerializeOptions {
+        stream_data,
+        max_stream_bytes: opt.max_stream_bytes,
+    };
+    serde_json::to_writer_pretty(std::io::stdout().lock(), &with_options(&pdf, options)).unwrap();
+    println!();
+}
@@ -0,0 +1,42 @@
+use claybrick::simple_encode::{EncodeOptions, XrefStyle};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Read a PDF file and write it again using the simple encoder.
+#[derive(StructOpt, Debug)]
+#[structopt(name = "claybrick-rw")]
+struct Opt {
+    /// Input file
+    #[structopt(short, long, parse(from_os_str))]
+    input: PathBuf,
+
+    /// Output file
+    #[structopt(short, long, parse(from_os_str))]
+    output: PathBuf,
+
+    /// Write a classic xref table instead of a xref stream.
+    #[structopt(long)]
+    table: bool,
+
+    /// Compress streams that are not compressed yet.
+    #[structopt(long)]
+    compress: bool,
+
+    /// Remove objects that are not referenced.
+    #[structopt(long)]
+    prune: bool,
+}
+
+pub fn main() {
+    env_logger::init();
+    let opt = Opt::from_args();
+
+    let options = EncodeOptions {
+        xref: if opt.table { XrefStyle::Table } else { XrefStyle::Stream },
+        compress_streams: opt.compress,
+        prune_unreferenced: opt.prune,
+        ..Default::default()
+    };
+    let pdf = claybrick::read_file(&opt.input).expect("Error while reading");
+    claybrick::write_file(&pdf, &opt.output, &options).expect("Error while writing");
+}
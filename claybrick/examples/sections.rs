@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Print the objects that each incremental update of the given PDF file added
+/// or replaced.
+#[derive(StructOpt, Debug)]
+#[structopt(name = "sections")]
+struct Opt {
+    /// Output file
+    #[structopt(short, long, parse(from_os_str))]
+    input: PathBuf,
+}
+
+pub fn main() {
+    env_logger::init();
+    let opt = Opt::from_args();
+
+    let pdf = match claybrick::read_file(opt.input.as_path()) {
+        Ok(pdf) => pdf,
+        Err(e) => {
+            log::error!("Error while parsing: {:?}", e);
+            return;
+        }
+    };
+
+    // The sections are sorted from the newest to the oldest.
+    let sections = pdf.sections();
+    for (index, section) in sections.iter().enumerate().rev() {
+        let older = &sections[index + 1..];
+        let (replaced, added): (Vec<_>, Vec<_>) = section
+            .objects()
+            .map(|(number, ..)| number)
+            .partition(|&number| older.iter().any(|s| s.contains(number)));
+        println!(
+            "Section {} (Size {}): {} added, {} replaced",
+            sections.len() - index - 1,
+            section.trailer().size,
+            added.len(),
+            replaced.len()
+        );
+        if !replaced.is_empty() {
+            println!("  replaced: {:?}", replaced);
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Print a summary of the objects, filters and sections of the given PDF file.
+#[derive(StructOpt, Debug)]
+#[structopt(name = "claybrick-stats")]
+struct Opt {
+    /// Input file
+    #[structopt(short, long, parse(from_os_str))]
+    input: PathBuf,
+}
+
+pub fn main() {
+    env_logger::init();
+    let opt = Opt::from_args();
+
+    let pdf = match claybrick::read_file(opt.input.as_path()) {
+        Ok(pdf) => pdf,
+        Err(e) => {
+            log::error!("Error while parsing: {:?}", e);
+            return;
+        }
+    };
+
+    let stats = pdf.stats();
+    println!(
+        "Sections: {} ({} incremental updates)",
+        stats.sections,
+        stats.incremental_updates()
+    );
+    println!("Xref: {:?}", stats.xref_layout);
+    match stats.pages {
+        Some(pages) => println!("Pages: {}", pages),
+        None => println!("Pages: invalid page tree"),
+    }
+    println!("Objects:");
+    for (type_name, count) in &stats.object_types {
+        println!("  {}: {}", type_name, count);
+    }
+    println!("Compressed objects: {}", stats.compressed_objects);
+    println!("Stream bytes: {}", stats.stream_bytes);
+    println!("Filters:");
+    for (filter, count) in &stats.filters {
+        println!("  {}: {}", filter, count);
+    }
+}
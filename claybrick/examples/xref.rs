@@ -21,7 +21,7 @@ pub fn main() {
     let mut buf = Vec::new();
     input_file.read_to_end(&mut buf).unwrap();
     let info = TracableInfo::new().forward(true).backward(true);
-    let input = LocatedSpan::new_extra(&buf[..], info);
+    let input = LocatedSpan::new_extra(&buf[..], info.into());
 
     // find start of the xref section
     let (remainder_xref, _) = claybrick::parse::eof_marker_tail(input).unwrap();
@@ -0,0 +1,472 @@
+//! Construction of new documents.
+//!
+//! ```
+//! use claybrick::build::PdfBuilder;
+//!
+//! let mut builder = PdfBuilder::new();
+//! let page = builder.add_page(595.0, 842.0);
+//! builder.set_page_content(page, &b"0 0 m 595 842 l S"[..]);
+//! let pdf = builder.finish().unwrap();
+//! assert_eq!(pdf.page_count(), Ok(1));
+//! ```
+
+use fnv::FnvHashMap;
+
+use attachment::{add_attachments, Attachment};
+
+use crate::{
+    pdf::{
+        date::PdfDate,
+        document::{
+            dict_types::{CATALOG, PAGE, PAGES},
+            AfRelationship, K_CONTENTS, K_COUNT, K_KIDS, K_LENGTH, K_MEDIA_BOX, K_PAGES, K_PARENT, K_RESOURCES, K_TYPE,
+        },
+        object::integer,
+        Dictionary, IndirectObject, IntegerOutOfRange, Name, Object, PdfSection, RawPdf, Rectangle, Reference, Stream,
+        ToObject, Trailer, Xref,
+    },
+    simple_encode::BINARY_INDICATOR,
+};
+
+pub use compose::{merge, MergeError};
+pub use content::ContentBuilder;
+pub use font::{FontHandle, StandardFont, UnmappableChar};
+
+pub(crate) use compose::extract_pages;
+
+mod attachment;
+mod compose;
+mod content;
+mod font;
+
+const K_TITLE: &[u8] = b"Title";
+const K_AUTHOR: &[u8] = b"Author";
+const K_SUBJECT: &[u8] = b"Subject";
+const K_KEYWORDS: &[u8] = b"Keywords";
+const K_CREATOR: &[u8] = b"Creator";
+const K_PRODUCER: &[u8] = b"Producer";
+const K_CREATION_DATE: &[u8] = b"CreationDate";
+const K_MOD_DATE: &[u8] = b"ModDate";
+const K_FONT: &[u8] = b"Font";
+
+const CATALOG_NUMBER: u32 = 1;
+const PAGES_NUMBER: u32 = 2;
+
+/// Errors of [PdfBuilder::finish].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// The value, e.g. the size of an attached file, is too large for an
+    /// integer.
+    ValueOutOfRange(usize),
+}
+
+impl From<IntegerOutOfRange> for BuildError {
+    fn from(IntegerOutOfRange(value): IntegerOutOfRange) -> Self {
+        BuildError::ValueOutOfRange(value)
+    }
+}
+
+/// The entries of the document information dictionary of a new document.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Info {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Option<String>,
+    /// The application that created the original content.
+    pub creator: Option<String>,
+    /// The application that converted the content to PDF.
+    pub producer: Option<String>,
+    pub creation_date: Option<PdfDate>,
+    pub mod_date: Option<PdfDate>,
+}
+
+/// Identifies a page added with [PdfBuilder::add_page].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PageHandle(usize);
+
+#[derive(Debug)]
+struct NewPage {
+    media_box: Rectangle,
+    content: Option<Vec<u8>>,
+    resources: Dictionary,
+}
+
+/// Builds a document with a flat page tree.
+#[derive(Debug, Default)]
+pub struct PdfBuilder {
+    pages: Vec<NewPage>,
+    fonts: Vec<StandardFont>,
+    info: Option<Info>,
+    attachments: Vec<Attachment>,
+}
+
+impl Info {
+    fn into_dictionary(self) -> Dictionary {
+        let texts = [
+            (K_TITLE, self.title),
+            (K_AUTHOR, self.author),
+            (K_SUBJECT, self.subject),
+            (K_KEYWORDS, self.keywords),
+            (K_CREATOR, self.creator),
+            (K_PRODUCER, self.producer),
+        ];
+        let dates = [(K_CREATION_DATE, self.creation_date), (K_MOD_DATE, self.mod_date)];
+        texts
+            .into_iter()
+            .filter_map(|(key, text)| Some((key, text?.to_object())))
+            .chain(
+                dates
+                    .into_iter()
+                    .filter_map(|(key, date)| Some((key, Object::String(date?.format().into_bytes().into())))),
+            )
+            .map(|(key, value)| (key.to_vec().into(), value))
+            .collect()
+    }
+}
+
+impl PdfBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a page with the given size in points (1/72 inch). The page is
+    /// empty until [PdfBuilder::set_page_content] is called.
+    pub fn add_page(&mut self, width: f32, height: f32) -> PageHandle {
+        self.pages.push(NewPage {
+            media_box: Rectangle::new(0.0, 0.0, width, height),
+            content: None,
+            resources: Dictionary::new(),
+        });
+        PageHandle(self.pages.len() - 1)
+    }
+
+    /// Set the unencoded content stream of the page.
+    pub fn set_page_content(&mut self, page: PageHandle, content: impl Into<Vec<u8>>) {
+        self.pages[page.0].content = Some(content.into());
+    }
+
+    /// Set the resource dictionary of the page, e.g. the fonts used by the
+    /// content stream.
+    pub fn set_page_resources(&mut self, page: PageHandle, resources: Dictionary) {
+        self.pages[page.0].resources = resources;
+    }
+
+    /// Add one of the standard 14 fonts to the `/Font` resources of every
+    /// page. Adding a font twice returns the same handle.
+    ///
+    /// The font is named by its `/BaseFont`, e.g. `/Helvetica-Bold`. Entries
+    /// with the same name in [PdfBuilder::set_page_resources] take
+    /// precedence.
+    pub fn add_standard_font(&mut self, font: StandardFont) -> FontHandle {
+        if !self.fonts.contains(&font) {
+            self.fonts.push(font);
+        }
+        FontHandle::new(font.base_font().as_bytes().to_vec().into(), font)
+    }
+
+    pub fn set_info(&mut self, info: Info) {
+        self.info = Some(info);
+    }
+
+    /// Embed a file with the given MIME type, e.g. the XML of a Factur-X
+    /// invoice with [AfRelationship::Data]. The file is added to the
+    /// `/EmbeddedFiles` name tree and the `/AF` array of the catalog and
+    /// replaces an earlier file with the same name.
+    pub fn attach_file(
+        &mut self,
+        name: impl Into<String>,
+        data: impl Into<Vec<u8>>,
+        mime: &str,
+        relationship: AfRelationship,
+    ) {
+        let name = name.into();
+        self.attachments.retain(|attachment| attachment.name != name);
+        self.attachments.push(Attachment {
+            name,
+            data: data.into(),
+            mime: mime.to_string(),
+            relationship,
+            mod_date: PdfDate::now(),
+        });
+    }
+
+    /// Create the document.
+    ///
+    /// The catalog is object 1 and the page tree root object 2, followed by
+    /// the fonts, each page and its content stream, and the attached files.
+    pub fn finish(self) -> Result<RawPdf, BuildError> {
+        let mut objects = NewObjects::new();
+        let fonts = self
+            .fonts
+            .iter()
+            .map(|font| {
+                let number = objects.add(Object::Dictionary(font.dictionary()));
+                (font.base_font().as_bytes().to_vec().into(), reference(number))
+            })
+            .collect::<Vec<_>>();
+        let mut kids = Vec::with_capacity(self.pages.len());
+        for mut page in self.pages {
+            if !fonts.is_empty() {
+                add_fonts(&mut page.resources, &fonts);
+            }
+            let page_number = objects.reserve();
+            kids.push(page_number);
+
+            let mut dict = Dictionary::from([
+                (K_TYPE.to_vec().into(), name(PAGE)),
+                (K_PARENT.to_vec().into(), reference(PAGES_NUMBER)),
+                (K_RESOURCES.to_vec().into(), Object::Dictionary(page.resources)),
+                (K_MEDIA_BOX.to_vec().into(), Object::Array(page.media_box.into())),
+            ]);
+            if let Some(content) = page.content {
+                let length = integer(content.len())?;
+                let content_number = objects.add(Object::Stream(Stream {
+                    dictionary: Dictionary::from([(K_LENGTH.to_vec().into(), length)]),
+                    data: content.into(),
+                    provenance: Default::default(),
+                }));
+                dict.insert(K_CONTENTS.to_vec().into(), reference(content_number));
+            }
+            objects.insert(page_number, Object::Dictionary(dict));
+        }
+
+        let info = self
+            .info
+            .map(|info| objects.add(Object::Dictionary(info.into_dictionary())));
+        add_attachments(&mut objects, self.attachments)?;
+        Ok(objects.into_document((1, 7), &kids, info)?)
+    }
+}
+
+/// Add the fonts to the `/Font` entry of `resources` unless there's a font
+/// with the same name already.
+fn add_fonts(resources: &mut Dictionary, fonts: &[(Name, Object)]) {
+    if resources.get(K_FONT).is_none() {
+        resources.insert(K_FONT.to_vec().into(), Object::Dictionary(Dictionary::new()));
+    }
+    let Some(Object::Dictionary(font_resources)) = resources.get_mut(K_FONT) else {
+        log::warn!("The /Font resources are not a direct dictionary, can't add the standard fonts");
+        return;
+    };
+    for (name, font) in fonts {
+        if font_resources.get(name).is_none() {
+            font_resources.insert(name.clone(), font.clone());
+        }
+    }
+}
+
+fn name(n: &[u8]) -> Object {
+    Object::Name(n.to_vec().into())
+}
+
+fn reference(number: u32) -> Object {
+    Object::Reference(Reference::new(number, 0))
+}
+
+/// The objects of a new document with generation 0.
+///
+/// Object 1 and 2 are reserved for the catalog and the page tree root, which
+/// are added by [NewObjects::into_document]. Pages must use object 2 as
+/// their parent.
+#[derive(Debug)]
+pub(crate) struct NewObjects {
+    objects: FnvHashMap<usize, Object>,
+    next_number: u32,
+    /// Catalog entries besides `/Type` and `/Pages`.
+    catalog: Dictionary,
+}
+
+impl NewObjects {
+    pub(crate) fn new() -> Self {
+        Self {
+            objects: FnvHashMap::default(),
+            next_number: PAGES_NUMBER + 1,
+            catalog: Dictionary::new(),
+        }
+    }
+
+    /// Allocate an object number for an object that is inserted later.
+    pub(crate) fn reserve(&mut self) -> u32 {
+        self.next_number += 1;
+        self.next_number - 1
+    }
+
+    pub(crate) fn insert(&mut self, number: u32, obj: Object) {
+        self.objects
+            .insert(number as usize, Object::Indirect(IndirectObject::new(number, 0, obj)));
+    }
+
+    pub(crate) fn add(&mut self, obj: Object) -> u32 {
+        let number = self.reserve();
+        self.insert(number, obj);
+        number
+    }
+
+    pub(crate) fn insert_catalog_entry(&mut self, key: &[u8], value: Object) {
+        self.catalog.insert(key.to_vec().into(), value);
+    }
+
+    /// Add the catalog and a flat page tree with the given pages.
+    pub(crate) fn into_document(
+        mut self,
+        version: (u8, u8),
+        pages: &[u32],
+        info: Option<u32>,
+    ) -> Result<RawPdf, IntegerOutOfRange> {
+        let mut catalog = Dictionary::from([
+            (K_TYPE.to_vec().into(), name(CATALOG)),
+            (K_PAGES.to_vec().into(), reference(PAGES_NUMBER)),
+        ]);
+        for (key, value) in std::mem::take(&mut self.catalog) {
+            catalog.insert(key, value);
+        }
+        self.insert(CATALOG_NUMBER, Object::Dictionary(catalog));
+        self.insert(
+            PAGES_NUMBER,
+            Object::Dictionary(Dictionary::from([
+                (K_TYPE.to_vec().into(), name(PAGES)),
+                (
+                    K_KIDS.to_vec().into(),
+                    Object::Array(pages.iter().map(|&n| reference(n)).collect::<Vec<_>>().into()),
+                ),
+                (K_COUNT.to_vec().into(), integer(pages.len())?),
+            ])),
+        );
+
+        let trailer = Trailer {
+            size: self.next_number as usize,
+            previous: None,
+            root: Reference::new(CATALOG_NUMBER, 0),
+            encrypt: None,
+            info: info.map(|number| Reference::new(number, 0)),
+            id: None,
+            x_ref_stm: None,
+        };
+        Ok(RawPdf {
+            version,
+            binary_indicator: Some(BINARY_INDICATOR.to_vec()),
+            header_offset: 0,
+            sections: vec![PdfSection {
+                objects: self.objects,
+                trailer,
+                xref: Xref::new(Vec::new()),
+                object_spans: Default::default(),
+                stream_spans: Default::default(),
+            }],
+            allocated: Default::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::parse_complete,
+        simple_encode::{EncodeOptions, SimpleEncoder},
+    };
+
+    use super::*;
+
+    #[test]
+    fn hello_world() {
+        let mut builder = PdfBuilder::new();
+        let page = builder.add_page(612.0, 792.0);
+        let font = Dictionary::from([
+            (b"Type".to_vec().into(), Object::Name(b"Font".to_vec().into())),
+            (b"Subtype".to_vec().into(), Object::Name(b"Type1".to_vec().into())),
+            (b"BaseFont".to_vec().into(), Object::Name(b"Helvetica".to_vec().into())),
+        ]);
+        builder.set_page_resources(
+            page,
+            Dictionary::from([(
+                b"Font".to_vec().into(),
+                Object::Dictionary(Dictionary::from([(b"F1".to_vec().into(), Object::Dictionary(font))])),
+            )]),
+        );
+        builder.set_page_content(page, &b"BT /F1 24 Tf 72 700 Td (Hello World) Tj ET"[..]);
+        builder.set_info(Info {
+            title: Some("Hello World".to_string()),
+            author: Some("Zoë".to_string()),
+            ..Default::default()
+        });
+        builder.add_page(200.0, 100.0);
+
+        let mut out = Vec::new();
+        SimpleEncoder::write_with(&builder.finish().unwrap(), &mut out, EncodeOptions::default()).unwrap();
+        let (_, pdf) = parse_complete(out[..].into()).unwrap();
+
+        let catalog = pdf.catalog().unwrap();
+        assert_eq!(catalog.page_count(), Ok(2));
+        let first = catalog.page(0).unwrap();
+        assert_eq!(first.media_box(), Some(Rectangle::new(0.0, 0.0, 612.0, 792.0)));
+        assert!(first.resources().unwrap().contains_key(&b"Font"[..]));
+        let contents = first.contents();
+        assert_eq!(contents.len(), 1);
+        assert_eq!(&contents[0].data[..], b"BT /F1 24 Tf 72 700 Td (Hello World) Tj ET");
+        let second = catalog.page(1).unwrap();
+        assert!(second.contents().is_empty());
+        assert_eq!(second.media_box(), Some(Rectangle::new(0.0, 0.0, 200.0, 100.0)));
+
+        let info = pdf.info().unwrap();
+        assert_eq!(info.title().as_deref(), Some("Hello World"));
+        assert_eq!(info.author().as_deref(), Some("Zoë"));
+    }
+
+    #[test]
+    fn standard_fonts() {
+        let mut builder = PdfBuilder::new();
+        let first = builder.add_page(100.0, 100.0);
+        builder.add_page(100.0, 100.0);
+        // the page's own resources take precedence
+        let own = Dictionary::from([(b"Courier".to_vec().into(), Object::Null)]);
+        builder.set_page_resources(
+            first,
+            Dictionary::from([(K_FONT.to_vec().into(), Object::Dictionary(own))]),
+        );
+        let times = builder.add_standard_font(StandardFont::TimesRoman);
+        assert_eq!(builder.add_standard_font(StandardFont::TimesRoman), times);
+        builder.add_standard_font(StandardFont::Courier);
+        let pdf = builder.finish().unwrap();
+
+        let fonts = |index| {
+            let page = pdf.page(index).unwrap();
+            page.resources()
+                .unwrap()
+                .get(K_FONT)
+                .and_then(Object::dictionary)
+                .unwrap()
+                .clone()
+        };
+        let first = fonts(0);
+        assert_eq!(first.len(), 2);
+        assert_eq!(first.get(&b"Courier"[..]), Some(&Object::Null));
+        let second = fonts(1);
+        assert_eq!(second.len(), 2);
+        let times = pdf
+            .resolve(second.get(&times.name()[..]).unwrap())
+            .dictionary()
+            .unwrap();
+        assert_eq!(times.get(&b"BaseFont"[..]), Some(&name(b"Times-Roman")));
+        assert_eq!(times.get(&b"Encoding"[..]), Some(&name(b"WinAnsiEncoding")));
+    }
+
+    #[test]
+    fn control_characters_in_info() {
+        let mut builder = PdfBuilder::new();
+        builder.add_page(100.0, 100.0);
+        builder.set_info(Info {
+            title: Some("a\rb".to_string()),
+            subject: Some("a\r\nb\tc".to_string()),
+            ..Default::default()
+        });
+
+        let mut out = Vec::new();
+        SimpleEncoder::write_with(&builder.finish().unwrap(), &mut out, EncodeOptions::default()).unwrap();
+        let (_, pdf) = parse_complete(out[..].into()).unwrap();
+
+        let info = pdf.info().unwrap();
+        assert_eq!(info.title().as_deref(), Some("a\rb"));
+        assert_eq!(info.subject().as_deref(), Some("a\r\nb\tc"));
+    }
+}
@@ -0,0 +1,145 @@
+//! Embedded files of new documents.
+
+use crate::{
+    pdf::{
+        date::PdfDate,
+        document::{name_tree::EMBEDDED_FILES, AfRelationship, K_AF, K_LENGTH, K_NAMES, K_TYPE},
+        object::integer,
+        text::encode_text,
+        Dictionary, Object, Stream, ToObject,
+    },
+    simple_encode::compress,
+};
+
+use super::{name, reference, BuildError, NewObjects};
+
+const K_SUBTYPE: &[u8] = b"Subtype";
+const K_PARAMS: &[u8] = b"Params";
+const K_SIZE: &[u8] = b"Size";
+const K_MOD_DATE: &[u8] = b"ModDate";
+const K_FILE: &[u8] = b"F";
+const K_UNICODE_FILE: &[u8] = b"UF";
+const K_EMBEDDED_FILE: &[u8] = b"EF";
+const K_AF_RELATIONSHIP: &[u8] = b"AFRelationship";
+const EMBEDDED_FILE: &[u8] = b"EmbeddedFile";
+const FILESPEC: &[u8] = b"Filespec";
+
+/// A file added with [PdfBuilder::attach_file](super::PdfBuilder::attach_file).
+#[derive(Debug)]
+pub(super) struct Attachment {
+    pub(super) name: String,
+    pub(super) data: Vec<u8>,
+    pub(super) mime: String,
+    pub(super) relationship: AfRelationship,
+    pub(super) mod_date: PdfDate,
+}
+
+/// Add the embedded files and their file specifications, and register them
+/// in the `/EmbeddedFiles` name tree and the `/AF` array of the catalog.
+pub(super) fn add_attachments(objects: &mut NewObjects, mut attachments: Vec<Attachment>) -> Result<(), BuildError> {
+    if attachments.is_empty() {
+        return Ok(());
+    }
+    // The keys of name trees are sorted by their bytes.
+    attachments.sort_by_cached_key(|attachment| encode_text(&attachment.name));
+
+    let mut names = Vec::with_capacity(attachments.len() * 2);
+    let mut associated = Vec::with_capacity(attachments.len());
+    for attachment in attachments {
+        let size = integer(attachment.data.len())?;
+        let params = Dictionary::from([
+            (K_SIZE.to_vec().into(), size),
+            (
+                K_MOD_DATE.to_vec().into(),
+                Object::String(attachment.mod_date.format().into_bytes().into()),
+            ),
+        ]);
+        let stream = Stream {
+            dictionary: Dictionary::from([
+                (K_TYPE.to_vec().into(), name(EMBEDDED_FILE)),
+                (K_SUBTYPE.to_vec().into(), name(attachment.mime.as_bytes())),
+                (K_PARAMS.to_vec().into(), Object::Dictionary(params)),
+            ]),
+            data: attachment.data.into(),
+            provenance: Default::default(),
+        };
+        let mut stream = compress(&stream).expect("The stream has no filters");
+        let length = integer(stream.data.len())?;
+        stream.dictionary.insert(K_LENGTH.to_vec().into(), length);
+        let file = reference(objects.add(Object::Stream(stream)));
+
+        let file_name = attachment.name.to_object();
+        let spec = Dictionary::from([
+            (K_TYPE.to_vec().into(), name(FILESPEC)),
+            (K_FILE.to_vec().into(), file_name.clone()),
+            (K_UNICODE_FILE.to_vec().into(), file_name.clone()),
+            (
+                K_EMBEDDED_FILE.to_vec().into(),
+                Object::Dictionary(Dictionary::from([
+                    (K_FILE.to_vec().into(), file.clone()),
+                    (K_UNICODE_FILE.to_vec().into(), file),
+                ])),
+            ),
+            (K_AF_RELATIONSHIP.to_vec().into(), name(attachment.relationship.name())),
+        ]);
+        let spec = reference(objects.add(Object::Dictionary(spec)));
+        names.extend([file_name, spec.clone()]);
+        associated.push(spec);
+    }
+
+    let tree = Dictionary::from([(K_NAMES.to_vec().into(), Object::Array(names.into()))]);
+    objects.insert_catalog_entry(
+        K_NAMES,
+        Object::Dictionary(Dictionary::from([(
+            EMBEDDED_FILES.to_vec().into(),
+            Object::Dictionary(tree),
+        )])),
+    );
+    objects.insert_catalog_entry(K_AF, Object::Array(associated.into()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        build::PdfBuilder,
+        simple_encode::{EncodeOptions, SimpleEncoder},
+    };
+
+    use super::*;
+
+    const INVOICE: &[u8] = b"<?xml version=\"1.0\"?><rsm:CrossIndustryInvoice/>";
+
+    #[test]
+    fn round_trip() {
+        let mut builder = PdfBuilder::new();
+        builder.add_page(595.0, 842.0);
+        builder.attach_file("factur-x.xml", b"outdated".to_vec(), "text/xml", AfRelationship::Data);
+        builder.attach_file("notes.txt", &b"notes"[..], "text/plain", AfRelationship::Supplement);
+        builder.attach_file("factur-x.xml", INVOICE, "text/xml", AfRelationship::Alternative);
+        let mut out = Vec::new();
+        SimpleEncoder::write_with(&builder.finish().unwrap(), &mut out, EncodeOptions::default()).unwrap();
+
+        let pdf = crate::read_bytes(out).unwrap();
+        let catalog = pdf.catalog().unwrap();
+        let files = catalog.embedded_files();
+        let names = files.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["factur-x.xml", "notes.txt"]);
+
+        let invoice = &files[0].1;
+        assert_eq!(invoice.file_name().as_deref(), Some("factur-x.xml"));
+        assert_eq!(&invoice.data().unwrap().unwrap()[..], INVOICE);
+        assert_eq!(invoice.mime_type().map(|m| &m[..]), Some(&b"text/xml"[..]));
+        assert_eq!(invoice.relationship(), Some(AfRelationship::Alternative));
+        assert_eq!(invoice.size(), Some(INVOICE.len() as i64));
+        assert!(invoice.mod_date().is_some());
+        // the data is compressed
+        let stream = invoice.embedded_file().unwrap();
+        assert_eq!(stream.filters().unwrap().len(), 1);
+
+        let associated = catalog.associated_files();
+        assert_eq!(associated.len(), 2);
+        assert_eq!(associated[1].relationship(), Some(AfRelationship::Supplement));
+        assert_eq!(&associated[1].data().unwrap().unwrap()[..], b"notes");
+    }
+}
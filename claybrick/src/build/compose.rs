@@ -0,0 +1,357 @@
+//! Creating documents from the pages of existing documents.
+
+use std::{collections::HashMap, ops::RangeBounds};
+
+use crate::pdf::{
+    document::{
+        dict_types::{PAGE, PAGES},
+        page::Page,
+        CatalogError, K_CROP_BOX, K_MEDIA_BOX, K_PARENT, K_RESOURCES, K_ROTATE, K_TYPE,
+    },
+    DictExt, Dictionary, ExtractError, IntegerOutOfRange, Object, RawPdf, Reference, Stream,
+};
+
+use super::{reference, NewObjects, PAGES_NUMBER};
+
+/// Attributes a page can inherit from its ancestors in the page tree.
+const INHERITABLE: [&[u8]; 4] = [K_RESOURCES, K_MEDIA_BOX, K_CROP_BOX, K_ROTATE];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeError {
+    /// At least one document is required.
+    NoDocuments,
+    /// The document with the given index is encrypted and must be decrypted
+    /// first.
+    Encrypted(usize),
+    /// The page tree of the document with the given index is invalid.
+    InvalidPages(usize, CatalogError),
+    /// The number of pages is too large for the `/Count` of the page tree.
+    TooManyPages(usize),
+}
+
+/// Copies objects of a source document and everything they reference into
+/// [NewObjects].
+///
+/// References to page tree nodes and pages that were not selected with
+/// [Copier::add_page] are replaced with null, otherwise the whole page tree
+/// of the source document would be copied, e.g. via `Parent` entries or link
+/// annotations.
+struct Copier<'a> {
+    source: &'a RawPdf,
+    /// Mapping from references in the source to new object numbers.
+    numbers: HashMap<Reference, u32>,
+    /// Objects that have a new number but weren't copied yet.
+    pending: Vec<(&'a Object, u32)>,
+}
+
+impl<'a> Copier<'a> {
+    fn new(source: &'a RawPdf) -> Self {
+        Self {
+            source,
+            numbers: HashMap::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// The reference to the object with the given number. Compressed objects
+    /// always have generation 0.
+    fn source_reference(&self, number: u32) -> Reference {
+        let generation = self
+            .source
+            .object(number as usize)
+            .and_then(Object::indirect)
+            .map_or(0, |indirect| indirect.generation);
+        Reference::new(number, generation)
+    }
+
+    fn is_page_tree_object(obj: &Object) -> bool {
+        obj.dictionary()
+            .and_then(|d| d.get_name_opt(K_TYPE).ok().flatten())
+            .is_some_and(|t| &t[..] == PAGE || &t[..] == PAGES)
+    }
+
+    /// Reserve a number for the page so references to it are kept. Call
+    /// [Copier::copy_page] for all pages after adding them.
+    fn add_page(&mut self, target: &mut NewObjects, page: &Page) -> u32 {
+        let number = target.reserve();
+        self.numbers.insert(self.source_reference(page.number()), number);
+        number
+    }
+
+    /// Copy the page dictionary with inherited attributes and `Parent`
+    /// pointing to the new page tree root.
+    fn copy_page(&mut self, target: &mut NewObjects, page: &Page<'a>) {
+        let mut dict = page.dictionary().clone();
+        for key in INHERITABLE {
+            if !dict.contains_key(key) {
+                if let Some(value) = page.inherited(key) {
+                    dict.insert(key.to_vec().into(), value.clone());
+                }
+            }
+        }
+        dict.remove(K_PARENT);
+        let mut dict = self.rewrite_dict(target, &dict);
+        dict.insert(K_PARENT.to_vec().into(), reference(PAGES_NUMBER));
+        let number = self.numbers[&self.source_reference(page.number())];
+        target.insert(number, Object::Dictionary(dict));
+    }
+
+    /// Copy the object the reference points to and return the new reference.
+    fn copy_reference(&mut self, target: &mut NewObjects, r: &Reference) -> Object {
+        if let Some(&number) = self.numbers.get(r) {
+            return reference(number);
+        }
+        match self.source.lookup_reference(r) {
+            Some(obj) if Self::is_page_tree_object(obj) => {
+                log::debug!("Dropping reference to page tree object {:?}", r);
+                Object::Null
+            }
+            Some(obj) => {
+                let number = target.reserve();
+                self.numbers.insert(*r, number);
+                self.pending.push((obj, number));
+                reference(number)
+            }
+            None => Object::Null,
+        }
+    }
+
+    fn rewrite(&mut self, target: &mut NewObjects, obj: &Object) -> Object {
+        match obj {
+            Object::Reference(r) => self.copy_reference(target, r),
+            Object::Array(a) => Object::Array(a.iter().map(|o| self.rewrite(target, o)).collect::<Vec<_>>().into()),
+            Object::Dictionary(d) => Object::Dictionary(self.rewrite_dict(target, d)),
+            Object::Stream(s) => Object::Stream(Stream {
+                dictionary: self.rewrite_dict(target, &s.dictionary),
+                data: s.data.clone(),
+                provenance: s.provenance,
+            }),
+            Object::Indirect(i) => self.rewrite(target, &i.object),
+            other => other.clone(),
+        }
+    }
+
+    fn rewrite_dict(&mut self, target: &mut NewObjects, dict: &Dictionary) -> Dictionary {
+        dict.iter()
+            .map(|(key, value)| (key.clone(), self.rewrite(target, value)))
+            .collect()
+    }
+
+    /// Copy all objects that were referenced so far, including the objects
+    /// they reference.
+    fn finish(&mut self, target: &mut NewObjects) {
+        while let Some((obj, number)) = self.pending.pop() {
+            let copy = self.rewrite(target, obj);
+            target.insert(number, copy);
+        }
+    }
+
+    /// Copy the document information dictionary.
+    fn copy_info(&mut self, target: &mut NewObjects) -> Option<u32> {
+        let info = self.source.sections.iter().find_map(|s| s.trailer.info)?;
+        match self.copy_reference(target, &info) {
+            Object::Reference(r) => Some(r.index),
+            _ => None,
+        }
+    }
+}
+
+/// Copy the selected pages of `source` into `target`. Returns the numbers
+/// of the new page objects.
+fn copy_pages<'a>(target: &mut NewObjects, copier: &mut Copier<'a>, pages: &[Page<'a>]) -> Vec<u32> {
+    let numbers = pages.iter().map(|page| copier.add_page(target, page)).collect();
+    for page in pages {
+        copier.copy_page(target, page);
+    }
+    copier.finish(target);
+    numbers
+}
+
+fn all_pages(pdf: &RawPdf) -> Result<Vec<Page<'_>>, CatalogError> {
+    pdf.catalog()?
+        .pages()
+        .and_then(|pages| pages.iter().collect())
+        .map_err(CatalogError::InvalidPages)
+}
+
+/// Concatenate the pages of all documents.
+///
+/// The new document gets a new catalog and a flat page tree. Only the pages
+/// and the objects they reference are copied, e.g. outlines and forms are
+/// lost. The document information is taken from the first document.
+pub fn merge(docs: &[RawPdf]) -> Result<RawPdf, MergeError> {
+    if docs.is_empty() {
+        return Err(MergeError::NoDocuments);
+    }
+    if let Some(index) = docs.iter().position(RawPdf::is_encrypted) {
+        return Err(MergeError::Encrypted(index));
+    }
+
+    let mut target = NewObjects::new();
+    let mut kids = Vec::new();
+    let mut info = None;
+    for (index, doc) in docs.iter().enumerate() {
+        let pages = all_pages(doc).map_err(|err| MergeError::InvalidPages(index, err))?;
+        let mut copier = Copier::new(doc);
+        kids.extend(copy_pages(&mut target, &mut copier, &pages));
+        if index == 0 {
+            info = copier.copy_info(&mut target);
+            copier.finish(&mut target);
+        }
+    }
+
+    let version = docs.iter().map(|doc| doc.version).max().unwrap_or_default();
+    target
+        .into_document(version, &kids, info)
+        .map_err(|IntegerOutOfRange(count)| MergeError::TooManyPages(count))
+}
+
+/// See [RawPdf::extract_pages].
+pub(crate) fn extract_pages(pdf: &RawPdf, range: impl RangeBounds<usize>) -> Result<RawPdf, ExtractError> {
+    if pdf.is_encrypted() {
+        return Err(ExtractError::Encrypted);
+    }
+    let pages = all_pages(pdf).map_err(ExtractError::InvalidPages)?;
+    let range = (range.start_bound().cloned(), range.end_bound().cloned());
+    let selected = pages
+        .get(range)
+        .filter(|selected| !selected.is_empty())
+        .ok_or(ExtractError::InvalidRange {
+            page_count: pages.len(),
+        })?;
+
+    let mut target = NewObjects::new();
+    let mut copier = Copier::new(pdf);
+    let kids = copy_pages(&mut target, &mut copier, selected);
+    let info = copier.copy_info(&mut target);
+    copier.finish(&mut target);
+    target
+        .into_document(pdf.version, &kids, info)
+        .map_err(|IntegerOutOfRange(count)| ExtractError::TooManyPages(count))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        build::{Info, PdfBuilder},
+        parse::parse_complete,
+        pdf::{tests::parsed_pdf, Rectangle},
+        simple_encode::{EncodeOptions, SimpleEncoder},
+    };
+
+    use super::*;
+
+    /// Two pages that inherit their media box and resources from an
+    /// intermediate node.
+    fn nested() -> RawPdf {
+        parsed_pdf(
+            Reference::new(1, 0),
+            &[
+                b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj",
+                b"2 0 obj <</Type /Pages /Kids [3 0 R] /Count 2>> endobj",
+                b"3 0 obj <</Type /Pages /Parent 2 0 R /Kids [4 0 R 5 0 R] /Count 2 /MediaBox [0 0 300 400] \
+            /Resources <</XObject <</X 7 0 R>>>>>> endobj",
+                b"4 0 obj <</Type /Page /Parent 3 0 R /Contents 6 0 R /Annots [8 0 R]>> endobj",
+                b"5 0 obj <</Type /Page /Parent 3 0 R /Rotate 90>> endobj",
+                b"6 0 obj <</Length 9>> stream\nBT (x) Tj\nendstream endobj",
+                b"7 0 obj <</Length 3>> stream\nabc\nendstream endobj",
+                b"8 0 obj <</Type /Annot /Subtype /Link /Rect [0 0 10 10] /P 4 0 R /Dest [5 0 R /Fit]>> endobj",
+            ],
+        )
+    }
+
+    fn built() -> RawPdf {
+        let mut builder = PdfBuilder::new();
+        let page = builder.add_page(612.0, 792.0);
+        builder.set_page_content(page, &b"0 0 m 10 10 l S"[..]);
+        builder.set_info(Info {
+            title: Some("Built".to_string()),
+            ..Default::default()
+        });
+        builder.finish().unwrap()
+    }
+
+    #[test]
+    fn merge_documents() {
+        let merged = merge(&[built(), nested()]).unwrap();
+
+        let mut out = Vec::new();
+        SimpleEncoder::write_with(&merged, &mut out, EncodeOptions::default()).unwrap();
+        let (_, pdf) = parse_complete(out[..].into()).unwrap();
+        assert_eq!(pdf.page_count(), Ok(3));
+        assert_eq!(pdf.info().unwrap().title().as_deref(), Some("Built"));
+
+        let pages = all_pages(&pdf).unwrap();
+        assert_eq!(pages[0].media_box(), Some(Rectangle::new(0.0, 0.0, 612.0, 792.0)));
+        assert_eq!(&pages[0].contents()[0].data[..], b"0 0 m 10 10 l S");
+
+        // inherited attributes are materialized
+        let second = pages[1].dictionary();
+        assert!(second.contains_key(K_MEDIA_BOX) && second.contains_key(K_RESOURCES));
+        assert_eq!(pages[1].media_box(), Some(Rectangle::new(0.0, 0.0, 300.0, 400.0)));
+        assert_eq!(&pages[1].contents()[0].data[..], b"BT (x) Tj");
+        assert_eq!(pages[2].rotate(), 90);
+        assert_eq!(pages[1].resources(), pages[2].resources());
+
+        // references between pages of the same document are kept
+        let annotation = pages[1].annotations().next().unwrap();
+        let link = annotation.dictionary();
+        assert_eq!(
+            link.get(&b"P"[..]),
+            Some(&Object::Reference(Reference::new(pages[1].number(), 0)))
+        );
+        let dest = link.get(&b"Dest"[..]).unwrap().array().unwrap();
+        assert_eq!(
+            dest.first(),
+            Some(&Object::Reference(Reference::new(pages[2].number(), 0)))
+        );
+    }
+
+    #[test]
+    fn extract_page() {
+        let merged = merge(&[built(), nested()]).unwrap();
+        let extracted = merged.extract_pages(1..2).unwrap();
+
+        let mut out = Vec::new();
+        SimpleEncoder::write_with(&extracted, &mut out, EncodeOptions::default()).unwrap();
+        let (_, pdf) = parse_complete(out[..].into()).unwrap();
+        assert_eq!(pdf.page_count(), Ok(1));
+        // catalog, pages, page, contents, annotation, xobject, info and the
+        // xref stream
+        assert_eq!(pdf.sections[0].trailer.size, 9);
+
+        let page = pdf.page(0).unwrap();
+        assert_eq!(page.media_box(), Some(Rectangle::new(0.0, 0.0, 300.0, 400.0)));
+        assert_eq!(&page.contents()[0].data[..], b"BT (x) Tj");
+        assert_eq!(page.operations().unwrap().len(), 2);
+        // the link to the other page is dropped
+        let link = page.annotations().next().unwrap();
+        let dest = link.dictionary().get(&b"Dest"[..]).unwrap().array().unwrap();
+        assert_eq!(dest.first(), Some(&Object::Null));
+    }
+
+    #[test]
+    fn extract_shared_objects() {
+        let extracted = nested().extract_pages(..).unwrap();
+        assert_eq!(extracted.page_count(), Ok(2));
+        // The xobject is shared by the resources of both pages.
+        let xobjects = |index| {
+            let page = extracted.page(index).unwrap();
+            let resources = page.resources().unwrap();
+            resources.get(&b"XObject"[..]).cloned()
+        };
+        assert_eq!(xobjects(0), xobjects(1));
+        // catalog, pages, two pages, contents, annotation and xobject
+        assert_eq!(extracted.sections[0].objects.len(), 7);
+
+        assert_eq!(
+            nested().extract_pages(2..),
+            Err(ExtractError::InvalidRange { page_count: 2 })
+        );
+    }
+
+    #[test]
+    fn merge_nothing() {
+        assert_eq!(merge(&[]), Err(MergeError::NoDocuments));
+    }
+}
@@ -0,0 +1,244 @@
+//! Writing of content streams.
+
+use crate::{
+    build::{FontHandle, UnmappableChar},
+    parse::content::Operator,
+    pdf::{CbString, Name},
+    simple_encode::SimpleEncoder,
+    writer::Encoder,
+};
+
+/// Builds a content stream operation by operation.
+///
+/// ```
+/// use claybrick::build::{ContentBuilder, PdfBuilder};
+///
+/// let mut content = ContentBuilder::new();
+/// content.rect(10.0, 10.0, 80.0, 80.0).stroke();
+/// let mut builder = PdfBuilder::new();
+/// let page = builder.add_page(100.0, 100.0);
+/// builder.set_page_content(page, content.finish());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ContentBuilder {
+    data: Vec<u8>,
+}
+
+impl ContentBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Begin a new subpath at `(x, y)`.
+    pub fn move_to(&mut self, x: f32, y: f32) -> &mut Self {
+        self.numbers(&[x, y]).operator(Operator::MoveTo)
+    }
+
+    /// Append a straight line to `(x, y)` to the current subpath.
+    pub fn line_to(&mut self, x: f32, y: f32) -> &mut Self {
+        self.numbers(&[x, y]).operator(Operator::LineTo)
+    }
+
+    /// Append a rectangle with its lower left corner at `(x, y)` as complete
+    /// subpath.
+    pub fn rect(&mut self, x: f32, y: f32, width: f32, height: f32) -> &mut Self {
+        self.numbers(&[x, y, width, height]).operator(Operator::Rectangle)
+    }
+
+    pub fn stroke(&mut self) -> &mut Self {
+        self.operator(Operator::Stroke)
+    }
+
+    /// Fill the path using the nonzero winding number rule.
+    pub fn fill(&mut self) -> &mut Self {
+        self.operator(Operator::Fill)
+    }
+
+    /// Push the graphics state, must be balanced by
+    /// [ContentBuilder::restore_state].
+    pub fn save_state(&mut self) -> &mut Self {
+        self.operator(Operator::Save)
+    }
+
+    pub fn restore_state(&mut self) -> &mut Self {
+        self.operator(Operator::Restore)
+    }
+
+    /// Concatenate `[a b c d e f]` to the current transformation matrix.
+    pub fn set_matrix(&mut self, matrix: [f32; 6]) -> &mut Self {
+        self.numbers(&matrix).operator(Operator::ConcatMatrix)
+    }
+
+    /// Begin a text object, must be balanced by [ContentBuilder::end_text].
+    pub fn begin_text(&mut self) -> &mut Self {
+        self.operator(Operator::BeginText)
+    }
+
+    pub fn end_text(&mut self) -> &mut Self {
+        self.operator(Operator::EndText)
+    }
+
+    /// Select the font with the given name in the `/Font` resources.
+    pub fn set_font(&mut self, font: Name, size: f32) -> &mut Self {
+        self.operand(&font).numbers(&[size]).operator(Operator::SetFont)
+    }
+
+    /// Move to the start of the next line, offset by `(x, y)` from the start
+    /// of the current line.
+    pub fn move_text(&mut self, x: f32, y: f32) -> &mut Self {
+        self.numbers(&[x, y]).operator(Operator::MoveText)
+    }
+
+    /// Show `text` in the current font, which must be `font`. The text is
+    /// encoded like the font dictionary of `font` specifies.
+    pub fn show_text(&mut self, font: &FontHandle, text: &str) -> Result<&mut Self, UnmappableChar> {
+        let mut bytes = Vec::with_capacity(text.len());
+        for byte in font.encode(text)? {
+            // Parentheses are escaped by the string encoder if necessary.
+            if byte == b'\\' {
+                bytes.push(b'\\');
+            }
+            bytes.push(byte);
+        }
+        Ok(self.operand(&CbString::from(bytes)).operator(Operator::ShowText))
+    }
+
+    /// Set the fill color in the DeviceRGB color space, the components range
+    /// from 0 to 1.
+    pub fn set_fill_color_rgb(&mut self, red: f32, green: f32, blue: f32) -> &mut Self {
+        self.numbers(&[red, green, blue]).operator(Operator::SetFillRgb)
+    }
+
+    /// Paint the image or form with the given name in the `/XObject`
+    /// resources.
+    pub fn draw_xobject(&mut self, xobject: Name) -> &mut Self {
+        self.operand(&xobject).operator(Operator::PaintXObject)
+    }
+
+    /// The unencoded content stream.
+    pub fn finish(self) -> Vec<u8> {
+        self.data
+    }
+
+    fn operand<T>(&mut self, operand: &T) -> &mut Self
+    where
+        SimpleEncoder: Encoder<T>,
+    {
+        SimpleEncoder::write_to(operand, &mut self.data).expect("Operands can always be encoded");
+        self.data.push(b' ');
+        self
+    }
+
+    /// Numbers are written without exponent, like all reals.
+    fn numbers(&mut self, numbers: &[f32]) -> &mut Self {
+        for number in numbers {
+            self.operand(number);
+        }
+        self
+    }
+
+    fn operator(&mut self, operator: Operator) -> &mut Self {
+        self.data.extend_from_slice(operator.keyword());
+        self.data.push(b'\n');
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        build::{PdfBuilder, StandardFont},
+        parse::content::{parse_operations, Operation},
+        pdf::Object,
+        simple_encode::{EncodeOptions, SimpleEncoder},
+    };
+
+    use super::*;
+
+    fn operation(operator: Operator, operands: Vec<Object>) -> Operation {
+        Operation { operands, operator }
+    }
+
+    fn name(name: &[u8]) -> Name {
+        name.to_vec().into()
+    }
+
+    #[test]
+    fn hello() {
+        let mut builder = PdfBuilder::new();
+        let page = builder.add_page(612.0, 792.0);
+        let helvetica = builder.add_standard_font(StandardFont::Helvetica);
+        let mut content = ContentBuilder::new();
+        content
+            .begin_text()
+            .set_font(helvetica.name().clone(), 24.0)
+            .move_text(72.0, 720.0)
+            .show_text(&helvetica, "Hello")
+            .unwrap()
+            .end_text();
+        builder.set_page_content(page, content.finish());
+
+        let mut out = Vec::new();
+        SimpleEncoder::write_with(&builder.finish().unwrap(), &mut out, EncodeOptions::default()).unwrap();
+        let pdf = crate::read_bytes(out).unwrap();
+        let page = pdf.page(0).unwrap();
+        let fonts = page
+            .resources()
+            .unwrap()
+            .get(&b"Font"[..])
+            .and_then(Object::dictionary)
+            .unwrap();
+        let font = pdf.resolve(fonts.get(&b"Helvetica"[..]).unwrap()).dictionary().unwrap();
+        assert_eq!(font.get(&b"BaseFont"[..]), Some(&Object::Name(name(b"Helvetica"))));
+        let data = page.contents()[0].filtered_data().unwrap();
+        assert_eq!(
+            parse_operations(&data).unwrap(),
+            vec![
+                operation(Operator::BeginText, vec![]),
+                operation(
+                    Operator::SetFont,
+                    vec![Object::Name(name(b"Helvetica")), Object::Integer(24)]
+                ),
+                operation(Operator::MoveText, vec![Object::Integer(72), Object::Integer(720)]),
+                operation(Operator::ShowText, vec![Object::String(b"Hello".to_vec().into())]),
+                operation(Operator::EndText, vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn graphics() {
+        let mut content = ContentBuilder::new();
+        content
+            .save_state()
+            .set_matrix([1.0, 0.0, 0.0, 1.0, 0.5, -0.25])
+            .set_fill_color_rgb(1.0, 0.5, 0.0)
+            .rect(0.0, 0.0, 1e-7, 10.0)
+            .fill()
+            .move_to(0.0, 0.0)
+            .line_to(100.0, 1e10)
+            .stroke()
+            .draw_xobject(name(b"Im 1"))
+            .restore_state();
+        assert_eq!(
+            String::from_utf8(content.finish()).unwrap(),
+            "q\n1 0 0 1 0.5 -0.25 cm\n1 0.5 0 rg\n0 0 0 10 re\nf\n0 0 m\n100 10000000000 l\nS\n/Im#201 Do\nQ\n"
+        );
+    }
+
+    #[test]
+    fn escape_text() {
+        let font = FontHandle::new(name(b"F1"), StandardFont::TimesRoman);
+        let mut content = ContentBuilder::new();
+        content.show_text(&font, r"(a\b))) – 10 €").unwrap();
+        assert_eq!(
+            content.show_text(&font, "😀").err().map(|err| err.character),
+            Some('😀')
+        );
+        let operations = parse_operations(&content.finish()).unwrap();
+        let Object::String(text) = &operations[0].operands[0] else {
+            panic!("Expected a string: {:?}", operations);
+        };
+        assert_eq!(text.unescaped(), b"(a\\b))) \x96 10 \x80");
+    }
+}
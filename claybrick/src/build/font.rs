@@ -0,0 +1,328 @@
+//! The standard 14 fonts, which readers must provide and thus don't have to
+//! be embedded.
+
+use crate::pdf::{
+    font::encoding::{win_ansi_char, win_ansi_code},
+    Dictionary, Name, Object,
+};
+
+const K_TYPE: &[u8] = b"Type";
+const K_SUBTYPE: &[u8] = b"Subtype";
+const K_BASE_FONT: &[u8] = b"BaseFont";
+const K_ENCODING: &[u8] = b"Encoding";
+
+/// The fonts every reader provides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StandardFont {
+    Helvetica,
+    HelveticaBold,
+    HelveticaOblique,
+    HelveticaBoldOblique,
+    TimesRoman,
+    TimesBold,
+    TimesItalic,
+    TimesBoldItalic,
+    Courier,
+    CourierBold,
+    CourierOblique,
+    CourierBoldOblique,
+    /// Greek letters and mathematical symbols in the font's own encoding.
+    Symbol,
+    /// Dingbats in the font's own encoding.
+    ZapfDingbats,
+}
+
+/// The character can't be shown with the font.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnmappableChar {
+    pub character: char,
+    pub font: StandardFont,
+}
+
+/// A standard font added with
+/// [PdfBuilder::add_standard_font](super::PdfBuilder::add_standard_font).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FontHandle {
+    name: Name,
+    font: StandardFont,
+}
+
+impl StandardFont {
+    /// The `/BaseFont` name.
+    pub fn base_font(self) -> &'static str {
+        match self {
+            StandardFont::Helvetica => "Helvetica",
+            StandardFont::HelveticaBold => "Helvetica-Bold",
+            StandardFont::HelveticaOblique => "Helvetica-Oblique",
+            StandardFont::HelveticaBoldOblique => "Helvetica-BoldOblique",
+            StandardFont::TimesRoman => "Times-Roman",
+            StandardFont::TimesBold => "Times-Bold",
+            StandardFont::TimesItalic => "Times-Italic",
+            StandardFont::TimesBoldItalic => "Times-BoldItalic",
+            StandardFont::Courier => "Courier",
+            StandardFont::CourierBold => "Courier-Bold",
+            StandardFont::CourierOblique => "Courier-Oblique",
+            StandardFont::CourierBoldOblique => "Courier-BoldOblique",
+            StandardFont::Symbol => "Symbol",
+            StandardFont::ZapfDingbats => "ZapfDingbats",
+        }
+    }
+
+    /// The code of `c` in the encoding the font dictionary uses:
+    /// WinAnsiEncoding for the text fonts and the built-in encoding for
+    /// Symbol and ZapfDingbats, of which only the codes 32 to 126 are
+    /// supported.
+    pub fn encode(self, c: char) -> Option<u8> {
+        match self {
+            StandardFont::Symbol => builtin_code(&SYMBOL_CHARS, c),
+            StandardFont::ZapfDingbats => builtin_code(&ZAPF_DINGBATS_CHARS, c),
+            _ => win_ansi_code(c),
+        }
+    }
+
+    /// The width of the glyph with the given code in thousandths of the font
+    /// size, taken from the font's AFM file.
+    pub fn width(self, code: u8) -> Option<u16> {
+        let table = match self {
+            StandardFont::Helvetica | StandardFont::HelveticaOblique => &HELVETICA,
+            StandardFont::HelveticaBold | StandardFont::HelveticaBoldOblique => &HELVETICA_BOLD,
+            StandardFont::TimesRoman => &TIMES_ROMAN,
+            StandardFont::TimesBold => &TIMES_BOLD,
+            StandardFont::TimesItalic => &TIMES_ITALIC,
+            StandardFont::TimesBoldItalic => &TIMES_BOLD_ITALIC,
+            StandardFont::Courier
+            | StandardFont::CourierBold
+            | StandardFont::CourierOblique
+            | StandardFont::CourierBoldOblique => return win_ansi_char(code).map(|_| COURIER_WIDTH),
+            StandardFont::Symbol => return builtin_width(&SYMBOL_WIDTHS, code),
+            StandardFont::ZapfDingbats => return builtin_width(&ZAPF_DINGBATS_WIDTHS, code),
+        };
+        let width = *table.get(usize::from(code).checked_sub(FIRST_CODE)?)?;
+        (width != 0).then_some(width)
+    }
+
+    /// The font dictionary.
+    pub(crate) fn dictionary(self) -> Dictionary {
+        let name = |name: &[u8]| Object::Name(name.to_vec().into());
+        let mut dictionary = Dictionary::from([
+            (K_TYPE.to_vec().into(), name(b"Font")),
+            (K_SUBTYPE.to_vec().into(), name(b"Type1")),
+            (K_BASE_FONT.to_vec().into(), name(self.base_font().as_bytes())),
+        ]);
+        if !matches!(self, StandardFont::Symbol | StandardFont::ZapfDingbats) {
+            dictionary.insert(K_ENCODING.to_vec().into(), name(b"WinAnsiEncoding"));
+        }
+        dictionary
+    }
+}
+
+impl FontHandle {
+    pub(crate) fn new(name: Name, font: StandardFont) -> Self {
+        Self { name, font }
+    }
+
+    /// The name of the font in the `/Font` resources, see
+    /// [ContentBuilder::set_font](super::ContentBuilder::set_font).
+    pub fn name(&self) -> &Name {
+        &self.name
+    }
+
+    pub fn font(&self) -> StandardFont {
+        self.font
+    }
+
+    /// The width of `text` in points when shown with the given font size,
+    /// without character or word spacing. Characters the font can't show
+    /// are ignored.
+    pub fn text_width(&self, text: &str, size: f32) -> f32 {
+        let thousandths: u32 = text
+            .chars()
+            .filter_map(|c| self.font.encode(c))
+            .filter_map(|code| self.font.width(code))
+            .map(u32::from)
+            .sum();
+        thousandths as f32 * size / 1000.0
+    }
+
+    /// The codes of `text` in the encoding of the font.
+    pub(crate) fn encode(&self, text: &str) -> Result<Vec<u8>, UnmappableChar> {
+        text.chars()
+            .map(|c| {
+                self.font.encode(c).ok_or(UnmappableChar {
+                    character: c,
+                    font: self.font,
+                })
+            })
+            .collect()
+    }
+}
+
+fn builtin_code(chars: &[char; 95], c: char) -> Option<u8> {
+    let index = chars.iter().position(|&other| other == c)?;
+    Some(FIRST_CODE as u8 + index as u8)
+}
+
+fn builtin_width(widths: &[u16; 95], code: u8) -> Option<u16> {
+    widths.get(usize::from(code).checked_sub(FIRST_CODE)?).copied()
+}
+
+/// The tables start with the space.
+const FIRST_CODE: usize = 0x20;
+
+const COURIER_WIDTH: u16 = 600;
+
+// Widths of the WinAnsiEncoding codes 32 to 255, 0 for undefined codes. The
+// AFM files have no Euro, it gets the width of the digits.
+
+const HELVETICA: [u16; 224] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556, 556, 556, 556, 556,
+    556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833,
+    722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556,
+    556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334,
+    260, 334, 584, 0, 556, 0, 222, 556, 333, 1000, 556, 556, 333, 1000, 667, 333, 1000, 0, 611, 0, 0, 222, 222, 333,
+    333, 350, 556, 1000, 333, 1000, 500, 333, 944, 0, 500, 667, 278, 333, 556, 556, 556, 556, 260, 556, 333, 737, 370,
+    556, 584, 333, 737, 333, 400, 584, 333, 333, 333, 556, 537, 278, 333, 333, 365, 556, 834, 834, 834, 611, 667, 667,
+    667, 667, 667, 667, 1000, 722, 667, 667, 667, 667, 278, 278, 278, 278, 722, 722, 778, 778, 778, 778, 778, 584, 778,
+    722, 722, 722, 722, 667, 667, 611, 556, 556, 556, 556, 556, 556, 889, 500, 556, 556, 556, 556, 278, 278, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 584, 611, 556, 556, 556, 556, 500, 556, 500,
+];
+
+const HELVETICA_BOLD: [u16; 224] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556, 556, 556, 556, 556,
+    556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611, 833,
+    722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611,
+    556, 333, 611, 611, 278, 278, 556, 278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389,
+    280, 389, 584, 0, 556, 0, 278, 556, 500, 1000, 556, 556, 333, 1000, 667, 333, 1000, 0, 611, 0, 0, 278, 278, 500,
+    500, 350, 556, 1000, 333, 1000, 556, 333, 944, 0, 500, 667, 278, 333, 556, 556, 556, 556, 280, 556, 333, 737, 370,
+    556, 584, 333, 737, 333, 400, 584, 333, 333, 333, 611, 556, 278, 333, 333, 365, 556, 834, 834, 834, 611, 722, 722,
+    722, 722, 722, 722, 1000, 722, 667, 667, 667, 667, 278, 278, 278, 278, 722, 722, 778, 778, 778, 778, 778, 584, 778,
+    722, 722, 722, 722, 667, 667, 611, 556, 556, 556, 556, 556, 556, 889, 556, 556, 556, 556, 556, 278, 278, 278, 278,
+    611, 611, 611, 611, 611, 611, 611, 584, 611, 611, 611, 611, 611, 556, 611, 556,
+];
+
+const TIMES_ROMAN: [u16; 224] = [
+    250, 333, 408, 500, 500, 833, 778, 180, 333, 333, 500, 564, 250, 333, 250, 278, 500, 500, 500, 500, 500, 500, 500,
+    500, 500, 500, 278, 278, 564, 564, 564, 444, 921, 722, 667, 667, 722, 611, 556, 722, 722, 333, 389, 722, 611, 889,
+    722, 722, 556, 722, 667, 556, 611, 722, 722, 944, 722, 722, 611, 333, 278, 333, 469, 500, 333, 444, 500, 444, 500,
+    444, 333, 500, 500, 278, 278, 500, 278, 778, 500, 500, 500, 500, 333, 389, 278, 500, 500, 722, 500, 500, 444, 480,
+    200, 480, 541, 0, 500, 0, 333, 500, 444, 1000, 500, 500, 333, 1000, 556, 333, 889, 0, 611, 0, 0, 333, 333, 444,
+    444, 350, 500, 1000, 333, 980, 389, 333, 722, 0, 444, 722, 250, 333, 500, 500, 500, 500, 200, 500, 333, 760, 276,
+    500, 564, 333, 760, 333, 400, 564, 300, 300, 333, 500, 453, 250, 333, 300, 310, 500, 750, 750, 750, 444, 722, 722,
+    722, 722, 722, 722, 889, 667, 611, 611, 611, 611, 333, 333, 333, 333, 722, 722, 722, 722, 722, 722, 722, 564, 722,
+    722, 722, 722, 722, 722, 556, 500, 444, 444, 444, 444, 444, 444, 667, 444, 444, 444, 444, 444, 278, 278, 278, 278,
+    500, 500, 500, 500, 500, 500, 500, 564, 500, 500, 500, 500, 500, 500, 500, 500,
+];
+
+const TIMES_BOLD: [u16; 224] = [
+    250, 333, 555, 500, 500, 1000, 833, 278, 333, 333, 500, 570, 250, 333, 250, 278, 500, 500, 500, 500, 500, 500, 500,
+    500, 500, 500, 333, 333, 570, 570, 570, 500, 930, 722, 667, 722, 722, 667, 611, 778, 778, 389, 500, 778, 667, 944,
+    722, 778, 611, 778, 722, 556, 667, 722, 722, 1000, 722, 722, 667, 333, 278, 333, 581, 500, 333, 500, 556, 444, 556,
+    444, 333, 500, 556, 278, 333, 556, 278, 833, 556, 500, 556, 556, 444, 389, 333, 556, 500, 722, 500, 500, 444, 394,
+    220, 394, 520, 0, 500, 0, 333, 500, 500, 1000, 500, 500, 333, 1000, 556, 333, 1000, 0, 667, 0, 0, 333, 333, 500,
+    500, 350, 500, 1000, 333, 1000, 389, 333, 722, 0, 444, 722, 250, 333, 500, 500, 500, 500, 220, 500, 333, 747, 300,
+    500, 570, 333, 747, 333, 400, 570, 300, 300, 333, 556, 540, 250, 333, 300, 330, 500, 750, 750, 750, 500, 722, 722,
+    722, 722, 722, 722, 1000, 722, 667, 667, 667, 667, 389, 389, 389, 389, 722, 722, 778, 778, 778, 778, 778, 570, 778,
+    722, 722, 722, 722, 722, 611, 556, 500, 500, 500, 500, 500, 500, 722, 444, 444, 444, 444, 444, 278, 278, 278, 278,
+    500, 556, 500, 500, 500, 500, 500, 570, 500, 556, 556, 556, 556, 500, 556, 500,
+];
+
+const TIMES_ITALIC: [u16; 224] = [
+    250, 333, 420, 500, 500, 833, 778, 214, 333, 333, 500, 675, 250, 333, 250, 278, 500, 500, 500, 500, 500, 500, 500,
+    500, 500, 500, 333, 333, 675, 675, 675, 500, 920, 611, 611, 667, 722, 611, 611, 722, 722, 333, 444, 667, 556, 833,
+    667, 722, 611, 722, 611, 500, 556, 722, 611, 833, 611, 556, 556, 389, 278, 389, 422, 500, 333, 500, 500, 444, 500,
+    444, 278, 500, 500, 278, 278, 444, 278, 722, 500, 500, 500, 500, 389, 389, 278, 500, 444, 667, 444, 444, 389, 400,
+    275, 400, 541, 0, 500, 0, 333, 500, 556, 889, 500, 500, 333, 1000, 500, 333, 944, 0, 556, 0, 0, 333, 333, 556, 556,
+    350, 500, 889, 333, 980, 389, 333, 667, 0, 389, 556, 250, 389, 500, 500, 500, 500, 275, 500, 333, 760, 276, 500,
+    675, 333, 760, 333, 400, 675, 300, 300, 333, 500, 523, 250, 333, 300, 310, 500, 750, 750, 750, 500, 611, 611, 611,
+    611, 611, 611, 889, 667, 611, 611, 611, 611, 333, 333, 333, 333, 722, 667, 722, 722, 722, 722, 722, 675, 722, 722,
+    722, 722, 722, 556, 611, 500, 500, 500, 500, 500, 500, 500, 667, 444, 444, 444, 444, 444, 278, 278, 278, 278, 500,
+    500, 500, 500, 500, 500, 500, 675, 500, 500, 500, 500, 500, 444, 500, 444,
+];
+
+const TIMES_BOLD_ITALIC: [u16; 224] = [
+    250, 389, 555, 500, 500, 833, 778, 278, 333, 333, 500, 570, 250, 333, 250, 278, 500, 500, 500, 500, 500, 500, 500,
+    500, 500, 500, 333, 333, 570, 570, 570, 500, 832, 667, 667, 667, 722, 667, 667, 722, 778, 389, 500, 667, 611, 889,
+    722, 722, 611, 722, 667, 556, 611, 722, 667, 889, 667, 611, 611, 333, 278, 333, 570, 500, 333, 500, 500, 444, 500,
+    444, 333, 500, 556, 278, 278, 500, 278, 778, 556, 500, 500, 500, 389, 389, 278, 556, 444, 667, 500, 444, 389, 348,
+    220, 348, 570, 0, 500, 0, 333, 500, 500, 1000, 500, 500, 333, 1000, 556, 333, 944, 0, 611, 0, 0, 333, 333, 500,
+    500, 350, 500, 1000, 333, 1000, 389, 333, 722, 0, 389, 611, 250, 389, 500, 500, 500, 500, 220, 500, 333, 747, 266,
+    500, 606, 333, 747, 333, 400, 570, 300, 300, 333, 576, 500, 250, 333, 300, 300, 500, 750, 750, 750, 500, 667, 667,
+    667, 667, 667, 667, 944, 667, 667, 667, 667, 667, 389, 389, 389, 389, 722, 722, 722, 722, 722, 722, 722, 570, 722,
+    722, 722, 722, 722, 611, 611, 500, 500, 500, 500, 500, 500, 500, 722, 444, 444, 444, 444, 444, 278, 278, 278, 278,
+    500, 556, 500, 500, 500, 500, 500, 570, 500, 556, 556, 556, 556, 444, 500, 444,
+];
+
+/// The Unicode characters of the codes 32 to 126 of the Symbol encoding.
+const SYMBOL_CHARS: [char; 95] = [
+    ' ', '!', '∀', '#', '∃', '%', '&', '∋', '(', ')', '∗', '+', ',', '−', '.', '/', '0', '1', '2', '3', '4', '5', '6',
+    '7', '8', '9', ':', ';', '<', '=', '>', '?', '≅', 'Α', 'Β', 'Χ', 'Δ', 'Ε', 'Φ', 'Γ', 'Η', 'Ι', 'ϑ', 'Κ', 'Λ', 'Μ',
+    'Ν', 'Ο', 'Π', 'Θ', 'Ρ', 'Σ', 'Τ', 'Υ', 'ς', 'Ω', 'Ξ', 'Ψ', 'Ζ', '[', '∴', ']', '⊥', '_', '\u{F8E5}', 'α', 'β',
+    'χ', 'δ', 'ε', 'φ', 'γ', 'η', 'ι', 'ϕ', 'κ', 'λ', 'μ', 'ν', 'ο', 'π', 'θ', 'ρ', 'σ', 'τ', 'υ', 'ϖ', 'ω', 'ξ', 'ψ',
+    'ζ', '{', '|', '}', '∼',
+];
+
+const SYMBOL_WIDTHS: [u16; 95] = [
+    250, 333, 713, 500, 549, 833, 778, 439, 333, 333, 500, 549, 250, 549, 250, 278, 500, 500, 500, 500, 500, 500, 500,
+    500, 500, 500, 278, 278, 549, 549, 549, 444, 549, 722, 667, 722, 612, 611, 763, 603, 722, 333, 631, 722, 686, 889,
+    722, 722, 768, 741, 556, 592, 611, 690, 439, 768, 645, 795, 611, 333, 863, 333, 658, 500, 500, 631, 549, 549, 494,
+    439, 521, 411, 603, 329, 603, 549, 549, 576, 521, 549, 549, 521, 549, 603, 439, 576, 713, 686, 493, 686, 494, 480,
+    200, 480, 549,
+];
+
+/// The Unicode characters of the codes 32 to 126 of the ZapfDingbats
+/// encoding.
+const ZAPF_DINGBATS_CHARS: [char; 95] = [
+    ' ', '✁', '✂', '✃', '✄', '☎', '✆', '✇', '✈', '✉', '☛', '☞', '✌', '✍', '✎', '✏', '✐', '✑', '✒', '✓', '✔', '✕', '✖',
+    '✗', '✘', '✙', '✚', '✛', '✜', '✝', '✞', '✟', '✠', '✡', '✢', '✣', '✤', '✥', '✦', '✧', '★', '✩', '✪', '✫', '✬', '✭',
+    '✮', '✯', '✰', '✱', '✲', '✳', '✴', '✵', '✶', '✷', '✸', '✹', '✺', '✻', '✼', '✽', '✾', '✿', '❀', '❁', '❂', '❃', '❄',
+    '❅', '❆', '❇', '❈', '❉', '❊', '❋', '●', '❍', '■', '❏', '❐', '❑', '❒', '▲', '▼', '◆', '❖', '◗', '❘', '❙', '❚', '❛',
+    '❜', '❝', '❞',
+];
+
+const ZAPF_DINGBATS_WIDTHS: [u16; 95] = [
+    278, 974, 961, 974, 980, 719, 789, 790, 791, 690, 960, 939, 549, 855, 911, 933, 911, 945, 974, 755, 846, 762, 761,
+    571, 677, 763, 760, 759, 754, 494, 552, 537, 577, 692, 786, 788, 788, 790, 793, 794, 816, 823, 789, 841, 823, 833,
+    816, 831, 923, 744, 723, 749, 790, 792, 695, 776, 768, 792, 759, 707, 708, 682, 701, 826, 815, 789, 789, 707, 687,
+    696, 689, 786, 787, 713, 791, 785, 791, 873, 761, 762, 762, 759, 759, 892, 892, 788, 784, 438, 138, 277, 415, 392,
+    392, 668, 668,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn width(font: StandardFont, text: &str, size: f32) -> f32 {
+        FontHandle::new(b"F1".to_vec().into(), font).text_width(text, size)
+    }
+
+    #[test]
+    fn text_width() {
+        // sums of the AFM widths
+        assert!((width(StandardFont::Helvetica, "Hello", 12.0) - 27.336).abs() < 1e-3);
+        assert!((width(StandardFont::HelveticaBold, "Hello", 1000.0) - 2445.0).abs() < 1e-3);
+        assert!((width(StandardFont::TimesRoman, "Hello World", 10.0) - 50.27).abs() < 1e-3);
+        assert!((width(StandardFont::Courier, "Größe €", 10.0) - 42.0).abs() < 1e-3);
+        assert!((width(StandardFont::Symbol, "αβ", 1000.0) - 1180.0).abs() < 1e-3);
+        assert!((width(StandardFont::ZapfDingbats, "✓", 1000.0) - 755.0).abs() < 1e-3);
+        // unmappable characters are ignored
+        assert_eq!(width(StandardFont::Helvetica, "😀", 12.0), 0.0);
+    }
+
+    #[test]
+    fn encode() {
+        let helvetica = FontHandle::new(b"F1".to_vec().into(), StandardFont::Helvetica);
+        assert_eq!(helvetica.encode("Grüße €"), Ok(b"Gr\xFC\xDFe \x80".to_vec()));
+        assert_eq!(
+            helvetica.encode("α"),
+            Err(UnmappableChar {
+                character: 'α',
+                font: StandardFont::Helvetica
+            })
+        );
+        let symbol = FontHandle::new(b"F2".to_vec().into(), StandardFont::Symbol);
+        assert_eq!(symbol.encode("α∀"), Ok(b"a\"".to_vec()));
+        assert_eq!(StandardFont::ZapfDingbats.encode('❞'), Some(0x7E));
+        assert_eq!(StandardFont::Helvetica.width(0x81), None);
+        assert_eq!(StandardFont::Courier.width(0x81), None);
+    }
+}
@@ -0,0 +1,522 @@
+//! Semantic comparison of two documents.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use crate::pdf::{
+    document::K_LENGTH,
+    references::walk,
+    trailer::{K_INFO, K_PREVIOUS, K_ROOT, K_SIZE, K_X_REF_STM},
+    Dictionary, Object, PathElement, RawPdf, Reference, Stream,
+};
+
+/// Numbers that differ by at most this value are equal.
+const EPSILON: f32 = 1e-4;
+
+const K_FILTER: &[u8] = b"Filter";
+const K_DECODE_PARMS: &[u8] = b"DecodeParms";
+const K_DECODED_LENGTH: &[u8] = b"DL";
+
+/// How strictly the documents are compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Comparison {
+    /// All trailer entries and the raw stream data.
+    Exact,
+    /// Only `Root` and `Info` of the trailers. Streams are compared by their
+    /// decoded data and strings by their bytes, regardless of the encoding.
+    Equivalent,
+}
+
+/// How a value differs between two documents.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Value {
+        before: Object,
+        after: Object,
+    },
+    /// The key or array element only exists in the second document.
+    Added(Object),
+    /// The key or array element only exists in the first document.
+    Removed(Object),
+    /// The streams have different data.
+    StreamData,
+}
+
+/// A difference inside an object or the trailer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueChange {
+    /// Path from the object to the changed value. Empty if the object
+    /// itself changed.
+    pub path: Vec<PathElement>,
+    pub change: Change,
+}
+
+/// An object that exists in both documents but differs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectChange {
+    /// The object number in the first document.
+    pub before: usize,
+    /// The object number in the second document.
+    pub after: usize,
+    pub changes: Vec<ValueChange>,
+}
+
+/// Differences between the object graphs of two documents.
+///
+/// Objects are matched by their position in the graph rather than their
+/// numbers, e.g. the objects referenced by the `Pages` entries of the
+/// catalogs are the same object. Objects that are not reachable from the
+/// trailer are ignored.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PdfDiff {
+    /// Numbers of the objects that only exist in the second document.
+    pub added: Vec<usize>,
+    /// Numbers of the objects that only exist in the first document.
+    pub removed: Vec<usize>,
+    pub changed: Vec<ObjectChange>,
+    /// Changes of the trailer entries, except `Size`, `Prev` and `XRefStm`.
+    pub trailer: Vec<ValueChange>,
+    /// The page counts of both documents if they differ. `None` if the page
+    /// tree is invalid.
+    pub page_count: Option<(Option<usize>, Option<usize>)>,
+}
+
+impl PdfDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.trailer.is_empty()
+            && self.page_count.is_none()
+    }
+}
+
+/// Compare the documents starting at the trailer.
+pub fn diff(a: &RawPdf, b: &RawPdf) -> PdfDiff {
+    diff_with(a, b, Comparison::Exact)
+}
+
+pub(crate) fn diff_with(a: &RawPdf, b: &RawPdf, comparison: Comparison) -> PdfDiff {
+    let mut differ = Differ {
+        a,
+        b,
+        comparison,
+        pairs: HashMap::new(),
+        paired: HashSet::new(),
+        pending: Vec::new(),
+    };
+
+    let mut trailer = Vec::new();
+    differ.compare(
+        &Object::Dictionary(trailer_dict(a, comparison)),
+        &Object::Dictionary(trailer_dict(b, comparison)),
+        &mut Vec::new(),
+        &mut trailer,
+    );
+
+    let mut changed = Vec::new();
+    while let Some((ra, rb)) = differ.pending.pop() {
+        let null = Object::Null;
+        let x = unwrap_indirect(a.lookup_reference(&ra).unwrap_or(&null));
+        let y = unwrap_indirect(b.lookup_reference(&rb).unwrap_or(&null));
+        let mut changes = Vec::new();
+        differ.compare(x, y, &mut Vec::new(), &mut changes);
+        if !changes.is_empty() {
+            changed.push(ObjectChange {
+                before: ra.index as usize,
+                after: rb.index as usize,
+                changes,
+            });
+        }
+    }
+    changed.sort_by_key(|c| c.before);
+
+    let matched_a = differ.pairs.keys().copied().collect::<HashSet<_>>();
+    let mut removed = reachable(a, comparison)
+        .into_iter()
+        .filter(|r| !matched_a.contains(r))
+        .map(|r| r.index as usize)
+        .collect::<Vec<_>>();
+    removed.sort_unstable();
+    let mut added = reachable(b, comparison)
+        .into_iter()
+        .filter(|r| !differ.paired.contains(r))
+        .map(|r| r.index as usize)
+        .collect::<Vec<_>>();
+    added.sort_unstable();
+
+    let page_counts = (a.page_count().ok(), b.page_count().ok());
+    PdfDiff {
+        added,
+        removed,
+        changed,
+        trailer,
+        page_count: (page_counts.0 != page_counts.1).then_some(page_counts),
+    }
+}
+
+/// The trailer without entries that depend on the file layout.
+fn trailer_dict(pdf: &RawPdf, comparison: Comparison) -> Dictionary {
+    let mut dict = pdf
+        .sections
+        .first()
+        .and_then(|s| Dictionary::try_from(s.trailer.clone()).ok())
+        .unwrap_or_default();
+    match comparison {
+        Comparison::Exact => {
+            for key in [K_SIZE, K_PREVIOUS, K_X_REF_STM] {
+                dict.remove(key);
+            }
+        }
+        Comparison::Equivalent => dict.retain(|key, _| &key[..] == K_ROOT || &key[..] == K_INFO),
+    }
+    dict
+}
+
+/// The stream dictionary without the entries that describe the encoding of
+/// the data, and the decoded data. Data that can't be decoded is kept.
+fn decoded(stream: &Stream) -> (Dictionary, Vec<u8>) {
+    let Ok(data) = stream.filtered_data() else {
+        return (stream.dictionary.clone(), stream.data.to_vec());
+    };
+    let mut dictionary = stream.dictionary.clone();
+    for key in [K_LENGTH, K_FILTER, K_DECODE_PARMS, K_DECODED_LENGTH] {
+        dictionary.remove(key);
+    }
+    (dictionary, data.to_vec())
+}
+
+/// The bytes of literal and hex strings.
+fn string_bytes(obj: &Object) -> Option<Vec<u8>> {
+    match obj {
+        Object::String(s) => Some(s.unescaped()),
+        Object::HexString(b) => Some(b.to_vec()),
+        _ => None,
+    }
+}
+
+fn unwrap_indirect(obj: &Object) -> &Object {
+    match obj {
+        Object::Indirect(indirect) => &indirect.object,
+        obj => obj,
+    }
+}
+
+/// All objects that are reachable from the trailer.
+fn reachable(pdf: &RawPdf, comparison: Comparison) -> HashSet<Reference> {
+    let mut pending = Vec::new();
+    walk(
+        &Object::Dictionary(trailer_dict(pdf, comparison)),
+        &mut Vec::new(),
+        &mut |r, _| pending.push(*r),
+    );
+    let mut reachable = HashSet::new();
+    while let Some(r) = pending.pop() {
+        let Some(obj) = pdf.lookup_reference(&r) else {
+            continue;
+        };
+        if reachable.insert(r) {
+            walk(obj, &mut Vec::new(), &mut |r, _| pending.push(*r));
+        }
+    }
+    reachable
+}
+
+struct Differ<'a> {
+    a: &'a RawPdf,
+    b: &'a RawPdf,
+    comparison: Comparison,
+    /// Objects of the first document and the object at the same position in
+    /// the second document.
+    pairs: HashMap<Reference, Reference>,
+    /// The objects of the second document that are in `pairs`.
+    paired: HashSet<Reference>,
+    /// Pairs of objects that still have to be compared.
+    pending: Vec<(Reference, Reference)>,
+}
+
+impl Differ<'_> {
+    fn compare(&mut self, x: &Object, y: &Object, path: &mut Vec<PathElement>, changes: &mut Vec<ValueChange>) {
+        let equal = match (x, y) {
+            (Object::Reference(ra), Object::Reference(rb)) => self.pair(*ra, *rb),
+            (Object::Dictionary(dx), Object::Dictionary(dy)) => {
+                self.compare_dicts(dx, dy, path, changes);
+                true
+            }
+            (Object::Stream(sx), Object::Stream(sy)) if self.comparison == Comparison::Equivalent => {
+                let ((dx, data_x), (dy, data_y)) = (decoded(sx), decoded(sy));
+                self.compare_dicts(&dx, &dy, path, changes);
+                if data_x != data_y {
+                    changes.push(ValueChange {
+                        path: path.clone(),
+                        change: Change::StreamData,
+                    });
+                }
+                true
+            }
+            (Object::String(_) | Object::HexString(_), Object::String(_) | Object::HexString(_))
+                if self.comparison == Comparison::Equivalent =>
+            {
+                string_bytes(x) == string_bytes(y)
+            }
+            (Object::Stream(sx), Object::Stream(sy)) => {
+                self.compare_dicts(&sx.dictionary, &sy.dictionary, path, changes);
+                if sx.data != sy.data {
+                    changes.push(ValueChange {
+                        path: path.clone(),
+                        change: Change::StreamData,
+                    });
+                }
+                true
+            }
+            (Object::Array(ax), Object::Array(ay)) => {
+                for index in 0..ax.len().max(ay.len()) {
+                    path.push(PathElement::Index(index));
+                    match (ax.get(index), ay.get(index)) {
+                        (Some(x), Some(y)) => self.compare(x, y, path, changes),
+                        (Some(x), None) => changes.push(ValueChange {
+                            path: path.clone(),
+                            change: Change::Removed(x.clone()),
+                        }),
+                        (None, Some(y)) => changes.push(ValueChange {
+                            path: path.clone(),
+                            change: Change::Added(y.clone()),
+                        }),
+                        (None, None) => unreachable!("index is below the length of one array"),
+                    }
+                    path.pop();
+                }
+                true
+            }
+            (Object::Integer(_) | Object::Float(_), Object::Integer(_) | Object::Float(_)) => {
+                (number(x) - number(y)).abs() <= EPSILON
+            }
+            (x, y) => x == y,
+        };
+        if !equal {
+            changes.push(ValueChange {
+                path: path.clone(),
+                change: Change::Value {
+                    before: x.clone(),
+                    after: y.clone(),
+                },
+            });
+        }
+    }
+
+    fn compare_dicts(
+        &mut self,
+        dx: &Dictionary,
+        dy: &Dictionary,
+        path: &mut Vec<PathElement>,
+        changes: &mut Vec<ValueChange>,
+    ) {
+        let mut keys = dx
+            .keys()
+            .chain(dy.keys())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        keys.sort_unstable_by_key(|key| &key[..]);
+        for key in keys {
+            path.push(PathElement::Key(key.clone()));
+            match (dx.get(key), dy.get(key)) {
+                (Some(x), Some(y)) => self.compare(x, y, path, changes),
+                (Some(x), None) => changes.push(ValueChange {
+                    path: path.clone(),
+                    change: Change::Removed(x.clone()),
+                }),
+                (None, Some(y)) => changes.push(ValueChange {
+                    path: path.clone(),
+                    change: Change::Added(y.clone()),
+                }),
+                (None, None) => unreachable!("key is from one of the dictionaries"),
+            }
+            path.pop();
+        }
+    }
+
+    /// Match the referenced objects. Returns `false` if one of them is
+    /// already matched with a different object.
+    fn pair(&mut self, ra: Reference, rb: Reference) -> bool {
+        if let Some(paired) = self.pairs.get(&ra) {
+            return *paired == rb;
+        }
+        if self.paired.contains(&rb) {
+            return false;
+        }
+        // Dangling references are compared as values.
+        if self.a.lookup_reference(&ra).is_none() || self.b.lookup_reference(&rb).is_none() {
+            return self.a.lookup_reference(&ra).is_none() && self.b.lookup_reference(&rb).is_none();
+        }
+        self.pairs.insert(ra, rb);
+        self.paired.insert(rb);
+        self.pending.push((ra, rb));
+        true
+    }
+}
+
+fn number(obj: &Object) -> f32 {
+    match obj {
+        Object::Integer(i) => *i as f32,
+        Object::Float(f) => *f,
+        _ => f32::NAN,
+    }
+}
+
+struct Path<'a>(&'a [PathElement]);
+
+impl Display for Path<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "(object)");
+        }
+        self.0.iter().try_for_each(|element| write!(f, "{}", element))
+    }
+}
+
+impl Display for ValueChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = Path(&self.path);
+        match &self.change {
+            Change::Value { before, after } => write!(f, "{}: {} -> {}", path, before, after),
+            Change::Added(value) => write!(f, "{}: added {}", path, value),
+            Change::Removed(value) => write!(f, "{}: removed {}", path, value),
+            Change::StreamData => write!(f, "{}: stream data differs", path),
+        }
+    }
+}
+
+fn write_numbers(f: &mut std::fmt::Formatter<'_>, title: &str, numbers: &[usize]) -> std::fmt::Result {
+    if !numbers.is_empty() {
+        let numbers = numbers.iter().map(usize::to_string).collect::<Vec<_>>();
+        writeln!(f, "{}: {}", title, numbers.join(", "))?;
+    }
+    Ok(())
+}
+
+impl Display for PdfDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No differences");
+        }
+        if let Some((before, after)) = self.page_count {
+            let count = |c: Option<usize>| c.map_or_else(|| "invalid".to_string(), |c| c.to_string());
+            writeln!(f, "Page count: {} -> {}", count(before), count(after))?;
+        }
+        if !self.trailer.is_empty() {
+            writeln!(f, "Trailer:")?;
+            for change in &self.trailer {
+                writeln!(f, "  {}", change)?;
+            }
+        }
+        write_numbers(f, "Removed objects", &self.removed)?;
+        write_numbers(f, "Added objects", &self.added)?;
+        for object in &self.changed {
+            if object.before == object.after {
+                writeln!(f, "Object {}:", object.before)?;
+            } else {
+                writeln!(f, "Object {} (now {}):", object.before, object.after)?;
+            }
+            for change in &object.changes {
+                writeln!(f, "  {}", change)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf::tests::parsed_pdf;
+
+    use super::*;
+
+    const ORIGINAL: [&[u8]; 5] = [
+        b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj",
+        b"2 0 obj <</Type /Pages /Kids [3 0 R] /Count 1>> endobj",
+        b"3 0 obj <</Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Resources <</Font <</F1 4 0 R>>>>>> endobj",
+        b"4 0 obj <</Type /Font /Subtype /Type1 /BaseFont /Helvetica>> endobj",
+        b"9 0 obj (orphan) endobj",
+    ];
+
+    #[test]
+    fn identical() {
+        let diff = diff(
+            &parsed_pdf(Reference::new(1, 0), &ORIGINAL),
+            &parsed_pdf(Reference::new(1, 0), &ORIGINAL),
+        );
+        assert!(diff.is_empty(), "{:?}", diff);
+        assert_eq!(diff.to_string(), "No differences\n");
+    }
+
+    #[test]
+    fn changed_and_added() {
+        // The objects are renumbered, a font is added and the base font of
+        // the existing one changed.
+        let changed = parsed_pdf(
+            Reference::new(1, 0),
+            &[
+                b"1 0 obj <</Type /Catalog /Pages 5 0 R>> endobj",
+                b"5 0 obj <</Type /Pages /Kids [6 0 R] /Count 1>> endobj",
+                b"6 0 obj <</Type /Page /Parent 5 0 R /MediaBox [0 0 612.00001 792] \
+              /Resources <</Font <</F1 7 0 R /F2 8 0 R>>>>>> endobj",
+                b"7 0 obj <</Type /Font /Subtype /Type1 /BaseFont /Times-Roman>> endobj",
+                b"8 0 obj <</Type /Font /Subtype /Type1 /BaseFont /Courier>> endobj",
+            ],
+        );
+        let diff = diff(&parsed_pdf(Reference::new(1, 0), &ORIGINAL), &changed);
+
+        assert_eq!(diff.added, [8]);
+        assert!(diff.removed.is_empty());
+        assert!(diff.trailer.is_empty());
+        assert_eq!(diff.page_count, None);
+        assert_eq!(
+            diff.changed,
+            [
+                ObjectChange {
+                    before: 3,
+                    after: 6,
+                    changes: vec![ValueChange {
+                        path: ["Resources", "Font", "F2"]
+                            .iter()
+                            .map(|k| PathElement::Key(k.as_bytes().to_vec().into()))
+                            .collect(),
+                        change: Change::Added(Object::Reference(Reference::new(8, 0))),
+                    }],
+                },
+                ObjectChange {
+                    before: 4,
+                    after: 7,
+                    changes: vec![ValueChange {
+                        path: vec![PathElement::Key(b"BaseFont".to_vec().into())],
+                        change: Change::Value {
+                            before: Object::Name(b"Helvetica".to_vec().into()),
+                            after: Object::Name(b"Times-Roman".to_vec().into()),
+                        },
+                    }],
+                },
+            ]
+        );
+        assert_eq!(
+            diff.to_string(),
+            "Added objects: 8\n\
+             Object 3 (now 6):\n  /Resources/Font/F2: added 8 0 R\n\
+             Object 4 (now 7):\n  /BaseFont: /Helvetica -> /Times-Roman\n"
+        );
+    }
+
+    #[test]
+    fn image_data_keeps_its_filter() {
+        let pdf = parsed_pdf(
+            Reference::new(1, 0),
+            &[b"1 0 obj <</Filter /DCTDecode /Length 4>> stream\n\xff\xd8\xff\xd9\nendstream endobj"],
+        );
+        let stream = pdf.object(1).unwrap().indirect().unwrap().object().stream().unwrap();
+        let (dictionary, data) = decoded(stream);
+        assert_eq!(dictionary, stream.dictionary);
+        assert_eq!(data, b"\xff\xd8\xff\xd9");
+    }
+}
@@ -1,22 +1,74 @@
-use std::fmt::Debug;
+#[cfg(feature = "std-fs")]
+use std::path::Path;
+use std::{fmt::Debug, io, path::PathBuf};
 
-use crate::parse::error::CbParseError;
+use nom::error::ErrorKind;
+
+use crate::{
+    parse::error::{CbParseError, CbParseErrorKind},
+    pdf::object::stream::filter::FilterError,
+    simple_encode::EncodeError,
+};
 
 #[derive(Debug, Clone)]
 pub enum CbError {
-    Parse,
-    Io,
+    /// The document is invalid, the error describes where parsing failed.
+    Parse(CbParseError<()>),
+    /// Parsing was cancelled with
+    /// [ParseOptions::cancel](crate::parse::ParseOptions::cancel).
+    Cancelled,
+    /// Reading or writing failed. `path` is the file, if the error is about
+    /// one.
+    Io {
+        path: Option<PathBuf>,
+        kind: io::ErrorKind,
+        message: String,
+    },
+    Encode(EncodeError),
 }
 
 impl<I: Debug> From<nom::Err<CbParseError<I>>> for CbError {
     fn from(err: nom::Err<CbParseError<I>>) -> Self {
+        let err = match err {
+            nom::Err::Error(e) | nom::Err::Failure(e) => e,
+            nom::Err::Incomplete(needed) => {
+                log::error!("Parsing failed: {:?}", needed);
+                return CbError::Parse(CbParseError::new((), CbParseErrorKind::Nom(ErrorKind::Eof)));
+            }
+        };
+        if let CbParseErrorKind::Cancelled | CbParseErrorKind::StreamError(FilterError::Cancelled) = err.kind {
+            return CbError::Cancelled;
+        }
         log::error!("Parsing failed: {:?}", err);
-        CbError::Parse
+        CbError::Parse(err.without_input())
+    }
+}
+
+impl CbError {
+    /// An I/O error about the file at `path`.
+    #[cfg(feature = "std-fs")]
+    pub(crate) fn io(err: io::Error, path: &Path) -> Self {
+        log::error!("I/O error for {:?}: {}", path, err);
+        CbError::Io {
+            path: Some(path.to_path_buf()),
+            kind: err.kind(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<io::Error> for CbError {
+    fn from(err: io::Error) -> Self {
+        CbError::Io {
+            path: None,
+            kind: err.kind(),
+            message: err.to_string(),
+        }
     }
 }
 
-impl From<std::io::Error> for CbError {
-    fn from(_: std::io::Error) -> Self {
-        CbError::Io
+impl From<EncodeError> for CbError {
+    fn from(err: EncodeError) -> Self {
+        CbError::Encode(err)
     }
 }
@@ -0,0 +1,110 @@
+//! Entry points for fuzzing, e.g. with `cargo fuzz`.
+//!
+//! The functions take arbitrary bytes, exercise the parser and ignore all
+//! errors. Any panic is a bug. Inputs that caused panics are kept in
+//! `tests/corpus`.
+
+use std::sync::Arc;
+
+use crate::{
+    parse::{content::parse_operations, parse_complete, parse_indirect_object, parse_object, shared_span},
+    pdf::{object::stream::filter::filter, LazyPdf, RawPdf},
+    simple_encode::{EncodeOptions, SimpleEncoder},
+};
+
+/// Pages that are inspected at most, to keep runs short.
+const MAX_PAGES: usize = 64;
+
+/// Filters that are applied by [fuzz_filters].
+const FILTERS: &[&[u8]] = &[b"ASCIIHexDecode", b"ASCII85Decode", b"FlateDecode", b"RunLengthDecode"];
+
+/// Parse `data` as complete and as lazy document and access the pages.
+pub fn fuzz_parse_document(data: &[u8]) {
+    let buf = Arc::new(data.to_vec());
+    if let Ok((_, pdf)) = parse_complete(shared_span(&buf)) {
+        inspect(&pdf);
+        let _ = pdf.stats();
+        let _ = pdf.validate();
+        let _ = pdf.reference_map();
+        for section in &pdf.sections {
+            for obj in section.objects.values() {
+                if let Some(stream) = obj.indirect().and_then(|i| i.object().stream()) {
+                    let _ = stream.filtered_data();
+                }
+            }
+        }
+        let _ = SimpleEncoder::write_with(&pdf, &mut Vec::new(), EncodeOptions::default());
+    }
+
+    if let Ok(pdf) = LazyPdf::parse(data.to_vec()) {
+        let _ = pdf.info().map(|info| info.title());
+        if let Ok(count) = pdf.page_count() {
+            for page in (0..count.min(MAX_PAGES)).filter_map(|i| pdf.page(i)) {
+                let _ = page.operations();
+            }
+        }
+    }
+}
+
+fn inspect(pdf: &RawPdf) {
+    let _ = pdf.info().map(|info| info.title());
+    let Ok(count) = pdf.page_count() else {
+        return;
+    };
+    for page in (0..count.min(MAX_PAGES)).filter_map(|i| pdf.page(i)) {
+        let _ = page.media_box();
+        let _ = page.resources();
+        let _ = page.operations();
+        let _ = page.annotations().count();
+    }
+}
+
+/// Parse `data` as object, as indirect object and as content stream.
+pub fn fuzz_parse_object(data: &[u8]) {
+    let mut rest = data;
+    while let Ok((_, consumed)) = parse_object(rest) {
+        if consumed == 0 {
+            break;
+        }
+        rest = &rest[consumed..];
+    }
+    let _ = parse_indirect_object(data);
+    let _ = parse_operations(data);
+}
+
+/// Apply a filter to `data`. The first byte selects the filter.
+pub fn fuzz_filters(data: &[u8]) {
+    let Some((selector, data)) = data.split_first() else {
+        return;
+    };
+    let name = FILTERS[usize::from(*selector) % FILTERS.len()];
+    let _ = filter(&name.to_vec().into(), None, &data.to_vec().into());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    /// Run the inputs in `tests/corpus` through the fuzz target that is named
+    /// by the prefix of the file name.
+    #[test]
+    fn corpus() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+        let mut count = 0;
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            let data = std::fs::read(&path).unwrap();
+            match name.split('-').next() {
+                Some("document") => fuzz_parse_document(&data),
+                Some("object") => fuzz_parse_object(&data),
+                Some("filter") => fuzz_filters(&data),
+                _ => panic!("No fuzz target for {}", name),
+            }
+            count += 1;
+        }
+        assert!(count > 0);
+    }
+}
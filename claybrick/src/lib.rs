@@ -1,30 +1,232 @@
-use std::{fs::File, io::Read};
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
 
-use error::CbError;
-use nom_locate::LocatedSpan;
-use nom_tracable::TracableInfo;
-use parse::parse_complete;
-use pdf::RawPdf;
+use parse::{
+    parse_complete, parse_complete_with, shared_span, span, with_options, ObjectOptions, ParseOptions, ParseWarning,
+};
+#[cfg(feature = "std-fs")]
+use pdf::LazyPdf;
+use pdf::{Object, PdfSection, RawPdf, Trailer, Xref};
+use simple_encode::{EncodeError, EncodeOptions, SimpleEncoder};
+use writer::{IoWriter, Writer};
 
+// The parser logs through `tracing` if the feature is enabled, thus events are
+// recorded in the spans of the objects.
+#[cfg(not(feature = "tracing"))]
+use log as logging;
+#[cfg(feature = "tracing")]
+use tracing as logging;
+
+pub use build::merge;
+pub use diff::diff;
+pub use error::CbError;
+
+pub mod build;
+pub mod diff;
 mod error;
+#[doc(hidden)]
+pub mod fuzz;
 pub mod parse;
-mod pdf;
+pub mod pdf;
 pub mod simple_encode;
+pub mod testing;
 pub mod writer;
 
+/// Parse a PDF that is already in memory, e.g. in environments without file
+/// system. Stream data shares `input` instead of copying it.
+pub fn read_bytes(input: Vec<u8>) -> Result<RawPdf, CbError> {
+    read_bytes_with(input, &ParseOptions::default())
+}
+
+/// Like [read_bytes] but reports the progress and can be cancelled, see
+/// [ParseOptions].
+pub fn read_bytes_with(input: Vec<u8>, options: &ParseOptions) -> Result<RawPdf, CbError> {
+    let buf = Arc::new(input);
+    let object_options = ObjectOptions::from(options);
+    let span = with_options(shared_span(&buf), &object_options);
+    let (_, pdf) = parse_complete_with(span, options)?;
+
+    Ok(pdf)
+}
+
 /// Read a PDF file and return the parsed `Pdf`.
-///
-/// Panics if the file cannot be read or the PDF cannot get parsed.
-/// FIXME: don't panic.
+#[cfg(feature = "std-fs")]
 pub fn read_file(file_path: &std::path::Path) -> Result<RawPdf, CbError> {
-    let mut input_file = File::open(file_path)?;
-    let mut buf = Vec::new();
-    input_file.read_to_end(&mut buf)?;
+    read_bytes(std::fs::read(file_path).map_err(|err| CbError::io(err, file_path))?)
+}
 
-    let info = TracableInfo::new().forward(true).backward(true);
-    let span = LocatedSpan::new_extra(&buf[..], info);
+/// Read a PDF file but only parse the structure of the document. Objects are
+/// parsed on first access, see [LazyPdf].
+#[cfg(feature = "std-fs")]
+pub fn read_file_lazy(file_path: &std::path::Path) -> Result<LazyPdf, CbError> {
+    LazyPdf::parse(std::fs::read(file_path).map_err(|err| CbError::io(err, file_path))?)
+}
 
+/// Write `pdf` to `writer` with [SimpleEncoder::write_with]. The output is
+/// written while it is encoded, thus `writer` should be buffered.
+pub fn write_to(pdf: &RawPdf, writer: impl io::Write, options: &EncodeOptions) -> Result<(), CbError> {
+    let mut out = IoWriter::new(writer);
+    SimpleEncoder::write_with(pdf, &mut out, *options)?;
+    out.into_result()?;
+    Ok(())
+}
+
+/// Write `pdf` to the file at `path`, which is created or truncated. The file
+/// is synced to disk if [EncodeOptions::sync_file] is set.
+///
+/// I/O errors contain the path. Encoding errors are found before anything is
+/// written, except for numbers that are too large for the output.
+#[cfg(feature = "std-fs")]
+pub fn write_file(pdf: &RawPdf, path: &std::path::Path, options: &EncodeOptions) -> Result<(), CbError> {
+    let io_error = |err| CbError::io(err, path);
+    let file = std::fs::File::create(path).map_err(io_error)?;
+    let mut out = IoWriter::new(io::BufWriter::new(file));
+    SimpleEncoder::write_with(pdf, &mut out, *options)?;
+    let file = out
+        .into_result()
+        .map_err(io_error)?
+        .into_inner()
+        .map_err(|err| io_error(err.into_error()))?;
+    if options.sync_file {
+        file.sync_all().map_err(io_error)?;
+    }
+    Ok(())
+}
+
+/// Append `changes` to the `original` file as an incremental update.
+///
+/// The original bytes are copied unchanged, followed by the changed objects,
+/// a xref section for them and a trailer that points to the original xref
+/// section. Each change is an object number and the new object. Objects with
+/// numbers beyond the `Size` of the original trailer are added, all others
+/// replace the original object. Numbers for new objects can be taken from
+/// [RawPdf::allocate_object_number], which reuses freed numbers.
+///
+/// The offsets in the xref section are counted from the header of the
+/// original file at the start of `out`, thus `out` should be empty. Xref
+/// tables are written instead of streams for originals older than PDF 1.5.
+pub fn write_incremental(
+    original: &[u8],
+    changes: &[(usize, Object)],
+    out: &mut dyn Writer,
+    options: EncodeOptions,
+) -> Result<(), CbError> {
+    let span = span(original);
     let (_, pdf) = parse_complete(span)?;
+    let (_, startxref) = parse::startxref(span, &ParseOptions::default())?;
 
-    Ok(pdf)
+    out.write(original);
+    simple_encode::write_update(&pdf, startxref, changes, out, options)?;
+    Ok(())
+}
+
+/// Parse a damaged document and write it again, like readers that fix
+/// documents on open.
+///
+/// The parser works around the problems of `input`, e.g. it rebuilds invalid
+/// xref sections and uses the data up to the end of line before `endstream`
+/// for streams with a wrong `/Length`. The document is written with
+/// [SimpleEncoder] as a single section, with the newest version of each
+/// object. Returns the written document and the problems that were worked
+/// around.
+///
+/// Encrypted documents fail with [EncodeError::Encrypted], the objects of
+/// their object streams can't be read without decrypting them.
+pub fn repair(input: &[u8]) -> Result<(Vec<u8>, Vec<ParseWarning>), CbError> {
+    let warnings = Arc::new(Mutex::new(Vec::new()));
+    let collected = warnings.clone();
+    let options = ParseOptions {
+        warnings: Some(Arc::new(move |warning| {
+            if let Ok(mut warnings) = collected.lock() {
+                warnings.push(warning);
+            }
+        })),
+        ..Default::default()
+    };
+    let pdf = read_bytes_with(input.to_vec(), &options)?;
+    if pdf.is_encrypted() {
+        logging::error!("Encrypted documents can't be repaired");
+        return Err(EncodeError::Encrypted.into());
+    }
+
+    let mut out = Vec::new();
+    SimpleEncoder::write_with(&single_section(&pdf), &mut out, EncodeOptions::default())?;
+    let warnings = warnings
+        .lock()
+        .map(|mut warnings| std::mem::take(&mut *warnings))
+        .unwrap_or_default();
+    Ok((out, warnings))
+}
+
+/// Read the file at `path` and [repair] it.
+#[cfg(feature = "std-fs")]
+pub fn repair_file(path: &std::path::Path) -> Result<(Vec<u8>, Vec<ParseWarning>), CbError> {
+    repair(&std::fs::read(path).map_err(|err| CbError::io(err, path))?)
+}
+
+/// The objects of all sections that are in use, in a single section.
+fn single_section(pdf: &RawPdf) -> RawPdf {
+    let objects = pdf
+        .objects()
+        .map(|(number, _, obj)| (number, obj.clone()))
+        .collect::<fnv::FnvHashMap<_, _>>();
+    let trailer = &pdf.sections[0].trailer;
+    let trailer = Trailer {
+        size: objects.keys().max().map_or(1, |&last| last + 1),
+        previous: None,
+        x_ref_stm: None,
+        ..trailer.clone()
+    };
+
+    RawPdf {
+        version: pdf.version,
+        binary_indicator: pdf.binary_indicator.clone(),
+        header_offset: 0,
+        sections: vec![PdfSection {
+            objects,
+            trailer,
+            xref: Xref::new(Vec::new()),
+            object_spans: Default::default(),
+            stream_spans: Default::default(),
+        }],
+        allocated: Default::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::PdfBuilder;
+
+    use super::*;
+
+    /// Works without `std-fs`, like on `wasm32-unknown-unknown`.
+    #[test]
+    fn read_bytes_without_file_system() {
+        let mut builder = PdfBuilder::new();
+        builder.add_page(612.0, 792.0);
+        builder.add_page(595.0, 842.0);
+        let mut out = Vec::new();
+        SimpleEncoder::write_with(&builder.finish().unwrap(), &mut out, EncodeOptions::default()).unwrap();
+
+        let pdf = read_bytes(out).unwrap();
+        let catalog = pdf.catalog().unwrap();
+        assert_eq!(catalog.page_count(), Ok(2));
+        let page = catalog.page(1).unwrap();
+        assert_eq!(page.media_box().map(|b| (b.width(), b.height())), Some((595.0, 842.0)));
+
+        assert!(read_bytes(b"%PDF-1.7\nnot a pdf".to_vec()).is_err());
+    }
+
+    #[cfg(not(feature = "encryption"))]
+    #[test]
+    fn encrypt_without_feature() {
+        let pdf = PdfBuilder::new().finish().unwrap();
+        let params = simple_encode::EncryptionParams::default();
+        assert_eq!(
+            SimpleEncoder::write_encrypted(&pdf, &mut Vec::new(), EncodeOptions::default(), &params),
+            Err(simple_encode::EncodeError::EncryptionUnsupported)
+        );
+    }
 }
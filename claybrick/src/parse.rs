@@ -1,30 +1,379 @@
+// With the `trace` feature the spans carry the tracer state, which makes the
+// errors of all parsers larger than the lint allows.
+#![cfg_attr(feature = "trace", allow(clippy::result_large_err))]
+
 use nom::{bytes, character, error::ParseError, IResult, InputIter, InputLength, InputTake, Parser};
 use nom_locate::LocatedSpan;
-use nom_tracable::{tracable_parser, TracableInfo};
+#[cfg(feature = "trace")]
+use nom_tracable::{tracable_parser, HasTracableInfo, TracableInfo};
 
-use crate::pdf::{PdfSection, RawPdf};
+use fnv::{FnvHashMap, FnvHashSet};
+use std::{
+    ops::Range,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use crate::{
+    error::CbError,
+    logging,
+    pdf::{Bytes, Name, Object, PdfSection, RawPdf, Reference, StreamDataProvenance, Trailer, Xref},
+};
 
 use self::{
     error::{CbParseError, CbParseErrorKind},
     object::{indirect_object, object},
     object_stream::object_stream,
-    trailer::trailer_tail,
+    trailer::{trailer_tail, xref_and_trailer},
 };
 
-pub use self::xref::{eof_marker_tail, startxref_tail, xref};
+pub use self::xref::{eof_marker_tail, startxref_tail, xref, EOF_SEARCH_WINDOW};
 
+pub mod content;
 pub mod error;
 pub(crate) mod object;
-mod object_stream;
+pub(crate) mod object_stream;
+mod rebuild;
 mod trailer;
-mod xref;
+pub(crate) mod xref;
+
+/// The state that is passed along with the input of all parsers, see [Span].
+#[derive(Clone, Copy, Default)]
+pub struct ParseContext<'a> {
+    /// The buffer that the input starts at, stream data shares it instead of
+    /// copying it. See [shared_span].
+    buffer: Option<&'a Arc<Vec<u8>>>,
+    /// The defaults apply without options, see [with_options].
+    options: Option<&'a ObjectOptions>,
+    /// The number of the object stream whose data is parsed, see
+    /// [ParseContext::in_object_stream]. Object numbers fit `u32`, a `usize`
+    /// would make the parse errors too large.
+    object_stream: Option<u32>,
+    #[cfg(feature = "trace")]
+    trace: TracableInfo,
+}
+
+impl<'a> ParseContext<'a> {
+    /// How dictionaries with duplicate keys are parsed.
+    pub(crate) fn duplicate_keys(&self) -> DuplicateKeys {
+        self.options.map(|options| options.duplicate_keys).unwrap_or_default()
+    }
+
+    pub(crate) fn strict(&self) -> bool {
+        self.options.is_some_and(|options| options.strict)
+    }
 
-pub type Span<'a> = LocatedSpan<&'a [u8], TracableInfo>;
+    /// The context for the data of the object stream `number`. Offsets in
+    /// warnings are relative to the data then.
+    pub(crate) fn in_object_stream(self, number: usize) -> Self {
+        Self {
+            object_stream: u32::try_from(number).ok(),
+            ..self
+        }
+    }
+
+    /// The number of the object stream whose data is parsed.
+    pub(crate) fn object_stream(&self) -> Option<usize> {
+        self.object_stream.map(|number| number as usize)
+    }
+
+    /// The flag that cancels the parse, filters check it too.
+    pub(crate) fn cancel(&self) -> Option<&'a AtomicBool> {
+        self.options?.cancel.as_deref()
+    }
+
+    /// Pass `warning` to the callback of the options.
+    pub(crate) fn warn(&self, warning: ParseWarning) {
+        let (new, callback) = match self.options {
+            Some(options) => {
+                let new = options
+                    .reported
+                    .lock()
+                    .map_or(true, |mut reported| reported.insert(warning.clone()));
+                (new, options.warnings.as_ref())
+            }
+            None => (true, None),
+        };
+        if !new {
+            return;
+        }
+        logging::warn!("{:?}", warning);
+        if let Some(callback) = callback {
+            callback(warning);
+        }
+    }
+}
+
+// Custom impl since the buffer is too long to show.
+impl std::fmt::Debug for ParseContext<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("ParseContext");
+        debug.field("buffer", &self.buffer.map(|buffer| buffer.len()));
+        debug.field("options", &self.options.is_some());
+        debug.field("object_stream", &self.object_stream);
+        #[cfg(feature = "trace")]
+        debug.field("trace", &self.trace);
+        debug.finish()
+    }
+}
+
+/// Starts tracing with `trace`, e.g. for [LocatedSpan::new_extra].
+#[cfg(feature = "trace")]
+impl From<TracableInfo> for ParseContext<'_> {
+    fn from(trace: TracableInfo) -> Self {
+        Self {
+            trace,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(feature = "trace")]
+impl HasTracableInfo for ParseContext<'_> {
+    fn get_tracable_info(&self) -> TracableInfo {
+        self.trace
+    }
+
+    fn set_tracable_info(mut self, trace: TracableInfo) -> Self {
+        self.trace = trace;
+        self
+    }
+}
+
+/// The input of all parsers. Carries the [ParseContext], which includes the
+/// trace state if the `trace` feature is enabled.
+pub type Span<'a> = LocatedSpan<&'a [u8], ParseContext<'a>>;
 type CbParseResult<'a, O> = IResult<Span<'a>, O, error::CbParseError<Span<'a>>>;
 
-#[tracable_parser]
+/// Wrap `input` to start parsing at its beginning.
+pub(crate) fn span(input: &[u8]) -> Span<'_> {
+    Span::from(input)
+}
+
+/// Like [span], but stream data that is parsed from `buffer` shares the
+/// buffer instead of copying it.
+pub(crate) fn shared_span(buffer: &Arc<Vec<u8>>) -> Span<'_> {
+    let context = ParseContext {
+        buffer: Some(buffer),
+        ..Default::default()
+    };
+    Span::new_extra(&buffer[..], context)
+}
+
+/// `input` that is parsed with the object `options`.
+pub(crate) fn with_options<'a>(input: Span<'a>, options: &'a ObjectOptions) -> Span<'a> {
+    input.map_extra(|context| ParseContext {
+        options: Some(options),
+        ..context
+    })
+}
+
+/// Like [span] for input that is parsed while another parser runs, e.g. the
+/// data of an object stream. The input keeps the options of `context`. The
+/// tracer resets its state for input at depth 0, which breaks the trace of
+/// the running parser.
+pub(crate) fn nested_span<'a>(input: &'a [u8], context: ParseContext<'a>) -> Span<'a> {
+    let span = Span::new_extra(
+        input,
+        ParseContext {
+            buffer: None,
+            ..context
+        },
+    );
+    #[cfg(feature = "trace")]
+    return nom_tracable::Tracable::inc_depth(span);
+    #[cfg(not(feature = "trace"))]
+    span
+}
+
+/// The parsed bytes as `Bytes` that share the input buffer if the input
+/// was created by [shared_span]. The offset of a span is relative to the
+/// start of that buffer.
+pub(crate) fn shared_bytes(parsed: &Span) -> Bytes {
+    let start = parsed.location_offset();
+    let range = start..start + parsed.len();
+    match parsed.extra.buffer {
+        Some(buffer) if range.end <= buffer.len() => {
+            debug_assert!(buffer[range.clone()] == **parsed.fragment());
+            Bytes::shared(buffer, range)
+        }
+        _ => parsed.fragment().to_vec().into(),
+    }
+}
+
+/// The parts of [ParseOptions] that are used by the object parsers, see
+/// [with_options].
+#[derive(Default)]
+pub(crate) struct ObjectOptions {
+    duplicate_keys: DuplicateKeys,
+    warnings: Option<WarningCallback>,
+    strict: bool,
+    cancel: Option<Arc<AtomicBool>>,
+    /// The parsers backtrack and parse some dictionaries twice, but each
+    /// warning is reported once.
+    reported: Mutex<FnvHashSet<ParseWarning>>,
+}
+
+impl From<&ParseOptions> for ObjectOptions {
+    fn from(options: &ParseOptions) -> Self {
+        Self {
+            duplicate_keys: options.duplicate_keys,
+            warnings: options.warnings.clone(),
+            strict: options.strict,
+            cancel: options.cancel.clone(),
+            reported: Mutex::default(),
+        }
+    }
+}
+
+/// How dictionaries that contain a key more than once are parsed. The spec
+/// doesn't allow duplicate keys, but readers disagree which value to use.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    /// Keep the value of the last occurrence.
+    #[default]
+    LastWins,
+    /// Keep the value of the first occurrence.
+    FirstWins,
+    /// Fail with [CbParseErrorKind::DuplicateKey].
+    Reject,
+}
+
+/// Problems of the document that the parser works around.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ParseWarning {
+    /// A dictionary contains `key` more than once. `dictionary_offset` is the
+    /// byte offset of the dictionary, relative to the data of the object
+    /// stream `object_stream` if the dictionary is in one.
+    DuplicateKey {
+        key: Name,
+        dictionary_offset: usize,
+        object_stream: Option<usize>,
+    },
+    /// No `%%EOF` marker was found near the end, `startxref` was searched
+    /// instead.
+    MissingEof,
+    /// The `stream` keyword is followed by a lone carriage return.
+    /// `object_offset` is the byte offset of the stream dictionary.
+    StreamCr { object_offset: usize },
+    /// The `/Length` of a stream doesn't end at `endstream`. The data up to
+    /// `endstream` is used. `object_hint` is the byte offset of the stream
+    /// dictionary.
+    StreamLengthMismatch {
+        declared: usize,
+        actual: usize,
+        object_hint: usize,
+    },
+    /// The header announces a version that doesn't exist, e.g. `%PDF-9.9`.
+    UnknownVersion { version: (u8, u8) },
+    /// The xref sections are missing or invalid. A single section was
+    /// rebuilt from the objects found in the document.
+    XrefRebuilt,
+    /// A xref stream contains more or less entries than its `/Index`
+    /// declares. Extra entries are dropped.
+    XrefEntryCount { declared: usize, actual: usize },
+    /// The `/Prev` of a trailer is not an offset. The older sections are
+    /// not parsed.
+    InvalidPrevious,
+}
+
+/// Called for each [ParseWarning], see [ParseOptions::warnings].
+pub type WarningCallback = Arc<dyn Fn(ParseWarning) + Send + Sync>;
+
+/// Parsed objects between two progress reports.
+pub const PROGRESS_INTERVAL: usize = 1000;
+
+/// Options for parsing complete documents with
+/// [read_bytes_with](crate::read_bytes_with).
+pub struct ParseOptions {
+    /// Called at the start and the end of each section and every
+    /// [PROGRESS_INTERVAL] objects. With the `rayon` feature, it's called from
+    /// the thread pool.
+    pub progress: Option<Box<dyn Fn(ParseProgress) + Send + Sync>>,
+    /// Parsing fails with [CbParseErrorKind::Cancelled] soon after the flag is
+    /// set.
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// How dictionaries with duplicate keys are parsed.
+    pub duplicate_keys: DuplicateKeys,
+    /// Called for each problem that the parser works around. With the
+    /// `rayon` feature, it's called from the thread pool.
+    pub warnings: Option<WarningCallback>,
+    /// Bytes at the end of the document that are searched for the `%%EOF`
+    /// marker.
+    pub eof_search_window: usize,
+    /// Fail instead of working around a missing `%%EOF` marker, a lone
+    /// carriage return after the `stream` keyword, a wrong stream length, an
+    /// unknown version, invalid xref sections or an invalid `/Prev`.
+    pub strict: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            progress: None,
+            cancel: None,
+            duplicate_keys: DuplicateKeys::default(),
+            warnings: None,
+            eof_search_window: EOF_SEARCH_WINDOW,
+            strict: false,
+        }
+    }
+}
+
+// Custom impl since the callback isn't `Debug`.
+impl std::fmt::Debug for ParseOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParseOptions")
+            .field("progress", &self.progress.is_some())
+            .field("cancel", &self.cancel)
+            .field("duplicate_keys", &self.duplicate_keys)
+            .field("warnings", &self.warnings.is_some())
+            .field("eof_search_window", &self.eof_search_window)
+            .field("strict", &self.strict)
+            .finish()
+    }
+}
+
+impl ParseOptions {
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+
+    fn check_cancelled<'a>(&self, input: Span<'a>) -> Result<(), nom::Err<CbParseError<Span<'a>>>> {
+        if self.is_cancelled() {
+            logging::info!("Parsing cancelled");
+            return Err(nom::Err::Failure(CbParseError::new(input, CbParseErrorKind::Cancelled)));
+        }
+        Ok(())
+    }
+
+    fn report(&self, progress: ParseProgress) {
+        if let Some(callback) = &self.progress {
+            callback(progress);
+        }
+    }
+}
+
+/// Progress of parsing a document, see [ParseOptions::progress].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseProgress {
+    /// Index of the current section, starting with the newest one.
+    pub section: usize,
+    /// Number of sections in the document.
+    pub sections: usize,
+    /// Objects of the current section that were parsed so far.
+    pub objects_done: usize,
+    /// Objects of the current section according to its xref section.
+    pub objects_total: usize,
+}
+
+#[cfg_attr(feature = "trace", tracable_parser)]
 fn version(input: Span) -> CbParseResult<(u8, u8)> {
-    let (remainder, _) = bytes::complete::tag_no_case("%PDF-")(input)?;
+    let (remainder, _) = bytes::complete::tag_no_case(VERSION_PREFIX)(input)?;
     let (remainder, major) = character::complete::u8(remainder)?;
     let (remainder, _) = character::complete::char('.')(remainder)?;
     let (remainder, minor) = character::complete::u8(remainder)?;
@@ -33,7 +382,7 @@ fn version(input: Span) -> CbParseResult<(u8, u8)> {
     Ok((remainder, (major, minor)))
 }
 
-#[tracable_parser]
+#[cfg_attr(feature = "trace", tracable_parser)]
 fn comment(input: Span) -> CbParseResult<Span> {
     let (remainder, _) = character::complete::multispace0(input)?;
     let (remainder, _) = character::complete::char('%')(remainder)?;
@@ -44,105 +393,609 @@ fn comment(input: Span) -> CbParseResult<Span> {
     Ok((remainder, comment))
 }
 
-#[tracable_parser]
-fn binary_indicator(input: Span) -> CbParseResult<bool> {
+#[cfg_attr(feature = "trace", tracable_parser)]
+fn binary_indicator(input: Span) -> CbParseResult<Option<Vec<u8>>> {
     if let Ok((r, comment)) = comment(input) {
         if comment.len() > 3 && !comment.iter().any(|&d| d < 128) {
-            Ok((r, true))
+            Ok((r, Some(comment.to_vec())))
         } else {
-            Ok((input, false))
+            Ok((input, None))
         }
     } else {
-        Ok((input, false))
+        Ok((input, None))
+    }
+}
+
+const VERSION_PREFIX: &[u8] = b"%PDF-";
+
+/// Bytes before the header that are skipped, like Acrobat does.
+pub const MAX_HEADER_OFFSET: usize = 1024;
+
+/// The `%PDF-` header and the comment that follows it.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Header {
+    pub(crate) version: (u8, u8),
+    /// The bytes of the comment if it marks the file as binary.
+    pub(crate) binary_indicator: Option<Vec<u8>>,
+    /// Bytes of junk before the header.
+    pub(crate) offset: usize,
+}
+
+/// Whether `version` is one of the PDF versions 1.0 to 1.7 and 2.0.
+pub(crate) fn is_known_version(version: (u8, u8)) -> bool {
+    matches!(version, (1, 0..=7) | (2, 0))
+}
+
+/// Warn about an unknown version in the header, or fail if `options` are
+/// strict.
+fn check_version<'a>(input: Span<'a>, version: (u8, u8), options: &ParseOptions) -> CbParseResult<'a, ()> {
+    if is_known_version(version) {
+        return Ok((input, ()));
     }
+    if options.strict {
+        return Err(nom::Err::Error(CbParseError::new(
+            input,
+            CbParseErrorKind::UnknownVersion(version),
+        )));
+    }
+    input.extra.warn(ParseWarning::UnknownVersion { version });
+    Ok((input, ()))
 }
 
 /// parse version and binary indicator comment.
-#[tracable_parser]
-pub(crate) fn header(input: Span) -> CbParseResult<((u8, u8), bool)> {
-    let (remainder, _) = character::complete::multispace0(input)?;
+///
+/// Up to [MAX_HEADER_OFFSET] bytes of junk before the header are skipped,
+/// e.g. a HTTP header or a printer driver preamble.
+#[cfg_attr(feature = "trace", tracable_parser)]
+pub(crate) fn header(input: Span) -> CbParseResult<Header> {
+    let window = &input.fragment()[..input.len().min(MAX_HEADER_OFFSET + VERSION_PREFIX.len())];
+    let offset = window
+        .windows(VERSION_PREFIX.len())
+        .position(|w| w.eq_ignore_ascii_case(VERSION_PREFIX))
+        .unwrap_or(0);
+    let (remainder, _) = bytes::complete::take(offset)(input)?;
+    let (remainder, _) = character::complete::multispace0(remainder)?;
+    let header_offset = remainder.location_offset() - input.location_offset();
     let (remainder, version) = version(remainder)?;
-    let (remainder, announced_binary) = binary_indicator(remainder)?;
+    let (remainder, binary_indicator) = binary_indicator(remainder)?;
 
-    Ok((remainder, (version, announced_binary)))
+    Ok((
+        remainder,
+        Header {
+            version,
+            binary_indicator,
+            offset: header_offset,
+        },
+    ))
 }
 
-#[tracable_parser]
-pub(crate) fn pdf_section(input: Span) -> CbParseResult<Vec<PdfSection>> {
+/// Parse the indirect object `number` at `byte_offset` of `input`. Offsets
+/// are counted from the start of the file, but some writers count from the
+/// header at `header_offset`. The offset from the header is tried if the
+/// object isn't found at the offset from the start.
+///
+/// Returns the input that starts with the object, the remainder and the
+/// object.
+pub(crate) fn indirect_object_at(
+    input: Span,
+    number: usize,
+    byte_offset: usize,
+    header_offset: usize,
+) -> CbParseResult<(Span, Object)> {
+    let parse = |byte_offset: usize| {
+        let (obj_bytes, _) = bytes::complete::take(byte_offset)(input)?;
+        let (remainder, obj) = indirect_object(obj_bytes)?;
+        Ok((remainder, (obj_bytes, obj)))
+    };
+    let found = |result: &CbParseResult<(Span, Object)>| matches!(result, Ok((_, (_, obj))) if obj.indirect().is_some_and(|i| i.index as usize == number));
+
+    let absolute = parse(byte_offset);
+    if header_offset == 0 || found(&absolute) {
+        return absolute;
+    }
+    let relative = parse(byte_offset.saturating_add(header_offset));
+    if found(&relative) {
+        logging::debug!("Offset {} of object {} is counted from the header", byte_offset, number);
+        return relative;
+    }
+    absolute
+}
+
+/// Find the offset of the newest xref section that follows `startxref` at the
+/// end of the document. Without a `%%EOF` marker the last `startxref` of the
+/// whole input is used, unless `options` are strict.
+///
+/// Returns the input before `startxref` and the offset.
+pub(crate) fn startxref<'a>(input: Span<'a>, options: &ParseOptions) -> CbParseResult<'a, usize> {
+    match xref::eof_marker_within(input, options.eof_search_window) {
+        Ok((remainder, _)) => xref::startxref_tail(remainder),
+        Err(err) if options.strict => Err(err),
+        Err(_) => {
+            input.extra.warn(ParseWarning::MissingEof);
+            xref::startxref_within(input, input.len())
+        }
+    }
+}
+
+/// Parse the xref sections and trailers, starting with the newest one. Like
+/// object offsets, the offsets of the sections are tried from the start of
+/// the file and from the header at `header_offset`.
+pub(crate) fn xref_sections<'a>(
+    input: Span<'a>,
+    header_offset: usize,
+    options: &ParseOptions,
+) -> CbParseResult<'a, Vec<(Xref, Trailer)>> {
     // find start of the xref section and trailer
-    let (remainder_xref, _) = xref::eof_marker_tail(input)?;
-    let (remainder_xref, startxref) = xref::startxref_tail(remainder_xref)?;
+    let (remainder_xref, startxref) = startxref(input, options)?;
 
-    let mut pdf_sections: Vec<PdfSection> = Vec::with_capacity(5);
+    let mut sections = Vec::with_capacity(5);
     let mut maybe_startxref: Option<usize> = Some(startxref);
 
     while let Some(startxref) = maybe_startxref.take() {
-        log::debug!("Parse section {}", startxref);
-
-        let trailer = trailer_tail(remainder_xref)
-            .map_err(|err| match err {
-                nom::Err::Error(CbParseError {
-                    kind: CbParseErrorKind::BackwardSearchNotFound,
-                    ..
-                }) => log::error!("No trailer in PDF section"),
-                _ => log::error!("Error in trailer {:?}", err),
-            })
-            .ok()
-            .map(|(_, trailer)| trailer)
-            .expect("FIXME: Trailer is required");
-        let (remainder_xref, _) = nom::bytes::complete::take(startxref)(input)?;
-        let (_, xref) = xref::xref(remainder_xref)?;
-
-        let object_count = xref.used_objects().count();
-        let mut objects = fnv::FnvHashMap::with_capacity_and_hasher(object_count, Default::default());
-
-        for obj_xref in xref.used_objects() {
-            // we always use input since the byte_offset is from the start of the file
-            log::debug!("Parse object {:?}", obj_xref);
-            let (obj_bytes, _) = bytes::complete::take(obj_xref.byte_offset)(input)?;
-            let (_, obj) = indirect_object(obj_bytes)?;
-
-            objects.insert(obj_xref.number, obj);
-        }
-
-        // TODO: read compressed objects
-        for obj_xref in xref.compressed_objects() {
-            let obj = objects.get(&obj_xref.number).expect("FIXME: missing stream object");
-            let stream = obj
-                .indirect()
-                .expect("FIXME: handle invalid object")
-                .object
-                .stream()
-                .expect("FIXME: handle invalid object");
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("xref", byte_offset = startxref).entered();
+        logging::debug!("Parse section {}", startxref);
 
-            for (number, obj) in object_stream(stream).expect("FIXME: handle error") {
-                objects.insert(number, obj);
+        let (section_input, _) = nom::bytes::complete::take(startxref)(input)?;
+        let mut parsed = xref_and_trailer(section_input);
+        if parsed.is_err() && header_offset > 0 {
+            let relative =
+                nom::bytes::complete::take::<_, _, CbParseError<Span>>(startxref.saturating_add(header_offset))(input);
+            if let Ok(found @ Ok(_)) = relative.map(|(relative_input, _)| xref_and_trailer(relative_input)) {
+                logging::debug!("Offset {} of the section is counted from the header", startxref);
+                parsed = found;
             }
         }
+        let (xref, trailer) = match parsed {
+            Ok((_, xref_and_trailer)) => xref_and_trailer,
+            Err(err) => {
+                logging::warn!(
+                    "No trailer found after xref section at {}, searching from the end: {:?}",
+                    startxref,
+                    err
+                );
+                let (_, xref) = xref::xref(section_input)?;
+                let (_, trailer) = trailer_tail(remainder_xref).map_err(|err| {
+                    match &err {
+                        nom::Err::Error(CbParseError {
+                            kind: CbParseErrorKind::BackwardSearchNotFound,
+                            ..
+                        }) => logging::error!("No trailer in PDF section"),
+                        _ => logging::error!("Error in trailer {:?}", err),
+                    }
+                    err
+                })?;
+                (xref, trailer)
+            }
+        };
 
         // The filter ensures that each new section is before the current one, thus
         // preventing a loop.
         maybe_startxref = trailer.previous.filter(|&new| new < startxref);
-        pdf_sections.push(PdfSection { objects, xref, trailer });
+        sections.push((xref, trailer));
+    }
+
+    Ok((remainder_xref, sections))
+}
+
+/// Parse the sections of the document, including the objects of their object
+/// streams. If the xref sections or the objects they point to are invalid, a
+/// single section is rebuilt from the objects found in `input`, unless
+/// `options` are strict.
+pub(crate) fn pdf_section<'a>(
+    input: Span<'a>,
+    header_offset: usize,
+    options: &ParseOptions,
+) -> CbParseResult<'a, Vec<PdfSection>> {
+    let (remainder, mut sections) = uncompressed_sections(input, header_offset, options)?;
+    // The `/Length` of object streams is often a reference, thus the lengths
+    // are resolved before and again after decoding the object streams.
+    resolve_stream_lengths(input, &mut sections);
+    let count = sections.len();
+    for (index, section) in sections.iter_mut().enumerate() {
+        let objects_total = section.xref.used_objects().count();
+        let progress = ParseProgress {
+            section: index,
+            sections: count,
+            objects_done: objects_total,
+            objects_total,
+        };
+        decompress_object_streams(input, section, cfg!(feature = "rayon"), options, progress)?;
+    }
+    resolve_stream_lengths(input, &mut sections);
+    Ok((remainder, sections))
+}
+
+/// The sections with their uncompressed objects, see [pdf_section].
+fn uncompressed_sections<'a>(
+    input: Span<'a>,
+    header_offset: usize,
+    options: &ParseOptions,
+) -> CbParseResult<'a, Vec<PdfSection>> {
+    let err = match sections_from_xref(input, header_offset, options) {
+        Ok(parsed) => return Ok(parsed),
+        Err(err) if options.strict || is_requested(&err) => return Err(err),
+        Err(err) => err,
+    };
+    let Some((xref, trailer)) = rebuild::rebuild_xref(input) else {
+        return Err(err);
+    };
+    logging::warn!("Invalid xref sections, rebuilt from the objects: {:?}", err);
+    input.extra.warn(ParseWarning::XrefRebuilt);
+    let progress = ParseProgress {
+        section: 0,
+        sections: 1,
+        objects_done: 0,
+        objects_total: xref.used_objects().count(),
+    };
+    let section = parse_section(
+        input,
+        header_offset,
+        xref,
+        trailer,
+        cfg!(feature = "rayon"),
+        options,
+        progress,
+    )?;
+    Ok((input, vec![section]))
+}
+
+/// Errors that a rebuilt xref section doesn't fix, since `options` asked for
+/// them.
+fn is_requested(err: &nom::Err<CbParseError<Span>>) -> bool {
+    let (nom::Err::Error(err) | nom::Err::Failure(err)) = err else {
+        return false;
+    };
+    err.chain().any(|err| {
+        matches!(
+            err.kind,
+            CbParseErrorKind::Cancelled | CbParseErrorKind::DuplicateKey(_)
+        )
+    })
+}
+
+/// Parse the sections that the xref sections point to.
+fn sections_from_xref<'a>(
+    input: Span<'a>,
+    header_offset: usize,
+    options: &ParseOptions,
+) -> CbParseResult<'a, Vec<PdfSection>> {
+    let (remainder_xref, sections) = xref_sections(input, header_offset, options)?;
+    options.check_cancelled(input)?;
+
+    let count = sections.len();
+    let mut pdf_sections: Vec<PdfSection> = Vec::with_capacity(count);
+    for (index, (xref, trailer)) in sections.into_iter().enumerate() {
+        let progress = ParseProgress {
+            section: index,
+            sections: count,
+            objects_done: 0,
+            objects_total: xref.used_objects().count(),
+        };
+        pdf_sections.push(parse_section(
+            input,
+            header_offset,
+            xref,
+            trailer,
+            cfg!(feature = "rayon"),
+            options,
+            progress,
+        )?);
     }
 
     Ok((remainder_xref, pdf_sections))
 }
 
-#[tracable_parser]
-pub(crate) fn parse_complete(input: Span) -> CbParseResult<RawPdf> {
-    let (_, (version, announced_binary)) = header(input)?;
+/// Parse the uncompressed objects of the `xref` section, the objects of the
+/// object streams are added by [decompress_object_streams]. If `parallel` is
+/// set and the `rayon` feature is enabled, objects are parsed on the rayon
+/// thread pool. The result is the same in both cases.
+///
+/// `progress` is reported with the number of parsed objects.
+fn parse_section<'a>(
+    input: Span<'a>,
+    header_offset: usize,
+    xref: Xref,
+    trailer: Trailer,
+    parallel: bool,
+    options: &ParseOptions,
+    progress: ParseProgress,
+) -> Result<PdfSection, nom::Err<CbParseError<Span<'a>>>> {
+    #[cfg(feature = "tracing")]
+    let section_span = tracing::debug_span!("section", section = progress.section, objects = progress.objects_total);
+    #[cfg(feature = "tracing")]
+    let _entered = section_span.enter();
+    options.report(progress);
+    let done = AtomicUsize::new(0);
+    let used_objects: Vec<_> = xref.used_objects().collect();
+    let parsed = map_in_order(&used_objects, parallel, |obj_xref| {
+        options.check_cancelled(input)?;
+        if options.progress.is_some() {
+            let objects_done = done.fetch_add(1, Ordering::Relaxed) + 1;
+            if objects_done.is_multiple_of(PROGRESS_INTERVAL) {
+                options.report(ParseProgress {
+                    objects_done,
+                    ..progress
+                });
+            }
+        }
+        // Worker threads don't know the current span, thus the parent is explicit.
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            parent: &section_span,
+            "object",
+            object_number = obj_xref.number,
+            generation = obj_xref.generation,
+            byte_offset = obj_xref.byte_offset
+        )
+        .entered();
+        // we always use input since the byte_offset is from the start of the file
+        logging::debug!("Parse object {:?}", obj_xref);
+        let (remainder, (obj_bytes, obj)) =
+            indirect_object_at(input, obj_xref.number, obj_xref.byte_offset, header_offset)?;
 
-    let (_, sections) = pdf_section(input)?;
+        // The object parser also consumes the whitespace after `endobj`.
+        let start = obj_bytes.location_offset();
+        let end = input.fragment()[..remainder.location_offset()]
+            .iter()
+            .rposition(|c| !c.is_ascii_whitespace())
+            .map_or(start, |last| last + 1);
+        let stream_span = match obj.indirect().map(|indirect| &*indirect.object) {
+            Some(Object::Stream(stream)) => {
+                let (_, start) = object::stream_data_offset(obj_bytes)?;
+                Some(start..start + stream.data.len())
+            }
+            _ => None,
+        };
+        Ok((obj_xref.number, obj, start..end, stream_span))
+    });
 
-    Ok((
-        input,
-        RawPdf {
+    let mut objects = FnvHashMap::with_capacity_and_hasher(used_objects.len(), Default::default());
+    let mut object_spans = FnvHashMap::with_capacity_and_hasher(used_objects.len(), Default::default());
+    let mut stream_spans = FnvHashMap::default();
+    // The first error in xref order wins, like without rayon.
+    for result in parsed {
+        let (number, obj, span, stream_span) = result?;
+        objects.insert(number, obj);
+        object_spans.insert(number, span);
+        if let Some(stream_span) = stream_span {
+            stream_spans.insert(number, stream_span);
+        }
+    }
+
+    Ok(PdfSection {
+        objects,
+        trailer,
+        xref,
+        object_spans,
+        stream_spans,
+    })
+}
+
+/// Add the objects of the object streams of `section`. If `parallel` is set
+/// and the `rayon` feature is enabled, the streams are decoded on the rayon
+/// thread pool.
+///
+/// `progress` is reported once the streams are decoded.
+fn decompress_object_streams<'a>(
+    input: Span<'a>,
+    section: &mut PdfSection,
+    parallel: bool,
+    options: &ParseOptions,
+    progress: ParseProgress,
+) -> Result<(), nom::Err<CbParseError<Span<'a>>>> {
+    #[cfg(feature = "tracing")]
+    let section_span = tracing::debug_span!("section", section = progress.section, objects = progress.objects_total);
+    #[cfg(feature = "tracing")]
+    let _entered = section_span.enter();
+    let mut seen = FnvHashSet::default();
+    let streams: Vec<usize> = section
+        .xref
+        .compressed_objects()
+        .map(|obj_xref| obj_xref.containing_object)
+        .filter(|&number| seen.insert(number))
+        .collect();
+    // Invalid object streams are skipped like in `LazyPdf`, the objects they
+    // contain are missing.
+    let objects = &section.objects;
+    let decompressed = map_in_order(&streams, parallel, |number| {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(parent: &section_span, "object_stream", object_number = number).entered();
+        let stream = objects
+            .get(number)
+            .and_then(Object::indirect)
+            .and_then(|indirect| indirect.object.stream());
+        let Some(stream) = stream else {
+            logging::error!("Object stream {} is missing", number);
+            return Vec::new();
+        };
+        object_stream(stream, input.extra.in_object_stream(*number)).unwrap_or_else(|err| {
+            logging::error!("Invalid object stream {}: {:?}", number, err);
+            Vec::new()
+        })
+    });
+    // Object streams that were cancelled while decoding are skipped as
+    // invalid.
+    options.check_cancelled(input)?;
+    for (number, obj) in decompressed.into_iter().flatten() {
+        section.objects.insert(number, obj);
+    }
+    options.report(progress);
+    Ok(())
+}
+
+/// Apply `f` to all `items` and return the results in the order of the
+/// items. Runs on the rayon thread pool if `parallel` is set and the `rayon`
+/// feature is enabled.
+fn map_in_order<I: Sync, T: Send>(items: &[I], parallel: bool, f: impl Fn(&I) -> T + Sync) -> Vec<T> {
+    #[cfg(feature = "rayon")]
+    if parallel {
+        use rayon::prelude::*;
+
+        return items.par_iter().map(&f).collect();
+    }
+    #[cfg(not(feature = "rayon"))]
+    let _ = parallel;
+
+    items.iter().map(f).collect()
+}
+
+#[cfg_attr(feature = "trace", tracable_parser)]
+pub(crate) fn parse_complete(input: Span) -> CbParseResult<RawPdf> {
+    parse_complete_with(input, &ParseOptions::default())
+}
+
+/// Parse a complete document. The object parsers and the filters use the
+/// options of the context of `input`, which are set by [with_options].
+pub(crate) fn parse_complete_with<'a>(input: Span<'a>, options: &ParseOptions) -> CbParseResult<'a, RawPdf> {
+    let (
+        _,
+        Header {
             version,
-            announced_binary,
-            sections,
+            binary_indicator,
+            offset: header_offset,
         },
-    ))
+    ) = header(input)?;
+
+    check_version(input, version, options)?;
+    let (_, sections) = pdf_section(input, header_offset, options)?;
+
+    let pdf = RawPdf {
+        version,
+        binary_indicator,
+        header_offset,
+        sections,
+        allocated: Default::default(),
+    };
+
+    Ok((input, pdf))
+}
+
+/// The object parser can't resolve a `/Length` that is a reference and
+/// searches for `endstream` instead. Use the resolved length if the data
+/// with that length is followed by `endstream`. Lengths stored in object
+/// streams are only found once the streams are decoded.
+fn resolve_stream_lengths(input: Span, sections: &mut [PdfSection]) {
+    let dereference = |reference: &Reference| {
+        sections
+            .iter()
+            .find_map(|section| section.objects.get(&usize::try_from(reference.index).ok()?))
+            .and_then(|obj| match obj {
+                Object::Indirect(indirect) => {
+                    (indirect.generation == reference.generation).then_some(&*indirect.object)
+                }
+                // objects from object streams
+                direct => (reference.generation == 0).then_some(direct),
+            })
+    };
+    let mut resolved = Vec::new();
+    for (index, section) in sections.iter().enumerate() {
+        for (&number, obj) in &section.objects {
+            let Some(stream) = obj.indirect().and_then(|i| i.object().stream()) else {
+                continue;
+            };
+            if stream.provenance != StreamDataProvenance::KeywordScan {
+                continue;
+            }
+            let Some(Object::Reference(length)) = stream.dictionary.get(&b"Length"[..]) else {
+                continue;
+            };
+            let (Some(length), Some(data)) = (dereference(length), section.stream_spans.get(&number)) else {
+                continue;
+            };
+            if let Some(range) = resolved_stream_data(input.fragment(), data.start, length) {
+                resolved.push((index, number, range));
+            }
+        }
+    }
+
+    for (index, number, range) in resolved {
+        let section = &mut sections[index];
+        if let Some(Object::Stream(stream)) = section.objects.get_mut(&number).and_then(|obj| match obj {
+            Object::Indirect(indirect) => Some(&mut *indirect.object),
+            _ => None,
+        }) {
+            stream.data = shared_bytes(&nom::Slice::slice(&input, range.clone()));
+            stream.provenance = StreamDataProvenance::LengthResolvedFromReference;
+            section.stream_spans.insert(number, range);
+        }
+    }
+}
+
+/// The range of the data of a stream that starts at `start` in `input` and
+/// has the resolved `length`, if the data with that length is followed by
+/// `endstream`.
+pub(crate) fn resolved_stream_data(input: &[u8], start: usize, length: &Object) -> Option<Range<usize>> {
+    let &Object::Integer(length) = length else {
+        return None;
+    };
+    let end = start.checked_add(usize::try_from(length).ok()?)?;
+    let tail = input.get(end..)?;
+    let tail = tail
+        .strip_prefix(b"\r\n")
+        .or_else(|| tail.strip_prefix(b"\n"))
+        .or_else(|| tail.strip_prefix(b"\r"))
+        .unwrap_or(tail);
+    tail.starts_with(b"endstream").then_some(start..end)
+}
+
+/// Parse a single object from the beginning of `input`, e.g. a snippet of a
+/// document or a content stream. Leading whitespace is skipped.
+///
+/// Returns the object and the number of bytes that were consumed, which
+/// allows parsing a sequence of objects. References inside the snippet
+/// remain unresolved.
+///
+/// ```
+/// use claybrick::{parse::parse_object, pdf::Object};
+///
+/// let input = b"<< /Type /Page /Parent 2 0 R >> [1 2]";
+/// let (page, consumed) = parse_object(input).unwrap();
+/// let page = page.dictionary().unwrap();
+/// assert_eq!(page.get(&b"Type"[..]).and_then(Object::name).map(|n| &n[..]), Some(&b"Page"[..]));
+/// assert!(page.get(&b"Parent"[..]).unwrap().is_reference());
+///
+/// let (array, _) = parse_object(&input[consumed..]).unwrap();
+/// assert_eq!(array.array().unwrap().len(), 2);
+/// ```
+pub fn parse_object(input: &[u8]) -> Result<(Object, usize), CbError> {
+    parse_snippet(input, object)
+}
+
+/// Parse an indirect object like `1 0 obj ... endobj` from the beginning of
+/// `input`. Leading whitespace is skipped.
+///
+/// Returns the object and the number of bytes that were consumed. References
+/// inside the object remain unresolved.
+///
+/// ```
+/// use claybrick::parse::parse_indirect_object;
+///
+/// let input = b"12 0 obj\n(Hello) \nendobj\n";
+/// let (obj, consumed) = parse_indirect_object(input).unwrap();
+/// let indirect = obj.indirect().unwrap();
+/// assert_eq!((indirect.index(), indirect.generation()), (12, 0));
+/// assert_eq!(indirect.object().string().map(|s| &s[..]), Some(&b"Hello"[..]));
+/// assert_eq!(consumed, input.len());
+///
+/// // A reference is not an indirect object.
+/// assert!(parse_indirect_object(b"12 0 R").is_err());
+/// ```
+pub fn parse_indirect_object(input: &[u8]) -> Result<(Object, usize), CbError> {
+    parse_snippet(
+        input,
+        nom::combinator::verify(indirect_object, |obj: &Object| obj.indirect().is_some()),
+    )
+}
+
+fn parse_snippet<'a>(
+    input: &'a [u8],
+    mut parser: impl FnMut(Span<'a>) -> CbParseResult<'a, Object>,
+) -> Result<(Object, usize), CbError> {
+    let span = span(input);
+    let (remainder, _) = character::complete::multispace0::<_, CbParseError<Span>>(span)?;
+    let (remainder, obj) = parser(remainder)?;
+    Ok((obj, remainder.location_offset()))
 }
 
 /// Applies the supplied parser to the end of the input. Returns the beginning
@@ -171,12 +1024,701 @@ where
 }
 
 #[cfg(test)]
-mod tests {
-    use nom::AsBytes;
-    use nom_tracable::TracableInfo;
+pub(crate) mod tests {
+    use std::sync::Mutex;
 
     use super::*;
 
+    /// A callback for [ParseOptions::warnings] and the warnings it collects.
+    pub(crate) fn collect_warnings() -> (WarningCallback, Arc<Mutex<Vec<ParseWarning>>>) {
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let callback: WarningCallback = Arc::new({
+            let warnings = warnings.clone();
+            move |warning| warnings.lock().unwrap().push(warning)
+        });
+        (callback, warnings)
+    }
+
+    /// A `%PDF-1.7` header followed by the `objects` and the offsets of the
+    /// objects.
+    pub(crate) fn with_objects<'a>(objects: impl IntoIterator<Item = &'a [u8]>) -> (Vec<u8>, Vec<usize>) {
+        let mut input = b"%PDF-1.7\n".to_vec();
+        let mut offsets = Vec::new();
+        for obj in objects {
+            offsets.push(input.len());
+            input.extend_from_slice(obj);
+        }
+        (input, offsets)
+    }
+
+    /// The offset or the next free object, the generation and whether the
+    /// object is in use.
+    pub(crate) type TableEntry = (usize, u16, bool);
+
+    /// Append a xref table, the `trailer` dictionary and the `startxref` of
+    /// the table to `input`. `subsections` are the first object number and
+    /// the entries of each subsection.
+    pub(crate) fn push_xref_table(input: &mut Vec<u8>, subsections: &[(usize, &[TableEntry])], trailer: &str) {
+        let startxref = input.len();
+        input.extend_from_slice(b"xref\n");
+        for (first, entries) in subsections {
+            input.extend_from_slice(format!("{} {}\n", first, entries.len()).as_bytes());
+            for (offset, generation, used) in *entries {
+                let kind = if *used { 'n' } else { 'f' };
+                input.extend_from_slice(format!("{:010} {:05} {}\r\n", offset, generation, kind).as_bytes());
+            }
+        }
+        input.extend_from_slice(format!("trailer\n{}\nstartxref\n{}\n%%EOF\n", trailer, startxref).as_bytes());
+    }
+
+    /// The `objects` with a xref table for the objects 1 to n, object 1 is
+    /// the catalog.
+    pub(crate) fn table_document(objects: &[&[u8]]) -> (Vec<u8>, Vec<usize>) {
+        let (mut input, offsets) = with_objects(objects.iter().copied());
+        let entries: Vec<_> = std::iter::once((0, 65535, false))
+            .chain(offsets.iter().map(|&offset| (offset, 0, true)))
+            .collect();
+        let trailer = format!("<</Size {} /Root 1 0 R>>", entries.len());
+        push_xref_table(&mut input, &[(0, &entries)], &trailer);
+        (input, offsets)
+    }
+
+    #[test]
+    fn stream_data_is_shared() {
+        const SIZE: usize = 1024;
+        let mut builder = crate::build::PdfBuilder::new();
+        let page = builder.add_page(100.0, 100.0);
+        builder.set_page_content(page, vec![b' '; SIZE]);
+        let mut out = Vec::new();
+        crate::simple_encode::SimpleEncoder::write_with(&builder.finish().unwrap(), &mut out, Default::default())
+            .unwrap();
+
+        let buffer = Arc::new(out);
+        let (_, pdf) = parse_complete(shared_span(&buffer)).unwrap();
+        let data = &pdf.page(0).unwrap().contents()[0].data;
+        assert_eq!(data.len(), SIZE);
+        assert!(buffer.as_ptr_range().contains(&data.as_ptr()));
+
+        // Without a shared input, the data is copied.
+        let (_, pdf) = parse_complete(span(&buffer[..])).unwrap();
+        let data = &pdf.page(0).unwrap().contents()[0].data;
+        assert!(!buffer.as_ptr_range().contains(&data.as_ptr()));
+    }
+
+    #[test]
+    fn object_spans() {
+        use crate::pdf::xref::UsedObject;
+
+        let mut builder = crate::build::PdfBuilder::new();
+        let page = builder.add_page(100.0, 100.0);
+        builder.set_page_content(page, &b"0 0 m 100 100 l S"[..]);
+        let mut input = Vec::new();
+        crate::simple_encode::SimpleEncoder::write_with(&builder.finish().unwrap(), &mut input, Default::default())
+            .unwrap();
+        let (_, mut sections) = xref_sections(span(&input[..]), 0, &ParseOptions::default()).unwrap();
+        let (xref, trailer) = sections.remove(0);
+
+        // Append a stream with an invalid length that is parsed by searching
+        // for `endstream`.
+        let mut entries = xref.entries;
+        entries.push(
+            UsedObject {
+                number: 99,
+                byte_offset: input.len(),
+                generation: 0,
+            }
+            .into(),
+        );
+        input.extend_from_slice(b"99 0 obj <</Length 1>> stream\nfallback data\nendstream\nendobj\n");
+
+        let section = parse_section(
+            span(&input[..]),
+            0,
+            Xref::new(entries),
+            trailer,
+            false,
+            &ParseOptions::default(),
+            ParseProgress::default(),
+        )
+        .unwrap();
+        let pdf = RawPdf {
+            version: (1, 7),
+            binary_indicator: None,
+            header_offset: 0,
+            sections: vec![section],
+            allocated: Default::default(),
+        };
+
+        let mut streams = 0;
+        for (&number, range) in pdf.sections[0].object_spans() {
+            assert_eq!(pdf.object_span(number).as_ref(), Some(range));
+            let slice = &input[range.clone()];
+            assert!(slice.ends_with(b"endobj"));
+            let (remainder, obj) = indirect_object(span(slice)).unwrap();
+            assert!(remainder.is_empty());
+            assert_eq!(Some(&obj), pdf.object(number));
+
+            if let Some(stream) = obj.indirect().and_then(|obj| obj.object.stream()) {
+                let data = pdf.stream_span(number).unwrap();
+                assert_eq!(&input[data], &stream.data[..]);
+                streams += 1;
+            }
+        }
+        assert_eq!(streams, pdf.sections[0].stream_spans().len());
+        assert_eq!(&input[pdf.stream_span(99).unwrap()], b"fallback data");
+        assert_eq!(pdf.object_span(1000), None);
+    }
+
+    fn document(pages: usize) -> Vec<u8> {
+        let mut builder = crate::build::PdfBuilder::new();
+        for _ in 0..pages {
+            let page = builder.add_page(100.0, 100.0);
+            builder.set_page_content(page, &b"0 0 m 100 100 l S"[..]);
+        }
+        let mut input = Vec::new();
+        crate::simple_encode::SimpleEncoder::write_with(&builder.finish().unwrap(), &mut input, Default::default())
+            .unwrap();
+        input
+    }
+
+    #[test]
+    fn header_version() {
+        let with_version = |version: &[u8]| {
+            let mut input = document(1);
+            assert!(input.starts_with(b"%PDF-1.7"));
+            input[5..8].copy_from_slice(version);
+            input
+        };
+        let (callback, warnings) = collect_warnings();
+        let options = |strict| ParseOptions {
+            warnings: Some(callback.clone()),
+            strict,
+            ..Default::default()
+        };
+
+        for (version, expected) in [(b"1.4", (1, 4)), (b"2.0", (2, 0))] {
+            let pdf = crate::read_bytes_with(with_version(version), &options(true)).unwrap();
+            assert_eq!(pdf.version(), expected);
+            assert_eq!(pdf.effective_version(), expected);
+        }
+        assert!(warnings.lock().unwrap().is_empty());
+
+        let pdf = crate::read_bytes_with(with_version(b"3.1"), &options(false)).unwrap();
+        assert_eq!(pdf.version(), (3, 1));
+        assert_eq!(
+            *warnings.lock().unwrap(),
+            vec![ParseWarning::UnknownVersion { version: (3, 1) }]
+        );
+        let Err(crate::error::CbError::Parse(err)) = crate::read_bytes_with(with_version(b"3.1"), &options(true))
+        else {
+            panic!("Expected a parse error");
+        };
+        assert_eq!(err.kind, CbParseErrorKind::UnknownVersion((3, 1)));
+    }
+
+    #[test]
+    fn junk_before_header() {
+        let junk = b"HTTP/1.1 200 OK\r\nServer: printers\r\n\r\n";
+        assert_eq!(junk.len(), 37);
+        // The offsets of the document are counted from the header.
+        let input = [&junk[..], &document(2)].concat();
+
+        let pdf = crate::read_bytes(input.clone()).unwrap();
+        assert_eq!(pdf.header_offset(), 37);
+        assert_eq!(pdf.version, (1, 7));
+        assert_eq!(pdf.page_count(), Ok(2));
+        let span = pdf.object_span(1).unwrap();
+        assert!(input[span].starts_with(b"1 0 obj"));
+
+        let lazy = crate::pdf::LazyPdf::parse(input.clone()).unwrap();
+        assert_eq!(lazy.page_count(), Ok(2));
+
+        // Offsets of updates are counted from the start of the file.
+        let mut updated = Vec::new();
+        crate::write_incremental(&input, &[(100, Object::Integer(7))], &mut updated, Default::default()).unwrap();
+        let pdf = crate::read_bytes(updated).unwrap();
+        assert_eq!(pdf.sections.len(), 2);
+        assert_eq!(
+            pdf.object(100).and_then(Object::indirect).map(|i| &*i.object),
+            Some(&Object::Integer(7))
+        );
+        assert_eq!(pdf.page_count(), Ok(2));
+    }
+
+    #[test]
+    fn header_search_limit() {
+        let mut input = vec![b'x'; MAX_HEADER_OFFSET];
+        input.extend_from_slice(b"%PDF-1.4\n");
+        assert_eq!(
+            header(span(&input)).unwrap().1,
+            Header {
+                version: (1, 4),
+                binary_indicator: None,
+                offset: MAX_HEADER_OFFSET,
+            }
+        );
+        input.insert(0, b'x');
+        assert!(header(span(&input)).is_err());
+    }
+
+    #[test]
+    fn text_after_eof_marker() {
+        let mut input = document(2);
+        input.extend_from_slice(&[b'x'; 200]);
+
+        let pdf = crate::read_bytes(input.clone()).unwrap();
+        assert_eq!(pdf.page_count().unwrap(), 2);
+        assert_eq!(crate::pdf::LazyPdf::parse(input).unwrap().page_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn invalid_previous() {
+        let mut builder = crate::build::PdfBuilder::new();
+        builder.add_page(100.0, 100.0);
+        let mut input = Vec::new();
+        let options = crate::simple_encode::EncodeOptions {
+            xref: crate::simple_encode::XrefStyle::Table,
+            ..Default::default()
+        };
+        crate::simple_encode::SimpleEncoder::write_with(&builder.finish().unwrap(), &mut input, options).unwrap();
+        let trailer = input.windows(10).rposition(|w| w == b"trailer\n<<").unwrap() + 10;
+        let input = [&input[..trailer], b"/Prev (x) ", &input[trailer..]].concat();
+
+        let (callback, warnings) = collect_warnings();
+        let options = ParseOptions {
+            warnings: Some(callback),
+            ..Default::default()
+        };
+        let pdf = crate::read_bytes_with(input.clone(), &options).unwrap();
+        assert_eq!(pdf.page_count().unwrap(), 1);
+        assert!(pdf.previous_offsets().is_empty());
+        assert_eq!(*warnings.lock().unwrap(), vec![ParseWarning::InvalidPrevious]);
+
+        let strict = ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+        assert!(crate::read_bytes_with(input, &strict).is_err());
+    }
+
+    #[test]
+    fn missing_eof_marker() {
+        let mut input = document(2);
+        let marker = input.windows(5).rposition(|w| w == b"%%EOF").unwrap();
+        input.truncate(marker);
+
+        let (callback, warnings) = collect_warnings();
+        let options = ParseOptions {
+            warnings: Some(callback),
+            ..Default::default()
+        };
+        let pdf = crate::read_bytes_with(input.clone(), &options).unwrap();
+        assert_eq!(pdf.page_count().unwrap(), 2);
+        assert_eq!(*warnings.lock().unwrap(), vec![ParseWarning::MissingEof]);
+
+        let strict = ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+        assert!(crate::read_bytes_with(input.clone(), &strict).is_err());
+
+        // a too small window misses the marker
+        let mut input = document(2);
+        input.extend_from_slice(&[b'x'; 200]);
+        let narrow = ParseOptions {
+            eof_search_window: 100,
+            strict: true,
+            ..Default::default()
+        };
+        assert!(crate::read_bytes_with(input, &narrow).is_err());
+    }
+
+    #[test]
+    fn progress() {
+        let input = document(600);
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let options = ParseOptions {
+            progress: Some(Box::new({
+                let reports = reports.clone();
+                move |progress| reports.lock().unwrap().push(progress)
+            })),
+            ..Default::default()
+        };
+        let pdf = crate::read_bytes_with(input, &options).unwrap();
+        assert_eq!(pdf.page_count(), Ok(600));
+
+        // Start, 1000 objects and end of the single section.
+        let reports = reports.lock().unwrap();
+        let total = pdf.sections[0].xref.used_objects().count();
+        assert!(total > PROGRESS_INTERVAL);
+        let done: Vec<_> = reports
+            .iter()
+            .map(|p| (p.section, p.sections, p.objects_done))
+            .collect();
+        assert_eq!(done, [(0, 1, 0), (0, 1, PROGRESS_INTERVAL), (0, 1, total)]);
+        assert!(reports.iter().all(|p| p.objects_total == total));
+    }
+
+    #[test]
+    fn cancel() {
+        let input = document(600);
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (started, wait_for_start) = std::sync::mpsc::channel();
+        let (cancelled, wait_for_cancel) = std::sync::mpsc::channel();
+
+        // Cancel from another thread once parsing started.
+        let canceller = std::thread::spawn({
+            let cancel = cancel.clone();
+            move || {
+                wait_for_start.recv().unwrap();
+                cancel.store(true, Ordering::Relaxed);
+                cancelled.send(()).unwrap();
+            }
+        });
+        let reports = Arc::new(AtomicUsize::new(0));
+        let options = ParseOptions {
+            progress: Some(Box::new({
+                let reports = reports.clone();
+                let started = std::sync::Mutex::new(started);
+                let wait_for_cancel = std::sync::Mutex::new(wait_for_cancel);
+                move |progress| {
+                    reports.fetch_add(1, Ordering::Relaxed);
+                    if progress.objects_done == 0 {
+                        started.lock().unwrap().send(()).unwrap();
+                        wait_for_cancel.lock().unwrap().recv().unwrap();
+                    }
+                }
+            })),
+            cancel: Some(cancel),
+            ..Default::default()
+        };
+        assert!(matches!(
+            crate::read_bytes_with(input, &options),
+            Err(crate::error::CbError::Cancelled)
+        ));
+        canceller.join().unwrap();
+        // Stopped before the first 1000 objects.
+        assert_eq!(reports.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn stream_provenance() {
+        let objects: [&[u8]; 5] = [
+            b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj\n",
+            b"2 0 obj <</Type /Pages /Kids [] /Count 0>> endobj\n",
+            b"3 0 obj <</Length 5>> stream\nabcde\nendstream endobj\n",
+            b"4 0 obj <</Length 5 0 R>> stream\nab\ncd\nendstream endobj\n",
+            b"5 0 obj 5 endobj\n",
+        ];
+        let (input, _) = table_document(&objects);
+
+        let pdf = crate::read_bytes(input).unwrap();
+        let stream = |number| {
+            pdf.object(number)
+                .and_then(Object::indirect)
+                .unwrap()
+                .object
+                .stream()
+                .unwrap()
+        };
+        assert_eq!(stream(3).provenance, StreamDataProvenance::Declared);
+        assert!(stream(3).is_exact());
+        // without the EOL before `endstream`
+        assert_eq!(&stream(4).data[..], b"ab\ncd");
+        assert_eq!(stream(4).provenance, StreamDataProvenance::LengthResolvedFromReference);
+        assert!(stream(4).is_exact());
+        let range = pdf.sections[0].stream_spans()[&4].clone();
+        assert_eq!(range.len(), 5);
+    }
+
+    #[test]
+    fn referenced_object_stream_length() {
+        // Object stream 3 contains `endstream` and its `/Length` is object 4.
+        // Without the length, the stream ends before object 6.
+        let data = b"5 0 6 21 (a)\nendstream endobj\n(b)";
+        let objects = [
+            b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj\n".to_vec(),
+            b"2 0 obj <</Type /Pages /Kids [] /Count 0>> endobj\n".to_vec(),
+            [
+                &b"3 0 obj <</Type /ObjStm /N 2 /First 9 /Length 4 0 R>> stream\n"[..],
+                data,
+                b"\nendstream endobj\n",
+            ]
+            .concat(),
+            format!("4 0 obj {} endobj\n", data.len()).into_bytes(),
+        ];
+        let (mut input, offsets) = with_objects(objects.iter().map(Vec::as_slice));
+        let offset = |offset: usize| -> [u8; 4] { u32::try_from(offset).unwrap().to_be_bytes() };
+        let mut entries = vec![[0, 0, 0, 0, 0, 0xff, 0xff]];
+        for &obj_offset in &offsets {
+            let [a, b, c, d] = offset(obj_offset);
+            entries.push([1, a, b, c, d, 0, 0]);
+        }
+        entries.push([2, 0, 0, 0, 3, 0, 0]);
+        entries.push([2, 0, 0, 0, 3, 0, 1]);
+        let startxref = input.len();
+        let [a, b, c, d] = offset(startxref);
+        entries.push([1, a, b, c, d, 0, 0]);
+        let entries = entries.concat();
+        input.extend_from_slice(
+            format!(
+                "7 0 obj <</Type /XRef /Size 8 /W [1 4 2] /Root 1 0 R /Length {}>> stream\n",
+                entries.len()
+            )
+            .as_bytes(),
+        );
+        input.extend_from_slice(&entries);
+        input.extend_from_slice(format!("\nendstream endobj\nstartxref\n{}\n%%EOF\n", startxref).as_bytes());
+
+        let string = |s: &[u8]| Object::String(s.to_vec().into());
+        let pdf = crate::read_bytes(input.clone()).unwrap();
+        assert_eq!(pdf.object(6), Some(&string(b"b")));
+        let lazy = crate::pdf::LazyPdf::parse(input).unwrap();
+        assert_eq!(lazy.object(6), Some(&string(b"b")));
+    }
+
+    #[test]
+    fn duplicate_key_warnings() {
+        let objects: [&[u8]; 3] = [
+            b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj\n",
+            b"2 0 obj <</Type /Pages /Kids [] /Count 0 /Count 1>> endobj\n",
+            b"3 0 obj <</Length 5 /Length 3>> stream\nabcde\nendstream endobj\n",
+        ];
+        let (input, offsets) = table_document(&objects);
+
+        let (callback, warnings) = collect_warnings();
+        let options = ParseOptions {
+            duplicate_keys: DuplicateKeys::FirstWins,
+            warnings: Some(callback),
+            ..Default::default()
+        };
+        let pdf = crate::read_bytes_with(input.clone(), &options).unwrap();
+        let stream = pdf
+            .object(3)
+            .and_then(Object::indirect)
+            .unwrap()
+            .object
+            .stream()
+            .unwrap();
+        assert_eq!(stream.dictionary.get(&b"Length"[..]), Some(&Object::Integer(5)));
+        assert_eq!(&stream.data[..], b"abcde");
+        let mut warnings = warnings.lock().unwrap().clone();
+        warnings.sort_by_key(|warning| match warning {
+            ParseWarning::DuplicateKey { dictionary_offset, .. } => *dictionary_offset,
+            _ => 0,
+        });
+        assert_eq!(
+            warnings,
+            vec![
+                ParseWarning::DuplicateKey {
+                    key: b"Count".to_vec().into(),
+                    dictionary_offset: offsets[1] + 8,
+                    object_stream: None,
+                },
+                ParseWarning::DuplicateKey {
+                    key: b"Length".to_vec().into(),
+                    dictionary_offset: offsets[2] + 8,
+                    object_stream: None,
+                },
+            ]
+        );
+
+        let options = ParseOptions {
+            duplicate_keys: DuplicateKeys::Reject,
+            ..Default::default()
+        };
+        assert!(matches!(
+            crate::read_bytes_with(input, &options),
+            Err(crate::error::CbError::Parse(_))
+        ));
+    }
+
+    /// Records the `object_number` of all `object` spans.
+    #[cfg(feature = "tracing")]
+    #[derive(Default)]
+    struct ObjectSpanRecorder {
+        numbers: std::sync::Mutex<Vec<u64>>,
+        next_id: AtomicUsize,
+    }
+
+    #[cfg(feature = "tracing")]
+    impl tracing::Subscriber for ObjectSpanRecorder {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            struct Visitor<'a>(&'a std::sync::Mutex<Vec<u64>>);
+            impl tracing::field::Visit for Visitor<'_> {
+                fn record_u64(&mut self, field: &tracing::field::Field, value: u64) {
+                    if field.name() == "object_number" {
+                        self.0.lock().unwrap().push(value);
+                    }
+                }
+
+                fn record_debug(&mut self, _: &tracing::field::Field, _: &dyn std::fmt::Debug) {}
+            }
+
+            if span.metadata().name() == "object" {
+                span.record(&mut Visitor(&self.numbers));
+            }
+            tracing::span::Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) as u64 + 1)
+        }
+
+        fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, _: &tracing::Event<'_>) {}
+
+        fn enter(&self, _: &tracing::span::Id) {}
+
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn tracing_object_spans() {
+        let input = document(3);
+        let (_, mut sections) = xref_sections(span(&input[..]), 0, &ParseOptions::default()).unwrap();
+        let (xref, trailer) = sections.remove(0);
+        let mut expected: Vec<u64> = xref.used_objects().map(|obj| obj.number as u64).collect();
+
+        let spans = Arc::new(ObjectSpanRecorder::default());
+        tracing::subscriber::with_default(spans.clone(), || {
+            parse_section(
+                span(&input[..]),
+                0,
+                xref,
+                trailer,
+                false,
+                &ParseOptions::default(),
+                ParseProgress::default(),
+            )
+            .unwrap()
+        });
+
+        let mut numbers = spans.numbers.lock().unwrap().clone();
+        numbers.sort_unstable();
+        expected.sort_unstable();
+        assert_eq!(numbers, expected);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_section_objects() {
+        use crate::pdf::xref::{UsedCompressedObject, UsedObject, XrefEntry};
+
+        let mut builder = crate::build::PdfBuilder::new();
+        for _ in 0..100 {
+            let page = builder.add_page(100.0, 100.0);
+            builder.set_page_content(page, &b"0 0 m 100 100 l S"[..]);
+        }
+        let mut input = Vec::new();
+        crate::simple_encode::SimpleEncoder::write_with(&builder.finish().unwrap(), &mut input, Default::default())
+            .unwrap();
+        let (_, sections) = xref_sections(span(&input[..]), 0, &ParseOptions::default()).unwrap();
+        let mut entries = sections[0].0.entries.clone();
+
+        // Append two object streams with three compressed objects.
+        let streams = [
+            (1000, 2, 14, &b"1002 0 1003 3 42 7"[..]),
+            (1001, 1, 7, &b"1004 0 (text)"[..]),
+        ];
+        for (stream, n, first, data) in streams {
+            entries.push(
+                UsedObject {
+                    number: stream,
+                    byte_offset: input.len(),
+                    generation: 0,
+                }
+                .into(),
+            );
+            input.extend_from_slice(
+                format!(
+                    "{} 0 obj <</Type /ObjStm /N {} /First {} /Length {}>> stream\n",
+                    stream,
+                    n,
+                    first,
+                    data.len()
+                )
+                .as_bytes(),
+            );
+            input.extend_from_slice(data);
+            input.extend_from_slice(b"\nendstream endobj\n");
+        }
+        for (number, containing_object, index) in [(1002, 1000, 0), (1003, 1000, 1), (1004, 1001, 0)] {
+            entries.push(XrefEntry::UsedCompressed(UsedCompressedObject {
+                number,
+                containing_object,
+                index,
+            }));
+        }
+        let xref = Xref::new(entries.clone());
+
+        let span = span(&input[..]);
+        let trailer = &sections[0].1;
+        let section = |parallel| {
+            let mut section = parse_section(
+                span,
+                0,
+                xref.clone(),
+                trailer.clone(),
+                parallel,
+                &ParseOptions::default(),
+                ParseProgress::default(),
+            )
+            .unwrap();
+            decompress_object_streams(
+                span,
+                &mut section,
+                parallel,
+                &ParseOptions::default(),
+                ParseProgress::default(),
+            )
+            .unwrap();
+            section
+        };
+        let sequential = section(false);
+        let parallel = section(true);
+        assert_eq!(sequential.objects.len(), sections[0].0.used_objects().count() + 5);
+        assert_eq!(sequential.objects.get(&1003), Some(&Object::Integer(7)));
+        assert_eq!(parallel, sequential);
+
+        // Both report the first invalid object.
+        for number in [5, 9] {
+            let entry = entries.iter_mut().find(|e| e.number() == number).unwrap();
+            *entry = UsedObject {
+                number,
+                byte_offset: 1,
+                generation: 0,
+            }
+            .into();
+        }
+        let xref = Xref::new(entries);
+        let sequential = parse_section(
+            span,
+            0,
+            xref.clone(),
+            trailer.clone(),
+            false,
+            &ParseOptions::default(),
+            ParseProgress::default(),
+        )
+        .unwrap_err();
+        let parallel = parse_section(
+            span,
+            0,
+            xref,
+            trailer.clone(),
+            true,
+            &ParseOptions::default(),
+            ParseProgress::default(),
+        )
+        .unwrap_err();
+        assert_eq!(parallel, sequential);
+    }
+
     #[test]
     fn test_backward_search() {
         let input = &b"Hello World!"[..];
@@ -196,17 +1738,63 @@ mod tests {
 
     #[test]
     fn test_parse_version() {
-        let info = TracableInfo::new().forward(true).backward(true);
-        let input = LocatedSpan::new_extra(b"%PDF-1.8".as_bytes(), info);
+        let input = span(b"%PDF-1.8");
 
         assert_eq!((1, 8), version(input).unwrap().1);
     }
 
     #[test]
     fn test_parse_binary_indicator() {
-        let info = TracableInfo::new().forward(true).backward(true);
-        let input = LocatedSpan::new_extra(b"%\xbf\xbf\xbf\xbf\xbf\n".as_bytes(), info);
+        let input = span(b"%\xbf\xbf\xbf\xbf\xbf\n");
+
+        assert_eq!(
+            binary_indicator(input).unwrap().1,
+            Some(b"\xbf\xbf\xbf\xbf\xbf".to_vec())
+        );
+        assert_eq!(binary_indicator(span(b"%\0\0\0\0\n")).unwrap().1, None);
+    }
+
+    #[test]
+    fn binary_indicator_round_trip() {
+        let write = |pdf: &RawPdf, binary_indicator| {
+            let mut out = Vec::new();
+            let options = crate::simple_encode::EncodeOptions {
+                binary_indicator,
+                ..Default::default()
+            };
+            crate::simple_encode::SimpleEncoder::write_with(pdf, &mut out, options).unwrap();
+            crate::read_bytes(out).unwrap()
+        };
+
+        let pdf = crate::read_bytes(document(1)).unwrap();
+        assert!(pdf.announced_binary());
+        assert_eq!(pdf.binary_indicator(), Some(crate::simple_encode::BINARY_INDICATOR));
+
+        // the original bytes are kept
+        let mut input = document(1);
+        input.splice(10..14, *b"\xaa\xbb\xcc\xdd");
+        let pdf = crate::read_bytes(input).unwrap();
+        assert_eq!(pdf.binary_indicator(), Some(&b"\xaa\xbb\xcc\xdd"[..]));
+        assert_eq!(write(&pdf, None).binary_indicator(), Some(&b"\xaa\xbb\xcc\xdd"[..]));
+        let written = write(&pdf, Some(b"\x80\x81\x82\x83\x84"));
+        assert!(written.announced_binary());
+        assert_eq!(written.binary_indicator(), Some(&b"\x80\x81\x82\x83\x84"[..]));
+        for invalid in [&b"\x80\x81\x82"[..], b"\x80\x81\x82\x7f", b"\x80\x81\x82\n"] {
+            let options = crate::simple_encode::EncodeOptions {
+                binary_indicator: Some(invalid),
+                ..Default::default()
+            };
+            assert_eq!(
+                crate::simple_encode::SimpleEncoder::write_with(&pdf, &mut Vec::new(), options),
+                Err(crate::simple_encode::EncodeError::InvalidBinaryIndicator)
+            );
+        }
 
-        assert!(binary_indicator(input).unwrap().1);
+        // documents without the comment are marked as binary
+        let mut input = document(1);
+        input.splice(10..14, *b"text");
+        let pdf = crate::read_bytes(input).unwrap();
+        assert!(!pdf.announced_binary());
+        assert!(write(&pdf, None).announced_binary());
     }
 }
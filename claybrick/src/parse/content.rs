@@ -0,0 +1,445 @@
+//! Parser for content streams.
+//!
+//! Content streams are a sequence of operations. Each operation consists of
+//! operands written in the object syntax followed by an operator keyword.
+
+use nom::{branch, bytes, character, combinator::into, error::ErrorKind, multi, sequence};
+#[cfg(feature = "trace")]
+use nom_tracable::tracable_parser;
+
+use crate::{
+    logging,
+    pdf::{Array, Dictionary, Name, Object, Stream},
+};
+
+use super::{
+    error::{CbParseError, CbParseErrorKind},
+    object::{
+        bool_object, dictionary_object, hex_string_object, is_regular, name_object, null_object, number_object,
+        string_object,
+    },
+    CbParseResult, Span,
+};
+
+macro_rules! operators {
+    ($($(#[$doc:meta])* $variant:ident => $keyword:literal,)*) => {
+        /// Operators of content streams.
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub enum Operator {
+            $($(#[$doc])* $variant,)*
+            /// An operator that is not defined by the PDF specification.
+            Other(Name),
+        }
+
+        impl Operator {
+            pub fn from_keyword(keyword: &[u8]) -> Self {
+                match keyword {
+                    $($keyword => Operator::$variant,)*
+                    other => Operator::Other(other.to_vec().into()),
+                }
+            }
+
+            /// The keyword of the operator as written in the content stream.
+            pub fn keyword(&self) -> &[u8] {
+                match self {
+                    $(Operator::$variant => $keyword,)*
+                    Operator::Other(name) => name,
+                }
+            }
+        }
+    };
+}
+
+operators! {
+    /// `w`
+    SetLineWidth => b"w",
+    /// `J`
+    SetLineCap => b"J",
+    /// `j`
+    SetLineJoin => b"j",
+    /// `M`
+    SetMiterLimit => b"M",
+    /// `d`
+    SetDash => b"d",
+    /// `ri`
+    SetRenderingIntent => b"ri",
+    /// `i`
+    SetFlatness => b"i",
+    /// `gs`
+    SetExtGState => b"gs",
+    /// `q`
+    Save => b"q",
+    /// `Q`
+    Restore => b"Q",
+    /// `cm`
+    ConcatMatrix => b"cm",
+    /// `m`
+    MoveTo => b"m",
+    /// `l`
+    LineTo => b"l",
+    /// `c`
+    CurveTo => b"c",
+    /// `v`
+    CurveToInitial => b"v",
+    /// `y`
+    CurveToFinal => b"y",
+    /// `h`
+    ClosePath => b"h",
+    /// `re`
+    Rectangle => b"re",
+    /// `S`
+    Stroke => b"S",
+    /// `s`
+    CloseStroke => b"s",
+    /// `f`
+    Fill => b"f",
+    /// `F`, same as `f`.
+    FillObsolete => b"F",
+    /// `f*`
+    FillEvenOdd => b"f*",
+    /// `B`
+    FillStroke => b"B",
+    /// `B*`
+    FillStrokeEvenOdd => b"B*",
+    /// `b`
+    CloseFillStroke => b"b",
+    /// `b*`
+    CloseFillStrokeEvenOdd => b"b*",
+    /// `n`
+    EndPath => b"n",
+    /// `W`
+    Clip => b"W",
+    /// `W*`
+    ClipEvenOdd => b"W*",
+    /// `BT`
+    BeginText => b"BT",
+    /// `ET`
+    EndText => b"ET",
+    /// `Tc`
+    SetCharSpacing => b"Tc",
+    /// `Tw`
+    SetWordSpacing => b"Tw",
+    /// `Tz`
+    SetHorizontalScaling => b"Tz",
+    /// `TL`
+    SetLeading => b"TL",
+    /// `Tf`
+    SetFont => b"Tf",
+    /// `Tr`
+    SetTextRenderingMode => b"Tr",
+    /// `Ts`
+    SetTextRise => b"Ts",
+    /// `Td`
+    MoveText => b"Td",
+    /// `TD`
+    MoveTextSetLeading => b"TD",
+    /// `Tm`
+    SetTextMatrix => b"Tm",
+    /// `T*`
+    NextLine => b"T*",
+    /// `Tj`
+    ShowText => b"Tj",
+    /// `TJ`
+    ShowTextArray => b"TJ",
+    /// `'`
+    NextLineShowText => b"'",
+    /// `"`
+    NextLineShowTextSpacing => b"\"",
+    /// `d0`
+    SetGlyphWidth => b"d0",
+    /// `d1`
+    SetGlyphWidthBoundingBox => b"d1",
+    /// `CS`
+    SetStrokeColorSpace => b"CS",
+    /// `cs`
+    SetFillColorSpace => b"cs",
+    /// `SC`
+    SetStrokeColor => b"SC",
+    /// `SCN`
+    SetStrokeColorN => b"SCN",
+    /// `sc`
+    SetFillColor => b"sc",
+    /// `scn`
+    SetFillColorN => b"scn",
+    /// `G`
+    SetStrokeGray => b"G",
+    /// `g`
+    SetFillGray => b"g",
+    /// `RG`
+    SetStrokeRgb => b"RG",
+    /// `rg`
+    SetFillRgb => b"rg",
+    /// `K`
+    SetStrokeCmyk => b"K",
+    /// `k`
+    SetFillCmyk => b"k",
+    /// `sh`
+    PaintShading => b"sh",
+    /// `BI` ... `ID` ... `EI`. The only operand is a stream with the image
+    /// parameters and data.
+    InlineImage => b"BI",
+    /// `Do`
+    PaintXObject => b"Do",
+    /// `MP`
+    MarkedContentPoint => b"MP",
+    /// `DP`
+    MarkedContentPointProperties => b"DP",
+    /// `BMC`
+    BeginMarkedContent => b"BMC",
+    /// `BDC`
+    BeginMarkedContentProperties => b"BDC",
+    /// `EMC`
+    EndMarkedContent => b"EMC",
+    /// `BX`
+    BeginCompatibility => b"BX",
+    /// `EX`
+    EndCompatibility => b"EX",
+}
+
+/// An operator with its operands.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation {
+    pub operands: Vec<Object>,
+    pub operator: Operator,
+}
+
+/// Consume whitespace and comments.
+fn skip_whitespace(input: Span) -> Span {
+    multi::many0::<_, _, (), _>(branch::alt((
+        character::complete::multispace1,
+        sequence::preceded(character::complete::char('%'), character::complete::not_line_ending),
+    )))(input)
+    .map(|(r, _)| r)
+    .unwrap_or(input)
+}
+
+/// Arrays of operands. Unlike [`array_object`](super::object::array_object)
+/// this doesn't accept references.
+#[cfg_attr(feature = "trace", tracable_parser)]
+fn operand_array(input: Span) -> CbParseResult<Array> {
+    let (remainder, array) = sequence::delimited(
+        sequence::pair(character::complete::char('['), character::complete::multispace0),
+        multi::fold_many0(
+            |i| operand(i).map(|(r, obj)| (skip_whitespace(r), obj)),
+            Array::new,
+            |mut acc, obj| {
+                acc.push(obj);
+                acc
+            },
+        ),
+        character::complete::char(']'),
+    )(input)?;
+
+    Ok((remainder, array))
+}
+
+/// Operands use the object syntax, except that references are not allowed.
+#[cfg_attr(feature = "trace", tracable_parser)]
+fn operand(input: Span) -> CbParseResult<Object> {
+    branch::alt((
+        into(dictionary_object),
+        into(operand_array),
+        string_object,
+        number_object,
+        bool_object,
+        null_object,
+        hex_string_object,
+        into(name_object),
+    ))(input)
+}
+
+#[cfg_attr(feature = "trace", tracable_parser)]
+fn operator(input: Span) -> CbParseResult<Operator> {
+    let (remainder, keyword) = bytes::complete::take_while1(is_regular)(input)?;
+    Ok((remainder, Operator::from_keyword(&keyword)))
+}
+
+/// The parameters and data of an inline image, following the `BI` operator.
+#[cfg_attr(feature = "trace", tracable_parser)]
+fn inline_image(input: Span) -> CbParseResult<Stream> {
+    let mut dictionary = Dictionary::new();
+    let mut remainder = skip_whitespace(input);
+    while let Ok((r, key)) = name_object(remainder) {
+        let (r, value) = operand(skip_whitespace(r))?;
+        dictionary.insert(key, value);
+        remainder = skip_whitespace(r);
+    }
+
+    let (remainder, _) = bytes::complete::tag(b"ID")(remainder)?;
+    // A single whitespace separates `ID` from the data.
+    let (remainder, _) = bytes::complete::take(1_usize)(remainder)?;
+
+    // The data ends at the first `EI` surrounded by whitespace.
+    let data = remainder.fragment();
+    let end = (0..data.len())
+        .find(|&i| {
+            data[i..].starts_with(b"EI")
+                && (i == 0 || data[i - 1].is_ascii_whitespace())
+                && data.get(i + 2).is_none_or(|&b| !is_regular(b))
+        })
+        .ok_or_else(|| {
+            nom::Err::Error(CbParseError::new(
+                remainder,
+                CbParseErrorKind::Nom(ErrorKind::TakeUntil),
+            ))
+        })?;
+    let (remainder, data) = bytes::complete::take(end)(remainder)?;
+    let (remainder, _) = bytes::complete::tag(b"EI")(remainder)?;
+
+    // The whitespace before `EI` is not part of the data.
+    let mut data = &data.fragment()[..];
+    if let Some(d) = data.strip_suffix(b"\r\n") {
+        data = d;
+    } else if let Some((_, d)) = data.split_last().filter(|(last, _)| last.is_ascii_whitespace()) {
+        data = d;
+    }
+
+    Ok((
+        remainder,
+        Stream {
+            dictionary,
+            data: data.to_vec().into(),
+            provenance: Default::default(),
+        },
+    ))
+}
+
+#[cfg_attr(feature = "trace", tracable_parser)]
+fn operations(input: Span) -> CbParseResult<Vec<Operation>> {
+    let mut operations = Vec::new();
+    let mut operands = Vec::new();
+    let mut remainder = skip_whitespace(input);
+
+    while !remainder.is_empty() {
+        if let Ok((r, obj)) = operand(remainder) {
+            operands.push(obj);
+            remainder = skip_whitespace(r);
+            continue;
+        }
+
+        let (r, operator) = operator(remainder)?;
+        let r = if operator == Operator::InlineImage {
+            let (r, image) = inline_image(r)?;
+            operands.push(Object::Stream(image));
+            r
+        } else {
+            r
+        };
+        operations.push(Operation {
+            operands: std::mem::take(&mut operands),
+            operator,
+        });
+        remainder = skip_whitespace(r);
+    }
+
+    if !operands.is_empty() {
+        logging::warn!("Ignoring {} operands at the end of the content stream", operands.len());
+    }
+
+    Ok((remainder, operations))
+}
+
+/// Parse the operations of a (decoded) content stream.
+pub fn parse_operations(data: &[u8]) -> Result<Vec<Operation>, CbParseError<()>> {
+    operations(data.into()).map(|(_, ops)| ops).map_err(|err| match err {
+        nom::Err::Error(err) | nom::Err::Failure(err) => err.without_input(),
+        nom::Err::Incomplete(_) => CbParseError::new((), CbParseErrorKind::Nom(ErrorKind::Complete)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op(operator: Operator, operands: Vec<Object>) -> Operation {
+        Operation { operands, operator }
+    }
+
+    fn name(n: &[u8]) -> Object {
+        Object::Name(n.to_vec().into())
+    }
+
+    #[test]
+    fn text_operators() {
+        let content = b"BT
+/F1 12 Tf
+1 0 0 1 72 712 Tm
+(Hello\\) World) Tj
+0 -14 Td [(A) 120 (W) -50.5 <0041>] TJ
+ET";
+        assert_eq!(
+            parse_operations(content),
+            Ok(vec![
+                op(Operator::BeginText, vec![]),
+                op(Operator::SetFont, vec![name(b"F1"), Object::Integer(12)]),
+                op(
+                    Operator::SetTextMatrix,
+                    [1, 0, 0, 1, 72, 712].into_iter().map(Object::Integer).collect()
+                ),
+                op(
+                    Operator::ShowText,
+                    vec![Object::String(b"Hello\\) World".to_vec().into())]
+                ),
+                op(Operator::MoveText, vec![Object::Integer(0), Object::Integer(-14)]),
+                op(
+                    Operator::ShowTextArray,
+                    vec![Object::from(vec![
+                        Object::String(b"A".to_vec().into()),
+                        Object::Integer(120),
+                        Object::String(b"W".to_vec().into()),
+                        Object::Float(-50.5),
+                        Object::HexString(vec![0x00, 0x41].into()),
+                    ])]
+                ),
+                op(Operator::EndText, vec![]),
+            ])
+        );
+    }
+
+    #[test]
+    fn path_operators() {
+        let content = b"q 0.5 0 0 0.5 0 0 cm % scale
+10 0 R
+0 0 m 100 100 l 10 20 30 40 50 60 c h
+0 0 200 100 re f* S Q
+/Im1 Do";
+        let operations = parse_operations(content).unwrap();
+        let operators = operations.iter().map(|o| o.operator.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            operators,
+            vec![
+                Operator::Save,
+                Operator::ConcatMatrix,
+                // not a reference
+                Operator::Other(b"R".to_vec().into()),
+                Operator::MoveTo,
+                Operator::LineTo,
+                Operator::CurveTo,
+                Operator::ClosePath,
+                Operator::Rectangle,
+                Operator::FillEvenOdd,
+                Operator::Stroke,
+                Operator::Restore,
+                Operator::PaintXObject,
+            ]
+        );
+        assert_eq!(operations[2].operands, vec![Object::Integer(10), Object::Integer(0)]);
+        assert_eq!(operations[11].operands, vec![name(b"Im1")]);
+    }
+
+    #[test]
+    fn inline_image() {
+        let content = b"q BI /W 2 /H 1 /BPC 8 /CS /G ID \x00EI\xff\nEI Q";
+        let operations = parse_operations(content).unwrap();
+        assert_eq!(operations.len(), 3);
+        assert_eq!(operations[1].operator, Operator::InlineImage);
+        match &operations[1].operands[..] {
+            [Object::Stream(image)] => {
+                assert_eq!(&image.data[..], b"\x00EI\xff");
+                assert_eq!(image.dictionary.get(&b"W"[..]), Some(&Object::Integer(2)));
+            }
+            other => panic!("unexpected operands {:?}", other),
+        }
+        assert_eq!(operations[2].operator, Operator::Restore);
+    }
+}
@@ -1,26 +1,107 @@
 use nom::error::{ErrorKind, ParseError};
 
-use super::xref::XrefError;
-use crate::pdf::{object::stream::filter::FilterError, trailer::TrailerError};
+use crate::pdf::{object::stream::filter::FilterError, trailer::TrailerError, KeyError, Name};
+
+/// Errors that occur while parsing the xref section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XrefError {
+    /// The stream object wasn't the correct type.
+    StreamObject,
+
+    /// The W entry in the stream object dictionary was invalid.
+    WEntry,
+
+    /// There was an error in the content of the xref stream.
+    StreamContent,
+
+    /// The Index entry in the stream object dictionary was invalid.
+    IndexEntry,
+
+    /// The number of entries in the xref stream differs from the number the
+    /// Index entry declares.
+    EntryCount,
+
+    /// An entry of the xref table was invalid.
+    TableEntry,
+}
+
+impl std::fmt::Display for XrefError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            XrefError::StreamObject => "the xref stream is not a stream object",
+            XrefError::WEntry => "invalid W entry in the xref stream",
+            XrefError::StreamContent => "invalid xref stream data",
+            XrefError::IndexEntry => "invalid Index entry in the xref stream",
+            XrefError::EntryCount => "the number of xref stream entries doesn't match the Index entry",
+            XrefError::TableEntry => "invalid xref table entry",
+        };
+        f.write_str(message)
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CbParseErrorKind {
     InvalidTrailer(TrailerError),
     StartxrefInvalid,
     BackwardSearchNotFound,
-    // TODO: More detailed errors
     XrefInvalid(XrefError),
     StreamError(FilterError),
+    /// The dictionary or the header of an object stream is invalid.
+    InvalidObjectStream,
     InvalidName,
+    /// Boxed to keep the errors of all parsers small.
+    InvalidKey(Box<KeyError>),
+    /// A dictionary contains the key more than once and
+    /// [DuplicateKeys::Reject](crate::parse::DuplicateKeys::Reject) is set.
+    DuplicateKey(Name),
+    /// The `/Length` of a stream doesn't end at `endstream` and the parse
+    /// options are strict.
+    StreamLengthMismatch {
+        declared: usize,
+        actual: usize,
+    },
+    /// The header announces a version that doesn't exist and the parse
+    /// options are strict.
+    UnknownVersion((u8, u8)),
+    /// The cancellation flag of the parse options was set.
+    Cancelled,
     Nom(ErrorKind),
 }
 
+impl std::fmt::Display for CbParseErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CbParseErrorKind::InvalidTrailer(err) => write!(f, "invalid trailer ({:?})", err),
+            CbParseErrorKind::StartxrefInvalid => write!(f, "invalid startxref"),
+            CbParseErrorKind::BackwardSearchNotFound => write!(f, "marker not found at the end of the file"),
+            CbParseErrorKind::XrefInvalid(err) => write!(f, "invalid xref section: {}", err),
+            CbParseErrorKind::StreamError(err) => write!(f, "stream filter failed ({:?})", err),
+            CbParseErrorKind::InvalidObjectStream => write!(f, "invalid object stream"),
+            CbParseErrorKind::InvalidName => write!(f, "invalid name"),
+            CbParseErrorKind::InvalidKey(err) => write!(f, "invalid dictionary key: {}", err),
+            CbParseErrorKind::DuplicateKey(key) => write!(f, "duplicate dictionary key /{}", key),
+            CbParseErrorKind::StreamLengthMismatch { declared, actual } => {
+                write!(f, "stream /Length is {} but the data has {} bytes", declared, actual)
+            }
+            CbParseErrorKind::UnknownVersion((major, minor)) => write!(f, "unknown version {}.{}", major, minor),
+            CbParseErrorKind::Cancelled => write!(f, "parsing was cancelled"),
+            CbParseErrorKind::Nom(kind) => write!(f, "{}", kind.description()),
+        }
+    }
+}
+
 impl From<TrailerError> for CbParseErrorKind {
     fn from(err: TrailerError) -> Self {
         CbParseErrorKind::InvalidTrailer(err)
     }
 }
 
+impl From<KeyError> for CbParseErrorKind {
+    fn from(err: KeyError) -> Self {
+        CbParseErrorKind::InvalidKey(Box::new(err))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CbParseError<I> {
     pub input: I,
@@ -36,6 +117,32 @@ impl<I> CbParseError<I> {
             from: None,
         }
     }
+
+    /// This error followed by the errors that caused it.
+    pub fn chain(&self) -> impl Iterator<Item = &Self> {
+        std::iter::successors(Some(self), |err| err.from.as_deref())
+    }
+
+    /// Drop the input, e.g. if the error has to outlive the parsed data.
+    pub fn without_input(self) -> CbParseError<()> {
+        CbParseError {
+            input: (),
+            kind: self.kind,
+            from: self.from.map(|from| Box::new(from.without_input())),
+        }
+    }
+}
+
+/// Shows the kind followed by the errors that caused it, the input is
+/// omitted.
+impl<I> std::fmt::Display for CbParseError<I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind)?;
+        match &self.from {
+            Some(from) => write!(f, ": {}", from),
+            None => Ok(()),
+        }
+    }
 }
 
 impl<I> ParseError<I> for CbParseError<I> {
@@ -5,11 +5,13 @@ use nom::{
     combinator::{self, into},
     multi, number, sequence,
 };
+#[cfg(feature = "trace")]
 use nom_tracable::tracable_parser;
 
 use crate::{
-    parse::{comment, Span},
-    pdf::{Array, Dictionary, IndirectObject, Name, Object, Reference, Stream},
+    logging,
+    parse::{comment, shared_bytes, DuplicateKeys, ParseWarning, Span},
+    pdf::{Array, Bytes, Dictionary, IndirectObject, Name, Object, Reference, Stream, StreamDataProvenance},
 };
 
 use super::{
@@ -31,7 +33,7 @@ pub(crate) fn is_regular(chr: u8) -> bool {
 
 /// Consume all whitespace. If input doesn't start with a whitespace, peek the
 /// next char and require it to be a delimiter.
-#[tracable_parser]
+#[cfg_attr(feature = "trace", tracable_parser)]
 fn require_termination(input: Span) -> CbParseResult<()> {
     let (remainder, whitespace) = character::complete::multispace0(input)?;
     if whitespace.is_empty() && !input.is_empty() {
@@ -52,7 +54,7 @@ fn consume_until_parenthesis(input: Span) -> Span {
     .unwrap_or(input)
 }
 
-#[tracable_parser]
+#[cfg_attr(feature = "trace", tracable_parser)]
 fn consume_string_content(input: Span) -> CbParseResult<()> {
     let mut open_parathesis = 0;
     let mut remainder = input;
@@ -104,7 +106,7 @@ fn hex_decode(input: &[u8]) -> Option<Vec<u8>> {
     Some(out)
 }
 
-#[tracable_parser]
+#[cfg_attr(feature = "trace", tracable_parser)]
 pub(crate) fn hex_string_object(input: Span) -> CbParseResult<Object> {
     let (remainder, content) = sequence::delimited(
         character::complete::char('<'),
@@ -120,7 +122,7 @@ pub(crate) fn hex_string_object(input: Span) -> CbParseResult<Object> {
     Ok((remainder, Object::HexString(bytes.into())))
 }
 
-#[tracable_parser]
+#[cfg_attr(feature = "trace", tracable_parser)]
 pub(crate) fn string_object(input: Span) -> CbParseResult<Object> {
     let (remainder, content) = sequence::delimited(
         character::complete::char('('),
@@ -132,7 +134,7 @@ pub(crate) fn string_object(input: Span) -> CbParseResult<Object> {
     Ok((remainder, Object::String(content.to_vec().into())))
 }
 
-#[tracable_parser]
+#[cfg_attr(feature = "trace", tracable_parser)]
 pub(crate) fn bool_object(input: Span) -> CbParseResult<Object> {
     let (remainder, obj) = branch::alt((
         combinator::value(Object::Bool(true), bytes::complete::tag(TRUE_OBJECT)),
@@ -144,7 +146,7 @@ pub(crate) fn bool_object(input: Span) -> CbParseResult<Object> {
     Ok((remainder, obj))
 }
 
-#[tracable_parser]
+#[cfg_attr(feature = "trace", tracable_parser)]
 pub(crate) fn number_object(input: Span) -> CbParseResult<Object> {
     branch::alt((
         combinator::map(
@@ -158,7 +160,7 @@ pub(crate) fn number_object(input: Span) -> CbParseResult<Object> {
     ))(input)
 }
 
-#[tracable_parser]
+#[cfg_attr(feature = "trace", tracable_parser)]
 pub(crate) fn null_object(input: Span) -> CbParseResult<Object> {
     let (remainder, _) = bytes::complete::tag(NULL_OBJECT)(input)?;
     let (remainder, _) = require_termination(remainder)?;
@@ -166,7 +168,7 @@ pub(crate) fn null_object(input: Span) -> CbParseResult<Object> {
     Ok((remainder, Object::Null))
 }
 
-#[tracable_parser]
+#[cfg_attr(feature = "trace", tracable_parser)]
 pub(crate) fn name_object(input: Span) -> CbParseResult<Name> {
     let (remainder, _) = character::complete::char('/')(input)?;
     let (remainder, name) = bytes::complete::take_while(is_regular)(remainder)?;
@@ -196,7 +198,7 @@ pub(crate) fn name_object(input: Span) -> CbParseResult<Name> {
     Ok((remainder, out.into()))
 }
 
-#[tracable_parser]
+#[cfg_attr(feature = "trace", tracable_parser)]
 pub(crate) fn dictionary_entry(input: Span) -> CbParseResult<(Name, Object)> {
     let (remainder, name) = name_object(input)?;
     let (remainder, obj) = object(remainder)?;
@@ -205,22 +207,50 @@ pub(crate) fn dictionary_entry(input: Span) -> CbParseResult<(Name, Object)> {
     Ok((remainder, (name, obj)))
 }
 
-#[tracable_parser]
+#[cfg_attr(feature = "trace", tracable_parser)]
 pub(crate) fn dictionary_object(input: Span) -> CbParseResult<Dictionary> {
-    let (remainder, map) = sequence::delimited(
+    let duplicate_keys = input.extra.duplicate_keys();
+    let (remainder, (map, rejected)) = sequence::delimited(
         sequence::terminated(bytes::complete::tag(b"<<"), character::complete::multispace0),
-        multi::fold_many0(dictionary_entry, Dictionary::new, |mut acc, (name, obj)| {
-            acc.insert(name, obj);
-            acc
-        }),
+        multi::fold_many0(
+            dictionary_entry,
+            || (Dictionary::new(), None),
+            |(mut acc, mut rejected), (name, obj)| {
+                if !acc.contains_key(&name) {
+                    acc.insert(name, obj);
+                    return (acc, rejected);
+                }
+                input.extra.warn(ParseWarning::DuplicateKey {
+                    key: name.clone(),
+                    dictionary_offset: input.location_offset(),
+                    object_stream: input.extra.object_stream(),
+                });
+                match duplicate_keys {
+                    DuplicateKeys::LastWins => {
+                        acc.insert(name, obj);
+                    }
+                    DuplicateKeys::FirstWins => {}
+                    DuplicateKeys::Reject => {
+                        rejected.get_or_insert(name);
+                    }
+                }
+                (acc, rejected)
+            },
+        ),
         bytes::complete::tag(b">>"),
     )(input)?;
+    if let Some(key) = rejected {
+        return Err(nom::Err::Failure(CbParseError::new(
+            input,
+            CbParseErrorKind::DuplicateKey(key),
+        )));
+    }
     let (remainder, _) = character::complete::multispace0(remainder)?;
 
     Ok((remainder, map))
 }
 
-#[tracable_parser]
+#[cfg_attr(feature = "trace", tracable_parser)]
 pub(crate) fn array_object(input: Span) -> CbParseResult<Array> {
     let (remainder, array) = sequence::delimited(
         sequence::pair(character::complete::char('['), character::complete::multispace0),
@@ -236,8 +266,8 @@ pub(crate) fn array_object(input: Span) -> CbParseResult<Array> {
 }
 
 /// Get the stream content using the provided length.
-fn stream_by_length(length: usize, input: Span) -> CbParseResult<Vec<u8>> {
-    let (remainder, data) = combinator::map(take(length), |b: Span| b.to_vec())(input)?;
+fn stream_by_length(length: usize, input: Span) -> CbParseResult<Bytes> {
+    let (remainder, data) = combinator::map(take(length), |b: Span| shared_bytes(&b))(input)?;
     let remainder = character::complete::line_ending::<_, CbParseError<Span>>(remainder)
         .map(|(r, _)| r)
         .unwrap_or(remainder);
@@ -249,44 +279,109 @@ fn stream_by_length(length: usize, input: Span) -> CbParseResult<Vec<u8>> {
 
 /// Get the stream content by searching for the `endstream` keyword. This is a
 /// fallback incase the stream length was invalid.
-#[tracable_parser]
-fn stream_by_keyword(input: Span) -> CbParseResult<Vec<u8>> {
-    log::warn!("Using fallback stream content parser.");
-
-    let (remainder, data) =
-        combinator::map(bytes::complete::take_until(&b"endstream"[..]), |b: Span| b.to_vec())(input)?;
+#[cfg_attr(feature = "trace", tracable_parser)]
+fn stream_by_keyword(input: Span) -> CbParseResult<Bytes> {
+    logging::warn!("Using fallback stream content parser.");
+
+    let (remainder, data) = bytes::complete::take_until(&b"endstream"[..])(input)?;
+    // The end of line before `endstream` is not part of the data.
+    let eol = [&b"\r\n"[..], b"\n", b"\r"]
+        .into_iter()
+        .find(|eol| data.fragment().ends_with(eol))
+        .map_or(0, <[u8]>::len);
+    let (_, data) = take(data.len() - eol)(data)?;
+    let data = shared_bytes(&data);
     let (remainder, _) = bytes::complete::tag(b"endstream")(remainder)?;
     let (remainder, _) = require_termination(remainder)?;
 
     Ok((remainder, data))
 }
 
-#[tracable_parser]
-pub(crate) fn stream_object(input: Span) -> CbParseResult<Stream> {
+/// The stream dictionary and the `stream` keyword. The remainder starts with
+/// the stream data.
+#[cfg_attr(feature = "trace", tracable_parser)]
+fn stream_header(input: Span) -> CbParseResult<Dictionary> {
     let (remainder, dict) = dictionary_object(input)?;
 
     let (remainder, _) = bytes::complete::tag(b"stream")(remainder)?;
+    // some writers put spaces before the EOL
+    let (remainder, _) = bytes::complete::take_while(|c| c == b' ')(remainder)?;
     // stream keyword must not be followed by \r only because that would prevent
-    // streams from beginning with \n.
-    let (remainder, _) = branch::alt((bytes::complete::tag("\r\n"), bytes::complete::tag("\n")))(remainder)?;
+    // streams from beginning with \n. Some writers do it anyway.
+    let remainder = match branch::alt((bytes::complete::tag("\r\n"), bytes::complete::tag("\n")))(remainder) {
+        Ok((remainder, _)) => remainder,
+        Err(err) if input.extra.strict() => return Err(err),
+        Err(_) => {
+            let (remainder, _) = bytes::complete::tag("\r")(remainder)?;
+            input.extra.warn(ParseWarning::StreamCr {
+                object_offset: input.location_offset(),
+            });
+            remainder
+        }
+    };
+
+    Ok((remainder, dict))
+}
+
+/// The offset of the data of the indirect stream object at the beginning of
+/// `input`, relative to the beginning of the parsed file.
+pub(crate) fn stream_data_offset(input: Span) -> CbParseResult<usize> {
+    let (remainder, _) = sequence::tuple((
+        character::complete::u32,
+        character::complete::multispace1,
+        character::complete::u32,
+        character::complete::multispace1,
+        bytes::complete::tag(b"obj"),
+        character::complete::multispace0,
+    ))(input)?;
+    let (remainder, _) = stream_header(remainder)?;
+
+    Ok((remainder, remainder.location_offset()))
+}
 
-    let length = match dict.get(&b"Length"[..]) {
-        Some(Object::Integer(length)) => *length,
+#[cfg_attr(feature = "trace", tracable_parser)]
+pub(crate) fn stream_object(input: Span) -> CbParseResult<Stream> {
+    let (remainder, dict) = stream_header(input)?;
+
+    let declared = match dict.get(&b"Length"[..]) {
+        Some(Object::Integer(length)) if *length >= 0 => Some(*length as usize),
         l => {
-            log::warn!("ignoring length object: {:?}", l);
-            0
+            logging::warn!("ignoring length object: {:?}", l);
+            None
         }
     };
 
     // FIXME: handle huge streams
-    let (remainder, data) =
-        stream_by_length(usize::try_from(length).unwrap(), remainder).or_else(|_| stream_by_keyword(remainder))?;
+    let (remainder, data, provenance) = match stream_by_length(declared.unwrap_or(0), remainder) {
+        Ok((remainder, data)) if declared.is_some() => (remainder, data, StreamDataProvenance::Declared),
+        // an empty stream without length
+        Ok((remainder, data)) => (remainder, data, StreamDataProvenance::KeywordScan),
+        Err(_) => {
+            let (remainder, data) = stream_by_keyword(remainder)?;
+            if let Some(declared) = declared {
+                let actual = data.len();
+                if input.extra.strict() {
+                    return Err(nom::Err::Failure(CbParseError::new(
+                        input,
+                        CbParseErrorKind::StreamLengthMismatch { declared, actual },
+                    )));
+                }
+                input.extra.warn(ParseWarning::StreamLengthMismatch {
+                    declared,
+                    actual,
+                    object_hint: input.location_offset(),
+                });
+            }
+            (remainder, data, StreamDataProvenance::KeywordScan)
+        }
+    };
 
     Ok((
         remainder,
         Stream {
             dictionary: dict,
-            data: data.into(),
+            data,
+            provenance,
         },
     ))
 }
@@ -315,7 +410,7 @@ pub(crate) fn reference_object<'a>(index: u32, generation: u32) -> impl FnMut(Sp
     )
 }
 
-#[tracable_parser]
+#[cfg_attr(feature = "trace", tracable_parser)]
 pub(crate) fn indirect_object(input: Span) -> CbParseResult<Object> {
     let (remainder, index) = character::complete::u32(input)?;
     let (remainder, _) = character::complete::multispace1(remainder)?;
@@ -325,7 +420,7 @@ pub(crate) fn indirect_object(input: Span) -> CbParseResult<Object> {
     branch::alt((reference_object(index, generation), referred_object(index, generation)))(remainder)
 }
 
-#[tracable_parser]
+#[cfg_attr(feature = "trace", tracable_parser)]
 pub(crate) fn object(input: Span) -> CbParseResult<Object> {
     // The order is important!
     branch::alt((
@@ -346,12 +441,131 @@ pub(crate) fn object(input: Span) -> CbParseResult<Object> {
 #[cfg(test)]
 mod tests {
     use nom::AsBytes;
-    use std::collections::HashMap;
 
-    use crate::pdf::Reference;
+    use crate::{
+        parse::{self, ObjectOptions},
+        pdf::Reference,
+    };
 
     use super::*;
 
+    #[test]
+    fn duplicate_keys() {
+        let input = b"<</Length 5 /Type /XObject /Length 3>>";
+        let parse = |duplicate_keys| {
+            let options = ObjectOptions {
+                duplicate_keys,
+                ..Default::default()
+            };
+            dictionary_object(parse::with_options(input[..].into(), &options))
+                .map(|(_, dict)| dict)
+                .map_err(|err| err.map(CbParseError::without_input))
+        };
+
+        let last = parse(DuplicateKeys::LastWins).unwrap();
+        assert_eq!(last.get(&b"Length"[..]), Some(&Object::Integer(3)));
+        assert_eq!(last.len(), 2);
+        let first = parse(DuplicateKeys::FirstWins).unwrap();
+        assert_eq!(first.get(&b"Length"[..]), Some(&Object::Integer(5)));
+        assert!(matches!(
+            parse(DuplicateKeys::Reject),
+            Err(nom::Err::Failure(CbParseError {
+                kind: CbParseErrorKind::DuplicateKey(key),
+                ..
+            })) if &key[..] == b"Length"
+        ));
+        // Last wins without options.
+        assert_eq!(dictionary_object(input[..].into()).unwrap().1, last);
+    }
+
+    #[test]
+    fn stream_length_mismatch() {
+        let parse = |input: &[u8], strict| {
+            let (callback, warnings) = parse::tests::collect_warnings();
+            let options = ObjectOptions {
+                strict,
+                warnings: Some(callback),
+                ..Default::default()
+            };
+            let stream = indirect_object(parse::with_options(input.into(), &options))
+                .ok()
+                .and_then(|(_, obj)| obj.indirect().and_then(|i| i.object().stream()).cloned());
+            let warnings = warnings.lock().unwrap().clone();
+            (stream, warnings)
+        };
+
+        // 10 bytes short, the length ends inside the text
+        let short = b"1 0 obj <</Length 10>> stream\n0 0 m 100 100 l S\nendstream endobj";
+        let (stream, warnings) = parse(short, false);
+        assert_eq!(&stream.unwrap().data[..], b"0 0 m 100 100 l S");
+        assert_eq!(
+            warnings,
+            vec![ParseWarning::StreamLengthMismatch {
+                declared: 10,
+                actual: 17,
+                object_hint: 8,
+            }]
+        );
+        assert_eq!(parse(short, true), (None, vec![]));
+
+        // the length ends inside the next object
+        let long = b"1 0 obj <</Length 30>> stream\nabcde\nendstream endobj\n2 0 obj (text) endobj";
+        let (stream, warnings) = parse(long, false);
+        assert_eq!(&stream.unwrap().data[..], b"abcde");
+        assert_eq!(
+            warnings,
+            vec![ParseWarning::StreamLengthMismatch {
+                declared: 30,
+                actual: 5,
+                object_hint: 8,
+            }]
+        );
+        assert_eq!(parse(long, true), (None, vec![]));
+
+        // only the end of line before `endstream` is removed
+        let crlf = b"1 0 obj <</Length 30>> stream\r\nabcde\n\r\nendstream endobj";
+        let (stream, _) = parse(crlf, false);
+        assert_eq!(&stream.unwrap().data[..], b"abcde\n");
+
+        let exact = b"1 0 obj <</Length 5>> stream\nabcde\nendstream endobj";
+        let (stream, warnings) = parse(exact, true);
+        let stream = stream.unwrap();
+        assert_eq!(&stream.data[..], b"abcde");
+        assert!(warnings.is_empty());
+        assert_eq!(stream.provenance, StreamDataProvenance::Declared);
+
+        let (scanned, _) = parse(short, false);
+        let scanned = scanned.unwrap();
+        assert_eq!(scanned.provenance, StreamDataProvenance::KeywordScan);
+        assert!(!scanned.is_exact());
+        // the provenance isn't compared
+        let declared = Stream {
+            provenance: StreamDataProvenance::Declared,
+            ..scanned.clone()
+        };
+        assert_eq!(scanned, declared);
+    }
+
+    #[test]
+    fn stream_keyword_line_endings() {
+        // The data begins with bytes that look like a line ending.
+        let parse = |input: &[u8], strict| {
+            let options = ObjectOptions {
+                strict,
+                ..Default::default()
+            };
+            stream_object(parse::with_options(input.into(), &options))
+                .ok()
+                .map(|(_, stream)| stream)
+        };
+
+        let stream = parse(b"<</Length 3>>stream \r\n\n\xffa\nendstream", true).unwrap();
+        assert_eq!(&stream.data[..], b"\n\xffa");
+        let stream = parse(b"<</Length 3>>stream\r\r\xffa\nendstream", false).unwrap();
+        assert_eq!(&stream.data[..], b"\r\xffa");
+        assert!(parse(b"<</Length 3>>stream\r\r\xffa\nendstream", true).is_none());
+    }
+
     #[test]
     pub fn test_termination() {
         assert_eq!(
@@ -519,10 +733,10 @@ mod tests {
 
     #[test]
     pub fn test_dictionary() {
-        let obj = Object::Dictionary(HashMap::from([(b"Length".to_vec().into(), Object::Integer(93))]));
+        let obj = Object::Dictionary(Dictionary::from([(b"Length".to_vec().into(), Object::Integer(93))]));
         assert_eq!(object(b"<< /Length 93 >>".as_bytes().into()).unwrap().1, obj);
 
-        let obj = Object::Dictionary(HashMap::from([
+        let obj = Object::Dictionary(Dictionary::from([
             (b"Type".to_vec().into(), Object::Name(b"Example".to_vec().into())),
             (
                 b"Subtype".to_vec().into(),
@@ -536,7 +750,7 @@ mod tests {
             ),
             (
                 b"Subdictionary".to_vec().into(),
-                Object::Dictionary(HashMap::from([
+                Object::Dictionary(Dictionary::from([
                     (b"Item2".to_vec().into(), Object::Bool(true)),
                     (b"Item2".to_vec().into(), Object::Bool(true)),
                 ])),
@@ -730,6 +944,23 @@ endobj
         );
     }
 
+    #[test]
+    fn test_object_01_author_text() {
+        let parsed_obj = object(
+            b"<</Author<FEFF004A006F0073002000760061006E002000640065006E0020004F0065007600650072>>>"
+                .as_bytes()
+                .into(),
+        )
+        .unwrap()
+        .1;
+        let author = parsed_obj
+            .dictionary()
+            .and_then(|d| d.get(&b"Author"[..]))
+            .and_then(Object::hex_string)
+            .unwrap();
+        assert_eq!(author.decode_text((1, 7)), Ok("Jos van den Oever".to_string()));
+    }
+
     #[test]
     fn test_object_02() {
         let parsed_obj = object(
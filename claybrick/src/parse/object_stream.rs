@@ -1,77 +1,90 @@
-use nom::{bytes, character};
+use nom::{bytes, character, error::ErrorKind};
 
-use crate::pdf::{
-    document::{dict_types::OBJECT_STREAM, K_FIRST, K_LENGTH, K_STREAM_OBJECT_COUNT, K_TYPE},
-    Object, Stream,
+use crate::{
+    logging,
+    pdf::{
+        document::{dict_types::OBJECT_STREAM, K_FIRST, K_STREAM_OBJECT_COUNT, K_TYPE},
+        DictExt, KeyError, Object, Stream,
+    },
 };
 
-use super::{error::CbParseError, object::object, CbParseResult, Span};
+use super::{
+    error::{CbParseError, CbParseErrorKind},
+    nested_span,
+    object::object,
+    CbParseResult, ParseContext, Span,
+};
 
-fn parse_content(
-    _length: usize,
-    obj_count: usize,
-    first_offset: usize,
-    input: Span,
-) -> CbParseResult<Vec<(usize, Object)>> {
+fn parse_content(obj_count: usize, first_offset: usize, input: Span) -> CbParseResult<Vec<(usize, Object)>> {
     let mut remainder = input;
-    let mut objects = Vec::with_capacity(obj_count);
+    // Every object takes at least one byte, don't trust large counts.
+    let mut objects = Vec::with_capacity(obj_count.min(input.len()));
     for _ in 0..obj_count {
         // Next object number and byte offset.
         let (r, obj_number) = character::complete::u32(remainder)?;
-        let obj_number: usize = obj_number.try_into().expect("TODO: handle error");
         let (r, _) = character::complete::multispace1(r)?;
         let (r, byte_offset) = character::complete::u32(r)?;
-        let byte_offset: usize = byte_offset.try_into().expect("TODO: handle error");
         // the last pair might not be followed by a whitespace
         let (r, _) = character::complete::multispace0(r)?;
         remainder = r;
 
         // parse object with number `obj_number` at position `first_offset +
         // byte_offset`.
-        let (obj_bytes, _) = bytes::complete::take(first_offset + byte_offset)(input)?;
+        let offset = first_offset.saturating_add(byte_offset as usize);
+        let (obj_bytes, _) = bytes::complete::take(offset)(input)?;
         let (_, obj) = object(obj_bytes)?;
 
         // add object to the output vector.
-        objects.push((obj_number, obj));
+        objects.push((obj_number as usize, obj));
     }
 
     Ok((remainder, objects))
 }
 
-pub(crate) fn object_stream(stream: &Stream) -> Result<Vec<(usize, Object)>, CbParseError<()>> {
+/// The objects of the object `stream`, which are parsed with the options of
+/// `context`.
+pub(crate) fn object_stream(stream: &Stream, context: ParseContext) -> Result<Vec<(usize, Object)>, CbParseError<()>> {
     let dict = &stream.dictionary;
-    dict.get(K_TYPE)
-        .and_then(Object::name)
-        .filter(|name| &name[..] == OBJECT_STREAM)
-        .expect("FIXME: error for wrong type");
-    let length: usize = dict
-        .get(K_LENGTH)
-        .and_then(Object::integer)
-        .expect("FIXME: error for wrong length")
-        .try_into()
-        .expect("FIXME: error for invalid length");
+    let key_err = |err: KeyError| {
+        logging::error!("Invalid object stream dictionary: {}", err);
+        CbParseError::new((), err.into())
+    };
+
+    let invalid = |what: &str| {
+        logging::error!("Invalid object stream: {}", what);
+        CbParseError::new((), CbParseErrorKind::InvalidObjectStream)
+    };
+
+    if &dict.get_name(K_TYPE).map_err(key_err)?[..] != OBJECT_STREAM {
+        return Err(invalid("wrong type"));
+    }
+    // The `/Length` isn't checked, it can be a reference that was resolved
+    // while parsing the stream.
     let obj_count: usize = dict
-        .get(K_STREAM_OBJECT_COUNT)
-        .and_then(Object::integer)
-        .expect("FIXME: error for wrong count")
+        .get_integer(K_STREAM_OBJECT_COUNT)
+        .map_err(key_err)?
         .try_into()
-        .expect("FIXME: error for invalid count");
+        .map_err(|_| invalid("negative count"))?;
     let first_offset: usize = dict
-        .get(K_FIRST)
-        .and_then(Object::integer)
-        .expect("FIXME: error for wrong count")
+        .get_integer(K_FIRST)
+        .map_err(key_err)?
         .try_into()
-        .expect("FIXME: error for invalid count");
+        .map_err(|_| invalid("negative offset of the first object"))?;
 
-    let data = stream.filtered_data().expect("FIXME: error handling");
+    let data = stream
+        .filtered_data_with(context.cancel())
+        .map_err(|err| CbParseError::new((), CbParseErrorKind::StreamError(err)))?;
 
-    let (_, objs) = parse_content(length, obj_count, first_offset, data[..].into()).expect("TODO: error handling");
+    let (_, objs) = parse_content(obj_count, first_offset, nested_span(&data, context)).map_err(|err| match err {
+        nom::Err::Error(err) | nom::Err::Failure(err) => err.without_input(),
+        nom::Err::Incomplete(_) => CbParseError::new((), CbParseErrorKind::Nom(ErrorKind::Complete)),
+    })?;
     Ok(objs)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::pdf::{Bytes, Name};
+    use crate::pdf::{document::K_LENGTH, Bytes, Name};
 
     use super::*;
 
@@ -86,9 +99,10 @@ mod tests {
             ]
             .into(),
             data: b"".to_vec().into(),
+            provenance: Default::default(),
         };
 
-        assert_eq!(object_stream(&input_stream), Ok(vec![]))
+        assert_eq!(object_stream(&input_stream, ParseContext::default()), Ok(vec![]))
     }
 
     #[test]
@@ -106,8 +120,47 @@ mod tests {
             ]
             .into(),
             data,
+            provenance: Default::default(),
         };
 
-        assert_eq!(object_stream(&input_stream), Ok(vec![(123, Object::Integer(999))]))
+        assert_eq!(
+            object_stream(&input_stream, ParseContext::default()),
+            Ok(vec![(123, Object::Integer(999))])
+        )
+    }
+
+    #[test]
+    fn duplicate_keys_in_object_streams() {
+        use crate::parse::{span, tests::collect_warnings, with_options, ObjectOptions, ParseOptions, ParseWarning};
+
+        let data: Bytes = b"1 0 <</A 1 /A 2>>".to_vec().into();
+        let input_stream = Stream {
+            dictionary: [
+                (Name::new(K_TYPE.into()), Object::from(Name::new(OBJECT_STREAM.into()))),
+                (Name::new(K_STREAM_OBJECT_COUNT.into()), Object::Integer(1)),
+                (Name::new(K_FIRST.into()), Object::Integer(4)),
+            ]
+            .into(),
+            data,
+            provenance: Default::default(),
+        };
+        let (callback, warnings) = collect_warnings();
+        let options = ObjectOptions::from(&ParseOptions {
+            warnings: Some(callback),
+            ..Default::default()
+        });
+        let context = with_options(span(b""), &options).extra;
+
+        // The same offset in different object streams is reported for each
+        // stream.
+        for number in [5, 6, 5] {
+            object_stream(&input_stream, context.in_object_stream(number)).unwrap();
+        }
+        let duplicate = |object_stream| ParseWarning::DuplicateKey {
+            key: b"A".to_vec().into(),
+            dictionary_offset: 4,
+            object_stream,
+        };
+        assert_eq!(*warnings.lock().unwrap(), vec![duplicate(Some(5)), duplicate(Some(6))]);
     }
 }
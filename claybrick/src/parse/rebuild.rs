@@ -0,0 +1,208 @@
+//! Reconstruction of the xref section of damaged documents.
+//!
+//! The objects are found by scanning the whole input for `N G obj`. Later
+//! definitions of an object replace earlier ones, like in incremental
+//! updates.
+
+use std::collections::BTreeMap;
+
+use nom::{bytes, character};
+
+use super::{
+    error::CbParseError,
+    object::{dictionary_object, indirect_object, is_regular},
+    object_stream::object_stream,
+    Span,
+};
+use crate::{
+    logging,
+    pdf::{
+        document::{
+            dict_types::{CATALOG, OBJECT_STREAM, XREF},
+            K_TYPE,
+        },
+        trailer::{K_PREVIOUS, K_ROOT, K_SIZE, K_X_REF_STM, TRAILER},
+        xref::{UsedCompressedObject, UsedObject, XrefEntry},
+        Dictionary, Object, Reference, Trailer, Xref,
+    },
+};
+
+const OBJ: &[u8] = b"obj";
+
+/// Scan `input` for objects and build a single xref section for them. The
+/// trailer is the last `trailer` dictionary or xref stream dictionary, with
+/// the catalog that was found if its `/Root` is missing.
+///
+/// Returns `None` if no trailer can be built, e.g. without any catalog.
+pub(crate) fn rebuild_xref(input: Span) -> Option<(Xref, Trailer)> {
+    let bytes = input.fragment();
+    let mut entries = BTreeMap::<usize, XrefEntry>::new();
+    // The last trailer dictionary and its position.
+    let mut trailer: Option<(usize, Dictionary)> = None;
+    let mut catalog = None;
+
+    let mut position = 0;
+    while let Some(found) = find(&bytes[position..], OBJ) {
+        let keyword = position + found;
+        position = keyword + OBJ.len();
+        let Some(start) = object_start(bytes, keyword) else {
+            continue;
+        };
+        let Ok((obj_input, _)) = bytes::complete::take::<_, _, CbParseError<Span>>(start)(input) else {
+            break;
+        };
+        let Ok((remainder, obj)) = indirect_object(obj_input) else {
+            continue;
+        };
+        let Some(indirect) = obj.indirect() else {
+            continue;
+        };
+        // Skip the object, the data of streams may contain anything.
+        position = remainder.location_offset() - input.location_offset();
+        let number = indirect.index as usize;
+        entries.insert(
+            number,
+            UsedObject {
+                number,
+                byte_offset: start,
+                generation: indirect.generation as usize,
+            }
+            .into(),
+        );
+
+        let (dict, stream) = match indirect.object() {
+            Object::Dictionary(dict) => (dict, None),
+            Object::Stream(stream) => (&stream.dictionary, Some(stream)),
+            _ => continue,
+        };
+        match (dict.get(K_TYPE).and_then(Object::name).map(|name| &name[..]), stream) {
+            (Some(CATALOG), _) => catalog = Some(Reference::new(indirect.index, indirect.generation)),
+            (Some(XREF), Some(_)) => trailer = Some((start, dict.clone())),
+            (Some(OBJECT_STREAM), Some(stream)) => {
+                let Ok(objects) = object_stream(stream, input.extra.in_object_stream(number)) else {
+                    logging::warn!("Invalid object stream {} while rebuilding the xref section", number);
+                    continue;
+                };
+                for (index, (compressed, obj)) in objects.into_iter().enumerate() {
+                    let is_catalog = obj
+                        .dictionary()
+                        .and_then(|dict| dict.get(K_TYPE))
+                        .and_then(Object::name)
+                        .is_some_and(|name| &name[..] == CATALOG);
+                    if is_catalog {
+                        catalog = Some(Reference::new(compressed as u32, 0));
+                    }
+                    entries.insert(
+                        compressed,
+                        UsedCompressedObject {
+                            number: compressed,
+                            containing_object: number,
+                            index,
+                        }
+                        .into(),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut position = 0;
+    while let Some(found) = find(&bytes[position..], TRAILER) {
+        let keyword = position + found;
+        position = keyword + TRAILER.len();
+        let Ok((trailer_input, _)) = bytes::complete::take::<_, _, CbParseError<Span>>(position)(input) else {
+            break;
+        };
+        let parsed = character::complete::multispace0::<_, CbParseError<Span>>(trailer_input)
+            .and_then(|(remainder, _)| dictionary_object(remainder));
+        if let Ok((_, dict)) = parsed {
+            if trailer.as_ref().is_none_or(|(start, _)| *start < keyword) {
+                trailer = Some((keyword, dict));
+            }
+        }
+    }
+
+    let mut dict = trailer.map(|(_, dict)| dict).unwrap_or_default();
+    dict.remove(K_PREVIOUS);
+    dict.remove(K_X_REF_STM);
+    let size = entries.keys().next_back().map_or(0, |&last| last + 1);
+    dict.insert(K_SIZE.to_owned().into(), Object::Integer(i32::try_from(size).ok()?));
+    let has_root = dict
+        .get(K_ROOT)
+        .and_then(Object::reference)
+        .is_some_and(|root| entries.contains_key(&(root.index as usize)));
+    if !has_root {
+        dict.insert(K_ROOT.to_owned().into(), Object::Reference(catalog?));
+    }
+    let trailer = Trailer::try_from(dict).ok()?;
+
+    logging::debug!("Rebuilt xref section with {} objects", entries.len());
+    Some((Xref::from(entries.into_values().collect::<Vec<_>>()), trailer))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+/// The start of `N G obj` if the `obj` keyword at `keyword` is preceded by an
+/// object number and a generation.
+fn object_start(bytes: &[u8], keyword: usize) -> Option<usize> {
+    if bytes.get(keyword + OBJ.len()).is_some_and(|&c| is_regular(c)) {
+        return None;
+    }
+    let mut start = keyword;
+    let mut skip = |matches: fn(&u8) -> bool| {
+        let end = start;
+        while start > 0 && matches(&bytes[start - 1]) {
+            start -= 1;
+        }
+        end > start
+    };
+    let found = skip(u8::is_ascii_whitespace)
+        && skip(u8::is_ascii_digit)
+        && skip(u8::is_ascii_whitespace)
+        && skip(u8::is_ascii_digit);
+    let separated = start == 0 || !is_regular(bytes[start - 1]);
+    (found && separated).then_some(start)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::{
+        span,
+        tests::{push_xref_table, with_objects},
+    };
+
+    use super::*;
+
+    #[test]
+    fn rebuild() {
+        let (mut input, _) = with_objects([
+            &b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj\n"[..],
+            b"2 0 obj <</Type /Pages /Kids [] /Count 0>> endobj\n",
+            b"3 0 obj (old) endobj\n",
+            b"3 1 obj (new) endobj\n",
+        ]);
+        // The table misses the objects and the previous section doesn't exist.
+        push_xref_table(
+            &mut input,
+            &[(0, &[(0, 65535, false)])],
+            "<</Size 3 /Root 1 0 R /Prev 9999>>",
+        );
+        let (xref, trailer) = rebuild_xref(span(&input)).unwrap();
+        let objects: Vec<_> = xref
+            .used_objects()
+            .map(|o| (o.number, o.generation, o.byte_offset))
+            .collect();
+        assert_eq!(objects, vec![(1, 0, 9), (2, 0, 56), (3, 1, 127)]);
+        assert_eq!(trailer.size, 4);
+        assert_eq!(trailer.root, Reference::new(1, 0));
+        assert_eq!(trailer.previous, None);
+
+        // Without trailer, the catalog is the root.
+        let (_, trailer) = rebuild_xref(span(&input[..148])).unwrap();
+        assert_eq!(trailer.root, Reference::new(1, 0));
+        assert!(rebuild_xref(span(b"%PDF-1.7\n3 0 obj (no catalog) endobj")).is_none());
+    }
+}
@@ -1,10 +1,38 @@
-use nom::{bytes, character};
+use nom::{branch, bytes, character};
+#[cfg(feature = "trace")]
 use nom_tracable::tracable_parser;
 
-use super::{backward_search, error::CbParseError, object::dictionary_object, CbParseResult, Span};
-use crate::pdf::{trailer::TRAILER, Trailer};
+use super::{
+    backward_search,
+    error::CbParseError,
+    object::dictionary_object,
+    xref::{xref_section, xref_stream_and_dictionary},
+    CbParseResult, ParseWarning, Span,
+};
+use crate::{
+    logging,
+    pdf::{
+        trailer::{K_PREVIOUS, TRAILER},
+        DictExt, Dictionary, Trailer, Xref,
+    },
+};
 
-#[tracable_parser]
+/// Convert the trailer dictionary. Unless parsing is strict, an invalid
+/// `/Prev` is dropped with a warning, which ends the chain of sections.
+fn convert_trailer<'a>(input: Span<'a>, mut dict: Dictionary) -> Result<Trailer, nom::Err<CbParseError<Span<'a>>>> {
+    let valid = match dict.get_integer_opt(K_PREVIOUS) {
+        Ok(previous) => previous.is_none_or(|previous| usize::try_from(previous).is_ok()),
+        Err(_) => false,
+    };
+    if !valid && !input.extra.strict() {
+        logging::warn!("Invalid previous offset {:?}", dict.get(K_PREVIOUS));
+        input.extra.warn(ParseWarning::InvalidPrevious);
+        dict.remove(K_PREVIOUS);
+    }
+    Trailer::try_from(dict).map_err(|err| nom::Err::Failure(CbParseError::new(input, err.into())))
+}
+
+#[cfg_attr(feature = "trace", tracable_parser)]
 pub fn trailer_tail(input: Span) -> CbParseResult<Trailer> {
     // find `trailer` key word (start search from the end)
     let (remainder, (trailing, _)) = backward_search::<_, _, _, CbParseError<Span>>(
@@ -17,10 +45,34 @@ pub fn trailer_tail(input: Span) -> CbParseResult<Trailer> {
     let (trailing, trailer) = dictionary_object(trailing)?;
     let (trailing, _) = character::complete::multispace0(trailing)?;
     if trailing.len() > 0 {
-        log::warn!("Unexpected bytes after trailer: {:?}", trailing);
+        logging::warn!("Unexpected bytes after trailer: {:?}", trailing);
     }
 
-    let trailer = Trailer::try_from(trailer).map_err(|err| nom::Err::Failure(CbParseError::new(input, err.into())))?;
+    let trailer = convert_trailer(input, trailer)?;
 
     Ok((remainder, trailer))
 }
+
+/// Parse a xref table followed by the trailer dictionary.
+fn xref_table_and_trailer(input: Span) -> CbParseResult<(Xref, Dictionary)> {
+    let (remainder, xref) = xref_section(input)?;
+    let (remainder, _) = character::complete::multispace0(remainder)?;
+    let (remainder, _) = bytes::complete::tag_no_case(TRAILER)(remainder)?;
+    let (remainder, _) = character::complete::multispace0(remainder)?;
+    let (remainder, trailer) = dictionary_object(remainder)?;
+
+    Ok((remainder, (xref, trailer)))
+}
+
+/// Parse the xref section at the start of the input and the trailer that
+/// belongs to it.
+///
+/// The trailer of a xref table follows the table, xref streams contain the
+/// trailer entries in the stream dictionary.
+#[cfg_attr(feature = "trace", tracable_parser)]
+pub(crate) fn xref_and_trailer(input: Span) -> CbParseResult<(Xref, Trailer)> {
+    let (remainder, (xref, trailer)) = branch::alt((xref_table_and_trailer, xref_stream_and_dictionary))(input)?;
+    let trailer = convert_trailer(input, trailer)?;
+
+    Ok((remainder, (xref, trailer)))
+}
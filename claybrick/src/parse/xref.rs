@@ -1,45 +1,45 @@
 //! XRef Parsing.
 
 use nom::{branch, bytes, character, combinator, error::ParseError, multi, sequence, IResult};
+#[cfg(feature = "trace")]
 use nom_tracable::tracable_parser;
 
 use crate::{
+    logging,
     parse::{
         backward_search,
-        error::{CbParseError, CbParseErrorKind},
-        object, CbParseResult, Span,
+        error::{CbParseError, CbParseErrorKind, XrefError},
+        object, CbParseResult, ParseWarning, Span,
     },
-    pdf::xref::{
-        FreeObject, Unsupported, UsedCompressedObject, UsedObject, Xref, XrefEntry, XREF_COMPRESSED, XREF_FREE,
-        XREF_USED,
+    pdf::{
+        trailer::K_SIZE,
+        xref::{
+            FreeObject, Unsupported, UsedCompressedObject, UsedObject, Xref, XrefEntry, XREF_COMPRESSED, XREF_FREE,
+            XREF_USED,
+        },
+        Dictionary, Object,
     },
 };
 
 const EOF_MARKER: &[u8] = b"%%EOF";
+/// Bytes at the end of the input that [eof_marker_tail] searches for the
+/// marker. Some writers append text after it.
+pub const EOF_SEARCH_WINDOW: usize = 1024;
 const STARTXREF: &[u8] = b"startxref";
-
-/// Errors that occur while parsing the xref section.
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum XrefError {
-    /// The stream object wasn't the correct type.
-    StreamObject,
-
-    /// The W entry in the stream object dictionary was invalid.
-    WEntry,
-
-    /// There was an error in the content of the xref stream.
-    StreamContent,
-}
+const K_INDEX: &[u8] = b"Index";
 
 /// Find and returns the position of the xref table/stream by searching for
 /// `startxref <number>` from the end of the input and parsing the number that
 /// follows.
-#[tracable_parser]
+#[cfg_attr(feature = "trace", tracable_parser)]
 pub fn startxref_tail(input: Span) -> CbParseResult<usize> {
-    let (remainder, (trailing, _)) = backward_search::<_, _, _, CbParseError<Span>>(
-        STARTXREF.len() + 2048,
-        bytes::complete::tag_no_case(STARTXREF),
-    )(input)?;
+    startxref_within(input, STARTXREF.len() + 2048)
+}
+
+/// Like [startxref_tail], but searches the last `window` bytes.
+pub(crate) fn startxref_within(input: Span, window: usize) -> CbParseResult<usize> {
+    let (remainder, (trailing, _)) =
+        backward_search::<_, _, _, CbParseError<Span>>(window, bytes::complete::tag_no_case(STARTXREF))(input)?;
     let (trailing, _) = character::complete::multispace0(trailing)?;
     let (_, xref_pos) = character::complete::u64(trailing)?;
     let xref_pos: usize = xref_pos
@@ -53,20 +53,15 @@ pub fn startxref_tail(input: Span) -> CbParseResult<usize> {
 ///
 /// Retruns a vector of free objects or used objects that can be accessed by the
 /// byte offset.
-#[tracable_parser]
+#[cfg_attr(feature = "trace", tracable_parser)]
 fn xref_entries(input: Span) -> CbParseResult<Vec<XrefEntry>> {
     let (remainder, obj_index_offset) = character::complete::u32(input)?;
     let (remainder, _) = character::complete::multispace0(remainder)?;
     let (remainder, obj_count) = character::complete::u32(remainder)?;
     let (remainder, _) = character::complete::multispace0(remainder)?;
 
-    // FIXME: is it fine to just take a user defined value and request memory like
-    // that? Might be a way to crash software?
-    let mut entries = if let Ok(count) = obj_count.try_into() {
-        Vec::<XrefEntry>::with_capacity(count)
-    } else {
-        Vec::<XrefEntry>::new()
-    };
+    // Every entry takes more than one byte, don't trust large counts.
+    let mut entries = Vec::<XrefEntry>::with_capacity((obj_count as usize).min(remainder.len()));
 
     let mut remainder = remainder;
     for i in 0..obj_count {
@@ -80,30 +75,32 @@ fn xref_entries(input: Span) -> CbParseResult<Vec<XrefEntry>> {
         ))(inner_rmndr)?;
         let (inner_rmndr, _) = character::complete::multispace0(inner_rmndr)?;
 
+        let number = obj_index_offset as usize + i as usize;
+        let offset = usize::try_from(offset).map_err(|_| {
+            logging::error!("Offset {} of object {} is too large", offset, number);
+            nom::Err::Error(CbParseError::new(
+                remainder,
+                CbParseErrorKind::XrefInvalid(XrefError::TableEntry),
+            ))
+        })?;
         let entry = if free {
             XrefEntry::Free(FreeObject {
-                // FIXME: no unwrap!
-                number: (obj_index_offset + i).try_into().unwrap(),
-                // FIXME: no unwrap!
-                next_free: offset.try_into().unwrap(),
-                // FIXME: no unwrap!
-                generation: gen.try_into().unwrap(),
+                number,
+                next_free: offset,
+                generation: gen as usize,
             })
         } else {
             XrefEntry::Used(UsedObject {
-                // FIXME: no unwrap!
-                number: (obj_index_offset + i).try_into().unwrap(),
-                // FIXME: no unwrap!
-                byte_offset: offset.try_into().unwrap(),
-                // FIXME: no unwrap!
-                generation: gen.try_into().unwrap(),
+                number,
+                byte_offset: offset,
+                generation: gen as usize,
             })
         };
 
         entries.push(entry);
         remainder = inner_rmndr;
     }
-    log::debug!("Expected {} xef entries, got {}", obj_count, entries.len());
+    logging::debug!("Expected {} xef entries, got {}", obj_count, entries.len());
 
     Ok((remainder, entries))
 }
@@ -156,7 +153,16 @@ fn xref_stream_entry<'a, E: ParseError<Span<'a>>>(
 /// Each entry contains three integers (Type, x, y). The byte length of each
 /// integer is specified by the three w values.
 pub(crate) fn xref_stream_data(w: [usize; 3], input: Span) -> CbParseResult<Vec<XrefEntry>> {
+    // Wider fields would overflow while parsing and entries without any field
+    // would never end.
     let entry_len: usize = w.iter().sum();
+    if entry_len == 0 || w.iter().any(|&w| w > std::mem::size_of::<usize>()) {
+        logging::error!("Unsupported field widths {:?} in xref stream", w);
+        return Err(nom::Err::Error(CbParseError::new(
+            input,
+            CbParseErrorKind::XrefInvalid(XrefError::WEntry),
+        )));
+    }
     let mut entries = Vec::<XrefEntry>::with_capacity(input.len() / entry_len);
     let mut remainder = input;
     let mut entry_parser = xref_stream_entry(w);
@@ -203,18 +209,40 @@ pub(crate) fn xref_stream_data(w: [usize; 3], input: Span) -> CbParseResult<Vec<
 
 /// Parse an indirect object that contains a xref stream.
 pub(crate) fn xref_stream(input: Span) -> CbParseResult<Xref> {
+    combinator::map(xref_stream_and_dictionary, |(xref, _)| xref)(input)
+}
+
+/// Object numbers of the entries in a xref stream according to the `Index`
+/// entry. Defaults to `[0 Size]`.
+fn xref_stream_numbers(dict: &Dictionary) -> Option<Vec<(usize, usize)>> {
+    match dict.get(K_INDEX) {
+        Some(index) => {
+            let values = index
+                .array()?
+                .iter()
+                .map(|o| o.integer().and_then(|i| usize::try_from(i).ok()))
+                .collect::<Option<Vec<_>>>()?;
+            (values.len() % 2 == 0).then(|| values.chunks_exact(2).map(|r| (r[0], r[1])).collect())
+        }
+        None => Some(vec![(0, usize::try_from(dict.get(K_SIZE)?.integer()?).ok()?)]),
+    }
+}
+
+/// Parse an indirect object that contains a xref stream. Returns the stream
+/// dictionary which contains the trailer entries too.
+pub(crate) fn xref_stream_and_dictionary(input: Span) -> CbParseResult<(Xref, Dictionary)> {
     let (remainder, obj) = object::indirect_object(input)?;
 
     // get stream that is contained in the indirect object
     let indirect_obj = obj.indirect().ok_or_else(|| {
-        log::error!("startxref didn't point to an indirect object");
+        logging::error!("startxref didn't point to an indirect object");
         nom::Err::Error(CbParseError::new(
             input,
             CbParseErrorKind::XrefInvalid(XrefError::StreamObject),
         ))
     })?;
     let stream = indirect_obj.object.stream().ok_or_else(|| {
-        log::error!("indirect object didn't contain a stream");
+        logging::error!("indirect object didn't contain a stream");
         nom::Err::Error(CbParseError::new(
             input,
             CbParseErrorKind::XrefInvalid(XrefError::StreamObject),
@@ -222,18 +250,18 @@ pub(crate) fn xref_stream(input: Span) -> CbParseResult<Xref> {
     })?;
 
     // get the data that is contained in the stream
-    log::trace!("Xref stream: {:?}", stream);
+    logging::trace!("Xref stream: {:?}", stream);
     let data = stream
-        .filtered_data()
+        .filtered_data_with(input.extra.cancel())
         .map_err(|err| nom::Err::Error(CbParseError::new(input, CbParseErrorKind::StreamError(err))))?;
-    log::trace!("Parse Xref stream data");
+    logging::trace!("Parse Xref stream data");
 
     // get the W entry in from the stream dictionary
-    let w: [i32; 3] = stream
+    let w: [i64; 3] = stream
         .dictionary
         .get(&b"W"[..])
         .ok_or_else(|| {
-            log::error!("Missing W entry in xref stream dictionary");
+            logging::error!("Missing W entry in xref stream dictionary");
             nom::Err::Error(CbParseError::new(
                 input,
                 CbParseErrorKind::XrefInvalid(XrefError::WEntry),
@@ -241,17 +269,17 @@ pub(crate) fn xref_stream(input: Span) -> CbParseResult<Xref> {
         })?
         .array()
         .ok_or_else(|| {
-            log::error!("W entry didn't contain an array object");
+            logging::error!("W entry didn't contain an array object");
             nom::Err::Error(CbParseError::new(
                 input,
                 CbParseErrorKind::XrefInvalid(XrefError::WEntry),
             ))
         })?
         .iter()
-        .map(|o| o.integer())
-        .collect::<Option<Vec<i32>>>()
+        .map(Object::as_i64)
+        .collect::<Option<Vec<i64>>>()
         .ok_or_else(|| {
-            log::error!("Not all entries where integer objects");
+            logging::error!("Not all entries where integer objects");
             nom::Err::Error(CbParseError::new(
                 input,
                 CbParseErrorKind::XrefInvalid(XrefError::WEntry),
@@ -259,7 +287,7 @@ pub(crate) fn xref_stream(input: Span) -> CbParseResult<Xref> {
         })?
         .try_into()
         .map_err(|_| {
-            log::error!("W didn't contain exactly 3 entries.");
+            logging::error!("W didn't contain exactly 3 entries.");
             nom::Err::Error(CbParseError::new(
                 input,
                 CbParseErrorKind::XrefInvalid(XrefError::WEntry),
@@ -267,21 +295,21 @@ pub(crate) fn xref_stream(input: Span) -> CbParseResult<Xref> {
         })?;
     let w = [
         usize::try_from(w[0]).map_err(|e| {
-            log::error!("W[0] can't be converted to usize ({})", e);
+            logging::error!("W[0] can't be converted to usize ({})", e);
             nom::Err::Error(CbParseError::new(
                 input,
                 CbParseErrorKind::XrefInvalid(XrefError::WEntry),
             ))
         })?,
         usize::try_from(w[1]).map_err(|e| {
-            log::error!("W[1] can't be converted to usize ({})", e);
+            logging::error!("W[1] can't be converted to usize ({})", e);
             nom::Err::Error(CbParseError::new(
                 input,
                 CbParseErrorKind::XrefInvalid(XrefError::WEntry),
             ))
         })?,
         usize::try_from(w[2]).map_err(|e| {
-            log::error!("W[2] can't be converted to usize ({})", e);
+            logging::error!("W[2] can't be converted to usize ({})", e);
             nom::Err::Error(CbParseError::new(
                 input,
                 CbParseErrorKind::XrefInvalid(XrefError::WEntry),
@@ -289,43 +317,160 @@ pub(crate) fn xref_stream(input: Span) -> CbParseResult<Xref> {
         })?,
     ];
 
-    let (_empty, entries) = xref_stream_data(w, data[..].into()).map_err(|err| {
-        log::error!("Error while parsing xref stream content: {:?}", err);
+    let (_empty, mut entries) = xref_stream_data(w, data[..].into()).map_err(|err| {
+        logging::error!("Error while parsing xref stream content: {:?}", err);
         nom::Err::Error(CbParseError::new(
             input,
             CbParseErrorKind::XrefInvalid(XrefError::StreamContent),
         ))
     })?;
 
-    log::debug!("xref stream data parsed");
+    logging::debug!("xref stream data parsed");
+
+    let numbers = xref_stream_numbers(&stream.dictionary).ok_or_else(|| {
+        logging::error!("Invalid Index entry in xref stream dictionary");
+        nom::Err::Error(CbParseError::new(
+            input,
+            CbParseErrorKind::XrefInvalid(XrefError::IndexEntry),
+        ))
+    })?;
+    let declared = numbers
+        .iter()
+        .fold(0_usize, |sum, (_, count)| sum.saturating_add(*count));
+    if declared != entries.len() {
+        logging::warn!("Xref stream has {} entries, Index declares {}", entries.len(), declared);
+        if input.extra.strict() {
+            return Err(nom::Err::Error(CbParseError::new(
+                input,
+                CbParseErrorKind::XrefInvalid(XrefError::EntryCount),
+            )));
+        }
+        input.extra.warn(ParseWarning::XrefEntryCount {
+            declared,
+            actual: entries.len(),
+        });
+        // Extra entries are dropped, the objects without entry are missing.
+        entries.truncate(declared);
+    }
+    for (entry, number) in entries.iter_mut().zip(
+        numbers
+            .into_iter()
+            .flat_map(|(first, count)| first..first.saturating_add(count)),
+    ) {
+        entry.set_number(number);
+    }
 
     let xref = Xref::new_stream(entries, indirect_obj.index, indirect_obj.generation);
-    Ok((remainder, xref))
+    Ok((remainder, (xref, stream.dictionary.clone())))
 }
 
 /// Parse either a xref stream or xref table.
-#[tracable_parser]
+#[cfg_attr(feature = "trace", tracable_parser)]
 pub fn xref(input: Span) -> CbParseResult<Xref> {
     branch::alt((xref_section, combinator::into(xref_stream)))(input)
 }
 
 /// Parse the End-Of-File marker and removes it from the end of the input.
-#[tracable_parser]
+/// The marker is searched in the last [EOF_SEARCH_WINDOW] bytes.
+#[cfg_attr(feature = "trace", tracable_parser)]
 pub fn eof_marker_tail(input: Span) -> CbParseResult<()> {
-    // trailing bytes that follow the EOF marker are not possible since the limit we
-    // provided is the length of the EOF marker
-    let (remainder, _trailing) = backward_search::<_, _, _, CbParseError<Span>>(
-        EOF_MARKER.len() + 4,
-        bytes::complete::tag_no_case(EOF_MARKER),
-    )(input)?;
+    eof_marker_within(input, EOF_SEARCH_WINDOW)
+}
+
+/// Like [eof_marker_tail], but searches the last `window` bytes.
+pub(crate) fn eof_marker_within(input: Span, window: usize) -> CbParseResult<()> {
+    // the bytes that follow the marker are dropped
+    let (remainder, _trailing) =
+        backward_search::<_, _, _, CbParseError<Span>>(window, bytes::complete::tag_no_case(EOF_MARKER))(input)?;
 
     Ok((remainder, ()))
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::{
+        build::PdfBuilder,
+        error::CbError,
+        simple_encode::{EncodeOptions, SimpleEncoder},
+    };
+
     use super::*;
 
+    /// A document with a xref stream, written with `edit` applied to its
+    /// stream dictionary.
+    fn with_xref_stream(edit: impl Fn(&[u8]) -> Vec<u8>) -> Vec<u8> {
+        let mut builder = PdfBuilder::new();
+        builder.add_page(595.0, 842.0);
+        let mut out = Vec::new();
+        SimpleEncoder::write_with(&builder.finish().unwrap(), &mut out, EncodeOptions::default()).unwrap();
+        let start = out.windows(7).rposition(|w| w == b"<</Size").unwrap();
+        let end = start + out[start..].windows(2).position(|w| w == b">>").unwrap();
+        let dict = edit(&out[start..end]);
+        assert_eq!(dict.len(), end - start, "offsets must not change");
+        out.splice(start..end, dict);
+        out
+    }
+
+    fn replace(haystack: &[u8], from: &[u8], to: &[u8]) -> Vec<u8> {
+        let index = haystack.windows(from.len()).position(|w| w == from).unwrap();
+        [&haystack[..index], to, &haystack[index + from.len()..]].concat()
+    }
+
+    /// Strict, since otherwise the invalid xref section is rebuilt.
+    fn read_strict(input: Vec<u8>) -> Result<crate::pdf::RawPdf, CbError> {
+        let strict = crate::parse::ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+        crate::read_bytes_with(input, &strict)
+    }
+
+    fn xref_error(input: Vec<u8>) -> Option<XrefError> {
+        let Err(CbError::Parse(err)) = read_strict(input) else {
+            panic!("Expected a parse error");
+        };
+        let xref = err.chain().find_map(|err| match &err.kind {
+            CbParseErrorKind::XrefInvalid(xref) => Some(xref.clone()),
+            _ => None,
+        });
+        xref
+    }
+
+    #[test]
+    fn invalid_xref_stream() {
+        assert!(crate::read_bytes(with_xref_stream(<[u8]>::to_vec)).is_ok());
+
+        let input = with_xref_stream(|dict| replace(dict, b"/W [1 1 2]", b"/W [1 1]  "));
+        assert_eq!(xref_error(input), Some(XrefError::WEntry));
+        let input = with_xref_stream(|dict| replace(dict, b"/W [1 ", b"/W [-1"));
+        assert_eq!(xref_error(input.clone()), Some(XrefError::WEntry));
+        assert!(crate::read_bytes(input.clone()).is_ok(), "the section is rebuilt");
+        let Err(CbError::Parse(err)) = read_strict(input) else {
+            panic!("Expected a parse error");
+        };
+        assert!(
+            err.to_string().contains("invalid xref section: invalid W entry"),
+            "{}",
+            err
+        );
+
+        // One entry less than the stream contains, the last entry is dropped.
+        let input = with_xref_stream(|dict| replace(dict, b"/Index [0 5]", b"/Index [0 4]"));
+        assert_eq!(xref_error(input.clone()), Some(XrefError::EntryCount));
+        let (callback, warnings) = crate::parse::tests::collect_warnings();
+        let options = crate::parse::ParseOptions {
+            warnings: Some(callback),
+            ..Default::default()
+        };
+        let pdf = crate::read_bytes_with(input, &options).unwrap();
+        assert_eq!(
+            *warnings.lock().unwrap(),
+            [ParseWarning::XrefEntryCount { declared: 4, actual: 5 }]
+        );
+        assert_eq!(pdf.sections[0].xref.get(4), None);
+        assert!(pdf.sections[0].xref.get(3).is_some());
+    }
+
     #[test]
     fn test_startxref_tail() {
         let input = &b"         startxref\n2132"[..];
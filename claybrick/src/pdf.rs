@@ -1,53 +1,458 @@
-use fnv::FnvHashMap;
-use std::{collections::HashMap, ops::Deref};
+use fnv::{FnvHashMap, FnvHashSet};
+use std::{
+    ops::{Deref, Range, RangeBounds},
+    sync::Arc,
+};
+
+use self::{
+    document::{catalog::catalog_version, ext_g_state::resources_use_transparency, form::FormTraversal},
+    xref::{XrefEntry, MAX_GENERATION},
+};
 
 pub use self::{
-    document::{Catalog, CatalogError},
-    object::{Array, CbString, IndirectObject, Name, Object, Reference, Stream},
+    convert::{ConvertError, FromObject, ToObject},
+    date::{DateError, PdfDate},
+    document::{
+        AcroForm, Annotation, Catalog, CatalogError, ColorSpace, Destination, DocumentInfo, FieldValue, Layer,
+        NameTree, NumberTree, OptionalContent, Outline, OutlineItem, Page, PageIndex, PageLabel, PageLabelStyle, Pages,
+        PagesError, SignatureInfo, StructTree,
+    },
+    dump::DumpOptions,
+    font::CMap,
+    lazy::LazyPdf,
+    object::{
+        Array, CbString, DictExt, Dictionary, IndirectObject, IntegerOutOfRange, KeyError, Name, Object, Reference,
+        Stream, StreamDataProvenance,
+    },
+    permissions::Permissions,
+    rectangle::Rectangle,
+    redact::RedactError,
+    references::{PathElement, ReferenceMap, ReferenceSite},
+    source::ObjectSource,
+    stats::{PdfStats, XrefLayout},
+    text::TextDecodeError,
     trailer::Trailer,
+    validation::{Severity, ValidationIssue},
     xref::Xref,
 };
 
+#[cfg(feature = "encryption")]
+pub use self::security::DecryptError;
+#[cfg(feature = "encryption")]
+use self::security::StandardSecurityHandler;
+
+pub mod convert;
+pub mod date;
+#[cfg(feature = "serde")]
+pub mod deserialize;
 pub mod document;
+pub mod dump;
+pub mod font;
+pub mod lazy;
 pub mod object;
+pub mod permissions;
+pub mod rectangle;
+pub mod redact;
+pub mod references;
+#[cfg(feature = "encryption")]
+pub mod security;
+#[cfg(feature = "serde")]
+pub mod serialize;
+pub mod source;
+pub mod stats;
+pub(crate) mod text;
 pub mod trailer;
+pub mod validation;
 pub mod xref;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct RawPdf {
     pub(crate) version: (u8, u8),
-    pub(crate) announced_binary: bool,
+    /// The bytes of the comment after the header that marks the file as
+    /// binary, without `%`.
+    pub(crate) binary_indicator: Option<Vec<u8>>,
+    /// Bytes of junk before the `%PDF-` header of the parsed file.
+    pub(crate) header_offset: usize,
     pub(crate) sections: Vec<PdfSection>,
+    /// Numbers returned by [RawPdf::allocate_object_number]. The parsed xref
+    /// sections are not changed.
+    pub(crate) allocated: FnvHashSet<usize>,
+}
+
+/// The error of [RawPdf::allocate_object_number].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AllocationError {
+    /// The next object number or its generation doesn't fit into a xref
+    /// entry.
+    OutOfRange(usize),
+}
+
+/// The error of [RawPdf::extract_pages].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtractError {
+    /// The range doesn't select any page of a document with `page_count`
+    /// pages.
+    InvalidRange {
+        page_count: usize,
+    },
+    /// The document is encrypted and must be decrypted first.
+    Encrypted,
+    InvalidPages(CatalogError),
+    /// The number of pages is too large for the `/Count` of the page tree.
+    TooManyPages(usize),
 }
 
 impl RawPdf {
+    /// The version of the `%PDF-` header.
+    pub fn version(&self) -> (u8, u8) {
+        self.version
+    }
+
+    /// The version of the document, the newer one of the header and the
+    /// `/Version` of the catalog. Incremental updates raise the version with
+    /// the catalog entry.
+    pub fn effective_version(&self) -> (u8, u8) {
+        let catalog = self.trailer().and_then(|trailer| catalog_version(self, &trailer.root));
+        catalog.map_or(self.version, |catalog| catalog.max(self.version))
+    }
+
+    /// Decode a text string. Strings with the UTF-8 byte order mark are only
+    /// decoded as UTF-8 if the [effective version](RawPdf::effective_version)
+    /// is 2.0 or newer, older versions don't support UTF-8.
+    pub fn decode_text(&self, string: &CbString) -> Result<String, TextDecodeError> {
+        string.decode_text(self.effective_version())
+    }
+
+    /// Offset of the `%PDF-` header in the parsed file. Files can start with
+    /// junk, e.g. a HTTP header.
+    pub fn header_offset(&self) -> usize {
+        self.header_offset
+    }
+
+    /// Whether a comment after the header marks the file as binary.
+    pub fn announced_binary(&self) -> bool {
+        self.binary_indicator.is_some()
+    }
+
+    /// The bytes of the comment that marks the file as binary, without `%`.
+    /// Written again by [SimpleEncoder](crate::simple_encode::SimpleEncoder)
+    /// unless [EncodeOptions](crate::simple_encode::EncodeOptions) say
+    /// otherwise.
+    pub fn binary_indicator(&self) -> Option<&[u8]> {
+        self.binary_indicator.as_deref()
+    }
+
+    /// The trailer of the newest section. Documents with xref streams have
+    /// the trailer entries in the stream dictionary.
+    pub fn trailer(&self) -> Option<&Trailer> {
+        self.sections.first().map(|section| &section.trailer)
+    }
+
+    /// The file identifier of the most recent trailer that contains one. The
+    /// first identifier stays the same for all versions of the document, the
+    /// second one changes with each update.
+    pub fn document_id(&self) -> Option<(&Bytes, &Bytes)> {
+        let [id0, id1] = self.sections.iter().find_map(|s| s.trailer.id.as_ref())?;
+        Some((id0, id1))
+    }
+
+    /// The `/Size` of the newest trailer, one more than the highest object
+    /// number.
+    pub fn size(&self) -> Option<usize> {
+        self.trailer().map(|trailer| trailer.size)
+    }
+
+    /// The `/Prev` offsets of the trailers, the newest first. Each points to
+    /// the xref section of the previous version of the document.
+    pub fn previous_offsets(&self) -> Vec<usize> {
+        self.sections.iter().filter_map(|s| s.trailer.previous).collect()
+    }
+
     pub fn object(&self, num: usize) -> Option<&Object> {
         self.sections.iter().find_map(|s| s.objects.get(&num))
     }
 
-    pub fn catalog(&self) -> Result<Catalog, CatalogError> {
-        // TODO: enforce at-least-one-section assertion.
-        // TODO: enforce required-trailer assertion.
-        let root = &self
+    /// The newest xref entry of object `number` and the section that contains
+    /// it. Sections are searched from the newest to the oldest, thus an object
+    /// that was freed by an update is found as free entry.
+    pub fn lookup(&self, number: usize) -> Option<(&PdfSection, &XrefEntry)> {
+        self.sections
+            .iter()
+            .find_map(|section| section.xref.get(number).map(|entry| (section, entry)))
+    }
+
+    /// The sections of the document, the newest first. Each incremental
+    /// update adds a section.
+    pub fn sections(&self) -> &[PdfSection] {
+        &self.sections
+    }
+
+    /// All objects of the document as number, generation and object, sorted
+    /// by number. Objects that were replaced by a newer section are skipped,
+    /// like in [RawPdf::object], and so are objects that a newer section
+    /// frees.
+    pub fn objects(&self) -> impl Iterator<Item = (usize, u32, &Object)> {
+        let mut objects: Vec<_> = self
             .sections
-            .first()
-            .expect("FIXME: We always assert at least one section.")
-            .trailer
-            .root;
-        let catalog = self
-            .object(
-                root.index
-                    .try_into()
-                    .expect("FIXME: replace u32 in data model with usize"),
-            )
-            .unwrap()
-            .indirect()
-            .unwrap()
-            .object
-            .dictionary()
-            .unwrap();
+            .iter()
+            .enumerate()
+            .flat_map(|(index, section)| {
+                section
+                    .objects
+                    .iter()
+                    .filter(move |(&number, _)| !self.is_superseded(index, number))
+                    .map(|(&number, obj)| object_entry(number, obj))
+            })
+            .collect();
+        objects.sort_unstable_by_key(|(number, ..)| *number);
+        objects.into_iter()
+    }
+
+    /// Whether a section newer than the section `index` contains or frees the
+    /// object `number`.
+    fn is_superseded(&self, index: usize, number: usize) -> bool {
+        self.sections[..index]
+            .iter()
+            .any(|s| s.objects.contains_key(&number) || matches!(s.xref.get(number), Some(XrefEntry::Free(_))))
+    }
+
+    /// The objects of the section `index` like [RawPdf::objects], including
+    /// the ones that were replaced by newer sections. `None` if there is no
+    /// such section.
+    pub fn objects_in_section(&self, index: usize) -> Option<impl Iterator<Item = (usize, u32, &Object)>> {
+        Some(self.sections.get(index)?.objects())
+    }
+
+    /// Number of objects yielded by [RawPdf::objects].
+    pub fn object_count(&self) -> usize {
+        self.sections
+            .iter()
+            .enumerate()
+            .flat_map(|(index, s)| {
+                s.objects
+                    .keys()
+                    .filter(move |&&number| !self.is_superseded(index, number))
+            })
+            .count()
+    }
+
+    /// Allocate the number and generation for a new object.
+    ///
+    /// Numbers on the free lists of the sections are reused first, with the
+    /// generation of the free entry, which was incremented when the object
+    /// was freed. Otherwise the number after the highest one is used. Each
+    /// call returns a different number.
+    pub fn allocate_object_number(&mut self) -> Result<(u32, u16), AllocationError> {
+        let reusable = self
+            .sections
+            .iter()
+            .flat_map(|section| section.xref.free_list())
+            .filter(|free| free.generation < MAX_GENERATION && !self.allocated.contains(&free.number))
+            // Skip numbers that are used again in a newer section.
+            .find(|free| match self.lookup(free.number) {
+                Some((_, XrefEntry::Free(newest))) => std::ptr::eq(newest, *free),
+                _ => false,
+            })
+            .map(|free| (free.number, free.generation));
+        let (number, generation) = reusable.unwrap_or_else(|| {
+            let next = self
+                .sections
+                .iter()
+                .flat_map(|s| {
+                    let highest = s.xref.highest_index().map(|n| n + 1);
+                    s.objects.keys().map(|n| n + 1).chain(highest).chain([s.trailer.size])
+                })
+                .chain(self.allocated.iter().map(|n| n + 1))
+                .fold(1, usize::max);
+            (next, 0)
+        });
+
+        let out_of_range = |value| {
+            log::error!("Can't allocate object {}, {} is out of range", number, value);
+            AllocationError::OutOfRange(value)
+        };
+        let allocated = (
+            u32::try_from(number).map_err(|_| out_of_range(number))?,
+            u16::try_from(generation).map_err(|_| out_of_range(generation))?,
+        );
+        self.allocated.insert(number);
+        Ok(allocated)
+    }
+
+    /// Byte range of the newest version of the object in the parsed file, from
+    /// the object number to the end of `endobj`.
+    pub fn object_span(&self, num: usize) -> Option<Range<usize>> {
+        let section = self.sections.iter().find(|s| s.objects.contains_key(&num))?;
+        section.object_spans.get(&num).cloned()
+    }
+
+    /// Byte range of the data of the newest version of the stream object in
+    /// the parsed file.
+    pub fn stream_span(&self, num: usize) -> Option<Range<usize>> {
+        let section = self.sections.iter().find(|s| s.objects.contains_key(&num))?;
+        section.stream_spans.get(&num).cloned()
+    }
+
+    pub fn catalog(&self) -> Result<Catalog<'_>, CatalogError> {
+        catalog(self)
+    }
 
-        Catalog::new_with(self, catalog)
+    /// Whether the document is encrypted. Stays `true` until
+    /// [RawPdf::decrypt] succeeds.
+    pub fn is_encrypted(&self) -> bool {
+        self.sections.iter().any(|s| s.trailer.encrypt.is_some())
+    }
+
+    /// The encryption dictionary of the most recent trailer that contains
+    /// one.
+    pub(crate) fn encrypt_dict(&self) -> Option<&Dictionary> {
+        let encrypt = self.sections.iter().find_map(|s| s.trailer.encrypt.as_ref())?;
+        self.resolve(encrypt).dictionary()
+    }
+
+    /// The access permissions of an encrypted document.
+    pub fn permissions(&self) -> Option<Permissions> {
+        Permissions::from_encrypt_dict(self.encrypt_dict()?)
+    }
+
+    /// Decrypt all strings and streams using the standard security handler.
+    /// The objects of object streams are added after decryption.
+    ///
+    /// The password can be the user or the owner password, most documents use
+    /// an empty user password. Strings are replaced with hex strings
+    /// containing the plain text.
+    #[cfg(feature = "encryption")]
+    pub fn decrypt(&mut self, password: &[u8]) -> Result<(), DecryptError> {
+        let encrypt = self.encrypt_dict().ok_or(DecryptError::NotEncrypted)?;
+        let id = self.sections.iter().find_map(|s| s.trailer.id.as_ref());
+        let handler = StandardSecurityHandler::new_with(encrypt, id.map(|[id0, _]| &id0[..]))?;
+        let key = handler.authenticate(password)?;
+
+        // The encryption dictionary itself is not encrypted.
+        let encrypt_refs = self
+            .sections
+            .iter()
+            .filter_map(|s| s.trailer.encrypt.as_ref().and_then(Object::reference))
+            .map(|r| r.index)
+            .collect::<Vec<_>>();
+        for section in &mut self.sections {
+            for obj in section.objects.values_mut() {
+                match obj {
+                    Object::Indirect(io) if !encrypt_refs.contains(&io.index) => handler.decrypt_object(&key, io),
+                    _ => {}
+                }
+            }
+            section.trailer.encrypt = None;
+            // Object streams are ciphertext while parsing, their objects can
+            // only be added now.
+            let mut seen = FnvHashSet::default();
+            let streams: Vec<usize> = section
+                .xref
+                .compressed_objects()
+                .map(|obj_xref| obj_xref.containing_object)
+                .filter(|&number| seen.insert(number))
+                .collect();
+            for number in streams {
+                let stream = section
+                    .objects
+                    .get(&number)
+                    .and_then(Object::indirect)
+                    .and_then(|indirect| indirect.object.stream());
+                let Some(stream) = stream else {
+                    log::error!("Object stream {} is missing", number);
+                    continue;
+                };
+                match crate::parse::object_stream::object_stream(
+                    stream,
+                    crate::parse::ParseContext::default().in_object_stream(number),
+                ) {
+                    Ok(objects) => section.objects.extend(objects),
+                    Err(err) => log::error!("Invalid object stream {}: {:?}", number, err),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The document information dictionary.
+    ///
+    /// Uses the `Info` entry of the most recent trailer that contains one.
+    pub fn info(&self) -> Option<DocumentInfo<'_>> {
+        let info = self.sections.iter().find_map(|s| s.trailer.info.as_ref())?;
+        let dict = self.lookup_reference(info)?.dictionary()?;
+        Some(DocumentInfo::new_with(self, dict))
+    }
+
+    /// Create a new document that only contains the pages with the
+    /// zero-based indices in `range`.
+    ///
+    /// Everything the pages reference is copied, objects shared by several
+    /// pages only once. Attributes the pages inherit from the page tree are
+    /// copied to the page dictionaries. References to pages that are not
+    /// extracted are replaced with null.
+    pub fn extract_pages(&self, range: impl RangeBounds<usize>) -> Result<RawPdf, ExtractError> {
+        crate::build::extract_pages(self, range)
+    }
+
+    /// Number of pages in the document.
+    pub fn page_count(&self) -> Result<usize, CatalogError> {
+        self.catalog()?.page_count()
+    }
+
+    /// The page with the given zero-based index or `None` if the index is out
+    /// of range.
+    pub fn page(&self, index: usize) -> Option<Page<'_>> {
+        self.catalog().ok()?.page(index)
+    }
+
+    /// Resolve an explicit or named destination to the index of the target
+    /// page and the view of the page.
+    pub fn resolve_destination(&self, dest: &Object) -> Option<(PageIndex, Destination)> {
+        self.catalog().ok()?.resolve_destination(dest)
+    }
+
+    /// The interactive form of the document.
+    pub fn acro_form(&self) -> Option<AcroForm<'_>> {
+        self.catalog().ok()?.acro_form()
+    }
+
+    /// The signed signature fields of the document.
+    pub fn signatures(&self) -> Vec<SignatureInfo> {
+        self.acro_form().map(|form| form.signatures()).unwrap_or_default()
+    }
+
+    /// The optional content groups (layers) of the document.
+    pub fn layers(&self) -> Vec<Layer> {
+        self.catalog().map(|c| c.layers()).unwrap_or_default()
+    }
+
+    /// Whether the pages or the forms they paint use transparency: graphics
+    /// states with an alpha below 1, a soft mask or a blend mode other than
+    /// `Normal`, or images with a soft mask.
+    pub fn uses_transparency(&self) -> bool {
+        let Some(pages) = self.catalog().ok().and_then(|catalog| catalog.pages().ok()) else {
+            return false;
+        };
+        let mut forms = FormTraversal::default();
+        pages.iter().filter_map(Result::ok).any(|page| {
+            page.resources()
+                .is_some_and(|resources| resources_use_transparency(self, resources, &mut forms))
+        })
+    }
+
+    /// Whether the document is a tagged PDF with a structure tree.
+    pub fn is_tagged(&self) -> bool {
+        self.catalog().is_ok_and(|c| c.is_tagged())
+    }
+
+    /// The structure tree of a tagged document.
+    pub fn struct_tree(&self) -> Option<StructTree<'_>> {
+        self.catalog().ok()?.struct_tree()
+    }
+
+    /// The label of the page with the given zero-based index, e.g. `iv`.
+    pub fn page_label(&self, index: usize) -> Option<PageLabel> {
+        self.catalog().ok()?.page_label(index)
     }
 
     pub fn dereference(&self, reference: &Reference) -> Option<&Object> {
@@ -59,6 +464,113 @@ impl RawPdf {
                 .map(|io| &*io.object)
         })
     }
+
+    /// Like [RawPdf::dereference] but also finds objects from object streams,
+    /// which are stored without the indirect object wrapper and always have
+    /// generation 0.
+    pub(crate) fn lookup_reference(&self, reference: &Reference) -> Option<&Object> {
+        let number = usize::try_from(reference.index).ok()?;
+        match self.object(number)? {
+            Object::Indirect(indirect) if indirect.generation == reference.generation => Some(&*indirect.object),
+            Object::Indirect(_) => None,
+            direct if reference.generation == 0 => Some(direct),
+            _ => None,
+        }
+    }
+
+    /// Check the consistency between objects, e.g. of the page tree, stream
+    /// lengths and references. Issues the parser already rejects are not
+    /// reported.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        validation::validate(self)
+    }
+
+    /// Write an overview of the objects for debugging: their number,
+    /// generation, type, `/Type` and `/Subtype`, and the references they
+    /// contain. See [DumpOptions] for a tree starting at the trailer instead.
+    pub fn dump(&self, w: &mut impl std::fmt::Write, options: DumpOptions) -> std::fmt::Result {
+        dump::dump(self, w, options)
+    }
+
+    /// Counts of the objects, filters and sections of the document.
+    pub fn stats(&self) -> PdfStats {
+        stats::stats(self)
+    }
+
+    /// Replace object `number` with null and remove its previous versions
+    /// from all sections. References to the object are replaced with null.
+    ///
+    /// Write the document with
+    /// [SimpleEncoder](crate::simple_encode::SimpleEncoder) rather than as
+    /// an incremental update, so that the old content of the object isn't
+    /// copied from the parsed file. The catalog and the root of
+    /// the page tree can't be redacted.
+    pub fn redact_object(&mut self, number: usize) -> Result<(), RedactError> {
+        redact::redact_object(self, number)
+    }
+
+    /// Parse a color space, references are resolved in this document.
+    pub fn color_space<'a>(&'a self, object: &'a Object) -> ColorSpace<'a> {
+        ColorSpace::new_with(self, object)
+    }
+
+    /// Index of which objects reference which other objects.
+    pub fn reference_map(&self) -> ReferenceMap {
+        ReferenceMap::new_with(self)
+    }
+
+    /// Follow references until a direct object is reached.
+    ///
+    /// References to missing objects and reference cycles resolve to the null
+    /// object.
+    pub fn resolve<'a>(&'a self, obj: &'a Object) -> &'a Object {
+        ObjectSource::resolve(self, obj)
+    }
+
+    /// Get the value for `key` and resolve it if it's a reference.
+    ///
+    /// Returns `None` if the key is missing or the value resolves to null.
+    pub fn dict_get_resolved<'a>(&'a self, dict: &'a Dictionary, key: &[u8]) -> Option<&'a Object> {
+        ObjectSource::dict_get_resolved(self, dict, key)
+    }
+}
+
+impl ObjectSource for RawPdf {
+    fn trailer(&self) -> Option<&Trailer> {
+        RawPdf::trailer(self)
+    }
+
+    /// Like [RawPdf::dereference] but also finds objects from object
+    /// streams.
+    fn dereference(&self, reference: &Reference) -> Option<&Object> {
+        self.lookup_reference(reference)
+    }
+
+    fn effective_version(&self) -> (u8, u8) {
+        RawPdf::effective_version(self)
+    }
+}
+
+fn catalog(source: &dyn ObjectSource) -> Result<Catalog<'_>, CatalogError> {
+    let root = source.trailer().ok_or(CatalogError::MissingTrailer)?.root;
+    // Roots from object streams are dictionaries without the indirect object
+    // wrapper, `dereference` returns both kinds.
+    let catalog = match source.dereference(&root) {
+        Some(Object::Dictionary(catalog)) => catalog,
+        Some(Object::Null) => return Err(CatalogError::MissingRoot),
+        Some(_) => return Err(CatalogError::RootNotDictionary),
+        None => return Err(CatalogError::RootNotFound(root)),
+    };
+
+    Catalog::new_with(source, catalog)
+}
+
+/// Split the indirect object wrapper off `obj`.
+fn object_entry(number: usize, obj: &Object) -> (usize, u32, &Object) {
+    match obj {
+        Object::Indirect(indirect) => (number, indirect.generation, &*indirect.object),
+        direct => (number, 0, direct),
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -67,42 +579,539 @@ pub struct PdfSection {
     pub(crate) objects: FnvHashMap<usize, Object>,
     pub(crate) trailer: Trailer,
     pub(crate) xref: Xref,
+    /// Byte range of the uncompressed objects in the parsed file, from the
+    /// object number to the end of `endobj`.
+    pub(crate) object_spans: FnvHashMap<usize, Range<usize>>,
+    /// Byte range of the stream data of the uncompressed stream objects.
+    pub(crate) stream_spans: FnvHashMap<usize, Range<usize>>,
+}
+
+impl PdfSection {
+    /// The objects of this section as number, generation and object, sorted
+    /// by number. Objects from object streams have generation 0.
+    pub fn objects(&self) -> impl Iterator<Item = (usize, u32, &Object)> {
+        let mut objects: Vec<_> = self
+            .objects
+            .iter()
+            .map(|(&number, obj)| object_entry(number, obj))
+            .collect();
+        objects.sort_unstable_by_key(|(number, ..)| *number);
+        objects.into_iter()
+    }
+
+    /// The object `number` of this section, like [RawPdf::object].
+    pub fn get(&self, number: usize) -> Option<&Object> {
+        self.objects.get(&number)
+    }
+
+    /// Whether this section contains the object `number`.
+    pub fn contains(&self, number: usize) -> bool {
+        self.objects.contains_key(&number)
+    }
+
+    /// Number of objects in this section.
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    pub fn trailer(&self) -> &Trailer {
+        &self.trailer
+    }
+
+    pub fn xref(&self) -> &Xref {
+        &self.xref
+    }
+
+    /// Byte ranges of the objects of this section in the parsed file. Empty
+    /// if the section wasn't parsed from a file. Compressed objects are
+    /// missing since they are part of an object stream.
+    pub fn object_spans(&self) -> &FnvHashMap<usize, Range<usize>> {
+        &self.object_spans
+    }
+
+    /// Byte ranges of the stream data of the stream objects of this section.
+    /// Also recorded for streams with an invalid `Length`.
+    pub fn stream_spans(&self) -> &FnvHashMap<usize, Range<usize>> {
+        &self.stream_spans
+    }
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
-pub struct Bytes(pub Vec<u8>);
+/// Immutable bytes with copy-on-write semantics.
+///
+/// The bytes can be a range of a larger buffer that is shared with other
+/// `Bytes`, e.g. stream data shares the buffer of the parsed file. Cloning
+/// doesn't copy the bytes.
+#[derive(Clone)]
+pub struct Bytes {
+    buffer: Buffer,
+    /// The used part of the buffer, `None` for the complete buffer.
+    range: Option<Range<usize>>,
+}
+
+#[derive(Clone)]
+enum Buffer {
+    Static(&'static [u8]),
+    Shared(Arc<Vec<u8>>),
+}
+
+impl Deref for Buffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Buffer::Static(bytes) => bytes,
+            Buffer::Shared(bytes) => bytes,
+        }
+    }
+}
 
 impl std::fmt::Debug for Bytes {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_tuple("Bytes").field(&hex::encode(&self.0[..])).finish()
+        f.debug_tuple("Bytes").field(&hex::encode(&self[..])).finish()
     }
 }
 
 impl From<Vec<u8>> for Bytes {
     fn from(v: Vec<u8>) -> Self {
-        Bytes(v)
+        Self {
+            buffer: Buffer::Shared(Arc::new(v)),
+            range: None,
+        }
+    }
+}
+
+impl Bytes {
+    /// Bytes that borrow `bytes` without copying them.
+    pub const fn from_static(bytes: &'static [u8]) -> Self {
+        Self {
+            buffer: Buffer::Static(bytes),
+            range: None,
+        }
+    }
+
+    /// The bytes in `range` of the shared `buffer`.
+    pub(crate) fn shared(buffer: &Arc<Vec<u8>>, range: Range<usize>) -> Self {
+        assert!(range.end <= buffer.len(), "range beyond the end of the buffer");
+        Self {
+            buffer: Buffer::Shared(buffer.clone()),
+            range: Some(range),
+        }
+    }
+
+    /// Mutable access to the bytes. They are copied first unless these
+    /// `Bytes` are the only owner of the complete buffer.
+    pub fn to_mut(&mut self) -> &mut Vec<u8> {
+        let unique = match &mut self.buffer {
+            Buffer::Shared(buffer) => self.range.is_none() && Arc::get_mut(buffer).is_some(),
+            Buffer::Static(_) => false,
+        };
+        if !unique {
+            *self = self.to_vec().into();
+        }
+        match &mut self.buffer {
+            Buffer::Shared(buffer) => Arc::get_mut(buffer).expect("the buffer is not shared"),
+            Buffer::Static(_) => unreachable!("static bytes are copied"),
+        }
+    }
+
+    /// The bytes as vector, without copying them if possible.
+    pub fn into_vec(self) -> Vec<u8> {
+        match (self.buffer, self.range) {
+            (Buffer::Shared(buffer), None) => Arc::try_unwrap(buffer).unwrap_or_else(|buffer| buffer.to_vec()),
+            (buffer, None) => buffer.to_vec(),
+            (buffer, Some(range)) => buffer[range].to_vec(),
+        }
+    }
+
+    /// Decode the bytes as text string. See [CbString::decode_text].
+    pub fn decode_text(&self, version: (u8, u8)) -> Result<String, TextDecodeError> {
+        text::decode_text(self, version)
+    }
+
+    /// Decode the bytes as text string, replacing invalid characters with
+    /// U+FFFD. Like [CbString::to_string_lossy], strings starting with
+    /// `EF BB BF` are always decoded as UTF-8.
+    pub fn to_string_lossy(&self) -> String {
+        text::decode_text_lossy(self, text::UTF8_VERSION)
     }
 }
 
 impl Deref for Bytes {
-    type Target = Vec<u8>;
+    type Target = [u8];
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        match &self.range {
+            Some(range) => &self.buffer[range.clone()],
+            None => &self.buffer,
+        }
+    }
+}
+
+impl PartialEq for Bytes {
+    fn eq(&self, other: &Self) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl Eq for Bytes {}
+
+impl std::hash::Hash for Bytes {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self[..].hash(state)
     }
 }
 
 impl std::fmt::Display for Bytes {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let limited_length = self.len().min(15);
-        write!(f, "{}", &String::from_utf8_lossy(&self.0[..limited_length]))
+        write!(f, "{}", &String::from_utf8_lossy(&self[..limited_length]))
     }
 }
 
 impl std::borrow::Borrow<[u8]> for Bytes {
     fn borrow(&self) -> &[u8] {
-        &self.0[..]
+        self
     }
 }
 
-pub type Dictionary = HashMap<Name, Object>;
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Create a single section PDF that contains the supplied objects.
+    pub(crate) fn raw_pdf(root: Reference, objects: Vec<IndirectObject>) -> RawPdf {
+        let objects = objects
+            .into_iter()
+            .map(|obj| (obj.index as usize, Object::Indirect(obj)))
+            .collect();
+        RawPdf {
+            version: (1, 7),
+            binary_indicator: None,
+            header_offset: 0,
+            sections: vec![PdfSection {
+                objects,
+                trailer: Trailer {
+                    size: 0,
+                    previous: None,
+                    root,
+                    encrypt: None,
+                    info: None,
+                    id: None,
+                    x_ref_stm: None,
+                },
+                xref: Xref::new(Vec::new()),
+                object_spans: Default::default(),
+                stream_spans: Default::default(),
+            }],
+            allocated: Default::default(),
+        }
+    }
+
+    /// Like [raw_pdf], but the objects are parsed from their bytes, e.g.
+    /// `1 0 obj <</Type /Catalog>> endobj`.
+    pub(crate) fn parsed_pdf(root: Reference, objects: &[&[u8]]) -> RawPdf {
+        let objects = objects
+            .iter()
+            .map(|o| {
+                crate::parse::object::object(o[..].into())
+                    .unwrap()
+                    .1
+                    .indirect()
+                    .unwrap()
+                    .clone()
+            })
+            .collect();
+        raw_pdf(root, objects)
+    }
+
+    #[test]
+    fn effective_version() {
+        let catalog = |catalog: &[u8]| {
+            let mut pdf = parsed_pdf(Reference::new(1, 0), &[catalog]);
+            pdf.version = (1, 4);
+            pdf
+        };
+        let pdf = catalog(b"1 0 obj <</Type /Catalog>> endobj");
+        assert_eq!((pdf.version(), pdf.effective_version()), ((1, 4), (1, 4)));
+        let pdf = catalog(b"1 0 obj <</Type /Catalog /Version /1.6>> endobj");
+        assert_eq!((pdf.version(), pdf.effective_version()), ((1, 4), (1, 6)));
+        // An older catalog entry doesn't lower the version.
+        let pdf = catalog(b"1 0 obj <</Type /Catalog /Version /1.3>> endobj");
+        assert_eq!(pdf.effective_version(), (1, 4));
+
+        // UTF-8 strings need PDF 2.0.
+        let utf8 = CbString::from(b"\xEF\xBB\xBF\xC3\xA9".to_vec());
+        let converted = |pdf: &RawPdf| String::from_object(&Object::String(utf8.clone()), Some(pdf)).unwrap();
+        assert_eq!(pdf.decode_text(&utf8).unwrap(), "\u{EF}\u{BB}\u{BF}\u{C3}\u{A9}");
+        assert_eq!(converted(&pdf), "\u{EF}\u{BB}\u{BF}\u{C3}\u{A9}");
+        let pdf = catalog(b"1 0 obj <</Type /Catalog /Version /2.0>> endobj");
+        assert_eq!(pdf.effective_version(), (2, 0));
+        assert_eq!(pdf.decode_text(&utf8).unwrap(), "é");
+        assert_eq!(converted(&pdf), "é");
+    }
+
+    #[test]
+    fn trailer() {
+        let mut pdf = raw_pdf(Reference::new(1, 0), Vec::new());
+        assert_eq!(pdf.trailer().map(|trailer| trailer.root), Some(Reference::new(1, 0)));
+        assert_eq!(pdf.size(), Some(0));
+        assert_eq!(pdf.document_id(), None);
+        assert_eq!(pdf.previous_offsets(), Vec::<usize>::new());
+        assert_eq!(
+            pdf.catalog().err(),
+            Some(CatalogError::RootNotFound(Reference::new(1, 0)))
+        );
+
+        let id = |id: &[u8]| Bytes::from(id.to_vec());
+        pdf.sections[0].trailer.id = Some([id(b"first"), id(b"first")]);
+        // Two updates, only the older one has a new ID.
+        for (size, previous, new_id) in [(5, 100, Some(id(b"second"))), (7, 200, None)] {
+            let mut update = pdf.sections[0].clone();
+            update.trailer.size = size;
+            update.trailer.previous = Some(previous);
+            update.trailer.id = new_id.map(|new_id| [id(b"first"), new_id]);
+            pdf.sections.insert(0, update);
+        }
+        assert_eq!(pdf.size(), Some(7));
+        assert_eq!(pdf.previous_offsets(), vec![200, 100]);
+        assert_eq!(pdf.document_id(), Some((&id(b"first"), &id(b"second"))));
+
+        pdf.sections.clear();
+        assert_eq!(pdf.trailer(), None);
+        assert_eq!(pdf.size(), None);
+        assert_eq!(pdf.catalog().err(), Some(CatalogError::MissingTrailer));
+    }
+
+    #[test]
+    fn catalog_errors() {
+        let pdf = parsed_pdf;
+        let catalog = |pdf: RawPdf| pdf.catalog().err();
+
+        let objects: &[&[u8]] = &[
+            b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj",
+            b"2 0 obj <</Type /Pages /Kids [] /Count 0>> endobj",
+            b"3 0 obj null endobj",
+            b"4 0 obj [/Catalog] endobj",
+        ];
+        assert_eq!(catalog(pdf(Reference::new(1, 0), objects)), None);
+        assert_eq!(
+            catalog(pdf(Reference::new(3, 0), objects)),
+            Some(CatalogError::MissingRoot)
+        );
+        assert_eq!(
+            catalog(pdf(Reference::new(4, 0), objects)),
+            Some(CatalogError::RootNotDictionary)
+        );
+        // The generation has to match.
+        assert_eq!(
+            catalog(pdf(Reference::new(1, 1), objects)),
+            Some(CatalogError::RootNotFound(Reference::new(1, 1)))
+        );
+        assert_eq!(
+            catalog(pdf(Reference::new(2, 0), objects)),
+            Some(CatalogError::MissingPages)
+        );
+        assert_eq!(
+            catalog(RawPdf {
+                sections: Vec::new(),
+                ..pdf(Reference::new(1, 0), objects)
+            }),
+            Some(CatalogError::MissingTrailer)
+        );
+        assert_eq!(
+            CatalogError::RootNotFound(Reference::new(1, 1)).to_string(),
+            "the root 1 1 R doesn't exist"
+        );
+    }
+
+    #[test]
+    fn catalog_in_object_stream() {
+        let mut builder = crate::build::PdfBuilder::new();
+        builder.add_page(612.0, 792.0);
+        let options = crate::simple_encode::EncodeOptions {
+            use_object_streams: true,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        crate::simple_encode::SimpleEncoder::write_with(&builder.finish().unwrap(), &mut out, options).unwrap();
+
+        let pdf = crate::read_bytes(out).unwrap();
+        let root = pdf.trailer().unwrap().root;
+        assert!(
+            matches!(pdf.object(root.index as usize), Some(Object::Dictionary(_))),
+            "the catalog is stored without the indirect object wrapper"
+        );
+        assert_eq!(pdf.catalog().unwrap().page_count(), Ok(1));
+    }
+
+    #[test]
+    fn lookup_respects_updates() {
+        use xref::{FreeObject, UsedObject};
+
+        let used = |number, byte_offset| {
+            XrefEntry::from(UsedObject {
+                number,
+                byte_offset,
+                generation: 0,
+            })
+        };
+        let mut pdf = raw_pdf(Reference::new(1, 0), Vec::new());
+        pdf.sections[0].xref = Xref::new(vec![used(1, 10), used(2, 20), used(3, 30)]);
+        // The update changes object 1 and frees object 2.
+        let mut update = pdf.sections[0].clone();
+        update.xref = Xref::new(vec![
+            used(1, 100),
+            FreeObject {
+                number: 2,
+                generation: 1,
+                next_free: 0,
+            }
+            .into(),
+        ]);
+        pdf.sections.insert(0, update);
+
+        let (section, entry) = pdf.lookup(1).unwrap();
+        assert!(std::ptr::eq(section, &pdf.sections[0]));
+        assert_eq!(entry, &used(1, 100));
+        let (_, entry) = pdf.lookup(2).unwrap();
+        assert!(matches!(entry, XrefEntry::Free(FreeObject { generation: 1, .. })));
+        let (section, entry) = pdf.lookup(3).unwrap();
+        assert!(std::ptr::eq(section, &pdf.sections[1]));
+        assert_eq!(entry, &used(3, 30));
+        assert!(pdf.lookup(4).is_none());
+    }
+
+    #[test]
+    fn objects_newest_wins() {
+        let pdf = |objects: &[&[u8]]| parsed_pdf(Reference::new(1, 0), objects);
+        let mut doc = pdf(&[b"1 0 obj <</Type /Catalog>> endobj", b"2 0 obj (old) endobj"]);
+        let update = pdf(&[b"2 1 obj (new) endobj", b"3 0 obj 42 endobj"]);
+        doc.sections.insert(0, update.sections[0].clone());
+        // An object from an object stream.
+        doc.sections[0].objects.insert(4, Object::Null);
+        // An object that the update frees.
+        doc.sections[1].objects.insert(5, Object::Null);
+        doc.sections[0].xref = Xref::new(vec![xref::FreeObject {
+            number: 5,
+            generation: 1,
+            next_free: 0,
+        }
+        .into()]);
+
+        let objects: Vec<_> = doc
+            .objects()
+            .map(|(number, generation, obj)| (number, generation, obj.clone()))
+            .collect();
+        assert_eq!(
+            objects,
+            vec![
+                (1, 0, doc.object(1).unwrap().indirect().unwrap().object().clone()),
+                (2, 1, Object::String(b"new".to_vec().into())),
+                (3, 0, Object::Integer(42)),
+                (4, 0, Object::Null),
+            ]
+        );
+        assert_eq!(doc.object_count(), 4);
+        assert_eq!(doc.sections().len(), 2);
+        assert_eq!(doc.sections()[1].len(), 3);
+        assert!(doc.sections()[0].contains(2));
+        assert!(!doc.sections()[0].contains(1));
+        assert_eq!(doc.sections()[0].get(3), doc.object(3));
+        let old: Vec<_> = doc
+            .objects_in_section(1)
+            .unwrap()
+            .map(|(number, generation, _)| (number, generation))
+            .collect();
+        assert_eq!(old, vec![(1, 0), (2, 0), (5, 0)]);
+        assert!(doc.objects_in_section(2).is_none());
+    }
+
+    #[test]
+    fn bytes_copy_on_write() {
+        let buffer = Arc::new(b"shared buffer".to_vec());
+        let mut bytes = Bytes::shared(&buffer, 0..6);
+        let clone = bytes.clone();
+        assert_eq!(clone.as_ptr(), bytes.as_ptr());
+
+        bytes.to_mut().extend_from_slice(b"!");
+        assert_eq!(&bytes[..], b"shared!");
+        assert_eq!(&clone[..], b"shared");
+        assert_eq!(&buffer[..], b"shared buffer");
+
+        // The only owner of the whole buffer is modified in place.
+        let ptr = bytes.as_ptr();
+        bytes.to_mut().push(b'!');
+        assert_eq!(bytes.as_ptr(), ptr);
+        let vec = bytes.into_vec();
+        assert_eq!(vec.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn bytes_from_static() {
+        static DATA: &[u8] = b"static";
+        let bytes = Bytes::from_static(DATA);
+        assert_eq!(bytes.as_ptr(), DATA.as_ptr());
+        assert_eq!(bytes, Bytes::from(b"static".to_vec()));
+        assert_eq!(Name::from_static(b"Type"), Name::new(b"Type".to_vec()));
+    }
+
+    #[test]
+    fn resolve_reference_chain() {
+        let pdf = raw_pdf(
+            Reference::new(1, 0),
+            vec![
+                IndirectObject::new(1, 0, Object::Reference(Reference::new(2, 0))),
+                IndirectObject::new(2, 0, Object::Reference(Reference::new(3, 0))),
+                IndirectObject::new(3, 0, Object::Integer(42)),
+            ],
+        );
+
+        let start = Object::Reference(Reference::new(1, 0));
+        assert_eq!(pdf.resolve(&start), &Object::Integer(42));
+
+        let direct = Object::Bool(true);
+        assert_eq!(pdf.resolve(&direct), &direct);
+    }
+
+    #[test]
+    fn resolve_reference_cycle() {
+        let pdf = raw_pdf(
+            Reference::new(1, 0),
+            vec![
+                IndirectObject::new(1, 0, Object::Reference(Reference::new(1, 0))),
+                IndirectObject::new(2, 0, Object::Reference(Reference::new(3, 0))),
+                IndirectObject::new(3, 0, Object::Reference(Reference::new(2, 0))),
+            ],
+        );
+
+        assert_eq!(pdf.resolve(&Object::Reference(Reference::new(1, 0))), &Object::Null);
+        assert_eq!(pdf.resolve(&Object::Reference(Reference::new(2, 0))), &Object::Null);
+
+        let dict = Dictionary::from([
+            (b"Self".to_vec().into(), Object::Reference(Reference::new(1, 0))),
+            (b"Missing".to_vec().into(), Object::Reference(Reference::new(9, 0))),
+        ]);
+        assert_eq!(pdf.dict_get_resolved(&dict, b"Self"), None);
+        assert_eq!(pdf.dict_get_resolved(&dict, b"Missing"), None);
+        assert_eq!(pdf.dict_get_resolved(&dict, b"Absent"), None);
+    }
+
+    #[test]
+    fn dict_get_resolved() {
+        let pdf = raw_pdf(
+            Reference::new(1, 0),
+            vec![IndirectObject::new(1, 0, Object::Integer(7))],
+        );
+        let dict = Dictionary::from([
+            (b"Ref".to_vec().into(), Object::Reference(Reference::new(1, 0))),
+            (b"Direct".to_vec().into(), Object::Integer(3)),
+        ]);
+
+        assert_eq!(pdf.dict_get_resolved(&dict, b"Ref"), Some(&Object::Integer(7)));
+        assert_eq!(pdf.dict_get_resolved(&dict, b"Direct"), Some(&Object::Integer(3)));
+    }
+}
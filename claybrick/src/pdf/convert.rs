@@ -0,0 +1,445 @@
+//! Conversion between objects and Rust types.
+
+use crate::pdf::{
+    text::{self, encode_text, TextDecodeError},
+    Array, Bytes, Dictionary, Name, Object, ObjectSource, Rectangle, Reference,
+};
+
+/// Error returned by [FromObject] conversions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConvertError {
+    /// The key is not present in the dictionary.
+    Missing(Name),
+    /// The object has a type that can't be converted.
+    WrongType {
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// The number doesn't fit into the target type.
+    OutOfRange { expected: &'static str, value: i64 },
+    /// The string is not a valid text string.
+    InvalidText(TextDecodeError),
+}
+
+impl std::fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConvertError::Missing(key) => write!(f, "missing key `{}`", key),
+            ConvertError::WrongType { expected, found } => write!(f, "expected {} but found {}", expected, found),
+            ConvertError::OutOfRange { expected, value } => write!(f, "{} is out of range for {}", value, expected),
+            ConvertError::InvalidText(err) => write!(f, "invalid text string: {:?}", err),
+        }
+    }
+}
+
+impl std::error::Error for ConvertError {}
+
+fn wrong_type(expected: &'static str, found: &Object) -> ConvertError {
+    ConvertError::WrongType {
+        expected,
+        found: found.type_name(),
+    }
+}
+
+/// Resolve `obj` if it's a reference and a document is available.
+fn resolve<'a>(obj: &'a Object, pdf: Option<&'a dyn ObjectSource>) -> &'a Object {
+    match pdf {
+        Some(pdf) => pdf.resolve(obj),
+        None => obj,
+    }
+}
+
+/// Conversion of an object into a Rust type.
+///
+/// If `pdf` is supplied, references are resolved before the conversion,
+/// including references inside arrays. Without a document, references can
+/// only be converted into [Reference].
+pub trait FromObject: Sized {
+    fn from_object(obj: &Object, pdf: Option<&dyn ObjectSource>) -> Result<Self, ConvertError>;
+
+    /// The value for a missing dictionary entry, `None` if the entry is
+    /// required.
+    fn missing() -> Option<Self> {
+        None
+    }
+}
+
+/// Conversion of a Rust value into an object for writing.
+pub trait ToObject {
+    fn to_object(&self) -> Object;
+}
+
+impl Dictionary {
+    /// Get the value for `key` converted into `T`. References are resolved if
+    /// `pdf` is supplied.
+    ///
+    /// Fails with [ConvertError::Missing] if the key is missing, unless `T` is
+    /// an `Option`.
+    pub fn get_as<T: FromObject>(&self, key: &[u8], pdf: Option<&dyn ObjectSource>) -> Result<T, ConvertError> {
+        match self.get(key) {
+            Some(obj) => T::from_object(obj, pdf),
+            None => T::missing().ok_or_else(|| ConvertError::Missing(key.to_vec().into())),
+        }
+    }
+}
+
+impl FromObject for i64 {
+    /// Reals without fractional part are accepted as well.
+    fn from_object(obj: &Object, pdf: Option<&dyn ObjectSource>) -> Result<Self, ConvertError> {
+        let obj = resolve(obj, pdf);
+        obj.as_i64().ok_or_else(|| wrong_type("integer", obj))
+    }
+}
+
+macro_rules! from_object_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl FromObject for $ty {
+                fn from_object(obj: &Object, pdf: Option<&dyn ObjectSource>) -> Result<Self, ConvertError> {
+                    let value = i64::from_object(obj, pdf)?;
+                    <$ty>::try_from(value).map_err(|_| ConvertError::OutOfRange {
+                        expected: stringify!($ty),
+                        value,
+                    })
+                }
+            }
+        )*
+    };
+}
+
+from_object_integer!(i32, u32, usize);
+
+impl FromObject for f64 {
+    fn from_object(obj: &Object, pdf: Option<&dyn ObjectSource>) -> Result<Self, ConvertError> {
+        let obj = resolve(obj, pdf);
+        obj.as_f64().ok_or_else(|| wrong_type("number", obj))
+    }
+}
+
+impl FromObject for bool {
+    fn from_object(obj: &Object, pdf: Option<&dyn ObjectSource>) -> Result<Self, ConvertError> {
+        let obj = resolve(obj, pdf);
+        obj.as_bool().ok_or_else(|| wrong_type("bool", obj))
+    }
+}
+
+impl FromObject for String {
+    /// Decodes literal and hex strings as text strings.
+    fn from_object(obj: &Object, pdf: Option<&dyn ObjectSource>) -> Result<Self, ConvertError> {
+        // Without document, UTF-8 strings are accepted.
+        let version = pdf.map_or(text::UTF8_VERSION, |pdf| pdf.effective_version());
+        match resolve(obj, pdf) {
+            Object::String(s) => s.decode_text(version),
+            Object::HexString(b) => b.decode_text(version),
+            obj => return Err(wrong_type("string", obj)),
+        }
+        .map_err(ConvertError::InvalidText)
+    }
+}
+
+impl FromObject for Name {
+    fn from_object(obj: &Object, pdf: Option<&dyn ObjectSource>) -> Result<Self, ConvertError> {
+        let obj = resolve(obj, pdf);
+        obj.name().cloned().ok_or_else(|| wrong_type("name", obj))
+    }
+}
+
+impl FromObject for Bytes {
+    /// The bytes of a literal string with resolved escape sequences or of a
+    /// hex string.
+    fn from_object(obj: &Object, pdf: Option<&dyn ObjectSource>) -> Result<Self, ConvertError> {
+        match resolve(obj, pdf) {
+            Object::String(s) => Ok(s.unescaped().into()),
+            Object::HexString(b) => Ok(b.clone()),
+            obj => Err(wrong_type("string", obj)),
+        }
+    }
+}
+
+impl FromObject for Reference {
+    /// References are never resolved.
+    fn from_object(obj: &Object, _pdf: Option<&dyn ObjectSource>) -> Result<Self, ConvertError> {
+        obj.reference().copied().ok_or_else(|| wrong_type("reference", obj))
+    }
+}
+
+impl FromObject for Rectangle {
+    fn from_object(obj: &Object, pdf: Option<&dyn ObjectSource>) -> Result<Self, ConvertError> {
+        let obj = resolve(obj, pdf);
+        let numbers = Vec::<f64>::from_object(obj, pdf)?;
+        match numbers[..] {
+            [x0, y0, x1, y1] => Ok(Rectangle::new(x0 as f32, y0 as f32, x1 as f32, y1 as f32).normalized()),
+            _ => Err(wrong_type("rectangle", obj)),
+        }
+    }
+}
+
+impl<T: FromObject> FromObject for Vec<T> {
+    fn from_object(obj: &Object, pdf: Option<&dyn ObjectSource>) -> Result<Self, ConvertError> {
+        let obj = resolve(obj, pdf);
+        obj.array()
+            .ok_or_else(|| wrong_type("array", obj))?
+            .iter()
+            .map(|item| T::from_object(item, pdf))
+            .collect()
+    }
+}
+
+impl<T: FromObject> FromObject for Option<T> {
+    /// The null object and missing entries are `None`.
+    fn from_object(obj: &Object, pdf: Option<&dyn ObjectSource>) -> Result<Self, ConvertError> {
+        match resolve(obj, pdf) {
+            Object::Null => Ok(None),
+            _ => T::from_object(obj, pdf).map(Some),
+        }
+    }
+
+    fn missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+impl ToObject for Object {
+    fn to_object(&self) -> Object {
+        self.clone()
+    }
+}
+
+impl ToObject for i32 {
+    fn to_object(&self) -> Object {
+        Object::Integer(*self)
+    }
+}
+
+impl ToObject for f32 {
+    fn to_object(&self) -> Object {
+        Object::Float(*self)
+    }
+}
+
+impl ToObject for bool {
+    fn to_object(&self) -> Object {
+        Object::Bool(*self)
+    }
+}
+
+impl ToObject for str {
+    /// A text string, literal if possible and hex encoded otherwise.
+    fn to_object(&self) -> Object {
+        let bytes = encode_text(self);
+        // Literal strings store escape sequences as they are. Control bytes
+        // like `\r` would be written as line breaks, which are read as `\n`.
+        if bytes
+            .iter()
+            .all(|b| (b.is_ascii_graphic() || *b == b' ') && *b != b'\\')
+        {
+            Object::String(bytes.into())
+        } else {
+            Object::HexString(bytes.into())
+        }
+    }
+}
+
+impl ToObject for String {
+    fn to_object(&self) -> Object {
+        self.as_str().to_object()
+    }
+}
+
+impl ToObject for Name {
+    fn to_object(&self) -> Object {
+        Object::Name(self.clone())
+    }
+}
+
+impl ToObject for Bytes {
+    /// A hex string, which doesn't need escaping.
+    fn to_object(&self) -> Object {
+        Object::HexString(self.clone())
+    }
+}
+
+impl ToObject for Reference {
+    fn to_object(&self) -> Object {
+        Object::Reference(*self)
+    }
+}
+
+impl ToObject for Rectangle {
+    fn to_object(&self) -> Object {
+        Object::Array(Array::from(*self))
+    }
+}
+
+impl ToObject for Dictionary {
+    fn to_object(&self) -> Object {
+        Object::Dictionary(self.clone())
+    }
+}
+
+impl<T: ToObject> ToObject for [T] {
+    fn to_object(&self) -> Object {
+        Object::from(self.iter().map(ToObject::to_object).collect::<Vec<_>>())
+    }
+}
+
+impl<T: ToObject> ToObject for Vec<T> {
+    fn to_object(&self) -> Object {
+        self[..].to_object()
+    }
+}
+
+impl<T: ToObject> ToObject for Option<T> {
+    /// `None` is the null object.
+    fn to_object(&self) -> Object {
+        self.as_ref().map_or(Object::Null, ToObject::to_object)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::object::object,
+        pdf::{tests::raw_pdf, IndirectObject, RawPdf},
+    };
+
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Widget {
+        name: String,
+        kind: Name,
+        count: usize,
+        scale: f64,
+        visible: bool,
+        rect: Rectangle,
+        tags: Vec<Name>,
+        parent: Reference,
+        id: Bytes,
+        note: Option<String>,
+    }
+
+    impl Widget {
+        fn from_dict(dict: &Dictionary, pdf: Option<&dyn ObjectSource>) -> Result<Self, ConvertError> {
+            Ok(Self {
+                name: dict.get_as(b"T", pdf)?,
+                kind: dict.get_as(b"Subtype", pdf)?,
+                count: dict.get_as(b"Count", pdf)?,
+                scale: dict.get_as(b"Scale", pdf)?,
+                visible: dict.get_as(b"Visible", pdf)?,
+                rect: dict.get_as(b"Rect", pdf)?,
+                tags: dict.get_as(b"Tags", pdf)?,
+                parent: dict.get_as(b"Parent", pdf)?,
+                id: dict.get_as(b"ID", pdf)?,
+                note: dict.get_as(b"Note", pdf)?,
+            })
+        }
+    }
+
+    fn dict(input: &[u8]) -> Dictionary {
+        object(input.into()).unwrap().1.dictionary().unwrap().clone()
+    }
+
+    fn pdf() -> RawPdf {
+        raw_pdf(
+            Reference::new(1, 0),
+            vec![
+                IndirectObject::new(5, 0, Object::Integer(3)),
+                IndirectObject::new(6, 0, Object::from(vec![Object::Integer(0), Object::Integer(0)])),
+            ],
+        )
+    }
+
+    #[test]
+    fn struct_from_dictionary() {
+        let widget = dict(
+            b"<</T (Caf\\351) /Subtype /Widget /Count 5 0 R /Scale 2 /Visible true
+                /Rect [100 50 0 0] /Tags [/A /B] /Parent 3 0 R /ID <0102>>>",
+        );
+        let pdf = pdf();
+        let expected = Widget {
+            name: "Café".to_string(),
+            kind: b"Widget".to_vec().into(),
+            count: 3,
+            scale: 2.0,
+            visible: true,
+            rect: Rectangle::new(0.0, 0.0, 100.0, 50.0),
+            tags: vec![b"A".to_vec().into(), b"B".to_vec().into()],
+            parent: Reference::new(3, 0),
+            id: b"\x01\x02".to_vec().into(),
+            note: None,
+        };
+        assert_eq!(Widget::from_dict(&widget, Some(&pdf)), Ok(expected));
+
+        // Without the document, the reference for `Count` can't be resolved.
+        assert_eq!(
+            Widget::from_dict(&widget, None),
+            Err(ConvertError::WrongType {
+                expected: "integer",
+                found: "reference"
+            })
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let pdf = pdf();
+        let dict = dict(b"<</Negative -1 /Real 1.5 /Rect 6 0 R /Text <FEFFD800>>>");
+        assert_eq!(
+            dict.get_as::<i64>(b"Nope", Some(&pdf)),
+            Err(ConvertError::Missing(b"Nope".to_vec().into()))
+        );
+        assert_eq!(dict.get_as::<Option<i64>>(b"Nope", Some(&pdf)), Ok(None));
+        assert_eq!(
+            dict.get_as::<usize>(b"Negative", None),
+            Err(ConvertError::OutOfRange {
+                expected: "usize",
+                value: -1
+            })
+        );
+        assert_eq!(dict.get_as::<i32>(b"Negative", None), Ok(-1));
+        assert_eq!(
+            dict.get_as::<i64>(b"Real", None),
+            Err(ConvertError::WrongType {
+                expected: "integer",
+                found: "float"
+            })
+        );
+        assert_eq!(dict.get_as::<f64>(b"Real", None), Ok(1.5));
+        assert_eq!(
+            dict.get_as::<Rectangle>(b"Rect", Some(&pdf)),
+            Err(ConvertError::WrongType {
+                expected: "rectangle",
+                found: "array"
+            })
+        );
+        assert_eq!(
+            dict.get_as::<String>(b"Text", None),
+            Err(ConvertError::InvalidText(TextDecodeError::InvalidUtf16))
+        );
+        assert_eq!(
+            dict.get_as::<Bytes>(b"Text", None),
+            Ok(b"\xFE\xFF\xD8\x00".to_vec().into())
+        );
+    }
+
+    #[test]
+    fn to_object_round_trip() {
+        let rect = Rectangle::new(0.0, 0.0, 612.0, 792.0);
+        let tags: Vec<Name> = vec![b"A".to_vec().into()];
+        let dict = Dictionary::from([
+            (b"T".to_vec().into(), "Grüße".to_object()),
+            (b"Count".to_vec().into(), 3.to_object()),
+            (b"Rect".to_vec().into(), rect.to_object()),
+            (b"Tags".to_vec().into(), tags.to_object()),
+            (b"Note".to_vec().into(), None::<String>.to_object()),
+        ]);
+
+        assert_eq!(dict.get_as::<String>(b"T", None).as_deref(), Ok("Grüße"));
+        assert_eq!(dict.get_as::<i64>(b"Count", None), Ok(3));
+        assert_eq!(dict.get_as::<Rectangle>(b"Rect", None), Ok(rect));
+        assert_eq!(dict.get_as::<Vec<Name>>(b"Tags", None), Ok(tags));
+        assert_eq!(dict.get_as::<Option<String>>(b"Note", None), Ok(None));
+        assert_eq!("plain".to_object(), Object::String(b"plain".to_vec().into()));
+    }
+}
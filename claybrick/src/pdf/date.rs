@@ -0,0 +1,262 @@
+//! PDF date strings.
+
+const DATE_PREFIX: &[u8] = b"D:";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DateError {
+    /// The year is missing or not a 4 digit number.
+    InvalidYear,
+    /// A field that is not a 2 digit number.
+    InvalidField,
+    /// A field is outside of its valid range (e.g. month 13).
+    OutOfRange,
+    /// The timezone offset is invalid.
+    InvalidOffset,
+    /// Unexpected bytes after the date.
+    TrailingData,
+}
+
+/// A date in the format `D:YYYYMMDDHHmmSSOHH'mm'`.
+///
+/// All fields except the year are optional.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PdfDate {
+    pub year: u16,
+    pub month: Option<u8>,
+    pub day: Option<u8>,
+    pub hour: Option<u8>,
+    pub minute: Option<u8>,
+    pub second: Option<u8>,
+    /// Offset to UTC in minutes. `Some(0)` for UTC.
+    pub utc_offset_minutes: Option<i16>,
+}
+
+/// Parse a number consisting of exactly `N` digits.
+fn digits<const N: usize>(input: &[u8]) -> Option<(u16, &[u8])> {
+    let (digits, remainder) = (input.get(..N)?, &input[N..]);
+    if !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    let value = digits.iter().fold(0_u16, |acc, d| acc * 10 + u16::from(d - b'0'));
+    Some((value, remainder))
+}
+
+/// Parse an optional 2 digit field. Stops at the end of the input or at the
+/// start of the timezone offset.
+fn field(input: &[u8], range: std::ops::RangeInclusive<u8>) -> Result<(Option<u8>, &[u8]), DateError> {
+    match input.first() {
+        None | Some(b'Z' | b'+' | b'-') => Ok((None, input)),
+        Some(_) => {
+            let (value, remainder) = digits::<2>(input).ok_or(DateError::InvalidField)?;
+            let value = value as u8;
+            if range.contains(&value) {
+                Ok((Some(value), remainder))
+            } else {
+                Err(DateError::OutOfRange)
+            }
+        }
+    }
+}
+
+impl PdfDate {
+    pub fn parse(input: &[u8]) -> Result<PdfDate, DateError> {
+        let input = input.strip_prefix(DATE_PREFIX).unwrap_or(input);
+        let (year, input) = digits::<4>(input).ok_or(DateError::InvalidYear)?;
+        let (month, input) = field(input, 1..=12)?;
+        let (day, input) = field(input, 1..=31)?;
+        let (hour, input) = field(input, 0..=23)?;
+        let (minute, input) = field(input, 0..=59)?;
+        let (second, input) = field(input, 0..=59)?;
+
+        let (utc_offset_minutes, input) = match input.split_first() {
+            None => (None, input),
+            Some((b'Z', remainder)) => (Some(0), offset(remainder).map(|(_, r)| r).unwrap_or(remainder)),
+            Some((&sign @ (b'+' | b'-'), remainder)) => {
+                let (minutes, remainder) = offset(remainder).ok_or(DateError::InvalidOffset)?;
+                (Some(if sign == b'-' { -minutes } else { minutes }), remainder)
+            }
+            Some(_) => return Err(DateError::TrailingData),
+        };
+
+        if !input.is_empty() {
+            return Err(DateError::TrailingData);
+        }
+
+        Ok(PdfDate {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            utc_offset_minutes,
+        })
+    }
+
+    /// The UTC date `seconds` after 1970-01-01 00:00:00 UTC.
+    pub fn from_unix_time(seconds: u64) -> PdfDate {
+        let (days, time) = (seconds / 86_400, seconds % 86_400);
+        // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+        let z = days + 719_468;
+        let era = z / 146_097;
+        let day_of_era = z % 146_097;
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = year_of_era + era * 400 + u64::from(month <= 2);
+        PdfDate {
+            year: year as u16,
+            month: Some(month as u8),
+            day: Some(day as u8),
+            hour: Some((time / 3600) as u8),
+            minute: Some((time / 60 % 60) as u8),
+            second: Some((time % 60) as u8),
+            utc_offset_minutes: Some(0),
+        }
+    }
+
+    /// The current UTC date, or the start of 1970 if the system clock is
+    /// before that.
+    pub fn now() -> PdfDate {
+        let seconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        PdfDate::from_unix_time(seconds)
+    }
+
+    /// Format the date in the canonical form `D:YYYYMMDDHHmmSSOHH'mm'`.
+    ///
+    /// Fields are written up to the first missing field. A zero offset is
+    /// written as `Z`.
+    pub fn format(&self) -> String {
+        let mut out = format!("D:{:04}", self.year);
+        for field in [self.month, self.day, self.hour, self.minute, self.second] {
+            match field {
+                Some(value) => out.push_str(&format!("{:02}", value)),
+                None => break,
+            }
+        }
+        match self.utc_offset_minutes {
+            Some(0) => out.push('Z'),
+            Some(offset) => {
+                let sign = if offset < 0 { '-' } else { '+' };
+                let offset = offset.unsigned_abs();
+                out.push_str(&format!("{}{:02}'{:02}'", sign, offset / 60, offset % 60));
+            }
+            None => {}
+        }
+        out
+    }
+}
+
+impl std::fmt::Display for PdfDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.format())
+    }
+}
+
+/// Parse the offset `HH'mm'` after the sign. Minutes and apostrophes are
+/// optional.
+fn offset(input: &[u8]) -> Option<(i16, &[u8])> {
+    let (hours, input) = digits::<2>(input)?;
+    let input = input.strip_prefix(b"'").unwrap_or(input);
+    let (minutes, input) = digits::<2>(input).unwrap_or((0, input));
+    let input = input.strip_prefix(b"'").unwrap_or(input);
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some(((hours * 60 + minutes) as i16, input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_full_date() {
+        assert_eq!(
+            PdfDate::parse(b"D:20170913090857+02'00'"),
+            Ok(PdfDate {
+                year: 2017,
+                month: Some(9),
+                day: Some(13),
+                hour: Some(9),
+                minute: Some(8),
+                second: Some(57),
+                utc_offset_minutes: Some(120),
+            })
+        );
+    }
+
+    #[test]
+    fn from_unix_time() {
+        for (seconds, expected) in [
+            (0, "D:19700101000000Z"),
+            (1_505_293_737, "D:20170913090857Z"),
+            // leap day
+            (951_782_400, "D:20000229000000Z"),
+            (4_102_444_799, "D:20991231235959Z"),
+        ] {
+            assert_eq!(PdfDate::from_unix_time(seconds).format(), expected);
+        }
+    }
+
+    #[test]
+    fn round_trip_full() {
+        let input = "D:20170913090857+02'00'";
+        let date = PdfDate::parse(input.as_bytes()).unwrap();
+        assert_eq!(date.format(), input);
+
+        let input = "D:19991231235959-05'30'";
+        let date = PdfDate::parse(input.as_bytes()).unwrap();
+        assert_eq!(date.utc_offset_minutes, Some(-330));
+        assert_eq!(date.format(), input);
+    }
+
+    #[test]
+    fn round_trip_partial() {
+        let date = PdfDate::parse(b"D:2017").unwrap();
+        assert_eq!(
+            date,
+            PdfDate {
+                year: 2017,
+                month: None,
+                day: None,
+                hour: None,
+                minute: None,
+                second: None,
+                utc_offset_minutes: None,
+            }
+        );
+        assert_eq!(date.format(), "D:2017");
+
+        let date = PdfDate::parse(b"D:201709").unwrap();
+        assert_eq!(date.month, Some(9));
+        assert_eq!(date.day, None);
+        assert_eq!(date.format(), "D:201709");
+    }
+
+    #[test]
+    fn round_trip_utc() {
+        let date = PdfDate::parse(b"D:20170913090857Z").unwrap();
+        assert_eq!(date.utc_offset_minutes, Some(0));
+        assert_eq!(date.format(), "D:20170913090857Z");
+
+        // `Z` with an explicit zero offset and a missing trailing apostrophe
+        let date = PdfDate::parse(b"D:20170913090857Z00'00").unwrap();
+        assert_eq!(date.utc_offset_minutes, Some(0));
+        assert_eq!(PdfDate::parse(date.format().as_bytes()), Ok(date));
+
+        // without the optional prefix
+        assert_eq!(PdfDate::parse(b"20170913090857Z"), Ok(date));
+    }
+
+    #[test]
+    fn parse_invalid_date() {
+        assert_eq!(PdfDate::parse(b"D:17"), Err(DateError::InvalidYear));
+        assert_eq!(PdfDate::parse(b"D:20171301"), Err(DateError::OutOfRange));
+        assert_eq!(PdfDate::parse(b"D:2017+x"), Err(DateError::InvalidOffset));
+    }
+}
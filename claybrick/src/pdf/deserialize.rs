@@ -0,0 +1,476 @@
+//! [Deserializer] for reading objects into types that implement
+//! [Deserialize].
+//!
+//! Dictionaries are maps with string keys, arrays are sequences and strings
+//! are text strings or byte buffers, depending on what the type asks for.
+//! Names are strings if they are valid UTF-8 and bytes otherwise, which also
+//! allows deserializing unit enum variants from names. Streams are not
+//! supported.
+//!
+//! References are resolved if a document is attached with [from_object]:
+//!
+//! ```
+//! use claybrick::pdf::{deserialize::from_object, Object, ObjectSource};
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! #[serde(rename_all = "PascalCase")]
+//! struct Catalog {
+//!     r#type: String,
+//!     page_layout: Option<String>,
+//! }
+//!
+//! let pdf = claybrick::build::PdfBuilder::new().finish().unwrap();
+//! let root = Object::Reference(pdf.trailer().unwrap().root);
+//! let catalog: Catalog = from_object(&root, Some(&pdf)).unwrap();
+//! assert_eq!(catalog.r#type, "Catalog");
+//! assert_eq!(catalog.page_layout, None);
+//! ```
+
+use serde::{
+    de::{self, IntoDeserializer, MapAccess, SeqAccess, Unexpected, Visitor},
+    forward_to_deserialize_any, Deserialize, Deserializer,
+};
+
+use crate::pdf::{text, Dictionary, Name, Object, ObjectSource};
+
+/// Error returned by the [Deserializer].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeserializeError(String);
+
+impl de::Error for DeserializeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+impl std::fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for DeserializeError {}
+
+/// Deserialize `T` from `obj`. References are resolved if `pdf` is supplied
+/// and are an error otherwise.
+pub fn from_object<'de, T: Deserialize<'de>>(
+    obj: &'de Object,
+    pdf: Option<&'de dyn ObjectSource>,
+) -> Result<T, DeserializeError> {
+    T::deserialize(ObjectDeserializer::new(obj, pdf))
+}
+
+/// Deserializer for an object with an optional document to resolve
+/// references.
+#[derive(Clone, Copy)]
+pub struct ObjectDeserializer<'de> {
+    obj: &'de Object,
+    pdf: Option<&'de dyn ObjectSource>,
+}
+
+impl<'de> ObjectDeserializer<'de> {
+    pub fn new(obj: &'de Object, pdf: Option<&'de dyn ObjectSource>) -> Self {
+        Self { obj, pdf }
+    }
+
+    fn with(&self, obj: &'de Object) -> Self {
+        Self::new(obj, self.pdf)
+    }
+
+    /// The object with references resolved and the indirect object wrapper
+    /// removed.
+    fn direct(&self) -> Result<&'de Object, DeserializeError> {
+        let mut obj = self.obj;
+        loop {
+            match (obj, self.pdf) {
+                (Object::Reference(_), Some(pdf)) => obj = pdf.resolve(obj),
+                (Object::Reference(_), None) => {
+                    return Err(de::Error::custom("can't resolve references without a document"));
+                }
+                (Object::Indirect(indirect), _) => obj = &indirect.object,
+                _ => return Ok(obj),
+            }
+        }
+    }
+}
+
+fn unexpected(obj: &Object) -> Unexpected<'_> {
+    match obj {
+        Object::Float(f) => Unexpected::Float(f64::from(*f)),
+        Object::Integer(i) => Unexpected::Signed(i64::from(*i)),
+        Object::Bool(b) => Unexpected::Bool(*b),
+        Object::Null => Unexpected::Unit,
+        obj => Unexpected::Other(obj.type_name()),
+    }
+}
+
+fn text<'de, V: Visitor<'de>>(bytes: &'de [u8], visitor: V) -> Result<V::Value, DeserializeError> {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => visitor.visit_borrowed_str(text),
+        Err(_) => visitor.visit_borrowed_bytes(bytes),
+    }
+}
+
+impl<'de> Deserializer<'de> for ObjectDeserializer<'de> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        // Without document, UTF-8 strings are accepted.
+        let version = self.pdf.map_or(text::UTF8_VERSION, |pdf| pdf.effective_version());
+        match self.direct()? {
+            Object::String(s) => match s.decode_text(version) {
+                Ok(text) => visitor.visit_string(text),
+                Err(_) => visitor.visit_byte_buf(s.unescaped()),
+            },
+            Object::HexString(b) => match b.decode_text(version) {
+                Ok(text) => visitor.visit_string(text),
+                Err(_) => visitor.visit_borrowed_bytes(b),
+            },
+            Object::Float(f) => visitor.visit_f32(*f),
+            Object::Integer(i) => visitor.visit_i32(*i),
+            Object::Bool(b) => visitor.visit_bool(*b),
+            Object::Name(name) => text(name, visitor),
+            Object::Array(array) => visitor.visit_seq(Seq {
+                de: self,
+                items: array.iter(),
+            }),
+            Object::Dictionary(dict) => visitor.visit_map(Map::new(self, dict)),
+            Object::Null => visitor.visit_unit(),
+            obj => Err(de::Error::invalid_type(unexpected(obj), &visitor)),
+        }
+    }
+
+    /// Strings without text decoding.
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.direct()? {
+            Object::String(s) => visitor.visit_byte_buf(s.unescaped()),
+            Object::HexString(b) => visitor.visit_borrowed_bytes(b),
+            Object::Name(name) => visitor.visit_borrowed_bytes(name),
+            obj => Err(de::Error::invalid_type(unexpected(obj), &visitor)),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    /// The null object and missing dictionary entries are `None`.
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.direct()? {
+            Object::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Unit variants from names and strings.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let variant = match self.direct()? {
+            Object::Name(name) => std::str::from_utf8(name).ok(),
+            Object::String(s) => std::str::from_utf8(s).ok(),
+            _ => None,
+        };
+        match variant {
+            Some(variant) => visitor.visit_enum(IntoDeserializer::<DeserializeError>::into_deserializer(variant)),
+            None => Err(de::Error::invalid_type(unexpected(self.direct()?), &visitor)),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, DeserializeError> for ObjectDeserializer<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Forward the methods of `&Object` to [ObjectDeserializer] without a
+/// document.
+macro_rules! forward_to_object_deserializer {
+    ($($method:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Self::Error> {
+                ObjectDeserializer::new(self, None).$method($($arg,)* visitor)
+            }
+        )*
+    };
+}
+
+/// Deserialize objects that don't contain references.
+impl<'de> Deserializer<'de> for &'de Object {
+    type Error = DeserializeError;
+
+    forward_to_object_deserializer! {
+        deserialize_any();
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_str();
+        deserialize_string();
+        deserialize_bytes();
+        deserialize_byte_buf();
+        deserialize_option();
+        deserialize_unit();
+        deserialize_unit_struct(name: &'static str);
+        deserialize_newtype_struct(name: &'static str);
+        deserialize_seq();
+        deserialize_tuple(len: usize);
+        deserialize_tuple_struct(name: &'static str, len: usize);
+        deserialize_map();
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]);
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+        deserialize_identifier();
+        deserialize_ignored_any();
+    }
+}
+
+struct Seq<'de, I> {
+    de: ObjectDeserializer<'de>,
+    items: I,
+}
+
+impl<'de, I: ExactSizeIterator<Item = &'de Object>> SeqAccess<'de> for Seq<'de, I> {
+    type Error = DeserializeError;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error> {
+        self.items
+            .next()
+            .map(|item| seed.deserialize(self.de.with(item)))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+struct Map<'de> {
+    de: ObjectDeserializer<'de>,
+    entries: crate::pdf::object::dictionary::Iter<'de>,
+    value: Option<&'de Object>,
+    len: usize,
+}
+
+impl<'de> Map<'de> {
+    fn new(de: ObjectDeserializer<'de>, dict: &'de Dictionary) -> Self {
+        Self {
+            de,
+            entries: dict.iter(),
+            value: None,
+            len: dict.len(),
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for Map<'de> {
+    type Error = DeserializeError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> {
+        let Some((key, value)) = self.entries.next() else {
+            return Ok(None);
+        };
+        self.len -= 1;
+        self.value = Some(value);
+        seed.deserialize(Key(key)).map(Some)
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Self::Error> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("value requested before key"))?;
+        seed.deserialize(self.de.with(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+/// Keys are strings, bytes that are not valid UTF-8 are `#xx` escapes like in
+/// PDF files.
+struct Key<'de>(&'de Name);
+
+impl<'de> Deserializer<'de> for Key<'de> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match std::str::from_utf8(self.0) {
+            Ok(key) => visitor.visit_borrowed_str(key),
+            Err(_) => visitor.visit_string(
+                self.0
+                    .iter()
+                    .map(|&b| {
+                        if b.is_ascii_graphic() && b != b'#' {
+                            (b as char).to_string()
+                        } else {
+                            format!("#{:02x}", b)
+                        }
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use crate::{
+        build::{Info, PdfBuilder},
+        parse::object::object,
+        pdf::Reference,
+    };
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct TrailerDict {
+        size: usize,
+        prev: Option<usize>,
+        root: CatalogDict,
+        info: Option<InfoDict>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct CatalogDict {
+        r#type: String,
+        page_mode: Option<PageMode>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum PageMode {
+        UseNone,
+        UseOutlines,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct InfoDict {
+        title: String,
+    }
+
+    #[test]
+    fn trailer() {
+        let mut builder = PdfBuilder::new();
+        builder.set_info(Info {
+            title: Some("Grüße".to_string()),
+            ..Default::default()
+        });
+        let pdf = builder.finish().unwrap();
+        let trailer = pdf.trailer().unwrap().clone();
+        let size = trailer.size;
+        let dict = Object::Dictionary(Dictionary::try_from(trailer).unwrap());
+
+        let expected = TrailerDict {
+            size,
+            prev: None,
+            root: CatalogDict {
+                r#type: "Catalog".to_string(),
+                page_mode: None,
+            },
+            info: Some(InfoDict {
+                title: "Grüße".to_string(),
+            }),
+        };
+        assert_eq!(from_object(&dict, Some(&pdf)), Ok(expected));
+
+        // References can't be resolved without the document.
+        assert!(TrailerDict::deserialize(&dict).is_err());
+    }
+
+    #[test]
+    fn xref_widths() {
+        let obj = object(b"<</Type /XRef /W [1 2 1] /Index [0 5]>>"[..].into()).unwrap().1;
+        let dict = obj.dictionary().unwrap();
+        let widths: [usize; 3] = Deserialize::deserialize(dict.get(&b"W"[..]).unwrap()).unwrap();
+        assert_eq!(widths, [1, 2, 1]);
+
+        let negative = Object::from(vec![Object::Integer(-1), Object::Integer(0), Object::Integer(0)]);
+        assert!(<[usize; 3]>::deserialize(&negative).is_err());
+        assert!(<[usize; 3]>::deserialize(dict.get(&b"Index"[..]).unwrap()).is_err());
+    }
+
+    #[test]
+    fn values() {
+        let obj = object(b"<</Mode /UseOutlines /Id <0102> /Scale 1 /Odd#ff null>>"[..].into())
+            .unwrap()
+            .1;
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct Values {
+            mode: PageMode,
+            #[serde(with = "bytes")]
+            id: Vec<u8>,
+            scale: f64,
+            #[serde(rename = "Odd#ff")]
+            odd: (),
+        }
+
+        mod bytes {
+            pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+                struct Visitor;
+                impl serde::de::Visitor<'_> for Visitor {
+                    type Value = Vec<u8>;
+
+                    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                        f.write_str("bytes")
+                    }
+
+                    fn visit_bytes<E>(self, v: &[u8]) -> Result<Vec<u8>, E> {
+                        Ok(v.to_vec())
+                    }
+                }
+                deserializer.deserialize_bytes(Visitor)
+            }
+        }
+
+        assert_eq!(
+            Values::deserialize(&obj),
+            Ok(Values {
+                mode: PageMode::UseOutlines,
+                id: vec![1, 2],
+                scale: 1.0,
+                odd: (),
+            })
+        );
+        assert!(String::deserialize(&Object::Reference(Reference::new(1, 0))).is_err());
+    }
+}
@@ -1,29 +1,90 @@
+pub use acro_form::{AcroForm, Field, FieldIter, FieldValue};
+pub use annotation::Annotation;
 pub use catalog::{Catalog, CatalogError};
+pub use color_space::ColorSpace;
+pub use destination::{Destination, InvalidDestination, PageIndex};
+pub use ext_g_state::ExtGState;
+pub use file_spec::{AfRelationship, FileSpec};
+pub use form::FormXObject;
+pub use function::{Function, FunctionType};
+pub use image::{EncodedImage, ImageData, ImageXObject, JpegError, JpegInfo};
+#[cfg(feature = "image")]
+pub use image::{ImageError, RgbaImage};
+pub use info::DocumentInfo;
+pub use name_tree::{NameTree, NameTreeIter};
+pub use number_tree::{NumberTree, NumberTreeIter};
+pub use optional_content::{Layer, OptionalContent, OrderNode};
+pub use outline::{Outline, OutlineItem, OutlineIter};
+pub use output_intent::OutputIntent;
+pub use page::Page;
+pub use page_label::{PageLabel, PageLabelStyle};
+pub use pages::{PageIter, Pages, PagesError};
+pub use resources::{ResourceCategory, ResourceUsage};
+pub use shading::{Pattern, PatternType, Shading, ShadingType};
+pub use signature::SignatureInfo;
+pub use struct_tree::{StructElement, StructKid, StructTree};
 
 use crate::pdf::{Dictionary, Object};
 
+pub mod acro_form;
+pub mod annotation;
 pub mod catalog;
+pub mod color_space;
+pub mod destination;
+pub mod ext_g_state;
+pub(crate) mod extract;
+pub mod file_spec;
+pub mod form;
+pub mod function;
+pub mod image;
+pub mod info;
+pub mod name_tree;
+pub mod number_tree;
+pub mod optional_content;
+pub mod outline;
+pub mod output_intent;
+pub mod page;
+pub mod page_label;
 pub mod pages;
+pub mod resources;
+pub mod shading;
+pub mod signature;
+pub mod struct_tree;
 
 /// Dictionary type names
 pub(crate) mod dict_types {
     pub const OBJECT_STREAM: &[u8] = b"ObjStm";
+    pub const XREF: &[u8] = b"XRef";
     pub const PAGES: &[u8] = b"Pages";
+    pub const PAGE: &[u8] = b"Page";
     pub const CATALOG: &[u8] = b"Catalog";
 }
 
 pub(crate) const K_TYPE: &[u8] = b"Type";
-// parent key, for parent objects. not yet needed
-// pub(crate) const K_PARENT: &[u8] = b"Parent";
+pub(crate) const K_PARENT: &[u8] = b"Parent";
 pub(crate) const K_KIDS: &[u8] = b"Kids";
 pub(crate) const K_COUNT: &[u8] = b"Count";
 pub(crate) const K_VERSION: &[u8] = b"Version";
 pub(crate) const K_PAGES: &[u8] = b"Pages";
-pub(crate) const K_PAGES_LABEL: &[u8] = b"PagesLabel";
-pub(crate) const K_NAME: &[u8] = b"Name";
+pub(crate) const K_PAGE_LABELS: &[u8] = b"PageLabels";
+pub(crate) const K_NAMES: &[u8] = b"Names";
+pub(crate) const K_DESTS: &[u8] = b"Dests";
+pub(crate) const K_ACRO_FORM: &[u8] = b"AcroForm";
+pub(crate) const K_OC_PROPERTIES: &[u8] = b"OCProperties";
+pub(crate) const K_OUTLINES: &[u8] = b"Outlines";
+pub(crate) const K_OUTPUT_INTENTS: &[u8] = b"OutputIntents";
+pub(crate) const K_STRUCT_TREE_ROOT: &[u8] = b"StructTreeRoot";
+pub(crate) const K_MARK_INFO: &[u8] = b"MarkInfo";
+pub(crate) const K_AF: &[u8] = b"AF";
 pub(crate) const K_LENGTH: &[u8] = b"Length";
 pub(crate) const K_STREAM_OBJECT_COUNT: &[u8] = b"N";
 pub(crate) const K_FIRST: &[u8] = b"First";
+pub(crate) const K_MEDIA_BOX: &[u8] = b"MediaBox";
+pub(crate) const K_CROP_BOX: &[u8] = b"CropBox";
+pub(crate) const K_ROTATE: &[u8] = b"Rotate";
+pub(crate) const K_RESOURCES: &[u8] = b"Resources";
+pub(crate) const K_CONTENTS: &[u8] = b"Contents";
+pub(crate) const K_ANNOTS: &[u8] = b"Annots";
 
 fn require_type(dict: &Dictionary, t: &[u8]) -> Result<(), ()> {
     if let Some(k) = dict.get(K_TYPE).and_then(Object::name) {
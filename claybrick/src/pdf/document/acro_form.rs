@@ -0,0 +1,312 @@
+use std::collections::HashSet;
+
+use crate::pdf::{text::text_lossy, Dictionary, Name, Object, ObjectSource};
+
+use super::{signature::SignatureInfo, K_KIDS};
+
+const K_FIELDS: &[u8] = b"Fields";
+const K_PARTIAL_NAME: &[u8] = b"T";
+const K_FIELD_TYPE: &[u8] = b"FT";
+const K_VALUE: &[u8] = b"V";
+
+const TEXT: &[u8] = b"Tx";
+const BUTTON: &[u8] = b"Btn";
+const CHOICE: &[u8] = b"Ch";
+const SIGNATURE: &[u8] = b"Sig";
+const OFF: &[u8] = b"Off";
+
+/// Upper bound for the depth of the field hierarchy. Protects against cycles.
+const MAX_DEPTH: usize = 64;
+
+/// The interactive form of the document.
+#[derive(Clone, PartialEq)]
+pub struct AcroForm<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    dict: &'a Dictionary,
+}
+
+// Custom impl to skip `raw_pdf` field.
+impl<'a> std::fmt::Debug for AcroForm<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AcroForm").field("dict", &self.dict).finish()
+    }
+}
+
+impl<'a> AcroForm<'a> {
+    pub(crate) fn new_with(raw_pdf: &'a dyn ObjectSource, dict: &'a Dictionary) -> Self {
+        Self { raw_pdf, dict }
+    }
+
+    /// The raw interactive form dictionary.
+    pub fn dictionary(&self) -> &'a Dictionary {
+        self.dict
+    }
+
+    /// All terminal fields in document order.
+    pub fn fields(&self) -> FieldIter<'a> {
+        let roots = match self.raw_pdf.dict_get_resolved(self.dict, K_FIELDS) {
+            Some(Object::Array(fields)) => fields.iter().rev().map(Node::root).collect(),
+            Some(other) => {
+                log::warn!("Invalid form fields {}", other);
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
+        FieldIter {
+            raw_pdf: self.raw_pdf,
+            version: self.raw_pdf.effective_version(),
+            stack: roots,
+            visited: HashSet::new(),
+        }
+    }
+
+    /// The fully qualified names of all terminal fields, e.g.
+    /// `form.address.street`.
+    pub fn field_names(&self) -> impl Iterator<Item = String> + 'a {
+        self.fields().map(|f| f.name)
+    }
+
+    /// All signed signature fields.
+    pub fn signatures(&self) -> Vec<SignatureInfo> {
+        let raw_pdf = self.raw_pdf;
+        self.fields()
+            .filter_map(|field| match field.value() {
+                Some(FieldValue::Signature(dict)) => SignatureInfo::new_with(raw_pdf, field.name, dict),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// The value of a form field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue<'a> {
+    Text(String),
+    /// The on-state of a checked check box or radio button, `None` if it is
+    /// off.
+    Checkbox(Option<&'a Name>),
+    /// The selected options of a list or combo box.
+    Choice(Vec<String>),
+    /// The signature dictionary of a signed signature field.
+    Signature(&'a Dictionary),
+}
+
+/// A terminal field of the form.
+#[derive(Clone, PartialEq)]
+pub struct Field<'a> {
+    dict: &'a Dictionary,
+    name: String,
+    field_type: Option<&'a Name>,
+    value: Option<&'a Object>,
+    version: (u8, u8),
+}
+
+impl<'a> std::fmt::Debug for Field<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Field")
+            .field("name", &self.name)
+            .field("field_type", &self.field_type)
+            .field("value", &self.value())
+            .finish()
+    }
+}
+
+impl<'a> Field<'a> {
+    /// The raw field dictionary.
+    pub fn dictionary(&self) -> &'a Dictionary {
+        self.dict
+    }
+
+    /// The fully qualified name, the partial names of all parents joined by
+    /// dots.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The field type, e.g. `Tx`. Might be inherited from a parent.
+    pub fn field_type(&self) -> Option<&'a Name> {
+        self.field_type
+    }
+
+    /// The value of the field. Might be inherited from a parent.
+    pub fn value(&self) -> Option<FieldValue<'a>> {
+        let value = self.value?;
+        let text_lossy = |obj| text_lossy(obj, self.version);
+        match (&self.field_type?[..], value) {
+            (TEXT, text @ (Object::String(_) | Object::HexString(_))) => text_lossy(text).map(FieldValue::Text),
+            (BUTTON, Object::Name(state)) => Some(FieldValue::Checkbox(Some(state).filter(|s| &s[..] != OFF))),
+            (CHOICE, Object::Array(options)) => {
+                Some(FieldValue::Choice(options.iter().filter_map(text_lossy).collect()))
+            }
+            (CHOICE, option) => text_lossy(option).map(|o| FieldValue::Choice(vec![o])),
+            (SIGNATURE, Object::Dictionary(dict)) => Some(FieldValue::Signature(dict)),
+            (field_type, value) => {
+                log::warn!(
+                    "Unsupported value {} for field type `{}`",
+                    value,
+                    String::from_utf8_lossy(field_type)
+                );
+                None
+            }
+        }
+    }
+}
+
+/// A field or widget that still needs to be visited.
+struct Node<'a> {
+    obj: &'a Object,
+    parent_name: Option<String>,
+    field_type: Option<&'a Name>,
+    value: Option<&'a Object>,
+    depth: usize,
+}
+
+impl<'a> Node<'a> {
+    fn root(obj: &'a Object) -> Self {
+        Self {
+            obj,
+            parent_name: None,
+            field_type: None,
+            value: None,
+            depth: 0,
+        }
+    }
+}
+
+/// Depth-first iterator over the terminal fields of a form.
+pub struct FieldIter<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    /// The version of the document, which decides how names and values are
+    /// decoded.
+    version: (u8, u8),
+    stack: Vec<Node<'a>>,
+    /// Object numbers of all visited fields.
+    visited: HashSet<u32>,
+}
+
+impl<'a> Iterator for FieldIter<'a> {
+    type Item = Field<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.stack.pop() {
+            if let Some(r) = node.obj.reference() {
+                if !self.visited.insert(r.index) {
+                    log::error!("Form fields contain a cycle at {:?}", r);
+                    continue;
+                }
+            }
+            if node.depth > MAX_DEPTH {
+                log::error!("Form fields exceed the maximum depth of {}", MAX_DEPTH);
+                continue;
+            }
+            let dict = match self.raw_pdf.resolve(node.obj).dictionary() {
+                Some(dict) => dict,
+                None => {
+                    log::warn!("Ignoring invalid form field {}", node.obj);
+                    continue;
+                }
+            };
+
+            let partial_name = self
+                .raw_pdf
+                .dict_get_resolved(dict, K_PARTIAL_NAME)
+                .and_then(|name| text_lossy(name, self.version));
+            let name = match (node.parent_name, partial_name) {
+                (Some(parent), Some(partial)) => format!("{}.{}", parent, partial),
+                (parent, partial) => parent.or(partial).unwrap_or_default(),
+            };
+            let field_type = self
+                .raw_pdf
+                .dict_get_resolved(dict, K_FIELD_TYPE)
+                .and_then(Object::name)
+                .or(node.field_type);
+            let value = self.raw_pdf.dict_get_resolved(dict, K_VALUE).or(node.value);
+
+            // Kids without a partial name are widget annotations of this field.
+            let kids = self
+                .raw_pdf
+                .dict_get_resolved(dict, K_KIDS)
+                .and_then(Object::array)
+                .map(|kids| {
+                    kids.iter()
+                        .filter(|kid| {
+                            self.raw_pdf
+                                .resolve(kid)
+                                .dictionary()
+                                .is_some_and(|k| k.contains_key(K_PARTIAL_NAME))
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+
+            if kids.is_empty() {
+                return Some(Field {
+                    dict,
+                    name,
+                    field_type,
+                    value,
+                    version: self.version,
+                });
+            }
+
+            for kid in kids.into_iter().rev() {
+                self.stack.push(Node {
+                    obj: kid,
+                    parent_name: Some(name.clone()),
+                    field_type,
+                    value,
+                    depth: node.depth + 1,
+                });
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf::{tests::parsed_pdf, Reference};
+
+    use super::*;
+
+    const OBJECTS: [&[u8]; 11] = [
+        b"1 0 obj <</Type /Catalog /Pages 2 0 R /AcroForm 10 0 R>> endobj",
+        b"2 0 obj <</Type /Pages /Kids [] /Count 0>> endobj",
+        b"10 0 obj <</Fields [11 0 R]>> endobj",
+        b"11 0 obj <</T (form) /Kids [12 0 R 15 0 R 16 0 R 17 0 R]>> endobj",
+        b"12 0 obj <</T (address) /Parent 11 0 R /FT /Tx /Kids [13 0 R]>> endobj",
+        b"13 0 obj <</T (street) /Parent 12 0 R /V <FEFF004D00610069006E002000530074002E00200031>
+            /Kids [14 0 R]>> endobj",
+        // widget only
+        b"14 0 obj <</Type /Annot /Subtype /Widget /Parent 13 0 R /Rect [0 0 100 20]>> endobj",
+        // field merged with its widget
+        b"15 0 obj <</T (agree) /Parent 11 0 R /FT /Btn /V /Yes /Subtype /Widget /Rect [0 0 10 10]>> endobj",
+        b"16 0 obj <</T (colors) /Parent 11 0 R /FT /Ch /V [(red) (green)]>> endobj",
+        b"17 0 obj <</T (sig) /Parent 11 0 R /FT /Sig /V 18 0 R>> endobj",
+        b"18 0 obj <</Type /Sig /Filter /Adobe.PPKLite>> endobj",
+    ];
+
+    #[test]
+    fn nested_fields() {
+        let pdf = parsed_pdf(Reference::new(1, 0), &OBJECTS);
+        let form = pdf.acro_form().unwrap();
+
+        assert_eq!(
+            form.field_names().collect::<Vec<_>>(),
+            vec!["form.address.street", "form.agree", "form.colors", "form.sig"]
+        );
+
+        let fields = form.fields().collect::<Vec<_>>();
+        assert_eq!(fields[0].field_type().map(|t| &t[..]), Some(TEXT));
+        assert_eq!(fields[0].value(), Some(FieldValue::Text("Main St. 1".to_string())));
+        assert_eq!(
+            fields[1].value(),
+            Some(FieldValue::Checkbox(Some(&Name::from(b"Yes".to_vec()))))
+        );
+        assert_eq!(
+            fields[2].value(),
+            Some(FieldValue::Choice(vec!["red".to_string(), "green".to_string()]))
+        );
+        assert!(matches!(fields[3].value(), Some(FieldValue::Signature(_))));
+    }
+}
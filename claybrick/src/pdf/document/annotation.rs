@@ -0,0 +1,188 @@
+use crate::pdf::{text, Dictionary, Name, Object, ObjectSource, Rectangle};
+
+use super::destination::{self, K_ACTION, K_ACTION_TYPE};
+
+const K_SUBTYPE: &[u8] = b"Subtype";
+const K_RECT: &[u8] = b"Rect";
+const K_CONTENTS: &[u8] = b"Contents";
+const K_FLAGS: &[u8] = b"F";
+const K_URI: &[u8] = b"URI";
+const LINK: &[u8] = b"Link";
+const URI_ACTION: &[u8] = b"URI";
+
+/// An annotation of a page, e.g. a note or a link.
+#[derive(Clone, PartialEq)]
+pub struct Annotation<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    dict: &'a Dictionary,
+    subtype: &'a Name,
+    rect: Rectangle,
+}
+
+// Custom impl to skip `raw_pdf` field.
+impl<'a> std::fmt::Debug for Annotation<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Annotation")
+            .field("subtype", &self.subtype)
+            .field("rect", &self.rect)
+            .field("dict", &self.dict)
+            .finish()
+    }
+}
+
+impl<'a> Annotation<'a> {
+    /// Returns `None` if the required `Subtype` or `Rect` entries are missing.
+    pub(crate) fn new_with(raw_pdf: &'a dyn ObjectSource, dict: &'a Dictionary) -> Option<Self> {
+        let subtype = raw_pdf.dict_get_resolved(dict, K_SUBTYPE).and_then(Object::name);
+        let rect = raw_pdf
+            .dict_get_resolved(dict, K_RECT)
+            .and_then(Object::array)
+            .and_then(|a| Rectangle::try_from(a).ok());
+
+        match (subtype, rect) {
+            (Some(subtype), Some(rect)) => Some(Self {
+                raw_pdf,
+                dict,
+                subtype,
+                rect,
+            }),
+            _ => {
+                log::warn!("Ignoring annotation without valid subtype or rectangle: {:?}", dict);
+                None
+            }
+        }
+    }
+
+    /// The raw annotation dictionary.
+    pub fn dictionary(&self) -> &'a Dictionary {
+        self.dict
+    }
+
+    pub fn subtype(&self) -> &'a Name {
+        self.subtype
+    }
+
+    /// The location of the annotation on the page.
+    pub fn rect(&self) -> Rectangle {
+        self.rect
+    }
+
+    /// Text displayed for the annotation or an alternate description.
+    pub fn contents(&self) -> Option<String> {
+        text::text_entry(self.raw_pdf, self.dict, K_CONTENTS, "annotation contents")
+    }
+
+    /// Annotation flags, e.g. hidden or print.
+    pub fn flags(&self) -> u32 {
+        self.raw_pdf
+            .dict_get_resolved(self.dict, K_FLAGS)
+            .and_then(Object::integer)
+            .map(|f| f as u32)
+            .unwrap_or(0)
+    }
+
+    pub fn is_link(&self) -> bool {
+        &self.subtype[..] == LINK
+    }
+
+    /// The action of a link annotation.
+    pub fn action(&self) -> Option<&'a Dictionary> {
+        if !self.is_link() {
+            return None;
+        }
+        self.raw_pdf.dict_get_resolved(self.dict, K_ACTION)?.dictionary()
+    }
+
+    /// The destination of a link annotation, either from `Dest` or from a
+    /// `GoTo` action.
+    pub fn destination(&self) -> Option<&'a Object> {
+        if !self.is_link() {
+            return None;
+        }
+        destination::dest_or_goto(self.raw_pdf, self.dict)
+    }
+
+    /// The target of a link annotation with a `URI` action.
+    pub fn link_uri(&self) -> Option<String> {
+        let action = self.action()?;
+        match self
+            .raw_pdf
+            .dict_get_resolved(action, K_ACTION_TYPE)
+            .and_then(Object::name)
+        {
+            Some(t) if &t[..] == URI_ACTION => {}
+            _ => return None,
+        }
+        // URIs are 7-bit ASCII, not text strings.
+        match self.raw_pdf.dict_get_resolved(action, K_URI)? {
+            Object::String(s) => Some(String::from_utf8_lossy(&s.unescaped()).into_owned()),
+            Object::HexString(b) => Some(String::from_utf8_lossy(b).into_owned()),
+            other => {
+                log::warn!("Invalid link URI {}", other);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf::{document::Destination, tests::parsed_pdf, RawPdf, Reference};
+
+    use super::*;
+
+    const OBJECTS: [&[u8]; 8] = [
+        b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj",
+        b"2 0 obj <</Type /Pages /Kids [3 0 R] /Count 1>> endobj",
+        b"3 0 obj <</Type /Page /Parent 2 0 R /Annots [4 0 R 5 0 R 6 0 R 7 0 R]>> endobj",
+        b"4 0 obj <</Type /Annot /Subtype /Text /Rect [10 10 30 30] /Contents (Caf\\351) /F 4>> endobj",
+        b"5 0 obj <</Type /Annot /Subtype /Link /Rect [150 70 50 50]
+            /A <</S /URI /URI (https://example.com/a\\(b\\))>>>> endobj",
+        // malformed: missing `Rect`
+        b"6 0 obj <</Type /Annot /Subtype /Link /A <</S /URI /URI (https://example.com)>>>> endobj",
+        b"7 0 obj (not an annotation) endobj",
+        b"8 0 obj <</Type /Annot /Subtype /Link /Rect [0 0 1 1] /Dest [3 0 R /Fit]>> endobj",
+    ];
+
+    fn pdf() -> RawPdf {
+        parsed_pdf(Reference::new(1, 0), &OBJECTS)
+    }
+
+    #[test]
+    fn page_annotations() {
+        let pdf = pdf();
+        let page = pdf.page(0).unwrap();
+        let annotations = page.annotations().collect::<Vec<_>>();
+        assert_eq!(annotations.len(), 2);
+
+        let text = &annotations[0];
+        assert_eq!(&text.subtype()[..], b"Text");
+        assert_eq!(text.rect(), Rectangle::new(10.0, 10.0, 30.0, 30.0));
+        assert_eq!(text.contents().as_deref(), Some("Café"));
+        assert_eq!(text.flags(), 4);
+        assert_eq!(text.link_uri(), None);
+
+        let link = &annotations[1];
+        assert!(link.is_link());
+        // The corners are normalized.
+        assert_eq!(link.rect(), Rectangle::new(50.0, 50.0, 150.0, 70.0));
+        assert_eq!(link.link_uri().as_deref(), Some("https://example.com/a(b)"));
+        assert_eq!(link.destination(), None);
+        assert_eq!(link.flags(), 0);
+    }
+
+    #[test]
+    fn link_destination() {
+        let pdf = pdf();
+        let dict = pdf
+            .dereference(&Reference::new(8, 0))
+            .and_then(Object::dictionary)
+            .unwrap();
+        let link = Annotation::new_with(&pdf, dict).unwrap();
+
+        assert_eq!(
+            link.destination().and_then(|d| pdf.resolve_destination(d)),
+            Some((0, Destination::Fit))
+        );
+    }
+}
@@ -1,42 +1,97 @@
 use crate::pdf::{
-    document::{pages::Pages, require_type},
+    document::{
+        acro_form::AcroForm,
+        destination::{Destination, PageIndex},
+        file_spec::FileSpec,
+        name_tree::{NameTree, DESTS, EMBEDDED_FILES},
+        number_tree::NumberTree,
+        optional_content::{Layer, OptionalContent},
+        outline::Outline,
+        output_intent::OutputIntent,
+        page::Page,
+        page_label::PageLabel,
+        pages::{self, PageIter, Pages},
+        require_type,
+        struct_tree::StructTree,
+    },
     object::Name,
-    Dictionary, Object, RawPdf,
+    Array, DictExt, Dictionary, KeyError, Object, ObjectSource, Reference,
 };
 
-use super::{dict_types::CATALOG, pages::PagesError, K_NAME, K_PAGES, K_PAGES_LABEL, K_VERSION};
+use super::{
+    dict_types::CATALOG, pages::PagesError, K_ACRO_FORM, K_AF, K_COUNT, K_DESTS, K_MARK_INFO, K_NAMES, K_OC_PROPERTIES,
+    K_OUTLINES, K_OUTPUT_INTENTS, K_PAGES, K_PAGE_LABELS, K_STRUCT_TREE_ROOT, K_VERSION,
+};
+
+const K_MARKED: &[u8] = b"Marked";
+
+/// The version of a catalog `/Version` entry like `/1.7`.
+pub(crate) fn parse_version(name: &[u8]) -> Option<(u8, u8)> {
+    let (major, minor) = std::str::from_utf8(name).ok()?.split_once('.')?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+/// The `/Version` of the catalog that `root` references.
+pub(crate) fn catalog_version(raw_pdf: &dyn ObjectSource, root: &Reference) -> Option<(u8, u8)> {
+    let version = raw_pdf.dereference(root)?.dictionary()?.get(K_VERSION)?.name()?;
+    parse_version(version)
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum CatalogError {
+    /// The document has no section and thus no trailer.
+    MissingTrailer,
+    /// The `/Root` of the trailer refers to the null object, which is the
+    /// same as no root.
+    MissingRoot,
+    /// The `/Root` of the trailer refers to a missing object.
+    RootNotFound(Reference),
+    /// The root object is not a dictionary.
+    RootNotDictionary,
     MissingPages,
+    InvalidPages(PagesError),
+}
+
+impl std::fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CatalogError::MissingTrailer => f.write_str("the document has no trailer"),
+            CatalogError::MissingRoot => f.write_str("the trailer has no root"),
+            CatalogError::RootNotFound(root) => write!(f, "the root {} doesn't exist", root),
+            CatalogError::RootNotDictionary => f.write_str("the root is not a dictionary"),
+            CatalogError::MissingPages => f.write_str("the catalog has no page tree"),
+            CatalogError::InvalidPages(err) => write!(f, "invalid page tree: {:?}", err),
+        }
+    }
 }
 
 #[derive(Clone, PartialEq)]
 pub struct Catalog<'a> {
-    raw_pdf: &'a RawPdf,
+    raw_pdf: &'a dyn ObjectSource,
     version: Option<&'a Name>,
     pages: &'a Dictionary,
-    pages_label: Option<&'a Dictionary>,
+    page_labels: Option<&'a Dictionary>,
     names: Option<&'a Dictionary>,
-    // dests: Option<&'a Dictionary>,
+    outlines: Option<&'a Dictionary>,
+    dests: Option<&'a Dictionary>,
+    acro_form: Option<&'a Dictionary>,
+    optional_content: Option<&'a Dictionary>,
+    output_intents: Option<&'a Array>,
+    struct_tree_root: Option<&'a Dictionary>,
+    mark_info: Option<&'a Dictionary>,
+    associated_files: Option<&'a Array>,
     // viewer_preferences: Option<&'a Dictionary>,
     // page_layout: Option<&'a Name>,
     // page_mode: Option<&'a Name>,
-    // outlines: Option<&'a Dictionary>,
     // threads: Option<&'a Array>,
     // /// Array or dictionary
     // open_action: Option<&'a Object>,
     // additional_actions: Option<&'a Dictionary>,
     // uri: Option<&'a Dictionary>,
-    // acro_form: Option<&'a Dictionary>,
     // metadata: Option<&'a Stream>,
-    // structure_tree: Option<&'a Dictionary>,
-    // mark_info: Option<&'a Dictionary>,
     // lang: Option<&'a CbString>,
     // spider_info: Option<&'a Dictionary>,
-    // output_intents: Option<&'a Array>,
     // piece_info: Option<&'a Dictionary>,
-    // optional_content_properties: Option<&'a Dictionary>,
     // permissions: Option<&'a Dictionary>,
     // legal: Option<&'a Dictionary>,
     // requirements: Option<&'a Array>,
@@ -50,37 +105,287 @@ impl<'a> std::fmt::Debug for Catalog<'a> {
         f.debug_struct("Catalog")
             .field("version", &self.version)
             .field("pages", &self.pages)
-            .field("pages_label", &self.pages_label)
+            .field("page_labels", &self.page_labels)
             .field("names", &self.names)
+            .field("dests", &self.dests)
+            .field("acro_form", &self.acro_form)
+            .field("optional_content", &self.optional_content)
+            .field("outlines", &self.outlines)
+            .field("output_intents", &self.output_intents)
+            .field("struct_tree_root", &self.struct_tree_root)
+            .field("mark_info", &self.mark_info)
+            .field("associated_files", &self.associated_files)
             .finish()
     }
 }
 
+/// Optional catalog entries with an invalid type are treated as missing.
+fn ignore_invalid<T>(err: KeyError) -> Option<T> {
+    log::warn!("Ignoring invalid catalog entry: {}", err);
+    None
+}
+
+/// Optional dictionary entry that might be a reference.
+fn resolved_dict<'a>(raw_pdf: &'a dyn ObjectSource, dict: &'a Dictionary, key: &[u8]) -> Option<&'a Dictionary> {
+    let obj = raw_pdf.dict_get_resolved(dict, key)?;
+    obj.dictionary().or_else(|| {
+        log::warn!(
+            "Ignoring invalid catalog entry `{}`: {}",
+            String::from_utf8_lossy(key),
+            obj
+        );
+        None
+    })
+}
+
+/// Optional array entry that might be a reference.
+fn resolved_array<'a>(raw_pdf: &'a dyn ObjectSource, dict: &'a Dictionary, key: &[u8]) -> Option<&'a Array> {
+    let obj = raw_pdf.dict_get_resolved(dict, key)?;
+    obj.array().or_else(|| {
+        log::warn!(
+            "Ignoring invalid catalog entry `{}`: {}",
+            String::from_utf8_lossy(key),
+            obj
+        );
+        None
+    })
+}
+
 impl<'a> Catalog<'a> {
-    pub(crate) fn new_with(raw_pdf: &'a RawPdf, dict: &'a Dictionary) -> Result<Self, CatalogError> {
+    pub(crate) fn new_with(raw_pdf: &'a dyn ObjectSource, dict: &'a Dictionary) -> Result<Self, CatalogError> {
         let _ = require_type(dict, CATALOG);
 
         Ok(Self {
             raw_pdf,
-            version: dict.get(K_VERSION).and_then(Object::name),
-            pages: dict
-                .get(K_PAGES)
-                .and_then(|o| match o {
-                    Object::Reference(r) => raw_pdf.dereference(r),
-                    other => Some(other),
-                })
+            version: dict.get_name_opt(K_VERSION).unwrap_or_else(ignore_invalid),
+            pages: raw_pdf
+                .dict_get_resolved(dict, K_PAGES)
                 .and_then(Object::dictionary)
                 .ok_or(CatalogError::MissingPages)
-                .map_err(|e| {
+                .inspect_err(|_| {
                     log::error!("Missing `{}` key. Got {:?}", String::from_utf8_lossy(K_PAGES), dict);
-                    e
                 })?,
-            pages_label: dict.get(K_PAGES_LABEL).and_then(Object::dictionary),
-            names: dict.get(K_NAME).and_then(Object::dictionary),
+            page_labels: resolved_dict(raw_pdf, dict, K_PAGE_LABELS),
+            names: resolved_dict(raw_pdf, dict, K_NAMES),
+            dests: resolved_dict(raw_pdf, dict, K_DESTS),
+            acro_form: resolved_dict(raw_pdf, dict, K_ACRO_FORM),
+            optional_content: resolved_dict(raw_pdf, dict, K_OC_PROPERTIES),
+            outlines: resolved_dict(raw_pdf, dict, K_OUTLINES),
+            output_intents: resolved_array(raw_pdf, dict, K_OUTPUT_INTENTS),
+            struct_tree_root: resolved_dict(raw_pdf, dict, K_STRUCT_TREE_ROOT),
+            mark_info: resolved_dict(raw_pdf, dict, K_MARK_INFO),
+            associated_files: resolved_array(raw_pdf, dict, K_AF),
         })
     }
 
-    pub fn pages(&self) -> Result<Pages, PagesError> {
+    pub fn pages(&self) -> Result<Pages<'a>, PagesError> {
         Pages::new_with(self.raw_pdf, self.pages)
     }
+
+    /// Number of pages in the document.
+    ///
+    /// Uses the `Count` entry of the page tree root. If the count is missing,
+    /// smaller than the number of kids or larger than the kids can hold, the
+    /// pages are counted instead. The counts of intermediate nodes are
+    /// trusted.
+    pub fn page_count(&self) -> Result<usize, CatalogError> {
+        let kids = pages::kids(self.raw_pdf, self.pages).map_err(CatalogError::InvalidPages)?;
+        let count_leafs = || PageIter::new(self.raw_pdf, kids).filter(Result::is_ok).count();
+
+        match self
+            .pages
+            .get_integer(K_COUNT)
+            .ok()
+            .and_then(|c| usize::try_from(c).ok())
+        {
+            // Flat page trees have a kid for each page, larger counts are
+            // checked against the kids.
+            Some(count) if count == kids.len() => Ok(count),
+            Some(count)
+                if count > kids.len()
+                    && pages::kids_capacity(self.raw_pdf, kids).is_some_and(|capacity| count <= capacity) =>
+            {
+                Ok(count)
+            }
+            Some(count) => {
+                let counted = count_leafs();
+                log::warn!("Page tree announces {} pages but contains {}", count, counted);
+                Ok(counted)
+            }
+            None => {
+                log::warn!("Missing or invalid page count, counting pages instead");
+                Ok(count_leafs())
+            }
+        }
+    }
+
+    /// The page with the given zero-based index.
+    pub fn page(&self, index: usize) -> Option<Page<'a>> {
+        pages::page_at(self.raw_pdf, self.pages, index)
+    }
+
+    /// Index of the page with the given object number.
+    pub fn page_index(&self, number: u32) -> Option<PageIndex> {
+        self.pages()
+            .ok()?
+            .iter()
+            .filter_map(Result::ok)
+            .position(|p| p.number() == number)
+    }
+
+    /// Resolve an explicit or named destination to the index of the target
+    /// page and the view of the page.
+    pub fn resolve_destination<'b>(&self, dest: &'b Object) -> Option<(PageIndex, Destination)>
+    where
+        'a: 'b,
+    {
+        let explicit = match self.raw_pdf.resolve(dest) {
+            Object::Array(array) => array,
+            named @ (Object::String(_) | Object::HexString(_) | Object::Name(_)) => self.named_destination(named)?,
+            other => {
+                log::warn!("Invalid destination {}", other);
+                return None;
+            }
+        };
+
+        let destination = Destination::try_from(explicit)
+            .inspect_err(|_| log::warn!("Invalid destination {}", explicit))
+            .ok()?;
+        let index = match explicit.first() {
+            Some(Object::Reference(page)) => self.page_index(page.index)?,
+            // Destinations of remote documents use page indices.
+            Some(Object::Integer(i)) => usize::try_from(*i).ok()?,
+            _ => {
+                log::warn!("Invalid destination page in {}", explicit);
+                return None;
+            }
+        };
+        Some((index, destination))
+    }
+
+    /// Look up a named destination in the `Dests` name tree or, for PDF 1.1
+    /// documents, in the `Dests` dictionary.
+    fn named_destination(&self, name: &Object) -> Option<&'a Array> {
+        let key = match name {
+            Object::String(s) => s.unescaped(),
+            Object::HexString(b) => b.to_vec(),
+            Object::Name(n) => n.to_vec(),
+            _ => return None,
+        };
+        let value = self
+            .name_tree(DESTS)
+            .and_then(|tree| tree.get(&key))
+            .or_else(|| self.raw_pdf.dict_get_resolved(self.dests?, &key))?;
+
+        // Either the destination or a dictionary with the destination in `D`.
+        match value {
+            Object::Array(array) => Some(array),
+            Object::Dictionary(dict) => self.raw_pdf.dict_get_resolved(dict, b"D")?.array(),
+            other => {
+                log::warn!("Invalid named destination {}", other);
+                None
+            }
+        }
+    }
+
+    /// A name tree of the `Names` dictionary, e.g.
+    /// [`name_tree::DESTS`](super::name_tree::DESTS).
+    pub fn name_tree(&self, key: &[u8]) -> Option<NameTree<'a>> {
+        let root = self.raw_pdf.dict_get_resolved(self.names?, key)?.dictionary()?;
+        Some(NameTree::new_with(self.raw_pdf, root))
+    }
+
+    /// The embedded files of the `/EmbeddedFiles` name tree with their
+    /// names. Entries that are not dictionaries are skipped.
+    pub fn embedded_files(&self) -> Vec<(String, FileSpec<'a>)> {
+        let Some(tree) = self.name_tree(EMBEDDED_FILES) else {
+            return Vec::new();
+        };
+        tree.iter()
+            .filter_map(|(name, file)| {
+                Some((
+                    name.to_string_lossy(),
+                    FileSpec::new_with(self.raw_pdf, file.dictionary()?),
+                ))
+            })
+            .collect()
+    }
+
+    /// The files associated with the whole document, e.g. the XML of an
+    /// electronic invoice.
+    pub fn associated_files(&self) -> Vec<FileSpec<'a>> {
+        let raw_pdf = self.raw_pdf;
+        self.associated_files
+            .into_iter()
+            .flat_map(|files| files.iter())
+            .filter_map(|file| raw_pdf.resolve(file).dictionary())
+            .map(|file| FileSpec::new_with(raw_pdf, file))
+            .collect()
+    }
+
+    /// The interactive form.
+    pub fn acro_form(&self) -> Option<AcroForm<'a>> {
+        Some(AcroForm::new_with(self.raw_pdf, self.acro_form?))
+    }
+
+    /// The optional content properties (layers) of the document.
+    pub fn optional_content(&self) -> Option<OptionalContent<'a>> {
+        Some(OptionalContent::new_with(self.raw_pdf, self.optional_content?))
+    }
+
+    /// All layers of the document. Empty if there is no optional content.
+    pub fn layers(&self) -> Vec<Layer> {
+        self.optional_content().map(|oc| oc.layers()).unwrap_or_default()
+    }
+
+    /// The document outline (bookmarks).
+    pub fn outlines(&self) -> Option<Outline<'a>> {
+        Some(Outline::new_with(self.raw_pdf, self.outlines?))
+    }
+
+    /// The intended output devices, e.g. of PDF/A documents. Entries that are
+    /// not dictionaries are skipped.
+    pub fn output_intents(&self) -> Vec<OutputIntent<'a>> {
+        let raw_pdf = self.raw_pdf;
+        self.output_intents
+            .into_iter()
+            .flat_map(|intents| intents.iter())
+            .filter_map(|intent| raw_pdf.resolve(intent).dictionary())
+            .map(|intent| OutputIntent::new_with(raw_pdf, intent))
+            .collect()
+    }
+
+    /// The structure tree of tagged documents.
+    pub fn struct_tree(&self) -> Option<StructTree<'a>> {
+        Some(StructTree::new_with(self.raw_pdf, self.struct_tree_root?))
+    }
+
+    /// Whether the document is a tagged PDF, that is `/MarkInfo` has
+    /// `/Marked true`.
+    pub fn is_tagged(&self) -> bool {
+        self.mark_info
+            .and_then(|mark_info| self.raw_pdf.dict_get_resolved(mark_info, K_MARKED))
+            .and_then(Object::as_bool)
+            .unwrap_or(false)
+    }
+
+    /// The page labels, a number tree keyed by page index.
+    pub fn page_labels(&self) -> Option<NumberTree<'a>> {
+        Some(NumberTree::new_with(self.raw_pdf, self.page_labels?))
+    }
+
+    /// The label of the page with the given zero-based index.
+    pub fn page_label(&self, index: usize) -> Option<PageLabel> {
+        let index = i64::try_from(index).ok()?;
+        let (start, dict) = self.page_labels()?.range_lookup(index)?;
+        let dict = dict.dictionary().or_else(|| {
+            log::warn!("Invalid page label dictionary {}", dict);
+            None
+        })?;
+        Some(PageLabel::new_with(
+            self.raw_pdf,
+            dict,
+            u32::try_from(index - start).ok()?,
+        ))
+    }
 }
@@ -0,0 +1,266 @@
+//! Color spaces of page resources and images.
+
+use crate::pdf::{Bytes, Dictionary, Name, Object, ObjectSource, Stream};
+
+use super::function::Function;
+
+const DEVICE_GRAY: &[u8] = b"DeviceGray";
+const DEVICE_RGB: &[u8] = b"DeviceRGB";
+const DEVICE_CMYK: &[u8] = b"DeviceCMYK";
+const CAL_GRAY: &[u8] = b"CalGray";
+const CAL_RGB: &[u8] = b"CalRGB";
+const LAB: &[u8] = b"Lab";
+const PATTERN: &[u8] = b"Pattern";
+const ICC_BASED: &[u8] = b"ICCBased";
+const INDEXED: &[u8] = b"Indexed";
+const SEPARATION: &[u8] = b"Separation";
+const DEVICE_N: &[u8] = b"DeviceN";
+const K_COMPONENTS: &[u8] = b"N";
+
+/// Base and alternate color spaces are nested at most one level deep, the
+/// limit guards against cycles.
+const MAX_DEPTH: usize = 4;
+
+/// A color space, either a name or an array with the family name first.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorSpace<'a> {
+    DeviceGray,
+    DeviceRgb,
+    DeviceCmyk,
+    /// A calibrated gray space with its parameter dictionary.
+    CalGray(&'a Dictionary),
+    /// A calibrated RGB space with its parameter dictionary.
+    CalRgb(&'a Dictionary),
+    /// A CIE L*a*b* space with its parameter dictionary.
+    Lab(&'a Dictionary),
+    /// A color space defined by an ICC profile with `n` color components,
+    /// which is 1, 3 or 4.
+    IccBased {
+        n: u32,
+        profile: &'a Stream,
+    },
+    /// A color map, the single component is an index into `lookup`, which
+    /// holds `hival + 1` colors in the `base` space.
+    Indexed {
+        base: Box<ColorSpace<'a>>,
+        hival: u8,
+        lookup: Bytes,
+    },
+    /// A single colorant, e.g. a spot color. The tint transform maps the tint
+    /// to the alternate space.
+    Separation {
+        name: &'a Name,
+        alternate: Box<ColorSpace<'a>>,
+        tint_transform: Function<'a>,
+    },
+    /// Several colorants, the tint transform maps them to the alternate
+    /// space.
+    DeviceN {
+        names: Vec<&'a Name>,
+        alternate: Box<ColorSpace<'a>>,
+        tint_transform: Function<'a>,
+    },
+    Pattern,
+    /// Other or invalid color spaces.
+    Other(&'a Object),
+}
+
+impl<'a> ColorSpace<'a> {
+    pub(crate) fn new_with(raw_pdf: &'a dyn ObjectSource, object: &'a Object) -> Self {
+        Self::parse(raw_pdf, object, 0)
+    }
+
+    fn parse(raw_pdf: &'a dyn ObjectSource, object: &'a Object, depth: usize) -> Self {
+        let object = raw_pdf.resolve(object);
+        let (family, parameters) = match object {
+            Object::Name(family) => (family, &[][..]),
+            Object::Array(array) => match array
+                .split_first()
+                .map(|(family, rest)| (raw_pdf.resolve(family), rest))
+            {
+                Some((Object::Name(family), parameters)) => (family, parameters),
+                _ => return ColorSpace::Other(object),
+            },
+            _ => return ColorSpace::Other(object),
+        };
+        if depth > MAX_DEPTH {
+            log::warn!("Color spaces nested too deeply at {}", object);
+            return ColorSpace::Other(object);
+        }
+        let nested = |space| Box::new(Self::parse(raw_pdf, space, depth + 1));
+        let dictionary = |parameters: &'a Object| raw_pdf.resolve(parameters).dictionary();
+        let color_space = match (&family[..], parameters) {
+            (DEVICE_GRAY, _) => Some(ColorSpace::DeviceGray),
+            (DEVICE_RGB, _) => Some(ColorSpace::DeviceRgb),
+            (DEVICE_CMYK, _) => Some(ColorSpace::DeviceCmyk),
+            (PATTERN, _) => Some(ColorSpace::Pattern),
+            (CAL_GRAY, [parameters]) => dictionary(parameters).map(ColorSpace::CalGray),
+            (CAL_RGB, [parameters]) => dictionary(parameters).map(ColorSpace::CalRgb),
+            (LAB, [parameters]) => dictionary(parameters).map(ColorSpace::Lab),
+            (ICC_BASED, [profile]) => {
+                let profile = raw_pdf.resolve(profile).stream();
+                let n = profile
+                    .and_then(|profile| raw_pdf.dict_get_resolved(&profile.dictionary, K_COMPONENTS))
+                    .and_then(Object::integer)
+                    .and_then(|n| u32::try_from(n).ok())
+                    .filter(|n| [1, 3, 4].contains(n));
+                n.zip(profile).map(|(n, profile)| ColorSpace::IccBased { n, profile })
+            }
+            (INDEXED, [base, hival, lookup]) => {
+                let hival = raw_pdf.resolve(hival).integer().and_then(|h| u8::try_from(h).ok());
+                let lookup = match raw_pdf.resolve(lookup) {
+                    Object::String(s) => Some(s.unescaped().into()),
+                    Object::HexString(b) => Some(b.clone()),
+                    Object::Stream(s) => s.filtered_data().ok(),
+                    _ => None,
+                };
+                hival.zip(lookup).map(|(hival, lookup)| ColorSpace::Indexed {
+                    base: nested(base),
+                    hival,
+                    lookup,
+                })
+            }
+            (SEPARATION, [name, alternate, tint_transform, ..]) => raw_pdf
+                .resolve(name)
+                .name()
+                .zip(Function::new_with(raw_pdf, tint_transform))
+                .map(|(name, tint_transform)| ColorSpace::Separation {
+                    name,
+                    alternate: nested(alternate),
+                    tint_transform,
+                }),
+            (DEVICE_N, [names, alternate, tint_transform, ..]) => raw_pdf
+                .resolve(names)
+                .array()
+                .and_then(|names| {
+                    names
+                        .iter()
+                        .map(|name| raw_pdf.resolve(name).name())
+                        .collect::<Option<Vec<_>>>()
+                })
+                .zip(Function::new_with(raw_pdf, tint_transform))
+                .map(|(names, tint_transform)| ColorSpace::DeviceN {
+                    names,
+                    alternate: nested(alternate),
+                    tint_transform,
+                }),
+            _ => return ColorSpace::Other(object),
+        };
+        color_space.unwrap_or_else(|| {
+            log::warn!("Invalid {} color space {}", family, object);
+            ColorSpace::Other(object)
+        })
+    }
+
+    /// The number of color components, `None` for patterns and other color
+    /// spaces.
+    pub fn components(&self) -> Option<u32> {
+        match self {
+            ColorSpace::DeviceGray | ColorSpace::CalGray(_) | ColorSpace::Indexed { .. } => Some(1),
+            ColorSpace::Separation { .. } => Some(1),
+            ColorSpace::DeviceRgb | ColorSpace::CalRgb(_) | ColorSpace::Lab(_) => Some(3),
+            ColorSpace::DeviceCmyk => Some(4),
+            ColorSpace::IccBased { n, .. } => Some(*n),
+            ColorSpace::DeviceN { names, .. } => u32::try_from(names.len()).ok(),
+            ColorSpace::Pattern | ColorSpace::Other(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::object::object,
+        pdf::{tests::parsed_pdf, RawPdf, Reference},
+    };
+
+    use super::*;
+
+    fn document() -> RawPdf {
+        parsed_pdf(
+            Reference::new(1, 0),
+            &[
+                b"1 0 obj [/Indexed 2 0 R 2 3 0 R] endobj",
+                b"2 0 obj [/ICCBased 4 0 R] endobj",
+                b"3 0 obj <</Filter /ASCIIHexDecode /Length 19>> stream\nff0000 00ff00 0000ff\nendstream endobj",
+                b"4 0 obj <</N 3 /Alternate /DeviceRGB /Length 0>> stream\n\nendstream endobj",
+                b"5 0 obj <</FunctionType 2 /Domain [0 1] /C0 [0 0 0 0] /C1 [0 1 0 0] /N 1>> endobj",
+                b"6 0 obj [/Indexed 6 0 R 0 <00>] endobj",
+                b"7 0 obj <</N 2 /Length 0>> stream\n\nendstream endobj",
+            ],
+        )
+    }
+
+    #[test]
+    fn indexed() {
+        let pdf = document();
+        let color_space = pdf.color_space(pdf.object(1).unwrap().indirect().unwrap().object());
+        assert_eq!(color_space.components(), Some(1));
+        let ColorSpace::Indexed { base, hival, lookup } = color_space else {
+            panic!("Expected an indexed color space, got {:?}", color_space);
+        };
+        assert_eq!(hival, 2);
+        assert_eq!(lookup.len(), (hival as usize + 1) * 3);
+        assert_eq!(&lookup[3..6], b"\x00\xff\x00");
+        assert!(matches!(*base, ColorSpace::IccBased { n: 3, .. }));
+        assert_eq!(base.components(), Some(3));
+
+        // The lookup table can be a string.
+        let inline = object(b"[/Indexed /DeviceGray 1 (\\000\\377)]"[..].into()).unwrap().1;
+        let ColorSpace::Indexed { lookup, .. } = pdf.color_space(&inline) else {
+            panic!("Expected an indexed color space");
+        };
+        assert_eq!(&lookup[..], b"\x00\xff");
+
+        // The base of object 6 is itself.
+        let cycle = Object::Reference(Reference::new(6, 0));
+        let ColorSpace::Indexed { base, .. } = pdf.color_space(&cycle) else {
+            panic!("Expected an indexed color space");
+        };
+        assert!(matches!(*base, ColorSpace::Indexed { .. }));
+    }
+
+    #[test]
+    fn families() {
+        let pdf = document();
+        for (color_space, components) in [
+            (&b"/DeviceCMYK"[..], Some(4)),
+            (b"[/CalGray <</WhitePoint [0.9505 1 1.089]>>]", Some(1)),
+            (
+                b"[/CalRGB <</WhitePoint [0.9505 1 1.089] /Gamma [2.2 2.2 2.2]>>]",
+                Some(3),
+            ),
+            (
+                b"[/Lab <</WhitePoint [0.9505 1 1.089] /Range [-100 100 -100 100]>>]",
+                Some(3),
+            ),
+            (b"[/Separation /Spot /DeviceCMYK 5 0 R]", Some(1)),
+            (b"[/DeviceN [/Cyan /Spot] /DeviceCMYK 5 0 R <<>>]", Some(2)),
+            (b"[/Pattern /DeviceRGB]", None),
+            (b"[/CalRGB]", None),
+            (b"[/ICCBased 7 0 R]", None),
+            (b"/Unknown", None),
+        ] {
+            let object = object(color_space.into()).unwrap().1;
+            assert_eq!(
+                pdf.color_space(&object).components(),
+                components,
+                "{}",
+                String::from_utf8_lossy(color_space)
+            );
+        }
+
+        let separation = object(b"[/Separation /Spot /DeviceCMYK 5 0 R]"[..].into()).unwrap().1;
+        let ColorSpace::Separation {
+            name,
+            alternate,
+            tint_transform,
+        } = pdf.color_space(&separation)
+        else {
+            panic!("Expected a separation color space");
+        };
+        assert_eq!(&name[..], b"Spot");
+        assert_eq!(*alternate, ColorSpace::DeviceCmyk);
+        assert!(tint_transform.dictionary().contains_key(&b"C1"[..]));
+    }
+}
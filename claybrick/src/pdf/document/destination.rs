@@ -0,0 +1,205 @@
+use crate::pdf::{Array, Dictionary, Object, ObjectSource};
+
+const K_DEST: &[u8] = b"Dest";
+pub(crate) const K_ACTION: &[u8] = b"A";
+pub(crate) const K_ACTION_TYPE: &[u8] = b"S";
+const K_ACTION_DEST: &[u8] = b"D";
+const GOTO_ACTION: &[u8] = b"GoTo";
+
+/// Zero-based index of a page in the page tree.
+pub type PageIndex = usize;
+
+/// Error returned if an array can't be converted into a [Destination].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidDestination;
+
+/// How a page is displayed when jumping to a destination.
+///
+/// `None` parameters (`null` in the file) keep the current value of the
+/// viewer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Destination {
+    /// Position `(left, top)` at the upper-left corner of the window and zoom
+    /// by `zoom`.
+    Xyz {
+        left: Option<f32>,
+        top: Option<f32>,
+        zoom: Option<f32>,
+    },
+    /// Fit the entire page into the window.
+    Fit,
+    /// Fit the width of the page, `top` at the top edge of the window.
+    FitH { top: Option<f32> },
+    /// Fit the height of the page, `left` at the left edge of the window.
+    FitV { left: Option<f32> },
+    /// Fit the rectangle into the window.
+    FitR {
+        left: f32,
+        bottom: f32,
+        right: f32,
+        top: f32,
+    },
+    /// Fit the bounding box of the page contents into the window.
+    FitB,
+    /// Fit the width of the bounding box, `top` at the top edge of the window.
+    FitBH { top: Option<f32> },
+    /// Fit the height of the bounding box, `left` at the left edge of the
+    /// window.
+    FitBV { left: Option<f32> },
+}
+
+/// The destination of an outline item or link annotation, either from `Dest`
+/// or from a `GoTo` action.
+pub(crate) fn dest_or_goto<'a>(raw_pdf: &'a dyn ObjectSource, dict: &'a Dictionary) -> Option<&'a Object> {
+    if let Some(dest) = raw_pdf.dict_get_resolved(dict, K_DEST) {
+        return Some(dest);
+    }
+
+    let action = raw_pdf.dict_get_resolved(dict, K_ACTION)?.dictionary()?;
+    match raw_pdf.dict_get_resolved(action, K_ACTION_TYPE).and_then(Object::name) {
+        Some(t) if &t[..] == GOTO_ACTION => raw_pdf.dict_get_resolved(action, K_ACTION_DEST),
+        _ => None,
+    }
+}
+
+/// A number or `null`.
+fn optional_number(obj: Option<&Object>) -> Result<Option<f32>, InvalidDestination> {
+    match obj {
+        Some(Object::Integer(i)) => Ok(Some(*i as f32)),
+        Some(Object::Float(f)) => Ok(Some(*f)),
+        // Missing trailing parameters are treated like `null`.
+        Some(Object::Null) | None => Ok(None),
+        Some(_) => Err(InvalidDestination),
+    }
+}
+
+fn number(obj: Option<&Object>) -> Result<f32, InvalidDestination> {
+    optional_number(obj)?.ok_or(InvalidDestination)
+}
+
+/// Parses an explicit destination `[page /Type params...]`. The page is
+/// ignored.
+impl TryFrom<&Array> for Destination {
+    type Error = InvalidDestination;
+
+    fn try_from(array: &Array) -> Result<Self, Self::Error> {
+        let kind = array.get(1).and_then(Object::name).ok_or(InvalidDestination)?;
+        let param = |i: usize| array.get(i + 2);
+
+        Ok(match &kind[..] {
+            b"XYZ" => Destination::Xyz {
+                left: optional_number(param(0))?,
+                top: optional_number(param(1))?,
+                zoom: optional_number(param(2))?,
+            },
+            b"Fit" => Destination::Fit,
+            b"FitH" => Destination::FitH {
+                top: optional_number(param(0))?,
+            },
+            b"FitV" => Destination::FitV {
+                left: optional_number(param(0))?,
+            },
+            b"FitR" => Destination::FitR {
+                left: number(param(0))?,
+                bottom: number(param(1))?,
+                right: number(param(2))?,
+                top: number(param(3))?,
+            },
+            b"FitB" => Destination::FitB,
+            b"FitBH" => Destination::FitBH {
+                top: optional_number(param(0))?,
+            },
+            b"FitBV" => Destination::FitBV {
+                left: optional_number(param(0))?,
+            },
+            _ => {
+                log::warn!("Unknown destination type `{}`", kind);
+                return Err(InvalidDestination);
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::object::object,
+        pdf::{document::name_tree::DESTS, tests::parsed_pdf, Name, RawPdf, Reference},
+    };
+
+    use super::*;
+
+    const OBJECTS: [&[u8]; 6] = [
+        b"1 0 obj <</Type /Catalog /Pages 2 0 R /Names <</Dests 5 0 R>> /Dests 6 0 R>> endobj",
+        b"2 0 obj <</Type /Pages /Kids [3 0 R 4 0 R] /Count 2>> endobj",
+        b"3 0 obj <</Type /Page /Parent 2 0 R>> endobj",
+        b"4 0 obj <</Type /Page /Parent 2 0 R>> endobj",
+        b"5 0 obj <</Names [(chapter1) [3 0 R /Fit] (chapter2) <</D [4 0 R /FitH 700]>>]>> endobj",
+        b"6 0 obj <</intro [3 0 R /XYZ 0 792 2]>> endobj",
+    ];
+
+    fn pdf() -> RawPdf {
+        parsed_pdf(Reference::new(1, 0), &OBJECTS)
+    }
+
+    #[test]
+    fn explicit_destination() {
+        let pdf = pdf();
+        let dest = object(b"[4 0 R /FitR 10 20.5 300 400]"[..].into()).unwrap().1;
+        assert_eq!(
+            pdf.resolve_destination(&dest),
+            Some((
+                1,
+                Destination::FitR {
+                    left: 10.0,
+                    bottom: 20.5,
+                    right: 300.0,
+                    top: 400.0
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn named_destination() {
+        let pdf = pdf();
+        let dest = Object::String(b"chapter2".to_vec().into());
+        assert_eq!(
+            pdf.resolve_destination(&dest),
+            Some((1, Destination::FitH { top: Some(700.0) }))
+        );
+        let dest = Object::String(b"chapter1".to_vec().into());
+        assert_eq!(pdf.resolve_destination(&dest), Some((0, Destination::Fit)));
+        assert!(pdf.catalog().unwrap().name_tree(DESTS).is_some());
+
+        // `Dests` dictionary of the catalog
+        let dest = Object::Name(Name::new(b"intro".to_vec()));
+        assert_eq!(
+            pdf.resolve_destination(&dest),
+            Some((
+                0,
+                Destination::Xyz {
+                    left: Some(0.0),
+                    top: Some(792.0),
+                    zoom: Some(2.0)
+                }
+            ))
+        );
+
+        let dest = Object::String(b"missing".to_vec().into());
+        assert_eq!(pdf.resolve_destination(&dest), None);
+    }
+
+    #[test]
+    fn null_zoom() {
+        let dest = object(b"[3 0 R /XYZ 10 null null]"[..].into()).unwrap().1;
+        assert_eq!(
+            Destination::try_from(dest.array().unwrap()),
+            Ok(Destination::Xyz {
+                left: Some(10.0),
+                top: None,
+                zoom: None
+            })
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//! Graphics state parameter dictionaries, set with the `gs` operator.
+
+use crate::pdf::{Dictionary, Name, Object, ObjectSource, Stream};
+
+use super::form::{FormTraversal, FormXObject, K_XOBJECT};
+
+const K_STROKE_ALPHA: &[u8] = b"CA";
+const K_FILL_ALPHA: &[u8] = b"ca";
+const K_BLEND_MODE: &[u8] = b"BM";
+const K_SOFT_MASK: &[u8] = b"SMask";
+const K_LINE_WIDTH: &[u8] = b"LW";
+const K_FONT: &[u8] = b"Font";
+const K_EXT_G_STATE: &[u8] = b"ExtGState";
+const K_SUBTYPE: &[u8] = b"Subtype";
+const K_SOFT_MASK_IN_DATA: &[u8] = b"SMaskInData";
+const IMAGE: &[u8] = b"Image";
+
+/// Blend modes that don't blend, the source color replaces the backdrop.
+const OPAQUE_BLEND_MODES: &[&[u8]] = &[b"Normal", b"Compatible"];
+
+/// An `/ExtGState` resource.
+#[derive(Clone)]
+pub struct ExtGState<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    dict: &'a Dictionary,
+}
+
+// Custom impl to skip `raw_pdf` field.
+impl<'a> std::fmt::Debug for ExtGState<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExtGState").field("dict", &self.dict).finish()
+    }
+}
+
+impl<'a> ExtGState<'a> {
+    pub(crate) fn new_with(raw_pdf: &'a dyn ObjectSource, dict: &'a Dictionary) -> Self {
+        Self { raw_pdf, dict }
+    }
+
+    /// The raw graphics state parameter dictionary.
+    pub fn dictionary(&self) -> &'a Dictionary {
+        self.dict
+    }
+
+    /// The constant alpha of stroking operations.
+    pub fn stroke_alpha(&self) -> Option<f32> {
+        self.number(K_STROKE_ALPHA)
+    }
+
+    /// The constant alpha of all other painting operations.
+    pub fn fill_alpha(&self) -> Option<f32> {
+        self.number(K_FILL_ALPHA)
+    }
+
+    /// The blend mode. Older documents may use an array of blend modes, of
+    /// which the first supported one is used.
+    pub fn blend_modes(&self) -> Vec<&'a Name> {
+        match self.get(K_BLEND_MODE) {
+            Some(Object::Name(mode)) => vec![mode],
+            Some(Object::Array(modes)) => modes
+                .iter()
+                .filter_map(|mode| self.raw_pdf.resolve(mode).name())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The soft mask dictionary, `None` if it's missing or `/None`.
+    pub fn soft_mask(&self) -> Option<&'a Dictionary> {
+        self.get(K_SOFT_MASK).and_then(Object::dictionary)
+    }
+
+    pub fn line_width(&self) -> Option<f32> {
+        self.number(K_LINE_WIDTH)
+    }
+
+    /// The font dictionary and the font size.
+    pub fn font(&self) -> Option<(&'a Dictionary, f32)> {
+        let font = self.get(K_FONT)?.array()?;
+        match &font[..] {
+            [font, size] => Some((
+                self.raw_pdf.resolve(font).dictionary()?,
+                self.raw_pdf.resolve(size).as_f64()? as f32,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Whether the parameters make painting transparent, that is an alpha
+    /// below 1, a soft mask or a blend mode other than `Normal`.
+    pub fn uses_transparency(&self) -> bool {
+        let transparent_alpha = |alpha: Option<f32>| alpha.is_some_and(|alpha| alpha < 1.0);
+        transparent_alpha(self.stroke_alpha())
+            || transparent_alpha(self.fill_alpha())
+            || self.soft_mask().is_some()
+            || self
+                .blend_modes()
+                .first()
+                .is_some_and(|mode| !OPAQUE_BLEND_MODES.contains(&&mode[..]))
+    }
+
+    fn number(&self, key: &[u8]) -> Option<f32> {
+        self.get(key).and_then(Object::as_f64).map(|number| number as f32)
+    }
+
+    fn get(&self, key: &[u8]) -> Option<&'a Object> {
+        self.raw_pdf.dict_get_resolved(self.dict, key)
+    }
+}
+
+/// The graphics state parameter dictionaries of a resource dictionary.
+pub(crate) fn ext_g_states<'a>(
+    raw_pdf: &'a dyn ObjectSource,
+    resources: &'a Dictionary,
+) -> impl Iterator<Item = (&'a Name, ExtGState<'a>)> + 'a {
+    raw_pdf
+        .dict_get_resolved(resources, K_EXT_G_STATE)
+        .and_then(Object::dictionary)
+        .into_iter()
+        .flat_map(|states| states.iter())
+        .filter_map(move |(name, state)| {
+            Some((name, ExtGState::new_with(raw_pdf, raw_pdf.resolve(state).dictionary()?)))
+        })
+}
+
+/// Whether the graphics states or images of `resources`, or of the forms in
+/// them, use transparency. Forms that `forms` entered before are skipped.
+pub(crate) fn resources_use_transparency<'a>(
+    raw_pdf: &'a dyn ObjectSource,
+    resources: &'a Dictionary,
+    forms: &mut FormTraversal,
+) -> bool {
+    if ext_g_states(raw_pdf, resources).any(|(_, state)| state.uses_transparency()) {
+        return true;
+    }
+    let xobjects = raw_pdf
+        .dict_get_resolved(resources, K_XOBJECT)
+        .and_then(Object::dictionary)
+        .into_iter()
+        .flat_map(|xobjects| xobjects.values())
+        .filter_map(|xobject| raw_pdf.resolve(xobject).stream());
+    for xobject in xobjects {
+        let uses_transparency = match FormXObject::new_with(raw_pdf, xobject) {
+            Some(form) if forms.enter(xobject) => {
+                let uses_transparency = form
+                    .resources()
+                    .is_some_and(|resources| resources_use_transparency(raw_pdf, resources, forms));
+                forms.leave();
+                uses_transparency
+            }
+            Some(_) => false,
+            None => is_image(xobject) && image_has_soft_mask(raw_pdf, &xobject.dictionary),
+        };
+        if uses_transparency {
+            return true;
+        }
+    }
+    false
+}
+
+fn is_image(xobject: &Stream) -> bool {
+    xobject.dictionary.get(K_SUBTYPE).and_then(Object::name).map(|n| &n[..]) == Some(IMAGE)
+}
+
+/// Whether the image has a soft mask image or alpha channel in its data.
+fn image_has_soft_mask(raw_pdf: &dyn ObjectSource, image: &Dictionary) -> bool {
+    raw_pdf
+        .dict_get_resolved(image, K_SOFT_MASK)
+        .and_then(Object::stream)
+        .is_some()
+        || raw_pdf
+            .dict_get_resolved(image, K_SOFT_MASK_IN_DATA)
+            .and_then(Object::integer)
+            .is_some_and(|in_data| in_data != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf::{tests::parsed_pdf, RawPdf, Reference};
+
+    fn document(resources: &str) -> RawPdf {
+        let page = format!(
+            "3 0 obj <</Type /Page /Parent 2 0 R /MediaBox [0 0 100 100] /Resources {}>> endobj",
+            resources
+        );
+        parsed_pdf(Reference::new(1, 0), &[
+            b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj",
+            b"2 0 obj <</Type /Pages /Kids [3 0 R] /Count 1>> endobj",
+            page.as_bytes(),
+            b"4 0 obj <</Type /XObject /Subtype /Form /BBox [0 0 1 1] /Resources <</ExtGState <</GS1 5 0 R>>>> /Length 0>> stream\n\nendstream endobj",
+            b"5 0 obj <</Type /ExtGState /BM /Multiply>> endobj",
+            b"6 0 obj <</Type /XObject /Subtype /Image /Width 1 /Height 1 /ColorSpace /DeviceGray /BitsPerComponent 8 /SMask 7 0 R /Length 1>> stream\n\x00\nendstream endobj",
+            b"7 0 obj <</Type /XObject /Subtype /Image /Width 1 /Height 1 /ColorSpace /DeviceGray /BitsPerComponent 8 /Length 1>> stream\n\x00\nendstream endobj",
+            b"10 0 obj <</Type /Font /Subtype /Type1 /BaseFont /Helvetica>> endobj",
+        ])
+    }
+
+    #[test]
+    fn parameters() {
+        let pdf = document(
+            "<</ExtGState <</GS1 <</CA 1 /ca 0.5 /LW 2 /Font [10 0 R 12] /BM [/Foo /Screen] /SMask /None>> >>>>",
+        );
+        let page = pdf.page(0).unwrap();
+        let states = page.ext_g_states();
+        assert_eq!(states.len(), 1);
+        let (name, state) = &states[0];
+        assert_eq!(&name[..], b"GS1");
+        assert_eq!(state.stroke_alpha(), Some(1.0));
+        assert_eq!(state.fill_alpha(), Some(0.5));
+        assert_eq!(state.line_width(), Some(2.0));
+        let (font, size) = state.font().unwrap();
+        assert!(font.contains_key(&b"BaseFont"[..]));
+        assert_eq!(size, 12.0);
+        let modes = state.blend_modes().iter().map(|m| m.to_vec()).collect::<Vec<_>>();
+        assert_eq!(modes, vec![b"Foo".to_vec(), b"Screen".to_vec()]);
+        assert!(state.soft_mask().is_none());
+        assert!(state.uses_transparency());
+    }
+
+    #[test]
+    fn uses_transparency() {
+        for (resources, expected) in [
+            ("<<>>", false),
+            (
+                "<</ExtGState <</GS1 <</CA 1 /ca 1.0 /BM /Normal /SMask /None>>>>>>",
+                false,
+            ),
+            ("<</ExtGState <</GS1 <</BM [/Compatible /Multiply]>>>>>>", false),
+            ("<</ExtGState <</GS1 <</ca 0.5>>>>>>", true),
+            ("<</ExtGState <</GS1 <</SMask <</S /Luminosity /G 4 0 R>>>>>>>>", true),
+            // images
+            ("<</XObject <</Im1 7 0 R>>>>", false),
+            ("<</XObject <</Im1 6 0 R>>>>", true),
+            // graphics states of forms
+            ("<</XObject <</Fm1 4 0 R>>>>", true),
+        ] {
+            assert_eq!(document(resources).uses_transparency(), expected, "{}", resources);
+        }
+    }
+}
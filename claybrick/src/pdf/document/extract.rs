@@ -0,0 +1,184 @@
+//! Extraction of the text that content streams show.
+
+use crate::{
+    parse::{
+        content::{Operation, Operator},
+        error::CbParseError,
+    },
+    pdf::{
+        font::{encoding::win_ansi_char, CMap},
+        Dictionary, Object, ObjectSource,
+    },
+};
+
+use super::form::{lookup_form, FormTraversal};
+
+const K_FONT: &[u8] = b"Font";
+const K_SUBTYPE: &[u8] = b"Subtype";
+const K_TO_UNICODE: &[u8] = b"ToUnicode";
+const TYPE0: &[u8] = b"Type0";
+
+/// Adjustments in `TJ` arrays below this, in thousandths of text space, are
+/// taken as word gaps.
+const WORD_GAP: f64 = -200.0;
+
+/// The text shown by `operations` and the forms they paint. Lines are
+/// separated by `\n`.
+pub(crate) fn extract_text<'a>(
+    source: &'a dyn ObjectSource,
+    operations: &[Operation],
+    resources: Option<&'a Dictionary>,
+) -> Result<String, CbParseError<()>> {
+    let mut extractor = TextExtractor {
+        source,
+        font: None,
+        decoders: Vec::new(),
+        forms: FormTraversal::default(),
+        text: String::new(),
+    };
+    extractor.extract(operations, resources)?;
+    Ok(extractor.text)
+}
+
+/// How the codes of a font are mapped to unicode.
+enum Decoder {
+    ToUnicode(CMap),
+    /// Composite fonts without `/ToUnicode`, their codes are not mapped.
+    TwoByte,
+    /// Simple fonts without `/ToUnicode` are assumed to use WinAnsiEncoding.
+    WinAnsi,
+}
+
+impl Decoder {
+    fn new(source: &dyn ObjectSource, font: &Dictionary) -> Self {
+        let cmap = source
+            .dict_get_resolved(font, K_TO_UNICODE)
+            .and_then(Object::stream)
+            .and_then(|stream| stream.filtered_data().ok())
+            .and_then(|data| CMap::parse(&data).ok());
+        match cmap {
+            Some(cmap) => Decoder::ToUnicode(cmap),
+            None if font.get(K_SUBTYPE).and_then(Object::name).map(|n| &n[..]) == Some(TYPE0) => Decoder::TwoByte,
+            None => Decoder::WinAnsi,
+        }
+    }
+
+    fn decode(&self, bytes: &[u8], out: &mut String) {
+        match self {
+            Decoder::ToUnicode(cmap) => out.push_str(&cmap.decode(bytes)),
+            Decoder::TwoByte => out.extend(bytes.chunks(2).map(|_| char::REPLACEMENT_CHARACTER)),
+            Decoder::WinAnsi => out.extend(
+                bytes
+                    .iter()
+                    .map(|&b| win_ansi_char(b).unwrap_or(char::REPLACEMENT_CHARACTER)),
+            ),
+        }
+    }
+}
+
+struct TextExtractor<'a> {
+    source: &'a dyn ObjectSource,
+    /// The font dictionary selected by the last `Tf`.
+    font: Option<&'a Dictionary>,
+    /// Decoders of the fonts used so far.
+    decoders: Vec<(&'a Dictionary, Decoder)>,
+    forms: FormTraversal,
+    text: String,
+}
+
+impl<'a> TextExtractor<'a> {
+    fn extract(&mut self, operations: &[Operation], resources: Option<&'a Dictionary>) -> Result<(), CbParseError<()>> {
+        for operation in operations {
+            let operands = &operation.operands;
+            match operation.operator {
+                Operator::SetFont => {
+                    self.font = operands
+                        .first()
+                        .and_then(Object::name)
+                        .and_then(|name| self.lookup_font(resources, name));
+                }
+                Operator::ShowText => self.show(operands.first()),
+                Operator::NextLineShowText | Operator::NextLineShowTextSpacing => {
+                    self.new_line();
+                    self.show(operands.last());
+                }
+                Operator::ShowTextArray => {
+                    for element in operands
+                        .first()
+                        .and_then(Object::array)
+                        .into_iter()
+                        .flat_map(|a| a.iter())
+                    {
+                        match element.as_f64() {
+                            Some(adjustment) if adjustment < WORD_GAP => self.text.push(' '),
+                            Some(_) => (),
+                            None => self.show(Some(element)),
+                        }
+                    }
+                }
+                Operator::NextLine | Operator::SetTextMatrix => self.new_line(),
+                Operator::MoveText | Operator::MoveTextSetLeading
+                    if operands.get(1).and_then(Object::as_f64).is_some_and(|ty| ty != 0.0) =>
+                {
+                    self.new_line()
+                }
+                Operator::PaintXObject => {
+                    if let Some(name) = operands.first().and_then(Object::name) {
+                        self.extract_form(name, resources)?;
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Extract the text of the form XObject with the given name. The font is
+    /// restored afterwards like the rest of the graphics state.
+    fn extract_form(&mut self, name: &[u8], resources: Option<&'a Dictionary>) -> Result<(), CbParseError<()>> {
+        let Some(form) = lookup_form(self.source, resources, name) else {
+            return Ok(());
+        };
+        if !self.forms.enter(form.stream()) {
+            return Ok(());
+        }
+        let font = self.font;
+        let result = form
+            .operations()
+            .and_then(|operations| self.extract(&operations, form.resources().or(resources)));
+        self.font = font;
+        self.forms.leave();
+        result
+    }
+
+    fn lookup_font(&self, resources: Option<&'a Dictionary>, name: &[u8]) -> Option<&'a Dictionary> {
+        let fonts = self.source.dict_get_resolved(resources?, K_FONT)?.dictionary()?;
+        self.source.dict_get_resolved(fonts, name)?.dictionary()
+    }
+
+    fn show(&mut self, string: Option<&Object>) {
+        let bytes = match string {
+            Some(Object::String(string)) => string.unescaped(),
+            Some(Object::HexString(bytes)) => bytes.to_vec(),
+            _ => return,
+        };
+        let Some(font) = self.font else {
+            log::warn!("Ignoring text shown without a font");
+            return;
+        };
+        let index = match self.decoders.iter().position(|(f, _)| std::ptr::eq(*f, font)) {
+            Some(index) => index,
+            None => {
+                self.decoders.push((font, Decoder::new(self.source, font)));
+                self.decoders.len() - 1
+            }
+        };
+        self.decoders[index].1.decode(&bytes, &mut self.text);
+    }
+
+    fn new_line(&mut self) {
+        if !self.text.is_empty() && !self.text.ends_with('\n') {
+            self.text.push('\n');
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! File specifications, e.g. of embedded files.
+
+use crate::pdf::{
+    date::PdfDate, object::stream::filter::FilterError, text::text_entry, Bytes, Dictionary, Name, Object,
+    ObjectSource, Stream,
+};
+
+const K_FILE: &[u8] = b"F";
+const K_UNICODE_FILE: &[u8] = b"UF";
+const K_DESCRIPTION: &[u8] = b"Desc";
+const K_EMBEDDED_FILES: &[u8] = b"EF";
+const K_AF_RELATIONSHIP: &[u8] = b"AFRelationship";
+const K_SUBTYPE: &[u8] = b"Subtype";
+const K_PARAMS: &[u8] = b"Params";
+const K_SIZE: &[u8] = b"Size";
+const K_MOD_DATE: &[u8] = b"ModDate";
+
+/// How an associated file relates to the content that refers to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AfRelationship {
+    /// The original source material of the content.
+    Source,
+    /// The data the content is a visual representation of, e.g. the XML of
+    /// an invoice.
+    Data,
+    /// An alternative representation of the content, e.g. audio.
+    Alternative,
+    /// Additional information about the content.
+    Supplement,
+    EncryptedPayload,
+    /// The data of an interactive form.
+    FormData,
+    /// A schema definition of the content.
+    Schema,
+    Unspecified,
+}
+
+impl AfRelationship {
+    /// The value of the `/AFRelationship` entry.
+    pub fn name(self) -> &'static [u8] {
+        match self {
+            AfRelationship::Source => b"Source",
+            AfRelationship::Data => b"Data",
+            AfRelationship::Alternative => b"Alternative",
+            AfRelationship::Supplement => b"Supplement",
+            AfRelationship::EncryptedPayload => b"EncryptedPayload",
+            AfRelationship::FormData => b"FormData",
+            AfRelationship::Schema => b"Schema",
+            AfRelationship::Unspecified => b"Unspecified",
+        }
+    }
+
+    fn from_name(name: &[u8]) -> Option<Self> {
+        [
+            AfRelationship::Source,
+            AfRelationship::Data,
+            AfRelationship::Alternative,
+            AfRelationship::Supplement,
+            AfRelationship::EncryptedPayload,
+            AfRelationship::FormData,
+            AfRelationship::Schema,
+            AfRelationship::Unspecified,
+        ]
+        .into_iter()
+        .find(|relationship| relationship.name() == name)
+    }
+}
+
+/// A file specification dictionary.
+#[derive(Clone, PartialEq)]
+pub struct FileSpec<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    dict: &'a Dictionary,
+}
+
+// Custom impl to skip `raw_pdf` field.
+impl<'a> std::fmt::Debug for FileSpec<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileSpec").field("dict", &self.dict).finish()
+    }
+}
+
+impl<'a> FileSpec<'a> {
+    pub(crate) fn new_with(raw_pdf: &'a dyn ObjectSource, dict: &'a Dictionary) -> Self {
+        Self { raw_pdf, dict }
+    }
+
+    /// The raw file specification dictionary.
+    pub fn dictionary(&self) -> &'a Dictionary {
+        self.dict
+    }
+
+    /// The name of the file, the unicode name if there is one.
+    pub fn file_name(&self) -> Option<String> {
+        self.text(K_UNICODE_FILE).or_else(|| self.text(K_FILE))
+    }
+
+    pub fn description(&self) -> Option<String> {
+        self.text(K_DESCRIPTION)
+    }
+
+    /// The relationship of an associated file, `None` if it's missing or
+    /// unknown.
+    pub fn relationship(&self) -> Option<AfRelationship> {
+        let name = self.get(K_AF_RELATIONSHIP)?.name()?;
+        AfRelationship::from_name(name)
+    }
+
+    /// The embedded file stream, preferably the one of the unicode name.
+    pub fn embedded_file(&self) -> Option<&'a Stream> {
+        let files = self.get(K_EMBEDDED_FILES)?.dictionary()?;
+        [K_UNICODE_FILE, K_FILE]
+            .into_iter()
+            .find_map(|key| self.raw_pdf.dict_get_resolved(files, key).and_then(Object::stream))
+    }
+
+    /// The MIME type of the embedded file, e.g. `text/xml`.
+    pub fn mime_type(&self) -> Option<&'a Name> {
+        self.raw_pdf
+            .dict_get_resolved(&self.embedded_file()?.dictionary, K_SUBTYPE)?
+            .name()
+    }
+
+    /// The size of the embedded file in bytes as stated by the file.
+    pub fn size(&self) -> Option<i64> {
+        self.params()?.get(K_SIZE).and_then(Object::as_i64)
+    }
+
+    /// The modification date of the embedded file.
+    pub fn mod_date(&self) -> Option<PdfDate> {
+        let date = match self.raw_pdf.resolve(self.params()?.get(K_MOD_DATE)?) {
+            Object::String(s) => s.unescaped(),
+            Object::HexString(b) => b.to_vec(),
+            _ => return None,
+        };
+        PdfDate::parse(&date).ok()
+    }
+
+    /// The decoded content of the embedded file.
+    pub fn data(&self) -> Option<Result<Bytes, FilterError>> {
+        self.embedded_file().map(Stream::filtered_data)
+    }
+
+    /// The `/Params` of the embedded file stream.
+    fn params(&self) -> Option<&'a Dictionary> {
+        self.raw_pdf
+            .dict_get_resolved(&self.embedded_file()?.dictionary, K_PARAMS)?
+            .dictionary()
+    }
+
+    fn get(&self, key: &[u8]) -> Option<&'a Object> {
+        self.raw_pdf.dict_get_resolved(self.dict, key)
+    }
+
+    fn text(&self, key: &[u8]) -> Option<String> {
+        text_entry(self.raw_pdf, self.dict, key, "file specification")
+    }
+}
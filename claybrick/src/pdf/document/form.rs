@@ -0,0 +1,233 @@
+//! Form XObjects, content streams that are painted with the `Do` operator.
+
+use std::collections::HashSet;
+
+use crate::{
+    parse::{
+        content::{parse_operations, Operation},
+        error::{CbParseError, CbParseErrorKind},
+    },
+    pdf::{Dictionary, Object, ObjectSource, Rectangle, Stream},
+};
+
+use super::K_RESOURCES;
+
+pub(crate) const K_XOBJECT: &[u8] = b"XObject";
+const K_SUBTYPE: &[u8] = b"Subtype";
+const K_BBOX: &[u8] = b"BBox";
+const K_MATRIX: &[u8] = b"Matrix";
+const FORM: &[u8] = b"Form";
+
+const IDENTITY: [f32; 6] = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// Forms are not entered deeper than this, even if they don't paint
+/// themselves.
+const MAX_FORM_DEPTH: usize = 32;
+
+/// An XObject with `/Subtype /Form`.
+#[derive(Clone)]
+pub struct FormXObject<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    stream: &'a Stream,
+}
+
+// Custom impl to skip `raw_pdf` field.
+impl<'a> std::fmt::Debug for FormXObject<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FormXObject").field("stream", &self.stream).finish()
+    }
+}
+
+impl<'a> FormXObject<'a> {
+    /// The form if `stream` is a form XObject.
+    pub(crate) fn new_with(raw_pdf: &'a dyn ObjectSource, stream: &'a Stream) -> Option<Self> {
+        let subtype = stream.dictionary.get(K_SUBTYPE).and_then(Object::name)?;
+        (&subtype[..] == FORM).then_some(Self { raw_pdf, stream })
+    }
+
+    /// The raw form stream.
+    pub fn stream(&self) -> &'a Stream {
+        self.stream
+    }
+
+    /// The bounding box in form space that the form is clipped to.
+    pub fn bbox(&self) -> Option<Rectangle> {
+        let bbox = self
+            .raw_pdf
+            .dict_get_resolved(&self.stream.dictionary, K_BBOX)?
+            .array()?;
+        Rectangle::try_from(bbox).ok()
+    }
+
+    /// The matrix from form space to the user space of the content that
+    /// paints the form. Defaults to the identity matrix.
+    pub fn matrix(&self) -> [f32; 6] {
+        let matrix = self
+            .raw_pdf
+            .dict_get_resolved(&self.stream.dictionary, K_MATRIX)
+            .and_then(Object::array)
+            .filter(|matrix| matrix.len() == 6)
+            .and_then(|matrix| {
+                matrix
+                    .iter()
+                    .map(|v| v.as_f64().map(|v| v as f32))
+                    .collect::<Option<Vec<_>>>()
+            });
+        match matrix {
+            Some(matrix) => std::array::from_fn(|i| matrix[i]),
+            None => IDENTITY,
+        }
+    }
+
+    /// The resources of the form. Forms without resources use the resources
+    /// of the content that paints them.
+    pub fn resources(&self) -> Option<&'a Dictionary> {
+        self.raw_pdf
+            .dict_get_resolved(&self.stream.dictionary, K_RESOURCES)
+            .and_then(Object::dictionary)
+    }
+
+    /// Parse the decoded content of the form.
+    pub fn operations(&self) -> Result<Vec<Operation>, CbParseError<()>> {
+        let data = self
+            .stream
+            .filtered_data()
+            .map_err(|err| CbParseError::new((), CbParseErrorKind::StreamError(err)))?;
+        parse_operations(&data)
+    }
+}
+
+/// The form XObject with the given name in `resources`.
+pub(crate) fn lookup_form<'a>(
+    raw_pdf: &'a dyn ObjectSource,
+    resources: Option<&'a Dictionary>,
+    name: &[u8],
+) -> Option<FormXObject<'a>> {
+    let xobjects = raw_pdf.dict_get_resolved(resources?, K_XOBJECT)?.dictionary()?;
+    let stream = raw_pdf.dict_get_resolved(xobjects, name)?.stream()?;
+    FormXObject::new_with(raw_pdf, stream)
+}
+
+/// The forms entered by a traversal of content and the forms it paints. Each
+/// form is entered once, which stops at forms that paint themselves and skips
+/// forms that several forms paint.
+#[derive(Debug, Default)]
+pub(crate) struct FormTraversal {
+    visited: HashSet<*const Stream>,
+    depth: usize,
+}
+
+impl FormTraversal {
+    /// Whether to enter `form`, which is the case if it wasn't entered before
+    /// and isn't nested too deep. Entered forms are left with
+    /// [FormTraversal::leave].
+    pub(crate) fn enter(&mut self, form: &Stream) -> bool {
+        if self.depth >= MAX_FORM_DEPTH {
+            log::warn!("Not entering a form nested deeper than {} forms", MAX_FORM_DEPTH);
+            return false;
+        }
+        if !self.visited.insert(form) {
+            return false;
+        }
+        self.depth += 1;
+        true
+    }
+
+    pub(crate) fn leave(&mut self) {
+        self.depth -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf::{document::ResourceCategory, tests::parsed_pdf, RawPdf, Reference};
+
+    fn document() -> RawPdf {
+        parsed_pdf(
+            Reference::new(1, 0),
+            &[
+                b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj",
+                b"2 0 obj <</Type /Pages /Kids [3 0 R] /Count 1>> endobj",
+                b"3 0 obj <</Type /Page /Parent 2 0 R /MediaBox [0 0 100 100] /Contents 4 0 R /Resources <<
+                /Font <</F1 10 0 R>>
+                /XObject <</Fm1 5 0 R /Fm2 6 0 R /Im1 7 0 R>>
+            >>>> endobj",
+                b"4 0 obj <</Length 19>> stream
+q /Fm1 Do /Im1 Do Q
+endstream endobj",
+                b"5 0 obj <</Type /XObject /Subtype /Form /BBox [0 0 50 20] /Matrix [2 0 0 2 10 10] /Length 61>> stream
+BT /F1 12 Tf (Hello from ) Tj [(a) -250 (form)] TJ ET /Fm2 Do
+endstream endobj",
+                b"6 0 obj <</Type /XObject /Subtype /Form /BBox [0 0 1 1] /Length 46>> stream
+BT 0 -14 Td (\\(nested\\)) Tj ET /Fm1 Do /Fm2 Do
+endstream endobj",
+                b"7 0 obj <</Type /XObject /Subtype /Image /Width 1 /Height 1 /Length 0>> stream\n\nendstream endobj",
+                b"10 0 obj <</Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding>> endobj",
+            ],
+        )
+    }
+
+    #[test]
+    fn form_xobjects() {
+        let pdf = document();
+        let page = pdf.page(0).unwrap();
+        let forms = page.form_xobjects();
+        let names = forms.iter().map(|(name, _)| name.to_vec()).collect::<Vec<_>>();
+        assert_eq!(names, vec![b"Fm1".to_vec(), b"Fm2".to_vec()]);
+        let form = &forms[0].1;
+        let bbox = form.bbox().unwrap();
+        assert_eq!((bbox.x1, bbox.y1), (50.0, 20.0));
+        assert_eq!(form.matrix(), [2.0, 0.0, 0.0, 2.0, 10.0, 10.0]);
+        assert!(form.resources().is_none());
+        assert_eq!(forms[1].1.matrix(), super::IDENTITY);
+    }
+
+    #[test]
+    fn text_in_forms() {
+        let pdf = document();
+        // The forms paint each other, which stops at the first repetition.
+        assert_eq!(
+            pdf.page(0).unwrap().extract_text().unwrap(),
+            "Hello from a form\n(nested)"
+        );
+    }
+
+    #[test]
+    fn shared_forms() {
+        // Each form paints the next one twice, the last one shows text.
+        let mut objects = vec![
+            b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj".to_vec(),
+            b"2 0 obj <</Type /Pages /Kids [3 0 R] /Count 1>> endobj".to_vec(),
+            b"3 0 obj <</Type /Page /Parent 2 0 R /MediaBox [0 0 100 100] /Contents 10 0 R
+                /Resources <</XObject <</Fm 11 0 R>>>>>> endobj"
+                .to_vec(),
+            b"4 0 obj <</Type /Font /Subtype /Type1 /BaseFont /Helvetica>> endobj".to_vec(),
+        ];
+        for number in 10..40 {
+            let content = match number {
+                39 => "BT /F1 12 Tf (end) Tj ET".to_owned(),
+                _ => "/Fm Do /Fm Do".to_owned(),
+            };
+            objects.push(
+                format!(
+                    "{} 0 obj <</Subtype /Form /Resources <</XObject <</Fm {} 0 R>> /Font <</F1 4 0 R>>>> /Length {}>> stream\n{}\nendstream endobj",
+                    number,
+                    number + 1,
+                    content.len(),
+                    content
+                )
+                .into_bytes(),
+            );
+        }
+        let pdf = parsed_pdf(
+            Reference::new(1, 0),
+            &objects.iter().map(Vec::as_slice).collect::<Vec<_>>(),
+        );
+        let page = pdf.page(0).unwrap();
+        assert_eq!(page.extract_text().unwrap(), "end");
+        assert!(page
+            .referenced_resources()
+            .unwrap()
+            .contains(ResourceCategory::XObject, b"Fm"));
+    }
+}
@@ -0,0 +1,126 @@
+//! Function dictionaries, e.g. the color functions of shadings.
+
+use crate::pdf::{object::stream::filter::FilterError, Bytes, Dictionary, Object, ObjectSource, Stream};
+
+const K_FUNCTION_TYPE: &[u8] = b"FunctionType";
+const K_DOMAIN: &[u8] = b"Domain";
+const K_RANGE: &[u8] = b"Range";
+const K_FUNCTIONS: &[u8] = b"Functions";
+
+/// The kinds of functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FunctionType {
+    /// Type 0, a table of samples.
+    Sampled,
+    /// Type 2, exponential interpolation between two values.
+    Exponential,
+    /// Type 3, one of several functions depending on the input.
+    Stitching,
+    /// Type 4, a PostScript calculator program.
+    PostScript,
+}
+
+impl FunctionType {
+    fn from_number(number: i32) -> Option<Self> {
+        match number {
+            0 => Some(FunctionType::Sampled),
+            2 => Some(FunctionType::Exponential),
+            3 => Some(FunctionType::Stitching),
+            4 => Some(FunctionType::PostScript),
+            _ => None,
+        }
+    }
+}
+
+/// A function dictionary or stream. Functions are only read, not evaluated.
+#[derive(Clone, PartialEq)]
+pub struct Function<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    object: &'a Object,
+    dict: &'a Dictionary,
+}
+
+// Custom impl to skip `raw_pdf` field.
+impl<'a> std::fmt::Debug for Function<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Function").field("object", &self.object).finish()
+    }
+}
+
+impl<'a> Function<'a> {
+    /// Returns `None` if `object` is neither a dictionary nor a stream.
+    pub(crate) fn new_with(raw_pdf: &'a dyn ObjectSource, object: &'a Object) -> Option<Self> {
+        let object = raw_pdf.resolve(object);
+        let dict = match object {
+            Object::Dictionary(dict) => dict,
+            Object::Stream(stream) => &stream.dictionary,
+            other => {
+                log::warn!("Ignoring function that is not a dictionary: {}", other);
+                return None;
+            }
+        };
+        Some(Self { raw_pdf, object, dict })
+    }
+
+    /// The raw function dictionary.
+    pub fn dictionary(&self) -> &'a Dictionary {
+        self.dict
+    }
+
+    /// The function stream, sampled and PostScript functions are streams.
+    pub fn stream(&self) -> Option<&'a Stream> {
+        self.object.stream()
+    }
+
+    /// The type of the function, `None` if it's unknown.
+    pub fn function_type(&self) -> Option<FunctionType> {
+        self.raw_pdf
+            .dict_get_resolved(self.dict, K_FUNCTION_TYPE)
+            .and_then(Object::integer)
+            .and_then(FunctionType::from_number)
+    }
+
+    /// The intervals of the inputs as `[min0 max0 min1 max1 ...]`.
+    pub fn domain(&self) -> Option<Vec<f32>> {
+        number_array(self.raw_pdf.dict_get_resolved(self.dict, K_DOMAIN)?)
+    }
+
+    /// The intervals the outputs are clipped to as `[min0 max0 min1 max1
+    /// ...]`. Only required for sampled and PostScript functions.
+    pub fn range(&self) -> Option<Vec<f32>> {
+        number_array(self.raw_pdf.dict_get_resolved(self.dict, K_RANGE)?)
+    }
+
+    /// The decoded sample table of a sampled function.
+    pub fn samples(&self) -> Option<Result<Bytes, FilterError>> {
+        if self.function_type() != Some(FunctionType::Sampled) {
+            return None;
+        }
+        self.stream().map(Stream::filtered_data)
+    }
+
+    /// The functions a stitching function combines. Invalid functions are
+    /// skipped.
+    pub fn functions(&self) -> Vec<Function<'a>> {
+        if self.function_type() != Some(FunctionType::Stitching) {
+            return Vec::new();
+        }
+        let raw_pdf = self.raw_pdf;
+        raw_pdf
+            .dict_get_resolved(self.dict, K_FUNCTIONS)
+            .and_then(Object::array)
+            .into_iter()
+            .flat_map(|functions| functions.iter())
+            .filter_map(|function| Function::new_with(raw_pdf, function))
+            .collect()
+    }
+}
+
+/// The values of an array of numbers, `None` if `object` is no such array.
+pub(crate) fn number_array(object: &Object) -> Option<Vec<f32>> {
+    object
+        .array()?
+        .iter()
+        .map(|number| number.as_f64().map(|number| number as f32))
+        .collect()
+}
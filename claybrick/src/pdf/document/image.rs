@@ -0,0 +1,387 @@
+//! Image XObjects, sampled images that are painted with the `Do` operator.
+
+use crate::pdf::{object::stream::filter::FilterError, Bytes, Dictionary, Object, ObjectSource, Stream};
+
+use super::color_space::ColorSpace;
+
+#[cfg(feature = "image")]
+mod rgba;
+
+#[cfg(feature = "image")]
+pub use rgba::{ImageError, RgbaImage};
+
+const K_SUBTYPE: &[u8] = b"Subtype";
+const K_WIDTH: &[u8] = b"Width";
+const K_HEIGHT: &[u8] = b"Height";
+const K_BITS_PER_COMPONENT: &[u8] = b"BitsPerComponent";
+const K_COLOR_SPACE: &[u8] = b"ColorSpace";
+const K_IMAGE_MASK: &[u8] = b"ImageMask";
+const IMAGE: &[u8] = b"Image";
+const K_DECODE_PARMS: &[u8] = b"DecodeParms";
+const K_JBIG2_GLOBALS: &[u8] = b"JBIG2Globals";
+const FILTER_DCT: &[u8] = b"DCTDecode";
+const FILTER_JPX: &[u8] = b"JPXDecode";
+const FILTER_JBIG2: &[u8] = b"JBIG2Decode";
+
+/// Start of image marker.
+const SOI: u8 = 0xd8;
+/// End of image marker.
+const EOI: u8 = 0xd9;
+/// Start of scan marker.
+const SOS: u8 = 0xda;
+/// Temporary private use marker, without a segment like `RSTn`.
+const TEM: u8 = 0x01;
+
+/// An XObject with `/Subtype /Image`.
+#[derive(Clone)]
+pub struct ImageXObject<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    stream: &'a Stream,
+}
+
+// Custom impl to skip `raw_pdf` field.
+impl<'a> std::fmt::Debug for ImageXObject<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ImageXObject").field("stream", &self.stream).finish()
+    }
+}
+
+impl<'a> ImageXObject<'a> {
+    /// The image if `stream` is an image XObject.
+    pub(crate) fn new_with(raw_pdf: &'a dyn ObjectSource, stream: &'a Stream) -> Option<Self> {
+        let subtype = stream.dictionary.get(K_SUBTYPE).and_then(Object::name)?;
+        (&subtype[..] == IMAGE).then_some(Self { raw_pdf, stream })
+    }
+
+    /// The raw image stream.
+    pub fn stream(&self) -> &'a Stream {
+        self.stream
+    }
+
+    /// The width in samples.
+    pub fn width(&self) -> Option<i32> {
+        self.get(K_WIDTH).and_then(Object::integer)
+    }
+
+    /// The height in samples.
+    pub fn height(&self) -> Option<i32> {
+        self.get(K_HEIGHT).and_then(Object::integer)
+    }
+
+    pub fn bits_per_component(&self) -> Option<i32> {
+        self.get(K_BITS_PER_COMPONENT).and_then(Object::integer)
+    }
+
+    /// The color space of the samples, `None` for image masks and JPX images
+    /// that use the color space of their data.
+    pub fn color_space(&self) -> Option<ColorSpace<'a>> {
+        self.stream
+            .dictionary
+            .get(K_COLOR_SPACE)
+            .map(|color_space| ColorSpace::new_with(self.raw_pdf, color_space))
+    }
+
+    /// Whether the image is a stencil mask that paints the current color.
+    pub fn is_mask(&self) -> bool {
+        self.get(K_IMAGE_MASK).and_then(Object::as_bool).unwrap_or(false)
+    }
+
+    /// The data of the image with all filters applied, except for an image
+    /// filter like `DCTDecode` at the end of the chain.
+    pub fn decoded_data(&self) -> Result<ImageData, FilterError> {
+        let (data, image_filter) = self.stream.filtered_data_before_image_filter()?;
+        let encoded = match image_filter.map(|name| &name[..]) {
+            Some(FILTER_DCT) => EncodedImage::Dct(data),
+            Some(FILTER_JPX) => EncodedImage::Jpx(data),
+            Some(FILTER_JBIG2) => EncodedImage::Jbig2 {
+                data,
+                globals: self.jbig2_globals()?,
+            },
+            _ => return Ok(ImageData::Samples(data)),
+        };
+        Ok(ImageData::Encoded(encoded))
+    }
+
+    /// The frame header of the JPEG data of a `DCTDecode` image, `None` if
+    /// `DCTDecode` isn't the last filter. Filters before it are applied, the
+    /// JPEG data itself isn't decoded.
+    pub fn embedded_image_info(&self) -> Option<Result<JpegInfo, JpegError>> {
+        let filters = self.stream.filters().ok()?;
+        if filters.last().map(|name| &name[..]) != Some(FILTER_DCT) {
+            return None;
+        }
+        Some(
+            self.stream
+                .filtered_data_before_image_filter()
+                .map_err(JpegError::Filter)
+                .and_then(|(data, _)| jpeg_info(&data)),
+        )
+    }
+
+    /// The decoded data of the `/JBIG2Globals` stream.
+    fn jbig2_globals(&self) -> Result<Option<Bytes>, FilterError> {
+        let globals = self
+            .last_decode_params()
+            .and_then(|params| self.raw_pdf.dict_get_resolved(params, K_JBIG2_GLOBALS))
+            .and_then(Object::stream);
+        globals.map(Stream::filtered_data).transpose()
+    }
+
+    /// The parameters of the last filter.
+    fn last_decode_params(&self) -> Option<&'a Dictionary> {
+        match self.get(K_DECODE_PARMS)? {
+            Object::Dictionary(params) => Some(params),
+            Object::Array(params) => self.raw_pdf.resolve(params.last()?).dictionary(),
+            _ => None,
+        }
+    }
+
+    fn get(&self, key: &[u8]) -> Option<&'a Object> {
+        self.raw_pdf.dict_get_resolved(&self.stream.dictionary, key)
+    }
+}
+
+/// The data of an image XObject, see [ImageXObject::decoded_data].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageData {
+    /// The samples of the image.
+    Samples(Bytes),
+    /// Data that is still encoded by an image filter.
+    Encoded(EncodedImage),
+}
+
+/// Image data that is still encoded, e.g. to pass it to an external decoder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodedImage {
+    /// A JPEG image.
+    Dct(Bytes),
+    /// A JPEG 2000 codestream or JP2 file.
+    Jpx(Bytes),
+    /// JBIG2 segments in the embedded format, and the segments of the
+    /// `/JBIG2Globals` stream that they refer to. Decoders that expect a
+    /// single stream need the globals first.
+    Jbig2 { data: Bytes, globals: Option<Bytes> },
+}
+
+impl EncodedImage {
+    /// The encoded image data, without JBIG2 globals.
+    pub fn data(&self) -> &Bytes {
+        match self {
+            EncodedImage::Dct(data) | EncodedImage::Jpx(data) | EncodedImage::Jbig2 { data, .. } => data,
+        }
+    }
+}
+
+/// The frame header of JPEG data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JpegInfo {
+    pub width: u16,
+    /// The number of lines, 0 if it's defined after the first scan.
+    pub height: u16,
+    /// The number of color components, e.g. 3 for YCbCr.
+    pub components: u8,
+    /// The sample precision in bits.
+    pub bits: u8,
+    pub progressive: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JpegError {
+    /// The data doesn't start with the start of image marker.
+    NotJpeg,
+    /// The data ends before the frame header.
+    Truncated,
+    /// Bytes that are not a marker where a marker was expected, at the
+    /// offset.
+    InvalidMarker(usize),
+    /// The segment of the marker is too short.
+    InvalidSegment(u8),
+    /// The scan starts or the image ends before the frame header.
+    MissingFrameHeader,
+    /// A filter before `DCTDecode` failed.
+    Filter(FilterError),
+}
+
+/// Whether the marker starts a frame, `SOFn` except `DHT`, `JPG` and `DAC`.
+fn is_start_of_frame(marker: u8) -> bool {
+    matches!(marker, 0xc0..=0xcf) && !matches!(marker, 0xc4 | 0xc8 | 0xcc)
+}
+
+/// Walk the marker segments up to the frame header and read it.
+pub(crate) fn jpeg_info(data: &[u8]) -> Result<JpegInfo, JpegError> {
+    if !data.starts_with(&[0xff, SOI]) {
+        return Err(JpegError::NotJpeg);
+    }
+    let mut offset = 2;
+    loop {
+        if *data.get(offset).ok_or(JpegError::Truncated)? != 0xff {
+            return Err(JpegError::InvalidMarker(offset));
+        }
+        // Markers can be preceded by any number of fill bytes.
+        while data.get(offset) == Some(&0xff) {
+            offset += 1;
+        }
+        let marker = *data.get(offset).ok_or(JpegError::Truncated)?;
+        offset += 1;
+        match marker {
+            SOI | TEM | 0xd0..=0xd7 => continue,
+            EOI | SOS => return Err(JpegError::MissingFrameHeader),
+            _ => {}
+        }
+
+        let length = data.get(offset..offset + 2).ok_or(JpegError::Truncated)?;
+        let length = u16::from_be_bytes([length[0], length[1]]) as usize;
+        if length < 2 {
+            return Err(JpegError::InvalidSegment(marker));
+        }
+        let segment = data.get(offset + 2..offset + length).ok_or(JpegError::Truncated)?;
+        if is_start_of_frame(marker) {
+            // precision, lines, samples per line, components and 3 bytes per
+            // component
+            let components = *segment.get(5).ok_or(JpegError::InvalidSegment(marker))?;
+            if segment.len() < 6 + 3 * components as usize {
+                return Err(JpegError::InvalidSegment(marker));
+            }
+            return Ok(JpegInfo {
+                width: u16::from_be_bytes([segment[3], segment[4]]),
+                height: u16::from_be_bytes([segment[1], segment[2]]),
+                components,
+                bits: segment[0],
+                progressive: matches!(marker, 0xc2 | 0xc6 | 0xca | 0xce),
+            });
+        }
+        offset += length;
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::pdf::{tests::parsed_pdf, RawPdf, Reference};
+
+    use super::*;
+
+    /// The markers of a 3x2 baseline YCbCr JPEG, without entropy coded data.
+    pub(crate) const JPEG: &[u8] = b"\xff\xd8\
+        \xff\xe0\x00\x10JFIF\x00\x01\x01\x00\x00\x01\x00\x01\x00\x00\
+        \xff\xdb\x00\x43\x00\x10\x0b\x0c\x0e\x0c\x0a\x10\x0e\x0d\x0e\x12\x11\x10\x13\x18\x28\x1a\x18\x16\x16\x18\x31\x23\x25\x1d\x28\x3a\x33\x3d\x3c\x39\x33\x38\x37\x40\x48\x5c\x4e\x40\x44\x57\x45\x37\x38\x50\x6d\x51\x57\x5f\x62\x67\x68\x67\x3e\x4d\x71\x79\x70\x64\x78\x5c\x65\x67\x63\
+        \xff\xff\xc0\x00\x11\x08\x00\x02\x00\x03\x03\x01\x22\x00\x02\x11\x01\x03\x11\x01\
+        \xff\xda\x00\x0c\x03\x01\x00\x02\x11\x03\x11\x00\x3f\x00\
+        \xff\xd9";
+
+    fn page_images(jpeg: &[u8], filter: &str, size: &str) -> RawPdf {
+        let image = [
+            format!(
+                "4 0 obj <</Type /XObject /Subtype /Image {} /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter {} /Length {}>> stream\n",
+                size,
+                filter,
+                jpeg.len()
+            )
+            .as_bytes(),
+            jpeg,
+            b"\nendstream endobj",
+        ]
+        .concat();
+        parsed_pdf(Reference::new(1, 0), &[
+            b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj",
+            b"2 0 obj <</Type /Pages /Kids [3 0 R] /Count 1>> endobj",
+            b"3 0 obj <</Type /Page /Parent 2 0 R /MediaBox [0 0 100 100] /Resources <</XObject <</Im1 4 0 R /Im2 5 0 R>>>>>> endobj",
+            &image,
+            b"5 0 obj <</Type /XObject /Subtype /Image /Width 1 /Height 1 /ImageMask true /Length 1>> stream\n\x00\nendstream endobj",
+        ])
+    }
+
+    #[test]
+    fn frame_header() {
+        let info = jpeg_info(JPEG).unwrap();
+        assert_eq!(
+            info,
+            JpegInfo {
+                width: 3,
+                height: 2,
+                components: 3,
+                bits: 8,
+                progressive: false,
+            }
+        );
+
+        let progressive = JPEG.windows(2).position(|marker| marker == b"\xff\xc0").unwrap();
+        let mut jpeg = JPEG.to_vec();
+        jpeg[progressive + 1] = 0xc2;
+        assert!(jpeg_info(&jpeg).unwrap().progressive);
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(jpeg_info(&JPEG[..30]), Err(JpegError::Truncated));
+        assert_eq!(jpeg_info(&JPEG[..JPEG.len() - 2]).unwrap().width, 3);
+        assert_eq!(jpeg_info(b"\x89PNG"), Err(JpegError::NotJpeg));
+        assert_eq!(
+            jpeg_info(b"\xff\xd8\xff\xda\x00\x02"),
+            Err(JpegError::MissingFrameHeader)
+        );
+        assert_eq!(jpeg_info(b"\xff\xd8\x00"), Err(JpegError::InvalidMarker(2)));
+        assert_eq!(
+            jpeg_info(b"\xff\xd8\xff\xc0\x00\x05\x08\x00\x02"),
+            Err(JpegError::InvalidSegment(0xc0))
+        );
+    }
+
+    #[test]
+    fn embedded_image_info() {
+        let pdf = page_images(JPEG, "/DCTDecode", "/Width 3 /Height 2");
+        let page = pdf.page(0).unwrap();
+        let images = page.images();
+        assert_eq!(images.len(), 2);
+        let (name, image) = &images[0];
+        assert_eq!(&name[..], b"Im1");
+        assert_eq!((image.width(), image.height()), (Some(3), Some(2)));
+        assert_eq!(image.bits_per_component(), Some(8));
+        assert_eq!(image.color_space(), Some(ColorSpace::DeviceRgb));
+        assert!(!image.is_mask());
+        assert_eq!(image.embedded_image_info().unwrap().unwrap().width, 3);
+
+        let (_, mask) = &images[1];
+        assert!(mask.is_mask());
+        assert_eq!(mask.color_space(), None);
+        assert_eq!(mask.embedded_image_info(), None);
+
+        // preceding filters are applied
+        let hex = JPEG.iter().map(|b| format!("{:02x}", b)).collect::<String>() + ">";
+        let pdf = page_images(hex.as_bytes(), "[/ASCIIHexDecode /DCTDecode]", "");
+        let (_, image) = &pdf.page(0).unwrap().images()[0];
+        assert_eq!(image.embedded_image_info().unwrap().unwrap().height, 2);
+
+        let pdf = page_images(&JPEG[..30], "/DCTDecode", "");
+        let (_, image) = &pdf.page(0).unwrap().images()[0];
+        assert_eq!(image.embedded_image_info(), Some(Err(JpegError::Truncated)));
+    }
+
+    #[test]
+    fn decoded_data() {
+        let pdf = page_images(JPEG, "/DCTDecode", "");
+        let images = pdf.page(0).unwrap().images();
+        let data = images[0].1.decoded_data().unwrap();
+        assert_eq!(data, ImageData::Encoded(EncodedImage::Dct(JPEG.to_vec().into())));
+        assert_eq!(images[1].1.decoded_data(), Ok(ImageData::Samples(vec![0].into())));
+
+        // segment data in hex, the globals are referenced from the parameters
+        // of the last filter
+        let pdf = parsed_pdf(Reference::new(1, 0), &[
+            b"1 0 obj <</Type /XObject /Subtype /Image /Width 8 /Height 1 /BitsPerComponent 1 /ColorSpace /DeviceGray /Filter [/ASCIIHexDecode /JBIG2Decode] /DecodeParms [null <</JBIG2Globals 2 0 R>>] /Length 9>> stream\n00000001>\nendstream endobj",
+            b"2 0 obj <</Filter /ASCIIHexDecode /Length 9>> stream\n00000000>\nendstream endobj",
+        ]);
+        let stream = pdf.object(1).unwrap().indirect().unwrap().object().stream().unwrap();
+        let image = ImageXObject::new_with(&pdf, stream).unwrap();
+        let ImageData::Encoded(jbig2) = image.decoded_data().unwrap() else {
+            panic!("JBIG2 data is not decoded");
+        };
+        assert_eq!(
+            jbig2,
+            EncodedImage::Jbig2 {
+                data: b"\x00\x00\x00\x01".to_vec().into(),
+                globals: Some(b"\x00\x00\x00\x00".to_vec().into()),
+            }
+        );
+        assert_eq!(&jbig2.data()[..], b"\x00\x00\x00\x01");
+    }
+}
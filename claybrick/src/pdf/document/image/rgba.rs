@@ -0,0 +1,346 @@
+//! Conversion of image XObjects to RGBA pixels.
+
+use jpeg_decoder::PixelFormat;
+
+use crate::pdf::{object::stream::filter::FilterError, Object};
+
+use super::{ColorSpace, EncodedImage, ImageData, ImageXObject};
+
+const K_DECODE: &[u8] = b"Decode";
+const K_SOFT_MASK: &[u8] = b"SMask";
+
+/// Pixels with 8 bit red, green, blue and alpha, row by row from the top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl RgbaImage {
+    /// The pixel in column `x` of row `y`.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let offset = (y as usize * self.width as usize + x as usize) * 4;
+        std::array::from_fn(|i| self.data[offset + i])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageError {
+    Filter(FilterError),
+    /// The JPEG data couldn't be decoded.
+    Jpeg(String),
+    /// The data is still encoded with a filter like `JPXDecode`.
+    UnsupportedEncoding,
+    UnsupportedColorSpace,
+    UnsupportedBitsPerComponent(i32),
+    /// `/Width` or `/Height` is missing or invalid.
+    InvalidSize,
+    /// The pixels of the image don't fit into memory.
+    TooLarge,
+    /// The data contains less samples than the size requires.
+    MissingSamples,
+}
+
+impl From<FilterError> for ImageError {
+    fn from(err: FilterError) -> Self {
+        ImageError::Filter(err)
+    }
+}
+
+/// Samples of an image with their layout.
+struct Samples {
+    width: usize,
+    height: usize,
+    bits: u32,
+    components: usize,
+    /// The bytes of a row, rows start at byte boundaries.
+    row_bytes: usize,
+    data: Vec<u8>,
+}
+
+impl Samples {
+    /// The layout of the samples, `None` if the size of the data overflows.
+    fn new(width: usize, height: usize, bits: u32, components: usize, data: Vec<u8>) -> Option<Self> {
+        let row_bytes = width.checked_mul(components)?.checked_mul(bits as usize)?.div_ceil(8);
+        // The RGBA pixels must fit as well.
+        row_bytes.checked_mul(height)?;
+        width.checked_mul(height)?.checked_mul(4)?;
+        Some(Samples {
+            width,
+            height,
+            bits,
+            components,
+            row_bytes,
+            data,
+        })
+    }
+
+    /// The sample of component `component` of the pixel.
+    fn get(&self, x: usize, y: usize, component: usize) -> u32 {
+        let bit = (x * self.components + component) * self.bits as usize;
+        let offset = y * self.row_bytes + bit / 8;
+        match self.bits {
+            16 => u16::from_be_bytes([self.data[offset], self.data[offset + 1]]) as u32,
+            bits => (self.data[offset] as u32 >> (8 - bits - (bit % 8) as u32)) & ((1 << bits) - 1),
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.data.len() >= self.row_bytes * self.height
+    }
+}
+
+impl<'a> ImageXObject<'a> {
+    /// Convert the image to RGBA pixels.
+    ///
+    /// Supports gray, RGB, CMYK, ICC-based and indexed color spaces with 1 to
+    /// 16 bits per component, `DCTDecode` images and stencil masks. The
+    /// `/Decode` array is applied and an `/SMask` becomes the alpha channel.
+    /// CMYK is converted without color management.
+    pub fn to_rgba(&self) -> Result<RgbaImage, ImageError> {
+        self.convert(true)
+    }
+
+    /// Convert the image, with its `/SMask` if `with_soft_mask` is set. The
+    /// `/SMask` of a soft mask is ignored.
+    fn convert(&self, with_soft_mask: bool) -> Result<RgbaImage, ImageError> {
+        let samples = self.samples()?;
+        let mut image = RgbaImage {
+            width: samples.width as u32,
+            height: samples.height as u32,
+            data: Vec::with_capacity(samples.width * samples.height * 4),
+        };
+        let max = ((1u64 << samples.bits) - 1) as f32;
+
+        if self.is_mask() {
+            // The sample is 0 where the mask paints, unless `/Decode` is `[1 0]`.
+            let paint = match self.decode() {
+                Some(decode) if decode.first() == Some(&1.0) => 1,
+                _ => 0,
+            };
+            for (x, y) in pixels(&samples) {
+                let alpha = if samples.get(x, y, 0) == paint { 255 } else { 0 };
+                image.data.extend([0, 0, 0, alpha]);
+            }
+            return Ok(image);
+        }
+
+        let color_space = self.color_space().ok_or(ImageError::UnsupportedColorSpace)?;
+        let default_max = match &color_space {
+            ColorSpace::Indexed { .. } => max,
+            _ => 1.0,
+        };
+        let decode = self.decode().filter(|decode| decode.len() >= samples.components * 2);
+        let mut components = vec![0.0; samples.components];
+        for (x, y) in pixels(&samples) {
+            for (component, value) in components.iter_mut().enumerate() {
+                let (min, decode_max) = match &decode {
+                    Some(decode) => (decode[component * 2], decode[component * 2 + 1]),
+                    None => (0.0, default_max),
+                };
+                *value = min + samples.get(x, y, component) as f32 * (decode_max - min) / max;
+            }
+            let [r, g, b] = rgb(&color_space, &components).ok_or(ImageError::UnsupportedColorSpace)?;
+            image.data.extend([r, g, b, 255]);
+        }
+
+        let soft_mask = self
+            .get(K_SOFT_MASK)
+            .filter(|_| with_soft_mask)
+            .and_then(Object::stream)
+            .and_then(|stream| ImageXObject::new_with(self.raw_pdf, stream));
+        if let Some(soft_mask) = soft_mask {
+            let alpha = soft_mask.convert(false)?;
+            // The mask can have a different resolution than the image.
+            for (x, y) in pixels(&samples) {
+                let mask_x = x * alpha.width as usize / samples.width;
+                let mask_y = y * alpha.height as usize / samples.height;
+                image.data[(y * samples.width + x) * 4 + 3] = alpha.pixel(mask_x as u32, mask_y as u32)[0];
+            }
+        }
+        Ok(image)
+    }
+
+    /// The decoded samples and their layout.
+    fn samples(&self) -> Result<Samples, ImageError> {
+        let size = |value: Option<i32>| value.and_then(|v| usize::try_from(v).ok()).filter(|&v| v > 0);
+        let samples = match self.decoded_data()? {
+            ImageData::Samples(data) => {
+                let bits = match self.is_mask() {
+                    true => 1,
+                    false => self.bits_per_component().unwrap_or(8),
+                };
+                if ![1, 2, 4, 8, 16].contains(&bits) {
+                    return Err(ImageError::UnsupportedBitsPerComponent(bits));
+                }
+                let components = match self.is_mask() {
+                    true => 1,
+                    false => self
+                        .color_space()
+                        .and_then(|color_space| color_space.components())
+                        .ok_or(ImageError::UnsupportedColorSpace)? as usize,
+                };
+                let width = size(self.width()).ok_or(ImageError::InvalidSize)?;
+                let height = size(self.height()).ok_or(ImageError::InvalidSize)?;
+                Samples::new(width, height, bits as u32, components, data.into_vec()).ok_or(ImageError::TooLarge)?
+            }
+            ImageData::Encoded(EncodedImage::Dct(data)) => {
+                let mut decoder = jpeg_decoder::Decoder::new(&data[..]);
+                let pixels = decoder.decode().map_err(|err| ImageError::Jpeg(err.to_string()))?;
+                let info = decoder.info().ok_or(ImageError::InvalidSize)?;
+                let (bits, components) = match info.pixel_format {
+                    PixelFormat::L8 => (8, 1),
+                    PixelFormat::L16 => (16, 1),
+                    PixelFormat::RGB24 => (8, 3),
+                    PixelFormat::CMYK32 => (8, 4),
+                };
+                Samples::new(info.width as usize, info.height as usize, bits, components, pixels)
+                    .ok_or(ImageError::TooLarge)?
+            }
+            ImageData::Encoded(_) => return Err(ImageError::UnsupportedEncoding),
+        };
+        match samples.is_complete() {
+            true => Ok(samples),
+            false => Err(ImageError::MissingSamples),
+        }
+    }
+
+    /// The `/Decode` array, ranges that the samples are mapped to.
+    fn decode(&self) -> Option<Vec<f32>> {
+        self.get(K_DECODE)?
+            .array()?
+            .iter()
+            .map(|value| self.raw_pdf.resolve(value).as_f64().map(|v| v as f32))
+            .collect()
+    }
+}
+
+fn pixels(samples: &Samples) -> impl Iterator<Item = (usize, usize)> {
+    let width = samples.width;
+    (0..samples.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+}
+
+/// Convert the components, in the range 0 to 1 or the index of indexed
+/// colors, to RGB.
+fn rgb(color_space: &ColorSpace, components: &[f32]) -> Option<[u8; 3]> {
+    let byte = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    match (color_space, components) {
+        (ColorSpace::DeviceGray | ColorSpace::CalGray(_) | ColorSpace::IccBased { n: 1, .. }, [gray]) => {
+            Some([byte(*gray); 3])
+        }
+        (ColorSpace::DeviceRgb | ColorSpace::CalRgb(_) | ColorSpace::IccBased { n: 3, .. }, [r, g, b]) => {
+            Some([byte(*r), byte(*g), byte(*b)])
+        }
+        (ColorSpace::DeviceCmyk | ColorSpace::IccBased { n: 4, .. }, [c, m, y, k]) => {
+            let white = 1.0 - k.clamp(0.0, 1.0);
+            Some([
+                byte((1.0 - c) * white),
+                byte((1.0 - m) * white),
+                byte((1.0 - y) * white),
+            ])
+        }
+        (ColorSpace::Indexed { base, hival, lookup }, [index]) => {
+            let index = (index.round().max(0.0) as usize).min(*hival as usize);
+            let n = base.components()? as usize;
+            let color = lookup.get(index * n..(index + 1) * n)?;
+            let color = color.iter().map(|&c| c as f32 / 255.0).collect::<Vec<_>>();
+            rgb(base, &color)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf::{tests::parsed_pdf, RawPdf, Reference};
+
+    use super::*;
+
+    fn document(objects: &[&[u8]]) -> RawPdf {
+        parsed_pdf(Reference::new(1, 0), objects)
+    }
+
+    fn image(pdf: &RawPdf, number: usize) -> ImageXObject<'_> {
+        let stream = pdf
+            .object(number)
+            .unwrap()
+            .indirect()
+            .unwrap()
+            .object()
+            .stream()
+            .unwrap();
+        ImageXObject::new_with(pdf, stream).unwrap()
+    }
+
+    #[test]
+    fn indexed() {
+        // red, green, blue and white, the rows are 0 1 2 3 and 3 2 1 0
+        let pdf = document(&[
+            b"1 0 obj <</Subtype /Image /Width 4 /Height 2 /BitsPerComponent 4 /ColorSpace [/Indexed /DeviceRGB 3 <ff000000ff000000ffffffff>] /Filter /ASCIIHexDecode /Length 9>> stream\n01233210>\nendstream endobj",
+        ]);
+        let rgba = image(&pdf, 1).to_rgba().unwrap();
+        assert_eq!((rgba.width, rgba.height), (4, 2));
+        assert_eq!(rgba.pixel(0, 0), [255, 0, 0, 255]);
+        assert_eq!(rgba.pixel(1, 0), [0, 255, 0, 255]);
+        assert_eq!(rgba.pixel(3, 0), [255, 255, 255, 255]);
+        assert_eq!(rgba.pixel(1, 1), [0, 0, 255, 255]);
+        assert_eq!(rgba.pixel(3, 1), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn soft_mask() {
+        let pdf = document(&[
+            b"1 0 obj <</Subtype /Image /Width 2 /Height 2 /BitsPerComponent 8 /ColorSpace /DeviceGray /SMask 2 0 R /Filter /ASCIIHexDecode /Length 9>> stream\n0080ff40>\nendstream endobj",
+            b"2 0 obj <</Subtype /Image /Width 1 /Height 2 /BitsPerComponent 8 /ColorSpace /DeviceGray /Filter /ASCIIHexDecode /Length 5>> stream\nff80>\nendstream endobj",
+            b"3 0 obj <</Subtype /Image /Width 2 /Height 2 /BitsPerComponent 1 /ColorSpace /DeviceGray /Decode [1 0] /Filter /ASCIIHexDecode /Length 5>> stream\n8040>\nendstream endobj",
+            b"4 0 obj <</Subtype /Image /Width 2 /Height 1 /ImageMask true /Filter /ASCIIHexDecode /Length 3>> stream\n40>\nendstream endobj",
+            b"5 0 obj <</Subtype /Image /Width 2 /Height 2 /BitsPerComponent 8 /ColorSpace /DeviceGray /Length 3>> stream\n\x00\x00\x00\nendstream endobj",
+        ]);
+        let rgba = image(&pdf, 1).to_rgba().unwrap();
+        assert_eq!(rgba.pixel(0, 0), [0, 0, 0, 255]);
+        assert_eq!(rgba.pixel(1, 0), [128, 128, 128, 255]);
+        assert_eq!(rgba.pixel(0, 1), [255, 255, 255, 128]);
+        assert_eq!(rgba.pixel(1, 1), [64, 64, 64, 128]);
+
+        // inverted 1 bit gray
+        let rgba = image(&pdf, 3).to_rgba().unwrap();
+        assert_eq!(rgba.pixel(0, 0), [0, 0, 0, 255]);
+        assert_eq!(rgba.pixel(1, 0), [255, 255, 255, 255]);
+        assert_eq!(rgba.pixel(1, 1), [0, 0, 0, 255]);
+
+        // stencil mask, 0 paints
+        let rgba = image(&pdf, 4).to_rgba().unwrap();
+        assert_eq!(rgba.data, vec![0, 0, 0, 255, 0, 0, 0, 0]);
+
+        assert_eq!(image(&pdf, 5).to_rgba(), Err(ImageError::MissingSamples));
+    }
+
+    #[test]
+    fn too_large() {
+        let pdf = document(&[
+            b"1 0 obj <</Subtype /Image /Width 2147483647 /Height 2147483647 /BitsPerComponent 16 /ColorSpace /DeviceCMYK /Length 1>> stream\n\x00\nendstream endobj",
+        ]);
+        assert_eq!(image(&pdf, 1).to_rgba(), Err(ImageError::TooLarge));
+    }
+
+    #[test]
+    fn soft_mask_cycle() {
+        let pdf = document(&[
+            b"1 0 obj <</Subtype /Image /Width 1 /Height 1 /BitsPerComponent 8 /ColorSpace /DeviceGray /SMask 2 0 R /Filter /ASCIIHexDecode /Length 3>> stream\nff>\nendstream endobj",
+            b"2 0 obj <</Subtype /Image /Width 1 /Height 1 /BitsPerComponent 8 /ColorSpace /DeviceGray /SMask 1 0 R /Filter /ASCIIHexDecode /Length 3>> stream\n80>\nendstream endobj",
+        ]);
+        assert_eq!(image(&pdf, 1).to_rgba().unwrap().pixel(0, 0), [255, 255, 255, 128]);
+        assert_eq!(image(&pdf, 2).to_rgba().unwrap().pixel(0, 0), [128, 128, 128, 255]);
+    }
+
+    #[test]
+    fn cmyk() {
+        assert_eq!(rgb(&ColorSpace::DeviceCmyk, &[0.0, 1.0, 1.0, 0.0]), Some([255, 0, 0]));
+        assert_eq!(
+            rgb(&ColorSpace::DeviceCmyk, &[0.0, 0.0, 0.0, 0.5]),
+            Some([128, 128, 128])
+        );
+        assert_eq!(rgb(&ColorSpace::DeviceCmyk, &[0.0, 0.0]), None);
+    }
+}
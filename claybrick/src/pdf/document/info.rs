@@ -0,0 +1,154 @@
+use crate::pdf::{date::PdfDate, text::text_entry, Dictionary, Object, ObjectSource};
+
+const K_TITLE: &[u8] = b"Title";
+const K_AUTHOR: &[u8] = b"Author";
+const K_SUBJECT: &[u8] = b"Subject";
+const K_KEYWORDS: &[u8] = b"Keywords";
+const K_CREATOR: &[u8] = b"Creator";
+const K_PRODUCER: &[u8] = b"Producer";
+const K_CREATION_DATE: &[u8] = b"CreationDate";
+const K_MOD_DATE: &[u8] = b"ModDate";
+
+/// The document information dictionary.
+#[derive(Clone, PartialEq)]
+pub struct DocumentInfo<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    dict: &'a Dictionary,
+}
+
+// Custom impl to skip `raw_pdf` field.
+impl<'a> std::fmt::Debug for DocumentInfo<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DocumentInfo")
+            .field("title", &self.title())
+            .field("author", &self.author())
+            .field("subject", &self.subject())
+            .field("keywords", &self.keywords())
+            .field("creator", &self.creator())
+            .field("producer", &self.producer())
+            .field("creation_date", &self.creation_date())
+            .field("mod_date", &self.mod_date())
+            .finish()
+    }
+}
+
+impl<'a> DocumentInfo<'a> {
+    pub(crate) fn new_with(raw_pdf: &'a dyn ObjectSource, dict: &'a Dictionary) -> Self {
+        Self { raw_pdf, dict }
+    }
+
+    /// The raw information dictionary.
+    pub fn dictionary(&self) -> &'a Dictionary {
+        self.dict
+    }
+
+    pub fn title(&self) -> Option<String> {
+        self.text(K_TITLE)
+    }
+
+    pub fn author(&self) -> Option<String> {
+        self.text(K_AUTHOR)
+    }
+
+    pub fn subject(&self) -> Option<String> {
+        self.text(K_SUBJECT)
+    }
+
+    pub fn keywords(&self) -> Option<String> {
+        self.text(K_KEYWORDS)
+    }
+
+    /// The application that created the original document.
+    pub fn creator(&self) -> Option<String> {
+        self.text(K_CREATOR)
+    }
+
+    /// The application that converted the document to PDF.
+    pub fn producer(&self) -> Option<String> {
+        self.text(K_PRODUCER)
+    }
+
+    pub fn creation_date(&self) -> Option<PdfDate> {
+        self.date(K_CREATION_DATE)
+    }
+
+    pub fn mod_date(&self) -> Option<PdfDate> {
+        self.date(K_MOD_DATE)
+    }
+
+    /// Raw bytes of a string entry.
+    fn bytes(&self, key: &[u8]) -> Option<Vec<u8>> {
+        match self.raw_pdf.dict_get_resolved(self.dict, key)? {
+            Object::String(s) => Some(s.unescaped()),
+            Object::HexString(b) => Some(b.to_vec()),
+            other => {
+                log::warn!(
+                    "Expected string for info entry `{}`, got {}",
+                    String::from_utf8_lossy(key),
+                    other
+                );
+                None
+            }
+        }
+    }
+
+    fn text(&self, key: &[u8]) -> Option<String> {
+        text_entry(self.raw_pdf, self.dict, key, "info")
+    }
+
+    fn date(&self, key: &[u8]) -> Option<PdfDate> {
+        let bytes = self.bytes(key)?;
+        PdfDate::parse(&bytes)
+            .map_err(|err| log::warn!("Invalid date `{}`: {:?}", String::from_utf8_lossy(&bytes), err))
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::object::object,
+        pdf::{tests::raw_pdf, Reference},
+    };
+
+    use super::*;
+
+    #[test]
+    fn info_from_object() {
+        let info = object(
+            b"20 0 obj
+<</Author<FEFF004A006F0073002000760061006E002000640065006E0020004F0065007600650072>
+/Creator<FEFF005700720069007400650072>
+/Producer<FEFF004C0069006200720065004F0066006600690063006500200035002E0033>
+/Title(Caf\\351 \\(draft\\))
+/CreationDate(D:20170913090857+02'00')>>
+endobj
+"[..]
+                .into(),
+        )
+        .unwrap()
+        .1;
+        let mut pdf = raw_pdf(Reference::new(1, 0), vec![info.indirect().unwrap().clone()]);
+        pdf.sections[0].trailer.info = Some(Reference::new(20, 0));
+
+        let info = pdf.info().unwrap();
+        assert_eq!(info.author().as_deref(), Some("Jos van den Oever"));
+        assert_eq!(info.creator().as_deref(), Some("Writer"));
+        assert_eq!(info.producer().as_deref(), Some("LibreOffice 5.3"));
+        assert_eq!(info.title().as_deref(), Some("Café (draft)"));
+        assert_eq!(info.subject(), None);
+        assert_eq!(
+            info.creation_date(),
+            Some(PdfDate {
+                year: 2017,
+                month: Some(9),
+                day: Some(13),
+                hour: Some(9),
+                minute: Some(8),
+                second: Some(57),
+                utc_offset_minutes: Some(120),
+            })
+        );
+        assert_eq!(info.mod_date(), None);
+    }
+}
@@ -0,0 +1,318 @@
+use std::collections::HashSet;
+
+use crate::pdf::{Array, CbString, Dictionary, Object, ObjectSource};
+
+use super::{K_KIDS, K_NAMES};
+
+/// Name tree of named destinations.
+pub const DESTS: &[u8] = b"Dests";
+/// Name tree of embedded file streams.
+pub const EMBEDDED_FILES: &[u8] = b"EmbeddedFiles";
+/// Name tree of document-level JavaScript actions.
+pub const JAVASCRIPT: &[u8] = b"JavaScript";
+
+const K_LIMITS: &[u8] = b"Limits";
+
+/// Upper bound for the depth of a name tree. Protects against cycles.
+const MAX_DEPTH: usize = 64;
+
+/// A tree that maps strings to objects.
+///
+/// Intermediate nodes contain `Kids` and the `Limits` of their subtree, leaf
+/// nodes contain a sorted `Names` array `[key1 value1 key2 value2 ...]`.
+#[derive(Clone, PartialEq)]
+pub struct NameTree<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    root: &'a Dictionary,
+}
+
+// Custom impl to skip `raw_pdf` field.
+impl<'a> std::fmt::Debug for NameTree<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NameTree").field("root", &self.root).finish()
+    }
+}
+
+/// The bytes of a string key.
+fn key_bytes(obj: &Object) -> Option<Vec<u8>> {
+    match obj {
+        Object::String(s) => Some(s.unescaped()),
+        Object::HexString(b) => Some(b.to_vec()),
+        _ => None,
+    }
+}
+
+impl<'a> NameTree<'a> {
+    pub fn new_with(raw_pdf: &'a dyn ObjectSource, root: &'a Dictionary) -> Self {
+        Self { raw_pdf, root }
+    }
+
+    /// Find the value for `key`. References are resolved.
+    ///
+    /// The `Limits` of intermediate nodes are used to find the right leaf. If
+    /// that fails, the whole tree is searched in case the limits are wrong.
+    pub fn get(&self, key: &[u8]) -> Option<&'a Object> {
+        if let Some(value) = self.find(key) {
+            return Some(value);
+        }
+
+        let value = self.iter().find(|(k, _)| &k[..] == key).map(|(_, v)| v);
+        if value.is_some() {
+            log::warn!(
+                "Name tree limits are inconsistent, found `{}` using a linear search",
+                String::from_utf8_lossy(key)
+            );
+        }
+        value
+    }
+
+    /// Iterate over all entries in key order. References are resolved.
+    pub fn iter(&self) -> NameTreeIter<'a> {
+        let mut iter = NameTreeIter {
+            raw_pdf: self.raw_pdf,
+            stack: Vec::new(),
+            visited: HashSet::new(),
+        };
+        iter.push_node(self.root);
+        iter
+    }
+
+    fn names(&self, node: &'a Dictionary) -> Option<&'a Array> {
+        self.raw_pdf.dict_get_resolved(node, K_NAMES).and_then(Object::array)
+    }
+
+    fn kids(&self, node: &'a Dictionary) -> Option<&'a Array> {
+        self.raw_pdf.dict_get_resolved(node, K_KIDS).and_then(Object::array)
+    }
+
+    /// Whether `key` is within the limits of the node. Nodes without valid
+    /// limits might contain any key.
+    fn in_limits(&self, node: &Dictionary, key: &[u8]) -> bool {
+        let limits = self
+            .raw_pdf
+            .dict_get_resolved(node, K_LIMITS)
+            .and_then(Object::array)
+            .filter(|l| l.len() == 2)
+            .and_then(|l| key_bytes(&l[0]).zip(key_bytes(&l[1])));
+        match limits {
+            Some((low, high)) => &low[..] <= key && key <= &high[..],
+            None => true,
+        }
+    }
+
+    /// Limits guided search. Descends into the first kid whose limits
+    /// contain `key`.
+    fn find(&self, key: &[u8]) -> Option<&'a Object> {
+        let mut node = self.root;
+        let mut visited = HashSet::new();
+        for _ in 0..=MAX_DEPTH {
+            if let Some(names) = self.names(node) {
+                for pair in names.chunks_exact(2) {
+                    if key_bytes(&pair[0]).as_deref() == Some(key) {
+                        return Some(self.raw_pdf.resolve(&pair[1]));
+                    }
+                }
+            }
+
+            let kid = self.kids(node)?.iter().find(|kid| {
+                self.raw_pdf
+                    .resolve(kid)
+                    .dictionary()
+                    .is_some_and(|kid| self.in_limits(kid, key))
+            })?;
+            if let Some(r) = kid.reference() {
+                if !visited.insert(r.index) {
+                    log::error!("Name tree contains a cycle at {:?}", r);
+                    return None;
+                }
+            }
+            node = self.raw_pdf.resolve(kid).dictionary()?;
+        }
+        log::error!("Name tree exceeds the maximum depth of {}", MAX_DEPTH);
+        None
+    }
+}
+
+enum Frame<'a> {
+    Kids(std::slice::Iter<'a, Object>),
+    Names(std::slice::ChunksExact<'a, Object>),
+}
+
+/// Depth-first iterator over the entries of a name tree.
+pub struct NameTreeIter<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    stack: Vec<Frame<'a>>,
+    /// Object numbers of all visited kids.
+    visited: HashSet<u32>,
+}
+
+impl<'a> NameTreeIter<'a> {
+    fn push_node(&mut self, node: &'a Dictionary) {
+        if let Some(kids) = self.raw_pdf.dict_get_resolved(node, K_KIDS).and_then(Object::array) {
+            self.stack.push(Frame::Kids(kids.iter()));
+        }
+        if let Some(names) = self.raw_pdf.dict_get_resolved(node, K_NAMES).and_then(Object::array) {
+            self.stack.push(Frame::Names(names.chunks_exact(2)));
+        }
+    }
+}
+
+impl<'a> Iterator for NameTreeIter<'a> {
+    type Item = (CbString, &'a Object);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.last_mut()? {
+                Frame::Names(names) => match names.next() {
+                    Some(pair) => match key_bytes(&pair[0]) {
+                        Some(key) => return Some((key.into(), self.raw_pdf.resolve(&pair[1]))),
+                        None => log::warn!("Ignoring name tree entry with invalid key {}", pair[0]),
+                    },
+                    None => {
+                        self.stack.pop();
+                    }
+                },
+                Frame::Kids(kids) => match kids.next() {
+                    Some(kid) => {
+                        if let Some(r) = kid.reference() {
+                            if !self.visited.insert(r.index) {
+                                log::error!("Name tree contains a cycle at {:?}", r);
+                                continue;
+                            }
+                        }
+                        match self.raw_pdf.resolve(kid).dictionary() {
+                            Some(node) => self.push_node(node),
+                            None => log::warn!("Ignoring invalid name tree kid {}", kid),
+                        }
+                    }
+                    None => {
+                        self.stack.pop();
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf::{tests::raw_pdf, IndirectObject, RawPdf, Reference};
+
+    use super::*;
+
+    fn string(s: &[u8]) -> Object {
+        Object::String(s.to_vec().into())
+    }
+
+    fn leaf(entries: &[(&[u8], i32)], limits: Option<(&[u8], &[u8])>) -> Dictionary {
+        let names = entries
+            .iter()
+            .flat_map(|(k, v)| [string(k), Object::Integer(*v)])
+            .collect::<Vec<_>>();
+        let mut dict = Dictionary::from([(K_NAMES.to_vec().into(), Object::from(names))]);
+        if let Some((low, high)) = limits {
+            dict.insert(K_LIMITS.to_vec().into(), Object::from(vec![string(low), string(high)]));
+        }
+        dict
+    }
+
+    fn two_level(first_limits: (&[u8], &[u8])) -> (RawPdf, Dictionary) {
+        let pdf = raw_pdf(
+            Reference::new(1, 0),
+            vec![
+                IndirectObject::new(
+                    2,
+                    0,
+                    Object::Dictionary(leaf(&[(b"Apple", 1), (b"Banana", 2)], Some(first_limits))),
+                ),
+                IndirectObject::new(
+                    3,
+                    0,
+                    Object::Dictionary(leaf(&[(b"Cherry", 3), (b"Date", 4)], Some((b"Cherry", b"Date")))),
+                ),
+            ],
+        );
+        let root = Dictionary::from([(
+            K_KIDS.to_vec().into(),
+            Object::from(vec![
+                Object::Reference(Reference::new(2, 0)),
+                Object::Reference(Reference::new(3, 0)),
+            ]),
+        )]);
+        (pdf, root)
+    }
+
+    #[test]
+    fn flat_leaf() {
+        let pdf = raw_pdf(Reference::new(1, 0), vec![]);
+        let root = leaf(&[(b"A", 1), (b"B", 2)], None);
+        let tree = NameTree::new_with(&pdf, &root);
+
+        assert_eq!(tree.get(b"A"), Some(&Object::Integer(1)));
+        assert_eq!(tree.get(b"B"), Some(&Object::Integer(2)));
+        assert_eq!(tree.get(b"C"), None);
+        assert_eq!(
+            tree.iter().collect::<Vec<_>>(),
+            vec![
+                (b"A".to_vec().into(), &Object::Integer(1)),
+                (b"B".to_vec().into(), &Object::Integer(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn two_level_tree() {
+        let (pdf, root) = two_level((b"Apple", b"Banana"));
+        let tree = NameTree::new_with(&pdf, &root);
+
+        let keys = tree.iter().map(|(k, _)| k.to_vec()).collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            vec![
+                b"Apple".to_vec(),
+                b"Banana".to_vec(),
+                b"Cherry".to_vec(),
+                b"Date".to_vec()
+            ]
+        );
+    }
+
+    #[test]
+    fn lookup_across_kids() {
+        let (pdf, root) = two_level((b"Apple", b"Banana"));
+        let tree = NameTree::new_with(&pdf, &root);
+
+        assert_eq!(tree.get(b"Apple"), Some(&Object::Integer(1)));
+        assert_eq!(tree.get(b"Banana"), Some(&Object::Integer(2)));
+        assert_eq!(tree.get(b"Cherry"), Some(&Object::Integer(3)));
+        assert_eq!(tree.get(b"Date"), Some(&Object::Integer(4)));
+        assert_eq!(tree.get(b"Blueberry"), None);
+    }
+
+    #[test]
+    fn invalid_limits() {
+        // The limits of the first kid exclude `Banana`.
+        let (pdf, root) = two_level((b"Apple", b"Avocado"));
+        let tree = NameTree::new_with(&pdf, &root);
+
+        assert_eq!(tree.get(b"Banana"), Some(&Object::Integer(2)));
+    }
+
+    #[test]
+    fn self_referencing_kids() {
+        let kids = Object::from(vec![
+            Object::Reference(Reference::new(2, 0)),
+            Object::Reference(Reference::new(2, 0)),
+        ]);
+        let node = Dictionary::from([(K_KIDS.to_vec().into(), kids.clone())]);
+        let pdf = raw_pdf(
+            Reference::new(1, 0),
+            vec![IndirectObject::new(2, 0, Object::Dictionary(node))],
+        );
+        let root = Dictionary::from([(K_KIDS.to_vec().into(), kids)]);
+        let tree = NameTree::new_with(&pdf, &root);
+
+        assert_eq!(tree.get(b"A"), None);
+        assert_eq!(tree.iter().count(), 0);
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::HashSet;
+
+use crate::pdf::{Array, Dictionary, Object, ObjectSource};
+
+use super::K_KIDS;
+
+const K_NUMS: &[u8] = b"Nums";
+const K_LIMITS: &[u8] = b"Limits";
+
+/// Upper bound for the depth of a number tree. Protects against cycles.
+const MAX_DEPTH: usize = 64;
+
+/// A tree that maps integers to objects.
+///
+/// Same structure as a [`NameTree`](super::NameTree), but leaf nodes contain a
+/// sorted `Nums` array `[key1 value1 key2 value2 ...]` with integer keys.
+#[derive(Clone, PartialEq)]
+pub struct NumberTree<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    root: &'a Dictionary,
+}
+
+// Custom impl to skip `raw_pdf` field.
+impl<'a> std::fmt::Debug for NumberTree<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NumberTree").field("root", &self.root).finish()
+    }
+}
+
+impl<'a> NumberTree<'a> {
+    pub fn new_with(raw_pdf: &'a dyn ObjectSource, root: &'a Dictionary) -> Self {
+        Self { raw_pdf, root }
+    }
+
+    /// Find the value for `n`. References are resolved.
+    ///
+    /// The `Limits` of intermediate nodes are used to find the right leaf. If
+    /// that fails, the whole tree is searched in case the limits are wrong.
+    pub fn get(&self, n: i64) -> Option<&'a Object> {
+        if let Some(value) = self.find(n) {
+            return Some(value);
+        }
+
+        let value = self.iter().find(|(k, _)| *k == n).map(|(_, v)| v);
+        if value.is_some() {
+            log::warn!("Number tree limits are inconsistent, found {} using a linear search", n);
+        }
+        value
+    }
+
+    /// Find the entry with the greatest key less than or equal to `n`.
+    pub fn range_lookup(&self, n: i64) -> Option<(i64, &'a Object)> {
+        if let Some(entry) = self.find_range(n) {
+            return Some(entry);
+        }
+
+        let entry = self.iter().filter(|(k, _)| *k <= n).max_by_key(|(k, _)| *k);
+        if entry.is_some() {
+            log::warn!("Number tree limits are inconsistent, found {} using a linear search", n);
+        }
+        entry
+    }
+
+    /// Iterate over all entries in key order. References are resolved.
+    pub fn iter(&self) -> NumberTreeIter<'a> {
+        let mut iter = NumberTreeIter {
+            raw_pdf: self.raw_pdf,
+            stack: Vec::new(),
+            visited: HashSet::new(),
+        };
+        iter.push_node(self.root);
+        iter
+    }
+
+    fn nums(&self, node: &'a Dictionary) -> Option<&'a Array> {
+        self.raw_pdf.dict_get_resolved(node, K_NUMS).and_then(Object::array)
+    }
+
+    /// The kids of the node with their dictionaries.
+    fn kids(&self, node: &'a Dictionary) -> impl DoubleEndedIterator<Item = (&'a Object, &'a Dictionary)> + 'a {
+        let raw_pdf = self.raw_pdf;
+        raw_pdf
+            .dict_get_resolved(node, K_KIDS)
+            .and_then(Object::array)
+            .into_iter()
+            .flat_map(|kids| kids.iter())
+            .filter_map(move |kid| Some((kid, raw_pdf.resolve(kid).dictionary()?)))
+    }
+
+    /// Entries of a leaf node with a valid key.
+    fn entries(&self, node: &'a Dictionary) -> impl Iterator<Item = (i64, &'a Object)> + 'a {
+        let raw_pdf = self.raw_pdf;
+        self.nums(node)
+            .into_iter()
+            .flat_map(|nums| nums.chunks_exact(2))
+            .filter_map(move |pair| Some((i64::from(pair[0].integer()?), raw_pdf.resolve(&pair[1]))))
+    }
+
+    /// The limits of the node, if they are valid.
+    fn limits(&self, node: &Dictionary) -> Option<(i64, i64)> {
+        let limits = self
+            .raw_pdf
+            .dict_get_resolved(node, K_LIMITS)
+            .and_then(Object::array)
+            .filter(|l| l.len() == 2)?;
+        Some((i64::from(limits[0].integer()?), i64::from(limits[1].integer()?)))
+    }
+
+    /// Walk down from the root until `entry` finds a value in a node. `pick`
+    /// selects the only kid of a node that is searched.
+    fn search<T>(
+        &self,
+        entry: impl Fn(&'a Dictionary) -> Option<T>,
+        pick: impl Fn(&'a Dictionary) -> Option<(&'a Object, &'a Dictionary)>,
+    ) -> Option<T> {
+        let mut node = self.root;
+        let mut visited = HashSet::new();
+        for _ in 0..=MAX_DEPTH {
+            if let Some(value) = entry(node) {
+                return Some(value);
+            }
+
+            let (kid, next) = pick(node)?;
+            if let Some(r) = kid.reference() {
+                if !visited.insert(r.index) {
+                    log::error!("Number tree contains a cycle at {:?}", r);
+                    return None;
+                }
+            }
+            node = next;
+        }
+        log::error!("Number tree exceeds the maximum depth of {}", MAX_DEPTH);
+        None
+    }
+
+    /// Limits guided search.
+    fn find(&self, n: i64) -> Option<&'a Object> {
+        self.search(
+            |node| self.entries(node).find(|(k, _)| *k == n).map(|(_, v)| v),
+            |node| {
+                self.kids(node)
+                    .find(|(_, kid)| self.limits(kid).is_none_or(|(low, high)| low <= n && n <= high))
+            },
+        )
+    }
+
+    /// Limits guided search for the greatest key less than or equal to `n`.
+    fn find_range(&self, n: i64) -> Option<(i64, &'a Object)> {
+        self.search(
+            |node| self.entries(node).filter(|(k, _)| *k <= n).max_by_key(|(k, _)| *k),
+            // Kids are sorted, the last kid that starts before `n` contains the entry.
+            |node| {
+                self.kids(node)
+                    .rev()
+                    .find(|(_, kid)| self.limits(kid).is_none_or(|(low, _)| low <= n))
+            },
+        )
+    }
+}
+
+enum Frame<'a> {
+    Kids(std::slice::Iter<'a, Object>),
+    Nums(std::slice::ChunksExact<'a, Object>),
+}
+
+/// Depth-first iterator over the entries of a number tree.
+pub struct NumberTreeIter<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    stack: Vec<Frame<'a>>,
+    /// Object numbers of all visited kids.
+    visited: HashSet<u32>,
+}
+
+impl<'a> NumberTreeIter<'a> {
+    fn push_node(&mut self, node: &'a Dictionary) {
+        if let Some(kids) = self.raw_pdf.dict_get_resolved(node, K_KIDS).and_then(Object::array) {
+            self.stack.push(Frame::Kids(kids.iter()));
+        }
+        if let Some(nums) = self.raw_pdf.dict_get_resolved(node, K_NUMS).and_then(Object::array) {
+            self.stack.push(Frame::Nums(nums.chunks_exact(2)));
+        }
+    }
+}
+
+impl<'a> Iterator for NumberTreeIter<'a> {
+    type Item = (i64, &'a Object);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.last_mut()? {
+                Frame::Nums(nums) => match nums.next() {
+                    Some(pair) => match pair[0].integer() {
+                        Some(key) => return Some((i64::from(key), self.raw_pdf.resolve(&pair[1]))),
+                        None => log::warn!("Ignoring number tree entry with invalid key {}", pair[0]),
+                    },
+                    None => {
+                        self.stack.pop();
+                    }
+                },
+                Frame::Kids(kids) => match kids.next() {
+                    Some(kid) => {
+                        if let Some(r) = kid.reference() {
+                            if !self.visited.insert(r.index) {
+                                log::error!("Number tree contains a cycle at {:?}", r);
+                                continue;
+                            }
+                        }
+                        match self.raw_pdf.resolve(kid).dictionary() {
+                            Some(node) => self.push_node(node),
+                            None => log::warn!("Ignoring invalid number tree kid {}", kid),
+                        }
+                    }
+                    None => {
+                        self.stack.pop();
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf::{tests::raw_pdf, IndirectObject, Reference};
+
+    use super::*;
+
+    fn leaf(entries: &[(i32, i32)], limits: (i32, i32)) -> Object {
+        let nums = entries
+            .iter()
+            .flat_map(|(k, v)| [Object::Integer(*k), Object::Integer(*v)])
+            .collect::<Vec<_>>();
+        Object::Dictionary(Dictionary::from([
+            (K_NUMS.to_vec().into(), Object::from(nums)),
+            (
+                K_LIMITS.to_vec().into(),
+                Object::from(vec![Object::Integer(limits.0), Object::Integer(limits.1)]),
+            ),
+        ]))
+    }
+
+    #[test]
+    fn two_level_tree() {
+        let pdf = raw_pdf(
+            Reference::new(1, 0),
+            vec![
+                IndirectObject::new(2, 0, leaf(&[(0, 10), (4, 14)], (0, 4))),
+                IndirectObject::new(3, 0, leaf(&[(10, 20), (20, 30)], (10, 20))),
+            ],
+        );
+        let root = Dictionary::from([(
+            K_KIDS.to_vec().into(),
+            Object::from(vec![
+                Object::Reference(Reference::new(2, 0)),
+                Object::Reference(Reference::new(3, 0)),
+            ]),
+        )]);
+        let tree = NumberTree::new_with(&pdf, &root);
+
+        assert_eq!(tree.iter().map(|(k, _)| k).collect::<Vec<_>>(), vec![0, 4, 10, 20]);
+        assert_eq!(tree.get(4), Some(&Object::Integer(14)));
+        assert_eq!(tree.get(10), Some(&Object::Integer(20)));
+        assert_eq!(tree.get(5), None);
+
+        assert_eq!(tree.range_lookup(3), Some((0, &Object::Integer(10))));
+        // Falls between the limits of the two kids.
+        assert_eq!(tree.range_lookup(7), Some((4, &Object::Integer(14))));
+        assert_eq!(tree.range_lookup(25), Some((20, &Object::Integer(30))));
+        assert_eq!(tree.range_lookup(-1), None);
+    }
+
+    #[test]
+    fn self_referencing_kids() {
+        let kids = Object::from(vec![
+            Object::Reference(Reference::new(2, 0)),
+            Object::Reference(Reference::new(2, 0)),
+        ]);
+        let node = Dictionary::from([(K_KIDS.to_vec().into(), kids.clone())]);
+        let pdf = raw_pdf(
+            Reference::new(1, 0),
+            vec![IndirectObject::new(2, 0, Object::Dictionary(node))],
+        );
+        let root = Dictionary::from([(K_KIDS.to_vec().into(), kids)]);
+        let tree = NumberTree::new_with(&pdf, &root);
+
+        assert_eq!(tree.get(1), None);
+        assert_eq!(tree.range_lookup(1), None);
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::HashSet;
+
+use crate::pdf::{
+    text::{text_entry, text_lossy},
+    Array, Dictionary, Object, ObjectSource, Reference,
+};
+
+const K_OCGS: &[u8] = b"OCGs";
+const K_DEFAULT_CONFIG: &[u8] = b"D";
+const K_NAME: &[u8] = b"Name";
+const K_BASE_STATE: &[u8] = b"BaseState";
+const K_ON: &[u8] = b"ON";
+const K_OFF: &[u8] = b"OFF";
+const K_ORDER: &[u8] = b"Order";
+
+const STATE_OFF: &[u8] = b"OFF";
+
+/// Upper bound for the depth of the `Order` tree. Protects against cycles.
+const MAX_DEPTH: usize = 32;
+
+/// An optional content group, shown as a layer by viewers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layer {
+    pub name: String,
+    /// Visibility in the default configuration.
+    pub visible: bool,
+    /// The reference to the group dictionary.
+    pub reference: Reference,
+}
+
+/// A node of the `Order` tree that describes how layers are presented.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderNode {
+    Layer(Reference),
+    /// A nested array. The label is a string that isn't a layer itself.
+    Group {
+        label: Option<String>,
+        children: Vec<OrderNode>,
+    },
+}
+
+/// The optional content properties of the document.
+#[derive(Clone, PartialEq)]
+pub struct OptionalContent<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    dict: &'a Dictionary,
+}
+
+// Custom impl to skip `raw_pdf` field.
+impl<'a> std::fmt::Debug for OptionalContent<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OptionalContent").field("dict", &self.dict).finish()
+    }
+}
+
+impl<'a> OptionalContent<'a> {
+    pub(crate) fn new_with(raw_pdf: &'a dyn ObjectSource, dict: &'a Dictionary) -> Self {
+        Self { raw_pdf, dict }
+    }
+
+    /// The default configuration.
+    fn default_config(&self) -> Option<&'a Dictionary> {
+        self.raw_pdf
+            .dict_get_resolved(self.dict, K_DEFAULT_CONFIG)
+            .and_then(Object::dictionary)
+    }
+
+    fn config_array(&self, key: &[u8]) -> Option<&'a Array> {
+        self.raw_pdf
+            .dict_get_resolved(self.default_config()?, key)
+            .and_then(Object::array)
+    }
+
+    /// Whether the group is visible in the default configuration.
+    fn visible(&self, reference: &Reference) -> bool {
+        let contains = |key| {
+            self.config_array(key)
+                .is_some_and(|a| a.iter().any(|o| o.reference() == Some(reference)))
+        };
+        if contains(K_OFF) {
+            return false;
+        }
+        if contains(K_ON) {
+            return true;
+        }
+        let base_state = self
+            .default_config()
+            .and_then(|c| self.raw_pdf.dict_get_resolved(c, K_BASE_STATE))
+            .and_then(Object::name);
+        !matches!(base_state, Some(state) if &state[..] == STATE_OFF)
+    }
+
+    /// All optional content groups of the document.
+    pub fn layers(&self) -> Vec<Layer> {
+        let ocgs = match self.raw_pdf.dict_get_resolved(self.dict, K_OCGS) {
+            Some(Object::Array(ocgs)) => ocgs,
+            other => {
+                log::warn!("Invalid optional content groups {:?}", other);
+                return Vec::new();
+            }
+        };
+
+        ocgs.iter()
+            .filter_map(|ocg| {
+                let reference = match ocg.reference() {
+                    Some(r) => *r,
+                    None => {
+                        log::warn!("Optional content group is not a reference: {}", ocg);
+                        return None;
+                    }
+                };
+                let dict = self.raw_pdf.dereference(&reference)?.dictionary()?;
+                let name = text_entry(self.raw_pdf, dict, K_NAME, "optional content group").unwrap_or_default();
+                Some(Layer {
+                    name,
+                    visible: self.visible(&reference),
+                    reference,
+                })
+            })
+            .collect()
+    }
+
+    /// The `Order` tree of the default configuration.
+    pub fn order(&self) -> Vec<OrderNode> {
+        self.config_array(K_ORDER)
+            .map(|order| self.order_nodes(order, 0, &mut HashSet::new()))
+            .unwrap_or_default()
+    }
+
+    /// `visited` contains the object numbers of the referenced arrays, arrays
+    /// that are referenced again are skipped.
+    fn order_nodes(&self, entries: &[Object], depth: usize, visited: &mut HashSet<u32>) -> Vec<OrderNode> {
+        if depth > MAX_DEPTH {
+            log::error!("Optional content order exceeds the maximum depth of {}", MAX_DEPTH);
+            return Vec::new();
+        }
+        entries
+            .iter()
+            .filter_map(|entry| match (entry, self.raw_pdf.resolve(entry)) {
+                (Object::Reference(r), Object::Array(_)) if !visited.insert(r.index) => {
+                    log::error!("Optional content order references the array {:?} again", r);
+                    None
+                }
+                (_, Object::Array(group)) => Some(self.group(group, depth, visited)),
+                (Object::Reference(r), _) => Some(OrderNode::Layer(*r)),
+                (other, _) => {
+                    log::warn!("Ignoring invalid optional content order entry {}", other);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// A nested array, optionally starting with a label.
+    fn group(&self, entries: &[Object], depth: usize, visited: &mut HashSet<u32>) -> OrderNode {
+        let version = self.raw_pdf.effective_version();
+        let (label, children) = match entries.split_first() {
+            Some((first, children)) => match text_lossy(first, version) {
+                Some(label) => (Some(label), children),
+                None => (None, entries),
+            },
+            None => (None, entries),
+        };
+        OrderNode::Group {
+            label,
+            children: self.order_nodes(children, depth + 1, visited),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf::tests::parsed_pdf;
+
+    use super::*;
+
+    const OBJECTS: [&[u8]; 4] = [
+        b"1 0 obj <</Type /Catalog /Pages 2 0 R /OCProperties <</OCGs [10 0 R 11 0 R]
+            /D <</OFF [11 0 R] /Order [10 0 R [(Annotations) 11 0 R [10 0 R]]]>>>>>> endobj",
+        b"2 0 obj <</Type /Pages /Kids [] /Count 0>> endobj",
+        b"10 0 obj <</Type /OCG /Name (Walls)>> endobj",
+        b"11 0 obj <</Type /OCG /Name <FEFF00440069006D0065006E00730069006F006E0073>>> endobj",
+    ];
+
+    #[test]
+    fn layers_and_order() {
+        let pdf = parsed_pdf(Reference::new(1, 0), &OBJECTS);
+
+        assert_eq!(
+            pdf.layers(),
+            vec![
+                Layer {
+                    name: "Walls".to_string(),
+                    visible: true,
+                    reference: Reference::new(10, 0),
+                },
+                Layer {
+                    name: "Dimensions".to_string(),
+                    visible: false,
+                    reference: Reference::new(11, 0),
+                },
+            ]
+        );
+
+        let optional_content = pdf.catalog().unwrap().optional_content().unwrap();
+        assert_eq!(
+            optional_content.order(),
+            vec![
+                OrderNode::Layer(Reference::new(10, 0)),
+                OrderNode::Group {
+                    label: Some("Annotations".to_string()),
+                    children: vec![
+                        OrderNode::Layer(Reference::new(11, 0)),
+                        OrderNode::Group {
+                            label: None,
+                            children: vec![OrderNode::Layer(Reference::new(10, 0))],
+                        },
+                    ],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn self_referencing_order() {
+        let pdf = parsed_pdf(
+            Reference::new(1, 0),
+            &[
+                b"1 0 obj <</Type /Catalog /Pages 2 0 R /OCProperties <</OCGs [] /D <</Order [3 0 R]>>>>>> endobj",
+                b"2 0 obj <</Type /Pages /Kids [] /Count 0>> endobj",
+                b"3 0 obj [3 0 R 3 0 R] endobj",
+            ],
+        );
+
+        let optional_content = pdf.catalog().unwrap().optional_content().unwrap();
+        assert_eq!(
+            optional_content.order(),
+            vec![OrderNode::Group {
+                label: None,
+                children: Vec::new(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::HashSet;
+
+use crate::pdf::{text, Dictionary, Object, ObjectSource};
+
+use super::{destination, K_COUNT, K_FIRST};
+
+const K_NEXT: &[u8] = b"Next";
+const K_TITLE: &[u8] = b"Title";
+
+/// The document outline (bookmarks).
+#[derive(Clone, PartialEq)]
+pub struct Outline<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    dict: &'a Dictionary,
+}
+
+// Custom impl to skip `raw_pdf` field.
+impl<'a> std::fmt::Debug for Outline<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Outline").field("dict", &self.dict).finish()
+    }
+}
+
+impl<'a> Outline<'a> {
+    pub(crate) fn new_with(raw_pdf: &'a dyn ObjectSource, dict: &'a Dictionary) -> Self {
+        Self { raw_pdf, dict }
+    }
+
+    /// Number of visible items in the outline.
+    pub fn count(&self) -> Option<i64> {
+        count(self.raw_pdf, self.dict)
+    }
+
+    /// The top-level items.
+    pub fn items(&self) -> OutlineIter<'a> {
+        OutlineIter::new(self.raw_pdf, self.dict, Vec::new())
+    }
+}
+
+fn count(raw_pdf: &dyn ObjectSource, dict: &Dictionary) -> Option<i64> {
+    raw_pdf
+        .dict_get_resolved(dict, K_COUNT)
+        .and_then(Object::integer)
+        .map(i64::from)
+}
+
+/// An item of the document outline.
+#[derive(Clone, PartialEq)]
+pub struct OutlineItem<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    /// Object number of the item dictionary.
+    number: u32,
+    dict: &'a Dictionary,
+    /// Object numbers of all parents and the item itself. Used to detect
+    /// cycles.
+    path: Vec<u32>,
+}
+
+// Custom impl to skip `raw_pdf` field.
+impl<'a> std::fmt::Debug for OutlineItem<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OutlineItem")
+            .field("number", &self.number)
+            .field("title", &self.title())
+            .finish()
+    }
+}
+
+impl<'a> OutlineItem<'a> {
+    /// The object number of the item dictionary.
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// The raw item dictionary.
+    pub fn dictionary(&self) -> &'a Dictionary {
+        self.dict
+    }
+
+    pub fn title(&self) -> Option<String> {
+        text::text_entry(self.raw_pdf, self.dict, K_TITLE, "outline title")
+    }
+
+    /// The destination of the item, either from `Dest` or from a `GoTo`
+    /// action.
+    pub fn destination(&self) -> Option<&'a Object> {
+        destination::dest_or_goto(self.raw_pdf, self.dict)
+    }
+
+    /// The `Count` entry. Positive for open items, negative for closed items.
+    pub fn count(&self) -> Option<i64> {
+        count(self.raw_pdf, self.dict)
+    }
+
+    /// Whether the children of the item are shown.
+    pub fn is_open(&self) -> bool {
+        self.count().is_some_and(|c| c > 0)
+    }
+
+    pub fn children(&self) -> OutlineIter<'a> {
+        OutlineIter::new(self.raw_pdf, self.dict, self.path.clone())
+    }
+}
+
+/// Iterator over siblings of the outline, starting at the `First` item of a
+/// parent.
+pub struct OutlineIter<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    next: Option<&'a Object>,
+    /// Object numbers of the parents.
+    path: Vec<u32>,
+    /// Object numbers of the parents and all visited siblings.
+    visited: HashSet<u32>,
+}
+
+impl<'a> OutlineIter<'a> {
+    fn new(raw_pdf: &'a dyn ObjectSource, parent: &'a Dictionary, path: Vec<u32>) -> Self {
+        Self {
+            raw_pdf,
+            next: parent.get(K_FIRST),
+            visited: path.iter().copied().collect(),
+            path,
+        }
+    }
+}
+
+impl<'a> Iterator for OutlineIter<'a> {
+    type Item = OutlineItem<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.next.take()?;
+        let reference = match next.reference() {
+            Some(r) => r,
+            None => {
+                log::warn!("Outline item is not a reference: {}", next);
+                return None;
+            }
+        };
+        if !self.visited.insert(reference.index) {
+            log::error!("Outline contains a cycle at {:?}", reference);
+            return None;
+        }
+        let dict = match self.raw_pdf.dereference(reference).and_then(Object::dictionary) {
+            Some(dict) => dict,
+            None => {
+                log::warn!("Outline item {:?} is not a dictionary", reference);
+                return None;
+            }
+        };
+
+        self.next = dict.get(K_NEXT);
+        let mut path = self.path.clone();
+        path.push(reference.index);
+        Some(OutlineItem {
+            raw_pdf: self.raw_pdf,
+            number: reference.index,
+            dict,
+            path,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf::{tests::parsed_pdf, Reference};
+
+    use super::*;
+
+    const OBJECTS: [&[u8]; 6] = [
+        b"10 0 obj <</Type /Outlines /First 11 0 R /Last 12 0 R /Count 4>> endobj",
+        b"11 0 obj <</Title (Chapter 1) /Parent 10 0 R /Next 12 0 R /First 13 0 R /Count 2
+            /Dest [3 0 R /Fit]>> endobj",
+        b"12 0 obj <</Title <FEFF004300680061007000740065007200200032> /Parent 10 0 R /Prev 11 0 R /Count -1
+            /First 14 0 R /A <</S /GoTo /D (chapter2)>>>> endobj",
+        b"13 0 obj <</Title (Section 1.1) /Parent 11 0 R /First 15 0 R /Count 1>> endobj",
+        b"14 0 obj <</Title (Section 2.1) /Parent 12 0 R>> endobj",
+        // broken: points back to an ancestor
+        b"15 0 obj <</Title (Section 1.1.1) /Parent 13 0 R /Next 11 0 R>> endobj",
+    ];
+
+    fn titles(items: OutlineIter) -> Vec<(String, Vec<String>)> {
+        items
+            .map(|i| {
+                let children = i.children().map(|c| c.title().unwrap()).collect();
+                (i.title().unwrap(), children)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn three_level_outline() {
+        let pdf = parsed_pdf(Reference::new(1, 0), &OBJECTS);
+        let root = pdf
+            .dereference(&Reference::new(10, 0))
+            .and_then(Object::dictionary)
+            .unwrap();
+        let outline = Outline::new_with(&pdf, root);
+
+        assert_eq!(outline.count(), Some(4));
+        assert_eq!(
+            titles(outline.items()),
+            vec![
+                ("Chapter 1".to_string(), vec!["Section 1.1".to_string()]),
+                ("Chapter 2".to_string(), vec!["Section 2.1".to_string()]),
+            ]
+        );
+
+        let chapters = outline.items().collect::<Vec<_>>();
+        assert!(chapters[0].is_open());
+        assert!(!chapters[1].is_open());
+        assert_eq!(
+            chapters[0].destination().and_then(Object::array).map(|a| a.len()),
+            Some(2)
+        );
+        assert_eq!(
+            chapters[1].destination(),
+            Some(&Object::String(b"chapter2".to_vec().into()))
+        );
+
+        let section = chapters[0].children().next().unwrap();
+        assert_eq!(titles(section.children()), vec![("Section 1.1.1".to_string(), vec![])]);
+    }
+}
@@ -0,0 +1,152 @@
+//! Output intents, the intended output device of color-managed documents.
+
+use crate::pdf::{
+    object::stream::filter::FilterError, text::text_entry, Bytes, Dictionary, Name, Object, ObjectSource, Stream,
+};
+
+const K_SUBTYPE: &[u8] = b"S";
+const K_OUTPUT_CONDITION: &[u8] = b"OutputCondition";
+const K_OUTPUT_CONDITION_IDENTIFIER: &[u8] = b"OutputConditionIdentifier";
+const K_REGISTRY_NAME: &[u8] = b"RegistryName";
+const K_INFO: &[u8] = b"Info";
+const K_DEST_OUTPUT_PROFILE: &[u8] = b"DestOutputProfile";
+
+/// An entry of the `/OutputIntents` array of the catalog.
+#[derive(Clone, PartialEq)]
+pub struct OutputIntent<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    dict: &'a Dictionary,
+}
+
+// Custom impl to skip `raw_pdf` field.
+impl<'a> std::fmt::Debug for OutputIntent<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OutputIntent").field("dict", &self.dict).finish()
+    }
+}
+
+impl<'a> OutputIntent<'a> {
+    pub(crate) fn new_with(raw_pdf: &'a dyn ObjectSource, dict: &'a Dictionary) -> Self {
+        Self { raw_pdf, dict }
+    }
+
+    /// The raw output intent dictionary.
+    pub fn dictionary(&self) -> &'a Dictionary {
+        self.dict
+    }
+
+    /// The standard the intent is for, e.g. `GTS_PDFA1` or `GTS_PDFX`.
+    pub fn subtype(&self) -> Option<&'a Name> {
+        self.get(K_SUBTYPE).and_then(Object::name)
+    }
+
+    /// A human readable description of the output condition.
+    pub fn output_condition(&self) -> Option<String> {
+        self.text(K_OUTPUT_CONDITION)
+    }
+
+    /// The name of the output condition, e.g. `FOGRA39`.
+    pub fn output_condition_identifier(&self) -> Option<String> {
+        self.text(K_OUTPUT_CONDITION_IDENTIFIER)
+    }
+
+    /// The registry the output condition identifier is defined in.
+    pub fn registry_name(&self) -> Option<String> {
+        self.text(K_REGISTRY_NAME)
+    }
+
+    /// Additional information about the output condition.
+    pub fn info(&self) -> Option<String> {
+        self.text(K_INFO)
+    }
+
+    /// The ICC profile stream of the output device.
+    pub fn dest_output_profile(&self) -> Option<&'a Stream> {
+        self.get(K_DEST_OUTPUT_PROFILE).and_then(Object::stream)
+    }
+
+    /// The decoded ICC profile of the output device.
+    pub fn profile_data(&self) -> Option<Result<Bytes, FilterError>> {
+        self.dest_output_profile().map(Stream::filtered_data)
+    }
+
+    fn get(&self, key: &[u8]) -> Option<&'a Object> {
+        self.raw_pdf.dict_get_resolved(self.dict, key)
+    }
+
+    fn text(&self, key: &[u8]) -> Option<String> {
+        text_entry(self.raw_pdf, self.dict, key, "output intent")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf::{document::ColorSpace, tests::parsed_pdf, RawPdf, Reference};
+
+    /// The start of an ICC profile header, the signature is at offset 36.
+    fn profile() -> Vec<u8> {
+        let mut profile = vec![0; 128];
+        profile[..4].copy_from_slice(&128u32.to_be_bytes());
+        profile[12..16].copy_from_slice(b"mntr");
+        profile[16..20].copy_from_slice(b"RGB ");
+        profile[36..40].copy_from_slice(b"acsp");
+        profile
+    }
+
+    fn document() -> RawPdf {
+        let hex = profile().iter().map(|b| format!("{:02X}", b)).collect::<String>();
+        let profile = format!(
+            "5 0 obj <</N 3 /Filter /ASCIIHexDecode /Length {}>> stream\n{}>\nendstream endobj",
+            hex.len() + 1,
+            hex
+        );
+        parsed_pdf(
+            Reference::new(1, 0),
+            &[
+                b"1 0 obj <</Type /Catalog /Pages 2 0 R /OutputIntents [4 0 R 6]>> endobj",
+                b"2 0 obj <</Type /Pages /Kids [3 0 R] /Count 1>> endobj",
+                b"3 0 obj <</Type /Page /Parent 2 0 R /MediaBox [0 0 100 100] /Resources <<
+                /ColorSpace <</CS0 [/ICCBased 5 0 R] /CS1 /DeviceRGB /CS2 [/Indexed /DeviceRGB 1 <000000FFFFFF>]>>
+            >>>> endobj",
+                b"4 0 obj <</Type /OutputIntent /S /GTS_PDFA1 /OutputConditionIdentifier (sRGB IEC61966-2.1)
+                /RegistryName (http://www.color.org) /DestOutputProfile 5 0 R>> endobj",
+                profile.as_bytes(),
+            ],
+        )
+    }
+
+    #[test]
+    fn output_intents() {
+        let pdf = document();
+        let intents = pdf.catalog().unwrap().output_intents();
+        assert_eq!(intents.len(), 1);
+        let intent = &intents[0];
+        assert_eq!(intent.subtype().map(|s| &s[..]), Some(&b"GTS_PDFA1"[..]));
+        assert_eq!(
+            intent.output_condition_identifier().as_deref(),
+            Some("sRGB IEC61966-2.1")
+        );
+        assert_eq!(intent.registry_name().as_deref(), Some("http://www.color.org"));
+        assert_eq!(intent.output_condition(), None);
+        let data = intent.profile_data().unwrap().unwrap();
+        assert_eq!(&data[..], &profile()[..]);
+        assert_eq!(&data[36..40], b"acsp");
+    }
+
+    #[test]
+    fn icc_based_color_space() {
+        let pdf = document();
+        let page = pdf.page(0).unwrap();
+        let color_spaces = page.color_spaces();
+        assert_eq!(color_spaces.len(), 3);
+        match &color_spaces[0].1 {
+            ColorSpace::IccBased { n, profile: stream } => {
+                assert_eq!(*n, 3);
+                assert_eq!(&stream.filtered_data().unwrap()[36..40], b"acsp");
+            }
+            other => panic!("Expected ICCBased color space, got {:?}", other),
+        }
+        assert_eq!(color_spaces[1].1, ColorSpace::DeviceRgb);
+        assert!(matches!(color_spaces[2].1, ColorSpace::Indexed { hival: 1, .. }));
+    }
+}
@@ -0,0 +1,368 @@
+use crate::{
+    parse::{
+        content::{parse_operations, Operation},
+        error::{CbParseError, CbParseErrorKind},
+    },
+    pdf::{DictExt, Dictionary, Name, Object, ObjectSource, Rectangle, Stream},
+};
+
+use super::{
+    annotation::Annotation,
+    color_space::ColorSpace,
+    ext_g_state::{ext_g_states, ExtGState},
+    extract::extract_text,
+    form::FormXObject,
+    image::ImageXObject,
+    resources::{resource_usage, ResourceCategory, ResourceUsage},
+    shading::{Pattern, Shading},
+    K_ANNOTS, K_CONTENTS, K_CROP_BOX, K_MEDIA_BOX, K_PARENT, K_RESOURCES, K_ROTATE,
+};
+
+/// A leaf of the page tree.
+#[derive(Clone, PartialEq)]
+pub struct Page<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    /// Object number of the page dictionary.
+    number: u32,
+    dict: &'a Dictionary,
+}
+
+// Custom impl to skip `raw_pdf` field.
+impl<'a> std::fmt::Debug for Page<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Page")
+            .field("number", &self.number)
+            .field("dict", &self.dict)
+            .finish()
+    }
+}
+
+impl<'a> Page<'a> {
+    pub(crate) fn new_with(raw_pdf: &'a dyn ObjectSource, number: u32, dict: &'a Dictionary) -> Self {
+        Self { raw_pdf, number, dict }
+    }
+
+    /// The object number of the page dictionary.
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// The raw page dictionary.
+    pub fn dictionary(&self) -> &'a Dictionary {
+        self.dict
+    }
+
+    /// The boundaries of the physical medium.
+    pub fn media_box(&self) -> Option<Rectangle> {
+        self.inherited_rectangle(K_MEDIA_BOX)
+    }
+
+    /// The visible region of the page. Defaults to the media box.
+    pub fn crop_box(&self) -> Option<Rectangle> {
+        self.inherited_rectangle(K_CROP_BOX).or_else(|| self.media_box())
+    }
+
+    /// Clockwise rotation of the page in degrees.
+    pub fn rotate(&self) -> i32 {
+        self.inherited(K_ROTATE).and_then(Object::integer).unwrap_or(0)
+    }
+
+    /// Resources required by the page content.
+    pub fn resources(&self) -> Option<&'a Dictionary> {
+        self.inherited(K_RESOURCES).and_then(Object::dictionary)
+    }
+
+    /// The content streams of this page.
+    ///
+    /// `Contents` is either a single stream or an array of streams which are
+    /// concatenated when the page is rendered.
+    pub fn contents(&self) -> Vec<&'a Stream> {
+        match self.raw_pdf.dict_get_resolved(self.dict, K_CONTENTS) {
+            Some(Object::Stream(s)) => vec![s],
+            Some(Object::Array(a)) => a
+                .iter()
+                .filter_map(|o| {
+                    let stream = self.raw_pdf.resolve(o).stream();
+                    if stream.is_none() {
+                        log::warn!("Ignoring invalid content stream {} of page {}", o, self.number);
+                    }
+                    stream
+                })
+                .collect(),
+            Some(other) => {
+                log::warn!("Invalid page contents {}", other);
+                Vec::new()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Parse the content streams of this page.
+    ///
+    /// The streams are decoded and concatenated before parsing, operations
+    /// may span multiple streams.
+    pub fn operations(&self) -> Result<Vec<Operation>, CbParseError<()>> {
+        let mut data = Vec::new();
+        for stream in self.contents() {
+            let decoded = stream
+                .filtered_data()
+                .map_err(|err| CbParseError::new((), CbParseErrorKind::StreamError(err)))?;
+            data.extend_from_slice(&decoded);
+            // Streams are separated by whitespace.
+            data.push(b'\n');
+        }
+        parse_operations(&data)
+    }
+
+    /// The names of the page resources that the content streams use,
+    /// including the names used by form XObjects without own resources.
+    /// Resources that are missing from the dictionary are included.
+    pub fn referenced_resources(&self) -> Result<ResourceUsage, CbParseError<()>> {
+        resource_usage(self.raw_pdf, &self.operations()?, self.resources())
+    }
+
+    /// The text shown by the content streams and the forms they paint, with
+    /// lines separated by `\n`. Codes are mapped to unicode with the
+    /// `/ToUnicode` CMap of the font, simple fonts without one are assumed to
+    /// use WinAnsiEncoding.
+    pub fn extract_text(&self) -> Result<String, CbParseError<()>> {
+        extract_text(self.raw_pdf, &self.operations()?, self.resources())
+    }
+
+    /// The form XObjects in the page resources with their names.
+    pub fn form_xobjects(&self) -> Vec<(&'a Name, FormXObject<'a>)> {
+        let raw_pdf = self.raw_pdf;
+        self.named_resources(ResourceCategory::XObject)
+            .filter_map(|(name, xobject)| {
+                Some((
+                    name,
+                    FormXObject::new_with(raw_pdf, raw_pdf.resolve(xobject).stream()?)?,
+                ))
+            })
+            .collect()
+    }
+
+    /// The image XObjects in the page resources with their names.
+    pub fn images(&self) -> Vec<(&'a Name, ImageXObject<'a>)> {
+        let raw_pdf = self.raw_pdf;
+        self.named_resources(ResourceCategory::XObject)
+            .filter_map(|(name, xobject)| {
+                Some((
+                    name,
+                    ImageXObject::new_with(raw_pdf, raw_pdf.resolve(xobject).stream()?)?,
+                ))
+            })
+            .collect()
+    }
+
+    /// The patterns in the page resources with their names. Patterns of
+    /// unknown types are skipped.
+    pub fn patterns(&self) -> Vec<(&'a Name, Pattern<'a>)> {
+        let raw_pdf = self.raw_pdf;
+        self.named_resources(ResourceCategory::Pattern)
+            .filter_map(|(name, pattern)| Some((name, Pattern::new_with(raw_pdf, pattern)?)))
+            .collect()
+    }
+
+    /// The shadings in the page resources with their names.
+    pub fn shadings(&self) -> Vec<(&'a Name, Shading<'a>)> {
+        let raw_pdf = self.raw_pdf;
+        self.named_resources(ResourceCategory::Shading)
+            .filter_map(|(name, shading)| Some((name, Shading::new_with(raw_pdf, shading)?)))
+            .collect()
+    }
+
+    /// The color spaces in the page resources with their names.
+    pub fn color_spaces(&self) -> Vec<(&'a Name, ColorSpace<'a>)> {
+        let raw_pdf = self.raw_pdf;
+        self.named_resources(ResourceCategory::ColorSpace)
+            .map(|(name, color_space)| (name, ColorSpace::new_with(raw_pdf, color_space)))
+            .collect()
+    }
+
+    /// The graphics state parameter dictionaries in the page resources with
+    /// their names.
+    pub fn ext_g_states(&self) -> Vec<(&'a Name, ExtGState<'a>)> {
+        let raw_pdf = self.raw_pdf;
+        self.resources()
+            .into_iter()
+            .flat_map(|resources| ext_g_states(raw_pdf, resources))
+            .collect()
+    }
+
+    /// The annotations of this page. Invalid annotations are skipped.
+    pub fn annotations(&self) -> impl Iterator<Item = Annotation<'a>> + 'a {
+        let raw_pdf = self.raw_pdf;
+        let annots = match raw_pdf.dict_get_resolved(self.dict, K_ANNOTS) {
+            Some(Object::Array(a)) => &a[..],
+            Some(other) => {
+                log::warn!("Invalid annotations {} of page {}", other, self.number);
+                &[]
+            }
+            None => &[],
+        };
+        annots.iter().filter_map(move |annot| match raw_pdf.resolve(annot) {
+            Object::Dictionary(dict) => Annotation::new_with(raw_pdf, dict),
+            other => {
+                log::warn!("Ignoring annotation that is not a dictionary: {}", other);
+                None
+            }
+        })
+    }
+
+    /// The entries of a subdictionary of the page resources.
+    fn named_resources(&self, category: ResourceCategory) -> impl Iterator<Item = (&'a Name, &'a Object)> {
+        self.resources()
+            .and_then(|resources| self.raw_pdf.dict_get_resolved(resources, category.key()))
+            .and_then(Object::dictionary)
+            .into_iter()
+            .flat_map(|entries| entries.iter())
+    }
+
+    fn inherited_rectangle(&self, key: &[u8]) -> Option<Rectangle> {
+        self.inherited(key)
+            .and_then(Object::array)
+            .and_then(|a| Rectangle::try_from(a).ok())
+    }
+
+    /// Lookup an inheritable attribute. If the page dictionary doesn't contain
+    /// the key, walk up the page tree until a node contains it.
+    pub(crate) fn inherited(&self, key: &[u8]) -> Option<&'a Object> {
+        let mut visited = vec![self.number];
+        let mut dict = self.dict;
+        loop {
+            if let Some(obj) = self.raw_pdf.dict_get_resolved(dict, key) {
+                return Some(obj);
+            }
+
+            let parent = dict.get_reference_opt(K_PARENT).ok().flatten()?;
+            if visited.contains(&parent.index) {
+                log::warn!("Cycle in page tree parents at {:?}", parent);
+                return None;
+            }
+            visited.push(parent.index);
+            dict = self.raw_pdf.dereference(parent)?.dictionary()?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::content::Operator,
+        pdf::{
+            document::{K_COUNT, K_KIDS, K_TYPE},
+            tests::raw_pdf,
+            IndirectObject, Name, RawPdf, Reference,
+        },
+    };
+
+    use super::*;
+
+    fn reference(index: u32) -> Object {
+        Object::Reference(Reference::new(index, 0))
+    }
+
+    fn rect(values: [i32; 4]) -> Object {
+        Object::from(values.iter().copied().map(Object::Integer).collect::<Vec<_>>())
+    }
+
+    fn dict(index: u32, entries: Vec<(&[u8], Object)>) -> IndirectObject {
+        IndirectObject::new(
+            index,
+            0,
+            Object::Dictionary(entries.into_iter().map(|(k, v)| (Name::new(k.to_vec()), v)).collect()),
+        )
+    }
+
+    fn pdf() -> RawPdf {
+        raw_pdf(
+            Reference::new(1, 0),
+            vec![
+                dict(
+                    1,
+                    vec![
+                        (K_TYPE, Object::Name(Name::new(b"Catalog".to_vec()))),
+                        (b"Pages", reference(2)),
+                    ],
+                ),
+                dict(
+                    2,
+                    vec![
+                        (K_TYPE, Object::Name(Name::new(b"Pages".to_vec()))),
+                        (K_KIDS, Object::from(vec![reference(3)])),
+                        (K_COUNT, Object::Integer(2)),
+                        (K_MEDIA_BOX, rect([0, 0, 612, 792])),
+                        (K_ROTATE, Object::Integer(90)),
+                    ],
+                ),
+                dict(
+                    3,
+                    vec![
+                        (K_TYPE, Object::Name(Name::new(b"Pages".to_vec()))),
+                        (K_PARENT, reference(2)),
+                        (K_KIDS, Object::from(vec![reference(4), reference(5)])),
+                        (K_COUNT, Object::Integer(2)),
+                        (K_RESOURCES, Object::Dictionary(Dictionary::new())),
+                    ],
+                ),
+                dict(
+                    4,
+                    vec![
+                        (K_TYPE, Object::Name(Name::new(b"Page".to_vec()))),
+                        (K_PARENT, reference(3)),
+                        (K_CONTENTS, reference(6)),
+                    ],
+                ),
+                dict(
+                    5,
+                    vec![
+                        (K_TYPE, Object::Name(Name::new(b"Page".to_vec()))),
+                        (K_PARENT, reference(3)),
+                        (K_ROTATE, Object::Integer(180)),
+                        (K_CROP_BOX, rect([10, 10, 100, 100])),
+                        (K_CONTENTS, Object::from(vec![reference(6), reference(6)])),
+                    ],
+                ),
+                IndirectObject::new(
+                    6,
+                    0,
+                    Object::Stream(Stream {
+                        dictionary: Dictionary::new(),
+                        data: b"0 0 m".to_vec().into(),
+                        provenance: Default::default(),
+                    }),
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn inherited_attributes() {
+        let pdf = pdf();
+        let catalog = pdf.catalog().unwrap();
+        let pages = catalog.pages().unwrap().iter().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(pages.len(), 2);
+
+        let media_box = Some(Rectangle::new(0.0, 0.0, 612.0, 792.0));
+        assert_eq!(pages[0].media_box(), media_box);
+        assert_eq!(pages[0].crop_box(), media_box);
+        assert_eq!(pages[0].rotate(), 90);
+        assert_eq!(pages[0].resources(), Some(&Dictionary::new()));
+        assert_eq!(pages[0].contents().len(), 1);
+
+        assert_eq!(pages[1].media_box(), media_box);
+        assert_eq!(pages[1].crop_box(), Some(Rectangle::new(10.0, 10.0, 100.0, 100.0)));
+        assert_eq!(pages[1].rotate(), 180);
+        assert_eq!(pages[1].contents().len(), 2);
+    }
+
+    #[test]
+    fn operations_of_concatenated_streams() {
+        let pdf = pdf();
+        let page = pdf.page(1).unwrap();
+        let operations = page.operations().unwrap();
+        assert_eq!(operations.len(), 2);
+        assert!(operations.iter().all(|o| o.operator == Operator::MoveTo));
+    }
+}
@@ -0,0 +1,215 @@
+use crate::pdf::{text::text_lossy, Dictionary, Object, ObjectSource};
+
+const K_STYLE: &[u8] = b"S";
+const K_PREFIX: &[u8] = b"P";
+const K_START: &[u8] = b"St";
+
+/// The largest number written with roman numerals, `MMMCMXCIX`.
+const MAX_ROMAN: u32 = 3999;
+/// The largest number written with letters, 100 times `Z`.
+const MAX_LETTERS: u32 = 26 * 100;
+
+/// Numbering style of a page label range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageLabelStyle {
+    /// `D`: 1, 2, 3, ...
+    Decimal,
+    /// `R`: I, II, III, ...
+    UpperRoman,
+    /// `r`: i, ii, iii, ...
+    LowerRoman,
+    /// `A`: A to Z, then AA to ZZ, ...
+    UpperLetters,
+    /// `a`: a to z, then aa to zz, ...
+    LowerLetters,
+}
+
+impl PageLabelStyle {
+    fn from_name(name: &[u8]) -> Option<Self> {
+        match name {
+            b"D" => Some(Self::Decimal),
+            b"R" => Some(Self::UpperRoman),
+            b"r" => Some(Self::LowerRoman),
+            b"A" => Some(Self::UpperLetters),
+            b"a" => Some(Self::LowerLetters),
+            _ => None,
+        }
+    }
+
+    /// Format a page number in this style. Numbers above 3999 for roman
+    /// numerals, or above 2600 for letters, are formatted as decimal.
+    pub fn format(&self, number: u32) -> String {
+        match self {
+            Self::Decimal => number.to_string(),
+            Self::UpperRoman | Self::LowerRoman if number > MAX_ROMAN => number.to_string(),
+            Self::UpperLetters | Self::LowerLetters if number > MAX_LETTERS => number.to_string(),
+            Self::UpperRoman => roman(number),
+            Self::LowerRoman => roman(number).to_lowercase(),
+            Self::UpperLetters => letters(number),
+            Self::LowerLetters => letters(number).to_lowercase(),
+        }
+    }
+}
+
+fn roman(mut number: u32) -> String {
+    const NUMERALS: [(u32, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+
+    let mut out = String::new();
+    for (value, numeral) in NUMERALS {
+        while number >= value {
+            out.push_str(numeral);
+            number -= value;
+        }
+    }
+    out
+}
+
+/// 1 is `A`, 26 is `Z`, 27 is `AA`, 53 is `AAA`.
+fn letters(number: u32) -> String {
+    if number == 0 {
+        return String::new();
+    }
+    let letter = char::from(b'A' + ((number - 1) % 26) as u8);
+    std::iter::repeat_n(letter, ((number - 1) / 26 + 1) as usize).collect()
+}
+
+/// The label of a single page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageLabel {
+    /// Numbering style. Labels without a style consist only of the prefix.
+    pub style: Option<PageLabelStyle>,
+    pub prefix: String,
+    /// The number of the page within its label range.
+    pub number: u32,
+}
+
+impl PageLabel {
+    /// Label of the page `offset` pages after the start of the label range
+    /// described by `dict`.
+    pub(crate) fn new_with(raw_pdf: &dyn ObjectSource, dict: &Dictionary, offset: u32) -> Self {
+        let style = raw_pdf
+            .dict_get_resolved(dict, K_STYLE)
+            .and_then(Object::name)
+            .and_then(|s| {
+                PageLabelStyle::from_name(s).or_else(|| {
+                    log::warn!("Ignoring invalid page label style `{}`", s);
+                    None
+                })
+            });
+        let prefix = raw_pdf
+            .dict_get_resolved(dict, K_PREFIX)
+            .and_then(|prefix| text_lossy(prefix, raw_pdf.effective_version()))
+            .unwrap_or_default();
+        let start = raw_pdf
+            .dict_get_resolved(dict, K_START)
+            .and_then(Object::integer)
+            .and_then(|s| u32::try_from(s).ok())
+            .unwrap_or(1);
+
+        Self {
+            style,
+            prefix,
+            number: start.saturating_add(offset),
+        }
+    }
+
+    /// The formatted label, e.g. `iv` or `A-3`.
+    pub fn label(&self) -> String {
+        match self.style {
+            Some(style) => format!("{}{}", self.prefix, style.format(self.number)),
+            None => self.prefix.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for PageLabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf::{
+        document::{K_PAGES, K_PAGE_LABELS, K_TYPE},
+        tests::raw_pdf,
+        IndirectObject, Name, Reference,
+    };
+
+    use super::*;
+
+    fn name(n: &[u8]) -> Object {
+        Object::Name(Name::new(n.to_vec()))
+    }
+
+    #[test]
+    fn format_styles() {
+        assert_eq!(PageLabelStyle::LowerRoman.format(4), "iv");
+        assert_eq!(PageLabelStyle::UpperRoman.format(1994), "MCMXCIV");
+        assert_eq!(PageLabelStyle::UpperLetters.format(26), "Z");
+        assert_eq!(PageLabelStyle::LowerLetters.format(28), "bb");
+
+        // Large numbers fall back to decimal.
+        assert_eq!(PageLabelStyle::UpperRoman.format(3999), "MMMCMXCIX");
+        assert_eq!(PageLabelStyle::UpperRoman.format(4000), "4000");
+        assert_eq!(PageLabelStyle::LowerLetters.format(2600).len(), 100);
+        assert_eq!(PageLabelStyle::LowerLetters.format(u32::MAX), u32::MAX.to_string());
+    }
+
+    #[test]
+    fn roman_then_decimal() {
+        let labels = Dictionary::from([(
+            b"Nums".to_vec().into(),
+            Object::from(vec![
+                Object::Integer(0),
+                Object::Dictionary(Dictionary::from([(K_STYLE.to_vec().into(), name(b"r"))])),
+                Object::Integer(4),
+                Object::Dictionary(Dictionary::from([(K_STYLE.to_vec().into(), name(b"D"))])),
+                Object::Integer(7),
+                Object::Dictionary(Dictionary::from([
+                    (K_STYLE.to_vec().into(), name(b"D")),
+                    (K_PREFIX.to_vec().into(), Object::String(b"A-".to_vec().into())),
+                    (K_START.to_vec().into(), Object::Integer(3)),
+                ])),
+            ]),
+        )]);
+        let catalog = Dictionary::from([
+            (K_TYPE.to_vec().into(), name(b"Catalog")),
+            (K_PAGES.to_vec().into(), Object::Dictionary(Dictionary::new())),
+            (K_PAGE_LABELS.to_vec().into(), Object::Reference(Reference::new(2, 0))),
+        ]);
+        let pdf = raw_pdf(
+            Reference::new(1, 0),
+            vec![
+                IndirectObject::new(1, 0, Object::Dictionary(catalog)),
+                IndirectObject::new(2, 0, Object::Dictionary(labels)),
+            ],
+        );
+
+        let labels = (0..9)
+            .map(|i| pdf.page_label(i).map(|l| l.label()))
+            .collect::<Option<Vec<_>>>();
+        assert_eq!(
+            labels,
+            Some(
+                ["i", "ii", "iii", "iv", "1", "2", "3", "A-3", "A-4"]
+                    .map(String::from)
+                    .to_vec()
+            )
+        );
+    }
+}
@@ -1,18 +1,29 @@
-use crate::pdf::{document::require_type, Array, Dictionary, Object, RawPdf};
+use std::collections::HashSet;
 
-use super::{dict_types::PAGES, K_COUNT, K_KIDS};
+use crate::pdf::{document::require_type, Array, DictExt, Dictionary, Object, ObjectSource};
 
+use super::{
+    dict_types::{PAGE, PAGES},
+    page::Page,
+    K_COUNT, K_KIDS, K_TYPE,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PagesError {
     InvalidParent,
     MissingKids,
     InvalidKids,
     MissingCount,
     InvalidCount,
+    /// A kid of the page tree is neither a page nor a page tree node.
+    InvalidKidType,
+    /// A node of the page tree is visited twice.
+    Cycle,
 }
 
+#[derive(Clone)]
 pub struct Pages<'a> {
-    // storing a backref to the whole raw pdf document might be helpful to resolve objects etc.
-    // raw_pdf: &'a RawPdf,
+    raw_pdf: &'a dyn ObjectSource,
     // pages have a backref to their parent. We might want to store that here.
     // parent: Option<&'a IndirectObject>,
     /// PageTree or Page objects, indirect.
@@ -21,27 +32,29 @@ pub struct Pages<'a> {
     count: usize,
 }
 
+// Custom impl to skip `raw_pdf` field.
+impl<'a> std::fmt::Debug for Pages<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pages")
+            .field("kids", &self.kids)
+            .field("count", &self.count)
+            .finish()
+    }
+}
+
 impl<'a> Pages<'a> {
-    pub(crate) fn new_with(raw_pdf: &'a RawPdf, dict: &'a Dictionary) -> Result<Self, PagesError> {
+    pub(crate) fn new_with(raw_pdf: &'a dyn ObjectSource, dict: &'a Dictionary) -> Result<Self, PagesError> {
         let _ = require_type(dict, PAGES);
 
         let pages = Self {
-            // raw_pdf,
-            kids: match dict.get(K_KIDS).ok_or(PagesError::MissingKids)? {
-                Object::Array(a) => Ok(a),
-                Object::Reference(r) => raw_pdf
-                    .dereference(r)
-                    .and_then(Object::array)
-                    .ok_or(PagesError::InvalidKids),
-                _ => Err(PagesError::InvalidKids),
-            }?,
+            raw_pdf,
+            kids: kids(raw_pdf, dict)?,
             count: dict
                 .get(K_COUNT)
-                .ok_or(PagesError::MissingKids)?
-                .integer()
-                .ok_or(PagesError::InvalidCount)?
-                .try_into()
-                .map_err(|_| PagesError::InvalidCount)?,
+                .ok_or(PagesError::MissingCount)?
+                .as_i64()
+                .and_then(|count| usize::try_from(count).ok())
+                .ok_or(PagesError::InvalidCount)?,
         };
 
         if pages.count < pages.kids.len() {
@@ -55,4 +68,349 @@ impl<'a> Pages<'a> {
 
         Ok(pages)
     }
+
+    /// Number of pages as announced by the page tree root.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Iterate over all pages in the page tree.
+    ///
+    /// The tree is traversed depth-first, so pages are returned in document
+    /// order.
+    pub fn iter(&self) -> PageIter<'a> {
+        PageIter::new(self.raw_pdf, self.kids)
+    }
+}
+
+/// The resolved `Kids` array of a page tree node.
+pub(crate) fn kids<'a>(raw_pdf: &'a dyn ObjectSource, dict: &'a Dictionary) -> Result<&'a Array, PagesError> {
+    raw_pdf
+        .dict_get_resolved(dict, K_KIDS)
+        .ok_or(PagesError::MissingKids)?
+        .array()
+        .ok_or(PagesError::InvalidKids)
+}
+
+/// The pages the `kids` of a page tree node can hold: one for each leaf and
+/// the `Count` of each intermediate node. `None` if a kid is invalid.
+pub(crate) fn kids_capacity(raw_pdf: &dyn ObjectSource, kids: &Array) -> Option<usize> {
+    kids.iter().try_fold(0_usize, |capacity, kid| {
+        let dict = raw_pdf.resolve(kid).dictionary()?;
+        let pages = match is_tree_node(dict).ok()? {
+            true => usize::try_from(dict.get_integer(K_COUNT).ok()?).ok()?,
+            false => 1,
+        };
+        Some(capacity.saturating_add(pages))
+    })
+}
+
+/// Whether the dictionary is an intermediate node (`true`) or a leaf (`false`)
+/// of the page tree.
+fn is_tree_node(dict: &Dictionary) -> Result<bool, PagesError> {
+    match dict.get_name_opt(K_TYPE) {
+        Ok(Some(t)) if &t[..] == PAGES => Ok(true),
+        Ok(Some(t)) if &t[..] == PAGE => Ok(false),
+        // Be lenient with missing types, page tree nodes always have kids.
+        Ok(None) => {
+            log::warn!("Missing type for page tree kid");
+            Ok(dict.contains_key(K_KIDS))
+        }
+        _ => {
+            log::warn!("Invalid type for page tree kid");
+            Err(PagesError::InvalidKidType)
+        }
+    }
+}
+
+/// Find the page with the given zero-based index.
+///
+/// The `Count` entry of intermediate nodes is used to skip subtrees that can't
+/// contain the page. Subtrees without a valid count are iterated.
+pub(crate) fn page_at<'a>(raw_pdf: &'a dyn ObjectSource, root: &'a Dictionary, index: usize) -> Option<Page<'a>> {
+    let mut remaining = index;
+    let mut visited = HashSet::new();
+    let mut node = root;
+
+    'descend: loop {
+        for kid in kids(raw_pdf, node).ok()?.iter() {
+            let reference = kid.reference()?;
+            if !visited.insert(reference.index) {
+                log::error!("Page tree contains a cycle at {:?}", reference);
+                return None;
+            }
+            let dict = raw_pdf.resolve(kid).dictionary()?;
+
+            if !is_tree_node(dict).ok()? {
+                if remaining == 0 {
+                    return Some(Page::new_with(raw_pdf, reference.index, dict));
+                }
+                remaining -= 1;
+                continue;
+            }
+
+            let count = dict.get_integer(K_COUNT).ok().and_then(|c| usize::try_from(c).ok());
+            match count {
+                Some(count) if remaining >= count => remaining -= count,
+                Some(_) => {
+                    node = dict;
+                    continue 'descend;
+                }
+                None => {
+                    log::warn!("Invalid count in page tree node {:?}", reference);
+                    for page in PageIter::new(raw_pdf, kids(raw_pdf, dict).ok()?).filter_map(Result::ok) {
+                        if remaining == 0 {
+                            return Some(page);
+                        }
+                        remaining -= 1;
+                    }
+                }
+            }
+        }
+
+        return None;
+    }
+}
+
+/// Depth-first iterator over the leafs of a page tree.
+pub struct PageIter<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    /// Kids of all tree nodes from the root down to the current node.
+    stack: Vec<std::slice::Iter<'a, Object>>,
+    /// Object numbers of all visited kids.
+    visited: HashSet<u32>,
+}
+
+impl<'a> PageIter<'a> {
+    pub(crate) fn new(raw_pdf: &'a dyn ObjectSource, kids: &'a Array) -> Self {
+        Self {
+            raw_pdf,
+            stack: vec![kids.iter()],
+            visited: HashSet::new(),
+        }
+    }
+}
+
+impl<'a> Iterator for PageIter<'a> {
+    type Item = Result<Page<'a>, PagesError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let kid = match self.stack.last_mut()?.next() {
+                Some(kid) => kid,
+                None => {
+                    self.stack.pop();
+                    continue;
+                }
+            };
+
+            let reference = match kid.reference() {
+                Some(r) => r,
+                None => {
+                    log::warn!("Page tree kid is not a reference: {}", kid);
+                    return Some(Err(PagesError::InvalidKids));
+                }
+            };
+            if !self.visited.insert(reference.index) {
+                log::error!("Page tree contains a cycle at {:?}", reference);
+                return Some(Err(PagesError::Cycle));
+            }
+
+            let dict = match self.raw_pdf.resolve(kid).dictionary() {
+                Some(dict) => dict,
+                None => {
+                    log::warn!("Page tree kid {:?} is not a dictionary", reference);
+                    return Some(Err(PagesError::InvalidKids));
+                }
+            };
+
+            let is_node = match is_tree_node(dict) {
+                Ok(is_node) => is_node,
+                Err(err) => return Some(Err(err)),
+            };
+
+            if is_node {
+                match kids(self.raw_pdf, dict) {
+                    Ok(kids) => self.stack.push(kids.iter()),
+                    Err(err) => return Some(Err(err)),
+                }
+            } else {
+                return Some(Ok(Page::new_with(self.raw_pdf, reference.index, dict)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf::{tests::raw_pdf, IndirectObject, Name, RawPdf, Reference};
+
+    use super::*;
+
+    fn name(n: &[u8]) -> Object {
+        Object::Name(Name::new(n.to_vec()))
+    }
+
+    fn reference(index: u32) -> Object {
+        Object::Reference(Reference::new(index, 0))
+    }
+
+    fn node(index: u32, kids: Vec<Object>, count: i32) -> IndirectObject {
+        IndirectObject::new(
+            index,
+            0,
+            Object::Dictionary(Dictionary::from([
+                (K_TYPE.to_vec().into(), name(PAGES)),
+                (K_KIDS.to_vec().into(), Object::from(kids)),
+                (K_COUNT.to_vec().into(), Object::Integer(count)),
+            ])),
+        )
+    }
+
+    fn leaf(index: u32) -> IndirectObject {
+        IndirectObject::new(
+            index,
+            0,
+            Object::Dictionary(Dictionary::from([(K_TYPE.to_vec().into(), name(PAGE))])),
+        )
+    }
+
+    fn catalog(pages: u32) -> IndirectObject {
+        IndirectObject::new(
+            1,
+            0,
+            Object::Dictionary(Dictionary::from([
+                (K_TYPE.to_vec().into(), name(b"Catalog")),
+                (b"Pages".to_vec().into(), reference(pages)),
+            ])),
+        )
+    }
+
+    #[test]
+    fn two_level_page_tree() {
+        let pdf = raw_pdf(
+            Reference::new(1, 0),
+            vec![
+                catalog(2),
+                node(2, vec![reference(3), reference(4)], 3),
+                node(3, vec![reference(5), reference(6)], 2),
+                leaf(4),
+                leaf(5),
+                leaf(6),
+            ],
+        );
+
+        let catalog = pdf.catalog().unwrap();
+        let pages = catalog.pages().unwrap();
+        assert_eq!(pages.count(), 3);
+
+        let numbers = pages
+            .iter()
+            .map(|p| p.map(|p| p.number()))
+            .collect::<Result<Vec<_>, _>>();
+        assert_eq!(numbers, Ok(vec![5, 6, 4]));
+    }
+
+    #[test]
+    fn real_count() {
+        let mut root = node(2, vec![reference(3)], 1);
+        let count = |count| {
+            let mut root = root.clone();
+            if let Object::Dictionary(dict) = &mut *root.object {
+                dict.insert(K_COUNT.to_vec().into(), Object::Float(count));
+            }
+            let pdf = raw_pdf(Reference::new(1, 0), vec![catalog(2), root, leaf(3)]);
+            pdf.catalog().unwrap().pages().map(|pages| pages.count())
+        };
+        assert_eq!(count(1.0), Ok(1));
+        assert_eq!(count(1.5), Err(PagesError::InvalidCount));
+        assert_eq!(count(-1.0), Err(PagesError::InvalidCount));
+
+        if let Object::Dictionary(dict) = &mut *root.object {
+            dict.remove(K_COUNT);
+        }
+        let pdf = raw_pdf(Reference::new(1, 0), vec![catalog(2), root, leaf(3)]);
+        assert_eq!(pdf.catalog().unwrap().pages().err(), Some(PagesError::MissingCount));
+    }
+
+    #[test]
+    fn page_tree_cycle() {
+        let pdf = raw_pdf(
+            Reference::new(1, 0),
+            vec![
+                catalog(2),
+                node(2, vec![reference(3)], 2),
+                node(3, vec![reference(4), reference(2)], 2),
+                leaf(4),
+            ],
+        );
+
+        let catalog = pdf.catalog().unwrap();
+        let pages = catalog.pages().unwrap();
+        let numbers = pages.iter().map(|p| p.map(|p| p.number())).collect::<Vec<_>>();
+        assert_eq!(numbers, vec![Ok(4), Err(PagesError::Cycle)]);
+    }
+
+    #[test]
+    fn page_by_index_skips_subtrees() {
+        // The kids of node 3 don't exist. Looking up pages after this subtree
+        // only works if the subtree is skipped using its count.
+        let pdf = raw_pdf(
+            Reference::new(1, 0),
+            vec![
+                catalog(2),
+                node(2, vec![reference(3), reference(4), reference(7)], 5),
+                node(3, vec![reference(20), reference(21)], 2),
+                node(4, vec![reference(5), reference(6)], 2),
+                leaf(5),
+                leaf(6),
+                leaf(7),
+            ],
+        );
+
+        assert_eq!(pdf.page_count(), Ok(5));
+        assert_eq!(pdf.page(2).map(|p| p.number()), Some(5));
+        assert_eq!(pdf.page(3).map(|p| p.number()), Some(6));
+        assert_eq!(pdf.page(4).map(|p| p.number()), Some(7));
+        assert_eq!(pdf.page(5), None);
+    }
+
+    #[test]
+    fn page_count_fallback() {
+        let mut pdf = raw_pdf(
+            Reference::new(1, 0),
+            vec![
+                catalog(2),
+                node(2, vec![reference(3), reference(4)], 1),
+                node(3, vec![reference(5), reference(6)], 2),
+                leaf(4),
+                leaf(5),
+                leaf(6),
+            ],
+        );
+        // Count is smaller than the number of kids.
+        assert_eq!(pdf.page_count(), Ok(3));
+
+        let set_count = |pdf: &mut RawPdf, count: i32| {
+            if let Some(Object::Indirect(root)) = pdf.sections[0].objects.get_mut(&2) {
+                if let Object::Dictionary(dict) = &mut *root.object {
+                    dict.insert(K_COUNT.to_vec().into(), Object::Integer(count));
+                }
+            }
+        };
+        // Count is larger than the kids can hold.
+        set_count(&mut pdf, 1_000_000);
+        assert_eq!(pdf.page_count(), Ok(3));
+        set_count(&mut pdf, 3);
+        assert_eq!(pdf.page_count(), Ok(3));
+
+        if let Some(Object::Indirect(root)) = pdf.sections[0].objects.get_mut(&2) {
+            if let Object::Dictionary(dict) = &mut *root.object {
+                dict.remove(K_COUNT);
+            }
+        }
+        assert_eq!(pdf.page_count(), Ok(3));
+        assert_eq!(pdf.page(0).map(|p| p.number()), Some(5));
+        assert_eq!(pdf.page(2).map(|p| p.number()), Some(4));
+    }
 }
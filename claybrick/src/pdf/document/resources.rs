@@ -0,0 +1,253 @@
+//! The resources that content streams refer to by name.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    parse::{
+        content::{Operation, Operator},
+        error::CbParseError,
+    },
+    pdf::{Dictionary, Name, Object, ObjectSource},
+};
+
+use super::form::{lookup_form, FormTraversal};
+
+const K_COLOR_SPACE_ABBREVIATION: &[u8] = b"CS";
+const K_COLOR_SPACE: &[u8] = b"ColorSpace";
+
+/// Color spaces that are not looked up in the resources, including the
+/// abbreviations of inline images.
+const PREDEFINED_COLOR_SPACES: &[&[u8]] = &[
+    b"DeviceGray",
+    b"DeviceRGB",
+    b"DeviceCMYK",
+    b"Pattern",
+    b"G",
+    b"RGB",
+    b"CMYK",
+];
+
+/// The subdictionaries of a resource dictionary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResourceCategory {
+    ExtGState,
+    ColorSpace,
+    Pattern,
+    Shading,
+    XObject,
+    Font,
+    /// Property lists of marked content.
+    Properties,
+}
+
+impl ResourceCategory {
+    /// The key of the category in the resource dictionary.
+    pub fn key(self) -> &'static [u8] {
+        match self {
+            ResourceCategory::ExtGState => b"ExtGState",
+            ResourceCategory::ColorSpace => b"ColorSpace",
+            ResourceCategory::Pattern => b"Pattern",
+            ResourceCategory::Shading => b"Shading",
+            ResourceCategory::XObject => b"XObject",
+            ResourceCategory::Font => b"Font",
+            ResourceCategory::Properties => b"Properties",
+        }
+    }
+}
+
+/// The names of the resources used by content streams, by category.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceUsage {
+    names: HashMap<ResourceCategory, HashSet<Name>>,
+}
+
+impl ResourceUsage {
+    /// The used names of the category.
+    pub fn names(&self, category: ResourceCategory) -> impl Iterator<Item = &Name> {
+        self.names.get(&category).into_iter().flatten()
+    }
+
+    pub fn contains(&self, category: ResourceCategory, name: &[u8]) -> bool {
+        self.names.get(&category).is_some_and(|names| names.contains(name))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.values().all(HashSet::is_empty)
+    }
+
+    fn insert(&mut self, category: ResourceCategory, name: &Name) {
+        self.names.entry(category).or_default().insert(name.clone());
+    }
+}
+
+/// The names of `resources` that `operations` and the forms they paint use.
+pub(crate) fn resource_usage<'a>(
+    source: &'a dyn ObjectSource,
+    operations: &[Operation],
+    resources: Option<&'a Dictionary>,
+) -> Result<ResourceUsage, CbParseError<()>> {
+    let mut collector = UsageCollector {
+        source,
+        resources,
+        forms: FormTraversal::default(),
+        usage: ResourceUsage::default(),
+    };
+    collector.collect(operations, resources)?;
+    Ok(collector.usage)
+}
+
+struct UsageCollector<'a> {
+    source: &'a dyn ObjectSource,
+    /// The resources the usage is collected for.
+    resources: Option<&'a Dictionary>,
+    forms: FormTraversal,
+    usage: ResourceUsage,
+}
+
+impl<'a> UsageCollector<'a> {
+    /// Collect the names used by `operations`, which look up resources in
+    /// `resources`. Only names that refer to the resources of the collector
+    /// are added, but forms are followed either way.
+    fn collect(&mut self, operations: &[Operation], resources: Option<&'a Dictionary>) -> Result<(), CbParseError<()>> {
+        let own = match (resources, self.resources) {
+            (Some(resources), Some(collected)) => std::ptr::eq(resources, collected),
+            (None, None) => true,
+            _ => false,
+        };
+        for operation in operations {
+            let (category, name) = match used_resource(operation) {
+                Some(used) => used,
+                None => continue,
+            };
+            if own {
+                self.usage.insert(category, name);
+            }
+            if category == ResourceCategory::XObject {
+                self.collect_form(name, resources)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Collect the names used by the form XObject with the given name. Forms
+    /// without resources use the resources of the content that paints them.
+    fn collect_form(&mut self, name: &Name, resources: Option<&'a Dictionary>) -> Result<(), CbParseError<()>> {
+        let Some(form) = lookup_form(self.source, resources, name) else {
+            return Ok(());
+        };
+        if !self.forms.enter(form.stream()) {
+            return Ok(());
+        }
+        let result = form
+            .operations()
+            .and_then(|operations| self.collect(&operations, form.resources().or(resources)));
+        self.forms.leave();
+        result
+    }
+}
+
+/// The resource that an operation refers to.
+fn used_resource(operation: &Operation) -> Option<(ResourceCategory, &Name)> {
+    let first_name = || operation.operands.first().and_then(Object::name);
+    let last_name = || operation.operands.last().and_then(Object::name);
+    match operation.operator {
+        Operator::PaintXObject => Some((ResourceCategory::XObject, first_name()?)),
+        Operator::SetFont => Some((ResourceCategory::Font, first_name()?)),
+        Operator::SetExtGState => Some((ResourceCategory::ExtGState, first_name()?)),
+        Operator::PaintShading => Some((ResourceCategory::Shading, first_name()?)),
+        Operator::SetFillColorSpace | Operator::SetStrokeColorSpace => {
+            color_space(first_name()?).map(|name| (ResourceCategory::ColorSpace, name))
+        }
+        Operator::SetFillColorN | Operator::SetStrokeColorN => Some((ResourceCategory::Pattern, last_name()?)),
+        Operator::MarkedContentPointProperties | Operator::BeginMarkedContentProperties => {
+            Some((ResourceCategory::Properties, operation.operands.get(1)?.name()?))
+        }
+        Operator::InlineImage => {
+            let dictionary = &operation.operands.first()?.stream()?.dictionary;
+            let name = dictionary
+                .get(K_COLOR_SPACE_ABBREVIATION)
+                .or_else(|| dictionary.get(K_COLOR_SPACE))?
+                .name()?;
+            color_space(name).map(|name| (ResourceCategory::ColorSpace, name))
+        }
+        _ => None,
+    }
+}
+
+/// `name` unless it's a predefined color space.
+fn color_space(name: &Name) -> Option<&Name> {
+    (!PREDEFINED_COLOR_SPACES.contains(&&name[..])).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf::{tests::parsed_pdf, RawPdf, Reference};
+
+    use super::*;
+
+    fn form(number: u32, resources: &[u8], data: &[u8]) -> Vec<u8> {
+        [
+            format!(
+                "{} 0 obj <</Type /XObject /Subtype /Form /BBox [0 0 1 1] {} /Length {}>> stream\n",
+                number,
+                String::from_utf8_lossy(resources),
+                data.len()
+            )
+            .as_bytes(),
+            data,
+            b"\nendstream endobj",
+        ]
+        .concat()
+    }
+
+    fn document() -> RawPdf {
+        // uses the page resources and paints itself
+        let fm1 = form(5, b"", b"/F2 1 Tf /Sh1 sh /Fm1 Do /Fm2 Do");
+        // has its own resources, which are not the page's
+        let fm2 = form(6, b"/Resources <</Font <</F9 10 0 R>>>>", b"/F9 1 Tf /Fm1 Do");
+        parsed_pdf(
+            Reference::new(1, 0),
+            &[
+                b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj",
+                b"2 0 obj <</Type /Pages /Kids [3 0 R] /Count 1>> endobj",
+                b"3 0 obj <</Type /Page /Parent 2 0 R /MediaBox [0 0 100 100] /Contents 4 0 R /Resources <<
+                /Font <</F1 10 0 R /Unused 10 0 R>>
+                /XObject <</Fm1 5 0 R /Fm2 6 0 R /Im1 7 0 R>>
+                /ExtGState <</GS1 <<>> >>
+                /ColorSpace <</CS1 /DeviceRGB>>
+            >>>> endobj",
+                b"4 0 obj <</Length 81>> stream
+q /GS1 gs /CS1 cs /P1 scn /DeviceRGB CS BT /F1 12 Tf ET /Fm1 Do /Fm2 Do /Im1 Do Q
+endstream endobj",
+                &fm1,
+                &fm2,
+                b"7 0 obj <</Type /XObject /Subtype /Image /Width 1 /Height 1 /Length 0>> stream\n\nendstream endobj",
+                b"10 0 obj <</Type /Font /Subtype /Type1 /BaseFont /Helvetica>> endobj",
+            ],
+        )
+    }
+
+    #[test]
+    fn referenced_resources() {
+        let pdf = document();
+        let usage = pdf.page(0).unwrap().referenced_resources().unwrap();
+        let names = |category| {
+            let mut names = usage.names(category).map(|n| n.to_vec()).collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+        assert_eq!(names(ResourceCategory::Font), vec![b"F1".to_vec(), b"F2".to_vec()]);
+        assert!(!usage.contains(ResourceCategory::Font, b"Unused"));
+        assert!(!usage.contains(ResourceCategory::Font, b"F9"));
+        assert_eq!(
+            names(ResourceCategory::XObject),
+            vec![b"Fm1".to_vec(), b"Fm2".to_vec(), b"Im1".to_vec()]
+        );
+        assert_eq!(names(ResourceCategory::ExtGState), vec![b"GS1".to_vec()]);
+        // predefined color spaces are not resources
+        assert_eq!(names(ResourceCategory::ColorSpace), vec![b"CS1".to_vec()]);
+        assert_eq!(names(ResourceCategory::Pattern), vec![b"P1".to_vec()]);
+        assert_eq!(names(ResourceCategory::Shading), vec![b"Sh1".to_vec()]);
+        assert!(names(ResourceCategory::Properties).is_empty());
+    }
+}
@@ -0,0 +1,339 @@
+//! Shadings and patterns, e.g. gradients.
+
+use crate::pdf::{Dictionary, Object, ObjectSource, Rectangle, Stream};
+
+use super::{
+    function::{number_array, Function},
+    K_RESOURCES,
+};
+
+const K_SHADING_TYPE: &[u8] = b"ShadingType";
+const K_COLOR_SPACE: &[u8] = b"ColorSpace";
+const K_COORDS: &[u8] = b"Coords";
+const K_FUNCTION: &[u8] = b"Function";
+const K_EXTEND: &[u8] = b"Extend";
+const K_BBOX: &[u8] = b"BBox";
+const K_PATTERN_TYPE: &[u8] = b"PatternType";
+const K_SHADING: &[u8] = b"Shading";
+const K_MATRIX: &[u8] = b"Matrix";
+const K_X_STEP: &[u8] = b"XStep";
+const K_Y_STEP: &[u8] = b"YStep";
+
+/// The kinds of shadings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShadingType {
+    /// Type 1, colors are a function of the coordinates.
+    FunctionBased,
+    /// Type 2, a gradient along a line.
+    Axial,
+    /// Type 3, a gradient between two circles.
+    Radial,
+    /// Type 4, free-form triangle mesh.
+    FreeFormMesh,
+    /// Type 5, lattice-form triangle mesh.
+    LatticeFormMesh,
+    /// Type 6, Coons patch mesh.
+    CoonsPatchMesh,
+    /// Type 7, tensor-product patch mesh.
+    TensorProductPatchMesh,
+}
+
+impl ShadingType {
+    fn from_number(number: i32) -> Option<Self> {
+        match number {
+            1 => Some(ShadingType::FunctionBased),
+            2 => Some(ShadingType::Axial),
+            3 => Some(ShadingType::Radial),
+            4 => Some(ShadingType::FreeFormMesh),
+            5 => Some(ShadingType::LatticeFormMesh),
+            6 => Some(ShadingType::CoonsPatchMesh),
+            7 => Some(ShadingType::TensorProductPatchMesh),
+            _ => None,
+        }
+    }
+}
+
+/// A shading dictionary, or a stream for the mesh shadings.
+#[derive(Clone)]
+pub struct Shading<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    object: &'a Object,
+    dict: &'a Dictionary,
+}
+
+// Custom impl to skip `raw_pdf` field.
+impl<'a> std::fmt::Debug for Shading<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Shading").field("object", &self.object).finish()
+    }
+}
+
+impl<'a> Shading<'a> {
+    /// Returns `None` if `object` is neither a dictionary nor a stream.
+    pub(crate) fn new_with(raw_pdf: &'a dyn ObjectSource, object: &'a Object) -> Option<Self> {
+        let object = raw_pdf.resolve(object);
+        let dict = match object {
+            Object::Dictionary(dict) => dict,
+            Object::Stream(stream) => &stream.dictionary,
+            other => {
+                log::warn!("Ignoring shading that is not a dictionary: {}", other);
+                return None;
+            }
+        };
+        Some(Self { raw_pdf, object, dict })
+    }
+
+    /// The raw shading dictionary.
+    pub fn dictionary(&self) -> &'a Dictionary {
+        self.dict
+    }
+
+    /// The stream with the vertices of mesh shadings.
+    pub fn stream(&self) -> Option<&'a Stream> {
+        self.object.stream()
+    }
+
+    /// The type of the shading, `None` if it's unknown.
+    pub fn shading_type(&self) -> Option<ShadingType> {
+        self.get(K_SHADING_TYPE)
+            .and_then(Object::integer)
+            .and_then(ShadingType::from_number)
+    }
+
+    /// The color space of the colors, a name or an array.
+    pub fn color_space(&self) -> Option<&'a Object> {
+        self.get(K_COLOR_SPACE)
+    }
+
+    /// The start and end point of axial shadings as `[x0 y0 x1 y1]`, or the
+    /// start and end circle of radial shadings as `[x0 y0 r0 x1 y1 r1]`.
+    pub fn coords(&self) -> Option<Vec<f32>> {
+        number_array(self.get(K_COORDS)?)
+    }
+
+    /// The color functions, either a single function or one function per
+    /// color component. Invalid functions are skipped.
+    pub fn functions(&self) -> Vec<Function<'a>> {
+        let raw_pdf = self.raw_pdf;
+        match self.get(K_FUNCTION) {
+            Some(Object::Array(functions)) => functions
+                .iter()
+                .filter_map(|function| Function::new_with(raw_pdf, function))
+                .collect(),
+            Some(function) => Function::new_with(raw_pdf, function).into_iter().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Whether axial and radial shadings are extended beyond the start and
+    /// the end. Defaults to not extended.
+    pub fn extend(&self) -> [bool; 2] {
+        let extend = self.get(K_EXTEND).and_then(Object::array);
+        let extended = |i| {
+            extend
+                .and_then(|extend| extend.get(i))
+                .and_then(Object::as_bool)
+                .unwrap_or(false)
+        };
+        [extended(0), extended(1)]
+    }
+
+    /// The bounding box the shading is clipped to, in shading space.
+    pub fn bbox(&self) -> Option<Rectangle> {
+        Rectangle::try_from(self.get(K_BBOX)?.array()?).ok()
+    }
+
+    fn get(&self, key: &[u8]) -> Option<&'a Object> {
+        self.raw_pdf.dict_get_resolved(self.dict, key)
+    }
+}
+
+/// The kinds of patterns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternType {
+    /// Type 1, a cell that is repeated.
+    Tiling,
+    /// Type 2, a shading.
+    Shading,
+}
+
+/// A pattern dictionary, or a stream for tiling patterns.
+#[derive(Clone)]
+pub struct Pattern<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    object: &'a Object,
+    dict: &'a Dictionary,
+    pattern_type: PatternType,
+}
+
+// Custom impl to skip `raw_pdf` field.
+impl<'a> std::fmt::Debug for Pattern<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pattern")
+            .field("pattern_type", &self.pattern_type)
+            .field("object", &self.object)
+            .finish()
+    }
+}
+
+impl<'a> Pattern<'a> {
+    /// Returns `None` if `object` is no pattern of a known type.
+    pub(crate) fn new_with(raw_pdf: &'a dyn ObjectSource, object: &'a Object) -> Option<Self> {
+        let object = raw_pdf.resolve(object);
+        let dict = match object {
+            Object::Dictionary(dict) => dict,
+            Object::Stream(stream) => &stream.dictionary,
+            _ => return None,
+        };
+        let pattern_type = match raw_pdf
+            .dict_get_resolved(dict, K_PATTERN_TYPE)
+            .and_then(Object::integer)
+        {
+            Some(1) if object.stream().is_some() => PatternType::Tiling,
+            Some(2) => PatternType::Shading,
+            other => {
+                log::warn!("Ignoring pattern of unknown type {:?}", other);
+                return None;
+            }
+        };
+        Some(Self {
+            raw_pdf,
+            object,
+            dict,
+            pattern_type,
+        })
+    }
+
+    /// The raw pattern dictionary.
+    pub fn dictionary(&self) -> &'a Dictionary {
+        self.dict
+    }
+
+    pub fn pattern_type(&self) -> PatternType {
+        self.pattern_type
+    }
+
+    /// The content stream that paints the cell of tiling patterns.
+    pub fn content(&self) -> Option<&'a Stream> {
+        self.object.stream()
+    }
+
+    /// The resources of the content stream of tiling patterns.
+    pub fn resources(&self) -> Option<&'a Dictionary> {
+        self.get(K_RESOURCES).and_then(Object::dictionary)
+    }
+
+    /// The bounding box of the cell of tiling patterns.
+    pub fn bbox(&self) -> Option<Rectangle> {
+        Rectangle::try_from(self.get(K_BBOX)?.array()?).ok()
+    }
+
+    /// The horizontal and vertical spacing of the cells of tiling patterns.
+    pub fn steps(&self) -> Option<(f32, f32)> {
+        let step = |key| self.get(key).and_then(Object::as_f64).map(|step| step as f32);
+        Some((step(K_X_STEP)?, step(K_Y_STEP)?))
+    }
+
+    /// The shading of shading patterns.
+    pub fn shading(&self) -> Option<Shading<'a>> {
+        Shading::new_with(self.raw_pdf, self.get(K_SHADING)?)
+    }
+
+    /// The matrix from pattern space to the default coordinate space of the
+    /// page or form that uses the pattern. Defaults to the identity matrix.
+    pub fn matrix(&self) -> [f32; 6] {
+        match self.get(K_MATRIX).and_then(number_array) {
+            Some(matrix) if matrix.len() == 6 => std::array::from_fn(|i| matrix[i]),
+            _ => [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+        }
+    }
+
+    fn get(&self, key: &[u8]) -> Option<&'a Object> {
+        self.raw_pdf.dict_get_resolved(self.dict, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf::{document::FunctionType, tests::parsed_pdf, RawPdf, Reference};
+
+    use super::*;
+
+    fn document() -> RawPdf {
+        parsed_pdf(Reference::new(1, 0), &[
+            b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj",
+            b"2 0 obj <</Type /Pages /Kids [3 0 R] /Count 1>> endobj",
+            b"3 0 obj <</Type /Page /Parent 2 0 R /MediaBox [0 0 100 100] /Resources <<
+                /Shading <</Sh1 4 0 R /Sh2 <</ShadingType 9>> >>
+                /Pattern <</P1 <</PatternType 2 /Shading 4 0 R /Matrix [1 0 0 1 5 5]>> /P2 7 0 R /P3 <</PatternType 5>> >>
+            >>>> endobj",
+            b"4 0 obj <</ShadingType 2 /ColorSpace /DeviceRGB /Coords [0 0 100 0.5] /Extend [false true]
+                /Function <</FunctionType 3 /Domain [0 1] /Functions [5 0 R 6 0 R] /Bounds [0.5] /Encode [0 1 0 1]>>
+            >> endobj",
+            b"5 0 obj <</FunctionType 2 /Domain [0 1] /C0 [1 0 0] /C1 [0 0 1] /N 1>> endobj",
+            b"6 0 obj <</FunctionType 0 /Domain [0 1] /Range [0 1 0 1 0 1] /Size [2] /BitsPerSample 8 /Length 6>> stream
+abcdef
+endstream endobj",
+            b"7 0 obj <</PatternType 1 /PaintType 1 /TilingType 1 /BBox [0 0 10 10] /XStep 10 /YStep 12 /Length 12>> stream
+0 0 5 5 re f
+endstream endobj",
+        ])
+    }
+
+    #[test]
+    fn axial_shading() {
+        let pdf = document();
+        let page = pdf.page(0).unwrap();
+        let shadings = page.shadings();
+        assert_eq!(shadings.len(), 2);
+        // unknown types are still returned
+        assert_eq!(shadings[1].1.shading_type(), None);
+
+        let shading = &shadings[0].1;
+        assert_eq!(shading.shading_type(), Some(ShadingType::Axial));
+        assert_eq!(
+            shading.color_space().and_then(Object::name).map(|n| &n[..]),
+            Some(&b"DeviceRGB"[..])
+        );
+        assert_eq!(shading.coords(), Some(vec![0.0, 0.0, 100.0, 0.5]));
+        assert_eq!(shading.extend(), [false, true]);
+        assert!(shading.bbox().is_none() && shading.stream().is_none());
+
+        let functions = shading.functions();
+        assert_eq!(functions.len(), 1);
+        assert_eq!(functions[0].function_type(), Some(FunctionType::Stitching));
+        assert_eq!(functions[0].domain(), Some(vec![0.0, 1.0]));
+        assert!(functions[0].samples().is_none());
+        let stitched = functions[0].functions();
+        assert_eq!(stitched.len(), 2);
+        assert_eq!(stitched[0].function_type(), Some(FunctionType::Exponential));
+        assert_eq!(stitched[0].range(), None);
+        assert_eq!(stitched[1].function_type(), Some(FunctionType::Sampled));
+        assert_eq!(stitched[1].range(), Some(vec![0.0, 1.0, 0.0, 1.0, 0.0, 1.0]));
+        assert_eq!(&stitched[1].samples().unwrap().unwrap()[..], b"abcdef");
+    }
+
+    #[test]
+    fn patterns() {
+        let pdf = document();
+        let page = pdf.page(0).unwrap();
+        let patterns = page.patterns();
+        let names = patterns.iter().map(|(name, _)| name.to_vec()).collect::<Vec<_>>();
+        assert_eq!(names, vec![b"P1".to_vec(), b"P2".to_vec()]);
+
+        let shading = &patterns[0].1;
+        assert_eq!(shading.pattern_type(), PatternType::Shading);
+        assert_eq!(shading.matrix(), [1.0, 0.0, 0.0, 1.0, 5.0, 5.0]);
+        assert_eq!(
+            shading.shading().and_then(|s| s.shading_type()),
+            Some(ShadingType::Axial)
+        );
+
+        let tiling = &patterns[1].1;
+        assert_eq!(tiling.pattern_type(), PatternType::Tiling);
+        assert_eq!(tiling.steps(), Some((10.0, 12.0)));
+        assert_eq!(tiling.bbox().map(|bbox| bbox.x1), Some(10.0));
+        assert_eq!(&tiling.content().unwrap().data[..], b"0 0 5 5 re f");
+        assert!(tiling.shading().is_none());
+    }
+}
@@ -0,0 +1,164 @@
+use crate::pdf::{date::PdfDate, text::text_lossy, Dictionary, Name, Object, ObjectSource};
+
+const K_BYTE_RANGE: &[u8] = b"ByteRange";
+const K_CONTENTS: &[u8] = b"Contents";
+const K_SUB_FILTER: &[u8] = b"SubFilter";
+const K_REASON: &[u8] = b"Reason";
+const K_DATE: &[u8] = b"M";
+const K_NAME: &[u8] = b"Name";
+
+/// The signature dictionary of a signed signature field.
+///
+/// The signature itself is not verified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureInfo {
+    /// The fully qualified name of the signature field.
+    pub field_name: String,
+    /// The signed `(offset, length)` ranges of the file.
+    pub byte_range: Vec<(usize, usize)>,
+    /// The raw signature, e.g. a DER encoded PKCS#7 object.
+    pub contents: Vec<u8>,
+    /// The encoding of the signature, e.g. `adbe.pkcs7.detached`.
+    pub sub_filter: Option<Name>,
+    pub reason: Option<String>,
+    /// The time of signing.
+    pub date: Option<PdfDate>,
+    /// The name of the signer.
+    pub name: Option<String>,
+}
+
+fn bytes(obj: &Object) -> Option<Vec<u8>> {
+    match obj {
+        Object::String(s) => Some(s.unescaped()),
+        Object::HexString(b) => Some(b.to_vec()),
+        _ => None,
+    }
+}
+
+impl SignatureInfo {
+    /// Returns `None` if `ByteRange` or `Contents` are missing or invalid.
+    pub(crate) fn new_with(raw_pdf: &dyn ObjectSource, field_name: String, dict: &Dictionary) -> Option<Self> {
+        let byte_range = raw_pdf
+            .dict_get_resolved(dict, K_BYTE_RANGE)
+            .and_then(Object::array)
+            .and_then(|range| {
+                let values = range
+                    .iter()
+                    .map(|o| raw_pdf.resolve(o).integer().and_then(|i| usize::try_from(i).ok()))
+                    .collect::<Option<Vec<_>>>()?;
+                (values.len() % 2 == 0).then(|| values.chunks_exact(2).map(|r| (r[0], r[1])).collect())
+            });
+        let contents = raw_pdf.dict_get_resolved(dict, K_CONTENTS).and_then(bytes);
+
+        let (byte_range, contents) = match (byte_range, contents) {
+            (Some(byte_range), Some(contents)) => (byte_range, contents),
+            _ => {
+                log::warn!(
+                    "Ignoring signature `{}` without valid byte range or contents",
+                    field_name
+                );
+                return None;
+            }
+        };
+
+        let date = raw_pdf
+            .dict_get_resolved(dict, K_DATE)
+            .and_then(bytes)
+            .and_then(|date| {
+                PdfDate::parse(&date)
+                    .map_err(|err| log::warn!("Invalid date `{}`: {:?}", String::from_utf8_lossy(&date), err))
+                    .ok()
+            });
+
+        let version = raw_pdf.effective_version();
+        Some(Self {
+            field_name,
+            byte_range,
+            contents,
+            sub_filter: raw_pdf
+                .dict_get_resolved(dict, K_SUB_FILTER)
+                .and_then(Object::name)
+                .cloned(),
+            reason: raw_pdf
+                .dict_get_resolved(dict, K_REASON)
+                .and_then(|r| text_lossy(r, version)),
+            date,
+            name: raw_pdf
+                .dict_get_resolved(dict, K_NAME)
+                .and_then(|n| text_lossy(n, version)),
+        })
+    }
+
+    /// Concatenate the signed byte ranges of the original file.
+    ///
+    /// Returns `None` if a range exceeds the file, which means that
+    /// `original` is not the signed file.
+    pub fn covered_bytes(&self, original: &[u8]) -> Option<Vec<u8>> {
+        let mut total = 0usize;
+        for &(offset, len) in &self.byte_range {
+            let end = offset.checked_add(len).filter(|&end| end <= original.len());
+            let Some(end) = end else {
+                log::warn!(
+                    "Signed range at {} with length {} exceeds the file length {}",
+                    offset,
+                    len,
+                    original.len()
+                );
+                return None;
+            };
+            total = total.checked_add(end - offset)?;
+        }
+
+        let mut covered = Vec::with_capacity(total.min(original.len()));
+        for &(offset, len) in &self.byte_range {
+            covered.extend_from_slice(&original[offset..offset + len]);
+        }
+        Some(covered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf::{tests::parsed_pdf, Reference};
+
+    use super::*;
+
+    const OBJECTS: [&[u8]; 5] = [
+        b"1 0 obj <</Type /Catalog /Pages 2 0 R /AcroForm <</Fields [10 0 R 12 0 R] /SigFlags 3>>>> endobj",
+        b"2 0 obj <</Type /Pages /Kids [] /Count 0>> endobj",
+        b"10 0 obj <</T (Signature1) /FT /Sig /V 11 0 R>> endobj",
+        b"11 0 obj <</Type /Sig /Filter /Adobe.PPKLite /SubFilter /adbe.pkcs7.detached
+            /ByteRange [0 10 28 4] /Contents <3082000130>
+            /Reason (Approved) /M (D:20230102030405Z) /Name (Jane Doe)>> endobj",
+        // unsigned signature field
+        b"12 0 obj <</T (Signature2) /FT /Sig>> endobj",
+    ];
+
+    #[test]
+    fn signatures() {
+        let pdf = parsed_pdf(Reference::new(1, 0), &OBJECTS);
+
+        let signatures = pdf.signatures();
+        assert_eq!(signatures.len(), 1);
+
+        let signature = &signatures[0];
+        assert_eq!(signature.field_name, "Signature1");
+        assert_eq!(signature.byte_range, vec![(0, 10), (28, 4)]);
+        assert_eq!(signature.contents.len(), 5);
+        assert_eq!(signature.contents, vec![0x30, 0x82, 0x00, 0x01, 0x30]);
+        assert_eq!(signature.sub_filter, Some(Name::from(b"adbe.pkcs7.detached".to_vec())));
+        assert_eq!(signature.reason.as_deref(), Some("Approved"));
+        assert_eq!(signature.name.as_deref(), Some("Jane Doe"));
+        assert_eq!(signature.date.map(|d| d.year), Some(2023));
+
+        let original = b"%PDF-1.7\n1<3082000130000000>rest";
+        assert_eq!(signature.covered_bytes(original).unwrap(), b"%PDF-1.7\n1rest");
+        assert_eq!(signature.covered_bytes(&original[..20]), None);
+
+        let hostile = SignatureInfo {
+            byte_range: vec![(0, usize::MAX), (1, usize::MAX)],
+            ..signature.clone()
+        };
+        assert_eq!(hostile.covered_bytes(original), None);
+    }
+}
@@ -0,0 +1,395 @@
+//! The structure tree of tagged documents.
+
+use std::collections::HashSet;
+
+use crate::pdf::{text::text_entry, Dictionary, Name, Object, ObjectSource, Reference};
+
+use super::{number_tree::NumberTree, page::Page, K_TYPE};
+
+const K_KIDS: &[u8] = b"K";
+const K_ROLE_MAP: &[u8] = b"RoleMap";
+const K_PARENT_TREE: &[u8] = b"ParentTree";
+const K_STRUCTURE_TYPE: &[u8] = b"S";
+const K_PAGE: &[u8] = b"Pg";
+const K_MCID: &[u8] = b"MCID";
+const K_OBJECT: &[u8] = b"Obj";
+const K_ALT: &[u8] = b"Alt";
+const K_ACTUAL_TEXT: &[u8] = b"ActualText";
+const MARKED_CONTENT_REFERENCE: &[u8] = b"MCR";
+const OBJECT_REFERENCE: &[u8] = b"OBJR";
+
+/// The root of the structure tree, the `/StructTreeRoot` of the catalog.
+#[derive(Clone, PartialEq)]
+pub struct StructTree<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    dict: &'a Dictionary,
+}
+
+// Custom impl to skip `raw_pdf` field.
+impl<'a> std::fmt::Debug for StructTree<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StructTree").field("dict", &self.dict).finish()
+    }
+}
+
+impl<'a> StructTree<'a> {
+    pub(crate) fn new_with(raw_pdf: &'a dyn ObjectSource, dict: &'a Dictionary) -> Self {
+        Self { raw_pdf, dict }
+    }
+
+    /// The raw structure tree root dictionary.
+    pub fn dictionary(&self) -> &'a Dictionary {
+        self.dict
+    }
+
+    /// The mapping of custom structure types to standard ones.
+    pub fn role_map(&self) -> Option<&'a Dictionary> {
+        self.raw_pdf.dict_get_resolved(self.dict, K_ROLE_MAP)?.dictionary()
+    }
+
+    /// The number tree from the `/StructParents` entries of pages and
+    /// objects to their structure elements.
+    pub fn parent_tree(&self) -> Option<NumberTree<'a>> {
+        let root = self.raw_pdf.dict_get_resolved(self.dict, K_PARENT_TREE)?.dictionary()?;
+        Some(NumberTree::new_with(self.raw_pdf, root))
+    }
+
+    /// The top-level structure elements.
+    pub fn children(&self) -> Vec<StructElement<'a>> {
+        let context = Context {
+            raw_pdf: self.raw_pdf,
+            role_map: self.role_map(),
+            page: None,
+            path: vec![self.dict],
+        };
+        context
+            .kids(self.dict)
+            .into_iter()
+            .filter_map(|kid| match kid {
+                StructKid::Element(element) => Some(element),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// All structure elements in depth-first order. Elements that are
+    /// reachable on several paths are returned once.
+    pub fn elements(&self) -> Vec<StructElement<'a>> {
+        let mut visited = HashSet::new();
+        let mut elements = Vec::new();
+        let mut stack = self.children();
+        stack.reverse();
+        while let Some(element) = stack.pop() {
+            if !visited.insert(element.dict as *const Dictionary) {
+                continue;
+            }
+            stack.extend(element.children().into_iter().rev());
+            elements.push(element);
+        }
+        elements
+    }
+}
+
+/// A child of a structure element.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StructKid<'a> {
+    Element(StructElement<'a>),
+    /// The marked-content sequence with the given id in the content stream
+    /// of `page`.
+    MarkedContent {
+        mcid: i32,
+        page: Option<Page<'a>>,
+    },
+    /// A whole object, e.g. an annotation or a form XObject.
+    Object {
+        object: Reference,
+        page: Option<Page<'a>>,
+    },
+}
+
+/// What elements inherit from the tree and their ancestors.
+#[derive(Clone, PartialEq)]
+struct Context<'a> {
+    raw_pdf: &'a dyn ObjectSource,
+    role_map: Option<&'a Dictionary>,
+    /// The `/Pg` of the nearest ancestor with one.
+    page: Option<&'a Reference>,
+    /// The dictionaries of the ancestors, to stop at cycles.
+    path: Vec<&'a Dictionary>,
+}
+
+impl<'a> Context<'a> {
+    /// The kids of `parent` in `/K`, either a single kid or an array.
+    fn kids(&self, parent: &'a Dictionary) -> Vec<StructKid<'a>> {
+        // Not resolved here to keep the object numbers of the kids.
+        let Some(kids) = parent.get(K_KIDS) else {
+            return Vec::new();
+        };
+        match self.raw_pdf.resolve(kids) {
+            Object::Array(kids) => kids.iter().filter_map(|kid| self.kid(kid)).collect(),
+            _ => self.kid(kids).into_iter().collect(),
+        }
+    }
+
+    fn kid(&self, kid: &'a Object) -> Option<StructKid<'a>> {
+        let dict = match self.raw_pdf.resolve(kid) {
+            Object::Integer(mcid) => {
+                return Some(StructKid::MarkedContent {
+                    mcid: *mcid,
+                    page: self.page(self.page),
+                })
+            }
+            Object::Dictionary(dict) => dict,
+            other => {
+                log::warn!("Ignoring invalid structure element kid {}", other);
+                return None;
+            }
+        };
+        let page = self
+            .page(dict.get(K_PAGE).and_then(Object::reference))
+            .or_else(|| self.page(self.page));
+        match dict.get(K_TYPE).and_then(Object::name).map(|t| &t[..]) {
+            Some(MARKED_CONTENT_REFERENCE) => Some(StructKid::MarkedContent {
+                mcid: self.raw_pdf.dict_get_resolved(dict, K_MCID)?.integer()?,
+                page,
+            }),
+            Some(OBJECT_REFERENCE) => Some(StructKid::Object {
+                object: *dict.get(K_OBJECT)?.reference()?,
+                page,
+            }),
+            _ if self.path.iter().any(|ancestor| std::ptr::eq(*ancestor, dict)) => {
+                log::error!("Structure tree contains a cycle at {:?}", kid);
+                None
+            }
+            _ => Some(StructKid::Element(StructElement {
+                number: kid.reference().map(|r| r.index),
+                dict,
+                context: self.clone(),
+            })),
+        }
+    }
+
+    fn page(&self, reference: Option<&'a Reference>) -> Option<Page<'a>> {
+        let reference = reference?;
+        let dict = self.raw_pdf.dereference(reference)?.dictionary()?;
+        Some(Page::new_with(self.raw_pdf, reference.index, dict))
+    }
+}
+
+/// An element of the structure tree.
+#[derive(Clone, PartialEq)]
+pub struct StructElement<'a> {
+    /// Object number of the element dictionary, `None` for direct objects.
+    number: Option<u32>,
+    dict: &'a Dictionary,
+    /// The context of the parent.
+    context: Context<'a>,
+}
+
+// Custom impl to skip the context.
+impl<'a> std::fmt::Debug for StructElement<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StructElement")
+            .field("number", &self.number)
+            .field("structure_type", &self.structure_type())
+            .finish()
+    }
+}
+
+impl<'a> StructElement<'a> {
+    /// The object number of the element dictionary.
+    pub fn number(&self) -> Option<u32> {
+        self.number
+    }
+
+    /// The raw structure element dictionary.
+    pub fn dictionary(&self) -> &'a Dictionary {
+        self.dict
+    }
+
+    /// The structure type as written, e.g. `P` or a custom type.
+    pub fn structure_type(&self) -> Option<&'a Name> {
+        self.get(K_STRUCTURE_TYPE).and_then(Object::name)
+    }
+
+    /// The structure type after mapping custom types with the role map of
+    /// the tree. Types that are mapped in a cycle are returned unmapped.
+    pub fn role(&self) -> Option<&'a Name> {
+        let structure_type = self.structure_type()?;
+        let Some(role_map) = self.context.role_map else {
+            return Some(structure_type);
+        };
+        let mut role = structure_type;
+        let mut visited = vec![role];
+        while let Some(mapped) = self
+            .context
+            .raw_pdf
+            .dict_get_resolved(role_map, role)
+            .and_then(Object::name)
+        {
+            if visited.contains(&mapped) {
+                log::warn!("Role map contains a cycle at {:?}", mapped);
+                return Some(structure_type);
+            }
+            visited.push(mapped);
+            role = mapped;
+        }
+        Some(role)
+    }
+
+    /// The page of the content of the element, from the element or its
+    /// nearest ancestor with a page.
+    pub fn page(&self) -> Option<Page<'a>> {
+        self.context
+            .page(self.dict.get(K_PAGE).and_then(Object::reference))
+            .or_else(|| self.context.page(self.context.page))
+    }
+
+    /// The alternate description, e.g. of a figure.
+    pub fn alt(&self) -> Option<String> {
+        self.text(K_ALT)
+    }
+
+    /// The text the content of the element represents.
+    pub fn actual_text(&self) -> Option<String> {
+        self.text(K_ACTUAL_TEXT)
+    }
+
+    /// The child elements and content items. Children that are ancestors
+    /// of the element are skipped.
+    pub fn kids(&self) -> Vec<StructKid<'a>> {
+        let mut context = self.context.clone();
+        if let Some(page) = self.dict.get(K_PAGE).and_then(Object::reference) {
+            context.page = Some(page);
+        }
+        context.path.push(self.dict);
+        context.kids(self.dict)
+    }
+
+    /// The child elements without the content items.
+    pub fn children(&self) -> Vec<StructElement<'a>> {
+        self.kids()
+            .into_iter()
+            .filter_map(|kid| match kid {
+                StructKid::Element(element) => Some(element),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn get(&self, key: &[u8]) -> Option<&'a Object> {
+        self.context.raw_pdf.dict_get_resolved(self.dict, key)
+    }
+
+    fn text(&self, key: &[u8]) -> Option<String> {
+        text_entry(self.context.raw_pdf, self.dict, key, "structure element")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::pdf::{tests::parsed_pdf, RawPdf};
+
+    use super::*;
+
+    fn document(catalog: &[u8]) -> RawPdf {
+        parsed_pdf(Reference::new(1, 0), &[
+            catalog,
+            b"2 0 obj <</Type /Pages /Kids [3 0 R] /Count 1>> endobj",
+            b"3 0 obj <</Type /Page /Parent 2 0 R /MediaBox [0 0 100 100] /StructParents 0>> endobj",
+            b"10 0 obj <</Type /StructTreeRoot /K 11 0 R /ParentTree 20 0 R
+                /RoleMap <</Heading1 /H1 /Para /P /Loop1 /Loop2 /Loop2 /Loop1>>>> endobj",
+            b"11 0 obj <</Type /StructElem /S /Document /P 10 0 R /Pg 3 0 R /K [12 0 R 13 0 R 14 0 R 15 0 R]>> endobj",
+            b"12 0 obj <</Type /StructElem /S /Heading1 /P 11 0 R /K 0 /ActualText (Introduction)>> endobj",
+            b"13 0 obj <</Type /StructElem /S /Para /P 11 0 R /K [1 <</Type /MCR /MCID 2>>]>> endobj",
+            b"14 0 obj <</Type /StructElem /S /Figure /P 11 0 R /Alt <FEFF00420069007200640073>
+                /K <</Type /OBJR /Obj 30 0 R>>>> endobj",
+            // the second kid is an ancestor
+            b"15 0 obj <</Type /StructElem /S /P /P 11 0 R /K [16 0 R 11 0 R]>> endobj",
+            // 12 is also a kid of 11, roles are mapped in a cycle
+            b"16 0 obj <</S /Loop1 /P 15 0 R /K 12 0 R>> endobj",
+            b"20 0 obj <</Nums [0 [12 0 R 13 0 R 13 0 R]]>> endobj",
+        ])
+    }
+
+    fn tagged() -> RawPdf {
+        document(b"1 0 obj <</Type /Catalog /Pages 2 0 R /MarkInfo <</Marked true>> /StructTreeRoot 10 0 R>> endobj")
+    }
+
+    #[test]
+    fn is_tagged() {
+        assert!(tagged().is_tagged());
+        let untagged = document(b"1 0 obj <</Type /Catalog /Pages 2 0 R /MarkInfo <</Marked false>>>> endobj");
+        assert!(!untagged.is_tagged());
+        assert!(untagged.struct_tree().is_none());
+    }
+
+    #[test]
+    fn roles() {
+        let pdf = tagged();
+        let tree = pdf.struct_tree().unwrap();
+        let mut roles = HashMap::new();
+        for element in tree.elements() {
+            *roles.entry(element.role().unwrap().to_vec()).or_insert(0) += 1;
+        }
+        let expected = [
+            (&b"Document"[..], 1),
+            (b"H1", 1),
+            (b"P", 2),
+            (b"Figure", 1),
+            (b"Loop1", 1),
+        ]
+        .into_iter()
+        .map(|(role, count)| (role.to_vec(), count))
+        .collect::<HashMap<_, _>>();
+        assert_eq!(roles, expected);
+        assert!(tree.parent_tree().unwrap().get(0).is_some());
+    }
+
+    #[test]
+    fn elements() {
+        let pdf = tagged();
+        let tree = pdf.struct_tree().unwrap();
+        let document = &tree.children()[0];
+        assert_eq!(document.number(), Some(11));
+        let children = document.children();
+        assert_eq!(children.len(), 4);
+
+        let heading = &children[0];
+        assert_eq!(heading.structure_type().map(|s| &s[..]), Some(&b"Heading1"[..]));
+        assert_eq!(heading.actual_text().as_deref(), Some("Introduction"));
+        // inherited from the document element
+        assert_eq!(heading.page().map(|page| page.number()), Some(3));
+        match &heading.kids()[..] {
+            [StructKid::MarkedContent {
+                mcid: 0,
+                page: Some(page),
+            }] => assert_eq!(page.number(), 3),
+            other => panic!("Unexpected kids {:?}", other),
+        }
+
+        let mcids = children[1]
+            .kids()
+            .iter()
+            .map(|kid| match kid {
+                StructKid::MarkedContent { mcid, .. } => *mcid,
+                other => panic!("Unexpected kid {:?}", other),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(mcids, vec![1, 2]);
+
+        let figure = &children[2];
+        assert_eq!(figure.alt().as_deref(), Some("Birds"));
+        assert!(matches!(
+            &figure.kids()[..],
+            [StructKid::Object { object, .. }] if *object == Reference::new(30, 0)
+        ));
+
+        // the ancestor is skipped
+        let paragraph = children[3].children();
+        assert_eq!(paragraph.len(), 1);
+        assert_eq!(paragraph[0].children()[0].number(), Some(12));
+    }
+}
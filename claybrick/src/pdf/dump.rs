@@ -0,0 +1,279 @@
+//! Overview of the objects of a document for debugging.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::{self, Write},
+};
+
+use crate::pdf::{
+    document::K_TYPE, references::walk, xref::XrefEntry, Dictionary, Object, PathElement, RawPdf, Reference,
+};
+
+const K_SUBTYPE: &[u8] = b"Subtype";
+
+/// What [RawPdf::dump] writes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DumpOptions {
+    /// Expand the references from the trailer, e.g. `/Root`, instead of
+    /// listing the objects by number. Each object is expanded once.
+    pub tree: bool,
+    /// How many levels of references are expanded in the tree.
+    pub depth: usize,
+    /// Show the length of the stream data.
+    pub stream_lengths: bool,
+    /// List free xref entries and, in the tree, the objects that are not
+    /// reachable from the trailer.
+    pub unused_objects: bool,
+}
+
+impl Default for DumpOptions {
+    fn default() -> Self {
+        Self {
+            tree: false,
+            depth: usize::MAX,
+            stream_lengths: true,
+            unused_objects: false,
+        }
+    }
+}
+
+struct Dumper<'a, W> {
+    raw_pdf: &'a RawPdf,
+    out: &'a mut W,
+    options: &'a DumpOptions,
+    /// Objects that were already expanded in the tree.
+    visited: HashSet<usize>,
+}
+
+pub(crate) fn dump(raw_pdf: &RawPdf, out: &mut impl Write, options: DumpOptions) -> fmt::Result {
+    let mut dumper = Dumper {
+        raw_pdf,
+        out,
+        options: &options,
+        visited: HashSet::new(),
+    };
+    match options.tree {
+        true => dumper.tree(),
+        false => dumper.list(|_| true),
+    }
+}
+
+impl<W: Write> Dumper<'_, W> {
+    /// List the objects with their references, `filter` selects the object
+    /// numbers.
+    fn list(&mut self, filter: impl Fn(usize) -> bool) -> fmt::Result {
+        let mut entries = BTreeMap::new();
+        for (number, generation, obj) in self.raw_pdf.objects().filter(|(number, ..)| filter(*number)) {
+            entries.insert(number, (generation, Some(obj)));
+        }
+        if self.options.unused_objects {
+            let free = self
+                .raw_pdf
+                .sections
+                .iter()
+                .flat_map(|section| section.xref.free_objects())
+                .map(|free| free.number)
+                .collect::<Vec<_>>();
+            for number in free {
+                // Free entries of older sections can be used again.
+                if let Some((_, XrefEntry::Free(newest))) = self.raw_pdf.lookup(number) {
+                    entries.entry(number).or_insert((newest.generation as u32, None));
+                }
+            }
+        }
+
+        for (number, (generation, obj)) in entries {
+            let Some(obj) = obj else {
+                writeln!(self.out, "{} {} free", number, generation)?;
+                continue;
+            };
+            writeln!(self.out, "{} {} {}", number, generation, self.describe(obj))?;
+            for (path, reference) in references(obj) {
+                writeln!(self.out, "  {} → {}", Path(&path), reference)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn tree(&mut self) -> fmt::Result {
+        let Some(trailer) = self.raw_pdf.trailer().cloned() else {
+            return Ok(());
+        };
+        let root = trailer.root;
+        let references = match Dictionary::try_from(trailer) {
+            Ok(trailer) => references(&Object::Dictionary(trailer)),
+            Err(_) => vec![(vec![PathElement::Key(b"Root".to_vec().into())], root)],
+        };
+        writeln!(self.out, "trailer")?;
+        self.expand(references)?;
+
+        if self.options.unused_objects {
+            let visited = std::mem::take(&mut self.visited);
+            writeln!(self.out, "unreachable")?;
+            self.list(|number| !visited.contains(&number))?;
+        }
+        Ok(())
+    }
+
+    /// Write the referenced objects and the objects they reference, indented
+    /// by their level. Deep documents use an explicit stack instead of
+    /// recursion.
+    fn expand(&mut self, roots: Vec<(Vec<PathElement>, Reference)>) -> fmt::Result {
+        let mut stack: Vec<_> = roots.into_iter().rev().map(|(path, r)| (path, r, 1)).collect();
+        while let Some((path, reference, level)) = stack.pop() {
+            let indent = "  ".repeat(level);
+            write!(self.out, "{}{} → {}", indent, Path(&path), reference)?;
+            let Some(obj) = self.raw_pdf.lookup_reference(&reference) else {
+                writeln!(self.out, " missing")?;
+                continue;
+            };
+            if !self.visited.insert(reference.index as usize) {
+                writeln!(self.out, " (see above)")?;
+                continue;
+            }
+            writeln!(self.out, " {}", self.describe(obj))?;
+            if level < self.options.depth {
+                stack.extend(references(obj).into_iter().rev().map(|(path, r)| (path, r, level + 1)));
+            }
+        }
+        Ok(())
+    }
+
+    /// The type of `obj` and for dictionaries and streams their `/Type` and
+    /// `/Subtype`.
+    fn describe(&self, obj: &Object) -> String {
+        let mut description = obj.type_name().to_string();
+        let dict = match obj {
+            Object::Dictionary(dict) => Some(dict),
+            Object::Stream(stream) => Some(&stream.dictionary),
+            _ => None,
+        };
+        for key in [K_TYPE, K_SUBTYPE] {
+            if let Some(name) = dict.and_then(|dict| dict.get(key)).and_then(Object::name) {
+                let _ = write!(description, " /{}", name);
+            }
+        }
+        if let (Object::Stream(stream), true) = (obj, self.options.stream_lengths) {
+            let _ = write!(description, " ({} bytes)", stream.data.len());
+        }
+        description
+    }
+}
+
+/// The references in `obj` with their paths, sorted by path.
+fn references(obj: &Object) -> Vec<(Vec<PathElement>, Reference)> {
+    let mut references = Vec::new();
+    walk(obj, &mut Vec::new(), &mut |reference, path| {
+        references.push((path.to_vec(), *reference))
+    });
+    references
+}
+
+struct Path<'a>(&'a [PathElement]);
+
+impl fmt::Display for Path<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|element| write!(f, "{}", element))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf::{
+        tests::parsed_pdf,
+        xref::{FreeObject, Xref},
+    };
+
+    use super::*;
+
+    fn document() -> RawPdf {
+        let mut pdf = parsed_pdf(
+            Reference::new(1, 0),
+            &[
+                b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj",
+                b"2 0 obj <</Type /Pages /Kids [3 0 R] /Count 1>> endobj",
+                b"3 0 obj <</Type /Page /Parent 2 0 R /Contents 4 0 R /Resources <</Font <</F1 5 0 R>>>>>> endobj",
+                b"4 0 obj <</Length 5>> stream\n0 0 m\nendstream endobj",
+                b"5 0 obj <</Type /Font /Subtype /Type1 /BaseFont /Helvetica>> endobj",
+                b"7 0 obj (unused) endobj",
+            ],
+        );
+        pdf.sections[0].xref = Xref::from(vec![FreeObject {
+            number: 6,
+            generation: 1,
+            next_free: 0,
+        }
+        .into()]);
+        pdf
+    }
+
+    fn dump(options: DumpOptions) -> String {
+        let mut out = String::new();
+        document().dump(&mut out, options).unwrap();
+        out
+    }
+
+    #[test]
+    fn list() {
+        let options = DumpOptions {
+            unused_objects: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            dump(options),
+            "\
+1 0 dictionary /Catalog
+  /Pages → 2 0 R
+2 0 dictionary /Pages
+  /Kids[0] → 3 0 R
+3 0 dictionary /Page
+  /Contents → 4 0 R
+  /Parent → 2 0 R
+  /Resources/Font/F1 → 5 0 R
+4 0 stream (5 bytes)
+5 0 dictionary /Font /Type1
+6 1 free
+7 0 string
+"
+        );
+    }
+
+    #[test]
+    fn tree() {
+        let options = DumpOptions {
+            tree: true,
+            stream_lengths: false,
+            unused_objects: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            dump(options),
+            "\
+trailer
+  /Root → 1 0 R dictionary /Catalog
+    /Pages → 2 0 R dictionary /Pages
+      /Kids[0] → 3 0 R dictionary /Page
+        /Contents → 4 0 R stream
+        /Parent → 2 0 R (see above)
+        /Resources/Font/F1 → 5 0 R dictionary /Font /Type1
+unreachable
+6 1 free
+7 0 string
+"
+        );
+
+        let options = DumpOptions {
+            tree: true,
+            depth: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            dump(options),
+            "\
+trailer
+  /Root → 1 0 R dictionary /Catalog
+    /Pages → 2 0 R dictionary /Pages
+"
+        );
+    }
+}
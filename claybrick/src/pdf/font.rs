@@ -0,0 +1,4 @@
+pub use cmap::CMap;
+
+pub mod cmap;
+pub(crate) mod encoding;
@@ -0,0 +1,223 @@
+//! CMaps that map character codes to unicode (`ToUnicode`).
+//!
+//! Only the subset of the CMap syntax that is used in `ToUnicode` streams is
+//! supported: code space ranges, `bfchar` and `bfrange` mappings.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    parse::{
+        content::{parse_operations, Operator},
+        error::CbParseError,
+    },
+    pdf::{text::utf16_units, Object},
+};
+
+const END_CODESPACE_RANGE: &[u8] = b"endcodespacerange";
+const END_BF_CHAR: &[u8] = b"endbfchar";
+const END_BF_RANGE: &[u8] = b"endbfrange";
+
+/// Upper bound for the number of codes in a single `bfrange`.
+const MAX_RANGE_LEN: u32 = 0x10000;
+
+/// Maps character codes to unicode strings.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CMap {
+    /// Ranges of valid codes `(low, high)`. Both codes have the same length.
+    codespace: Vec<(Vec<u8>, Vec<u8>)>,
+    mappings: BTreeMap<Vec<u8>, String>,
+}
+
+/// The bytes of a hex string operand.
+fn code(obj: &Object) -> Option<&[u8]> {
+    match obj {
+        Object::HexString(b) => Some(b),
+        _ => None,
+    }
+}
+
+/// Big endian value of a code.
+fn code_value(code: &[u8]) -> u32 {
+    code.iter().fold(0, |acc, b| (acc << 8) | u32::from(*b))
+}
+
+/// Decode an UTF-16BE destination. Surrogate pairs are combined.
+fn decode_utf16(units: &[u16]) -> Option<String> {
+    char::decode_utf16(units.iter().copied())
+        .collect::<Result<String, _>>()
+        .ok()
+}
+
+impl CMap {
+    /// Parse a (decoded) CMap stream.
+    pub fn parse(data: &[u8]) -> Result<CMap, CbParseError<()>> {
+        let mut cmap = CMap::default();
+
+        for operation in parse_operations(data)? {
+            let keyword = match &operation.operator {
+                Operator::Other(keyword) => &keyword[..],
+                _ => continue,
+            };
+            match keyword {
+                END_CODESPACE_RANGE => {
+                    for range in operation.operands.chunks_exact(2) {
+                        match (code(&range[0]), code(&range[1])) {
+                            (Some(low), Some(high)) if low.len() == high.len() => {
+                                cmap.codespace.push((low.to_vec(), high.to_vec()))
+                            }
+                            _ => log::warn!("Ignoring invalid code space range {:?}", range),
+                        }
+                    }
+                }
+                END_BF_CHAR => {
+                    for mapping in operation.operands.chunks_exact(2) {
+                        match (
+                            code(&mapping[0]),
+                            code(&mapping[1]).map(|dst| utf16_units(dst).collect::<Vec<_>>()),
+                        ) {
+                            (Some(src), Some(dst)) => cmap.insert(src.to_vec(), &dst),
+                            _ => log::warn!("Ignoring invalid bfchar mapping {:?}", mapping),
+                        }
+                    }
+                }
+                END_BF_RANGE => {
+                    for range in operation.operands.chunks_exact(3) {
+                        if cmap.insert_range(&range[0], &range[1], &range[2]).is_none() {
+                            log::warn!("Ignoring invalid bfrange {:?}", range);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(cmap)
+    }
+
+    fn insert(&mut self, code: Vec<u8>, dst: &[u16]) {
+        match decode_utf16(dst) {
+            Some(s) => {
+                self.mappings.insert(code, s);
+            }
+            None => log::warn!("Ignoring invalid UTF-16 destination for code {:02X?}", code),
+        }
+    }
+
+    fn insert_range(&mut self, low: &Object, high: &Object, dst: &Object) -> Option<()> {
+        let (low, high) = (code(low)?, code(high)?);
+        if low.len() != high.len() || low.len() > 4 {
+            return None;
+        }
+        let (first, last) = (code_value(low), code_value(high));
+        if last < first || last - first >= MAX_RANGE_LEN {
+            return None;
+        }
+
+        let codes = (first..=last).map(|value| value.to_be_bytes()[4 - low.len()..].to_vec());
+        match dst {
+            // The last code unit is incremented for each code.
+            Object::HexString(dst) => {
+                let mut dst = utf16_units(dst).collect::<Vec<_>>();
+                let base = *dst.last()?;
+                for (offset, code) in codes.enumerate() {
+                    *dst.last_mut()? = base.wrapping_add(offset as u16);
+                    self.insert(code, &dst);
+                }
+            }
+            // One destination per code.
+            Object::Array(dsts) => {
+                for (code, dst) in codes.zip(dsts.iter()) {
+                    self.insert(code, &utf16_units(self::code(dst)?).collect::<Vec<_>>());
+                }
+            }
+            _ => return None,
+        }
+        Some(())
+    }
+
+    /// The unicode string for a character code.
+    pub fn map(&self, code: &[u8]) -> Option<&str> {
+        self.mappings.get(code).map(String::as_str)
+    }
+
+    /// Iterate over all mappings ordered by code.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &str)> {
+        self.mappings.iter().map(|(code, s)| (&code[..], s.as_str()))
+    }
+
+    /// Number of bytes of the code at the start of `bytes` according to the
+    /// code space ranges.
+    pub fn code_len(&self, bytes: &[u8]) -> Option<usize> {
+        self.codespace
+            .iter()
+            .find(|(low, high)| {
+                bytes.len() >= low.len()
+                    && bytes[..low.len()]
+                        .iter()
+                        .zip(low.iter().zip(high))
+                        .all(|(b, (l, h))| l <= b && b <= h)
+            })
+            .map(|(low, _)| low.len())
+    }
+
+    /// Map a string of character codes to unicode. Unmapped codes are
+    /// replaced with U+FFFD.
+    pub fn decode(&self, mut bytes: &[u8]) -> String {
+        let mut out = String::new();
+        while !bytes.is_empty() {
+            let len = self.code_len(bytes).unwrap_or(1);
+            let (code, remainder) = bytes.split_at(len);
+            out.push_str(self.map(code).unwrap_or("\u{FFFD}"));
+            bytes = remainder;
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TO_UNICODE: &[u8] = b"/CIDInit /ProcSet findresource begin
+12 dict begin
+begincmap
+/CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def
+/CMapName /Adobe-Identity-UCS def
+/CMapType 2 def
+1 begincodespacerange
+<0000> <FFFF>
+endcodespacerange
+3 beginbfchar
+<0003> <0020>
+<00B2> <006600660069>
+<00B3> <D835DC00>
+endbfchar
+2 beginbfrange
+<0041> <005A> <0041>
+<0061> <0063> [<0078> <0079> <007A>]
+endbfrange
+endcmap
+CMapName currentdict /CMap defineresource pop
+end
+end";
+
+    #[test]
+    fn parse_to_unicode() {
+        let cmap = CMap::parse(TO_UNICODE).unwrap();
+
+        assert_eq!(cmap.map(&[0x00, 0x03]), Some(" "));
+        assert_eq!(cmap.map(&[0x00, 0x41]), Some("A"));
+        assert_eq!(cmap.map(&[0x00, 0x4D]), Some("M"));
+        assert_eq!(cmap.map(&[0x00, 0x5A]), Some("Z"));
+        assert_eq!(cmap.map(&[0x00, 0x5B]), None);
+        // ligature
+        assert_eq!(cmap.map(&[0x00, 0xB2]), Some("ffi"));
+        // surrogate pair
+        assert_eq!(cmap.map(&[0x00, 0xB3]), Some("\u{1D400}"));
+        // array destination
+        assert_eq!(cmap.map(&[0x00, 0x62]), Some("y"));
+
+        assert_eq!(cmap.iter().count(), 3 + 26 + 3);
+        assert_eq!(cmap.decode(&[0x00, 0x48, 0x00, 0x03, 0x00, 0xB2]), "H ffi");
+    }
+}
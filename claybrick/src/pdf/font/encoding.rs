@@ -0,0 +1,76 @@
+//! The WinAnsiEncoding of simple fonts.
+
+/// The characters of the codes 128 to 159, which differ from Latin-1.
+const WIN_ANSI_HIGH: [Option<char>; 32] = [
+    Some('€'),
+    None,
+    Some('‚'),
+    Some('ƒ'),
+    Some('„'),
+    Some('…'),
+    Some('†'),
+    Some('‡'),
+    Some('ˆ'),
+    Some('‰'),
+    Some('Š'),
+    Some('‹'),
+    Some('Œ'),
+    None,
+    Some('Ž'),
+    None,
+    None,
+    Some('‘'),
+    Some('’'),
+    Some('“'),
+    Some('”'),
+    Some('•'),
+    Some('–'),
+    Some('—'),
+    Some('˜'),
+    Some('™'),
+    Some('š'),
+    Some('›'),
+    Some('œ'),
+    None,
+    Some('ž'),
+    Some('Ÿ'),
+];
+
+/// The character of a WinAnsiEncoding code. Control characters are not
+/// part of the encoding.
+pub(crate) fn win_ansi_char(code: u8) -> Option<char> {
+    match code {
+        0x20..=0x7E | 0xA0..=0xFF => Some(char::from(code)),
+        0x80..=0x9F => WIN_ANSI_HIGH[usize::from(code - 0x80)],
+        _ => None,
+    }
+}
+
+/// The WinAnsiEncoding code of `c`.
+pub(crate) fn win_ansi_code(c: char) -> Option<u8> {
+    match c {
+        ' '..='~' | '\u{A0}'..='\u{FF}' => Some(c as u8),
+        _ => WIN_ANSI_HIGH
+            .iter()
+            .position(|&high| high == Some(c))
+            .map(|index| 0x80 + index as u8),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for code in 0..=u8::MAX {
+            if let Some(c) = win_ansi_char(code) {
+                assert_eq!(win_ansi_code(c), Some(code));
+            }
+        }
+        assert_eq!(win_ansi_char(0x80), Some('€'));
+        assert_eq!(win_ansi_code('€'), Some(0x80));
+        assert_eq!(win_ansi_char(0x81), None);
+        assert_eq!(win_ansi_code('α'), None);
+    }
+}
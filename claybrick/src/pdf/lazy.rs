@@ -0,0 +1,379 @@
+//! Documents whose objects are parsed on first access.
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, OnceLock,
+};
+
+use fnv::FnvHashMap;
+
+use crate::{
+    error::CbError,
+    logging,
+    parse::{
+        header, indirect_object_at, object::stream_data_offset, object_stream::object_stream, resolved_stream_data,
+        shared_bytes, shared_span, span, xref_sections, ParseContext, ParseOptions, Span,
+    },
+    pdf::{
+        catalog, document::catalog::catalog_version, xref::XrefEntry, Catalog, CatalogError, DocumentInfo, Object,
+        ObjectSource, Page, Reference, StreamDataProvenance, Trailer, Xref,
+    },
+};
+
+/// A document that only parses the header, the xref sections and the
+/// trailers upfront. Objects are parsed when they are accessed for the first
+/// time and kept until the document is dropped.
+///
+/// Opening a large document is fast, but invalid objects are only detected
+/// when they are accessed. Use [RawPdf](crate::pdf::RawPdf) to process all
+/// objects of a document.
+pub struct LazyPdf {
+    version: (u8, u8),
+    announced_binary: bool,
+    /// Bytes of junk before the header.
+    header_offset: usize,
+    input: Arc<Vec<u8>>,
+    /// The xref sections and trailers, starting with the newest one.
+    sections: Vec<(Xref, Trailer)>,
+    /// Byte offset and parsed object of the uncompressed objects.
+    objects: FnvHashMap<usize, (usize, OnceLock<Option<Object>>)>,
+    /// Mapping from compressed objects to the number of the object stream
+    /// that contains them.
+    compressed: FnvHashMap<usize, usize>,
+    /// The parsed contents of the object streams.
+    object_streams: FnvHashMap<usize, OnceLock<FnvHashMap<usize, Object>>>,
+    /// Number of objects parsed so far.
+    loaded: AtomicUsize,
+}
+
+// Custom impl to skip the `input` and the parsed objects.
+impl std::fmt::Debug for LazyPdf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LazyPdf")
+            .field("version", &self.version)
+            .field("announced_binary", &self.announced_binary)
+            .field("sections", &self.sections)
+            .field("loaded", &self.loaded)
+            .finish()
+    }
+}
+
+impl LazyPdf {
+    /// Parse the structure of the document in `input`.
+    pub fn parse(input: Vec<u8>) -> Result<Self, CbError> {
+        let span = span(&input[..]);
+        let (_, header) = header(span)?;
+        let (_, sections) = xref_sections(span, header.offset, &ParseOptions::default())?;
+        Ok(Self::new_with(
+            header.version,
+            header.binary_indicator.is_some(),
+            header.offset,
+            Arc::new(input),
+            sections,
+        ))
+    }
+
+    fn new_with(
+        version: (u8, u8),
+        announced_binary: bool,
+        header_offset: usize,
+        input: Arc<Vec<u8>>,
+        sections: Vec<(Xref, Trailer)>,
+    ) -> Self {
+        let mut objects = FnvHashMap::default();
+        let mut compressed = FnvHashMap::default();
+        // The first entry of an object is the newest version.
+        for (xref, _) in &sections {
+            for entry in xref.entries() {
+                let number = entry.number();
+                if objects.contains_key(&number) || compressed.contains_key(&number) {
+                    continue;
+                }
+                match entry {
+                    XrefEntry::Used(used) => {
+                        objects.insert(number, (used.byte_offset, OnceLock::new()));
+                    }
+                    XrefEntry::UsedCompressed(c) => {
+                        compressed.insert(number, c.containing_object);
+                    }
+                    XrefEntry::Free(_) | XrefEntry::Unsupported(_) => {}
+                }
+            }
+        }
+        let object_streams = compressed.values().map(|&stream| (stream, OnceLock::new())).collect();
+
+        Self {
+            version,
+            announced_binary,
+            header_offset,
+            input,
+            sections,
+            objects,
+            compressed,
+            object_streams,
+            loaded: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn version(&self) -> (u8, u8) {
+        self.version
+    }
+
+    /// The version of the document, the newer one of the header and the
+    /// `/Version` of the catalog, like
+    /// [RawPdf::effective_version](crate::pdf::RawPdf::effective_version).
+    pub fn effective_version(&self) -> (u8, u8) {
+        let catalog = self.trailer().and_then(|trailer| catalog_version(self, &trailer.root));
+        catalog.map_or(self.version, |catalog| catalog.max(self.version))
+    }
+
+    /// The xref sections and trailers, starting with the newest one.
+    pub fn sections(&self) -> impl Iterator<Item = (&Xref, &Trailer)> {
+        self.sections.iter().map(|(xref, trailer)| (xref, trailer))
+    }
+
+    /// The newest version of the object. Objects from object streams are
+    /// returned without the indirect object wrapper, like in
+    /// [RawPdf](crate::pdf::RawPdf).
+    pub fn object(&self, number: usize) -> Option<&Object> {
+        if let Some((offset, cell)) = self.objects.get(&number) {
+            return cell.get_or_init(|| self.parse_object(number, *offset)).as_ref();
+        }
+        let stream = *self.compressed.get(&number)?;
+        self.object_streams[&stream]
+            .get_or_init(|| self.parse_object_stream(stream))
+            .get(&number)
+    }
+
+    /// Number of objects that were parsed so far.
+    pub fn loaded_objects(&self) -> usize {
+        self.loaded.load(Ordering::Relaxed)
+    }
+
+    pub fn catalog(&self) -> Result<Catalog<'_>, CatalogError> {
+        catalog(self)
+    }
+
+    /// The document information dictionary.
+    pub fn info(&self) -> Option<DocumentInfo<'_>> {
+        let info = self.sections.iter().find_map(|(_, trailer)| trailer.info.as_ref())?;
+        let dict = self.dereference(info)?.dictionary()?;
+        Some(DocumentInfo::new_with(self, dict))
+    }
+
+    /// Number of pages in the document.
+    pub fn page_count(&self) -> Result<usize, CatalogError> {
+        self.catalog()?.page_count()
+    }
+
+    /// The page with the given zero-based index or `None` if the index is out
+    /// of range.
+    pub fn page(&self, index: usize) -> Option<Page<'_>> {
+        self.catalog().ok()?.page(index)
+    }
+
+    fn parse_object(&self, number: usize, offset: usize) -> Option<Object> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("object", object_number = number, byte_offset = offset).entered();
+        logging::debug!("Parse object {} at {}", number, offset);
+        self.loaded.fetch_add(1, Ordering::Relaxed);
+        if offset > self.input.len() {
+            logging::error!("Offset {} of object {} is beyond the end of the file", offset, number);
+            return None;
+        }
+        // Stream data shares the input.
+        let input = shared_span(&self.input);
+        match indirect_object_at(input, number, offset, self.header_offset) {
+            Ok((_, (obj_bytes, mut obj))) => {
+                self.resolve_stream_length(input, number, obj_bytes, &mut obj);
+                Some(obj)
+            }
+            Err(err) => {
+                logging::error!("Invalid object {} at {}: {:?}", number, offset, err);
+                None
+            }
+        }
+    }
+
+    /// Use the `/Length` of a stream that is a reference, like
+    /// [read_bytes](crate::read_bytes). Only uncompressed lengths are
+    /// resolved. They are parsed again instead of loaded, since loading an
+    /// object could need the stream that is being parsed.
+    fn resolve_stream_length(&self, input: Span, number: usize, obj_bytes: Span, obj: &mut Object) {
+        let Object::Indirect(indirect) = obj else {
+            return;
+        };
+        let Object::Stream(stream) = &mut *indirect.object else {
+            return;
+        };
+        let Some(Object::Reference(length)) = stream.dictionary.get(&b"Length"[..]) else {
+            return;
+        };
+        if stream.provenance != StreamDataProvenance::KeywordScan {
+            return;
+        }
+        let Some(length_number) = usize::try_from(length.index).ok().filter(|&n| n != number) else {
+            return;
+        };
+        let Some((offset, _)) = self.objects.get(&length_number) else {
+            return;
+        };
+        let length = match indirect_object_at(input, length_number, *offset, self.header_offset) {
+            Ok((_, (_, Object::Indirect(resolved)))) if resolved.generation == length.generation => resolved.object,
+            _ => return,
+        };
+        let range = stream_data_offset(obj_bytes)
+            .ok()
+            .and_then(|(_, start)| resolved_stream_data(input.fragment(), start, &length));
+        if let Some(range) = range {
+            stream.data = shared_bytes(&nom::Slice::slice(&input, range));
+            stream.provenance = StreamDataProvenance::LengthResolvedFromReference;
+        }
+    }
+
+    fn parse_object_stream(&self, number: usize) -> FnvHashMap<usize, Object> {
+        // Object streams can't be compressed themselves, which also prevents
+        // a stream from containing itself.
+        let stream = self
+            .objects
+            .get(&number)
+            .and_then(|(offset, cell)| cell.get_or_init(|| self.parse_object(number, *offset)).as_ref())
+            .and_then(Object::indirect)
+            .and_then(|indirect| indirect.object.stream());
+        let Some(stream) = stream else {
+            logging::error!("Object stream {} is missing", number);
+            return FnvHashMap::default();
+        };
+        match object_stream(stream, ParseContext::default().in_object_stream(number)) {
+            Ok(objects) => {
+                self.loaded.fetch_add(objects.len(), Ordering::Relaxed);
+                objects.into_iter().collect()
+            }
+            Err(err) => {
+                logging::error!("Invalid object stream {}: {:?}", number, err);
+                FnvHashMap::default()
+            }
+        }
+    }
+}
+
+impl ObjectSource for LazyPdf {
+    fn trailer(&self) -> Option<&Trailer> {
+        self.sections.first().map(|(_, trailer)| trailer)
+    }
+
+    /// Like [RawPdf::dereference](crate::pdf::RawPdf::dereference) but also
+    /// finds objects from object streams, which always have generation 0.
+    fn dereference(&self, reference: &Reference) -> Option<&Object> {
+        match self.object(usize::try_from(reference.index).ok()?)? {
+            Object::Indirect(indirect) if indirect.generation == reference.generation => Some(&*indirect.object),
+            Object::Indirect(_) => None,
+            direct if reference.generation == 0 => Some(direct),
+            _ => None,
+        }
+    }
+
+    fn effective_version(&self) -> (u8, u8) {
+        LazyPdf::effective_version(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        build::{Info, PdfBuilder},
+        pdf::{xref::UsedCompressedObject, Stream},
+        simple_encode::{EncodeOptions, SimpleEncoder, XrefStyle},
+    };
+
+    use super::*;
+
+    fn document(pages: usize) -> Vec<u8> {
+        let mut builder = PdfBuilder::new();
+        for _ in 0..pages {
+            let page = builder.add_page(612.0, 792.0);
+            builder.set_page_content(page, &b"0 0 m 612 792 l S"[..]);
+        }
+        builder.set_info(Info {
+            title: Some("Lazy".to_string()),
+            ..Default::default()
+        });
+        let mut out = Vec::new();
+        let options = EncodeOptions {
+            xref: XrefStyle::Table,
+            ..Default::default()
+        };
+        SimpleEncoder::write_with(&builder.finish().unwrap(), &mut out, options).unwrap();
+        out
+    }
+
+    #[test]
+    fn load_on_access() {
+        let pdf = LazyPdf::parse(document(1000)).unwrap();
+        assert_eq!(pdf.version(), (1, 7));
+        assert_eq!(pdf.trailer().unwrap().size, 2004);
+        assert_eq!(pdf.loaded_objects(), 0);
+
+        // The info dictionary and the catalog for the version of the title.
+        assert_eq!(pdf.info().unwrap().title().as_deref(), Some("Lazy"));
+        assert_eq!(pdf.loaded_objects(), 2);
+
+        // Page tree root, page and content stream.
+        let page = pdf.page(0).unwrap();
+        assert_eq!(page.number(), 3);
+        assert_eq!(page.operations().unwrap().len(), 3);
+        assert_eq!(pdf.loaded_objects(), 5);
+
+        assert_eq!(pdf.page_count(), Ok(1000));
+        assert_eq!(pdf.loaded_objects(), 5);
+    }
+
+    #[test]
+    fn compressed_objects() {
+        let input = document(1);
+        let span = span(&input[..]);
+        let (_, mut sections) = xref_sections(span, 0, &ParseOptions::default()).unwrap();
+
+        // Append an object stream that contains object 10.
+        let offset = input.len();
+        let mut input = input;
+        let data = b"10 0 <</Compressed true>>";
+        input.extend_from_slice(
+            format!(
+                "9 0 obj <</Type /ObjStm /N 1 /First 5 /Length {}>> stream\n",
+                data.len()
+            )
+            .as_bytes(),
+        );
+        input.extend_from_slice(data);
+        input.extend_from_slice(b"\nendstream endobj\n");
+        let mut entries = sections[0].0.entries.clone();
+        entries.push(
+            crate::pdf::xref::UsedObject {
+                number: 9,
+                byte_offset: offset,
+                generation: 0,
+            }
+            .into(),
+        );
+        entries.push(XrefEntry::UsedCompressed(UsedCompressedObject {
+            number: 10,
+            containing_object: 9,
+            index: 0,
+        }));
+        sections[0].0 = Xref::new(entries);
+
+        let pdf = LazyPdf::new_with((1, 7), true, 0, Arc::new(input), sections);
+        let compressed = pdf.dereference(&Reference::new(10, 0)).unwrap();
+        assert_eq!(
+            compressed.dictionary().unwrap().get(&b"Compressed"[..]),
+            Some(&Object::Bool(true))
+        );
+        assert_eq!(pdf.dereference(&Reference::new(10, 1)), None);
+        assert!(matches!(
+            pdf.dereference(&Reference::new(9, 0)),
+            Some(Object::Stream(Stream { .. }))
+        ));
+        assert_eq!(pdf.loaded_objects(), 2);
+    }
+}
@@ -1,17 +1,21 @@
 use std::fmt::Display;
 
-use super::{Bytes, Dictionary};
+use super::Bytes;
 
 pub mod array;
+pub mod dictionary;
+mod display;
 pub mod indirect;
 pub mod name;
 pub mod stream;
 pub mod string;
 
 pub use array::Array;
+pub use dictionary::{DictExt, Dictionary, KeyError};
+pub use display::DisplayDepth;
 pub use indirect::{IndirectObject, Reference};
 pub use name::Name;
-pub use stream::Stream;
+pub use stream::{Stream, StreamDataProvenance};
 pub use string::CbString;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -31,6 +35,24 @@ pub enum Object {
 }
 
 impl Object {
+    /// Human readable name of the object type.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Object::String(_) => "string",
+            Object::HexString(_) => "hex string",
+            Object::Float(_) => "float",
+            Object::Integer(_) => "integer",
+            Object::Bool(_) => "bool",
+            Object::Name(_) => "name",
+            Object::Array(_) => "array",
+            Object::Dictionary(_) => "dictionary",
+            Object::Stream(_) => "stream",
+            Object::Null => "null",
+            Object::Indirect(_) => "indirect object",
+            Object::Reference(_) => "reference",
+        }
+    }
+
     pub fn name(&self) -> Option<&Name> {
         if let Object::Name(n) = self {
             Some(n)
@@ -94,28 +116,105 @@ impl Object {
             None
         }
     }
+
+    pub fn string(&self) -> Option<&CbString> {
+        if let Object::String(s) = self {
+            Some(s)
+        } else {
+            None
+        }
+    }
+
+    pub fn float(&self) -> Option<f32> {
+        if let Object::Float(f) = self {
+            Some(*f)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        if let Object::Bool(b) = self {
+            Some(*b)
+        } else {
+            None
+        }
+    }
+
+    /// The value of a number object. Writers are free to use reals for
+    /// integral values and the other way around.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Object::Integer(i) => Some(f64::from(*i)),
+            Object::Float(f) => Some(f64::from(*f)),
+            _ => None,
+        }
+    }
+
+    /// The value of an integer object or a real object without fractional
+    /// part.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Object::Integer(i) => Some(i64::from(*i)),
+            Object::Float(f) if f.fract() == 0.0 && (i64::MIN as f32..i64::MAX as f32).contains(f) => Some(*f as i64),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Object::Null)
+    }
+
+    pub fn is_stream(&self) -> bool {
+        matches!(self, Object::Stream(_))
+    }
+
+    pub fn is_reference(&self) -> bool {
+        matches!(self, Object::Reference(_))
+    }
+
+    /// The dictionary or the object itself if it's something else.
+    pub fn into_dictionary(self) -> Result<Dictionary, Object> {
+        match self {
+            Object::Dictionary(d) => Ok(d),
+            obj => Err(obj),
+        }
+    }
+
+    /// The array or the object itself if it's something else.
+    pub fn into_array(self) -> Result<Array, Object> {
+        match self {
+            Object::Array(a) => Ok(a),
+            obj => Err(obj),
+        }
+    }
+
+    /// The stream or the object itself if it's something else.
+    pub fn into_stream(self) -> Result<Stream, Object> {
+        match self {
+            Object::Stream(s) => Ok(s),
+            obj => Err(obj),
+        }
+    }
+
+    /// The string or the object itself if it's something else.
+    pub fn into_string(self) -> Result<CbString, Object> {
+        match self {
+            Object::String(s) => Ok(s),
+            obj => Err(obj),
+        }
+    }
+
+    /// Display the object with at most `depth` levels of nested arrays and
+    /// dictionaries, deeper ones only show their length.
+    pub fn display_depth(&self, depth: usize) -> DisplayDepth<'_> {
+        DisplayDepth::new(display::Value::Object(self), depth)
+    }
 }
 
 impl Display for Object {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Object::String(obj) => obj.fmt(f),
-            Object::HexString(obj) => obj.fmt(f),
-            Object::Float(obj) => obj.fmt(f),
-            Object::Integer(obj) => obj.fmt(f),
-            Object::Bool(obj) => obj.fmt(f),
-            Object::Name(obj) => obj.fmt(f),
-            Object::Array(obj) => obj.fmt(f),
-            //TODO: implement display
-            Object::Dictionary(_obj) => write!(f, "dict"),
-            Object::Stream(Stream {
-                dictionary: _dict,
-                data: _data,
-            }) => write!(f, "Stream {{}}"),
-            Object::Null => write!(f, "NULL"),
-            Object::Indirect(obj) => obj.fmt(f),
-            Object::Reference(obj) => write!(f, "{:?}", obj),
-        }
+        self.display_depth(usize::MAX).fmt(f)
     }
 }
 
@@ -172,3 +271,89 @@ impl From<Stream> for Object {
         Self::Stream(s)
     }
 }
+
+/// The value is too large for an integer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntegerOutOfRange(pub usize);
+
+/// The value as PDF integer, which is limited to 32 bits by most readers.
+pub(crate) fn integer(value: usize) -> Result<Object, IntegerOutOfRange> {
+    match i32::try_from(value) {
+        Ok(value) => Ok(Object::Integer(value)),
+        Err(_) => {
+            log::error!("{} is too large for an integer", value);
+            Err(IntegerOutOfRange(value))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers() {
+        assert_eq!(Object::Integer(3).as_f64(), Some(3.0));
+        assert_eq!(Object::Float(1.5).as_f64(), Some(1.5));
+        assert_eq!(Object::Bool(true).as_f64(), None);
+
+        assert_eq!(Object::Integer(-3).as_i64(), Some(-3));
+        assert_eq!(Object::Float(4.0).as_i64(), Some(4));
+        assert_eq!(Object::Float(4.5).as_i64(), None);
+        assert_eq!(Object::Float(f32::INFINITY).as_i64(), None);
+        assert_eq!(Object::Float(f32::NAN).as_i64(), None);
+        assert_eq!(Object::Float(1e30).as_i64(), None);
+        assert_eq!(Object::Null.as_i64(), None);
+
+        assert_eq!(Object::Float(2.5).float(), Some(2.5));
+        assert_eq!(Object::Integer(2).float(), None);
+    }
+
+    #[test]
+    fn accessors() {
+        assert_eq!(Object::Bool(false).as_bool(), Some(false));
+        assert_eq!(Object::Integer(0).as_bool(), None);
+
+        let string = CbString::from(b"text".to_vec());
+        assert_eq!(Object::String(string.clone()).string(), Some(&string));
+        assert_eq!(Object::HexString(b"text".to_vec().into()).string(), None);
+
+        assert!(Object::Null.is_null());
+        assert!(!Object::Bool(false).is_null());
+        assert!(Object::Reference(Reference::new(1, 0)).is_reference());
+        assert!(!Object::Integer(1).is_reference());
+        let stream = Stream {
+            dictionary: Dictionary::new(),
+            data: Vec::new().into(),
+            provenance: Default::default(),
+        };
+        assert!(Object::Stream(stream).is_stream());
+        assert!(!Object::Dictionary(Dictionary::new()).is_stream());
+    }
+
+    #[test]
+    fn consumers() {
+        let dict = Dictionary::from([(b"Key".to_vec().into(), Object::Integer(1))]);
+        assert_eq!(Object::Dictionary(dict.clone()).into_dictionary(), Ok(dict.clone()));
+        assert_eq!(Object::Integer(1).into_dictionary(), Err(Object::Integer(1)));
+
+        let array = Array::from(vec![Object::Null]);
+        assert_eq!(Object::Array(array.clone()).into_array(), Ok(array));
+        assert_eq!(
+            Object::Dictionary(dict.clone()).into_array(),
+            Err(Object::Dictionary(dict.clone()))
+        );
+
+        let stream = Stream {
+            dictionary: dict.clone(),
+            data: b"data".to_vec().into(),
+            provenance: Default::default(),
+        };
+        assert_eq!(Object::Stream(stream.clone()).into_stream(), Ok(stream));
+        assert_eq!(Object::Null.into_stream(), Err(Object::Null));
+
+        let string = CbString::from(b"text".to_vec());
+        assert_eq!(Object::String(string.clone()).into_string(), Ok(string));
+        assert_eq!(Object::Bool(true).into_string(), Err(Object::Bool(true)));
+    }
+}
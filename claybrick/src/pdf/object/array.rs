@@ -2,6 +2,8 @@ use std::ops::{Deref, DerefMut};
 
 use crate::pdf::Object;
 
+use super::display::{DisplayDepth, Value};
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Array(Vec<Object>);
 
@@ -39,11 +41,6 @@ impl From<Vec<Object>> for Array {
 
 impl std::fmt::Display for Array {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Array [")?;
-        for obj in self.iter() {
-            write!(f, "\n  {}", obj)?;
-        }
-        write!(f, "]")?;
-        Ok(())
+        DisplayDepth::new(Value::Array(self), usize::MAX).fmt(f)
     }
 }
@@ -0,0 +1,462 @@
+use std::{borrow::Borrow, collections::HashMap, hash::Hash};
+
+use fnv::FnvHashMap;
+
+use crate::pdf::{Array, Name, Object, Reference, Stream};
+
+use super::display::{DisplayDepth, Value};
+
+/// A dictionary that keeps the order in which the entries were inserted.
+///
+/// Parsed dictionaries keep the order of the file, so they are encoded with
+/// the same key order. Equality ignores the order.
+#[derive(Clone, Default)]
+pub struct Dictionary {
+    entries: Vec<(Name, Object)>,
+    /// Position of each key in `entries`.
+    index: FnvHashMap<Name, usize>,
+}
+
+pub type Iter<'a> =
+    std::iter::Map<std::slice::Iter<'a, (Name, Object)>, fn(&'a (Name, Object)) -> (&'a Name, &'a Object)>;
+
+impl Dictionary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+            index: FnvHashMap::with_capacity_and_hasher(capacity, Default::default()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<&Object>
+    where
+        Name: Borrow<Q>,
+    {
+        self.index.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    pub fn get_mut<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<&mut Object>
+    where
+        Name: Borrow<Q>,
+    {
+        self.index.get(key).map(|&i| &mut self.entries[i].1)
+    }
+
+    pub fn contains_key<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> bool
+    where
+        Name: Borrow<Q>,
+    {
+        self.index.contains_key(key)
+    }
+
+    /// Insert the entry and return the previous value. A replaced value keeps
+    /// the position of the previous one, new entries are appended.
+    pub fn insert(&mut self, key: Name, value: Object) -> Option<Object> {
+        match self.index.get(&key) {
+            Some(&i) => Some(std::mem::replace(&mut self.entries[i].1, value)),
+            None => {
+                self.index.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Remove the entry and keep the order of the remaining ones.
+    pub fn remove<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<Object>
+    where
+        Name: Borrow<Q>,
+    {
+        let i = self.index.remove(key)?;
+        let (_, value) = self.entries.remove(i);
+        for (moved, _) in &self.entries[i..] {
+            *self.index.get_mut::<Name>(moved).expect("all keys are indexed") -= 1;
+        }
+        Some(value)
+    }
+
+    /// Keep the entries for which `keep` returns `true`.
+    pub fn retain(&mut self, mut keep: impl FnMut(&Name, &mut Object) -> bool) {
+        self.entries.retain_mut(|(key, value)| keep(key, value));
+        self.reindex();
+    }
+
+    fn reindex(&mut self) {
+        self.index.clear();
+        for (i, (key, _)) in self.entries.iter().enumerate() {
+            self.index.insert(key.clone(), i);
+        }
+    }
+
+    /// The entries in insertion order.
+    pub fn iter(&self) -> Iter<'_> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    /// The entries sorted by their keys, e.g. for output that doesn't depend
+    /// on the insertion order.
+    pub(crate) fn sorted_entries(&self) -> Vec<(&Name, &Object)> {
+        let mut entries = self.iter().collect::<Vec<_>>();
+        entries.sort_unstable_by_key(|(key, _)| &key[..]);
+        entries
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&Name, &mut Object)> {
+        self.entries.iter_mut().map(|(key, value)| (&*key, value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Name> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Object> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut Object> {
+        self.entries.iter_mut().map(|(_, value)| value)
+    }
+}
+
+/// The order of the entries is ignored.
+impl PartialEq for Dictionary {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl std::fmt::Debug for Dictionary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl std::fmt::Display for Dictionary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        DisplayDepth::new(Value::Dictionary(self), usize::MAX).fmt(f)
+    }
+}
+
+impl Extend<(Name, Object)> for Dictionary {
+    fn extend<T: IntoIterator<Item = (Name, Object)>>(&mut self, iter: T) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl FromIterator<(Name, Object)> for Dictionary {
+    fn from_iter<T: IntoIterator<Item = (Name, Object)>>(iter: T) -> Self {
+        let mut dict = Dictionary::new();
+        dict.extend(iter);
+        dict
+    }
+}
+
+impl<const N: usize> From<[(Name, Object); N]> for Dictionary {
+    fn from(entries: [(Name, Object); N]) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+/// The entries are inserted in the iteration order of the map.
+impl From<HashMap<Name, Object>> for Dictionary {
+    fn from(map: HashMap<Name, Object>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl IntoIterator for Dictionary {
+    type Item = (Name, Object);
+    type IntoIter = std::vec::IntoIter<(Name, Object)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Dictionary {
+    type Item = (&'a Name, &'a Object);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Error returned by the typed getters of [DictExt].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyError {
+    /// The key is not present in the dictionary.
+    Missing(Name),
+
+    /// The key is present but the value has an unexpected type.
+    WrongType {
+        key: Name,
+        expected: &'static str,
+        found: &'static str,
+    },
+}
+
+impl KeyError {
+    /// The key that caused the error.
+    pub fn key(&self) -> &Name {
+        match self {
+            KeyError::Missing(key) => key,
+            KeyError::WrongType { key, .. } => key,
+        }
+    }
+
+    /// Returns `missing` if the key was not present and `invalid` if the value
+    /// had the wrong type.
+    pub fn into_either<E>(self, missing: E, invalid: E) -> E {
+        match self {
+            KeyError::Missing(_) => missing,
+            KeyError::WrongType { .. } => invalid,
+        }
+    }
+}
+
+impl std::fmt::Display for KeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyError::Missing(key) => write!(f, "missing key `{}`", key),
+            KeyError::WrongType { key, expected, found } => {
+                write!(f, "key `{}` has type {} but {} was expected", key, found, expected)
+            }
+        }
+    }
+}
+
+/// Typed access to dictionary values.
+///
+/// The `get_*` methods require the key to be present, the `get_*_opt` variants
+/// return `Ok(None)` for missing keys. Both fail if the value has a different
+/// type.
+pub trait DictExt {
+    fn get_integer(&self, key: &[u8]) -> Result<i64, KeyError>;
+    fn get_name(&self, key: &[u8]) -> Result<&Name, KeyError>;
+    fn get_array(&self, key: &[u8]) -> Result<&Array, KeyError>;
+    fn get_dict(&self, key: &[u8]) -> Result<&Dictionary, KeyError>;
+    fn get_reference(&self, key: &[u8]) -> Result<&Reference, KeyError>;
+    fn get_stream(&self, key: &[u8]) -> Result<&Stream, KeyError>;
+
+    fn get_integer_opt(&self, key: &[u8]) -> Result<Option<i64>, KeyError>;
+    fn get_name_opt(&self, key: &[u8]) -> Result<Option<&Name>, KeyError>;
+    fn get_array_opt(&self, key: &[u8]) -> Result<Option<&Array>, KeyError>;
+    fn get_dict_opt(&self, key: &[u8]) -> Result<Option<&Dictionary>, KeyError>;
+    fn get_reference_opt(&self, key: &[u8]) -> Result<Option<&Reference>, KeyError>;
+    fn get_stream_opt(&self, key: &[u8]) -> Result<Option<&Stream>, KeyError>;
+}
+
+fn typed_opt<'a, T>(
+    dict: &'a Dictionary,
+    key: &[u8],
+    expected: &'static str,
+    convert: impl FnOnce(&'a Object) -> Option<T>,
+) -> Result<Option<T>, KeyError> {
+    dict.get(key)
+        .map(|obj| {
+            convert(obj).ok_or_else(|| KeyError::WrongType {
+                key: key.to_vec().into(),
+                expected,
+                found: obj.type_name(),
+            })
+        })
+        .transpose()
+}
+
+fn typed<'a, T>(
+    dict: &'a Dictionary,
+    key: &[u8],
+    expected: &'static str,
+    convert: impl FnOnce(&'a Object) -> Option<T>,
+) -> Result<T, KeyError> {
+    typed_opt(dict, key, expected, convert)?.ok_or_else(|| KeyError::Missing(key.to_vec().into()))
+}
+
+impl DictExt for Dictionary {
+    fn get_integer(&self, key: &[u8]) -> Result<i64, KeyError> {
+        typed(self, key, "integer", |o| o.integer().map(i64::from))
+    }
+
+    fn get_name(&self, key: &[u8]) -> Result<&Name, KeyError> {
+        typed(self, key, "name", Object::name)
+    }
+
+    fn get_array(&self, key: &[u8]) -> Result<&Array, KeyError> {
+        typed(self, key, "array", Object::array)
+    }
+
+    fn get_dict(&self, key: &[u8]) -> Result<&Dictionary, KeyError> {
+        typed(self, key, "dictionary", Object::dictionary)
+    }
+
+    fn get_reference(&self, key: &[u8]) -> Result<&Reference, KeyError> {
+        typed(self, key, "reference", Object::reference)
+    }
+
+    fn get_stream(&self, key: &[u8]) -> Result<&Stream, KeyError> {
+        typed(self, key, "stream", Object::stream)
+    }
+
+    fn get_integer_opt(&self, key: &[u8]) -> Result<Option<i64>, KeyError> {
+        typed_opt(self, key, "integer", |o| o.integer().map(i64::from))
+    }
+
+    fn get_name_opt(&self, key: &[u8]) -> Result<Option<&Name>, KeyError> {
+        typed_opt(self, key, "name", Object::name)
+    }
+
+    fn get_array_opt(&self, key: &[u8]) -> Result<Option<&Array>, KeyError> {
+        typed_opt(self, key, "array", Object::array)
+    }
+
+    fn get_dict_opt(&self, key: &[u8]) -> Result<Option<&Dictionary>, KeyError> {
+        typed_opt(self, key, "dictionary", Object::dictionary)
+    }
+
+    fn get_reference_opt(&self, key: &[u8]) -> Result<Option<&Reference>, KeyError> {
+        typed_opt(self, key, "reference", Object::reference)
+    }
+
+    fn get_stream_opt(&self, key: &[u8]) -> Result<Option<&Stream>, KeyError> {
+        typed_opt(self, key, "stream", Object::stream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Dictionary {
+        Dictionary::from([
+            (b"Int".to_vec().into(), Object::Integer(42)),
+            (b"Name".to_vec().into(), Object::Name(b"Catalog".to_vec().into())),
+            (b"Array".to_vec().into(), Object::from(vec![Object::Integer(1)])),
+            (b"Dict".to_vec().into(), Object::Dictionary(Dictionary::new())),
+            (b"Ref".to_vec().into(), Object::Reference(Reference::new(3, 0))),
+            (
+                b"Stream".to_vec().into(),
+                Object::Stream(Stream {
+                    dictionary: Dictionary::new(),
+                    data: b"data".to_vec().into(),
+                    provenance: Default::default(),
+                }),
+            ),
+        ])
+    }
+
+    fn wrong_type(key: &[u8], expected: &'static str, found: &'static str) -> KeyError {
+        KeyError::WrongType {
+            key: key.to_vec().into(),
+            expected,
+            found,
+        }
+    }
+
+    #[test]
+    fn insertion_order() {
+        let mut dict = sample();
+        let keys = |dict: &Dictionary| dict.keys().map(|k| k.to_vec()).collect::<Vec<_>>();
+        assert_eq!(
+            keys(&dict),
+            [&b"Int"[..], b"Name", b"Array", b"Dict", b"Ref", b"Stream"]
+        );
+
+        assert_eq!(
+            dict.insert(b"Int".to_vec().into(), Object::Integer(1)),
+            Some(Object::Integer(42))
+        );
+        assert_eq!(dict.remove(&b"Array"[..]), Some(Object::from(vec![Object::Integer(1)])));
+        dict.insert(b"Array".to_vec().into(), Object::Null);
+        assert_eq!(
+            keys(&dict),
+            [&b"Int"[..], b"Name", b"Dict", b"Ref", b"Stream", b"Array"]
+        );
+        assert_eq!(dict.get(&b"Ref"[..]), Some(&Object::Reference(Reference::new(3, 0))));
+
+        // Equality ignores the order.
+        let reversed: Dictionary = dict.clone().into_iter().rev().collect();
+        assert_eq!(reversed, dict);
+        assert_ne!(keys(&reversed), keys(&dict));
+    }
+
+    #[test]
+    fn get_integer() {
+        let dict = sample();
+        assert_eq!(dict.get_integer(b"Int"), Ok(42));
+        assert_eq!(dict.get_integer(b"Name"), Err(wrong_type(b"Name", "integer", "name")));
+        assert_eq!(
+            dict.get_integer(b"Nope"),
+            Err(KeyError::Missing(b"Nope".to_vec().into()))
+        );
+        assert_eq!(dict.get_integer_opt(b"Nope"), Ok(None));
+        assert_eq!(dict.get_integer_opt(b"Int"), Ok(Some(42)));
+    }
+
+    #[test]
+    fn get_name() {
+        let dict = sample();
+        assert_eq!(&dict.get_name(b"Name").unwrap()[..], b"Catalog");
+        assert_eq!(dict.get_name(b"Int"), Err(wrong_type(b"Int", "name", "integer")));
+        assert_eq!(dict.get_name_opt(b"Nope"), Ok(None));
+    }
+
+    #[test]
+    fn get_array() {
+        let dict = sample();
+        assert_eq!(dict.get_array(b"Array").unwrap().len(), 1);
+        assert_eq!(dict.get_array(b"Dict"), Err(wrong_type(b"Dict", "array", "dictionary")));
+        assert_eq!(dict.get_array_opt(b"Nope"), Ok(None));
+    }
+
+    #[test]
+    fn get_dict() {
+        let dict = sample();
+        assert!(dict.get_dict(b"Dict").unwrap().is_empty());
+        assert_eq!(
+            dict.get_dict(b"Array"),
+            Err(wrong_type(b"Array", "dictionary", "array"))
+        );
+        assert_eq!(dict.get_dict_opt(b"Nope"), Ok(None));
+    }
+
+    #[test]
+    fn get_reference() {
+        let dict = sample();
+        assert_eq!(dict.get_reference(b"Ref"), Ok(&Reference::new(3, 0)));
+        assert_eq!(
+            dict.get_reference(b"Stream"),
+            Err(wrong_type(b"Stream", "reference", "stream"))
+        );
+        assert_eq!(dict.get_reference_opt(b"Nope"), Ok(None));
+    }
+
+    #[test]
+    fn get_stream() {
+        let dict = sample();
+        assert_eq!(&dict.get_stream(b"Stream").unwrap().data[..], b"data");
+        assert_eq!(dict.get_stream(b"Ref"), Err(wrong_type(b"Ref", "stream", "reference")));
+        assert_eq!(dict.get_stream_opt(b"Nope"), Ok(None));
+    }
+
+    #[test]
+    fn into_either() {
+        let dict = sample();
+        assert_eq!(dict.get_integer(b"Nope").unwrap_err().into_either(1, 2), 1);
+        assert_eq!(dict.get_integer(b"Name").unwrap_err().into_either(1, 2), 2);
+    }
+}
@@ -0,0 +1,302 @@
+//! Rendering of objects in a syntax close to PDF for logs and debugging.
+//!
+//! Containers are written on a single line if they are short, otherwise one
+//! entry per line with nested indentation. Long arrays and strings are
+//! truncated and stream data is summarized.
+
+use std::fmt::Write;
+
+use super::{Array, Dictionary, IndirectObject, Name, Object, Stream};
+
+/// Containers that are longer are split over several lines.
+const LINE_WIDTH: usize = 72;
+const MAX_ELEMENTS: usize = 16;
+const MAX_STRING_BYTES: usize = 40;
+const MAX_HEX_BYTES: usize = 16;
+const INDENT: usize = 2;
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Value<'a> {
+    Object(&'a Object),
+    Array(&'a Array),
+    Dictionary(&'a Dictionary),
+    Stream(&'a Stream),
+    Indirect(&'a IndirectObject),
+}
+
+/// Displays a value with at most `depth` levels of nested arrays and
+/// dictionaries, see [Object::display_depth].
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayDepth<'a> {
+    value: Value<'a>,
+    depth: usize,
+}
+
+impl<'a> DisplayDepth<'a> {
+    pub(crate) fn new(value: Value<'a>, depth: usize) -> Self {
+        Self { value, depth }
+    }
+}
+
+impl std::fmt::Display for DisplayDepth<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut out = String::new();
+        write_value(&mut out, self.value, self.depth, Some(0));
+        f.write_str(&out)
+    }
+}
+
+/// Write `value`, on a single line if `indent` is `None`.
+fn write_value(out: &mut String, value: Value, depth: usize, indent: Option<usize>) {
+    let object = match value {
+        Value::Object(object) => object,
+        Value::Array(array) => return write_array(out, array, depth, indent),
+        Value::Dictionary(dict) => return write_dictionary(out, dict, depth, indent),
+        Value::Stream(stream) => return write_stream(out, stream, depth, indent),
+        Value::Indirect(indirect) => return write_indirect(out, indirect, depth, indent),
+    };
+    match object {
+        Object::Array(array) => write_array(out, array, depth, indent),
+        Object::Dictionary(dict) => write_dictionary(out, dict, depth, indent),
+        Object::Stream(stream) => write_stream(out, stream, depth, indent),
+        Object::String(s) => write_string(out, &s.unescaped()),
+        Object::HexString(bytes) => {
+            out.push('<');
+            out.push_str(&hex::encode(&bytes[..bytes.len().min(MAX_HEX_BYTES)]));
+            if bytes.len() > MAX_HEX_BYTES {
+                let _ = write!(out, "… {} bytes", bytes.len());
+            }
+            out.push('>');
+        }
+        Object::Name(name) => write_name(out, name),
+        Object::Integer(i) => {
+            let _ = write!(out, "{}", i);
+        }
+        Object::Float(float) => {
+            let _ = write!(out, "{}", float);
+        }
+        Object::Bool(b) => {
+            let _ = write!(out, "{}", b);
+        }
+        Object::Null => out.push_str("null"),
+        Object::Reference(reference) => {
+            let _ = write!(out, "{}", reference);
+        }
+        Object::Indirect(indirect) => write_indirect(out, indirect, depth, indent),
+    }
+}
+
+fn write_indirect(out: &mut String, indirect: &IndirectObject, depth: usize, indent: Option<usize>) {
+    let _ = write!(out, "{} {} obj ", indirect.index, indirect.generation);
+    write_value(out, Value::Object(&indirect.object), depth, indent);
+    out.push_str(" endobj");
+}
+
+fn write_array(out: &mut String, array: &Array, depth: usize, indent: Option<usize>) {
+    if array.is_empty() {
+        return out.push_str("[]");
+    }
+    let note = (array.len() > MAX_ELEMENTS).then(|| format!("… {} elements", array.len()));
+    if depth == 0 {
+        let _ = write!(out, "[… {} elements]", array.len());
+        return;
+    }
+    let entries = array.iter().take(MAX_ELEMENTS).map(|element| (None, element));
+    write_container(out, ("[", "]"), entries, note, depth, indent);
+}
+
+fn write_dictionary(out: &mut String, dict: &Dictionary, depth: usize, indent: Option<usize>) {
+    if dict.is_empty() {
+        return out.push_str("<< >>");
+    }
+    if depth == 0 {
+        let _ = write!(out, "<< … {} entries >>", dict.len());
+        return;
+    }
+    let entries = dict.iter().map(|(key, value)| (Some(key), value));
+    write_container(out, ("<< ", " >>"), entries, None, depth, indent);
+}
+
+fn write_stream(out: &mut String, stream: &Stream, depth: usize, indent: Option<usize>) {
+    write_dictionary(out, &stream.dictionary, depth, indent);
+    let _ = write!(out, " stream(… {} bytes", stream.data.len());
+    let filters = stream.filters().unwrap_or_default();
+    if !filters.is_empty() {
+        let filters = filters.iter().map(|filter| filter.to_string()).collect::<Vec<_>>();
+        let _ = write!(out, ", filters: {}", filters.join(", "));
+    }
+    out.push(')');
+}
+
+fn write_container<'a>(
+    out: &mut String,
+    (open, close): (&str, &str),
+    entries: impl Iterator<Item = (Option<&'a Name>, &'a Object)> + Clone,
+    note: Option<String>,
+    depth: usize,
+    indent: Option<usize>,
+) {
+    let write_entry = |out: &mut String, (key, value): (Option<&Name>, &Object), indent| {
+        if let Some(key) = key {
+            write_name(out, key);
+            out.push(' ');
+        }
+        write_value(out, Value::Object(value), depth - 1, indent);
+    };
+
+    let start = out.len();
+    out.push_str(open);
+    for (i, entry) in entries.clone().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        write_entry(out, entry, None);
+    }
+    if let Some(note) = &note {
+        out.push(' ');
+        out.push_str(note);
+    }
+    out.push_str(close);
+    let indent = match indent {
+        Some(indent) if indent + out[start..].chars().count() > LINE_WIDTH => indent,
+        _ => return,
+    };
+
+    out.truncate(start);
+    out.push_str(open.trim_end());
+    let new_line = |out: &mut String, indent| {
+        out.push('\n');
+        out.extend(std::iter::repeat_n(' ', indent));
+    };
+    for entry in entries {
+        new_line(out, indent + INDENT);
+        write_entry(out, entry, Some(indent + INDENT));
+    }
+    if let Some(note) = &note {
+        new_line(out, indent + INDENT);
+        out.push_str(note);
+    }
+    new_line(out, indent);
+    out.push_str(close.trim_start());
+}
+
+fn write_name(out: &mut String, name: &Name) {
+    out.push('/');
+    for &byte in name.iter() {
+        match byte {
+            b'!'..=b'~' if !b"#()<>[]{}/%".contains(&byte) => out.push(byte as char),
+            _ => {
+                let _ = write!(out, "#{:02x}", byte);
+            }
+        }
+    }
+}
+
+fn write_string(out: &mut String, s: &[u8]) {
+    out.push('(');
+    for &byte in &s[..s.len().min(MAX_STRING_BYTES)] {
+        match byte {
+            b'(' | b')' | b'\\' => {
+                out.push('\\');
+                out.push(byte as char);
+            }
+            b' '..=b'~' => out.push(byte as char),
+            _ => {
+                let _ = write!(out, "\\{:03o}", byte);
+            }
+        }
+    }
+    if s.len() > MAX_STRING_BYTES {
+        let _ = write!(out, "… {} bytes", s.len());
+    }
+    out.push(')');
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::object::object;
+
+    use super::*;
+
+    fn parse(input: &[u8]) -> Object {
+        object(input.into()).unwrap().1
+    }
+
+    #[test]
+    fn dictionary() {
+        let page = parse(
+            b"<</Type /Page /MediaBox [0 0 612 792] /Parent 2 0 R /Resources <</Font <</F1 5 0 R>> \
+            /ProcSet [/PDF /Text]>> /Contents 4 0 R /Title (A \\(nested\\) title\\n) /Rotate 90.5>>",
+        );
+        assert_eq!(
+            page.to_string(),
+            "<<
+  /Type /Page
+  /MediaBox [0 0 612 792]
+  /Parent 2 0 R
+  /Resources << /Font << /F1 5 0 R >> /ProcSet [/PDF /Text] >>
+  /Contents 4 0 R
+  /Title (A \\(nested\\) title\\012)
+  /Rotate 90.5
+>>"
+        );
+        assert_eq!(
+            page.dictionary().unwrap().to_string(),
+            page.to_string(),
+            "a dictionary is displayed like the object"
+        );
+        assert_eq!(
+            parse(b"<</Type /Page /MediaBox [0 0 612 792]>>").to_string(),
+            "<< /Type /Page /MediaBox [0 0 612 792] >>"
+        );
+
+        assert_eq!(
+            page.display_depth(1).to_string(),
+            "<<
+  /Type /Page
+  /MediaBox [… 4 elements]
+  /Parent 2 0 R
+  /Resources << … 2 entries >>
+  /Contents 4 0 R
+  /Title (A \\(nested\\) title\\012)
+  /Rotate 90.5
+>>"
+        );
+        assert_eq!(page.display_depth(0).to_string(), "<< … 7 entries >>");
+    }
+
+    #[test]
+    fn truncation() {
+        let numbers = (0..20).map(Object::Integer).collect::<Vec<_>>();
+        assert_eq!(
+            Object::from(numbers).to_string(),
+            "[0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 … 20 elements]"
+        );
+        assert_eq!(
+            parse(b"(The quick brown fox jumps over the lazy dog)").to_string(),
+            "(The quick brown fox jumps over the lazy … 43 bytes)"
+        );
+        assert_eq!(
+            parse(b"<000102030405060708090a0b0c0d0e0f10>").to_string(),
+            "<000102030405060708090a0b0c0d0e0f… 17 bytes>"
+        );
+        assert_eq!(parse(b"/A#20name").to_string(), "/A#20name");
+    }
+
+    #[test]
+    fn stream() {
+        let stream =
+            parse(b"1 0 obj <</Filter [/ASCIIHexDecode /FlateDecode] /Length 4>> stream\n0102\nendstream endobj");
+        assert_eq!(
+            stream.to_string(),
+            "1 0 obj << /Filter [/ASCIIHexDecode /FlateDecode] /Length 4 >> \
+            stream(… 4 bytes, filters: ASCIIHexDecode, FlateDecode) endobj"
+        );
+        let stream = Stream {
+            dictionary: Dictionary::new(),
+            data: Vec::new().into(),
+            provenance: Default::default(),
+        };
+        assert_eq!(stream.to_string(), "<< >> stream(… 0 bytes)");
+    }
+}
@@ -2,6 +2,8 @@ use std::fmt::Display;
 
 use crate::pdf::Object;
 
+use super::display::{DisplayDepth, Value};
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct IndirectObject {
     pub(crate) index: u32,
@@ -9,14 +11,96 @@ pub struct IndirectObject {
     pub(crate) object: Box<Object>,
 }
 
+impl IndirectObject {
+    pub fn new(index: u32, generation: u32, object: Object) -> Self {
+        Self {
+            index,
+            generation,
+            object: Box::new(object),
+        }
+    }
+
+    /// The object that is wrapped by this indirect object.
+    pub fn object(&self) -> &Object {
+        &self.object
+    }
+
+    /// The object number.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// The generation number.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// A reference that points to this indirect object.
+    pub fn reference(&self) -> Reference {
+        Reference::new(self.index, self.generation)
+    }
+}
+
 impl Display for IndirectObject {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Indirect {} {} {{ {} }}", self.index, self.generation, self.object)
+        DisplayDepth::new(Value::Indirect(self), usize::MAX).fmt(f)
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Reference {
     pub(crate) index: u32,
     pub(crate) generation: u32,
 }
+
+impl Reference {
+    pub const fn new(index: u32, generation: u32) -> Self {
+        Self { index, generation }
+    }
+
+    /// The object number of the referenced object.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// The generation number of the referenced object.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+/// Shows the reference like in a document, e.g. `12 0 R`.
+impl Display for Reference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} R", self.index, self.generation)
+    }
+}
+
+impl From<(u32, u32)> for Reference {
+    fn from((index, generation): (u32, u32)) -> Self {
+        Self::new(index, generation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reference_accessors() {
+        let reference = Reference::from((12, 3));
+        assert_eq!(reference, Reference::new(12, 3));
+        assert_eq!(reference.index(), 12);
+        assert_eq!(reference.generation(), 3);
+    }
+
+    #[test]
+    fn indirect_object_accessors() {
+        let obj = IndirectObject::new(7, 1, Object::Integer(42));
+        assert_eq!(obj.index(), 7);
+        assert_eq!(obj.generation(), 1);
+        assert_eq!(obj.object(), &Object::Integer(42));
+        assert_eq!(obj.reference(), Reference::new(7, 1));
+    }
+}
@@ -1,11 +1,18 @@
-use std::{borrow::Borrow, ops::Deref};
+use std::{borrow::Borrow, convert::Infallible, ops::Deref, str::FromStr};
+
+use crate::pdf::Bytes;
 
 #[derive(Clone, PartialEq, Eq, Hash)]
-pub struct Name(Vec<u8>);
+pub struct Name(Bytes);
 
 impl Name {
-    pub const fn new(n: Vec<u8>) -> Self {
-        Self(n)
+    pub fn new(n: Vec<u8>) -> Self {
+        Self(n.into())
+    }
+
+    /// A name that borrows `n` without copying it.
+    pub const fn from_static(n: &'static [u8]) -> Self {
+        Self(Bytes::from_static(n))
     }
 }
 
@@ -17,12 +24,27 @@ impl Borrow<[u8]> for Name {
 
 impl From<Vec<u8>> for Name {
     fn from(v: Vec<u8>) -> Self {
-        Name(v)
+        Name(v.into())
+    }
+}
+
+impl FromStr for Name {
+    type Err = Infallible;
+
+    /// The UTF-8 bytes of `s` as name, without the leading `/`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(s.as_bytes().to_vec()))
+    }
+}
+
+impl From<Bytes> for Name {
+    fn from(b: Bytes) -> Self {
+        Name(b)
     }
 }
 
 impl Deref for Name {
-    type Target = Vec<u8>;
+    type Target = [u8];
 
     fn deref(&self) -> &Self::Target {
         &self.0
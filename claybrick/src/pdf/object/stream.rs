@@ -1,17 +1,60 @@
+use std::sync::atomic::AtomicBool;
+
 use self::filter::FilterError;
 
-use crate::pdf::{object::Name, Bytes, Dictionary, Object};
+use crate::{
+    logging,
+    pdf::{object::Name, Bytes, Dictionary, Object},
+};
+
+use super::display::{DisplayDepth, Value};
 
 const FILTER: &[u8] = b"Filter";
 const FILTER_PARAM: &[u8] = b"DecodeParms";
 
-#[derive(Clone, Debug, PartialEq)]
+/// How the end of the data of a parsed [Stream] was found.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StreamDataProvenance {
+    /// The `/Length` of the dictionary. Streams that weren't parsed use this
+    /// too.
+    #[default]
+    Declared,
+    /// The `endstream` keyword, since the `/Length` was missing or wrong. Data
+    /// that contains the keyword, e.g. compressed data, is truncated.
+    KeywordScan,
+    /// The `/Length` is a reference that was resolved after parsing the
+    /// objects.
+    LengthResolvedFromReference,
+}
+
+#[derive(Clone, Debug)]
 pub struct Stream {
     pub dictionary: Dictionary,
     pub data: Bytes,
+    /// How the data was found, it's ignored by `==`.
+    pub provenance: StreamDataProvenance,
+}
+
+impl PartialEq for Stream {
+    fn eq(&self, other: &Self) -> bool {
+        self.dictionary == other.dictionary && self.data == other.data
+    }
+}
+
+/// Shows the dictionary and a summary of the data.
+impl std::fmt::Display for Stream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        DisplayDepth::new(Value::Stream(self), usize::MAX).fmt(f)
+    }
 }
 
 impl Stream {
+    /// Whether the data has the length that the document declared, see
+    /// [StreamDataProvenance].
+    pub fn is_exact(&self) -> bool {
+        self.provenance != StreamDataProvenance::KeywordScan
+    }
+
     pub fn filters(&self) -> Result<Vec<&Name>, FilterError> {
         match self.dictionary.get(FILTER) {
             Some(Object::Array(a)) => a
@@ -25,25 +68,74 @@ impl Stream {
         }
     }
 
+    /// Apply the filters of the stream.
+    ///
+    /// Image filters like `DCTDecode` are not supported, see
+    /// [ImageXObject::decoded_data](crate::pdf::document::ImageXObject::decoded_data)
+    /// for the data of images.
     pub fn filtered_data(&self) -> Result<Bytes, FilterError> {
+        self.filtered_data_with(None)
+    }
+
+    /// Like [Stream::filtered_data], but long running filters stop with
+    /// [FilterError::Cancelled] once `cancel` is set.
+    pub(crate) fn filtered_data_with(&self, cancel: Option<&AtomicBool>) -> Result<Bytes, FilterError> {
+        self.apply_filters(&self.filters()?, cancel)
+    }
+
+    /// Apply the filters of the stream, except for an image filter like
+    /// `DCTDecode` at the end of the chain. Returns the data and the image
+    /// filter that it is still encoded with.
+    pub(crate) fn filtered_data_before_image_filter(&self) -> Result<(Bytes, Option<&Name>), FilterError> {
+        let mut filters = self.filters()?;
+        let image_filter = match filters.last() {
+            Some(name) if filter::is_image_filter(name) => filters.pop(),
+            _ => None,
+        };
+        Ok((self.apply_filters(&filters, None)?, image_filter))
+    }
+
+    fn apply_filters(&self, filters: &[&Name], cancel: Option<&AtomicBool>) -> Result<Bytes, FilterError> {
+        if !filters.is_empty() && !self.is_exact() {
+            logging::warn!("Decoding stream data that ends at the endstream keyword, it might be truncated");
+        }
+        let chain = self.filters()?.len();
         let mut out_data = self.data.clone();
-        for f in self.filters()? {
-            out_data = filter::filter(
-                f,
-                self.dictionary.get(FILTER_PARAM).and_then(Object::dictionary),
-                &out_data,
-            )?;
+        for (index, f) in filters.iter().enumerate() {
+            out_data = filter::filter_with(f, self.filter_params(index, chain), &out_data, cancel)?;
         }
         Ok(out_data)
     }
+
+    /// The parameters of the filter at `index` of a chain of `chain` filters.
+    /// `/DecodeParms` is an array with an entry per filter, `null` entries use
+    /// the defaults. A single dictionary belongs to the last filter, like in
+    /// [ImageXObject](crate::pdf::document::ImageXObject).
+    fn filter_params(&self, index: usize, chain: usize) -> Option<&Dictionary> {
+        match self.dictionary.get(FILTER_PARAM)? {
+            Object::Array(params) => params.get(index)?.dictionary(),
+            Object::Dictionary(params) if index + 1 == chain => Some(params),
+            _ => None,
+        }
+    }
 }
 
 pub mod filter {
-    use std::borrow::Borrow;
+    use std::{
+        borrow::Borrow,
+        sync::atomic::{AtomicBool, Ordering},
+    };
 
     use flate2::{Decompress, FlushDecompress, Status};
 
-    use crate::pdf::{object::Name, Bytes, Dictionary};
+    use self::ccitt::decode_ccitt_fax;
+
+    use crate::{
+        logging,
+        pdf::{object::Name, Bytes, Dictionary},
+    };
+
+    mod ccitt;
 
     const FILTER_ASCII_HEX: &[u8] = b"ASCIIHexDecode";
     const FILTER_ASCII_85: &[u8] = b"ASCII85Decode";
@@ -62,16 +154,42 @@ pub mod filter {
         UnsupportedFilter(Name),
         InvalidData,
         InvalidFilter,
+        /// Decoding was cancelled with
+        /// [ParseOptions::cancel](crate::parse::ParseOptions::cancel).
+        Cancelled,
+    }
+
+    fn is_cancelled(cancel: Option<&AtomicBool>) -> bool {
+        cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+
+    /// Whether the filter compresses images and is not decoded, see
+    /// [Stream::filtered_data_before_image_filter](super::Stream::filtered_data_before_image_filter).
+    pub(crate) fn is_image_filter(filter_name: &[u8]) -> bool {
+        [FILTER_DCT, FILTER_JPX, FILTER_JBIG2].contains(&filter_name)
+    }
+
+    pub fn filter(filter_name: &Name, params: Option<&Dictionary>, data: &Bytes) -> Result<Bytes, FilterError> {
+        filter_with(filter_name, params, data, None)
     }
 
-    pub fn filter(filter_name: &Name, _p: Option<&Dictionary>, data: &Bytes) -> Result<Bytes, FilterError> {
+    /// Like [filter], but long running filters stop with
+    /// [FilterError::Cancelled] once `cancel` is set.
+    pub(crate) fn filter_with(
+        filter_name: &Name,
+        params: Option<&Dictionary>,
+        data: &Bytes,
+        cancel: Option<&AtomicBool>,
+    ) -> Result<Bytes, FilterError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("filter", filter = %filter_name, length = data.len()).entered();
         match filter_name.borrow() {
             FILTER_ASCII_HEX => decode_ascii_hex(data.borrow()),
             FILTER_ASCII_85 => Err(FilterError::UnsupportedFilter(FILTER_ASCII_85.to_vec().into())),
             FILTER_LZW => Err(FilterError::UnsupportedFilter(FILTER_LZW.to_vec().into())),
-            FILTER_FLATE => decode_flate(data),
+            FILTER_FLATE => decode_flate(data, cancel),
             FILTER_RUN_LENGTH => Err(FilterError::UnsupportedFilter(FILTER_RUN_LENGTH.to_vec().into())),
-            FILTER_CCITT_FAX => Err(FilterError::UnsupportedFilter(FILTER_CCITT_FAX.to_vec().into())),
+            FILTER_CCITT_FAX => decode_ccitt_fax(data, params, cancel),
             FILTER_JBIG2 => Err(FilterError::UnsupportedFilter(FILTER_JBIG2.to_vec().into())),
             FILTER_DCT => Err(FilterError::UnsupportedFilter(FILTER_DCT.to_vec().into())),
             FILTER_JPX => Err(FilterError::UnsupportedFilter(FILTER_JPX.to_vec().into())),
@@ -114,11 +232,11 @@ pub mod filter {
         Ok(buffer.into())
     }
 
-    fn decode_flate(data: &Bytes) -> Result<Bytes, FilterError> {
+    fn decode_flate(data: &Bytes, cancel: Option<&AtomicBool>) -> Result<Bytes, FilterError> {
         let mut d = Decompress::new(true);
         let mut out = Vec::<u8>::with_capacity(2 * 1024 * 1024);
         let into_invalid_data_err = |err| {
-            log::error!(
+            logging::error!(
                 "Error while applying {} filter: {:?}",
                 String::from_utf8_lossy(FILTER_FLATE),
                 err
@@ -126,14 +244,29 @@ pub mod filter {
             FilterError::InvalidData
         };
 
-        while Status::StreamEnd
-            != d.decompress_vec(&data[..], &mut out, FlushDecompress::None)
-                .map_err(into_invalid_data_err)?
-        {
-            out.reserve(2 * 1024 * 1024);
+        loop {
+            if is_cancelled(cancel) {
+                return Err(FilterError::Cancelled);
+            }
+            let (total_in, total_out) = (d.total_in(), d.total_out());
+            let consumed = usize::try_from(total_in).map_err(|_| FilterError::InvalidData)?;
+            let status = d
+                .decompress_vec(&data[consumed..], &mut out, FlushDecompress::None)
+                .map_err(into_invalid_data_err)?;
+            if status == Status::StreamEnd {
+                return Ok(out.into());
+            }
+            if out.len() == out.capacity() {
+                out.reserve(2 * 1024 * 1024);
+            } else if d.total_in() == total_in && d.total_out() == total_out {
+                // The data ends before the end of the compressed stream.
+                logging::error!(
+                    "Error while applying {} filter: truncated data",
+                    String::from_utf8_lossy(FILTER_FLATE)
+                );
+                return Err(FilterError::InvalidData);
+            }
         }
-
-        Ok(out.into())
     }
 
     #[cfg(test)]
@@ -155,5 +288,69 @@ pub mod filter {
                 decode_ascii_hex(&b"4 8 6 5 6 c 6 c 6 f 2 0 7 7 6 f 7 2 6 c 6 4 2 1"[..])
             );
         }
+
+        #[test]
+        fn cancel_flate() {
+            let data: Bytes = vec![0x78, 0x9c, 0x03, 0x00, 0x00, 0x00, 0x00, 0x01].into();
+            assert_eq!(decode_flate(&data, None), Ok(Vec::new().into()));
+
+            let cancel = AtomicBool::new(true);
+            assert_eq!(decode_flate(&data, Some(&cancel)), Err(FilterError::Cancelled));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unfiltered_data_is_not_copied() {
+        let stream = Stream {
+            dictionary: Dictionary::new(),
+            data: b"0 0 m 10 10 l S".to_vec().into(),
+            provenance: Default::default(),
+        };
+        let copy = stream.clone();
+        let data = copy.filtered_data().unwrap();
+        assert_eq!(data.as_ptr(), stream.data.as_ptr());
+        assert_eq!(copy.data.as_ptr(), stream.data.as_ptr());
+    }
+
+    #[test]
+    fn image_filters_end_the_chain() {
+        let stream = |filter: &[&[u8]], data: &[u8]| Stream {
+            dictionary: Dictionary::from([(
+                FILTER.to_vec().into(),
+                Object::Array(
+                    filter
+                        .iter()
+                        .map(|f| Object::Name(f.to_vec().into()))
+                        .collect::<Vec<_>>()
+                        .into(),
+                ),
+            )]),
+            data: data.to_vec().into(),
+            provenance: Default::default(),
+        };
+        let jpeg = stream(&[b"ASCIIHexDecode", b"DCTDecode"], b"ffd8ffd9>");
+        let (data, image_filter) = jpeg.filtered_data_before_image_filter().unwrap();
+        assert_eq!(&data[..], b"\xff\xd8\xff\xd9");
+        assert_eq!(image_filter.map(|name| &name[..]), Some(&b"DCTDecode"[..]));
+        assert_eq!(
+            jpeg.filtered_data(),
+            Err(FilterError::UnsupportedFilter(b"DCTDecode".to_vec().into()))
+        );
+        let jbig2 = stream(&[b"JBIG2Decode"], b"\x00\x00\x00\x01");
+        let (data, _) = jbig2.filtered_data_before_image_filter().unwrap();
+        assert_eq!(&data[..], b"\x00\x00\x00\x01");
+        let hex = stream(&[b"ASCIIHexDecode"], b"00>");
+        assert_eq!(hex.filtered_data_before_image_filter(), Ok((vec![0].into(), None)));
+        // only the last filter is skipped
+        let invalid = stream(&[b"DCTDecode", b"ASCIIHexDecode"], b"00>");
+        assert_eq!(
+            invalid.filtered_data_before_image_filter(),
+            Err(FilterError::UnsupportedFilter(b"DCTDecode".to_vec().into()))
+        );
     }
 }
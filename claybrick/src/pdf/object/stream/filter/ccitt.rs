@@ -0,0 +1,676 @@
+//! The `CCITTFaxDecode` filter, Group 3 and Group 4 fax compression.
+
+use std::sync::atomic::AtomicBool;
+
+use crate::{
+    logging,
+    pdf::{Bytes, Dictionary, Object},
+};
+
+use super::{is_cancelled, FilterError};
+
+const K_K: &[u8] = b"K";
+const K_COLUMNS: &[u8] = b"Columns";
+const K_ROWS: &[u8] = b"Rows";
+const K_BLACK_IS_1: &[u8] = b"BlackIs1";
+const K_ENCODED_BYTE_ALIGN: &[u8] = b"EncodedByteAlign";
+const K_END_OF_BLOCK: &[u8] = b"EndOfBlock";
+
+/// The widest supported image, a row takes 8 KiB.
+const MAX_COLUMNS: usize = 1 << 16;
+/// The most pixels that are decoded, 512 MiB. A single bit of input can
+/// repeat the previous row.
+const MAX_PIXELS: u64 = 1 << 32;
+
+/// A code as number of bits, the bits and the run length it stands for.
+type Code = (u8, u16, u16);
+
+const WHITE_TERMINATING: &[Code] = &[
+    (8, 0b00110101, 0),
+    (6, 0b000111, 1),
+    (4, 0b0111, 2),
+    (4, 0b1000, 3),
+    (4, 0b1011, 4),
+    (4, 0b1100, 5),
+    (4, 0b1110, 6),
+    (4, 0b1111, 7),
+    (5, 0b10011, 8),
+    (5, 0b10100, 9),
+    (5, 0b00111, 10),
+    (5, 0b01000, 11),
+    (6, 0b001000, 12),
+    (6, 0b000011, 13),
+    (6, 0b110100, 14),
+    (6, 0b110101, 15),
+    (6, 0b101010, 16),
+    (6, 0b101011, 17),
+    (7, 0b0100111, 18),
+    (7, 0b0001100, 19),
+    (7, 0b0001000, 20),
+    (7, 0b0010111, 21),
+    (7, 0b0000011, 22),
+    (7, 0b0000100, 23),
+    (7, 0b0101000, 24),
+    (7, 0b0101011, 25),
+    (7, 0b0010011, 26),
+    (7, 0b0100100, 27),
+    (7, 0b0011000, 28),
+    (8, 0b00000010, 29),
+    (8, 0b00000011, 30),
+    (8, 0b00011010, 31),
+    (8, 0b00011011, 32),
+    (8, 0b00010010, 33),
+    (8, 0b00010011, 34),
+    (8, 0b00010100, 35),
+    (8, 0b00010101, 36),
+    (8, 0b00010110, 37),
+    (8, 0b00010111, 38),
+    (8, 0b00101000, 39),
+    (8, 0b00101001, 40),
+    (8, 0b00101010, 41),
+    (8, 0b00101011, 42),
+    (8, 0b00101100, 43),
+    (8, 0b00101101, 44),
+    (8, 0b00000100, 45),
+    (8, 0b00000101, 46),
+    (8, 0b00001010, 47),
+    (8, 0b00001011, 48),
+    (8, 0b01010010, 49),
+    (8, 0b01010011, 50),
+    (8, 0b01010100, 51),
+    (8, 0b01010101, 52),
+    (8, 0b00100100, 53),
+    (8, 0b00100101, 54),
+    (8, 0b01011000, 55),
+    (8, 0b01011001, 56),
+    (8, 0b01011010, 57),
+    (8, 0b01011011, 58),
+    (8, 0b01001010, 59),
+    (8, 0b01001011, 60),
+    (8, 0b00110010, 61),
+    (8, 0b00110011, 62),
+    (8, 0b00110100, 63),
+];
+
+const WHITE_MAKEUP: &[Code] = &[
+    (5, 0b11011, 64),
+    (5, 0b10010, 128),
+    (6, 0b010111, 192),
+    (7, 0b0110111, 256),
+    (8, 0b00110110, 320),
+    (8, 0b00110111, 384),
+    (8, 0b01100100, 448),
+    (8, 0b01100101, 512),
+    (8, 0b01101000, 576),
+    (8, 0b01100111, 640),
+    (9, 0b011001100, 704),
+    (9, 0b011001101, 768),
+    (9, 0b011010010, 832),
+    (9, 0b011010011, 896),
+    (9, 0b011010100, 960),
+    (9, 0b011010101, 1024),
+    (9, 0b011010110, 1088),
+    (9, 0b011010111, 1152),
+    (9, 0b011011000, 1216),
+    (9, 0b011011001, 1280),
+    (9, 0b011011010, 1344),
+    (9, 0b011011011, 1408),
+    (9, 0b010011000, 1472),
+    (9, 0b010011001, 1536),
+    (9, 0b010011010, 1600),
+    (6, 0b011000, 1664),
+    (9, 0b010011011, 1728),
+];
+
+const BLACK_TERMINATING: &[Code] = &[
+    (10, 0b0000110111, 0),
+    (3, 0b010, 1),
+    (2, 0b11, 2),
+    (2, 0b10, 3),
+    (3, 0b011, 4),
+    (4, 0b0011, 5),
+    (4, 0b0010, 6),
+    (5, 0b00011, 7),
+    (6, 0b000101, 8),
+    (6, 0b000100, 9),
+    (7, 0b0000100, 10),
+    (7, 0b0000101, 11),
+    (7, 0b0000111, 12),
+    (8, 0b00000100, 13),
+    (8, 0b00000111, 14),
+    (9, 0b000011000, 15),
+    (10, 0b0000010111, 16),
+    (10, 0b0000011000, 17),
+    (10, 0b0000001000, 18),
+    (11, 0b00001100111, 19),
+    (11, 0b00001101000, 20),
+    (11, 0b00001101100, 21),
+    (11, 0b00000110111, 22),
+    (11, 0b00000101000, 23),
+    (11, 0b00000010111, 24),
+    (11, 0b00000011000, 25),
+    (12, 0b000011001010, 26),
+    (12, 0b000011001011, 27),
+    (12, 0b000011001100, 28),
+    (12, 0b000011001101, 29),
+    (12, 0b000001101000, 30),
+    (12, 0b000001101001, 31),
+    (12, 0b000001101010, 32),
+    (12, 0b000001101011, 33),
+    (12, 0b000011010010, 34),
+    (12, 0b000011010011, 35),
+    (12, 0b000011010100, 36),
+    (12, 0b000011010101, 37),
+    (12, 0b000011010110, 38),
+    (12, 0b000011010111, 39),
+    (12, 0b000001101100, 40),
+    (12, 0b000001101101, 41),
+    (12, 0b000011011010, 42),
+    (12, 0b000011011011, 43),
+    (12, 0b000001010100, 44),
+    (12, 0b000001010101, 45),
+    (12, 0b000001010110, 46),
+    (12, 0b000001010111, 47),
+    (12, 0b000001100100, 48),
+    (12, 0b000001100101, 49),
+    (12, 0b000001010010, 50),
+    (12, 0b000001010011, 51),
+    (12, 0b000000100100, 52),
+    (12, 0b000000110111, 53),
+    (12, 0b000000111000, 54),
+    (12, 0b000000100111, 55),
+    (12, 0b000000101000, 56),
+    (12, 0b000001011000, 57),
+    (12, 0b000001011001, 58),
+    (12, 0b000000101011, 59),
+    (12, 0b000000101100, 60),
+    (12, 0b000001011010, 61),
+    (12, 0b000001100110, 62),
+    (12, 0b000001100111, 63),
+];
+
+const BLACK_MAKEUP: &[Code] = &[
+    (10, 0b0000001111, 64),
+    (12, 0b000011001000, 128),
+    (12, 0b000011001001, 192),
+    (12, 0b000001011011, 256),
+    (12, 0b000000110011, 320),
+    (12, 0b000000110100, 384),
+    (12, 0b000000110101, 448),
+    (13, 0b0000001101100, 512),
+    (13, 0b0000001101101, 576),
+    (13, 0b0000001001010, 640),
+    (13, 0b0000001001011, 704),
+    (13, 0b0000001001100, 768),
+    (13, 0b0000001001101, 832),
+    (13, 0b0000001110010, 896),
+    (13, 0b0000001110011, 960),
+    (13, 0b0000001110100, 1024),
+    (13, 0b0000001110101, 1088),
+    (13, 0b0000001110110, 1152),
+    (13, 0b0000001110111, 1216),
+    (13, 0b0000001010010, 1280),
+    (13, 0b0000001010011, 1344),
+    (13, 0b0000001010100, 1408),
+    (13, 0b0000001010101, 1472),
+    (13, 0b0000001011010, 1536),
+    (13, 0b0000001011011, 1600),
+    (13, 0b0000001100100, 1664),
+    (13, 0b0000001100101, 1728),
+];
+
+/// Makeup codes shared by white and black runs.
+const EXTENDED_MAKEUP: &[Code] = &[
+    (11, 0b00000001000, 1792),
+    (11, 0b00000001100, 1856),
+    (11, 0b00000001101, 1920),
+    (12, 0b000000010010, 1984),
+    (12, 0b000000010011, 2048),
+    (12, 0b000000010100, 2112),
+    (12, 0b000000010101, 2176),
+    (12, 0b000000010110, 2240),
+    (12, 0b000000010111, 2304),
+    (12, 0b000000011100, 2368),
+    (12, 0b000000011101, 2432),
+    (12, 0b000000011110, 2496),
+    (12, 0b000000011111, 2560),
+];
+
+/// The end of line code, `000000000001`.
+const EOL_LENGTH: u8 = 12;
+
+/// Modes of two-dimensional coding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Pass,
+    Horizontal,
+    /// The offset of `a1` from `b1`.
+    Vertical(isize),
+}
+
+/// The mode codes as number of bits, the bits and the mode.
+const MODES: &[(u8, u16, Mode)] = &[
+    (1, 0b1, Mode::Vertical(0)),
+    (3, 0b011, Mode::Vertical(1)),
+    (3, 0b010, Mode::Vertical(-1)),
+    (3, 0b001, Mode::Horizontal),
+    (4, 0b0001, Mode::Pass),
+    (6, 0b000011, Mode::Vertical(2)),
+    (6, 0b000010, Mode::Vertical(-2)),
+    (7, 0b0000011, Mode::Vertical(3)),
+    (7, 0b0000010, Mode::Vertical(-3)),
+];
+
+/// The `DecodeParms` of the filter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Params {
+    /// Negative for Group 4, 0 for Group 3 one-dimensional and positive for
+    /// Group 3 mixed one- and two-dimensional coding.
+    k: i32,
+    columns: usize,
+    /// 0 if unknown.
+    rows: usize,
+    black_is_1: bool,
+    byte_align: bool,
+    end_of_block: bool,
+}
+
+impl Params {
+    fn new_with(params: Option<&Dictionary>) -> Result<Self, FilterError> {
+        let integer = |key| params.and_then(|p| p.get(key)).and_then(Object::integer);
+        let flag = |key| params.and_then(|p| p.get(key)).and_then(Object::as_bool);
+        let positive = |value: Option<i32>| value.and_then(|v| usize::try_from(v).ok());
+        let params = Self {
+            k: integer(K_K).unwrap_or(0),
+            columns: positive(integer(K_COLUMNS)).unwrap_or(1728),
+            rows: positive(integer(K_ROWS)).unwrap_or(0),
+            black_is_1: flag(K_BLACK_IS_1).unwrap_or(false),
+            byte_align: flag(K_ENCODED_BYTE_ALIGN).unwrap_or(false),
+            end_of_block: flag(K_END_OF_BLOCK).unwrap_or(true),
+        };
+        if params.columns > MAX_COLUMNS || !params.fits(params.rows) {
+            logging::error!(
+                "CCITTFaxDecode image of {} columns and {} rows is too large",
+                params.columns,
+                params.rows
+            );
+            return Err(FilterError::InvalidData);
+        }
+        Ok(params)
+    }
+
+    /// Whether `rows` rows are within [MAX_PIXELS].
+    fn fits(&self, rows: usize) -> bool {
+        (rows as u64)
+            .checked_mul(self.columns as u64)
+            .is_some_and(|pixels| pixels <= MAX_PIXELS)
+    }
+}
+
+/// Reads the data bit by bit, the most significant bit first.
+struct BitReader<'a> {
+    data: &'a [u8],
+    /// Position in bits.
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    /// The next `count` bits, padded with 0 after the end of the data.
+    fn peek(&self, count: u8) -> u16 {
+        (0..count as usize).fold(0, |bits, i| {
+            let position = self.position + i;
+            let bit = self
+                .data
+                .get(position / 8)
+                .map_or(0, |byte| (byte >> (7 - position % 8)) & 1);
+            (bits << 1) | bit as u16
+        })
+    }
+
+    fn consume(&mut self, count: u8) {
+        self.position += count as usize;
+    }
+
+    fn bit(&mut self) -> bool {
+        let bit = self.peek(1) == 1;
+        self.consume(1);
+        bit
+    }
+
+    fn align(&mut self) {
+        self.position = self.position.next_multiple_of(8);
+    }
+
+    fn is_past_end(&self) -> bool {
+        self.position > self.data.len() * 8
+    }
+
+    /// Whether there are no 1 bits left.
+    fn only_zeros_left(&self) -> bool {
+        self.peek(8) == 0
+            && self
+                .data
+                .get(self.position.div_ceil(8)..)
+                .is_none_or(|rest| rest.iter().all(|&byte| byte == 0))
+    }
+
+    /// Skip an end of line code preceded by any number of fill bits.
+    fn skip_eol(&mut self) -> bool {
+        let mut position = self.position;
+        while position < self.data.len() * 8 && self.data[position / 8] & (0x80 >> (position % 8)) == 0 {
+            position += 1;
+        }
+        if position >= self.data.len() * 8 || position - self.position < EOL_LENGTH as usize - 1 {
+            return false;
+        }
+        self.position = position + 1;
+        true
+    }
+
+    /// The code that the next bits start with.
+    fn code<T: Copy>(&self, codes: &[(u8, u16, T)]) -> Option<(u8, T)> {
+        codes
+            .iter()
+            .find(|(length, bits, _)| self.peek(*length) == *bits)
+            .map(|&(length, _, value)| (length, value))
+    }
+
+    /// Read a run as any number of makeup codes and a terminating code.
+    fn run(&mut self, white: bool) -> Option<usize> {
+        let (terminating, makeup) = match white {
+            true => (WHITE_TERMINATING, WHITE_MAKEUP),
+            false => (BLACK_TERMINATING, BLACK_MAKEUP),
+        };
+        let mut run = 0;
+        loop {
+            if let Some((length, value)) = self.code(terminating) {
+                self.consume(length);
+                return Some(run + value as usize);
+            }
+            let (length, value) = self.code(makeup).or_else(|| self.code(EXTENDED_MAKEUP))?;
+            self.consume(length);
+            run += value as usize;
+        }
+    }
+}
+
+/// Decode a row coded with runs of alternating colors. Returns the positions
+/// where the color changes, the first one changes to black.
+fn decode_1d(reader: &mut BitReader, columns: usize) -> Option<Vec<usize>> {
+    let mut changes = Vec::new();
+    let mut position = 0;
+    let mut white = true;
+    while position < columns {
+        position += reader.run(white)?;
+        changes.push(position);
+        white = !white;
+    }
+    (position == columns).then_some(changes)
+}
+
+/// Decode a row coded relative to the changes of the `reference` row.
+fn decode_2d(reader: &mut BitReader, reference: &[usize], columns: usize) -> Option<Vec<usize>> {
+    let mut changes = Vec::new();
+    // `None` is the imaginary element before the row.
+    let mut a0: Option<usize> = None;
+    let mut white = true;
+    while a0.is_none_or(|a0| a0 < columns) {
+        // Changes to black have an even index, changes to white an odd one.
+        let b1_index = (0..reference.len())
+            .find(|&i| a0.is_none_or(|a0| reference[i] > a0) && (i % 2 == 0) == white)
+            .unwrap_or(reference.len());
+        let b1 = reference.get(b1_index).copied().unwrap_or(columns);
+        let b2 = reference.get(b1_index + 1).copied().unwrap_or(columns);
+
+        let (length, mode) = reader.code(MODES)?;
+        reader.consume(length);
+        let start = a0.unwrap_or(0);
+        match mode {
+            Mode::Pass => a0 = Some(b2),
+            Mode::Horizontal => {
+                let a1 = start + reader.run(white)?;
+                let a2 = a1 + reader.run(!white)?;
+                changes.extend([a1, a2]);
+                a0 = Some(a2);
+            }
+            Mode::Vertical(offset) => {
+                let a1 = b1.checked_add_signed(offset).filter(|&a1| a1 >= start)?;
+                changes.push(a1);
+                a0 = Some(a1);
+                white = !white;
+            }
+        }
+        if reader.is_past_end() || a0.is_some_and(|a0| a0 > columns) {
+            return None;
+        }
+    }
+    Some(changes)
+}
+
+/// Pack the row as 1 bit per pixel, the changes alternate between black and
+/// white starting with black.
+fn pack_row(changes: &[usize], columns: usize, black_is_1: bool, out: &mut Vec<u8>) {
+    let start = out.len();
+    out.resize(start + columns.div_ceil(8), 0);
+    let row = &mut out[start..];
+    for span in changes.chunks(2) {
+        let end = span.get(1).copied().unwrap_or(columns).min(columns);
+        for x in span[0]..end {
+            row[x / 8] |= 0x80 >> (x % 8);
+        }
+    }
+    if !black_is_1 {
+        row.iter_mut().for_each(|byte| *byte = !*byte);
+    }
+}
+
+pub(super) fn decode_ccitt_fax(
+    data: &[u8],
+    params: Option<&Dictionary>,
+    cancel: Option<&AtomicBool>,
+) -> Result<Bytes, FilterError> {
+    let params = Params::new_with(params)?;
+    let mut reader = BitReader { data, position: 0 };
+    let mut reference = Vec::new();
+    let mut out = Vec::new();
+    let mut row = 0;
+    while params.rows == 0 || row < params.rows {
+        if is_cancelled(cancel) {
+            return Err(FilterError::Cancelled);
+        }
+        if params.byte_align {
+            reader.align();
+        }
+        let mut eols = 0;
+        while reader.skip_eol() {
+            eols += 1;
+        }
+        // `EOFB` of Group 4 and `RTC` of Group 3 are repeated end of lines.
+        if (params.end_of_block && eols >= 2) || reader.only_zeros_left() {
+            break;
+        }
+        let two_dimensional = match params.k {
+            k if k < 0 => true,
+            0 => false,
+            // A tag bit after the end of line selects the coding.
+            _ => !reader.bit(),
+        };
+        let changes = match two_dimensional {
+            true => decode_2d(&mut reader, &reference, params.columns),
+            false => decode_1d(&mut reader, params.columns),
+        };
+        let Some(changes) = changes.filter(|_| !reader.is_past_end()) else {
+            logging::error!("Invalid CCITTFaxDecode data in row {}", row);
+            return Err(FilterError::InvalidData);
+        };
+        if !params.fits(row + 1) {
+            logging::error!("CCITTFaxDecode data exceeds {} pixels", MAX_PIXELS);
+            return Err(FilterError::InvalidData);
+        }
+        pack_row(&changes, params.columns, params.black_is_1, &mut out);
+        reference = changes;
+        row += 1;
+    }
+    Ok(out.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 21x8 pixels, `#` is black.
+    const ROWS: [&str; 8] = [
+        ".....................",
+        "..###.....#####......",
+        ".#...#....#....#.....",
+        "#.....#...#....#..##.",
+        "#######...#####...##.",
+        "#.....#...#....#.....",
+        "#.....#...#....#....#",
+        "#.....#...#####.....#",
+    ];
+
+    /// [ROWS] compressed with Group 4 and `EOFB`.
+    const G4: &[u8] =
+        b"\x97\x8e\x1d\x02\x30\xa5\x59\x58\x93\x2a\x38\x25\x8e\x4e\x39\x59\x95\x65\x67\xfa\xfc\x9e\x40\x04\x00\x40";
+    /// [ROWS] compressed with Group 3 one-dimensional coding, with an end of
+    /// line before each row.
+    const G3: &[u8] = b"\x00\x12\xe0\x02\xf6\x1f\x00\x08\xea\x15\xab\x58\x00\x26\xab\x14\x2b\x4f\x8e\x00\x26\xa3\x83\x8c\x70\x01\x35\x58\xa1\x5a\xc0\x01\x35\x58\xa1\x5a\xb4\x00\x26\xab\x14\x1e\x20";
+    /// [ROWS] compressed with Group 3 two-dimensional coding, `K` 2.
+    const G3_2D: &[u8] = b"\x00\x19\x70\x01\x17\x8e\x1c\x00\x63\xa8\x56\xad\x60\x00\x92\x65\x47\x04\xa0\x03\x35\x1c\x1c\x63\x80\x0a\x56\x65\x59\x58\x00\x33\x55\x8a\x15\xab\x40\x02\xf9\x3c\x80";
+    /// [ROWS] compressed with one-dimensional coding, each row starts on a
+    /// byte boundary.
+    const ALIGNED: &[u8] = b"\x2e\x7b\x0f\x80\x1d\x42\xb5\x6b\x00\x35\x58\xa1\x5a\x7c\x70\x35\x1c\x1c\x63\x80\x35\x58\xa1\x5a\xc0\x35\x58\xa1\x5a\xb4\x35\x58\xa0\xf1\x00";
+
+    fn bitmap(rows: &[&str], black_is_1: bool) -> Vec<u8> {
+        let mut out = Vec::new();
+        for row in rows {
+            let changes = row
+                .as_bytes()
+                .windows(2)
+                .enumerate()
+                .filter(|(_, pair)| pair[0] != pair[1])
+                .map(|(x, _)| x + 1);
+            let changes = match row.starts_with('#') {
+                true => std::iter::once(0).chain(changes).collect::<Vec<_>>(),
+                false => changes.collect(),
+            };
+            pack_row(&changes, row.len(), black_is_1, &mut out);
+        }
+        out
+    }
+
+    fn decode(data: &[u8], params: &str) -> Result<Bytes, FilterError> {
+        let params = crate::parse::object::object(params.as_bytes().into()).unwrap().1;
+        decode_ccitt_fax(data, params.dictionary(), None)
+    }
+
+    #[test]
+    fn group_4() {
+        assert_eq!(
+            &decode(G4, "<</K -1 /Columns 21 /BlackIs1 true>>").unwrap()[..],
+            bitmap(&ROWS, true)
+        );
+        let inverted = decode(G4, "<</K -1 /Columns 21 /Rows 8>>").unwrap();
+        assert_eq!(&inverted[..], bitmap(&ROWS, false));
+        assert_eq!(inverted[..3], [0xff, 0xff, 0xff]);
+        assert_eq!(inverted[3..6], [0xc7, 0xc1, 0xff]);
+
+        let rows = decode(G4, "<</K -1 /Columns 21 /Rows 3 /BlackIs1 true>>").unwrap();
+        assert_eq!(&rows[..], bitmap(&ROWS[..3], true));
+    }
+
+    #[test]
+    fn filter_chain() {
+        use std::io::Write;
+
+        use crate::pdf::Stream;
+
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(G4).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let stream = |dictionary: &str, data: &[u8]| Stream {
+            dictionary: crate::parse::object::object(dictionary.as_bytes().into())
+                .unwrap()
+                .1
+                .dictionary()
+                .unwrap()
+                .clone(),
+            data: data.to_vec().into(),
+            provenance: Default::default(),
+        };
+
+        let chain = stream(
+            "<</Filter [/FlateDecode /CCITTFaxDecode] /DecodeParms [null <</K -1 /Columns 21 /BlackIs1 true>>]>>",
+            &compressed,
+        );
+        assert_eq!(&chain.filtered_data().unwrap()[..], bitmap(&ROWS, true));
+        // A single dictionary belongs to the last filter.
+        let single = stream(
+            "<</Filter [/FlateDecode /CCITTFaxDecode] /DecodeParms <</K -1 /Columns 21 /BlackIs1 true>>>>",
+            &compressed,
+        );
+        assert_eq!(&single.filtered_data().unwrap()[..], bitmap(&ROWS, true));
+        let unchained = stream(
+            "<</Filter /CCITTFaxDecode /DecodeParms <</K -1 /Columns 21 /BlackIs1 true>>>>",
+            G4,
+        );
+        assert_eq!(&unchained.filtered_data().unwrap()[..], bitmap(&ROWS, true));
+    }
+
+    #[test]
+    fn group_3() {
+        for (data, params) in [
+            (G3, "<</Columns 21 /BlackIs1 true>>"),
+            (G3_2D, "<</K 2 /Columns 21 /BlackIs1 true>>"),
+            (ALIGNED, "<</K 0 /Columns 21 /EncodedByteAlign true /BlackIs1 true>>"),
+        ] {
+            assert_eq!(&decode(data, params).unwrap()[..], bitmap(&ROWS, true), "{}", params);
+        }
+    }
+
+    #[test]
+    fn long_runs() {
+        // 1000 white, 3 black and 997 white pixels, a white and a black row
+        let rows = [
+            format!("{}###{}", ".".repeat(1000), ".".repeat(997)),
+            ".".repeat(2000),
+            "#".repeat(2000),
+        ];
+        let rows = rows.iter().map(String::as_str).collect::<Vec<_>>();
+        let g4 = b"\x2d\x42\x9a\x32\x6a\x02\x40\xb8\x00\x80\x08";
+        let g3 = b"\x00\x16\xa1\x4c\xd4\x16\x00\x10\x12\xa8\x00\x4d\x40\x48\x17";
+        for (data, k) in [(&g4[..], -1), (&g3[..], 0)] {
+            let params = format!("<</K {} /Columns 2000 /BlackIs1 true>>", k);
+            assert_eq!(&decode(data, &params).unwrap()[..], bitmap(&rows, true));
+        }
+    }
+
+    #[test]
+    fn invalid_data() {
+        assert_eq!(
+            decode(&G4[..10], "<</K -1 /Columns 21>>"),
+            Err(FilterError::InvalidData)
+        );
+        // The rows are longer than the columns.
+        assert_eq!(decode(G4, "<</K -1 /Columns 20>>"), Err(FilterError::InvalidData));
+        assert_eq!(decode(b"\x00\x1f\xff", "<</K 0>>"), Err(FilterError::InvalidData));
+    }
+
+    #[test]
+    fn too_large() {
+        assert_eq!(
+            decode(G4, "<</K -1 /Columns 2147483647>>"),
+            Err(FilterError::InvalidData)
+        );
+        assert_eq!(
+            decode(G4, "<</K -1 /Columns 65536 /Rows 2147483647>>"),
+            Err(FilterError::InvalidData)
+        );
+        let params = Params::new_with(None).unwrap();
+        assert_eq!(params.columns, 1728);
+        let rows = (MAX_PIXELS / 1728) as usize;
+        assert!(params.fits(rows));
+        assert!(!params.fits(rows + 1));
+    }
+}
@@ -1,16 +1,54 @@
 use std::ops::Deref;
 
+#[cfg(doc)]
+use crate::pdf::ObjectSource;
+use crate::pdf::{
+    text::{self, TextDecodeError},
+    Bytes,
+};
+
 #[derive(Clone, PartialEq, Eq, Hash)]
-pub struct CbString(Vec<u8>);
+pub struct CbString(Bytes);
+
+impl CbString {
+    /// The string content with all escape sequences resolved.
+    pub fn unescaped(&self) -> Vec<u8> {
+        text::unescape_literal(&self.0)
+    }
+
+    /// Decode the string as text string of a document with the given
+    /// `version`, see [ObjectSource::effective_version].
+    ///
+    /// Strings starting with the byte order mark `FE FF` are decoded as
+    /// UTF-16BE, strings starting with `EF BB BF` as UTF-8 since PDF 2.0 and
+    /// all others using PDFDocEncoding.
+    pub fn decode_text(&self, version: (u8, u8)) -> Result<String, TextDecodeError> {
+        text::decode_text(&self.unescaped(), version)
+    }
+
+    /// Decode the text string, replacing invalid characters with U+FFFD.
+    ///
+    /// Unlike [CbString::decode_text] the version isn't known, strings
+    /// starting with `EF BB BF` are always decoded as UTF-8.
+    pub fn to_string_lossy(&self) -> String {
+        text::decode_text_lossy(&self.unescaped(), text::UTF8_VERSION)
+    }
+}
 
 impl From<Vec<u8>> for CbString {
     fn from(v: Vec<u8>) -> Self {
-        CbString(v)
+        CbString(v.into())
+    }
+}
+
+impl From<Bytes> for CbString {
+    fn from(b: Bytes) -> Self {
+        CbString(b)
     }
 }
 
 impl Deref for CbString {
-    type Target = Vec<u8>;
+    type Target = [u8];
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -30,3 +68,21 @@ impl std::fmt::Display for CbString {
         write!(f, "{}", &String::from_utf8_lossy(&self.0[..]))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_escaped_utf16() {
+        let s = CbString::from(br"\376\377\000A\000\(".to_vec());
+        assert_eq!(s.decode_text((1, 7)), Ok("A(".to_string()));
+        assert_eq!(s.to_string_lossy(), "A(");
+    }
+
+    #[test]
+    fn decode_pdf_doc() {
+        let s = CbString::from(b"\x80 Caf\xe9".to_vec());
+        assert_eq!(s.decode_text((1, 7)), Ok("\u{2022} Café".to_string()));
+    }
+}
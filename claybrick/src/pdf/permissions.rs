@@ -0,0 +1,203 @@
+//! Access permissions of encrypted documents and the parameters to encrypt
+//! written documents.
+
+use crate::pdf::{DictExt, Dictionary};
+
+const K_VERSION: &[u8] = b"V";
+const K_REVISION: &[u8] = b"R";
+const K_LENGTH: &[u8] = b"Length";
+const K_PERMISSIONS: &[u8] = b"P";
+
+/// The user access permissions stored in the `P` entry of the encryption
+/// dictionary, and the parameters of the encryption.
+///
+/// The permissions are not enforced by this crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Permissions {
+    /// The raw flags, bit 1 is the least significant bit.
+    pub flags: u32,
+    /// The algorithm version `V`.
+    pub version: u32,
+    /// The revision `R` of the security handler.
+    pub revision: u32,
+    /// The key length in bits.
+    pub key_length: u32,
+    pub can_print: bool,
+    /// Printing in a quality that allows reproducing the document.
+    pub can_print_high_res: bool,
+    /// Modifications other than annotating, filling forms and assembling.
+    pub can_modify: bool,
+    /// Copying or extracting text and graphics.
+    pub can_copy: bool,
+    /// Adding or modifying annotations and form fields.
+    pub can_annotate: bool,
+    pub can_fill_forms: bool,
+    /// Extracting text and graphics for accessibility.
+    pub can_extract_accessibility: bool,
+    /// Inserting, rotating or deleting pages and creating bookmarks.
+    pub can_assemble: bool,
+}
+
+impl Permissions {
+    /// Returns `None` if `P` is missing or invalid.
+    pub fn from_encrypt_dict(dict: &Dictionary) -> Option<Self> {
+        let flags = match dict.get_integer(K_PERMISSIONS) {
+            // Usually stored as negative 32 bit integer, but some writers use
+            // the unsigned value.
+            Ok(p) if (i64::from(i32::MIN)..=i64::from(u32::MAX)).contains(&p) => p as u32,
+            other => {
+                log::warn!("Invalid permissions {:?}", other);
+                return None;
+            }
+        };
+        let version = dict
+            .get_integer_opt(K_VERSION)
+            .ok()
+            .flatten()
+            .and_then(|v| u32::try_from(v).ok())
+            .unwrap_or(0);
+        let revision = dict
+            .get_integer_opt(K_REVISION)
+            .ok()
+            .flatten()
+            .and_then(|r| u32::try_from(r).ok())
+            .unwrap_or(2);
+        let key_length = match version {
+            0 | 1 => 40,
+            _ => dict
+                .get_integer_opt(K_LENGTH)
+                .ok()
+                .flatten()
+                .and_then(|l| u32::try_from(l).ok())
+                .unwrap_or(if version >= 4 { 128 } else { 40 }),
+        };
+
+        let bit = |n: u32| flags & (1 << (n - 1)) != 0;
+        // Revision 2 lacks the bits 9 to 12, they are controlled by the
+        // coarser bits instead.
+        let extended = |n: u32, fallback: u32| if revision >= 3 { bit(n) } else { bit(fallback) };
+
+        Some(Self {
+            flags,
+            version,
+            revision,
+            key_length,
+            can_print: bit(3),
+            can_print_high_res: bit(3) && extended(12, 3),
+            can_modify: bit(4),
+            can_copy: bit(5),
+            can_annotate: bit(6),
+            can_fill_forms: bit(6) || extended(9, 6),
+            can_extract_accessibility: extended(10, 5),
+            can_assemble: extended(11, 4),
+        })
+    }
+}
+
+/// The algorithm used to encrypt written documents.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EncryptionAlgorithm {
+    /// RC4 with a 128 bit key, PDF 1.4. Broken, only use it for old readers.
+    Rc4_128,
+    /// AES with a 128 bit key, PDF 1.6.
+    Aes128,
+    /// AES with a 256 bit key, PDF 2.0.
+    #[default]
+    Aes256,
+}
+
+/// The passwords and permissions of a document that is encrypted when it is
+/// written.
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionParams {
+    /// Required to open the document, usually empty.
+    pub user_password: Vec<u8>,
+    /// Grants all permissions. An empty owner password is replaced with the
+    /// user password.
+    pub owner_password: Vec<u8>,
+    /// The flags of the `P` entry, see [Permissions::flags]. The bits the
+    /// spec reserves are set as required.
+    pub permissions: u32,
+    pub algorithm: EncryptionAlgorithm,
+    /// Whether the metadata streams are encrypted. Metadata that isn't
+    /// encrypted can be indexed without password.
+    pub encrypt_metadata: bool,
+}
+
+impl Default for EncryptionParams {
+    /// Empty passwords, all permissions and AES-256.
+    fn default() -> Self {
+        Self {
+            user_password: Vec::new(),
+            owner_password: Vec::new(),
+            permissions: u32::MAX,
+            algorithm: EncryptionAlgorithm::default(),
+            encrypt_metadata: true,
+        }
+    }
+}
+
+impl std::fmt::Debug for EncryptionParams {
+    /// The passwords are not shown.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptionParams")
+            .field("permissions", &format_args!("{:#010X}", self.permissions))
+            .field("algorithm", &self.algorithm)
+            .field("encrypt_metadata", &self.encrypt_metadata)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf::{Name, Object};
+
+    use super::*;
+
+    fn encrypt_dict(revision: i32, p: i32) -> Dictionary {
+        Dictionary::from([
+            (Name::from(b"V".to_vec()), Object::Integer(2)),
+            (Name::from(b"R".to_vec()), Object::Integer(revision)),
+            (Name::from(b"Length".to_vec()), Object::Integer(128)),
+            (Name::from(b"P".to_vec()), Object::Integer(p)),
+        ])
+    }
+
+    #[test]
+    fn all_permissions() {
+        // 0xFFFFFFFC: everything allowed
+        let all = Permissions::from_encrypt_dict(&encrypt_dict(3, -4)).unwrap();
+        assert_eq!(all.flags, 0xFFFF_FFFC);
+        assert_eq!((all.version, all.revision, all.key_length), (2, 3, 128));
+        assert!(all.can_print && all.can_print_high_res && all.can_modify && all.can_copy);
+        assert!(all.can_annotate && all.can_fill_forms && all.can_extract_accessibility && all.can_assemble);
+    }
+
+    #[test]
+    fn restricted_permissions() {
+        // 0xFFFFF0C4: print (low res) only
+        let print_only = Permissions::from_encrypt_dict(&encrypt_dict(3, -3900)).unwrap();
+        assert!(print_only.can_print);
+        assert!(!print_only.can_print_high_res);
+        assert!(!print_only.can_modify);
+        assert!(!print_only.can_copy);
+        assert!(!print_only.can_annotate);
+        assert!(!print_only.can_fill_forms);
+        assert!(!print_only.can_extract_accessibility);
+        assert!(!print_only.can_assemble);
+
+        // 0xFFFFFDD4: print, copy, fill forms and assemble
+        let forms = Permissions::from_encrypt_dict(&encrypt_dict(3, -556)).unwrap();
+        assert!(forms.can_print && forms.can_print_high_res && forms.can_copy && forms.can_fill_forms);
+        assert!(!forms.can_modify && !forms.can_annotate && !forms.can_extract_accessibility);
+        assert!(forms.can_assemble);
+    }
+
+    #[test]
+    fn revision_2_permissions() {
+        // 0xFFFFFFD4: print and copy. The extended bits are ignored.
+        let r2 = Permissions::from_encrypt_dict(&encrypt_dict(2, -44)).unwrap();
+        assert!(r2.can_print && r2.can_print_high_res && r2.can_copy && r2.can_extract_accessibility);
+        assert!(!r2.can_modify && !r2.can_annotate && !r2.can_fill_forms && !r2.can_assemble);
+    }
+}
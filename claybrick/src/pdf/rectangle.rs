@@ -0,0 +1,162 @@
+use super::{Array, Object};
+
+/// Error returned if an array can't be converted into a [Rectangle].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidRectangle;
+
+/// A rectangle given by two diagonally opposite corners `[x0 y0 x1 y1]`.
+///
+/// Rectangles converted from arrays are normalized, so that `(x0, y0)` is the
+/// lower left and `(x1, y1)` the upper right corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rectangle {
+    pub x0: f32,
+    pub y0: f32,
+    pub x1: f32,
+    pub y1: f32,
+}
+
+impl Rectangle {
+    pub const fn new(x0: f32, y0: f32, x1: f32, y1: f32) -> Self {
+        Self { x0, y0, x1, y1 }
+    }
+
+    /// The corner coordinates in the order `[x0 y0 x1 y1]`.
+    pub fn as_array(&self) -> [f32; 4] {
+        [self.x0, self.y0, self.x1, self.y1]
+    }
+
+    /// The rectangle with `x0 <= x1` and `y0 <= y1`.
+    pub fn normalized(&self) -> Self {
+        Self::new(
+            self.x0.min(self.x1),
+            self.y0.min(self.y1),
+            self.x0.max(self.x1),
+            self.y0.max(self.y1),
+        )
+    }
+
+    pub fn width(&self) -> f32 {
+        (self.x1 - self.x0).abs()
+    }
+
+    pub fn height(&self) -> f32 {
+        (self.y1 - self.y0).abs()
+    }
+
+    /// Whether the rectangles overlap. Rectangles that only share an edge
+    /// intersect as well.
+    pub fn intersects(&self, other: &Rectangle) -> bool {
+        let (a, b) = (self.normalized(), other.normalized());
+        a.x0 <= b.x1 && b.x0 <= a.x1 && a.y0 <= b.y1 && b.y0 <= a.y1
+    }
+
+    /// Whether the point lies inside the rectangle or on its edge.
+    pub fn contains_point(&self, x: f32, y: f32) -> bool {
+        let r = self.normalized();
+        (r.x0..=r.x1).contains(&x) && (r.y0..=r.y1).contains(&y)
+    }
+}
+
+impl From<[f32; 4]> for Rectangle {
+    fn from([x0, y0, x1, y1]: [f32; 4]) -> Self {
+        Self::new(x0, y0, x1, y1)
+    }
+}
+
+impl TryFrom<&Array> for Rectangle {
+    type Error = InvalidRectangle;
+
+    /// Integers and reals are accepted, the corners can be in any order.
+    fn try_from(array: &Array) -> Result<Self, Self::Error> {
+        let values = array
+            .iter()
+            .map(|obj| obj.as_f64().map(|v| v as f32))
+            .collect::<Option<Vec<f32>>>()
+            .ok_or(InvalidRectangle)?;
+        let values: [f32; 4] = values.try_into().map_err(|_| InvalidRectangle)?;
+
+        Ok(Self::from(values).normalized())
+    }
+}
+
+impl From<Rectangle> for Array {
+    fn from(rect: Rectangle) -> Self {
+        rect.as_array().map(Object::Float).to_vec().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rectangle_from_array() {
+        let array = Array::from(vec![
+            Object::Integer(0),
+            Object::Float(0.5),
+            Object::Integer(612),
+            Object::Integer(792),
+        ]);
+        assert_eq!(Rectangle::try_from(&array), Ok(Rectangle::new(0.0, 0.5, 612.0, 792.0)));
+
+        let array = Array::from(vec![Object::Integer(0), Object::Integer(0), Object::Integer(612)]);
+        assert_eq!(Rectangle::try_from(&array), Err(InvalidRectangle));
+
+        let array = Array::from(vec![
+            Object::Integer(0),
+            Object::Integer(0),
+            Object::Integer(612),
+            Object::Null,
+        ]);
+        assert_eq!(Rectangle::try_from(&array), Err(InvalidRectangle));
+    }
+
+    #[test]
+    fn unordered_corners() {
+        let array = Array::from(vec![
+            Object::Integer(100),
+            Object::Integer(20),
+            Object::Float(10.5),
+            Object::Integer(200),
+        ]);
+        let rect = Rectangle::try_from(&array).unwrap();
+        assert_eq!(rect, Rectangle::new(10.5, 20.0, 100.0, 200.0));
+        assert_eq!(rect.width(), 89.5);
+        assert_eq!(rect.height(), 180.0);
+
+        let unordered = Rectangle::new(100.0, 200.0, 10.5, 20.0);
+        assert_eq!(unordered.width(), 89.5);
+        assert_eq!(unordered.normalized(), rect);
+        assert!(unordered.contains_point(50.0, 20.0));
+    }
+
+    #[test]
+    fn geometry() {
+        let rect = Rectangle::new(0.0, 0.0, 10.0, 10.0);
+        assert!(rect.contains_point(0.0, 10.0));
+        assert!(rect.contains_point(5.0, 5.0));
+        assert!(!rect.contains_point(10.5, 5.0));
+
+        assert!(rect.intersects(&Rectangle::new(5.0, 5.0, 15.0, 15.0)));
+        assert!(rect.intersects(&Rectangle::new(10.0, 0.0, 20.0, 10.0)));
+        assert!(rect.intersects(&Rectangle::new(2.0, 2.0, 3.0, 3.0)));
+        assert!(!rect.intersects(&Rectangle::new(11.0, 0.0, 20.0, 10.0)));
+        assert!(!rect.intersects(&Rectangle::new(0.0, -5.0, 10.0, -1.0)));
+    }
+
+    #[test]
+    fn into_array() {
+        let array = Array::from(Rectangle::new(0.0, 0.0, 612.0, 792.5));
+        assert_eq!(
+            array,
+            Array::from(vec![
+                Object::Float(0.0),
+                Object::Float(0.0),
+                Object::Float(612.0),
+                Object::Float(792.5)
+            ])
+        );
+        assert_eq!(Rectangle::try_from(&array), Ok(Rectangle::new(0.0, 0.0, 612.0, 792.5)));
+    }
+}
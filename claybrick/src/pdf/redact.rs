@@ -0,0 +1,161 @@
+//! Removal of objects including their previous versions.
+
+use crate::pdf::{document::K_PAGES, IndirectObject, Object, RawPdf, Reference};
+
+use super::references::PathElement;
+
+/// Reasons an object can't be redacted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RedactError {
+    /// The object is the document catalog.
+    Catalog,
+    /// The object is the root of the page tree.
+    PageTreeRoot,
+    /// There is no object with this number.
+    MissingObject(usize),
+}
+
+/// Replace object `number` with null and remove all previous versions of it.
+/// References to the object are replaced with null.
+pub(crate) fn redact_object(raw_pdf: &mut RawPdf, number: usize) -> Result<(), RedactError> {
+    let root = raw_pdf.sections.first().map(|s| s.trailer.root);
+    if root.is_some_and(|root| root.index as usize == number) {
+        return Err(RedactError::Catalog);
+    }
+    let pages = root
+        .and_then(|root| raw_pdf.lookup_reference(&root))
+        .and_then(Object::dictionary)
+        .and_then(|catalog| catalog.get(K_PAGES))
+        .and_then(Object::reference);
+    if pages.is_some_and(|pages| pages.index as usize == number) {
+        return Err(RedactError::PageTreeRoot);
+    }
+    let generation = match raw_pdf.object(number) {
+        Some(Object::Indirect(indirect)) => indirect.generation,
+        Some(_) => 0,
+        None => return Err(RedactError::MissingObject(number)),
+    };
+
+    let reference = Reference::new(number as u32, generation);
+    let sites = raw_pdf.reference_map().referrers(reference).to_vec();
+    for site in sites {
+        // The map only considers the newest version of the referencing object.
+        let referrer = raw_pdf
+            .sections
+            .iter_mut()
+            .find_map(|section| section.objects.get_mut(&site.object))
+            .and_then(|referrer| element_mut(referrer, &site.path));
+        match referrer {
+            Some(element) if element.reference() == Some(&reference) => *element = Object::Null,
+            _ => log::warn!("Reference to redacted object {} not found in {}", number, site.object),
+        }
+    }
+
+    for section in &mut raw_pdf.sections {
+        section.objects.remove(&number);
+        section.object_spans.remove(&number);
+        section.stream_spans.remove(&number);
+    }
+    if let Some(newest) = raw_pdf.sections.first_mut() {
+        newest.objects.insert(
+            number,
+            Object::Indirect(IndirectObject::new(number as u32, generation, Object::Null)),
+        );
+    }
+    Ok(())
+}
+
+/// The element at the end of `path`, see [super::references::walk].
+fn element_mut<'a>(obj: &'a mut Object, path: &[PathElement]) -> Option<&'a mut Object> {
+    let element = match (obj, path.first()) {
+        (Object::Indirect(indirect), _) => return element_mut(&mut indirect.object, path),
+        (obj, None) => return Some(obj),
+        (Object::Array(array), Some(PathElement::Index(index))) => array.get_mut(*index)?,
+        (Object::Dictionary(dict), Some(PathElement::Key(key))) => dict.get_mut(key)?,
+        (Object::Stream(stream), Some(PathElement::Key(key))) => stream.dictionary.get_mut(key)?,
+        _ => return None,
+    };
+    element_mut(element, &path[1..])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        build::PdfBuilder,
+        pdf::{document::AfRelationship, PdfSection},
+        simple_encode::{EncodeOptions, SimpleEncoder},
+    };
+
+    use super::*;
+
+    const SECRET: &[u8] = b"Jane Doe, born 1970-01-01, 221B Baker Street";
+
+    fn encode(pdf: &RawPdf) -> Vec<u8> {
+        let mut out = Vec::new();
+        SimpleEncoder::write_with(pdf, &mut out, EncodeOptions::default()).unwrap();
+        out
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack.windows(needle.len()).any(|window| window == needle)
+    }
+
+    #[test]
+    fn embedded_file() {
+        let mut builder = PdfBuilder::new();
+        builder.add_page(595.0, 842.0);
+        builder.attach_file("person.txt", SECRET, "text/plain", AfRelationship::Data);
+        let mut pdf = crate::read_bytes(encode(&builder.finish().unwrap())).unwrap();
+
+        let catalog = pdf.catalog().unwrap();
+        let spec = catalog.embedded_files().remove(0).1;
+        let file = spec.dictionary().get(&b"EF"[..]).unwrap().dictionary().unwrap();
+        let file = *file.get(&b"F"[..]).unwrap().reference().unwrap();
+        let data = spec.embedded_file().unwrap().data.to_vec();
+        // An incremental update with a newer version of the file.
+        let mut update = PdfSection {
+            objects: Default::default(),
+            ..pdf.sections[0].clone()
+        };
+        update
+            .objects
+            .insert(file.index as usize, pdf.object(file.index as usize).unwrap().clone());
+        pdf.sections.insert(0, update);
+        assert!(contains(&encode(&pdf), &data));
+
+        pdf.redact_object(file.index as usize).unwrap();
+        assert_eq!(
+            pdf.object(file.index as usize).unwrap().indirect().unwrap().object(),
+            &Object::Null
+        );
+        assert!(!pdf.sections[1].contains(file.index as usize));
+        let out = encode(&pdf);
+        assert!(!contains(&out, &data));
+
+        let pdf = crate::read_bytes(out).unwrap();
+        let catalog = pdf.catalog().unwrap();
+        let spec = &catalog.embedded_files()[0].1;
+        assert_eq!(spec.file_name().as_deref(), Some("person.txt"));
+        assert!(spec.embedded_file().is_none());
+        let files = spec.dictionary().get(&b"EF"[..]).unwrap().dictionary().unwrap();
+        assert!(files.values().all(|value| value == &Object::Null));
+    }
+
+    #[test]
+    fn protected_objects() {
+        let mut builder = PdfBuilder::new();
+        builder.add_page(595.0, 842.0);
+        let mut pdf = builder.finish().unwrap();
+        let root = pdf.sections[0].trailer.root;
+        let pages = *pdf
+            .dereference(&root)
+            .and_then(Object::dictionary)
+            .and_then(|catalog| catalog.get(K_PAGES))
+            .and_then(Object::reference)
+            .unwrap();
+        assert_eq!(pdf.redact_object(root.index as usize), Err(RedactError::Catalog));
+        assert_eq!(pdf.redact_object(pages.index as usize), Err(RedactError::PageTreeRoot));
+        assert_eq!(pdf.redact_object(1000), Err(RedactError::MissingObject(1000)));
+        assert!(pdf.page(0).is_some());
+    }
+}
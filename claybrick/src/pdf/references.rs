@@ -0,0 +1,208 @@
+//! Index of the references between objects.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::pdf::{xref::is_structural_stream, Dictionary, Name, Object, RawPdf, Reference};
+
+/// A step from an object to one of its elements.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathElement {
+    /// The value of a dictionary or stream dictionary entry.
+    Key(Name),
+    /// An array element.
+    Index(usize),
+}
+
+/// Shows a key as `/Key` and an index as `[0]`.
+impl std::fmt::Display for PathElement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathElement::Key(key) => write!(f, "/{}", key),
+            PathElement::Index(index) => write!(f, "[{}]", index),
+        }
+    }
+}
+
+/// The place where a reference occurs.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ReferenceSite {
+    /// Number of the object that contains the reference.
+    pub object: usize,
+    /// Path from the referencing object to the reference, e.g. `Resources`,
+    /// `Font`, `F1`. Empty if the object itself is a reference.
+    pub path: Vec<PathElement>,
+}
+
+/// Mapping from referenced objects to the places that reference them.
+///
+/// Only the newest version of each object is considered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReferenceMap {
+    referrers: HashMap<(u32, u32), Vec<ReferenceSite>>,
+    orphans: Vec<usize>,
+}
+
+/// Call `visit` for each reference in `obj` with the path to it.
+pub(crate) fn walk(obj: &Object, path: &mut Vec<PathElement>, visit: &mut dyn FnMut(&Reference, &[PathElement])) {
+    match obj {
+        Object::Reference(r) => visit(r, path),
+        Object::Array(a) => {
+            for (index, element) in a.iter().enumerate() {
+                path.push(PathElement::Index(index));
+                walk(element, path, visit);
+                path.pop();
+            }
+        }
+        Object::Dictionary(d) => walk_dict(d, path, visit),
+        Object::Stream(s) => walk_dict(&s.dictionary, path, visit),
+        Object::Indirect(i) => walk(&i.object, path, visit),
+        _ => {}
+    }
+}
+
+fn walk_dict(dict: &Dictionary, path: &mut Vec<PathElement>, visit: &mut dyn FnMut(&Reference, &[PathElement])) {
+    for (key, value) in dict.sorted_entries() {
+        path.push(PathElement::Key(key.clone()));
+        walk(value, path, visit);
+        path.pop();
+    }
+}
+
+impl ReferenceMap {
+    pub(crate) fn new_with(raw_pdf: &RawPdf) -> Self {
+        let mut numbers = raw_pdf
+            .sections
+            .iter()
+            .flat_map(|s| s.objects.keys().copied())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        numbers.sort_unstable();
+
+        let mut referrers = HashMap::<(u32, u32), Vec<ReferenceSite>>::new();
+        let mut outgoing = HashMap::<usize, Vec<Reference>>::new();
+        for &number in &numbers {
+            let Some(obj) = raw_pdf.object(number) else {
+                continue;
+            };
+            let mut path = Vec::new();
+            walk(obj, &mut path, &mut |r, path| {
+                referrers
+                    .entry((r.index, r.generation))
+                    .or_default()
+                    .push(ReferenceSite {
+                        object: number,
+                        path: path.to_vec(),
+                    });
+                outgoing.entry(number).or_default().push(*r);
+            });
+        }
+
+        // Everything reachable from the trailer.
+        let trailer = raw_pdf.sections.first().map(|s| &s.trailer);
+        let mut pending = trailer
+            .map(|t| std::iter::once(t.root).chain(t.info).collect::<Vec<_>>())
+            .unwrap_or_default();
+        let mut reachable = HashSet::new();
+        while let Some(r) = pending.pop() {
+            if raw_pdf.lookup_reference(&r).is_none() {
+                continue;
+            }
+            let number = r.index as usize;
+            if reachable.insert(number) {
+                pending.extend(outgoing.get(&number).into_iter().flatten());
+            }
+        }
+        let orphans = numbers
+            .into_iter()
+            .filter(|number| !reachable.contains(number))
+            .filter(|&number| raw_pdf.object(number).is_some_and(|obj| !is_structural_stream(obj)))
+            .collect();
+
+        Self { referrers, orphans }
+    }
+
+    /// The places that reference the object, ordered by the number of the
+    /// referencing object.
+    pub fn referrers(&self, reference: Reference) -> &[ReferenceSite] {
+        self.referrers
+            .get(&(reference.index, reference.generation))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Numbers of the objects that can't be reached from the `Root` or `Info`
+    /// entries of the trailer. Xref and object streams are not included.
+    pub fn orphans(&self) -> &[usize] {
+        &self.orphans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf::{tests::parsed_pdf, Reference};
+
+    use super::*;
+
+    fn key(k: &str) -> PathElement {
+        PathElement::Key(k.as_bytes().to_vec().into())
+    }
+
+    #[test]
+    fn referrers() {
+        let pdf = parsed_pdf(
+            Reference::new(1, 0),
+            &[
+                b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj",
+                b"2 0 obj <</Type /Pages /Kids [3 0 R 4 0 R] /Count 2>> endobj",
+                b"3 0 obj <</Type /Page /Parent 2 0 R /Resources <</Font <</F1 5 0 R>>>>>> endobj",
+                b"4 0 obj <</Type /Page /Parent 2 0 R /Resources <</Font <</F1 5 0 R>>>>>> endobj",
+                b"5 0 obj <</Type /Font /Subtype /Type1 /BaseFont /Helvetica>> endobj",
+                b"6 0 obj [1 0 R 7 0 R] endobj",
+                b"7 0 obj 6 0 R endobj",
+            ],
+        );
+        let map = pdf.reference_map();
+
+        assert_eq!(
+            map.referrers(Reference::new(2, 0)),
+            &[
+                ReferenceSite {
+                    object: 1,
+                    path: vec![key("Pages")],
+                },
+                ReferenceSite {
+                    object: 3,
+                    path: vec![key("Parent")],
+                },
+                ReferenceSite {
+                    object: 4,
+                    path: vec![key("Parent")],
+                },
+            ]
+        );
+        assert_eq!(
+            map.referrers(Reference::new(4, 0)),
+            &[ReferenceSite {
+                object: 2,
+                path: vec![key("Kids"), PathElement::Index(1)],
+            }]
+        );
+        assert_eq!(map.referrers(Reference::new(5, 0)).len(), 2);
+        assert_eq!(
+            map.referrers(Reference::new(5, 0))[1].path,
+            [key("Resources"), key("Font"), key("F1")]
+        );
+        assert_eq!(
+            map.referrers(Reference::new(6, 0)),
+            &[ReferenceSite {
+                object: 7,
+                path: vec![],
+            }]
+        );
+        assert!(map.referrers(Reference::new(1, 1)).is_empty());
+
+        // The cycle 6 <-> 7 is not reachable, although 6 references the
+        // catalog.
+        assert_eq!(map.orphans(), &[6, 7]);
+    }
+}
@@ -0,0 +1,682 @@
+//! The standard security handler (revisions 2 to 4 and 6) with RC4 and AES
+//! encryption.
+
+use md5::{Digest, Md5};
+
+use crate::{
+    pdf::{DictExt, Dictionary, IndirectObject, Name, Object},
+    simple_encode::EncodeError,
+};
+
+pub use super::permissions::{EncryptionAlgorithm, EncryptionParams};
+
+mod crypt;
+
+const K_FILTER: &[u8] = b"Filter";
+const K_VERSION: &[u8] = b"V";
+const K_REVISION: &[u8] = b"R";
+const K_LENGTH: &[u8] = b"Length";
+const K_OWNER: &[u8] = b"O";
+const K_USER: &[u8] = b"U";
+const K_OWNER_KEY: &[u8] = b"OE";
+const K_USER_KEY: &[u8] = b"UE";
+const K_PERMS: &[u8] = b"Perms";
+const K_PERMISSIONS: &[u8] = b"P";
+const K_ENCRYPT_METADATA: &[u8] = b"EncryptMetadata";
+const K_CRYPT_FILTERS: &[u8] = b"CF";
+const K_STREAM_FILTER: &[u8] = b"StmF";
+const K_STRING_FILTER: &[u8] = b"StrF";
+const K_CRYPT_METHOD: &[u8] = b"CFM";
+const K_AUTH_EVENT: &[u8] = b"AuthEvent";
+const K_TYPE: &[u8] = b"Type";
+
+const STANDARD: &[u8] = b"Standard";
+const IDENTITY: &[u8] = b"Identity";
+const STANDARD_CRYPT_FILTER: &[u8] = b"StdCF";
+const DOC_OPEN: &[u8] = b"DocOpen";
+const METHOD_NONE: &[u8] = b"None";
+const METHOD_RC4: &[u8] = b"V2";
+const METHOD_AES_128: &[u8] = b"AESV2";
+const METHOD_AES_256: &[u8] = b"AESV3";
+const XREF: &[u8] = b"XRef";
+const METADATA: &[u8] = b"Metadata";
+
+/// Used to pad passwords to 32 bytes.
+const PADDING: [u8; 32] = [
+    0x28, 0xBF, 0x4E, 0x5E, 0x4E, 0x75, 0x8A, 0x41, 0x64, 0x00, 0x4E, 0x56, 0xFF, 0xFA, 0x01, 0x08, 0x2E, 0x2E, 0x00,
+    0xB6, 0xD0, 0x68, 0x3E, 0x80, 0x2F, 0x0C, 0xA9, 0xFE, 0x64, 0x53, 0x69, 0x7A,
+];
+
+/// Revision 6 uses at most 127 bytes of the password.
+const MAX_PASSWORD_R6: usize = 127;
+
+/// Bits of `P` that must be set (7, 8 and 13 to 32) or cleared (1 and 2).
+const PERMISSIONS_SET: u32 = 0xFFFF_F0C0;
+const PERMISSIONS_CLEARED: u32 = 0b11;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecryptError {
+    /// The document has no `Encrypt` dictionary.
+    NotEncrypted,
+    /// A security handler other than `Standard`.
+    UnsupportedFilter(Name),
+    /// Unsupported algorithm version `V` or handler revision `R`.
+    UnsupportedVersion { version: i64, revision: i64 },
+    /// A crypt filter method other than RC4 and AES, e.g. a custom one.
+    UnsupportedCryptFilter(Name),
+    /// A required entry of the encryption dictionary is missing or invalid.
+    InvalidEncryptDictionary(&'static str),
+    /// The file identifier is required to compute the key.
+    MissingId,
+    /// The password is neither the user nor the owner password.
+    InvalidPassword,
+}
+
+/// Encryption of stream or string data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CryptMethod {
+    Identity,
+    Rc4,
+    /// AES-128 for revision 4, AES-256 for revision 6.
+    Aes,
+}
+
+/// Parameters of the standard security handler.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct StandardSecurityHandler {
+    revision: i64,
+    /// Key length in bytes.
+    key_len: usize,
+    owner: Vec<u8>,
+    user: Vec<u8>,
+    /// The encrypted file key of revision 6, `OE` and `UE`.
+    owner_key: Vec<u8>,
+    user_key: Vec<u8>,
+    permissions: i32,
+    encrypt_metadata: bool,
+    id: Vec<u8>,
+    streams: CryptMethod,
+    strings: CryptMethod,
+}
+
+fn bytes(obj: Option<&Object>) -> Option<Vec<u8>> {
+    match obj? {
+        Object::String(s) => Some(s.unescaped()),
+        Object::HexString(b) => Some(b.to_vec()),
+        _ => None,
+    }
+}
+
+fn rc4(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut state: [u8; 256] = std::array::from_fn(|i| i as u8);
+    let mut j = 0_u8;
+    for i in 0..256 {
+        j = j.wrapping_add(state[i]).wrapping_add(key[i % key.len()]);
+        state.swap(i, usize::from(j));
+    }
+
+    let (mut i, mut j) = (0_u8, 0_u8);
+    data.iter()
+        .map(|b| {
+            i = i.wrapping_add(1);
+            j = j.wrapping_add(state[usize::from(i)]);
+            state.swap(usize::from(i), usize::from(j));
+            b ^ state[usize::from(state[usize::from(i)].wrapping_add(state[usize::from(j)]))]
+        })
+        .collect()
+}
+
+/// Apply RC4 20 times with the key XOR the iteration number.
+fn rc4_rounds(key: &[u8], data: &[u8], rounds: impl Iterator<Item = u8>) -> Vec<u8> {
+    rounds.fold(data.to_vec(), |data, i| {
+        let round_key = key.iter().map(|b| b ^ i).collect::<Vec<_>>();
+        rc4(&round_key, &data)
+    })
+}
+
+fn padded(password: &[u8]) -> Vec<u8> {
+    password.iter().chain(PADDING.iter()).take(32).copied().collect()
+}
+
+fn string(data: impl Into<Vec<u8>>) -> Object {
+    Object::HexString(data.into().into())
+}
+
+fn name(name: &[u8]) -> Object {
+    Object::Name(name.to_vec().into())
+}
+
+/// Whether strings and streams are encrypted or decrypted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Encrypt,
+    Decrypt,
+}
+
+impl StandardSecurityHandler {
+    pub(crate) fn new_with(encrypt: &Dictionary, id: Option<&[u8]>) -> Result<Self, DecryptError> {
+        let filter = encrypt
+            .get_name(K_FILTER)
+            .map_err(|_| DecryptError::InvalidEncryptDictionary("Filter"))?;
+        if &filter[..] != STANDARD {
+            return Err(DecryptError::UnsupportedFilter(filter.clone()));
+        }
+
+        let version = encrypt.get_integer_opt(K_VERSION).ok().flatten().unwrap_or(0);
+        let revision = encrypt
+            .get_integer(K_REVISION)
+            .map_err(|_| DecryptError::InvalidEncryptDictionary("R"))?;
+        if !matches!((version, revision), (1 | 2, 2 | 3) | (4, 4) | (5, 6)) {
+            return Err(DecryptError::UnsupportedVersion { version, revision });
+        }
+
+        let (key_len, streams, strings) = if version >= 4 {
+            (
+                if version == 5 { 32 } else { 16 },
+                Self::crypt_filter(encrypt, K_STREAM_FILTER)?,
+                Self::crypt_filter(encrypt, K_STRING_FILTER)?,
+            )
+        } else {
+            let bits = match version {
+                1 => 40,
+                _ => encrypt.get_integer_opt(K_LENGTH).ok().flatten().unwrap_or(40),
+            };
+            if !(40..=128).contains(&bits) || bits % 8 != 0 {
+                return Err(DecryptError::InvalidEncryptDictionary("Length"));
+            }
+            (bits as usize / 8, CryptMethod::Rc4, CryptMethod::Rc4)
+        };
+
+        // Revision 6 stores a hash and two salts, older revisions a 32 byte
+        // hash.
+        let hash_len = if revision == 6 { 48 } else { 32 };
+        // Some writers pad the entries of revision 6 to 127 bytes.
+        let owner = bytes(encrypt.get(K_OWNER))
+            .filter(|o| o.len() >= hash_len)
+            .ok_or(DecryptError::InvalidEncryptDictionary("O"))?;
+        let user = bytes(encrypt.get(K_USER))
+            .filter(|u| u.len() >= hash_len)
+            .ok_or(DecryptError::InvalidEncryptDictionary("U"))?;
+        let (owner_key, user_key) = if revision == 6 {
+            (
+                bytes(encrypt.get(K_OWNER_KEY))
+                    .filter(|o| o.len() == 32)
+                    .ok_or(DecryptError::InvalidEncryptDictionary("OE"))?,
+                bytes(encrypt.get(K_USER_KEY))
+                    .filter(|u| u.len() == 32)
+                    .ok_or(DecryptError::InvalidEncryptDictionary("UE"))?,
+            )
+        } else {
+            Default::default()
+        };
+        // Some writers store the flags as unsigned integer.
+        let permissions = encrypt
+            .get_integer(K_PERMISSIONS)
+            .map_err(|_| DecryptError::InvalidEncryptDictionary("P"))? as i32;
+        // The key of revision 6 doesn't depend on the identifier.
+        let id = match (revision, id) {
+            (6, id) => id.unwrap_or_default().to_vec(),
+            (_, id) => id.ok_or(DecryptError::MissingId)?.to_vec(),
+        };
+
+        Ok(Self {
+            revision,
+            key_len,
+            owner: owner[..hash_len].to_vec(),
+            user: user[..hash_len].to_vec(),
+            owner_key,
+            user_key,
+            permissions,
+            encrypt_metadata: encrypt.get(K_ENCRYPT_METADATA) != Some(&Object::Bool(false)),
+            id,
+            streams,
+            strings,
+        })
+    }
+
+    /// The method of the crypt filter referenced by `key` (`StmF` or
+    /// `StrF`).
+    fn crypt_filter(encrypt: &Dictionary, key: &[u8]) -> Result<CryptMethod, DecryptError> {
+        let name = match encrypt.get(key).and_then(Object::name) {
+            Some(name) if &name[..] != IDENTITY => name,
+            _ => return Ok(CryptMethod::Identity),
+        };
+        let method = encrypt
+            .get(K_CRYPT_FILTERS)
+            .and_then(Object::dictionary)
+            .and_then(|filters| filters.get(&name[..]))
+            .and_then(Object::dictionary)
+            .and_then(|filter| filter.get(K_CRYPT_METHOD))
+            .and_then(Object::name);
+        match method {
+            None => Ok(CryptMethod::Identity),
+            Some(m) if &m[..] == METHOD_NONE => Ok(CryptMethod::Identity),
+            Some(m) if &m[..] == METHOD_RC4 => Ok(CryptMethod::Rc4),
+            Some(m) if &m[..] == METHOD_AES_128 || &m[..] == METHOD_AES_256 => Ok(CryptMethod::Aes),
+            Some(m) => Err(DecryptError::UnsupportedCryptFilter(m.clone())),
+        }
+    }
+
+    /// A handler for writing a document encrypted according to `params`, the
+    /// file key and the encryption dictionary. `id` is the first member of
+    /// the file identifier. Fails if no random bytes are available for the
+    /// keys of revision 6.
+    pub(crate) fn new_for_writing(
+        params: &EncryptionParams,
+        id: &[u8],
+    ) -> Result<(Self, Vec<u8>, Dictionary), EncodeError> {
+        let (version, revision, key_len, method) = match params.algorithm {
+            // Only revision 4 can leave the metadata unencrypted.
+            EncryptionAlgorithm::Rc4_128 if params.encrypt_metadata => (2, 3, 16, CryptMethod::Rc4),
+            EncryptionAlgorithm::Rc4_128 => (4, 4, 16, CryptMethod::Rc4),
+            EncryptionAlgorithm::Aes128 => (4, 4, 16, CryptMethod::Aes),
+            EncryptionAlgorithm::Aes256 => (5, 6, 32, CryptMethod::Aes),
+        };
+        let owner_password = match &params.owner_password[..] {
+            [] => &params.user_password[..],
+            owner_password => owner_password,
+        };
+        let mut handler = Self {
+            revision,
+            key_len,
+            owner: Vec::new(),
+            user: Vec::new(),
+            owner_key: Vec::new(),
+            user_key: Vec::new(),
+            permissions: ((params.permissions | PERMISSIONS_SET) & !PERMISSIONS_CLEARED) as i32,
+            encrypt_metadata: params.encrypt_metadata,
+            id: id.to_vec(),
+            streams: method,
+            strings: method,
+        };
+
+        let key = if revision == 6 {
+            handler.set_passwords_r6(&params.user_password, owner_password)?
+        } else {
+            // algorithms 3 and 5
+            let user_password = padded(&params.user_password);
+            handler.owner = rc4_rounds(&handler.owner_password_key(owner_password), &user_password, 0..20);
+            let key = handler.file_key(&params.user_password);
+            handler.user = [handler.user_hash(&key), [0; 16].to_vec()].concat();
+            key
+        };
+
+        let mut dictionary = Dictionary::from([
+            (K_FILTER.to_vec().into(), name(STANDARD)),
+            (K_VERSION.to_vec().into(), Object::Integer(version)),
+            (K_REVISION.to_vec().into(), Object::Integer(revision as i32)),
+            (K_LENGTH.to_vec().into(), Object::Integer(key_len as i32 * 8)),
+            (K_PERMISSIONS.to_vec().into(), Object::Integer(handler.permissions)),
+            (K_OWNER.to_vec().into(), string(handler.owner.clone())),
+            (K_USER.to_vec().into(), string(handler.user.clone())),
+        ]);
+        if revision == 6 {
+            dictionary.insert(K_OWNER_KEY.to_vec().into(), string(handler.owner_key.clone()));
+            dictionary.insert(K_USER_KEY.to_vec().into(), string(handler.user_key.clone()));
+            dictionary.insert(K_PERMS.to_vec().into(), string(handler.perms(&key)?));
+        }
+        if version >= 4 {
+            let method = match params.algorithm {
+                EncryptionAlgorithm::Rc4_128 => METHOD_RC4,
+                EncryptionAlgorithm::Aes128 => METHOD_AES_128,
+                EncryptionAlgorithm::Aes256 => METHOD_AES_256,
+            };
+            let filter = Dictionary::from([
+                (K_CRYPT_METHOD.to_vec().into(), name(method)),
+                (K_AUTH_EVENT.to_vec().into(), name(DOC_OPEN)),
+                (K_LENGTH.to_vec().into(), Object::Integer(key_len as i32)),
+            ]);
+            dictionary.insert(
+                K_CRYPT_FILTERS.to_vec().into(),
+                Object::Dictionary(Dictionary::from([(
+                    STANDARD_CRYPT_FILTER.to_vec().into(),
+                    Object::Dictionary(filter),
+                )])),
+            );
+            dictionary.insert(K_STREAM_FILTER.to_vec().into(), name(STANDARD_CRYPT_FILTER));
+            dictionary.insert(K_STRING_FILTER.to_vec().into(), name(STANDARD_CRYPT_FILTER));
+            dictionary.insert(
+                K_ENCRYPT_METADATA.to_vec().into(),
+                Object::Bool(params.encrypt_metadata),
+            );
+        }
+        Ok((handler, key, dictionary))
+    }
+
+    /// Set `U`, `UE`, `O` and `OE` for a random file key, which is returned
+    /// (algorithms 8 and 9).
+    fn set_passwords_r6(&mut self, user_password: &[u8], owner_password: &[u8]) -> Result<Vec<u8>, EncodeError> {
+        let key = crypt::random_bytes::<32>()?;
+        let user_password = &user_password[..user_password.len().min(MAX_PASSWORD_R6)];
+        let owner_password = &owner_password[..owner_password.len().min(MAX_PASSWORD_R6)];
+
+        let [validation_salt, key_salt] = [crypt::random_bytes::<8>()?, crypt::random_bytes::<8>()?];
+        self.user = [
+            &crypt::hash_r6(user_password, &validation_salt, &[])[..],
+            &validation_salt,
+            &key_salt,
+        ]
+        .concat();
+        self.user_key = crypt::aes256_no_iv(&crypt::hash_r6(user_password, &key_salt, &[]), &key, true);
+
+        let [validation_salt, key_salt] = [crypt::random_bytes::<8>()?, crypt::random_bytes::<8>()?];
+        self.owner = [
+            &crypt::hash_r6(owner_password, &validation_salt, &self.user)[..],
+            &validation_salt,
+            &key_salt,
+        ]
+        .concat();
+        self.owner_key = crypt::aes256_no_iv(&crypt::hash_r6(owner_password, &key_salt, &self.user), &key, true);
+        Ok(key.to_vec())
+    }
+
+    /// The permissions encrypted with the file key for `Perms` (algorithm
+    /// 10).
+    fn perms(&self, key: &[u8]) -> Result<Vec<u8>, EncodeError> {
+        let mut perms = [0xFF; 16];
+        perms[..4].copy_from_slice(&self.permissions.to_le_bytes());
+        perms[8] = if self.encrypt_metadata { b'T' } else { b'F' };
+        perms[9..12].copy_from_slice(b"adb");
+        perms[12..].copy_from_slice(&crypt::random_bytes::<4>()?);
+        Ok(crypt::aes256_no_iv(
+            &key.try_into().expect("The key has 32 bytes"),
+            &perms,
+            true,
+        ))
+    }
+
+    /// Compute the file key from the user password (algorithm 2).
+    fn file_key(&self, user_password: &[u8]) -> Vec<u8> {
+        let mut md5 = Md5::new();
+        md5.update(padded(user_password));
+        md5.update(&self.owner);
+        md5.update(self.permissions.to_le_bytes());
+        md5.update(&self.id);
+        if self.revision >= 4 && !self.encrypt_metadata {
+            md5.update([0xFF; 4]);
+        }
+        let mut hash = md5.finalize().to_vec();
+        if self.revision >= 3 {
+            for _ in 0..50 {
+                hash = Md5::digest(&hash[..self.key_len]).to_vec();
+            }
+        }
+        hash.truncate(self.key_len);
+        hash
+    }
+
+    /// The value `U` is compared with, 32 bytes for revision 2 and 16 bytes
+    /// for revisions 3 and 4 (algorithms 4 and 5).
+    fn user_hash(&self, key: &[u8]) -> Vec<u8> {
+        if self.revision == 2 {
+            rc4(key, &PADDING)
+        } else {
+            let mut md5 = Md5::new();
+            md5.update(PADDING);
+            md5.update(&self.id);
+            rc4_rounds(key, &md5.finalize(), 0..20)
+        }
+    }
+
+    /// Compute the file key if `password` is the user password (algorithms
+    /// 4, 5 and 6).
+    fn authenticate_user(&self, password: &[u8]) -> Option<Vec<u8>> {
+        let key = self.file_key(password);
+        let hash = self.user_hash(&key);
+        (hash[..] == self.user[..hash.len()]).then_some(key)
+    }
+
+    /// The RC4 key that encrypts the user password in `O` (algorithm 3).
+    fn owner_password_key(&self, owner_password: &[u8]) -> Vec<u8> {
+        let mut hash = Md5::digest(padded(owner_password)).to_vec();
+        if self.revision >= 3 {
+            for _ in 0..50 {
+                hash = Md5::digest(&hash[..self.key_len]).to_vec();
+            }
+        }
+        hash.truncate(self.key_len);
+        hash
+    }
+
+    /// Recover the user password if `password` is the owner password
+    /// (algorithm 7).
+    fn user_password(&self, owner_password: &[u8]) -> Vec<u8> {
+        let key = self.owner_password_key(owner_password);
+        if self.revision == 2 {
+            rc4(&key, &self.owner)
+        } else {
+            rc4_rounds(&key, &self.owner, (0..20).rev())
+        }
+    }
+
+    /// Compute the file key of revision 6 from the owner or user password
+    /// (algorithm 2.A).
+    fn authenticate_r6(&self, password: &[u8]) -> Option<Vec<u8>> {
+        let password = &password[..password.len().min(MAX_PASSWORD_R6)];
+        let candidates = [
+            (&self.owner, &self.user[..], &self.owner_key),
+            (&self.user, &[][..], &self.user_key),
+        ];
+        candidates.into_iter().find_map(|(hash, user, encrypted_key)| {
+            (crypt::hash_r6(password, &hash[32..40], user)[..] == hash[..32])
+                .then(|| crypt::aes256_no_iv(&crypt::hash_r6(password, &hash[40..48], user), encrypted_key, false))
+        })
+    }
+
+    /// Compute the file key. The password can be either the user or the
+    /// owner password.
+    pub(crate) fn authenticate(&self, password: &[u8]) -> Result<Vec<u8>, DecryptError> {
+        if self.revision == 6 {
+            return self.authenticate_r6(password).ok_or(DecryptError::InvalidPassword);
+        }
+        self.authenticate_user(password)
+            .or_else(|| self.authenticate_user(&self.user_password(password)))
+            .ok_or(DecryptError::InvalidPassword)
+    }
+
+    /// The key for the strings or streams of a single object.
+    fn object_key(&self, key: &[u8], index: u32, generation: u32, method: CryptMethod) -> Vec<u8> {
+        // Revision 6 uses the file key for all objects.
+        if self.revision == 6 {
+            return key.to_vec();
+        }
+        let mut md5 = Md5::new();
+        md5.update(key);
+        md5.update(&index.to_le_bytes()[..3]);
+        md5.update(&generation.to_le_bytes()[..2]);
+        if method == CryptMethod::Aes {
+            md5.update(b"sAlT");
+        }
+        let mut hash = md5.finalize().to_vec();
+        hash.truncate((self.key_len + 5).min(16));
+        hash
+    }
+
+    /// Decrypt all strings and streams of an indirect object in place.
+    pub(crate) fn decrypt_object(&self, key: &[u8], obj: &mut IndirectObject) {
+        // Only encryption needs random bytes and can fail.
+        let _ = self.crypt_object(key, obj, Direction::Decrypt);
+    }
+
+    /// Encrypt all strings and streams of an indirect object in place.
+    /// Strings are replaced with hex strings. Fails if no random bytes are
+    /// available for AES initialization vectors.
+    pub(crate) fn encrypt_object(&self, key: &[u8], obj: &mut IndirectObject) -> Result<(), EncodeError> {
+        self.crypt_object(key, obj, Direction::Encrypt)
+    }
+
+    fn crypt_object(&self, key: &[u8], obj: &mut IndirectObject, direction: Direction) -> Result<(), EncodeError> {
+        let keys = [
+            self.object_key(key, obj.index, obj.generation, self.strings),
+            self.object_key(key, obj.index, obj.generation, self.streams),
+        ];
+        self.crypt(&keys, &mut obj.object, direction)
+    }
+
+    fn crypt_data(method: CryptMethod, key: &[u8], data: &[u8], direction: Direction) -> Result<Vec<u8>, EncodeError> {
+        Ok(match (method, direction) {
+            (CryptMethod::Identity, _) => data.to_vec(),
+            (CryptMethod::Rc4, _) => rc4(key, data),
+            (CryptMethod::Aes, Direction::Encrypt) => crypt::aes_encrypt(key, data)?,
+            (CryptMethod::Aes, Direction::Decrypt) => crypt::aes_decrypt(key, data).unwrap_or_else(|| {
+                log::warn!("Invalid AES encrypted data");
+                data.to_vec()
+            }),
+        })
+    }
+
+    /// `keys` are the object keys for strings and streams.
+    fn crypt(&self, keys: &[Vec<u8>; 2], obj: &mut Object, direction: Direction) -> Result<(), EncodeError> {
+        let [string_key, stream_key] = keys;
+        match obj {
+            Object::String(s) if self.strings != CryptMethod::Identity => {
+                *obj = string(Self::crypt_data(self.strings, string_key, &s.unescaped(), direction)?);
+            }
+            Object::HexString(b) if self.strings != CryptMethod::Identity => {
+                *b = Self::crypt_data(self.strings, string_key, b, direction)?.into();
+            }
+            Object::Array(array) => array.iter_mut().try_for_each(|o| self.crypt(keys, o, direction))?,
+            Object::Dictionary(dict) => dict.values_mut().try_for_each(|o| self.crypt(keys, o, direction))?,
+            Object::Stream(stream) => {
+                stream
+                    .dictionary
+                    .values_mut()
+                    .try_for_each(|o| self.crypt(keys, o, direction))?;
+                let stream_type = stream.dictionary.get(K_TYPE).and_then(Object::name);
+                let skip = match stream_type.map(|t| &t[..]) {
+                    // Cross reference streams are never encrypted.
+                    Some(XREF) => true,
+                    Some(METADATA) => !self.encrypt_metadata,
+                    _ => false,
+                };
+                if !skip && self.streams != CryptMethod::Identity {
+                    stream.data = Self::crypt_data(self.streams, stream_key, &stream.data, direction)?.into();
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::object::object,
+        pdf::{
+            tests::parsed_pdf,
+            xref::{UsedCompressedObject, Xref, XrefEntry},
+            RawPdf, Reference,
+        },
+    };
+
+    use super::*;
+
+    const ID: &[u8] = b"\x01\x23\x45\x67\x89\xAB\xCD\xEF\x01\x23\x45\x67\x89\xAB\xCD\xEF";
+
+    fn pdf(encrypt: &[u8], secret: &[u8]) -> RawPdf {
+        let mut pdf = parsed_pdf(
+            Reference::new(1, 0),
+            &[
+                b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj",
+                b"2 0 obj <</Type /Pages /Kids [] /Count 0>> endobj",
+                encrypt,
+                secret,
+            ],
+        );
+        pdf.sections[0].trailer.encrypt = Some(Object::Reference(Reference::new(3, 0)));
+        pdf.sections[0].trailer.id = Some([ID.to_vec().into(), ID.to_vec().into()]);
+        pdf
+    }
+
+    fn assert_decrypted(pdf: &RawPdf) {
+        assert!(!pdf.is_encrypted());
+        let stream = pdf.dereference(&Reference::new(4, 0)).and_then(Object::stream).unwrap();
+        assert_eq!(
+            stream.dictionary.get(&b"Secret"[..]),
+            Some(&Object::HexString(b"Secret".to_vec().into()))
+        );
+        assert_eq!(&stream.filtered_data().unwrap()[..], b"BT (Hello) Tj ET");
+    }
+
+    fn rc4_40() -> RawPdf {
+        pdf(
+            b"3 0 obj <</Filter /Standard /V 1 /R 2 /Length 40 /P -44
+                /O <C92422687FACEE686E373F10B5C7D04738053152F7E2EE30E11C69EC442576AB>
+                /U <970A09B725B6BFE3A63743309E5BCFCEDFD799A6FA1D90CFCB3CF1EFC790D4C8>>> endobj",
+            b"4 0 obj <</Length 16 /Secret <9645C4550ED4>>> stream\n\
+                \x87\x74\x87\x0F\x23\xC5\x55\xE6\x56\x9F\x04\x0C\xE3\x4D\x6D\x2F\nendstream endobj",
+        )
+    }
+
+    fn rc4_128() -> RawPdf {
+        pdf(
+            b"3 0 obj <</Filter /Standard /V 2 /R 3 /Length 128 /P -3904
+                /O <566FA873EE33C797CD3B904FDADF814AFA34DF9A38F6ED41B984E2C6DA2AA6F5>
+                /U <04B8718D4765719BA91311D5246BB44100000000000000000000000000000000>>> endobj",
+            b"4 0 obj <</Length 16 /Secret <3B5130258886>>> stream\n\
+                \x2A\x60\x73\x7F\xA5\x97\x95\x30\xC0\xD4\x39\x0F\x8B\xD1\x91\xAB\nendstream endobj",
+        )
+    }
+
+    #[test]
+    fn decrypt_rc4_40() {
+        let mut pdf = rc4_40();
+        assert!(pdf.is_encrypted());
+        pdf.decrypt(b"").unwrap();
+        assert_decrypted(&pdf);
+    }
+
+    #[test]
+    fn decrypt_rc4_128() {
+        let mut pdf = rc4_128();
+        assert!(pdf.is_encrypted());
+        assert_eq!(pdf.decrypt(b"wrong"), Err(DecryptError::InvalidPassword));
+        pdf.decrypt(b"").unwrap();
+        assert_decrypted(&pdf);
+        assert_eq!(pdf.decrypt(b""), Err(DecryptError::NotEncrypted));
+    }
+
+    #[test]
+    fn decrypt_with_owner_password() {
+        for mut pdf in [rc4_40(), rc4_128()] {
+            pdf.decrypt(b"owner").unwrap();
+            assert_decrypted(&pdf);
+        }
+    }
+
+    #[test]
+    fn decrypt_object_stream() {
+        let mut pdf = rc4_40();
+        let encrypt = pdf.encrypt_dict().unwrap();
+        let handler = StandardSecurityHandler::new_with(encrypt, Some(ID)).unwrap();
+        let key = handler.authenticate(b"").unwrap();
+        let content = b"1 0 <</Type /Catalog /Pages 2 0 R>>";
+        let header = format!(
+            "5 0 obj <</Type /ObjStm /N 1 /First 4 /Length {}>> stream\n",
+            content.len()
+        );
+        let bytes = [header.as_bytes(), content, b"\nendstream endobj"].concat();
+        let mut object_stream = object(bytes[..].into()).unwrap().1.indirect().unwrap().clone();
+        handler.encrypt_object(&key, &mut object_stream).unwrap();
+
+        let section = &mut pdf.sections[0];
+        section.objects.remove(&1);
+        section.objects.insert(5, Object::Indirect(object_stream));
+        section.xref = Xref::new(vec![XrefEntry::UsedCompressed(UsedCompressedObject {
+            number: 1,
+            containing_object: 5,
+            index: 0,
+        })]);
+        assert!(pdf.catalog().is_err());
+
+        pdf.decrypt(b"").unwrap();
+        assert_decrypted(&pdf);
+        assert_eq!(pdf.catalog().unwrap().page_count(), Ok(0));
+    }
+
+    #[test]
+    fn rc4_test_vector() {
+        assert_eq!(rc4(b"Key", b"Plaintext"), hex::decode("BBF316E8D940AF0AD3").unwrap());
+    }
+}
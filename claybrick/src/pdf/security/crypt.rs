@@ -0,0 +1,142 @@
+//! AES encryption, the password hash of revision 6 and random bytes for
+//! keys and initialization vectors.
+
+use aes::{Aes128, Aes256};
+use cbc::cipher::{
+    block_padding::{NoPadding, Pkcs7},
+    BlockDecryptMut, BlockEncryptMut, KeyIvInit,
+};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use crate::simple_encode::EncodeError;
+
+type Aes128Encryptor = cbc::Encryptor<Aes128>;
+type Aes128Decryptor = cbc::Decryptor<Aes128>;
+type Aes256Encryptor = cbc::Encryptor<Aes256>;
+type Aes256Decryptor = cbc::Decryptor<Aes256>;
+
+/// Size of the AES blocks and initialization vectors.
+const BLOCK: usize = 16;
+
+/// Random bytes of the operating system for keys, salts and initialization
+/// vectors.
+///
+/// Fails if the operating system can't provide random bytes, e.g. on
+/// `wasm32-unknown-unknown` without the `wasm_js` feature of `getrandom`.
+pub(super) fn random_bytes<const N: usize>() -> Result<[u8; N], EncodeError> {
+    let mut bytes = [0; N];
+    getrandom::fill(&mut bytes).map_err(|err| {
+        log::error!("The operating system provides no random bytes: {}", err);
+        EncodeError::RandomUnavailable
+    })?;
+    Ok(bytes)
+}
+
+/// Encrypt `data` with AES in CBC mode. The key length selects AES-128 or
+/// AES-256. The result starts with a random initialization vector.
+pub(super) fn aes_encrypt(key: &[u8], data: &[u8]) -> Result<Vec<u8>, EncodeError> {
+    let iv = random_bytes::<BLOCK>()?;
+    let encrypted = match key.len() {
+        BLOCK => Aes128Encryptor::new_from_slices(key, &iv)
+            .expect("Key and initialization vector have a valid length")
+            .encrypt_padded_vec_mut::<Pkcs7>(data),
+        _ => Aes256Encryptor::new_from_slices(key, &iv)
+            .expect("Key and initialization vector have a valid length")
+            .encrypt_padded_vec_mut::<Pkcs7>(data),
+    };
+    Ok([&iv[..], &encrypted].concat())
+}
+
+/// Decrypt data that starts with the initialization vector, see
+/// [aes_encrypt]. Returns `None` if the data or the padding is invalid.
+pub(super) fn aes_decrypt(key: &[u8], data: &[u8]) -> Option<Vec<u8>> {
+    if data.len() < BLOCK {
+        return None;
+    }
+    let (iv, data) = data.split_at(BLOCK);
+    // Some writers encrypt empty strings to the initialization vector only.
+    if data.is_empty() {
+        return Some(Vec::new());
+    }
+    match key.len() {
+        BLOCK => Aes128Decryptor::new_from_slices(key, iv)
+            .ok()?
+            .decrypt_padded_vec_mut::<Pkcs7>(data)
+            .ok(),
+        _ => Aes256Decryptor::new_from_slices(key, iv)
+            .ok()?
+            .decrypt_padded_vec_mut::<Pkcs7>(data)
+            .ok(),
+    }
+}
+
+/// AES-256 without padding and a zero initialization vector, which encrypts
+/// the file key in `UE` and `OE` and the permissions in `Perms`. `data` must
+/// be a multiple of the block size.
+pub(super) fn aes256_no_iv(key: &[u8; 32], data: &[u8], encrypt: bool) -> Vec<u8> {
+    let iv = [0; BLOCK];
+    if encrypt {
+        Aes256Encryptor::new(key.into(), &iv.into()).encrypt_padded_vec_mut::<NoPadding>(data)
+    } else {
+        Aes256Decryptor::new(key.into(), &iv.into())
+            .decrypt_padded_vec_mut::<NoPadding>(data)
+            .expect("The data is a multiple of the block size")
+    }
+}
+
+/// The password hash of revision 6 (algorithm 2.B). `user_key` is the `U`
+/// entry when hashing owner passwords, otherwise empty.
+pub(super) fn hash_r6(password: &[u8], salt: &[u8], user_key: &[u8]) -> [u8; 32] {
+    let mut k = Sha256::new()
+        .chain_update(password)
+        .chain_update(salt)
+        .chain_update(user_key)
+        .finalize()
+        .to_vec();
+    let mut round = 0_u32;
+    loop {
+        let k1 = [password, &k, user_key].concat().repeat(64);
+        let e = Aes128Encryptor::new_from_slices(&k[..16], &k[16..32])
+            .expect("Key and initialization vector have a valid length")
+            .encrypt_padded_vec_mut::<NoPadding>(&k1);
+        // The first 16 bytes as big endian number modulo 3, which equals the
+        // sum of the bytes modulo 3 since 256 % 3 == 1.
+        k = match e[..16].iter().map(|b| u32::from(*b)).sum::<u32>() % 3 {
+            0 => Sha256::digest(&e).to_vec(),
+            1 => Sha384::digest(&e).to_vec(),
+            _ => Sha512::digest(&e).to_vec(),
+        };
+        round += 1;
+        if round >= 64 && u32::from(e[e.len() - 1]) + 32 <= round {
+            break;
+        }
+    }
+    std::array::from_fn(|i| k[i])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aes_round_trip() {
+        for key in [&[7_u8; 16][..], &[7; 32]] {
+            for data in [&b""[..], b"0123456789ABCDEF", b"Hello"] {
+                let encrypted = aes_encrypt(key, data).unwrap();
+                assert_eq!(encrypted.len(), BLOCK + (data.len() / BLOCK + 1) * BLOCK);
+                assert_eq!(aes_decrypt(key, &encrypted).as_deref(), Some(data));
+            }
+        }
+        assert_eq!(aes_decrypt(&[7; 16], b"short"), None);
+        assert_ne!(random_bytes::<16>().unwrap(), random_bytes::<16>().unwrap());
+    }
+
+    #[test]
+    fn revision_6_hash() {
+        // computed with an independent implementation of algorithm 2.B
+        assert_eq!(
+            hex::encode(hash_r6(b"user", b"\x01\x02\x03\x04\x05\x06\x07\x08", b"")),
+            "17424b40ead366f7ddef0ff073608aa68ba701714b5cef3409b94c4ffa763726"
+        );
+    }
+}
@@ -0,0 +1,324 @@
+//! [Serialize] implementations for dumping documents, e.g. to JSON.
+//!
+//! Names and strings are serialized as strings if they are valid UTF-8 and as
+//! byte arrays otherwise. Dictionary keys are always strings, bytes that are
+//! not valid UTF-8 are written as `#xx` escapes like in PDF files.
+//! Dictionaries and the objects of a section are sorted.
+//!
+//! The `Serialize` implementations of the types use the default
+//! [SerializeOptions], use [with_options] to configure how stream data is
+//! written:
+//!
+//! ```
+//! use claybrick::pdf::serialize::{with_options, SerializeOptions, StreamData};
+//!
+//! let pdf = claybrick::build::PdfBuilder::new().finish().unwrap();
+//! let options = SerializeOptions {
+//!     stream_data: StreamData::Omit,
+//!     ..Default::default()
+//! };
+//! let json = serde_json::to_string(&with_options(&pdf, options)).unwrap();
+//! assert!(json.starts_with("{\"version\":[1,7]"));
+//! ```
+
+use base64::Engine;
+use serde::{
+    ser::{SerializeMap, SerializeSeq, SerializeStruct},
+    Serialize, Serializer,
+};
+
+use crate::pdf::{Array, Bytes, CbString, Dictionary, IndirectObject, Name, Object, PdfSection, RawPdf, Stream};
+
+/// How the data of streams is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StreamData {
+    /// Only the length of the data.
+    Omit,
+    /// Lower case hexadecimal string.
+    Hex,
+    /// Standard base64 string with padding.
+    #[default]
+    Base64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SerializeOptions {
+    pub stream_data: StreamData,
+    /// Only write the first bytes of the stream data. The `length` of the
+    /// stream is still the length of the complete data.
+    pub max_stream_bytes: Option<usize>,
+}
+
+/// A value that is serialized with the given options.
+#[derive(Debug, Clone, Copy)]
+pub struct WithOptions<'a, T: ?Sized> {
+    value: &'a T,
+    options: SerializeOptions,
+}
+
+pub fn with_options<T: ?Sized>(value: &T, options: SerializeOptions) -> WithOptions<'_, T> {
+    WithOptions { value, options }
+}
+
+impl<'a, T: ?Sized> WithOptions<'a, T> {
+    fn wrap<U: ?Sized>(&self, value: &'a U) -> WithOptions<'a, U> {
+        with_options(value, self.options)
+    }
+}
+
+fn serialize_text<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => serializer.serialize_str(text),
+        Err(_) => serializer.serialize_bytes(bytes),
+    }
+}
+
+/// Bytes that are serialized like names and strings.
+struct Text<'a>(&'a [u8]);
+
+impl Serialize for Text<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_text(self.0, serializer)
+    }
+}
+
+/// Dictionary keys are strings in most formats.
+struct Key<'a>(&'a Name);
+
+impl Serialize for Key<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match std::str::from_utf8(self.0) {
+            Ok(text) => serializer.serialize_str(text),
+            Err(_) => serializer.collect_str(
+                &self
+                    .0
+                    .iter()
+                    .map(|&b| {
+                        if b.is_ascii_graphic() && b != b'#' {
+                            (b as char).to_string()
+                        } else {
+                            format!("#{:02x}", b)
+                        }
+                    })
+                    .collect::<String>(),
+            ),
+        }
+    }
+}
+
+impl Serialize for Name {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_text(self, serializer)
+    }
+}
+
+impl Serialize for CbString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_text(self, serializer)
+    }
+}
+
+impl Serialize for Bytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self)
+    }
+}
+
+impl Serialize for WithOptions<'_, Object> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        const NAME: &str = "Object";
+        match self.value {
+            Object::String(s) => serializer.serialize_newtype_variant(NAME, 0, "String", s),
+            Object::HexString(s) => serializer.serialize_newtype_variant(NAME, 1, "HexString", &Text(s)),
+            Object::Float(f) => serializer.serialize_newtype_variant(NAME, 2, "Float", f),
+            Object::Integer(i) => serializer.serialize_newtype_variant(NAME, 3, "Integer", i),
+            Object::Bool(b) => serializer.serialize_newtype_variant(NAME, 4, "Bool", b),
+            Object::Name(n) => serializer.serialize_newtype_variant(NAME, 5, "Name", n),
+            Object::Array(a) => serializer.serialize_newtype_variant(NAME, 6, "Array", &self.wrap(a)),
+            Object::Dictionary(d) => serializer.serialize_newtype_variant(NAME, 7, "Dictionary", &self.wrap(d)),
+            Object::Stream(s) => serializer.serialize_newtype_variant(NAME, 8, "Stream", &self.wrap(s)),
+            Object::Null => serializer.serialize_unit_variant(NAME, 9, "Null"),
+            Object::Indirect(i) => serializer.serialize_newtype_variant(NAME, 10, "Indirect", &self.wrap(i)),
+            Object::Reference(r) => serializer.serialize_newtype_variant(NAME, 11, "Reference", r),
+        }
+    }
+}
+
+impl Serialize for WithOptions<'_, Array> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.value.len()))?;
+        for element in self.value.iter() {
+            seq.serialize_element(&self.wrap(element))?;
+        }
+        seq.end()
+    }
+}
+
+impl Serialize for WithOptions<'_, Dictionary> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entries = self.value.sorted_entries();
+        let mut map = serializer.serialize_map(Some(entries.len()))?;
+        for (key, value) in entries {
+            map.serialize_entry(&Key(key), &self.wrap(value))?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for WithOptions<'_, Stream> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let data = &self.value.data[..];
+        let shown = &data[..self.options.max_stream_bytes.unwrap_or(data.len()).min(data.len())];
+        let encoded = match self.options.stream_data {
+            StreamData::Omit => None,
+            StreamData::Hex => Some(hex::encode(shown)),
+            StreamData::Base64 => Some(base64::engine::general_purpose::STANDARD.encode(shown)),
+        };
+
+        let mut s = serializer.serialize_struct("Stream", 3)?;
+        s.serialize_field("dictionary", &self.wrap(&self.value.dictionary))?;
+        s.serialize_field("length", &data.len())?;
+        match encoded {
+            Some(encoded) => s.serialize_field("data", &encoded)?,
+            None => s.skip_field("data")?,
+        }
+        s.end()
+    }
+}
+
+impl Serialize for WithOptions<'_, IndirectObject> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("IndirectObject", 3)?;
+        s.serialize_field("index", &self.value.index)?;
+        s.serialize_field("generation", &self.value.generation)?;
+        s.serialize_field("object", &self.wrap(&*self.value.object))?;
+        s.end()
+    }
+}
+
+impl Serialize for WithOptions<'_, PdfSection> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut objects = self.value.objects.iter().collect::<Vec<_>>();
+        objects.sort_unstable_by_key(|(number, _)| **number);
+        let objects = objects
+            .into_iter()
+            .map(|(number, obj)| (number, self.wrap(obj)))
+            .collect::<Vec<_>>();
+
+        let mut s = serializer.serialize_struct("PdfSection", 3)?;
+        s.serialize_field("trailer", &self.value.trailer)?;
+        s.serialize_field("xref", &self.value.xref)?;
+        s.serialize_field("objects", &Entries(&objects))?;
+        s.end()
+    }
+}
+
+/// Serializes key value pairs as map in the given order.
+struct Entries<'a, K, V>(&'a [(K, V)]);
+
+impl<K: Serialize, V: Serialize> Serialize for Entries<'_, K, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(k, v)| (k, v)))
+    }
+}
+
+impl Serialize for WithOptions<'_, RawPdf> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("RawPdf", 3)?;
+        s.serialize_field("version", &self.value.version)?;
+        s.serialize_field("announced_binary", &self.value.announced_binary())?;
+        s.serialize_field("sections", &SeqWithOptions(&self.value.sections, self.options))?;
+        s.end()
+    }
+}
+
+struct SeqWithOptions<'a, T>(&'a [T], SerializeOptions);
+
+impl<T> Serialize for SeqWithOptions<'_, T>
+where
+    for<'a> WithOptions<'a, T>: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(|element| with_options(element, self.1)))
+    }
+}
+
+macro_rules! serialize_with_default_options {
+    ($($ty:ty),*) => {
+        $(
+            impl Serialize for $ty {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    with_options(self, SerializeOptions::default()).serialize(serializer)
+                }
+            }
+        )*
+    };
+}
+
+serialize_with_default_options!(Object, Array, Dictionary, Stream, IndirectObject, PdfSection, RawPdf);
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::pdf::{tests::parsed_pdf, Reference};
+
+    use super::*;
+
+    fn pdf() -> RawPdf {
+        parsed_pdf(
+            Reference::new(1, 0),
+            &[
+                b"1 0 obj <</Type /Catalog /Pages 2 0 R /Odd#ff (caf\xe9)>> endobj",
+                b"2 0 obj <</Type /Pages /Kids [] /Count 0>> endobj",
+                b"3 0 obj <</Length 5>> stream\nhello\nendstream endobj",
+            ],
+        )
+    }
+
+    #[test]
+    fn json_structure() {
+        let value = serde_json::to_value(pdf()).unwrap();
+        assert_eq!(value["version"], json!([1, 7]));
+        let section = &value["sections"][0];
+        assert_eq!(section["trailer"]["root"], json!({"index": 1, "generation": 0}));
+
+        let catalog = &section["objects"]["1"]["Indirect"];
+        assert_eq!(catalog["index"], json!(1));
+        let dict = &catalog["object"]["Dictionary"];
+        assert_eq!(dict["Type"], json!({"Name": "Catalog"}));
+        assert_eq!(dict["Pages"], json!({"Reference": {"index": 2, "generation": 0}}));
+        // Invalid UTF-8 is escaped in keys and written as bytes otherwise.
+        assert_eq!(dict["Odd#ff"], json!({"String": [b'c', b'a', b'f', 0xe9]}));
+        let catalog = pdf().object(1).and_then(Object::indirect).unwrap().object.clone();
+        assert_eq!(&serde_json::to_value(catalog.dictionary().unwrap()).unwrap(), dict);
+
+        let stream = &section["objects"]["3"]["Indirect"]["object"]["Stream"];
+        assert_eq!(stream["dictionary"]["Length"], json!({"Integer": 5}));
+        assert_eq!(stream["length"], json!(5));
+        assert_eq!(stream["data"], json!("aGVsbG8="));
+    }
+
+    #[test]
+    fn stream_data_options() {
+        let pdf = pdf();
+        let stream = |options| {
+            let value = serde_json::to_value(with_options(&pdf, options)).unwrap();
+            value["sections"][0]["objects"]["3"]["Indirect"]["object"]["Stream"].clone()
+        };
+
+        let omitted = stream(SerializeOptions {
+            stream_data: StreamData::Omit,
+            max_stream_bytes: None,
+        });
+        assert_eq!(omitted.get("data"), None);
+        assert_eq!(omitted["length"], json!(5));
+
+        let truncated = stream(SerializeOptions {
+            stream_data: StreamData::Hex,
+            max_stream_bytes: Some(2),
+        });
+        assert_eq!(truncated["data"], Value::from("6865"));
+        assert_eq!(truncated["length"], json!(5));
+    }
+}
@@ -0,0 +1,64 @@
+//! Access to the objects of a document, independent of how it was parsed.
+
+use crate::pdf::{Dictionary, Object, Reference, Trailer};
+
+static NULL: Object = Object::Null;
+
+/// The objects of a document.
+///
+/// Implemented by [RawPdf](crate::pdf::RawPdf), which parses all objects
+/// upfront, and by [LazyPdf](crate::pdf::LazyPdf), which parses objects on
+/// first access. The document types like [Catalog](crate::pdf::Catalog) and
+/// [Page](crate::pdf::Page) work with both.
+pub trait ObjectSource: Sync {
+    /// The trailer of the newest section.
+    fn trailer(&self) -> Option<&Trailer>;
+
+    /// The object with the given reference, without the indirect object
+    /// wrapper.
+    fn dereference(&self, reference: &Reference) -> Option<&Object>;
+
+    /// The version of the document, the newer one of the header and the
+    /// `/Version` of the catalog. It decides how text strings are decoded.
+    fn effective_version(&self) -> (u8, u8);
+
+    /// Follow references until a direct object is reached.
+    ///
+    /// References to missing objects and reference cycles resolve to the null
+    /// object.
+    fn resolve<'a>(&'a self, obj: &'a Object) -> &'a Object {
+        let mut visited = Vec::new();
+        let mut current = obj;
+        while let Object::Reference(r) = current {
+            if visited.contains(r) {
+                log::warn!("Reference cycle detected at {:?}", r);
+                return &NULL;
+            }
+            visited.push(*r);
+            current = match self.dereference(r) {
+                Some(obj) => obj,
+                None => {
+                    log::warn!("Reference to missing object {:?}", r);
+                    return &NULL;
+                }
+            };
+        }
+        current
+    }
+
+    /// Get the value for `key` and resolve it if it's a reference.
+    ///
+    /// Returns `None` if the key is missing or the value resolves to null.
+    fn dict_get_resolved<'a>(&'a self, dict: &'a Dictionary, key: &[u8]) -> Option<&'a Object> {
+        dict.get(key)
+            .map(|obj| self.resolve(obj))
+            .filter(|obj| !matches!(obj, Object::Null))
+    }
+}
+
+/// Document types are equal if they belong to the same document.
+impl PartialEq for dyn ObjectSource + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::addr_eq(self, other)
+    }
+}
@@ -0,0 +1,211 @@
+//! Summary of the contents of a document.
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::pdf::{
+    xref::{XrefEntry, XrefKind},
+    Object, RawPdf,
+};
+
+/// How the cross reference sections of a document are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XrefLayout {
+    /// Only classic `xref` tables.
+    Table,
+    /// Only cross reference streams.
+    Stream,
+    /// Tables with an additional cross reference stream referenced by
+    /// `XRefStm`, readable by readers that don't support streams.
+    Hybrid,
+    /// Some sections use tables, others streams, e.g. after an incremental
+    /// update by a different application.
+    Mixed,
+}
+
+/// Counts of the objects, streams and sections of a document.
+///
+/// Only the newest version of each object is counted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PdfStats {
+    /// Number of objects per [type name](Object::type_name).
+    pub object_types: BTreeMap<&'static str, usize>,
+    /// Number of sections, i.e. the original document and its incremental
+    /// updates.
+    pub sections: usize,
+    /// Number of pages, `None` if the page tree is invalid.
+    pub pages: Option<usize>,
+    /// Total length of the stream data before filters are applied.
+    pub stream_bytes: usize,
+    /// How often each filter is used. A stream with several filters is
+    /// counted once per filter.
+    pub filters: BTreeMap<String, usize>,
+    /// Number of objects stored inside object streams.
+    pub compressed_objects: usize,
+    /// `None` for documents that were not parsed from a file.
+    pub xref_layout: Option<XrefLayout>,
+}
+
+impl PdfStats {
+    /// Number of incremental updates following the original document.
+    pub fn incremental_updates(&self) -> usize {
+        self.sections.saturating_sub(1)
+    }
+}
+
+fn xref_layout(raw_pdf: &RawPdf) -> Option<XrefLayout> {
+    raw_pdf
+        .sections
+        .iter()
+        .filter_map(|s| match s.xref.kind {
+            Some(XrefKind::Table) if s.trailer.x_ref_stm.is_some() => Some(XrefLayout::Hybrid),
+            Some(XrefKind::Table) => Some(XrefLayout::Table),
+            Some(XrefKind::Stream { .. }) => Some(XrefLayout::Stream),
+            None => None,
+        })
+        .reduce(|a, b| if a == b { a } else { XrefLayout::Mixed })
+}
+
+/// See [RawPdf::stats].
+pub(crate) fn stats(raw_pdf: &RawPdf) -> PdfStats {
+    let mut stats = PdfStats {
+        sections: raw_pdf.sections.len(),
+        pages: raw_pdf.page_count().ok(),
+        xref_layout: xref_layout(raw_pdf),
+        ..Default::default()
+    };
+
+    // Sections are ordered from newest to oldest, the first occurrence of a
+    // number is the current version of the object.
+    let mut seen = HashSet::new();
+    for section in &raw_pdf.sections {
+        for (number, obj) in &section.objects {
+            if !seen.insert(*number) {
+                continue;
+            }
+            let obj = match obj {
+                Object::Indirect(indirect) => &*indirect.object,
+                direct => direct,
+            };
+            *stats.object_types.entry(obj.type_name()).or_default() += 1;
+
+            if let Some(stream) = obj.stream() {
+                stats.stream_bytes += stream.data.len();
+                match stream.filters() {
+                    Ok(filters) => {
+                        for filter in filters {
+                            *stats.filters.entry(filter.to_string()).or_default() += 1;
+                        }
+                    }
+                    Err(err) => log::warn!("Invalid filter of object {}: {:?}", number, err),
+                }
+            }
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for section in &raw_pdf.sections {
+        for entry in section.xref.entries() {
+            if seen.insert(entry.number()) && matches!(entry, XrefEntry::UsedCompressed(_)) {
+                stats.compressed_objects += 1;
+            }
+        }
+    }
+
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        build::PdfBuilder,
+        parse::parse_complete,
+        pdf::{
+            xref::{UsedCompressedObject, UsedObject},
+            Xref,
+        },
+        simple_encode::{EncodeOptions, SimpleEncoder, XrefStyle},
+    };
+
+    use super::*;
+
+    #[test]
+    fn built_document() {
+        let mut builder = PdfBuilder::new();
+        let page = builder.add_page(612.0, 792.0);
+        builder.set_page_content(page, &b"0 0 m 612 792 l S"[..]);
+        builder.add_page(612.0, 792.0);
+        let pdf = builder.finish().unwrap();
+
+        let stats = pdf.stats();
+        assert_eq!(stats.sections, 1);
+        assert_eq!(stats.incremental_updates(), 0);
+        assert_eq!(stats.pages, Some(2));
+        assert_eq!(stats.object_types, BTreeMap::from([("dictionary", 4), ("stream", 1)]));
+        assert_eq!(stats.stream_bytes, 17);
+        assert!(stats.filters.is_empty());
+        assert_eq!(stats.xref_layout, None);
+
+        let mut out = Vec::new();
+        let options = EncodeOptions {
+            xref: XrefStyle::Table,
+            compress_streams: true,
+            ..Default::default()
+        };
+        SimpleEncoder::write_with(&pdf, &mut out, options).unwrap();
+        let (_, parsed) = parse_complete(out[..].into()).unwrap();
+
+        let stats = parsed.stats();
+        assert_eq!(stats.pages, Some(2));
+        assert_eq!(stats.object_types, BTreeMap::from([("dictionary", 4), ("stream", 1)]));
+        assert_eq!(stats.filters, BTreeMap::from([("FlateDecode".to_string(), 1)]));
+        assert_eq!(stats.compressed_objects, 0);
+        assert_eq!(stats.xref_layout, Some(XrefLayout::Table));
+    }
+
+    #[test]
+    fn layouts() {
+        let mut pdf = PdfBuilder::new().finish().unwrap();
+        pdf.sections[0].xref.kind = Some(XrefKind::Table);
+        let mut update = pdf.sections[0].clone();
+        update.trailer.x_ref_stm = Some(100);
+        assert_eq!(pdf.stats().xref_layout, Some(XrefLayout::Table));
+
+        pdf.sections.insert(0, update);
+        assert_eq!(pdf.stats().xref_layout, Some(XrefLayout::Mixed));
+        assert_eq!(pdf.stats().incremental_updates(), 1);
+
+        pdf.sections[1].trailer.x_ref_stm = Some(50);
+        assert_eq!(pdf.stats().xref_layout, Some(XrefLayout::Hybrid));
+
+        for section in &mut pdf.sections {
+            section.xref.kind = Some(XrefKind::Stream {
+                number: 5,
+                generation: 0,
+            });
+        }
+        assert_eq!(pdf.stats().xref_layout, Some(XrefLayout::Stream));
+    }
+
+    #[test]
+    fn compressed_objects() {
+        let compressed = |number| {
+            XrefEntry::UsedCompressed(UsedCompressedObject {
+                number,
+                containing_object: 10,
+                index: 0,
+            })
+        };
+        let mut pdf = PdfBuilder::new().finish().unwrap();
+        pdf.sections[0].xref = Xref::new(vec![compressed(1), compressed(2)]);
+        let mut update = pdf.sections[0].clone();
+        // Object 2 is replaced by an uncompressed object.
+        update.xref = Xref::new(vec![UsedObject {
+            number: 2,
+            byte_offset: 500,
+            generation: 0,
+        }
+        .into()]);
+        pdf.sections.insert(0, update);
+        assert_eq!(pdf.stats().compressed_objects, 1);
+    }
+}
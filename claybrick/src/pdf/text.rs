@@ -0,0 +1,303 @@
+//! Decoding and encoding of PDF text strings.
+//!
+//! Text strings are either encoded as UTF-16BE (starting with the byte order
+//! mark `FE FF`), UTF-8 (starting with `EF BB BF`, PDF 2.0) or using
+//! PDFDocEncoding.
+
+use crate::pdf::{Dictionary, Object, ObjectSource};
+
+const UTF16_BOM: &[u8] = b"\xFE\xFF";
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+/// The first version with UTF-8 text strings.
+pub(crate) const UTF8_VERSION: (u8, u8) = (2, 0);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextDecodeError {
+    /// Invalid UTF-16BE data after the byte order mark.
+    InvalidUtf16,
+    /// Invalid UTF-8 data after the byte order mark.
+    InvalidUtf8,
+    /// The byte has no character assigned in PDFDocEncoding.
+    UndefinedPdfDocChar(u8),
+}
+
+/// Characters of PDFDocEncoding in the range `0x18..=0x1F`.
+const PDF_DOC_0X18: [char; 8] = [
+    '\u{02D8}', '\u{02C7}', '\u{02C6}', '\u{02D9}', '\u{02DD}', '\u{02DB}', '\u{02DA}', '\u{02DC}',
+];
+
+/// Characters of PDFDocEncoding in the range `0x80..=0xA0`. `0x9F` is
+/// undefined.
+const PDF_DOC_0X80: [Option<char>; 33] = [
+    Some('\u{2022}'),
+    Some('\u{2020}'),
+    Some('\u{2021}'),
+    Some('\u{2026}'),
+    Some('\u{2014}'),
+    Some('\u{2013}'),
+    Some('\u{0192}'),
+    Some('\u{2044}'),
+    Some('\u{2039}'),
+    Some('\u{203A}'),
+    Some('\u{2212}'),
+    Some('\u{2030}'),
+    Some('\u{201E}'),
+    Some('\u{201C}'),
+    Some('\u{201D}'),
+    Some('\u{2018}'),
+    Some('\u{2019}'),
+    Some('\u{201A}'),
+    Some('\u{2122}'),
+    Some('\u{FB01}'),
+    Some('\u{FB02}'),
+    Some('\u{0141}'),
+    Some('\u{0152}'),
+    Some('\u{0160}'),
+    Some('\u{0178}'),
+    Some('\u{017D}'),
+    Some('\u{0131}'),
+    Some('\u{0142}'),
+    Some('\u{0153}'),
+    Some('\u{0161}'),
+    Some('\u{017E}'),
+    None,
+    Some('\u{20AC}'),
+];
+
+/// Map a PDFDocEncoding byte to its unicode character.
+pub(crate) fn pdf_doc_char(b: u8) -> Option<char> {
+    match b {
+        b'\t' | b'\n' | b'\r' => Some(char::from(b)),
+        0x18..=0x1F => Some(PDF_DOC_0X18[usize::from(b - 0x18)]),
+        0x20..=0x7E => Some(char::from(b)),
+        0x80..=0xA0 => PDF_DOC_0X80[usize::from(b - 0x80)],
+        0xAD => None,
+        0xA1..=0xFF => Some(char::from(b)),
+        _ => None,
+    }
+}
+
+/// The UTF-16BE code units of `bytes`. An odd last byte is padded with 0.
+pub(crate) fn utf16_units(bytes: &[u8]) -> impl Iterator<Item = u16> + '_ {
+    bytes
+        .chunks(2)
+        .map(|c| u16::from_be_bytes([c[0], c.get(1).copied().unwrap_or(0)]))
+}
+
+/// Decode a text string of a document with the given `version`. Strings
+/// starting with the UTF-8 byte order mark are only decoded as UTF-8 since
+/// [UTF8_VERSION].
+pub(crate) fn decode_text(bytes: &[u8], version: (u8, u8)) -> Result<String, TextDecodeError> {
+    if let Some(utf16) = bytes.strip_prefix(UTF16_BOM) {
+        if utf16.len() % 2 != 0 {
+            return Err(TextDecodeError::InvalidUtf16);
+        }
+        char::decode_utf16(utf16_units(utf16))
+            .collect::<Result<String, _>>()
+            .map_err(|_| TextDecodeError::InvalidUtf16)
+    } else if let Some(utf8) = bytes.strip_prefix(UTF8_BOM).filter(|_| version >= UTF8_VERSION) {
+        String::from_utf8(utf8.to_vec()).map_err(|_| TextDecodeError::InvalidUtf8)
+    } else {
+        bytes
+            .iter()
+            .map(|&b| pdf_doc_char(b).ok_or(TextDecodeError::UndefinedPdfDocChar(b)))
+            .collect()
+    }
+}
+
+/// Like [decode_text] but replaces invalid characters with U+FFFD.
+pub(crate) fn decode_text_lossy(bytes: &[u8], version: (u8, u8)) -> String {
+    if let Some(utf16) = bytes.strip_prefix(UTF16_BOM) {
+        char::decode_utf16(utf16_units(utf16))
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    } else if let Some(utf8) = bytes.strip_prefix(UTF8_BOM).filter(|_| version >= UTF8_VERSION) {
+        String::from_utf8_lossy(utf8).into_owned()
+    } else {
+        bytes
+            .iter()
+            .map(|&b| pdf_doc_char(b).unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    }
+}
+
+/// The text of a string or hex string object of a document with the given
+/// `version`, with invalid characters replaced with U+FFFD.
+pub(crate) fn text_lossy(obj: &Object, version: (u8, u8)) -> Option<String> {
+    match obj {
+        Object::String(s) => Some(decode_text_lossy(&s.unescaped(), version)),
+        Object::HexString(b) => Some(decode_text_lossy(b, version)),
+        _ => None,
+    }
+}
+
+/// The text of the entry `key` of `dict`. Other objects than strings are
+/// ignored with a warning that names the dictionary with `owner`.
+pub(crate) fn text_entry(source: &dyn ObjectSource, dict: &Dictionary, key: &[u8], owner: &str) -> Option<String> {
+    let obj = source.dict_get_resolved(dict, key)?;
+    let text = text_lossy(obj, source.effective_version());
+    if text.is_none() {
+        log::warn!(
+            "Expected string for {} entry `{}`, got {}",
+            owner,
+            String::from_utf8_lossy(key),
+            obj
+        );
+    }
+    text
+}
+
+/// Encode a text string.
+///
+/// Text that only consists of printable ASCII characters is encoded as it
+/// is, everything else as UTF-16BE with byte order mark.
+pub(crate) fn encode_text(text: &str) -> Vec<u8> {
+    if text
+        .bytes()
+        .all(|b| b == b'\t' || b == b'\n' || b == b'\r' || (0x20..=0x7E).contains(&b))
+    {
+        text.as_bytes().to_vec()
+    } else {
+        UTF16_BOM
+            .iter()
+            .copied()
+            .chain(text.encode_utf16().flat_map(u16::to_be_bytes))
+            .collect()
+    }
+}
+
+/// Resolve the escape sequences of a literal string.
+///
+/// Literal strings are stored as they appear in the file, e.g. `\(` or `\101`
+/// are not yet replaced.
+pub(crate) fn unescape_literal(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => {
+                i += 1;
+                match bytes.get(i) {
+                    Some(b'n') => out.push(b'\n'),
+                    Some(b'r') => out.push(b'\r'),
+                    Some(b't') => out.push(b'\t'),
+                    Some(b'b') => out.push(0x08),
+                    Some(b'f') => out.push(0x0C),
+                    // line continuation
+                    Some(b'\r') if bytes.get(i + 1) == Some(&b'\n') => i += 1,
+                    Some(b'\r' | b'\n') => {}
+                    Some(b'0'..=b'7') => {
+                        let digits = bytes[i..]
+                            .iter()
+                            .take(3)
+                            .take_while(|b| (b'0'..=b'7').contains(*b))
+                            .count();
+                        let value = bytes[i..i + digits]
+                            .iter()
+                            .fold(0_u16, |acc, d| (acc << 3) + u16::from(d - b'0'));
+                        out.push(value as u8);
+                        i += digits - 1;
+                    }
+                    // `\\`, `\(`, `\)` and unknown escapes map to the escaped char.
+                    Some(&other) => out.push(other),
+                    None => {}
+                }
+            }
+            // end-of-line markers inside a literal string are always a single `\n`.
+            b'\r' => {
+                if bytes.get(i + 1) == Some(&b'\n') {
+                    i += 1;
+                }
+                out.push(b'\n');
+            }
+            other => out.push(other),
+        }
+        i += 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf::{tests::parsed_pdf, Reference};
+
+    use super::*;
+
+    #[test]
+    fn decode_utf16() {
+        let bytes = hex::decode("FEFF004A006F0073002000760061006E002000640065006E0020004F0065007600650072").unwrap();
+        assert_eq!(decode_text_lossy(&bytes, (1, 7)), "Jos van den Oever");
+    }
+
+    #[test]
+    fn decode_utf8() {
+        assert_eq!(decode_text(b"\xEF\xBB\xBFCaf\xC3\xA9", (2, 0)), Ok("Café".to_string()));
+        assert_eq!(
+            decode_text(b"\xEF\xBB\xBF\xC3", (2, 0)),
+            Err(TextDecodeError::InvalidUtf8)
+        );
+        // PDFDocEncoding before PDF 2.0
+        assert_eq!(
+            decode_text(b"\xEF\xBB\xBFa", (1, 7)),
+            Ok("\u{EF}\u{BB}\u{BF}a".to_string())
+        );
+        assert_eq!(decode_text_lossy(b"\xEF\xBB\xBF\xC3", (2, 0)), "\u{FFFD}");
+        assert_eq!(decode_text_lossy(b"\xEF\xBB\xBFa", (1, 7)), "\u{EF}\u{BB}\u{BF}a");
+    }
+
+    #[test]
+    fn decode_invalid() {
+        assert_eq!(
+            decode_text(b"\xFE\xFF\xD8\x00", (1, 7)),
+            Err(TextDecodeError::InvalidUtf16)
+        );
+        assert_eq!(decode_text(b"\xFE\xFF\x00", (1, 7)), Err(TextDecodeError::InvalidUtf16));
+        assert_eq!(
+            decode_text(b"a\x9fb", (1, 7)),
+            Err(TextDecodeError::UndefinedPdfDocChar(0x9f))
+        );
+    }
+
+    #[test]
+    fn encode() {
+        assert_eq!(encode_text("Hello (World)"), b"Hello (World)");
+        assert_eq!(encode_text("Café"), b"\xFE\xFF\x00C\x00a\x00f\x00\xE9");
+        assert_eq!(
+            decode_text(&encode_text("Grüße 😀"), (1, 7)),
+            Ok("Grüße 😀".to_string())
+        );
+    }
+
+    #[test]
+    fn decode_pdf_doc() {
+        assert_eq!(decode_text(b"Caf\xe9", (1, 7)), Ok("Café".to_string()));
+        assert_eq!(decode_text_lossy(b"Caf\xe9 \x80 \xa0", (1, 7)), "Café • €");
+        assert_eq!(decode_text_lossy(b"\x9f", (1, 7)), "\u{FFFD}");
+    }
+
+    #[test]
+    fn text_entry_version() {
+        let pdf = |catalog: &[u8]| parsed_pdf(Reference::new(1, 0), &[catalog]);
+        let dict = Dictionary::from([(
+            b"T".to_vec().into(),
+            Object::HexString(b"\xEF\xBB\xBFa".to_vec().into()),
+        )]);
+
+        let pdf_1_7 = pdf(b"1 0 obj <</Type /Catalog>> endobj");
+        assert_eq!(
+            text_entry(&pdf_1_7, &dict, b"T", "test").as_deref(),
+            Some("\u{EF}\u{BB}\u{BF}a")
+        );
+        let pdf_2_0 = pdf(b"1 0 obj <</Type /Catalog /Version /2.0>> endobj");
+        assert_eq!(text_entry(&pdf_2_0, &dict, b"T", "test").as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn unescape() {
+        assert_eq!(unescape_literal(br"a\(b\)c\\"), b"a(b)c\\");
+        assert_eq!(unescape_literal(br"\376\377\0\101"), b"\xFE\xFF\x00A");
+        assert_eq!(unescape_literal(b"line\\\ncontinued"), b"linecontinued");
+        assert_eq!(unescape_literal(b"a\r\nb"), b"a\nb");
+        assert_eq!(unescape_literal(br"\n\t"), b"\n\t");
+    }
+}
@@ -1,11 +1,14 @@
-use super::{Bytes, Dictionary, Object, Reference};
+use super::{
+    object::{integer, IntegerOutOfRange},
+    Bytes, DictExt, Dictionary, Object, Reference,
+};
 
 pub const TRAILER: &[u8] = b"trailer";
 pub const K_SIZE: &[u8] = b"Size";
 pub const K_PREVIOUS: &[u8] = b"Prev";
 pub const K_ENCRYPT: &[u8] = b"Encrypt";
 pub const K_ROOT: &[u8] = b"Root";
-pub const K_INFO: &[u8] = b"info";
+pub const K_INFO: &[u8] = b"Info";
 pub const K_ID: &[u8] = b"ID";
 pub const K_X_REF_STM: &[u8] = b"XRefStm";
 
@@ -23,6 +26,7 @@ pub enum TrailerError {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Trailer {
     /// Highest object number used in the PDF document
     pub size: usize,
@@ -33,8 +37,9 @@ pub struct Trailer {
     /// Reference to the root object.
     pub root: Reference,
 
-    /// Dictionary containing information for decryption.
-    pub encrypt: Option<Dictionary>,
+    /// Dictionary containing information for decryption or a reference to
+    /// it.
+    pub encrypt: Option<Object>,
 
     /// Information for this document.
     pub info: Option<Reference>,
@@ -49,25 +54,22 @@ pub struct Trailer {
     pub x_ref_stm: Option<usize>,
 }
 
-impl From<Trailer> for Dictionary {
-    fn from(trailer: Trailer) -> Self {
+impl TryFrom<Trailer> for Dictionary {
+    type Error = IntegerOutOfRange;
+
+    /// Fails if `Size` or `Prev` are too large for an integer.
+    fn try_from(trailer: Trailer) -> Result<Self, Self::Error> {
         // we now that the trailer struct has 7 fields.
         let mut dict = Dictionary::with_capacity(7);
-        dict.insert(
-            K_SIZE.to_owned().into(),
-            Object::Integer(trailer.size.try_into().expect("FIXME")),
-        );
+        dict.insert(K_SIZE.to_owned().into(), integer(trailer.size)?);
         if let Some(prev) = trailer.previous {
-            dict.insert(
-                K_PREVIOUS.to_owned().into(),
-                Object::Integer(prev.try_into().expect("FIXME")),
-            );
+            dict.insert(K_PREVIOUS.to_owned().into(), integer(prev)?);
         }
 
         dict.insert(K_ROOT.to_owned().into(), Object::Reference(trailer.root));
 
         if let Some(enc) = trailer.encrypt {
-            dict.insert(K_ENCRYPT.to_owned().into(), Object::Dictionary(enc));
+            dict.insert(K_ENCRYPT.to_owned().into(), enc);
         }
 
         if let Some(info) = trailer.info {
@@ -81,7 +83,7 @@ impl From<Trailer> for Dictionary {
             );
         }
 
-        dict
+        Ok(dict)
     }
 }
 
@@ -91,42 +93,36 @@ impl TryFrom<Dictionary> for Trailer {
     fn try_from(dict: Dictionary) -> Result<Self, Self::Error> {
         Ok(Trailer {
             size: dict
-                .get(K_SIZE)
-                .ok_or(TrailerError::MissingSize)?
-                .integer()
-                .ok_or(TrailerError::InvalidSize)?
+                .get_integer(K_SIZE)
+                .map_err(|e| e.into_either(TrailerError::MissingSize, TrailerError::InvalidSize))?
                 .try_into()
                 .map_err(|_| TrailerError::InvalidSize)?,
 
             previous: dict
-                .get(K_PREVIOUS)
-                .and_then(Object::integer)
+                .get_integer_opt(K_PREVIOUS)
+                .map_err(|_| TrailerError::InvalidPrevious)?
                 .map(TryInto::try_into)
                 .transpose()
                 .map_err(|_| TrailerError::InvalidPrevious)?,
 
-            root: dict
-                .get(K_ROOT)
-                .ok_or(TrailerError::MissingRoot)?
-                .reference()
-                // TODO: don't clone
-                .cloned()
-                .ok_or(TrailerError::InvalidRoot)?,
+            root: *dict
+                .get_reference(K_ROOT)
+                .map_err(|e| e.into_either(TrailerError::MissingRoot, TrailerError::InvalidRoot))?,
 
             // TODO: don't clone
-            encrypt: dict.get(K_ENCRYPT).and_then(|enc| enc.dictionary()).cloned(),
+            encrypt: dict
+                .get(K_ENCRYPT)
+                .filter(|enc| matches!(enc, Object::Dictionary(_) | Object::Reference(_)))
+                .cloned(),
 
-            // TODO: don't clone
             info: dict
-                .get(K_INFO)
-                .map(|o| o.reference().ok_or(TrailerError::InvalidInfo))
-                .transpose()?
-                .cloned(),
+                .get_reference_opt(K_INFO)
+                .map_err(|_| TrailerError::InvalidInfo)?
+                .copied(),
 
             id: dict
-                .get(K_ID)
-                .map(|o| o.array().ok_or(TrailerError::InvalidId))
-                .transpose()?
+                .get_array_opt(K_ID)
+                .map_err(|_| TrailerError::InvalidId)?
                 .map(|a| {
                     if a.len() == 2 {
                         Ok([
@@ -148,12 +144,52 @@ impl TryFrom<Dictionary> for Trailer {
                 .transpose()?,
 
             x_ref_stm: dict
-                .get(K_X_REF_STM)
-                .map(|obj| obj.integer().ok_or(TrailerError::InvalidXRefStm))
-                .transpose()?
+                .get_integer_opt(K_X_REF_STM)
+                .map_err(|_| TrailerError::InvalidXRefStm)?
                 .map(TryInto::try_into)
                 .transpose()
                 .map_err(|_| TrailerError::InvalidXRefStm)?,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::object::object,
+        pdf::{self, Name},
+    };
+
+    use super::*;
+
+    #[test]
+    fn dictionary_round_trip() {
+        // `pdf::Trailer` is the trailer of this module.
+        let _: fn(pdf::Trailer) -> Trailer = |trailer| trailer;
+
+        let (_, obj) =
+            object(b"<</Size 12 /Prev 3456 /Root 1 0 R /Encrypt 7 0 R /Info 2 0 R /ID [<0102> <0304>]>>"[..].into())
+                .unwrap();
+        let dict = obj.dictionary().unwrap().clone();
+        let trailer = Trailer::try_from(dict.clone()).unwrap();
+        assert_eq!(trailer.encrypt, Some(Object::Reference(Reference::new(7, 0))));
+        assert_eq!(trailer.info, Some(Reference::new(2, 0)));
+
+        let encoded = Dictionary::try_from(trailer.clone()).unwrap();
+        assert_eq!(encoded, dict);
+        assert_eq!(Trailer::try_from(encoded), Ok(trailer.clone()));
+
+        // A direct encryption dictionary is kept as it is.
+        let encrypt = Object::Dictionary(Dictionary::from([(
+            "Filter".parse::<Name>().unwrap(),
+            Object::Name("Standard".parse().unwrap()),
+        )]));
+        let direct = Trailer {
+            encrypt: Some(encrypt.clone()),
+            ..trailer
+        };
+        let encoded = Dictionary::try_from(direct.clone()).unwrap();
+        assert_eq!(encoded.get(K_ENCRYPT), Some(&encrypt));
+        assert_eq!(Trailer::try_from(encoded), Ok(direct));
+    }
+}
@@ -0,0 +1,468 @@
+//! Structural checks across objects.
+
+use std::collections::HashSet;
+
+use crate::pdf::{
+    document::{
+        dict_types::{CATALOG, PAGE, PAGES},
+        ImageXObject, K_COUNT, K_KIDS, K_LENGTH, K_PAGES, K_PARENT, K_TYPE,
+    },
+    references::walk,
+    DictExt, Dictionary, Object, RawPdf, Reference,
+};
+
+/// Upper bound for the depth of the page tree. Protects the stack against
+/// degenerated trees.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Readers usually cope with the problem.
+    Warning,
+    /// The document violates the specification in a way that readers might
+    /// not recover from.
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    /// Number of the object with the problem, if any.
+    pub object: Option<usize>,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    fn error(object: impl Into<Option<usize>>, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            object: object.into(),
+            message,
+        }
+    }
+
+    fn warning(object: impl Into<Option<usize>>, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            object: object.into(),
+            message,
+        }
+    }
+}
+
+fn type_name(dict: &Dictionary) -> Option<&[u8]> {
+    dict.get_name_opt(K_TYPE).ok().flatten().map(|t| &t[..])
+}
+
+/// The numbers of all objects in ascending order.
+fn object_numbers(raw_pdf: &RawPdf) -> Vec<usize> {
+    let mut numbers = raw_pdf
+        .sections
+        .iter()
+        .flat_map(|s| s.objects.keys().copied())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    numbers.sort_unstable();
+    numbers
+}
+
+/// Objects must be found at the offset of their xref entry.
+fn check_xref(raw_pdf: &RawPdf, issues: &mut Vec<ValidationIssue>) {
+    for section in &raw_pdf.sections {
+        for entry in section.xref.used_objects() {
+            match section.objects.get(&entry.number).and_then(Object::indirect) {
+                Some(obj) if obj.index as usize != entry.number || obj.generation as usize != entry.generation => {
+                    issues.push(ValidationIssue::error(
+                        entry.number,
+                        format!(
+                            "The xref entry {} {} points to object {} {} at offset {}",
+                            entry.number, entry.generation, obj.index, obj.generation, entry.byte_offset
+                        ),
+                    ))
+                }
+                _ => {}
+            }
+        }
+
+        let highest = section.objects.keys().max().copied().unwrap_or(0);
+        if section.trailer.size <= highest {
+            issues.push(ValidationIssue::error(
+                None,
+                format!("Size {} but object {} is used", section.trailer.size, highest),
+            ));
+        }
+    }
+}
+
+/// References must point to existing objects and stream lengths must match.
+fn check_objects(raw_pdf: &RawPdf, issues: &mut Vec<ValidationIssue>) {
+    for number in object_numbers(raw_pdf) {
+        let Some(obj) = raw_pdf.object(number) else {
+            continue;
+        };
+        walk(obj, &mut Vec::new(), &mut |r, path| {
+            if raw_pdf.lookup_reference(r).is_none() {
+                issues.push(ValidationIssue::warning(
+                    number,
+                    format!(
+                        "Reference to free or missing object {} {} at {:?}",
+                        r.index, r.generation, path
+                    ),
+                ));
+            }
+        });
+
+        let stream = match obj {
+            Object::Indirect(indirect) => indirect.object.stream(),
+            direct => direct.stream(),
+        };
+        if let Some(stream) = stream {
+            let length = raw_pdf
+                .dict_get_resolved(&stream.dictionary, K_LENGTH)
+                .and_then(Object::integer)
+                .and_then(|length| usize::try_from(length).ok());
+            match length {
+                Some(length) if length != stream.data.len() => issues.push(ValidationIssue::warning(
+                    number,
+                    format!(
+                        "Length is {} but the stream contains {} bytes",
+                        length,
+                        stream.data.len()
+                    ),
+                )),
+                Some(_) => {}
+                None => issues.push(ValidationIssue::error(
+                    number,
+                    "Missing or invalid stream length".into(),
+                )),
+            }
+            if let Some(image) = ImageXObject::new_with(raw_pdf, stream) {
+                check_image(number, &image, issues);
+            }
+        }
+    }
+}
+
+/// The size of JPEG images must match the size of their data.
+fn check_image(number: usize, image: &ImageXObject, issues: &mut Vec<ValidationIssue>) {
+    match image.embedded_image_info() {
+        Some(Ok(info)) => {
+            let jpeg = (i32::from(info.width), i32::from(info.height));
+            let declared = (image.width(), image.height());
+            // The height can be defined after the first scan.
+            if declared.0 != Some(jpeg.0) || (jpeg.1 != 0 && declared.1 != Some(jpeg.1)) {
+                issues.push(ValidationIssue::error(
+                    number,
+                    format!(
+                        "The image is {:?}x{:?} but the JPEG data is {}x{}",
+                        declared.0, declared.1, jpeg.0, jpeg.1
+                    ),
+                ));
+            }
+        }
+        Some(Err(err)) => issues.push(ValidationIssue::warning(
+            number,
+            format!("Invalid JPEG data: {:?}", err),
+        )),
+        None => {}
+    }
+}
+
+/// Check the type, count and parent of each page tree node. Returns the
+/// number of leaves.
+fn check_page_tree(
+    raw_pdf: &RawPdf,
+    node: &Reference,
+    parent: Option<&Reference>,
+    depth: usize,
+    visited: &mut HashSet<Reference>,
+    issues: &mut Vec<ValidationIssue>,
+) -> usize {
+    let number = node.index as usize;
+    if depth > MAX_DEPTH {
+        issues.push(ValidationIssue::error(
+            number,
+            format!("Page tree exceeds the maximum depth of {}", MAX_DEPTH),
+        ));
+        return 0;
+    }
+    if !visited.insert(*node) {
+        issues.push(ValidationIssue::error(number, "Page tree node is visited twice".into()));
+        return 0;
+    }
+    let Some(dict) = raw_pdf.lookup_reference(node).and_then(Object::dictionary) else {
+        issues.push(ValidationIssue::error(
+            number,
+            "Page tree node is not a dictionary".into(),
+        ));
+        return 0;
+    };
+
+    let actual_parent = dict.get_reference_opt(K_PARENT).ok().flatten();
+    if actual_parent != parent {
+        issues.push(ValidationIssue::error(
+            number,
+            format!("Parent is {:?} but the node is a kid of {:?}", actual_parent, parent),
+        ));
+    }
+
+    let is_node = match type_name(dict) {
+        Some(PAGES) => true,
+        Some(PAGE) => false,
+        other => {
+            let is_node = dict.contains_key(K_KIDS);
+            issues.push(ValidationIssue::error(
+                number,
+                format!(
+                    "Type of page tree {} is {:?}",
+                    if is_node { "node" } else { "leaf" },
+                    other.map(String::from_utf8_lossy)
+                ),
+            ));
+            is_node
+        }
+    };
+    if !is_node {
+        return 1;
+    }
+
+    let kids = match raw_pdf.dict_get_resolved(dict, K_KIDS).and_then(Object::array) {
+        Some(kids) => kids.iter().filter_map(Object::reference).copied().collect::<Vec<_>>(),
+        None => {
+            issues.push(ValidationIssue::error(number, "Missing or invalid Kids".into()));
+            Vec::new()
+        }
+    };
+    let leaves = kids
+        .iter()
+        .map(|kid| check_page_tree(raw_pdf, kid, Some(node), depth + 1, visited, issues))
+        .sum();
+    match dict.get_integer(K_COUNT) {
+        Ok(count) if count == leaves as i64 => {}
+        count => issues.push(ValidationIssue::error(
+            number,
+            format!("Count is {:?} but the node has {} pages", count.ok(), leaves),
+        )),
+    }
+    leaves
+}
+
+fn check_catalog(raw_pdf: &RawPdf, issues: &mut Vec<ValidationIssue>) {
+    let Some(root) = raw_pdf.sections.first().map(|s| s.trailer.root) else {
+        issues.push(ValidationIssue::error(None, "Document without sections".into()));
+        return;
+    };
+    let Some(catalog) = raw_pdf.lookup_reference(&root).and_then(Object::dictionary) else {
+        issues.push(ValidationIssue::error(
+            root.index as usize,
+            "The catalog is missing or not a dictionary".into(),
+        ));
+        return;
+    };
+    if type_name(catalog) != Some(CATALOG) {
+        issues.push(ValidationIssue::error(
+            root.index as usize,
+            format!(
+                "The type of the catalog is {:?}",
+                type_name(catalog).map(String::from_utf8_lossy)
+            ),
+        ));
+    }
+
+    match catalog.get_reference_opt(K_PAGES) {
+        Ok(Some(pages)) => {
+            check_page_tree(raw_pdf, pages, None, 0, &mut HashSet::new(), issues);
+        }
+        _ => issues.push(ValidationIssue::error(
+            root.index as usize,
+            "The catalog has no page tree".into(),
+        )),
+    }
+}
+
+/// See [RawPdf::validate].
+pub(crate) fn validate(raw_pdf: &RawPdf) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    check_xref(raw_pdf, &mut issues);
+    check_catalog(raw_pdf, &mut issues);
+    check_objects(raw_pdf, &mut issues);
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf::{tests::parsed_pdf, IndirectObject};
+
+    use super::*;
+
+    fn pdf(objects: &[&[u8]]) -> RawPdf {
+        let mut pdf = parsed_pdf(Reference::new(1, 0), objects);
+        pdf.sections[0].trailer.size = 10;
+        pdf
+    }
+
+    const VALID: [&[u8]; 4] = [
+        b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj",
+        b"2 0 obj <</Type /Pages /Kids [3 0 R] /Count 1>> endobj",
+        b"3 0 obj <</Type /Page /Parent 2 0 R /Contents 4 0 R>> endobj",
+        b"4 0 obj <</Length 9>> stream\nBT (x) Tj\nendstream endobj",
+    ];
+
+    #[test]
+    fn valid() {
+        assert_eq!(pdf(&VALID).validate(), vec![]);
+    }
+
+    #[test]
+    fn invalid_page_tree() {
+        let pdf = pdf(&[
+            b"1 0 obj <</Type /Pages /Pages 2 0 R>> endobj",
+            b"2 0 obj <</Type /Pages /Kids [3 0 R 5 0 R] /Count 3>> endobj",
+            VALID[2],
+            VALID[3],
+            b"5 0 obj <</Type /Page /Parent 3 0 R /Annots [9 0 R]>> endobj",
+        ]);
+        let issues = pdf.validate();
+        let objects = issues.iter().map(|i| (i.severity, i.object)).collect::<Vec<_>>();
+        assert_eq!(
+            objects,
+            [
+                (Severity::Error, Some(1)),
+                (Severity::Error, Some(5)),
+                (Severity::Error, Some(2)),
+                (Severity::Warning, Some(5)),
+            ],
+            "{:#?}",
+            issues
+        );
+        assert_eq!(issues[0].message, "The type of the catalog is Some(\"Pages\")");
+        assert_eq!(issues[2].message, "Count is Some(3) but the node has 2 pages");
+        assert_eq!(
+            issues[3].message,
+            "Reference to free or missing object 9 0 at [Key(Name(\"Annots\")), Index(0)]"
+        );
+    }
+
+    #[test]
+    fn invalid_objects() {
+        let mut pdf = pdf(&[
+            VALID[0],
+            VALID[1],
+            VALID[2],
+            b"4 0 obj <</Length 3>> stream\nBT (x) Tj\nendstream endobj",
+        ]);
+        pdf.sections[0].trailer.size = 4;
+        // The xref entry of object 5 points to object 4.
+        pdf.sections[0]
+            .objects
+            .insert(5, Object::Indirect(IndirectObject::new(4, 0, Object::Null)));
+        pdf.sections[0].xref = crate::pdf::Xref::new(vec![crate::pdf::xref::UsedObject {
+            number: 5,
+            byte_offset: 100,
+            generation: 0,
+        }
+        .into()]);
+
+        let issues = pdf.validate();
+        let objects = issues.iter().map(|i| (i.severity, i.object)).collect::<Vec<_>>();
+        assert_eq!(
+            objects,
+            [
+                (Severity::Error, Some(5)),
+                (Severity::Error, None),
+                (Severity::Warning, Some(4))
+            ],
+            "{:#?}",
+            issues
+        );
+        assert_eq!(
+            issues[0].message,
+            "The xref entry 5 0 points to object 4 0 at offset 100"
+        );
+        assert_eq!(issues[1].message, "Size 4 but object 5 is used");
+        // The data is read up to the line break before `endstream`.
+        assert_eq!(issues[2].message, "Length is 3 but the stream contains 9 bytes");
+    }
+
+    #[test]
+    fn negative_length() {
+        let mut pdf = pdf(&VALID);
+        let Some(Object::Indirect(content)) = pdf.sections[0].objects.get_mut(&4) else {
+            panic!("the content stream is missing");
+        };
+        let Object::Stream(stream) = &mut *content.object else {
+            panic!("the content is not a stream");
+        };
+        stream.dictionary.insert(K_LENGTH.to_vec().into(), Object::Integer(-9));
+        assert_eq!(
+            pdf.validate(),
+            vec![ValidationIssue::error(4, "Missing or invalid stream length".into())]
+        );
+    }
+
+    #[test]
+    fn deep_page_tree() {
+        // Each node is the only kid of the previous one.
+        let nodes = (2..MAX_DEPTH + 4)
+            .map(|number| {
+                format!(
+                    "{} 0 obj <</Type /Pages /Parent {} 0 R /Kids [{} 0 R] /Count 0>> endobj",
+                    number,
+                    number - 1,
+                    number + 1
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut objects = vec![VALID[0]];
+        objects.extend(nodes.iter().map(|node| node.as_bytes()));
+        let issues = pdf(&objects).validate();
+        let depth_issues = issues
+            .iter()
+            .filter(|issue| issue.message.starts_with("Page tree exceeds"))
+            .collect::<Vec<_>>();
+        assert_eq!(depth_issues.len(), 1, "{:#?}", issues);
+        // The root of the page tree, object 2, has depth 0.
+        assert_eq!(depth_issues[0].object, Some(MAX_DEPTH + 3));
+    }
+
+    #[test]
+    fn jpeg_size() {
+        use crate::pdf::document::image::tests::JPEG;
+
+        let image = |size: &str, jpeg: &[u8]| {
+            [
+                format!(
+                    "5 0 obj <</Subtype /Image {} /BitsPerComponent 8 /Filter /DCTDecode /Length {}>> stream\n",
+                    size,
+                    jpeg.len()
+                )
+                .as_bytes(),
+                jpeg,
+                b"\nendstream endobj",
+            ]
+            .concat()
+        };
+        let valid = image("/Width 3 /Height 2", JPEG);
+        assert_eq!(
+            pdf(&[VALID[0], VALID[1], VALID[2], VALID[3], &valid]).validate(),
+            vec![]
+        );
+
+        let wrong_size = image("/Width 2 /Height 2", JPEG);
+        let truncated = image("/Width 3 /Height 2", &JPEG[..30]);
+        for (image, expected) in [
+            (
+                wrong_size,
+                ValidationIssue::error(5, "The image is Some(2)xSome(2) but the JPEG data is 3x2".into()),
+            ),
+            (
+                truncated,
+                ValidationIssue::warning(5, "Invalid JPEG data: Truncated".into()),
+            ),
+        ] {
+            let pdf = pdf(&[VALID[0], VALID[1], VALID[2], VALID[3], &image]);
+            assert_eq!(pdf.validate(), vec![expected]);
+        }
+    }
+}
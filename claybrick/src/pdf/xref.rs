@@ -1,4 +1,15 @@
+use fnv::FnvHashSet;
+
+use crate::pdf::{
+    document::{
+        dict_types::{OBJECT_STREAM, XREF},
+        K_TYPE,
+    },
+    Object,
+};
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum XrefKind {
     Table,
     Stream { number: u32, generation: u32 },
@@ -14,6 +25,7 @@ pub enum XrefKind {
 ///
 /// The entries are sorted by the object index.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Xref {
     /// The entries of the cross reference
     pub(crate) entries: Vec<XrefEntry>,
@@ -65,6 +77,73 @@ impl Xref {
     pub fn entries(&self) -> impl Iterator<Item = &XrefEntry> {
         self.entries.iter()
     }
+
+    /// The entry of object `number` in this section.
+    pub fn get(&self, number: usize) -> Option<&XrefEntry> {
+        let index = self.entries.binary_search_by_key(&number, XrefEntry::number).ok()?;
+        self.entries.get(index)
+    }
+
+    /// The free entries linked from the head of the free list, object 0, in
+    /// the order of the list. Stops at a broken link or a cycle, see
+    /// [Xref::validate_free_list].
+    pub fn free_list(&self) -> impl Iterator<Item = &FreeObject> {
+        let mut seen = FnvHashSet::default();
+        std::iter::successors(self.free_head(), move |free| {
+            seen.insert(free.number);
+            match self.get(free.next_free) {
+                Some(XrefEntry::Free(next)) if next.number != 0 && !seen.contains(&next.number) => Some(next),
+                _ => None,
+            }
+        })
+        .skip(1)
+    }
+
+    /// Problems of the free list, e.g. links to entries that are not free.
+    pub fn validate_free_list(&self) -> Vec<FreeListIssue> {
+        let mut issues = Vec::new();
+        let mut linked = FnvHashSet::default();
+        let mut current = self.free_head();
+        if current.is_none() {
+            issues.push(FreeListIssue::MissingHead);
+        }
+        while let Some(free) = current.take() {
+            linked.insert(free.number);
+            let next = free.next_free;
+            if next == 0 {
+                break;
+            }
+            match self.get(next) {
+                Some(XrefEntry::Free(_)) if linked.contains(&next) => issues.push(FreeListIssue::Cycle {
+                    number: free.number,
+                    next,
+                }),
+                Some(XrefEntry::Free(entry)) => current = Some(entry),
+                _ => issues.push(FreeListIssue::BrokenLink {
+                    number: free.number,
+                    next,
+                }),
+            }
+        }
+        issues.extend(
+            self.free_objects()
+                .filter(|free| !linked.contains(&free.number))
+                .map(|free| FreeListIssue::Unlinked { number: free.number }),
+        );
+        issues
+    }
+
+    fn free_head(&self) -> Option<&FreeObject> {
+        match self.get(0) {
+            Some(XrefEntry::Free(head)) => Some(head),
+            _ => None,
+        }
+    }
+
+    /// The highest object number in this section.
+    pub fn highest_index(&self) -> Option<usize> {
+        self.entries.last().map(XrefEntry::number)
+    }
 }
 
 impl std::ops::Deref for Xref {
@@ -81,7 +160,24 @@ impl From<Vec<XrefEntry>> for Xref {
     }
 }
 
+/// A structural problem of the free list of a xref section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FreeListIssue {
+    /// Object 0, the head of the list, is missing or not free.
+    MissingHead,
+    /// The free entry `number` links to `next`, which is not free.
+    BrokenLink { number: usize, next: usize },
+    /// The link from `number` to `next` closes a cycle.
+    Cycle { number: usize, next: usize },
+    /// The free entry `number` can't be reached from the head.
+    Unlinked { number: usize },
+}
+
+/// Free entries with this generation can't be reused.
+pub const MAX_GENERATION: usize = 65535;
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct FreeObject {
     /// Number of this object
     pub number: usize,
@@ -92,6 +188,7 @@ pub struct FreeObject {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct UsedObject {
     /// Number of this object
     pub number: usize,
@@ -103,6 +200,7 @@ pub struct UsedObject {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct UsedCompressedObject {
     /// Number of this object
     pub number: usize,
@@ -113,6 +211,7 @@ pub struct UsedCompressedObject {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Unsupported {
     /// Number of this object
     pub number: usize,
@@ -132,6 +231,7 @@ pub const XREF_USED: usize = 1;
 pub const XREF_COMPRESSED: usize = 2;
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum XrefEntry {
     Free(FreeObject),
     Used(UsedObject),
@@ -151,6 +251,15 @@ impl XrefEntry {
         }
     }
 
+    pub(crate) fn set_number(&mut self, new: usize) {
+        match self {
+            XrefEntry::Free(FreeObject { number, .. }) => *number = new,
+            XrefEntry::Used(UsedObject { number, .. }) => *number = new,
+            XrefEntry::UsedCompressed(UsedCompressedObject { number, .. }) => *number = new,
+            XrefEntry::Unsupported(Unsupported { number, .. }) => *number = new,
+        }
+    }
+
     pub fn number(&self) -> usize {
         match self {
             XrefEntry::Free(FreeObject { number, .. }) => *number,
@@ -179,8 +288,106 @@ impl From<UsedObject> for XrefEntry {
     }
 }
 
+/// Whether the object, or the object of an indirect object, is an object or
+/// xref stream. Writers create them anew instead of copying them.
+pub(crate) fn is_structural_stream(obj: &Object) -> bool {
+    match obj {
+        Object::Indirect(indirect) => is_structural_stream(&indirect.object),
+        obj => obj
+            .stream()
+            .and_then(|s| s.dictionary.get(K_TYPE))
+            .and_then(Object::name)
+            .is_some_and(|t| &t[..] == XREF || &t[..] == OBJECT_STREAM),
+    }
+}
+
 impl From<FreeObject> for XrefEntry {
     fn from(v: FreeObject) -> Self {
         Self::Free(v)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get() {
+        let xref = Xref::new(vec![
+            UsedObject {
+                number: 3,
+                byte_offset: 30,
+                generation: 0,
+            }
+            .into(),
+            FreeObject {
+                number: 0,
+                generation: 65535,
+                next_free: 0,
+            }
+            .into(),
+            UsedCompressedObject {
+                number: 7,
+                containing_object: 3,
+                index: 0,
+            }
+            .into(),
+        ]);
+
+        assert_eq!(xref.get(0).map(XrefEntry::type_num), Some(XREF_FREE));
+        assert_eq!(xref.get(3).map(XrefEntry::type_num), Some(XREF_USED));
+        assert_eq!(xref.get(7).map(XrefEntry::type_num), Some(XREF_COMPRESSED));
+        assert_eq!(xref.get(5), None);
+        assert_eq!(xref.get(8), None);
+        assert_eq!(xref.highest_index(), Some(7));
+        assert_eq!(Xref::new(Vec::new()).highest_index(), None);
+    }
+
+    fn free(number: usize, next_free: usize) -> XrefEntry {
+        FreeObject {
+            number,
+            generation: 1,
+            next_free,
+        }
+        .into()
+    }
+
+    #[test]
+    fn free_list() {
+        let used = UsedObject {
+            number: 2,
+            byte_offset: 20,
+            generation: 0,
+        };
+        let xref = Xref::new(vec![free(0, 3), free(1, 0), used.clone().into(), free(3, 1)]);
+        let numbers: Vec<_> = xref.free_list().map(|free| free.number).collect();
+        assert_eq!(numbers, vec![3, 1]);
+        assert_eq!(xref.validate_free_list(), vec![]);
+
+        // 3 is free but not on the free list.
+        let xref = Xref::new(vec![free(0, 1), free(1, 0), used.clone().into(), free(3, 1)]);
+        let numbers: Vec<_> = xref.free_list().map(|free| free.number).collect();
+        assert_eq!(numbers, vec![1]);
+        assert_eq!(xref.validate_free_list(), vec![FreeListIssue::Unlinked { number: 3 }]);
+
+        let broken = Xref::new(vec![free(0, 2), used.into()]);
+        assert_eq!(broken.free_list().count(), 0);
+        assert_eq!(
+            broken.validate_free_list(),
+            vec![FreeListIssue::BrokenLink { number: 0, next: 2 }]
+        );
+
+        let cycle = Xref::new(vec![free(0, 1), free(1, 2), free(2, 1)]);
+        let numbers: Vec<_> = cycle.free_list().map(|free| free.number).collect();
+        assert_eq!(numbers, vec![1, 2]);
+        assert_eq!(
+            cycle.validate_free_list(),
+            vec![FreeListIssue::Cycle { number: 2, next: 1 }]
+        );
+
+        assert_eq!(
+            Xref::new(vec![free(4, 0)]).validate_free_list(),
+            vec![FreeListIssue::MissingHead, FreeListIssue::Unlinked { number: 4 }]
+        );
+    }
+}
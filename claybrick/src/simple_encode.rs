@@ -3,5 +3,316 @@
 //! The implementation is as simple as possible and will result in an
 //! unoptimized PDF file (i.e using more bytes than necessary).
 
+use crate::{
+    pdf::{IntegerOutOfRange, RawPdf, Reference},
+    writer::{Encoder, Writer},
+};
+
+use object::string::{convert_strings, has_convertible_strings};
+
+pub use object::real::DEFAULT_PRECISION;
+
+#[cfg(feature = "encryption")]
+mod encrypt;
+mod id;
+mod incremental;
 mod object;
-struct SimpleEncoder;
+mod object_stream;
+mod prune;
+mod section;
+mod version;
+mod xref;
+
+pub use crate::pdf::permissions::{EncryptionAlgorithm, EncryptionParams};
+
+pub struct SimpleEncoder;
+
+/// How the cross reference sections are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum XrefStyle {
+    /// A cross reference stream, requires PDF 1.5.
+    #[default]
+    Stream,
+    /// A classic `xref` table followed by a `trailer` dictionary.
+    Table,
+}
+
+/// How the file identifier `/ID` of the trailer is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IdGeneration {
+    /// Write the identifier of the document unchanged, if it has one.
+    Keep,
+    /// Generate an identifier for documents without one. Documents with one
+    /// keep the first member and get a new second member, like the spec
+    /// requires for updated files. Generated members are hashes of the
+    /// written objects, thus writing the same document twice gives the same
+    /// output.
+    #[default]
+    Update,
+    /// Generate a new identifier for both members, which differs on each
+    /// write. Encrypted documents keep the first member, since the
+    /// encryption key depends on it.
+    Always,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EncodeOptions {
+    pub xref: XrefStyle,
+    /// Compress streams without filter with `FlateDecode`. Streams that
+    /// already have a filter are written as they are.
+    pub compress_streams: bool,
+    /// Only write objects that are reachable from the trailer. The objects
+    /// are renumbered and written as a single section.
+    ///
+    /// Ignored for encrypted documents, since the encryption keys depend on
+    /// the object numbers.
+    pub prune_unreferenced: bool,
+    /// The bytes of the comment after the header that marks the file as
+    /// binary, without `%`. The spec requires at least four bytes > 127,
+    /// other bytes fail with [EncodeError::InvalidBinaryIndicator]. Without
+    /// bytes, the comment of the parsed file is kept, documents without
+    /// comment get [BINARY_INDICATOR].
+    pub binary_indicator: Option<&'static [u8]>,
+    /// The version of the written document instead of the version of the
+    /// source. Newer versions than the header of the source are written to
+    /// the `/Version` of the catalog. Xref tables are written instead of
+    /// streams for versions before 1.5.
+    pub version: Option<(u8, u8)>,
+    /// How the `/ID` of the newest trailer is written.
+    pub regenerate_id: IdGeneration,
+    /// Store objects in compressed object streams. Streams, objects with a
+    /// generation other than 0 and the encryption dictionary are written as
+    /// usual. Requires xref streams and is ignored for encrypted documents.
+    pub use_object_streams: bool,
+    /// Write literal strings with bytes outside of printable ASCII as hex
+    /// strings, which survive tools that change line breaks or whitespace.
+    pub strings_as_hex: bool,
+    /// Write literal strings as they are. Otherwise literal strings are
+    /// written as hex strings if that is shorter, e.g. for binary data.
+    /// Hex strings are always kept.
+    pub keep_string_form: bool,
+    /// Sync the file to disk before [write_file](crate::write_file)
+    /// returns. Ignored by other writers.
+    pub sync_file: bool,
+    /// Number of decimal places of real numbers, [DEFAULT_PRECISION] if not
+    /// set. Real numbers are rounded and never written with exponent, e.g.
+    /// `1e-7` needs a precision of 7 to be written as `0.0000001`.
+    pub real_precision: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodeError {
+    /// The document catalog referenced by the trailer doesn't exist. Readers
+    /// can't open a document without catalog.
+    MissingRoot(Reference),
+    /// Objects can't be appended to encrypted documents, they would have to
+    /// be encrypted as well. Encrypted documents can't be encrypted again
+    /// before they are decrypted. Encrypted documents with object streams
+    /// can't be written before they are decrypted, the objects of the
+    /// object streams are only known after decryption.
+    Encrypted,
+    /// The object number can't be used for an object.
+    InvalidObjectNumber(usize),
+    /// The document has no section and thus no trailer with the root.
+    MissingTrailer,
+    /// The number is too large for the PDF integer or xref entry it is
+    /// written to.
+    ValueOutOfRange(usize),
+    /// Encryption requires the `encryption` feature.
+    EncryptionUnsupported,
+    /// The [EncodeOptions::binary_indicator] has less than four bytes or
+    /// bytes < 128, readers wouldn't recognize it.
+    InvalidBinaryIndicator,
+    /// The operating system provides no random bytes for the keys and
+    /// initialization vectors of the encryption, e.g. on
+    /// `wasm32-unknown-unknown` without the `wasm_js` feature of `getrandom`.
+    RandomUnavailable,
+}
+
+impl From<IntegerOutOfRange> for EncodeError {
+    fn from(IntegerOutOfRange(value): IntegerOutOfRange) -> Self {
+        EncodeError::ValueOutOfRange(value)
+    }
+}
+
+/// Marks the file as binary. Consists of bytes > 127.
+pub const BINARY_INDICATOR: &[u8] = b"\xE2\xE3\xCF\xD3";
+
+impl SimpleEncoder {
+    /// Write the complete document.
+    ///
+    /// Each section is written with its own cross reference section, starting
+    /// with the oldest one. Objects from object streams are written as
+    /// regular objects.
+    pub fn write_with(pdf: &RawPdf, writer: &mut dyn Writer, options: EncodeOptions) -> Result<(), EncodeError> {
+        Self::write(pdf, writer, options, None)
+    }
+
+    /// Write the complete document like [SimpleEncoder::write_with] and
+    /// encrypt all strings and streams with the standard security handler.
+    /// A file identifier is generated if the document has none, even with
+    /// [IdGeneration::Keep].
+    ///
+    /// Fails with [EncodeError::EncryptionUnsupported] without the
+    /// `encryption` feature and with [EncodeError::RandomUnavailable] if the
+    /// operating system provides no random bytes.
+    pub fn write_encrypted(
+        pdf: &RawPdf,
+        writer: &mut dyn Writer,
+        options: EncodeOptions,
+        params: &EncryptionParams,
+    ) -> Result<(), EncodeError> {
+        Self::write(pdf, writer, options, Some(params))
+    }
+
+    fn write(
+        pdf: &RawPdf,
+        writer: &mut dyn Writer,
+        mut options: EncodeOptions,
+        encrypt: Option<&EncryptionParams>,
+    ) -> Result<(), EncodeError> {
+        let Some(root) = pdf.sections.first().map(|s| s.trailer.root) else {
+            log::error!("The document has no trailer");
+            return Err(EncodeError::MissingTrailer);
+        };
+        if pdf.lookup_reference(&root).is_none() {
+            log::error!("The catalog {:?} doesn't exist", root);
+            return Err(EncodeError::MissingRoot(root));
+        }
+        #[cfg(not(feature = "encryption"))]
+        if encrypt.is_some() {
+            log::error!("Encryption requires the `encryption` feature");
+            return Err(EncodeError::EncryptionUnsupported);
+        }
+        if encrypt.is_some() && pdf.is_encrypted() {
+            log::error!("The document is already encrypted");
+            return Err(EncodeError::Encrypted);
+        }
+        let object_streams = pdf
+            .sections
+            .iter()
+            .any(|s| s.xref.compressed_objects().next().is_some());
+        if pdf.is_encrypted() && object_streams {
+            log::error!("The object streams of the encrypted document are not expanded");
+            return Err(EncodeError::Encrypted);
+        }
+        if let Some(binary_indicator) = options.binary_indicator {
+            if binary_indicator.len() < 4 || binary_indicator.iter().any(|&b| b < 128) {
+                log::error!("Invalid binary indicator {:?}", binary_indicator);
+                return Err(EncodeError::InvalidBinaryIndicator);
+            }
+        }
+
+        let pruned;
+        let pdf = if options.prune_unreferenced && pdf.is_encrypted() {
+            log::warn!("Unreferenced objects are not removed from encrypted documents");
+            pdf
+        } else if options.prune_unreferenced {
+            pruned = prune::prune(pdf);
+            &pruned
+        } else {
+            pdf
+        };
+
+        let root = pdf.sections[0].trailer.root;
+        // The catalog is written in place of the catalog of `pdf`, unless a
+        // copy of `pdf` is made anyway.
+        let ((major, minor), mut catalog) = version::versioned(pdf, root, &mut options);
+        let converted;
+        let convert = pdf
+            .sections
+            .iter()
+            .flat_map(|section| section.objects.values())
+            .chain(catalog.as_ref().map(|catalog| &catalog.object))
+            .any(|obj| has_convertible_strings(obj, &options));
+        let pdf = match convert {
+            true => {
+                let mut copy = pdf.clone();
+                if let Some(catalog) = catalog.take() {
+                    catalog.apply(&mut copy);
+                }
+                copy.sections
+                    .iter_mut()
+                    .flat_map(|section| section.objects.values_mut())
+                    .for_each(|obj| convert_strings(obj, &options));
+                converted = copy;
+                &converted
+            }
+            false => pdf,
+        };
+        writer.write(format!("%PDF-{}.{}\n", major, minor).as_bytes());
+        let binary_indicator = options
+            .binary_indicator
+            .or(pdf.binary_indicator())
+            .unwrap_or(BINARY_INDICATOR);
+        writer.write(b"%");
+        writer.write(binary_indicator);
+        writer.write(b"\n");
+
+        let id = id::file_id(pdf, options.regenerate_id);
+        // The file key of encrypted documents depends on the identifier.
+        let id = match encrypt {
+            Some(_) => id.or_else(|| id::file_id(pdf, IdGeneration::Update)),
+            None => id,
+        };
+        #[cfg(feature = "encryption")]
+        let encrypted;
+        #[cfg(feature = "encryption")]
+        let pdf = match encrypt {
+            Some(params) => {
+                let first = id.as_ref().map(|[first, _]| &first[..]).unwrap_or_default();
+                encrypted = encrypt::encrypted(pdf, catalog.take(), params, first, options.compress_streams)?;
+                options.compress_streams = false;
+                &encrypted
+            }
+            None => pdf,
+        };
+
+        if options.use_object_streams && options.xref == XrefStyle::Table {
+            log::warn!("Object streams require xref streams, writing objects uncompressed");
+            options.use_object_streams = false;
+        }
+        if options.use_object_streams && pdf.is_encrypted() {
+            log::warn!("Objects of encrypted documents are not stored in object streams");
+            options.use_object_streams = false;
+        }
+
+        let mut previous = None;
+        // The object streams of older sections get numbers that are not used by
+        // newer sections.
+        let mut size = match options.use_object_streams {
+            true => pdf
+                .sections
+                .iter()
+                .flat_map(|section| {
+                    let freed = section.xref.free_objects().map(|free| free.number);
+                    section.objects.keys().copied().chain(freed)
+                })
+                .max()
+                .map_or(1, |number| number + 1),
+            false => 1,
+        };
+        for (index, section) in pdf.sections.iter().enumerate().rev() {
+            // Only the newest trailer gets the new identifier.
+            let id = id.as_ref().filter(|_| index == 0);
+            let replaced = catalog
+                .as_ref()
+                .filter(|catalog| catalog.section == index)
+                .map(|catalog| (catalog.number, &catalog.object));
+            previous = Some(section::write_section(
+                section, writer, options, previous, &mut size, id, replaced,
+            )?);
+        }
+        Ok(())
+    }
+}
+
+pub(crate) use incremental::write_update;
+pub(crate) use object::stream::compress;
+
+impl Encoder<RawPdf> for SimpleEncoder {
+    /// See [SimpleEncoder::write_with].
+    fn write_to(pdf: &RawPdf, writer: &mut dyn Writer) -> Result<(), EncodeError> {
+        Self::write_with(pdf, writer, EncodeOptions::default())
+    }
+}
@@ -0,0 +1,201 @@
+//! Encryption of written documents with the standard security handler.
+
+use crate::pdf::{
+    permissions::EncryptionParams, security::StandardSecurityHandler, xref::is_structural_stream, IndirectObject,
+    Object, RawPdf, Reference,
+};
+
+use super::{object::stream::compress, version::VersionedCatalog, EncodeError};
+
+/// A copy of `pdf` with encrypted strings and streams. The encryption
+/// dictionary is added to the oldest section and referenced by all trailers.
+///
+/// `catalog` replaces the catalog of `pdf`. `id` is the first member of the
+/// file identifier the newest trailer gets. Streams are compressed before
+/// they are encrypted if `compress_streams` is set, since encrypted data
+/// doesn't compress.
+pub(super) fn encrypted(
+    pdf: &RawPdf,
+    catalog: Option<VersionedCatalog>,
+    params: &EncryptionParams,
+    id: &[u8],
+    compress_streams: bool,
+) -> Result<RawPdf, EncodeError> {
+    let (handler, key, dictionary) = StandardSecurityHandler::new_for_writing(params, id)?;
+    let mut pdf = pdf.clone();
+    if let Some(catalog) = catalog {
+        catalog.apply(&mut pdf);
+    }
+    let number = pdf
+        .sections
+        .iter()
+        .flat_map(|section| section.objects.keys())
+        .max()
+        .map_or(1, |number| number + 1);
+    let object_number = |number: usize| u32::try_from(number).map_err(|_| EncodeError::ValueOutOfRange(number));
+
+    for section in &mut pdf.sections {
+        for (number, obj) in section.objects.iter_mut() {
+            if is_structural_stream(obj) {
+                continue;
+            }
+            let mut indirect = match std::mem::replace(obj, Object::Null) {
+                Object::Indirect(indirect) => indirect,
+                // Objects from object streams are written as regular objects.
+                direct => IndirectObject::new(object_number(*number)?, 0, direct),
+            };
+            if let Some(compressed) = indirect.object.stream().filter(|_| compress_streams).and_then(compress) {
+                *indirect.object = Object::Stream(compressed);
+            }
+            handler.encrypt_object(&key, &mut indirect)?;
+            *obj = Object::Indirect(indirect);
+        }
+        section.trailer.encrypt = Some(Object::Reference(Reference::new(object_number(number)?, 0)));
+    }
+    if let Some(oldest) = pdf.sections.last_mut() {
+        oldest.objects.insert(
+            number,
+            Object::Indirect(IndirectObject::new(
+                object_number(number)?,
+                0,
+                Object::Dictionary(dictionary),
+            )),
+        );
+    }
+    Ok(pdf)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        pdf::{security::EncryptionAlgorithm, tests::parsed_pdf, DecryptError},
+        simple_encode::{EncodeError, EncodeOptions, SimpleEncoder},
+    };
+
+    use super::*;
+
+    const CONTENT: &[u8] = b"BT (Hello) Tj ET";
+    const XMP: &[u8] = b"<x:xmpmeta/>";
+
+    fn document() -> RawPdf {
+        parsed_pdf(
+            Reference::new(1, 0),
+            &[
+                b"1 0 obj <</Type /Catalog /Pages 2 0 R /Metadata 4 0 R>> endobj",
+                b"2 0 obj <</Type /Pages /Kids [] /Count 0 /Secret (Hidden)>> endobj",
+                b"3 0 obj <</Length 16>> stream\nBT (Hello) Tj ET\nendstream endobj",
+                b"4 0 obj <</Type /Metadata /Subtype /XML /Length 12>> stream\n<x:xmpmeta/>\nendstream endobj",
+            ],
+        )
+    }
+
+    fn write(pdf: &RawPdf, params: EncryptionParams, compress_streams: bool) -> Vec<u8> {
+        let mut out = Vec::new();
+        let options = EncodeOptions {
+            compress_streams,
+            ..Default::default()
+        };
+        SimpleEncoder::write_encrypted(pdf, &mut out, options, &params).unwrap();
+        out
+    }
+
+    fn contains(data: &[u8], part: &[u8]) -> bool {
+        data.windows(part.len()).any(|w| w == part)
+    }
+
+    fn assert_decrypted(pdf: &RawPdf) {
+        assert!(!pdf.is_encrypted());
+        let secret = pdf
+            .dereference(&Reference::new(2, 0))
+            .and_then(Object::dictionary)
+            .and_then(|pages| pages.get(&b"Secret"[..]));
+        assert_eq!(secret, Some(&Object::HexString(b"Hidden".to_vec().into())));
+        let content = pdf.dereference(&Reference::new(3, 0)).and_then(Object::stream).unwrap();
+        assert_eq!(&content.filtered_data().unwrap()[..], CONTENT);
+    }
+
+    #[test]
+    fn encrypt_and_decrypt() {
+        let algorithms = [
+            EncryptionAlgorithm::Rc4_128,
+            EncryptionAlgorithm::Aes128,
+            EncryptionAlgorithm::Aes256,
+        ];
+        for (algorithm, compress_streams) in algorithms.into_iter().flat_map(|a| [(a, false), (a, true)]) {
+            let params = EncryptionParams {
+                user_password: b"user".to_vec(),
+                owner_password: b"owner".to_vec(),
+                permissions: 0b100,
+                algorithm,
+                ..Default::default()
+            };
+            let out = write(&document(), params, compress_streams);
+            assert!(!contains(&out, CONTENT) && !contains(&out, b"Hidden") && !contains(&out, XMP));
+
+            let written = crate::read_bytes(out).unwrap();
+            assert!(written.is_encrypted());
+            let permissions = written.permissions().unwrap();
+            assert!(permissions.can_print && !permissions.can_copy && !permissions.can_modify);
+            assert!(written.sections[0].trailer.id.is_some());
+            for password in [&b""[..], b"wrong"] {
+                assert_eq!(
+                    written.clone().decrypt(password),
+                    Err(DecryptError::InvalidPassword),
+                    "{:?}",
+                    algorithm
+                );
+            }
+            for password in [&b"user"[..], b"owner"] {
+                let mut decrypted = written.clone();
+                decrypted.decrypt(password).unwrap();
+                assert_decrypted(&decrypted);
+            }
+        }
+    }
+
+    #[test]
+    fn unencrypted_metadata() {
+        for algorithm in [EncryptionAlgorithm::Rc4_128, EncryptionAlgorithm::Aes256] {
+            let params = EncryptionParams {
+                algorithm,
+                encrypt_metadata: false,
+                ..Default::default()
+            };
+            let out = write(&document(), params, false);
+            assert!(contains(&out, XMP) && !contains(&out, CONTENT));
+            let mut written = crate::read_bytes(out).unwrap();
+            written.decrypt(b"").unwrap();
+            assert_decrypted(&written);
+            let metadata = written.dereference(&Reference::new(4, 0)).and_then(Object::stream);
+            assert_eq!(&metadata.unwrap().data[..], XMP);
+        }
+    }
+
+    #[test]
+    fn versioned_catalog() {
+        let mut pdf = document();
+        pdf.version = (1, 4);
+        let options = EncodeOptions {
+            version: Some((2, 0)),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        SimpleEncoder::write_encrypted(&pdf, &mut out, options, &EncryptionParams::default()).unwrap();
+        let mut written = crate::read_bytes(out).unwrap();
+        written.decrypt(b"").unwrap();
+        assert_eq!(written.effective_version(), (2, 0));
+        assert_decrypted(&written);
+    }
+
+    #[test]
+    fn encrypt_encrypted() {
+        let written = crate::read_bytes(write(&document(), EncryptionParams::default(), false)).unwrap();
+        let params = EncryptionParams::default();
+        assert_eq!(
+            SimpleEncoder::write_encrypted(&written, &mut Vec::new(), EncodeOptions::default(), &params),
+            Err(EncodeError::Encrypted)
+        );
+        // encrypted documents can be written as they are
+        SimpleEncoder::write_with(&written, &mut Vec::new(), EncodeOptions::default()).unwrap();
+    }
+}
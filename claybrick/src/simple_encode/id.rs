@@ -0,0 +1,212 @@
+//! The file identifier `/ID` of the trailer.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{
+    pdf::{xref::is_structural_stream, Bytes, Object, RawPdf},
+    simple_encode::SimpleEncoder,
+    writer::{Encoder, Writer},
+};
+
+use super::IdGeneration;
+
+/// Distinguishes identifiers that are generated at the same time.
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// MD5 like the spec suggests. Without the `encryption` feature, two FNV
+/// hashes with different keys are used instead.
+struct IdHasher {
+    #[cfg(feature = "encryption")]
+    md5: md5::Md5,
+    #[cfg(not(feature = "encryption"))]
+    fnv: [fnv::FnvHasher; 2],
+    /// Number of hashed bytes.
+    len: usize,
+}
+
+impl IdHasher {
+    fn new() -> Self {
+        Self {
+            #[cfg(feature = "encryption")]
+            md5: md5::Digest::new(),
+            #[cfg(not(feature = "encryption"))]
+            fnv: [
+                fnv::FnvHasher::default(),
+                fnv::FnvHasher::with_key(0x6c62_272e_07bb_0142),
+            ],
+            len: 0,
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.len += data.len();
+        #[cfg(feature = "encryption")]
+        md5::Digest::update(&mut self.md5, data);
+        #[cfg(not(feature = "encryption"))]
+        self.fnv.iter_mut().for_each(|fnv| std::hash::Hasher::write(fnv, data));
+    }
+
+    fn finish(self) -> Vec<u8> {
+        #[cfg(feature = "encryption")]
+        return md5::Digest::finalize(self.md5).to_vec();
+        #[cfg(not(feature = "encryption"))]
+        return self
+            .fnv
+            .iter()
+            .flat_map(|fnv| std::hash::Hasher::finish(fnv).to_be_bytes())
+            .collect();
+    }
+}
+
+/// Objects are hashed in their written form without copying them.
+impl Writer for IdHasher {
+    fn write(&mut self, buf: &[u8]) {
+        self.update(buf);
+    }
+
+    fn position(&self) -> usize {
+        self.len
+    }
+}
+
+/// An identifier from the version and the written objects of `pdf`, thus
+/// writing the same document gives the same identifier. `unique` adds a
+/// counter and the current time, which makes each identifier different.
+fn generate(pdf: &RawPdf, unique: bool) -> Bytes {
+    let mut hasher = IdHasher::new();
+    if unique {
+        hasher.update(&COUNTER.fetch_add(1, Ordering::Relaxed).to_le_bytes());
+        // There is no clock on `wasm32-unknown-unknown`.
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        if let Ok(time) = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+            hasher.update(&time.as_nanos().to_le_bytes());
+        }
+    }
+    hasher.update(&[pdf.version.0, pdf.version.1]);
+    // Object and xref streams are created anew by the writer.
+    for (number, generation, obj) in pdf.objects().filter(|(.., obj)| !is_structural_stream(obj)) {
+        hasher.update(&number.to_le_bytes());
+        hasher.update(&generation.to_le_bytes());
+        let obj = match obj {
+            Object::Indirect(indirect) => &indirect.object,
+            obj => obj,
+        };
+        // Objects that can't be written fail when the document is written.
+        let _ = SimpleEncoder::write_to(obj, &mut hasher);
+    }
+    hasher.finish().into()
+}
+
+/// The identifier for the newest trailer of `pdf` when it's written.
+pub(super) fn file_id(pdf: &RawPdf, generation: IdGeneration) -> Option<[Bytes; 2]> {
+    let current = pdf.sections.first().and_then(|section| section.trailer.id.clone());
+    match (generation, current) {
+        (IdGeneration::Keep, current) => current,
+        (IdGeneration::Always, Some([first, _])) if pdf.is_encrypted() => {
+            log::warn!("The first identifier of encrypted documents is kept");
+            Some([first, generate(pdf, true)])
+        }
+        (IdGeneration::Always, _) => {
+            let id = generate(pdf, true);
+            Some([id.clone(), id])
+        }
+        (IdGeneration::Update, None) => {
+            let id = generate(pdf, false);
+            Some([id.clone(), id])
+        }
+        (IdGeneration::Update, Some([first, _])) => Some([first, generate(pdf, false)]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        build::PdfBuilder,
+        simple_encode::{EncodeOptions, SimpleEncoder},
+    };
+
+    use super::*;
+
+    fn write(pdf: &RawPdf, regenerate_id: IdGeneration) -> RawPdf {
+        let mut out = Vec::new();
+        let options = EncodeOptions {
+            regenerate_id,
+            ..Default::default()
+        };
+        SimpleEncoder::write_with(pdf, &mut out, options).unwrap();
+        crate::read_bytes(out).unwrap()
+    }
+
+    fn id(pdf: &RawPdf) -> Option<[Bytes; 2]> {
+        pdf.sections[0].trailer.id.clone()
+    }
+
+    #[test]
+    fn generate_id() {
+        let mut builder = PdfBuilder::new();
+        builder.add_page(100.0, 100.0);
+        let pdf = builder.finish().unwrap();
+        assert_eq!(id(&pdf), None);
+        assert_eq!(id(&write(&pdf, IdGeneration::Keep)), None);
+
+        let written = write(&pdf, IdGeneration::Update);
+        let [first, second] = id(&written).unwrap();
+        assert_eq!(first.len(), 16);
+        assert_eq!(first, second);
+
+        // re-encoding keeps the first member, the second changes with the
+        // contents
+        assert_eq!(
+            id(&write(&written, IdGeneration::Update)),
+            Some([first.clone(), second.clone()])
+        );
+        let mut changed = written.clone();
+        changed.sections[0].objects.insert(99, Object::Integer(99));
+        let rewritten = write(&changed, IdGeneration::Update);
+        let [first_again, second_again] = id(&rewritten).unwrap();
+        assert_eq!(first_again, first);
+        assert_ne!(second_again, second);
+        assert_eq!(id(&write(&rewritten, IdGeneration::Keep)), id(&rewritten));
+
+        let [new_first, new_second] = id(&write(&rewritten, IdGeneration::Always)).unwrap();
+        assert_ne!(new_first, first);
+        assert_eq!(new_first, new_second);
+    }
+
+    #[test]
+    fn default_id_is_reproducible() {
+        let mut builder = PdfBuilder::new();
+        builder.add_page(100.0, 100.0);
+        let pdf = builder.finish().unwrap();
+        assert_eq!(id(&pdf), None);
+
+        let encode = || {
+            let mut out = Vec::new();
+            SimpleEncoder::write_with(&pdf, &mut out, EncodeOptions::default()).unwrap();
+            out
+        };
+        let out = encode();
+        assert_eq!(out, encode());
+        assert!(id(&crate::read_bytes(out).unwrap()).is_some());
+        // Unique identifiers differ on each write.
+        assert_ne!(
+            id(&write(&pdf, IdGeneration::Always)),
+            id(&write(&pdf, IdGeneration::Always))
+        );
+    }
+
+    #[test]
+    fn table_trailer() {
+        let mut builder = PdfBuilder::new();
+        builder.add_page(100.0, 100.0);
+        let mut out = Vec::new();
+        let options = EncodeOptions {
+            xref: crate::simple_encode::XrefStyle::Table,
+            regenerate_id: IdGeneration::Update,
+            ..Default::default()
+        };
+        SimpleEncoder::write_with(&builder.finish().unwrap(), &mut out, options).unwrap();
+        assert!(out.windows(9).any(|w| w == b"\ntrailer\n"));
+        assert!(id(&crate::read_bytes(out).unwrap()).is_some());
+    }
+}
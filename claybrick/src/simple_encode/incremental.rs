@@ -0,0 +1,305 @@
+use crate::{
+    pdf::{xref::XrefEntry, IndirectObject, Object, PdfSection, RawPdf, Xref},
+    simple_encode::{
+        id, object::string::convert_strings, section::write_section, version::XREF_STREAM_VERSION, EncodeError,
+        EncodeOptions, XrefStyle,
+    },
+    writer::Writer,
+};
+
+/// Writes to `inner` with positions counted from the header of the original
+/// file, like readers count the offsets of files with junk before the header.
+struct HeaderRelativeWriter<'a> {
+    inner: &'a mut dyn Writer,
+    header_offset: usize,
+}
+
+impl Writer for HeaderRelativeWriter<'_> {
+    fn write(&mut self, buf: &[u8]) {
+        self.inner.write(buf);
+    }
+
+    fn position(&self) -> usize {
+        self.inner.position().saturating_sub(self.header_offset)
+    }
+
+    fn real_precision(&self) -> usize {
+        self.inner.real_precision()
+    }
+}
+
+/// Append `changes` as a new section to a document that was already written.
+///
+/// `pdf` is the parsed document and `startxref` the offset of its newest
+/// xref section. The writer must already contain the original file.
+/// Replaced objects keep their generation, freed numbers get the generation of
+/// the free entry and new objects generation 0. Offsets are counted from the
+/// header of the original file. Xref tables are written instead of streams if
+/// the original is older than PDF 1.5.
+pub(crate) fn write_update(
+    pdf: &RawPdf,
+    startxref: usize,
+    changes: &[(usize, Object)],
+    writer: &mut dyn Writer,
+    mut options: EncodeOptions,
+) -> Result<(), EncodeError> {
+    if pdf.is_encrypted() {
+        log::error!("Can't append unencrypted objects to an encrypted document");
+        return Err(EncodeError::Encrypted);
+    }
+    if let Some((number, _)) = changes.iter().find(|(number, _)| *number == 0) {
+        log::error!("Object {} is the head of the free list", number);
+        return Err(EncodeError::InvalidObjectNumber(*number));
+    }
+
+    let objects = changes
+        .iter()
+        .map(|(number, obj)| {
+            let index = u32::try_from(*number).map_err(|_| {
+                log::error!("Object number {} is too large", number);
+                EncodeError::ValueOutOfRange(*number)
+            })?;
+            let generation = match pdf.lookup(*number) {
+                // Freed numbers are reused with the generation of the free entry, see
+                // `RawPdf::allocate_object_number`.
+                Some((_, XrefEntry::Free(free))) => free.generation as u32,
+                _ => pdf
+                    .sections
+                    .iter()
+                    .find_map(|s| s.objects.get(number))
+                    .and_then(Object::indirect)
+                    .map_or(0, |indirect| indirect.generation),
+            };
+            let mut obj = match obj {
+                Object::Indirect(indirect) => (*indirect.object).clone(),
+                direct => direct.clone(),
+            };
+            convert_strings(&mut obj, &options);
+            Ok((*number, Object::Indirect(IndirectObject::new(index, generation, obj))))
+        })
+        .collect::<Result<_, EncodeError>>()?;
+
+    let mut trailer = pdf.sections[0].trailer.clone();
+    trailer.x_ref_stm = None;
+    let section = PdfSection {
+        objects,
+        trailer,
+        xref: Xref::new(Vec::new()),
+        object_spans: Default::default(),
+        stream_spans: Default::default(),
+    };
+
+    let (major, minor) = pdf.effective_version();
+    if (major, minor) < XREF_STREAM_VERSION && options.xref == XrefStyle::Stream {
+        log::warn!("PDF {}.{} doesn't support xref streams, writing a table", major, minor);
+        options.xref = XrefStyle::Table;
+    }
+
+    // The previous trailer might have a too small size.
+    let mut size = pdf
+        .sections
+        .iter()
+        .flat_map(|s| s.objects.keys().map(|number| number + 1))
+        .fold(pdf.sections[0].trailer.size, usize::max);
+    // The original file might not end with a line break after `%%EOF`.
+    if writer.position() > 0 {
+        writer.write(b"\n");
+    }
+    let id = id::file_id(pdf, options.regenerate_id);
+    let mut writer = HeaderRelativeWriter {
+        inner: writer,
+        header_offset: pdf.header_offset,
+    };
+    write_section(
+        &section,
+        &mut writer,
+        options,
+        Some(startxref),
+        &mut size,
+        id.as_ref(),
+        None,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::{
+            parse_complete,
+            tests::{push_xref_table, with_objects},
+        },
+        pdf::{tests::parsed_pdf, xref::UsedObject, Reference},
+        simple_encode::SimpleEncoder,
+    };
+
+    use super::*;
+
+    fn original() -> Vec<u8> {
+        let pdf = parsed_pdf(
+            Reference::new(1, 0),
+            &[
+                b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj",
+                b"2 0 obj <</Type /Pages /Kids [3 0 R] /Count 1>> endobj",
+                b"3 0 obj <</Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R>> endobj",
+                b"4 0 obj <</Length 9>> stream\nBT (x) Tj\nendstream endobj",
+                b"5 1 obj (old) endobj",
+            ],
+        );
+        let mut out = Vec::new();
+        SimpleEncoder::write_with(
+            &pdf,
+            &mut out,
+            EncodeOptions {
+                xref: XrefStyle::Table,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        out
+    }
+
+    /// Objects 3, 4 and 5 were freed, the free list is 0 -> 4 -> 3 -> 5.
+    fn original_with_free_list() -> Vec<u8> {
+        let (mut out, offsets) = with_objects([
+            &b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj\n"[..],
+            b"2 0 obj <</Type /Pages /Kids [] /Count 0>> endobj\n",
+            b"6 0 obj 42 endobj\n",
+        ]);
+        let entries = [
+            (4, 65535, false),
+            (offsets[0], 0, true),
+            (offsets[1], 0, true),
+            (5, 1, false),
+            (3, 1, false),
+            (0, 2, false),
+            (offsets[2], 0, true),
+        ];
+        push_xref_table(&mut out, &[(0, &entries)], "<</Size 7 /Root 1 0 R>>");
+        out
+    }
+
+    #[test]
+    fn reuse_free_numbers() {
+        let original = original_with_free_list();
+        let mut pdf = crate::read_bytes(original.clone()).unwrap();
+        assert_eq!(pdf.sections[0].xref.validate_free_list(), vec![]);
+
+        let allocated: Vec<_> = (0..5).map(|_| pdf.allocate_object_number().unwrap()).collect();
+        assert_eq!(allocated, vec![(4, 1), (3, 1), (5, 2), (7, 0), (8, 0)]);
+        // The parsed xref section is not changed.
+        assert_eq!(pdf.sections[0].xref.free_list().count(), 3);
+        assert_eq!(pdf.sections[0].trailer.size, 7);
+
+        let changes = [(4, Object::Integer(4)), (7, Object::Integer(7))];
+        let mut out = Vec::new();
+        crate::write_incremental(&original, &changes, &mut out, EncodeOptions::default()).unwrap();
+        let (_, pdf) = parse_complete(out[..].into()).unwrap();
+        assert_eq!(pdf.dereference(&Reference::new(4, 1)), Some(&Object::Integer(4)));
+        assert_eq!(pdf.dereference(&Reference::new(7, 0)), Some(&Object::Integer(7)));
+        // The remaining free numbers are still free.
+        assert!(matches!(pdf.lookup(3), Some((_, XrefEntry::Free(_)))));
+    }
+
+    #[test]
+    fn too_large_number() {
+        let number = u32::MAX as usize + 1;
+        let changes = [(number, Object::Integer(42))];
+        let original = original();
+        let pdf = crate::read_bytes(original.clone()).unwrap();
+        let mut out = original;
+        assert_eq!(
+            write_update(&pdf, 0, &changes, &mut out, EncodeOptions::default()),
+            Err(EncodeError::ValueOutOfRange(number))
+        );
+    }
+
+    #[test]
+    fn append_update() {
+        for xref in [XrefStyle::Table, XrefStyle::Stream] {
+            let original = original();
+            let changes = [(5, Object::String(b"new".to_vec().into())), (6, Object::Integer(42))];
+            let mut out = Vec::new();
+            crate::write_incremental(
+                &original,
+                &changes,
+                &mut out,
+                EncodeOptions {
+                    xref,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            assert_eq!(&out[..original.len()], &original[..]);
+
+            let (_, pdf) = parse_complete(out[..].into()).unwrap();
+            assert_eq!(pdf.sections.len(), 2);
+            assert_eq!(
+                pdf.dereference(&Reference::new(5, 1)),
+                Some(&Object::String(b"new".to_vec().into()))
+            );
+            assert_eq!(pdf.dereference(&Reference::new(6, 0)), Some(&Object::Integer(42)));
+            assert!(pdf.sections[0].trailer.previous.is_some());
+            assert!(pdf.sections[0].trailer.size >= 7);
+            // untouched objects are still found in the old section
+            assert!(pdf.dereference(&Reference::new(4, 0)).is_some());
+            assert_eq!(pdf.page_count(), Ok(1));
+        }
+    }
+
+    #[test]
+    fn table_for_old_versions() {
+        let original = [&b"%PDF-1.4"[..], &original()[8..]].concat();
+        let mut out = Vec::new();
+        crate::write_incremental(
+            &original,
+            &[(6, Object::Integer(42))],
+            &mut out,
+            EncodeOptions::default(),
+        )
+        .unwrap();
+        let update = &out[original.len()..];
+        assert!(update.windows(5).any(|w| w == b"xref\n"));
+        assert!(!update.windows(5).any(|w| w == b"/XRef"));
+
+        let (_, pdf) = parse_complete(out[..].into()).unwrap();
+        assert_eq!(pdf.dereference(&Reference::new(6, 0)), Some(&Object::Integer(42)));
+    }
+
+    #[test]
+    fn junk_before_header() {
+        let junk = b"junk before the header\n";
+        let original = [&junk[..], &original()].concat();
+        for xref in [XrefStyle::Table, XrefStyle::Stream] {
+            let mut out = Vec::new();
+            let options = EncodeOptions {
+                xref,
+                ..Default::default()
+            };
+            crate::write_incremental(&original, &[(6, Object::Integer(42))], &mut out, options).unwrap();
+
+            let pdf = crate::read_bytes(out.clone()).unwrap();
+            assert_eq!(pdf.sections.len(), 2);
+            assert_eq!(pdf.dereference(&Reference::new(6, 0)), Some(&Object::Integer(42)));
+            assert_eq!(pdf.page_count(), Ok(1));
+            // The offsets of the update are counted from the header.
+            for UsedObject {
+                number, byte_offset, ..
+            } in pdf.sections[0].xref.used_objects()
+            {
+                let expected = format!("{} 0 obj", number);
+                assert!(
+                    out[junk.len() + byte_offset..].starts_with(expected.as_bytes()),
+                    "{}",
+                    number
+                );
+            }
+            let (_, startxref) = crate::parse::startxref(out[..].into(), &Default::default()).unwrap();
+            let expected: &[u8] = match xref {
+                XrefStyle::Table => b"xref",
+                XrefStyle::Stream => b"7 0 obj",
+            };
+            assert!(out[junk.len() + startxref..].starts_with(expected), "{:?}", xref);
+        }
+    }
+}
@@ -4,41 +4,158 @@ use crate::{
     writer::{Encoder, Writer},
 };
 
-use super::SimpleEncoder;
+use super::{EncodeError, SimpleEncoder};
 
 pub(crate) mod array;
 pub(crate) mod dictionary;
 pub(crate) mod indirect;
 pub(crate) mod name;
+pub(crate) mod real;
 pub(crate) mod stream;
 pub(crate) mod string;
 
 impl Encoder<Object> for SimpleEncoder {
-    fn write_to(obj: &Object, writer: &mut dyn Writer) {
+    fn write_to(obj: &Object, writer: &mut dyn Writer) -> Result<(), EncodeError> {
         match obj {
-            Object::String(str) => Self::write_to(str, writer),
+            Object::String(str) => Self::write_to(str, writer)?,
             Object::HexString(bytes) => {
                 writer.write(b"<");
                 writer.write(hex::encode(&bytes[..]).as_bytes());
                 writer.write(b">");
             }
-            Object::Float(f) => writer.write(f.to_string().as_bytes()),
+            Object::Float(f) => Self::write_to(f, writer)?,
             Object::Integer(i) => writer.write(i.to_string().as_bytes()),
             Object::Bool(true) => writer.write(TRUE_OBJECT.as_bytes()),
             Object::Bool(false) => writer.write(FALSE_OBJECT.as_bytes()),
-            Object::Name(n) => Self::write_to(n, writer),
-            Object::Array(a) => Self::write_to(a, writer),
-            Object::Dictionary(d) => Self::write_to(d, writer),
-            Object::Stream(s) => Self::write_to(s, writer),
+            Object::Name(n) => Self::write_to(n, writer)?,
+            Object::Array(a) => Self::write_to(a, writer)?,
+            Object::Dictionary(d) => Self::write_to(d, writer)?,
+            Object::Stream(s) => Self::write_to(s, writer)?,
             Object::Null => writer.write(NULL_OBJECT.as_bytes()),
-            Object::Indirect(i) => Self::write_to(i, writer),
+            Object::Indirect(i) => Self::write_to(i, writer)?,
             Object::Reference(r) => {
-                writer.write(b"R");
+                writer.write(r.index.to_string().as_bytes());
                 writer.write(b" ");
                 writer.write(r.generation.to_string().as_bytes());
-                writer.write(b" ");
-                writer.write(r.index.to_string().as_bytes());
+                writer.write(b" R");
             }
         }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::object::object,
+        pdf::{tests::raw_pdf, Array, CbString, Dictionary, IndirectObject, Name, Reference, Stream},
+    };
+
+    use super::*;
+
+    #[test]
+    fn reference() {
+        let reference = Object::Reference(Reference::new(5, 0));
+        let mut out = Vec::new();
+        SimpleEncoder::write_to(&reference, &mut out).unwrap();
+        assert_eq!(out, b"5 0 R");
+        assert_eq!(SimpleEncoder::encoded_len(&reference).unwrap(), out.len());
+    }
+
+    #[test]
+    fn document_round_trip() {
+        let objects = [
+            &b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj"[..],
+            b"2 0 obj <</Type /Pages /Kids [3 0 R] /Count 1>> endobj",
+            b"3 0 obj <</Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R>> endobj",
+            b"4 0 obj <</Length 9>> stream\nBT (x) Tj\nendstream endobj",
+        ]
+        .iter()
+        .map(|o| object(o[..].into()).unwrap().1)
+        .collect::<Vec<_>>();
+
+        let mut out = Vec::new();
+        for obj in &objects {
+            SimpleEncoder::write_to(obj, &mut out).unwrap();
+        }
+
+        let mut input = &out[..];
+        let mut reparsed = Vec::new();
+        while !input.is_empty() {
+            let (remainder, obj) = object(input.into()).unwrap();
+            input = remainder.fragment().trim_ascii_start();
+            reparsed.push(obj);
+        }
+        assert_eq!(objects, reparsed, "encoded: {}", String::from_utf8_lossy(&out));
+
+        let pdf = raw_pdf(
+            Reference::new(1, 0),
+            reparsed.iter().map(|o| o.indirect().unwrap().clone()).collect(),
+        );
+        assert_eq!(pdf.page_count(), Ok(1));
+        assert_eq!(pdf.page(0).unwrap().contents().len(), 1);
+    }
+
+    /// Deterministic xorshift generator for property tests.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> usize {
+            (self.next() % n) as usize
+        }
+
+        fn bytes(&mut self) -> Vec<u8> {
+            (0..self.below(12)).map(|_| self.next() as u8).collect()
+        }
+    }
+
+    fn random_object(rng: &mut Rng, depth: usize) -> Object {
+        let kinds = if depth == 0 { 8 } else { 12 };
+        match rng.below(kinds) {
+            0 => Object::String(CbString::from(rng.bytes())),
+            1 => Object::HexString(rng.bytes().into()),
+            2 => Object::Float(rng.next() as f32 / 1000.0),
+            3 => Object::Integer(rng.next() as i32),
+            4 => Object::Bool(rng.next() & 1 == 0),
+            5 => Object::Name(Name::from(rng.bytes())),
+            6 => Object::Null,
+            7 => Object::Reference(Reference::new(rng.next() as u32, rng.below(3) as u32)),
+            8 => Object::Array(Array::from(
+                (0..rng.below(4))
+                    .map(|_| random_object(rng, depth - 1))
+                    .collect::<Vec<_>>(),
+            )),
+            9 => Object::Dictionary(random_dictionary(rng, depth - 1)),
+            10 => Object::Stream(Stream {
+                dictionary: random_dictionary(rng, depth - 1),
+                data: rng.bytes().into(),
+                provenance: Default::default(),
+            }),
+            _ => Object::Indirect(IndirectObject::new(rng.next() as u32, 0, random_object(rng, depth - 1))),
+        }
+    }
+
+    fn random_dictionary(rng: &mut Rng, depth: usize) -> Dictionary {
+        (0..rng.below(4))
+            .map(|_| (Name::from(rng.bytes()), random_object(rng, depth)))
+            .collect()
+    }
+
+    #[test]
+    fn encoded_len_matches_output() {
+        let mut rng = Rng(0x2545_F491_4F6C_DD1D);
+        for _ in 0..1000 {
+            let obj = random_object(&mut rng, 3);
+            let mut out = Vec::new();
+            SimpleEncoder::write_to(&obj, &mut out).unwrap();
+            assert_eq!(SimpleEncoder::encoded_len(&obj).unwrap(), out.len(), "{:?}", obj);
+        }
     }
 }
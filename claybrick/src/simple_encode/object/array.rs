@@ -1,17 +1,18 @@
 use crate::{pdf::Array, writer::Encoder};
 
-use crate::simple_encode::SimpleEncoder;
+use crate::simple_encode::{EncodeError, SimpleEncoder};
 
 impl Encoder<Array> for SimpleEncoder {
-    fn write_to(array: &Array, writer: &mut dyn crate::writer::Writer) {
+    fn write_to(array: &Array, writer: &mut dyn crate::writer::Writer) -> Result<(), EncodeError> {
         writer.write(b"[");
         for (i, item) in array.iter().enumerate() {
             if i != 0 {
                 writer.write(b" ");
             }
-            Self::write_to(item, writer);
+            Self::write_to(item, writer)?;
         }
         writer.write(b"]");
+        Ok(())
     }
 }
 
@@ -24,11 +25,11 @@ mod tests {
     #[test]
     fn empty_array() {
         let array = Array::from(vec![]);
-        let encoded_len = SimpleEncoder::encoded_len(&array);
+        let encoded_len = SimpleEncoder::encoded_len(&array).unwrap();
         assert_eq!(encoded_len, 2);
 
         let mut out = Vec::new();
-        SimpleEncoder::write_to(&array, &mut out);
+        SimpleEncoder::write_to(&array, &mut out).unwrap();
         let expected = b"[]";
         assert_eq!(expected, &out[..]);
         assert_eq!(encoded_len, out.len())
@@ -37,11 +38,11 @@ mod tests {
     #[test]
     fn array_with_numbers() {
         let array = Array::from(vec![Object::Integer(0), Object::Integer(1), Object::Integer(2)]);
-        let encoded_len = SimpleEncoder::encoded_len(&array);
+        let encoded_len = SimpleEncoder::encoded_len(&array).unwrap();
         assert_eq!(encoded_len, 7);
 
         let mut out = Vec::new();
-        SimpleEncoder::write_to(&array, &mut out);
+        SimpleEncoder::write_to(&array, &mut out).unwrap();
         let expected = b"[0 1 2]";
         assert_eq!(expected, &out[..]);
         assert_eq!(encoded_len, out.len())
@@ -1,39 +1,42 @@
 use crate::{pdf::Dictionary, writer::Encoder};
 
-use crate::simple_encode::SimpleEncoder;
+use crate::simple_encode::{EncodeError, SimpleEncoder};
 
 impl Encoder<Dictionary> for SimpleEncoder {
-    fn write_to(o: &Dictionary, writer: &mut dyn crate::writer::Writer) {
+    /// The entries are written in insertion order, so parsed dictionaries
+    /// keep the key order of the file.
+    fn write_to(o: &Dictionary, writer: &mut dyn crate::writer::Writer) -> Result<(), EncodeError> {
         writer.write(b"<<");
         let mut is_first = true;
-        for (key, value) in o.iter() {
+        for (key, value) in o {
             if !is_first {
                 writer.write(b" ");
             }
-            Self::write_to(key, writer);
+            Self::write_to(key, writer)?;
             writer.write(b" ");
-            Self::write_to(value, writer);
+            Self::write_to(value, writer)?;
             is_first = false
         }
         writer.write(b">>");
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::pdf::Object;
+    use crate::{parse::object::object, pdf::Object};
 
     use super::*;
 
     #[test]
     fn empty_dict() {
         let d = Dictionary::new();
-        let expected_len = SimpleEncoder::encoded_len(&d);
+        let expected_len = SimpleEncoder::encoded_len(&d).unwrap();
         let expected_output = b"<<>>";
         assert_eq!(expected_len, expected_output.len());
 
         let mut out = Vec::new();
-        SimpleEncoder::write_to(&d, &mut out);
+        SimpleEncoder::write_to(&d, &mut out).unwrap();
         assert_eq!(expected_output, &out[..]);
         assert_eq!(out.len(), expected_len);
     }
@@ -44,21 +47,56 @@ mod tests {
         d.insert(b"one".to_vec().into(), Object::Integer(1));
         d.insert(b"two".to_vec().into(), Object::Integer(2));
         d.insert(b"three".to_vec().into(), Object::Integer(3));
+        d.insert(b"Type".to_vec().into(), Object::Name(b"Example".to_vec().into()));
 
-        let expected_len = SimpleEncoder::encoded_len(&d);
-        let expected_output = br"<<\one 1 \two 2 \three 3>>";
+        let expected_len = SimpleEncoder::encoded_len(&d).unwrap();
+        let expected_output = b"<</one 1 /two 2 /three 3 /Type /Example>>";
         assert_eq!(expected_len, expected_output.len());
 
         let mut out = Vec::new();
-        SimpleEncoder::write_to(&d, &mut out);
-        // TODO: The order of the dictionary is not preserved or defined.
-        // assert_eq!(
-        //     expected_output,
-        //     &out[..],
-        //     "expected: {} got: {}",
-        //     String::from_utf8_lossy(expected_output),
-        //     String::from_utf8_lossy(&out[..])
-        // );
+        SimpleEncoder::write_to(&d, &mut out).unwrap();
+        assert_eq!(
+            expected_output,
+            &out[..],
+            "expected: {} got: {}",
+            String::from_utf8_lossy(expected_output),
+            String::from_utf8_lossy(&out[..])
+        );
         assert_eq!(out.len(), expected_len);
     }
+
+    #[test]
+    fn names_round_trip() {
+        let input = b"<</Type /Annot /Subtype /Link /Border [0 0 1] /A <</S /URI>> /Name#20With#23Escapes /Key#28#29>>";
+        let (_, parsed) = object(input[..].into()).unwrap();
+        let dict = parsed.dictionary().unwrap();
+
+        let mut out = Vec::new();
+        SimpleEncoder::write_to(dict, &mut out).unwrap();
+        assert_eq!(out.len(), SimpleEncoder::encoded_len(dict).unwrap());
+
+        let (_, reparsed) = object(out[..].into()).unwrap();
+        assert_eq!(parsed, reparsed, "encoded: {}", String::from_utf8_lossy(&out));
+    }
+
+    #[test]
+    fn key_order_round_trip() {
+        let input = b"<</Subtype /Link /Type /Annot /Rect [0 0 10 10] /A <</URI (x) /S /URI>>>>";
+        let (_, parsed) = object(input[..].into()).unwrap();
+        let mut out = Vec::new();
+        SimpleEncoder::write_to(&parsed, &mut out).unwrap();
+        assert_eq!(&out[..], &input[..], "encoded: {}", String::from_utf8_lossy(&out));
+
+        // Replaced values keep their position, removed keys don't change the
+        // order of the others.
+        let mut dict = parsed.dictionary().unwrap().clone();
+        dict.insert(b"Subtype".to_vec().into(), Object::Name(b"Widget".to_vec().into()));
+        dict.remove(&b"Type"[..]);
+        let mut out = Vec::new();
+        SimpleEncoder::write_to(&dict, &mut out).unwrap();
+        assert_eq!(
+            &out[..],
+            b"<</Subtype /Widget /Rect [0 0 10 10] /A <</URI (x) /S /URI>>>>"
+        );
+    }
 }
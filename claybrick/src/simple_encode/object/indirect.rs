@@ -1,19 +1,33 @@
 use crate::{pdf::IndirectObject, writer::Encoder};
 
-use crate::simple_encode::SimpleEncoder;
+use crate::simple_encode::{EncodeError, SimpleEncoder};
 
 impl Encoder<IndirectObject> for SimpleEncoder {
-    fn write_to(o: &IndirectObject, writer: &mut dyn crate::writer::Writer) {
+    fn write_to(o: &IndirectObject, writer: &mut dyn crate::writer::Writer) -> Result<(), EncodeError> {
         writer.write(o.index.to_string().as_bytes());
         writer.write(b" ");
         writer.write(o.generation.to_string().as_bytes());
         writer.write(b" obj\n");
-        Self::write_to(&*o.object, writer);
-        writer.write(b"endobj\n");
+        Self::write_to(&*o.object, writer)?;
+        writer.write(b"\nendobj\n");
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    // TODO: add tests
+    use crate::pdf::{Object, Reference};
+
+    use super::*;
+
+    #[test]
+    fn indirect_object() {
+        let obj = IndirectObject::new(12, 3, Object::Reference(Reference::new(5, 0)));
+        let encoded_len = SimpleEncoder::encoded_len(&obj).unwrap();
+
+        let mut out = Vec::new();
+        SimpleEncoder::write_to(&obj, &mut out).unwrap();
+        assert_eq!(out, b"12 3 obj\n5 0 R\nendobj\n");
+        assert_eq!(encoded_len, out.len());
+    }
 }
@@ -1,13 +1,14 @@
 use crate::{parse::object::is_regular, pdf::Name, writer::Encoder};
 
-use crate::simple_encode::SimpleEncoder;
+use crate::simple_encode::{EncodeError, SimpleEncoder};
 
 impl Encoder<Name> for SimpleEncoder {
-    fn write_to(n: &Name, writer: &mut dyn crate::writer::Writer) {
+    fn write_to(n: &Name, writer: &mut dyn crate::writer::Writer) -> Result<(), EncodeError> {
         let mut last_write = 0;
-        writer.write(br"\");
+        writer.write(b"/");
         for (index, &c) in n.iter().enumerate() {
-            if !is_regular(c) {
+            // `#` starts an escape sequence and must be escaped itself.
+            if !is_regular(c) || c == b'#' || !c.is_ascii_graphic() {
                 writer.write(&n[last_write..index]);
                 last_write = index + 1;
                 writer.write(b"#");
@@ -15,6 +16,7 @@ impl Encoder<Name> for SimpleEncoder {
             }
         }
         writer.write(&n[last_write..]);
+        Ok(())
     }
 }
 
@@ -25,11 +27,11 @@ mod tests {
     #[test]
     fn delimiter_in_the_middle() {
         let name = Name::from(b"Hello World!".to_vec());
-        let encoded_len = SimpleEncoder::encoded_len(&name);
+        let encoded_len = SimpleEncoder::encoded_len(&name).unwrap();
         assert_eq!(encoded_len, 15);
         let mut out = Vec::new();
-        SimpleEncoder::write_to(&name, &mut out);
-        let expected = br"\Hello#20World!";
+        SimpleEncoder::write_to(&name, &mut out).unwrap();
+        let expected = b"/Hello#20World!";
         assert_eq!(
             out,
             expected,
@@ -43,11 +45,11 @@ mod tests {
     #[test]
     fn delimiter_start() {
         let name = Name::from(b" HelloWorld!".to_vec());
-        let encoded_len = SimpleEncoder::encoded_len(&name);
+        let encoded_len = SimpleEncoder::encoded_len(&name).unwrap();
         assert_eq!(encoded_len, 15);
         let mut out = Vec::new();
-        SimpleEncoder::write_to(&name, &mut out);
-        let expected = br"\#20HelloWorld!";
+        SimpleEncoder::write_to(&name, &mut out).unwrap();
+        let expected = b"/#20HelloWorld!";
         assert_eq!(
             out,
             expected,
@@ -61,11 +63,11 @@ mod tests {
     #[test]
     fn delimiter_end() {
         let name = Name::from(b"HelloWorld! ".to_vec());
-        let encoded_len = SimpleEncoder::encoded_len(&name);
+        let encoded_len = SimpleEncoder::encoded_len(&name).unwrap();
         assert_eq!(encoded_len, 15);
         let mut out = Vec::new();
-        SimpleEncoder::write_to(&name, &mut out);
-        let expected = br"\HelloWorld!#20";
+        SimpleEncoder::write_to(&name, &mut out).unwrap();
+        let expected = b"/HelloWorld!#20";
         assert_eq!(
             out,
             expected,
@@ -79,11 +81,11 @@ mod tests {
     #[test]
     fn only_delimiters() {
         let name = Name::from(b"   ".to_vec());
-        let encoded_len = SimpleEncoder::encoded_len(&name);
+        let encoded_len = SimpleEncoder::encoded_len(&name).unwrap();
         assert_eq!(encoded_len, 10);
         let mut out = Vec::new();
-        SimpleEncoder::write_to(&name, &mut out);
-        let expected = br"\#20#20#20";
+        SimpleEncoder::write_to(&name, &mut out).unwrap();
+        let expected = b"/#20#20#20";
         assert_eq!(
             out,
             expected,
@@ -97,11 +99,11 @@ mod tests {
     #[test]
     fn no_delimiters() {
         let name = Name::from(b"HelloWorld!".to_vec());
-        let encoded_len = SimpleEncoder::encoded_len(&name);
+        let encoded_len = SimpleEncoder::encoded_len(&name).unwrap();
         assert_eq!(encoded_len, 12);
         let mut out = Vec::new();
-        SimpleEncoder::write_to(&name, &mut out);
-        let expected = br"\HelloWorld!";
+        SimpleEncoder::write_to(&name, &mut out).unwrap();
+        let expected = b"/HelloWorld!";
         assert_eq!(
             out,
             expected,
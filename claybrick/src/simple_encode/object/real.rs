@@ -0,0 +1,138 @@
+use crate::{
+    simple_encode::{EncodeError, SimpleEncoder},
+    writer::{Encoder, Writer},
+};
+
+/// Number of decimal places of real numbers unless
+/// [EncodeOptions::real_precision](crate::simple_encode::EncodeOptions::real_precision)
+/// is set.
+pub const DEFAULT_PRECISION: usize = 6;
+
+/// Format a real number with at most `precision` decimal places.
+///
+/// PDF has no exponential notation, so `1e-7` is written as `0.0000001` or
+/// rounded to `0`. Trailing zeros are removed and integral values are
+/// written without a decimal point.
+pub(crate) fn format_real(value: f32, precision: usize) -> String {
+    if !value.is_finite() {
+        log::warn!("Can't represent {} in PDF, writing 0 instead", value);
+        return "0".to_string();
+    }
+    let mut formatted = format!("{:.*}", precision, value);
+    if formatted.contains('.') {
+        let trimmed = formatted.trim_end_matches('0').trim_end_matches('.').len();
+        formatted.truncate(trimmed);
+    }
+    // negative values that were rounded to zero
+    if formatted == "-0" {
+        formatted.remove(0);
+    }
+    formatted
+}
+
+impl Encoder<f32> for SimpleEncoder {
+    fn write_to(value: &f32, writer: &mut dyn Writer) -> Result<(), EncodeError> {
+        writer.write(format_real(*value, writer.real_precision()).as_bytes());
+        Ok(())
+    }
+}
+
+/// Writes to `inner` with the precision of real numbers set.
+pub(crate) struct PrecisionWriter<'a> {
+    inner: &'a mut dyn Writer,
+    precision: usize,
+}
+
+impl<'a> PrecisionWriter<'a> {
+    pub(crate) fn new(inner: &'a mut dyn Writer, precision: usize) -> Self {
+        Self { inner, precision }
+    }
+}
+
+impl Writer for PrecisionWriter<'_> {
+    fn write(&mut self, buf: &[u8]) {
+        self.inner.write(buf);
+    }
+
+    fn position(&self) -> usize {
+        self.inner.position()
+    }
+
+    fn real_precision(&self) -> usize {
+        self.precision
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        build::PdfBuilder,
+        parse::object::object,
+        pdf::Object,
+        simple_encode::{EncodeOptions, XrefStyle},
+    };
+
+    use super::*;
+
+    fn encode(value: f32) -> String {
+        let mut out = Vec::new();
+        SimpleEncoder::write_to(&value, &mut out).unwrap();
+        assert_eq!(SimpleEncoder::encoded_len(&value).unwrap(), out.len());
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn reals() {
+        assert_eq!(encode(0.5), "0.5");
+        assert_eq!(encode(1e-7), "0");
+        assert_eq!(format_real(1e-7, 7), "0.0000001");
+        assert_eq!(encode(-0.0), "0");
+        assert_eq!(encode(-0.0000001), "0");
+        assert_eq!(encode(72.0), "72");
+        assert_eq!(encode(-12.25), "-12.25");
+        assert_eq!(encode(0.3), "0.3");
+        assert_eq!(encode(1e20), "100000002004087734272");
+        assert_eq!(encode(f32::NAN), "0");
+    }
+
+    #[test]
+    fn precision() {
+        let mut out = Vec::new();
+        SimpleEncoder::write_to(&1e-7, &mut PrecisionWriter::new(&mut out, 7)).unwrap();
+        assert_eq!(out, b"0.0000001");
+
+        let mut builder = PdfBuilder::new();
+        builder.add_page(612.375, 792.0);
+        let pdf = builder.finish().unwrap();
+        for (precision, width) in [(None, 612.375), (Some(1), 612.4)] {
+            for (xref, use_object_streams) in [(XrefStyle::Table, false), (XrefStyle::Stream, true)] {
+                let mut out = Vec::new();
+                let options = EncodeOptions {
+                    real_precision: precision,
+                    xref,
+                    use_object_streams,
+                    ..Default::default()
+                };
+                SimpleEncoder::write_with(&pdf, &mut out, options).unwrap();
+                let written = crate::read_bytes(out.clone()).unwrap();
+                let media_box = written.page(0).unwrap().media_box().unwrap();
+                assert_eq!(media_box.x1, width, "{:?} {:?}", precision, xref);
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        for value in [0.5, -12.25, 0.3, 612.0, 1e-7, 123.456] {
+            let mut out = Vec::new();
+            SimpleEncoder::write_to(&Object::Float(value), &mut out).unwrap();
+            out.push(b' ');
+            let parsed = match object(out[..].into()).unwrap().1 {
+                Object::Float(f) => f,
+                Object::Integer(i) => i as f32,
+                other => panic!("Unexpected {:?}", other),
+            };
+            assert!((parsed - value).abs() < 1e-6, "{} != {}", parsed, value);
+        }
+    }
+}
@@ -1,21 +1,104 @@
-use crate::{pdf::Stream, writer::Encoder};
+use std::io::Write;
 
-use crate::simple_encode::SimpleEncoder;
+use flate2::{write::ZlibEncoder, Compression};
+
+use crate::{
+    pdf::{document::K_LENGTH, Object, Stream},
+    writer::Encoder,
+};
+
+use crate::{
+    pdf::object::integer,
+    simple_encode::{EncodeError, SimpleEncoder},
+};
 
 const START_STREAM: &[u8] = b"stream\n";
 const END_STREAM: &[u8] = b"\nendstream";
+const K_FILTER: &[u8] = b"Filter";
+const FILTER_FLATE: &[u8] = b"FlateDecode";
+
+/// Compress the data of a stream without filters with `FlateDecode`.
+///
+/// Returns `None` if the stream already has a filter.
+pub(crate) fn compress(s: &Stream) -> Option<Stream> {
+    if !matches!(s.filters().as_deref(), Ok([])) {
+        return None;
+    }
+    let mut encoder = ZlibEncoder::new(Vec::with_capacity(s.data.len() / 2), Compression::default());
+    encoder.write_all(&s.data).expect("Writing into a Vec can't fail");
+    let data = encoder.finish().expect("Writing into a Vec can't fail");
+
+    let mut dictionary = s.dictionary.clone();
+    dictionary.insert(K_FILTER.to_vec().into(), Object::Name(FILTER_FLATE.to_vec().into()));
+    Some(Stream {
+        dictionary,
+        data: data.into(),
+        provenance: Default::default(),
+    })
+}
 
 impl Encoder<Stream> for SimpleEncoder {
-    fn write_to(s: &Stream, writer: &mut dyn crate::writer::Writer) {
-        Self::write_to(&s.dictionary, writer);
+    /// The `Length` entry is replaced with the actual length of the data.
+    fn write_to(s: &Stream, writer: &mut dyn crate::writer::Writer) -> Result<(), EncodeError> {
+        let length = integer(s.data.len())?;
+        if s.dictionary.get(K_LENGTH) == Some(&length) {
+            Self::write_to(&s.dictionary, writer)?;
+        } else {
+            let mut dictionary = s.dictionary.clone();
+            dictionary.insert(K_LENGTH.to_vec().into(), length);
+            Self::write_to(&dictionary, writer)?;
+        }
         writer.write(b" ");
         writer.write(START_STREAM);
         writer.write(&s.data);
         writer.write(END_STREAM);
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    // TODO: add tests
+    use crate::pdf::{Dictionary, Reference};
+
+    use super::*;
+
+    #[test]
+    fn stream() {
+        let stream = Stream {
+            dictionary: Dictionary::from([(b"Length".to_vec().into(), Object::Integer(5))]),
+            data: b"hello".to_vec().into(),
+            provenance: Default::default(),
+        };
+        let encoded_len = SimpleEncoder::encoded_len(&stream).unwrap();
+
+        let mut out = Vec::new();
+        SimpleEncoder::write_to(&stream, &mut out).unwrap();
+        assert_eq!(out, b"<</Length 5>> stream\nhello\nendstream");
+        assert_eq!(encoded_len, out.len());
+    }
+
+    #[test]
+    fn update_length() {
+        let stream = Stream {
+            dictionary: Dictionary::from([(b"Length".to_vec().into(), Object::Reference(Reference::new(4, 0)))]),
+            data: b"hello".to_vec().into(),
+            provenance: Default::default(),
+        };
+        let mut out = Vec::new();
+        SimpleEncoder::write_to(&stream, &mut out).unwrap();
+        assert_eq!(out, b"<</Length 5>> stream\nhello\nendstream");
+    }
+
+    #[test]
+    fn compress_stream() {
+        let stream = Stream {
+            dictionary: Dictionary::new(),
+            data: b"hello hello hello hello".to_vec().into(),
+            provenance: Default::default(),
+        };
+        let compressed = compress(&stream).unwrap();
+        assert!(compressed.data.len() < stream.data.len());
+        assert_eq!(compressed.filtered_data(), Ok(stream.data));
+        assert_eq!(compress(&compressed), None);
+    }
 }
@@ -1,19 +1,48 @@
 use crate::{
-    pdf::CbString,
-    simple_encode::SimpleEncoder,
+    pdf::{CbString, Object},
+    simple_encode::{EncodeError, EncodeOptions, SimpleEncoder},
     writer::{Encoder, Writer},
 };
 
+/// The escape sequence for a control character, which tools might mangle
+/// when it's written as it is.
+fn escape_sequence(byte: u8) -> Option<Vec<u8>> {
+    let escaped = match byte {
+        b'\t' => br"\t".to_vec(),
+        0x08 => br"\b".to_vec(),
+        0x0C => br"\f".to_vec(),
+        0x00..=0x1F | 0x7F => format!("\\{:03o}", byte).into_bytes(),
+        _ => return None,
+    };
+    Some(escaped)
+}
+
 impl Encoder<CbString> for SimpleEncoder {
-    fn write_to(str: &CbString, writer: &mut dyn Writer) {
+    /// Write a literal string.
+    ///
+    /// The string keeps the escape sequences of the parsed file, which are
+    /// written as they are. Unbalanced parentheses, a trailing backslash and
+    /// raw line breaks and control characters are escaped. A raw line break
+    /// is written as `\n`, which is what readers make of it.
+    fn write_to(str: &CbString, writer: &mut dyn Writer) -> Result<(), EncodeError> {
         writer.write(&b"("[..]);
 
+        // Characters after a backslash are part of an escape sequence.
+        let mut escaped = vec![false; str.len()];
+        for index in 1..str.len() {
+            escaped[index] = str[index - 1] == b'\\' && !escaped[index - 1];
+        }
+
         let mut open_paranthesis: usize = 0;
-        let mut remaining_closing_paranthesis = str.iter().filter(|&c| *c == b')').count();
+        let mut remaining_closing_paranthesis = str
+            .iter()
+            .zip(&escaped)
+            .filter(|(c, escaped)| **c == b')' && !**escaped)
+            .count();
 
         let mut last_written_index = 0;
         // check for characters that we need to escape.
-        for (index, char) in str.iter().enumerate() {
+        for (index, char) in str.iter().enumerate().filter(|(index, _)| !escaped[*index]) {
             match (char, open_paranthesis, remaining_closing_paranthesis) {
                 (b'(', _, 0) => {
                     open_paranthesis += 1;
@@ -34,26 +63,95 @@ impl Encoder<CbString> for SimpleEncoder {
                     open_paranthesis = open_paranthesis.saturating_sub(1);
                     remaining_closing_paranthesis = remaining_closing_paranthesis.saturating_sub(1);
                 }
-                // skip all others.
-                _ => {}
+                // a backslash at the end would escape the closing paranthesis
+                (b'\\', _, _) if index + 1 == str.len() => {
+                    writer.write(&str[last_written_index..index]);
+                    writer.write(&br"\"[..]);
+                    last_written_index = index;
+                }
+                // `\r\n` is a single line break
+                (b'\n', _, _) if index > 0 && str[index - 1] == b'\r' && !escaped[index - 1] => {
+                    last_written_index = index + 1;
+                }
+                (b'\r' | b'\n', _, _) => {
+                    writer.write(&str[last_written_index..index]);
+                    writer.write(&br"\n"[..]);
+                    last_written_index = index + 1;
+                }
+                (&other, _, _) => {
+                    if let Some(sequence) = escape_sequence(other) {
+                        writer.write(&str[last_written_index..index]);
+                        writer.write(&sequence);
+                        last_written_index = index + 1;
+                    }
+                }
             }
         }
         writer.write(&str[last_written_index..]);
         writer.write(&b")"[..]);
+        Ok(())
+    }
+}
+
+/// The bytes of `str` if it's written as hex string, depending on
+/// [EncodeOptions::strings_as_hex] and [EncodeOptions::keep_string_form].
+fn hex_form(str: &CbString, options: &EncodeOptions) -> Option<Vec<u8>> {
+    let bytes = str.unescaped();
+    let non_printable = bytes.iter().any(|b| !(b' '..=b'~').contains(b));
+    let shorter = || {
+        let literal = SimpleEncoder::encoded_len(str).unwrap_or(usize::MAX);
+        2 + 2 * bytes.len() < literal
+    };
+    ((options.strings_as_hex && non_printable) || (!options.keep_string_form && shorter())).then_some(bytes)
+}
+
+/// Whether [convert_strings] changes `obj`.
+pub(crate) fn has_convertible_strings(obj: &Object, options: &EncodeOptions) -> bool {
+    match obj {
+        Object::String(str) => hex_form(str, options).is_some(),
+        Object::Array(array) => array.iter().any(|o| has_convertible_strings(o, options)),
+        Object::Dictionary(dict) => dict.values().any(|o| has_convertible_strings(o, options)),
+        Object::Stream(stream) => stream.dictionary.values().any(|o| has_convertible_strings(o, options)),
+        Object::Indirect(indirect) => has_convertible_strings(&indirect.object, options),
+        _ => false,
+    }
+}
+
+/// Replace literal strings by hex strings where the options ask for it.
+/// Hex strings are never written as literal strings.
+pub(crate) fn convert_strings(obj: &mut Object, options: &EncodeOptions) {
+    match obj {
+        Object::String(str) => {
+            if let Some(bytes) = hex_form(str, options) {
+                *obj = Object::HexString(bytes.into());
+            }
+        }
+        Object::Array(array) => array.iter_mut().for_each(|o| convert_strings(o, options)),
+        Object::Dictionary(dict) => dict.values_mut().for_each(|o| convert_strings(o, options)),
+        Object::Stream(stream) => stream.dictionary.values_mut().for_each(|o| convert_strings(o, options)),
+        Object::Indirect(indirect) => convert_strings(&mut indirect.object, options),
+        _ => {}
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{pdf::CbString, simple_encode::SimpleEncoder, writer::Encoder};
+    use crate::{
+        parse::object::object,
+        pdf::{tests::parsed_pdf, RawPdf, Reference},
+        simple_encode::SimpleEncoder,
+        writer::Encoder,
+    };
+
+    use super::*;
 
     #[test]
     fn test_simple() {
         let simple = CbString::from(b"abcdefg".to_vec());
-        let encoded_len = SimpleEncoder::encoded_len(&simple);
+        let encoded_len = SimpleEncoder::encoded_len(&simple).unwrap();
         assert_eq!(encoded_len, simple.len() + 2);
         let mut out = Vec::new();
-        SimpleEncoder::write_to(&simple, &mut out);
+        SimpleEncoder::write_to(&simple, &mut out).unwrap();
         assert_eq!(out, b"(abcdefg)".to_vec());
         assert_eq!(encoded_len, out.len());
     }
@@ -61,10 +159,10 @@ mod tests {
     #[test]
     fn test_end_with_closing_paranthesis() {
         let simple = CbString::from(b"(abcdefg)".to_vec());
-        let encoded_len = SimpleEncoder::encoded_len(&simple);
+        let encoded_len = SimpleEncoder::encoded_len(&simple).unwrap();
         assert_eq!(encoded_len, simple.len() + 2);
         let mut out = Vec::new();
-        SimpleEncoder::write_to(&simple, &mut out);
+        SimpleEncoder::write_to(&simple, &mut out).unwrap();
         assert_eq!(out, b"((abcdefg))".to_vec());
         assert_eq!(encoded_len, out.len());
     }
@@ -74,10 +172,10 @@ mod tests {
         let simple = CbString::from(b"abcdefg)".to_vec());
 
         // 2 for start and end. One for escaping.
-        let encoded_len = SimpleEncoder::encoded_len(&simple);
+        let encoded_len = SimpleEncoder::encoded_len(&simple).unwrap();
         assert_eq!(encoded_len, simple.len() + 3);
         let mut out = Vec::new();
-        SimpleEncoder::write_to(&simple, &mut out);
+        SimpleEncoder::write_to(&simple, &mut out).unwrap();
         assert_eq!(out, br"(abcdefg\))".to_vec());
         assert_eq!(encoded_len, out.len());
     }
@@ -87,10 +185,10 @@ mod tests {
         let simple = CbString::from(b")))))))))".to_vec());
 
         // 2 for start and end. many for escaping.
-        let encoded_len = SimpleEncoder::encoded_len(&simple);
+        let encoded_len = SimpleEncoder::encoded_len(&simple).unwrap();
         assert_eq!(encoded_len, simple.len() * 2 + 2);
         let mut out = Vec::new();
-        SimpleEncoder::write_to(&simple, &mut out);
+        SimpleEncoder::write_to(&simple, &mut out).unwrap();
         assert_eq!(out, br"(\)\)\)\)\)\)\)\)\))".to_vec());
         assert_eq!(encoded_len, out.len());
     }
@@ -100,10 +198,10 @@ mod tests {
         let simple = CbString::from(b"(((((((((".to_vec());
 
         // 2 for start and end. many for escaping.
-        let encoded_len = SimpleEncoder::encoded_len(&simple);
+        let encoded_len = SimpleEncoder::encoded_len(&simple).unwrap();
         assert_eq!(encoded_len, simple.len() * 2 + 2);
         let mut out = Vec::new();
-        SimpleEncoder::write_to(&simple, &mut out);
+        SimpleEncoder::write_to(&simple, &mut out).unwrap();
         assert_eq!(out, br"(\(\(\(\(\(\(\(\(\()".to_vec());
         assert_eq!(encoded_len, out.len());
     }
@@ -113,10 +211,10 @@ mod tests {
         let simple = CbString::from(b"((((((()))))))".to_vec());
 
         // 2 for start and end. many for escaping.
-        let encoded_len = SimpleEncoder::encoded_len(&simple);
+        let encoded_len = SimpleEncoder::encoded_len(&simple).unwrap();
         assert_eq!(encoded_len, simple.len() + 2);
         let mut out = Vec::new();
-        SimpleEncoder::write_to(&simple, &mut out);
+        SimpleEncoder::write_to(&simple, &mut out).unwrap();
         assert_eq!(out, br"(((((((())))))))".to_vec());
         assert_eq!(encoded_len, out.len());
     }
@@ -126,11 +224,150 @@ mod tests {
         let simple = CbString::from(b")))))(((((".to_vec());
 
         // 2 for start and end. many for escaping.
-        let encoded_len = SimpleEncoder::encoded_len(&simple);
+        let encoded_len = SimpleEncoder::encoded_len(&simple).unwrap();
         assert_eq!(encoded_len, simple.len() * 2 + 2);
         let mut out = Vec::new();
-        SimpleEncoder::write_to(&simple, &mut out);
+        SimpleEncoder::write_to(&simple, &mut out).unwrap();
         assert_eq!(out, br"(\)\)\)\)\)\(\(\(\(\()".to_vec());
         assert_eq!(encoded_len, out.len());
     }
+
+    #[test]
+    fn test_escaped_paranthesis() {
+        // an escaped paranthesis, an escaped backslash and an unmatched
+        // closing paranthesis
+        let simple = CbString::from(br"a\)b\\)".to_vec());
+        let mut out = Vec::new();
+        SimpleEncoder::write_to(&simple, &mut out).unwrap();
+        assert_eq!(out, br"(a\)b\\\))".to_vec());
+    }
+
+    fn round_trip(str: &CbString) -> (Vec<u8>, CbString) {
+        let mut out = Vec::new();
+        SimpleEncoder::write_to(str, &mut out).unwrap();
+        assert_eq!(SimpleEncoder::encoded_len(str).unwrap(), out.len());
+        let reparsed = match object(out[..].into()).unwrap().1 {
+            Object::String(reparsed) => reparsed,
+            other => panic!("Expected a string: {:?}", other),
+        };
+        (out, reparsed)
+    }
+
+    #[test]
+    fn test_line_breaks_and_control_characters() {
+        let cases = [
+            (&b"a\rb\r\nc\nd"[..], &br"(a\nb\nc\nd)"[..]),
+            (br"a\rb\\c\000", br"(a\rb\\c\000)"),
+            (b"\x00\x01\x7F\t\x08\x0C", br"(\000\001\177\t\b\f)"),
+            (b"line\\\ncontinued", b"(line\\\ncontinued)"),
+        ];
+        for (str, expected) in cases {
+            let str = CbString::from(str.to_vec());
+            let (out, reparsed) = round_trip(&str);
+            assert_eq!(out, expected, "{}", String::from_utf8_lossy(&out));
+            assert_eq!(reparsed.unescaped(), str.unescaped());
+        }
+    }
+
+    #[test]
+    fn test_trailing_backslash() {
+        let (out, reparsed) = round_trip(&CbString::from(b"a\\".to_vec()));
+        assert_eq!(out, br"(a\\)");
+        assert_eq!(reparsed.unescaped(), b"a\\");
+    }
+
+    #[test]
+    fn test_strings_as_hex() {
+        let mut obj = Object::Array(
+            vec![
+                Object::String(br"printable \(\)".to_vec().into()),
+                Object::String(br"a\rb".to_vec().into()),
+                Object::String(b"\x00".to_vec().into()),
+            ]
+            .into(),
+        );
+        let options = EncodeOptions {
+            strings_as_hex: true,
+            keep_string_form: true,
+            ..Default::default()
+        };
+        assert!(has_convertible_strings(&obj, &options));
+        convert_strings(&mut obj, &options);
+        assert_eq!(
+            obj,
+            Object::Array(
+                vec![
+                    Object::String(br"printable \(\)".to_vec().into()),
+                    Object::HexString(b"a\rb".to_vec().into()),
+                    Object::HexString(b"\x00".to_vec().into()),
+                ]
+                .into()
+            )
+        );
+    }
+
+    /// Written with the options and parsed again, with the catalog.
+    fn write_catalog(entries: &str, options: EncodeOptions) -> (Vec<u8>, RawPdf) {
+        let catalog = format!("1 0 obj <</Type /Catalog /Pages 2 0 R {}>> endobj", entries);
+        let pdf = parsed_pdf(
+            Reference::new(1, 0),
+            &[catalog.as_bytes(), b"2 0 obj <</Type /Pages /Kids [] /Count 0>> endobj"],
+        );
+        let mut out = Vec::new();
+        SimpleEncoder::write_with(&pdf, &mut out, options).unwrap();
+        let written = crate::read_bytes(out.clone()).unwrap();
+        crate::testing::assert_equivalent(&pdf, &written).unwrap();
+        (out, written)
+    }
+
+    fn catalog_entry<'a>(pdf: &'a RawPdf, key: &[u8]) -> Option<&'a Object> {
+        pdf.dereference(&pdf.sections[0].trailer.root)?.dictionary()?.get(key)
+    }
+
+    #[test]
+    fn test_write_strings_as_hex() {
+        let options = EncodeOptions {
+            strings_as_hex: true,
+            ..Default::default()
+        };
+        let (_, written) = write_catalog(r"/Binary (a\000b) /Text (text)", options);
+        assert_eq!(
+            catalog_entry(&written, b"Binary"),
+            Some(&Object::HexString(b"a\x00b".to_vec().into()))
+        );
+        assert_eq!(
+            catalog_entry(&written, b"Text"),
+            Some(&Object::String(b"text".to_vec().into()))
+        );
+    }
+
+    #[test]
+    fn test_shortest_form() {
+        let entries = r"/Binary (\000\001\002\003\004\005\006\007) /Short (a\000b) /Text (text) /Hex <74657874>";
+        let (shortest, written) = write_catalog(entries, EncodeOptions::default());
+        assert_eq!(
+            catalog_entry(&written, b"Binary"),
+            Some(&Object::HexString(b"\x00\x01\x02\x03\x04\x05\x06\x07".to_vec().into()))
+        );
+        // literal strings are kept unless hex is shorter, hex strings are kept
+        assert_eq!(
+            catalog_entry(&written, b"Short"),
+            Some(&Object::String(br"a\000b".to_vec().into()))
+        );
+        assert_eq!(
+            catalog_entry(&written, b"Hex"),
+            Some(&Object::HexString(b"text".to_vec().into()))
+        );
+
+        let options = EncodeOptions {
+            keep_string_form: true,
+            ..Default::default()
+        };
+        let (kept, written) = write_catalog(entries, options);
+        assert_eq!(
+            catalog_entry(&written, b"Binary"),
+            Some(&Object::String(br"\000\001\002\003\004\005\006\007".to_vec().into()))
+        );
+        assert_eq!(kept.len() - shortest.len(), (2 + 8 * 4) - (2 + 8 * 2));
+    }
 }
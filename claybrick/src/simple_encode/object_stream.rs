@@ -0,0 +1,140 @@
+//! Packing of objects into object streams.
+
+use crate::{
+    pdf::{
+        document::{dict_types::OBJECT_STREAM, K_FIRST, K_LENGTH, K_STREAM_OBJECT_COUNT, K_TYPE},
+        object::integer,
+        Dictionary, Object, Stream, Trailer,
+    },
+    simple_encode::{
+        object::{real::PrecisionWriter, stream::compress},
+        EncodeError, SimpleEncoder,
+    },
+    writer::Encoder,
+};
+
+/// Objects per object stream. Readers have to parse the complete stream to
+/// access a single object, thus the streams are kept small.
+pub(crate) const OBJECTS_PER_STREAM: usize = 200;
+
+/// The object without indirect wrapper if it can be stored in an object
+/// stream: streams, objects with a generation other than 0 and the
+/// encryption dictionary can't.
+pub(crate) fn packable<'a>(number: usize, obj: &'a Object, trailer: &Trailer) -> Option<&'a Object> {
+    let (generation, direct) = match obj {
+        Object::Indirect(indirect) => (indirect.generation, &*indirect.object),
+        direct => (0, direct),
+    };
+    let encrypt = trailer
+        .encrypt
+        .as_ref()
+        .and_then(Object::reference)
+        .map(|r| r.index as usize);
+    (generation == 0 && !direct.is_stream() && encrypt != Some(number)).then_some(direct)
+}
+
+/// A compressed object stream that contains `objects`, each given by number
+/// and direct object. Real numbers are written with `precision` decimal
+/// places.
+pub(crate) fn object_stream(objects: &[(usize, &Object)], precision: usize) -> Result<Stream, EncodeError> {
+    let mut header = Vec::new();
+    let mut body = Vec::new();
+    for (number, obj) in objects {
+        header.extend_from_slice(format!("{} {} ", number, body.len()).as_bytes());
+        SimpleEncoder::write_to(*obj, &mut PrecisionWriter::new(&mut body, precision))?;
+        body.push(b'\n');
+    }
+    header.pop();
+    header.push(b'\n');
+
+    let dictionary = Dictionary::from([
+        (K_TYPE.to_vec().into(), Object::Name(OBJECT_STREAM.to_vec().into())),
+        (K_STREAM_OBJECT_COUNT.to_vec().into(), integer(objects.len())?),
+        (K_FIRST.to_vec().into(), integer(header.len())?),
+    ]);
+    header.extend_from_slice(&body);
+    let stream = Stream {
+        dictionary,
+        data: header.into(),
+        provenance: Default::default(),
+    };
+    let mut stream = compress(&stream).unwrap_or(stream);
+    stream
+        .dictionary
+        .insert(K_LENGTH.to_vec().into(), integer(stream.data.len())?);
+    Ok(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        build::PdfBuilder,
+        parse::{object_stream::object_stream as parse_object_stream, ParseContext},
+        pdf::{xref::is_structural_stream, RawPdf},
+        simple_encode::{EncodeOptions, SimpleEncoder, XrefStyle, DEFAULT_PRECISION},
+    };
+
+    use super::*;
+
+    fn document(pages: usize) -> RawPdf {
+        let mut builder = PdfBuilder::new();
+        for _ in 0..pages {
+            let page = builder.add_page(100.0, 100.0);
+            builder.set_page_content(page, &b"0 0 m 100 100 l S"[..]);
+        }
+        builder.finish().unwrap()
+    }
+
+    fn write(pdf: &RawPdf, xref: XrefStyle) -> Vec<u8> {
+        let mut out = Vec::new();
+        let options = EncodeOptions {
+            xref,
+            use_object_streams: true,
+            ..Default::default()
+        };
+        SimpleEncoder::write_with(pdf, &mut out, options).unwrap();
+        out
+    }
+
+    #[test]
+    fn pack_objects() {
+        let objects = [Object::Integer(999), Object::Array(vec![Object::Null].into())];
+        let stream = object_stream(&[(123, &objects[0]), (7, &objects[1])], DEFAULT_PRECISION).unwrap();
+        assert_eq!(
+            parse_object_stream(&stream, ParseContext::default()).unwrap(),
+            vec![(123, objects[0].clone()), (7, objects[1].clone())]
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        // more pages than one object stream can take
+        let pdf = document(250);
+        let out = write(&pdf, XrefStyle::Stream);
+        let reparsed = crate::read_bytes(out.clone()).unwrap();
+
+        let xref = &reparsed.sections()[0].xref();
+        let compressed = xref.compressed_objects().collect::<Vec<_>>();
+        // the pages, the page tree and the catalog, but not the contents
+        assert_eq!(compressed.len(), 252);
+        let containers = compressed
+            .iter()
+            .map(|c| c.containing_object)
+            .collect::<std::collections::BTreeSet<_>>();
+        assert_eq!(containers.len(), 252usize.div_ceil(OBJECTS_PER_STREAM));
+        assert_eq!(reparsed.page_count().unwrap(), 250);
+        // the same objects as without object streams
+        let table = crate::read_bytes(write(&pdf, XrefStyle::Table)).unwrap();
+        let strip = |pdf: &RawPdf| {
+            pdf.objects()
+                .filter(|(_, _, o)| !is_structural_stream(o))
+                .map(|(n, _, o)| (n, o.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(strip(&reparsed), strip(&table));
+        assert!(out.len() < write(&pdf, XrefStyle::Table).len());
+
+        // tables can't refer to object streams
+        assert_eq!(table.sections()[0].xref().compressed_objects().count(), 0);
+    }
+}
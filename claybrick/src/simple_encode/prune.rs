@@ -0,0 +1,184 @@
+//! Removal of objects that can't be reached from the trailer.
+
+use std::collections::HashMap;
+
+use crate::pdf::{
+    xref::is_structural_stream, Dictionary, IndirectObject, Object, PdfSection, RawPdf, Reference, Stream, Trailer,
+    Xref,
+};
+
+/// Xref and object streams are written anew and thus never reachable.
+fn lookup<'a>(pdf: &'a RawPdf, reference: &Reference) -> Option<&'a Object> {
+    pdf.lookup_reference(reference).filter(|obj| !is_structural_stream(obj))
+}
+
+/// Push all references contained in `obj`. Dictionary entries are visited in
+/// the order of their keys, so the result doesn't depend on the hash map.
+fn collect_references(obj: &Object, out: &mut Vec<Reference>) {
+    match obj {
+        Object::Reference(r) => out.push(*r),
+        Object::Array(a) => a.iter().for_each(|o| collect_references(o, out)),
+        Object::Dictionary(d) => collect_dict_references(d, out),
+        Object::Stream(s) => collect_dict_references(&s.dictionary, out),
+        Object::Indirect(i) => collect_references(&i.object, out),
+        _ => {}
+    }
+}
+
+fn collect_dict_references(dict: &Dictionary, out: &mut Vec<Reference>) {
+    dict.sorted_entries()
+        .into_iter()
+        .for_each(|(_, o)| collect_references(o, out));
+}
+
+/// Replace all references according to `numbers`. References to objects that
+/// don't exist are replaced with null, which is their meaning anyway.
+fn renumber(obj: &Object, numbers: &HashMap<Reference, u32>) -> Object {
+    match obj {
+        Object::Reference(r) => numbers
+            .get(r)
+            .map_or(Object::Null, |&index| Object::Reference(Reference::new(index, 0))),
+        Object::Array(a) => Object::Array(a.iter().map(|o| renumber(o, numbers)).collect::<Vec<_>>().into()),
+        Object::Dictionary(d) => Object::Dictionary(renumber_dict(d, numbers)),
+        Object::Stream(s) => Object::Stream(Stream {
+            dictionary: renumber_dict(&s.dictionary, numbers),
+            data: s.data.clone(),
+            provenance: s.provenance,
+        }),
+        Object::Indirect(i) => renumber(&i.object, numbers),
+        other => other.clone(),
+    }
+}
+
+fn renumber_dict(dict: &Dictionary, numbers: &HashMap<Reference, u32>) -> Dictionary {
+    dict.iter()
+        .map(|(key, value)| (key.clone(), renumber(value, numbers)))
+        .collect()
+}
+
+/// Create a document with a single section that only contains the objects
+/// reachable from the root and the info dictionary of the newest trailer.
+///
+/// Must not be used for encrypted documents.
+///
+/// The objects are numbered densely in the order they are reached, starting
+/// with the catalog as object 1. All generations are reset to 0.
+pub(crate) fn prune(pdf: &RawPdf) -> RawPdf {
+    let trailer = &pdf.sections[0].trailer;
+
+    let mut pending = vec![trailer.root];
+    pending.extend(trailer.info);
+    // Depth first, but the references of each object in their original order.
+    pending.reverse();
+
+    let mut numbers = HashMap::<Reference, u32>::new();
+    let mut reachable = Vec::new();
+    while let Some(reference) = pending.pop() {
+        if numbers.contains_key(&reference) {
+            continue;
+        }
+        let Some(obj) = lookup(pdf, &reference) else {
+            log::warn!("Reference to missing object {:?}", reference);
+            continue;
+        };
+        numbers.insert(reference, reachable.len() as u32 + 1);
+        reachable.push(obj);
+
+        let first_new = pending.len();
+        collect_references(obj, &mut pending);
+        pending[first_new..].reverse();
+    }
+    log::debug!("{} reachable objects", reachable.len());
+
+    let objects = reachable
+        .into_iter()
+        .enumerate()
+        .map(|(i, obj)| {
+            let number = i + 1;
+            let indirect = IndirectObject::new(number as u32, 0, renumber(obj, &numbers));
+            (number, Object::Indirect(indirect))
+        })
+        .collect();
+
+    let renumbered = |r: &Reference| numbers.get(r).map(|&index| Reference::new(index, 0));
+    let trailer = Trailer {
+        size: numbers.len() + 1,
+        previous: None,
+        root: renumbered(&trailer.root).unwrap_or(trailer.root),
+        encrypt: None,
+        info: trailer.info.as_ref().and_then(renumbered),
+        id: trailer.id.clone(),
+        x_ref_stm: None,
+    };
+
+    RawPdf {
+        version: pdf.version,
+        binary_indicator: pdf.binary_indicator.clone(),
+        header_offset: 0,
+        sections: vec![PdfSection {
+            objects,
+            trailer,
+            xref: Xref::new(Vec::new()),
+            object_spans: Default::default(),
+            stream_spans: Default::default(),
+        }],
+        allocated: Default::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::parse_complete,
+        pdf::tests::parsed_pdf,
+        simple_encode::{EncodeOptions, SimpleEncoder},
+    };
+
+    use super::*;
+
+    #[test]
+    fn remove_orphans() {
+        let pdf = parsed_pdf(
+            Reference::new(2, 0),
+            &[
+                b"2 0 obj <</Type /Catalog /Pages 4 0 R>> endobj",
+                b"4 0 obj <</Type /Pages /Kids [7 0 R] /Count 1>> endobj",
+                b"7 0 obj <</Type /Page /Parent 4 0 R /MediaBox [0 0 612 792] /Contents 9 0 R /Missing 12 0 R>> endobj",
+                b"9 0 obj <</Length 9>> stream\nBT (x) Tj\nendstream endobj",
+                // orphans, 11 references a live object
+                b"10 0 obj (orphan) endobj",
+                b"11 0 obj <</Next 10 0 R /Page 7 0 R>> endobj",
+            ],
+        );
+
+        let pruned = prune(&pdf);
+        let section = &pruned.sections[0];
+        assert_eq!(section.objects.len(), 4);
+        assert_eq!(section.trailer.root, Reference::new(1, 0));
+        assert_eq!(section.trailer.size, 5);
+        let page = pruned.dereference(&Reference::new(3, 0)).unwrap().dictionary().unwrap();
+        assert_eq!(page.get(&b"Parent"[..]), Some(&Object::Reference(Reference::new(2, 0))));
+        assert_eq!(
+            page.get(&b"Contents"[..]),
+            Some(&Object::Reference(Reference::new(4, 0)))
+        );
+        assert_eq!(page.get(&b"Missing"[..]), Some(&Object::Null));
+
+        let encode = |prune_unreferenced| {
+            let mut out = Vec::new();
+            let options = EncodeOptions {
+                prune_unreferenced,
+                ..Default::default()
+            };
+            SimpleEncoder::write_with(&pdf, &mut out, options).unwrap();
+            out
+        };
+        let pruned = encode(true);
+        assert!(pruned.len() < encode(false).len());
+
+        let (_, reparsed) = parse_complete(pruned[..].into()).unwrap();
+        assert_eq!(reparsed.sections[0].trailer.size, 6);
+        assert!(reparsed.catalog().is_ok());
+        assert_eq!(reparsed.page_count(), Ok(1));
+    }
+}
@@ -0,0 +1,532 @@
+use crate::{
+    pdf::{
+        document::{dict_types::XREF, K_LENGTH, K_TYPE},
+        object::integer,
+        trailer::{K_ID, K_PREVIOUS, K_SIZE, K_X_REF_STM},
+        xref::{is_structural_stream, FreeObject, UsedCompressedObject, UsedObject},
+        Bytes, Dictionary, IndirectObject, Object, PdfSection, Stream, Xref,
+    },
+    simple_encode::{
+        object::{real::PrecisionWriter, stream::compress},
+        object_stream::{object_stream, packable, OBJECTS_PER_STREAM},
+        xref::{subsections, write_xref_table, xref_stream_data, xref_stream_widths},
+        EncodeError, EncodeOptions, SimpleEncoder, XrefStyle,
+    },
+    writer::{Encoder, Writer},
+};
+
+const K_WIDTHS: &[u8] = b"W";
+const K_INDEX: &[u8] = b"Index";
+
+/// Write the objects, the xref section and the trailer of a section.
+///
+/// `previous` is the offset of the xref section of the previously written
+/// section. `size` is the highest object number + 1 of all sections written
+/// so far and gets updated. `id` replaces the file identifier of the
+/// trailer. `replaced` is an object number and the object that is written
+/// instead of the object of the section. Returns the offset of the xref
+/// section.
+pub(crate) fn write_section(
+    section: &PdfSection,
+    writer: &mut dyn Writer,
+    options: EncodeOptions,
+    previous: Option<usize>,
+    size: &mut usize,
+    id: Option<&[Bytes; 2]>,
+    replaced: Option<(usize, &Object)>,
+) -> Result<usize, EncodeError> {
+    let mut precision_writer;
+    let writer = match options.real_precision {
+        Some(precision) => {
+            precision_writer = PrecisionWriter::new(writer, precision);
+            &mut precision_writer as &mut dyn Writer
+        }
+        None => writer,
+    };
+    let mut entries = Vec::with_capacity(section.objects.len() + 2);
+    if previous.is_none() {
+        entries.push(
+            FreeObject {
+                number: 0,
+                generation: 65535,
+                next_free: 0,
+            }
+            .into(),
+        );
+    }
+
+    let pack = options.use_object_streams && options.xref == XrefStyle::Stream;
+    let mut packed = Vec::new();
+    let mut numbers = section.objects.keys().copied().collect::<Vec<_>>();
+    numbers.sort_unstable();
+    for number in numbers {
+        let obj = match replaced {
+            Some((replaced, obj)) if replaced == number => obj,
+            _ => &section.objects[&number],
+        };
+        if let Some(direct) = packable(number, obj, &section.trailer).filter(|_| pack) {
+            packed.push((number, direct));
+            *size = (*size).max(number + 1);
+            continue;
+        }
+        let byte_offset = writer.position();
+        let generation = match obj {
+            Object::Indirect(indirect) if is_structural_stream(&indirect.object) => continue,
+            Object::Indirect(indirect) => {
+                match indirect
+                    .object
+                    .stream()
+                    .filter(|_| options.compress_streams)
+                    .and_then(compress)
+                {
+                    Some(compressed) => SimpleEncoder::write_to(
+                        &IndirectObject::new(indirect.index, indirect.generation, Object::Stream(compressed)),
+                        writer,
+                    )?,
+                    None => SimpleEncoder::write_to(indirect, writer)?,
+                }
+                indirect.generation
+            }
+            // objects from object streams
+            direct if is_structural_stream(direct) => continue,
+            direct => {
+                let index = u32::try_from(number).map_err(|_| EncodeError::ValueOutOfRange(number))?;
+                SimpleEncoder::write_to(&IndirectObject::new(index, 0, direct.clone()), writer)?;
+                0
+            }
+        };
+        entries.push(
+            UsedObject {
+                number,
+                byte_offset,
+                generation: generation as usize,
+            }
+            .into(),
+        );
+        *size = (*size).max(number + 1);
+    }
+
+    // Objects freed by this section stay freed in the written document.
+    for free in section.xref.free_objects() {
+        if (free.number == 0 && previous.is_none()) || section.objects.contains_key(&free.number) {
+            continue;
+        }
+        entries.push(free.clone().into());
+        *size = (*size).max(free.number + 1);
+    }
+
+    for objects in packed.chunks(OBJECTS_PER_STREAM) {
+        let container = *size;
+        *size += 1;
+        entries.push(
+            UsedObject {
+                number: container,
+                byte_offset: writer.position(),
+                generation: 0,
+            }
+            .into(),
+        );
+        let stream = object_stream(objects, writer.real_precision())?;
+        let index = u32::try_from(container).map_err(|_| EncodeError::ValueOutOfRange(container))?;
+        SimpleEncoder::write_to(&IndirectObject::new(index, 0, Object::Stream(stream)), writer)?;
+        entries.extend(objects.iter().enumerate().map(|(index, (number, _))| {
+            UsedCompressedObject {
+                number: *number,
+                containing_object: container,
+                index,
+            }
+            .into()
+        }));
+    }
+
+    let mut trailer = Dictionary::try_from(section.trailer.clone())?;
+    trailer.remove(K_X_REF_STM);
+    trailer.remove(K_PREVIOUS);
+    if let Some([first, second]) = id {
+        trailer.insert(
+            K_ID.to_vec().into(),
+            Object::Array(vec![Object::HexString(first.clone()), Object::HexString(second.clone())].into()),
+        );
+    }
+    if let Some(previous) = previous {
+        trailer.insert(K_PREVIOUS.to_vec().into(), integer(previous)?);
+    }
+
+    let xref_offset = writer.position();
+    match options.xref {
+        XrefStyle::Table => {
+            trailer.insert(K_SIZE.to_vec().into(), integer(*size)?);
+            write_xref_table(&Xref::new_table(entries), writer)?;
+            writer.write(b"trailer\n");
+            SimpleEncoder::write_to(&trailer, writer)?;
+            writer.write(b"\n");
+        }
+        XrefStyle::Stream => {
+            // The xref stream is an object itself.
+            let number = *size;
+            *size += 1;
+            entries.push(
+                UsedObject {
+                    number,
+                    byte_offset: xref_offset,
+                    generation: 0,
+                }
+                .into(),
+            );
+            let index = u32::try_from(number).map_err(|_| EncodeError::ValueOutOfRange(number))?;
+            let xref = Xref::new_stream(entries, index, 0);
+            let widths = xref_stream_widths(&xref);
+
+            trailer.insert(K_TYPE.to_vec().into(), Object::Name(XREF.to_vec().into()));
+            trailer.insert(K_SIZE.to_vec().into(), integer(*size)?);
+            trailer.insert(
+                K_WIDTHS.to_vec().into(),
+                Object::Array(
+                    widths
+                        .iter()
+                        .map(|&w| integer(w))
+                        .collect::<Result<Vec<_>, _>>()?
+                        .into(),
+                ),
+            );
+            trailer.insert(
+                K_INDEX.to_vec().into(),
+                Object::Array(
+                    subsections(&xref)
+                        .into_iter()
+                        .flat_map(|(first, count)| [integer(first), integer(count)])
+                        .collect::<Result<Vec<_>, _>>()?
+                        .into(),
+                ),
+            );
+            let data = xref_stream_data(&xref, widths);
+            trailer.insert(K_LENGTH.to_vec().into(), integer(data.len())?);
+
+            let stream = Stream {
+                dictionary: trailer,
+                data: data.into(),
+                provenance: Default::default(),
+            };
+            SimpleEncoder::write_to(&IndirectObject::new(index, 0, Object::Stream(stream)), writer)?;
+        }
+    }
+
+    writer.write(format!("startxref\n{}\n%%EOF\n", xref_offset).as_bytes());
+    Ok(xref_offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::{parse_complete, parse_complete_with, tests::push_xref_table, ParseOptions},
+        pdf::{tests::parsed_pdf, xref::XrefEntry, IntegerOutOfRange, RawPdf, Reference},
+        simple_encode::EncodeError,
+    };
+
+    use super::*;
+
+    fn pdf() -> RawPdf {
+        parsed_pdf(
+            Reference::new(1, 0),
+            &[
+                b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj",
+                b"2 0 obj <</Type /Pages /Kids [3 0 R] /Count 1>> endobj",
+                b"3 0 obj <</Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 5 0 R>> endobj",
+                b"5 0 obj <</Length 9>> stream\nBT (x) Tj\nendstream endobj",
+                b"6 0 obj (unused) endobj",
+            ],
+        )
+    }
+
+    #[test]
+    fn table_round_trip() {
+        let pdf = pdf();
+        let mut out = Vec::new();
+        SimpleEncoder::write_with(
+            &pdf,
+            &mut out,
+            EncodeOptions {
+                xref: XrefStyle::Table,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let text = String::from_utf8_lossy(&out);
+        assert!(text.contains("xref\n0 4\n0000000000 65535 f\r\n"), "{}", text);
+        assert!(text.contains("\n5 2\n"), "{}", text);
+
+        let (_, reparsed) = parse_complete(out[..].into()).unwrap();
+        assert_eq!(reparsed.sections.len(), 1);
+        assert_eq!(reparsed.sections[0].objects, pdf.sections[0].objects);
+        assert_eq!(reparsed.sections[0].trailer.size, 7);
+        assert_eq!(reparsed.sections[0].trailer.root, Reference::new(1, 0));
+        assert_eq!(reparsed.page_count(), Ok(1));
+    }
+
+    #[test]
+    fn stream_round_trip() {
+        let mut pdf = pdf();
+        pdf.sections[0].trailer.info = Some(Reference::new(6, 0));
+        let mut out = Vec::new();
+        SimpleEncoder::write_with(
+            &pdf,
+            &mut out,
+            EncodeOptions {
+                xref: XrefStyle::Stream,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        // All offsets are below 64 KiB and fit into two bytes.
+        let text = String::from_utf8_lossy(&out);
+        assert!(text.contains("/W [1 2 2]"), "{}", text);
+
+        let (_, reparsed) = parse_complete(out[..].into()).unwrap();
+        let trailer = &reparsed.sections[0].trailer;
+        assert_eq!(trailer.root, Reference::new(1, 0));
+        assert_eq!(trailer.info, Some(Reference::new(6, 0)));
+        // 7 is the xref stream
+        assert_eq!(trailer.size, 8);
+        assert!(reparsed.catalog().is_ok());
+        assert_eq!(reparsed.page_count(), Ok(1));
+        // Every entry points at its object.
+        for entry in reparsed.sections[0].xref.entries() {
+            if let XrefEntry::Used(UsedObject {
+                number, byte_offset, ..
+            }) = entry
+            {
+                let expected = format!("{} 0 obj", number);
+                assert!(out[*byte_offset..].starts_with(expected.as_bytes()), "{:?}", entry);
+            }
+        }
+        for (number, obj) in &pdf.sections[0].objects {
+            assert_eq!(reparsed.sections[0].objects.get(number), Some(obj));
+        }
+    }
+
+    #[test]
+    fn compress_streams() {
+        let mut pdf = pdf();
+        let data = b"0 0 m 100 100 l S\n".repeat(100 * 1024 / 18);
+        let stream = Stream {
+            dictionary: Dictionary::new(),
+            data: data.clone().into(),
+            provenance: Default::default(),
+        };
+        pdf.sections[0]
+            .objects
+            .insert(5, Object::Indirect(IndirectObject::new(5, 0, Object::Stream(stream))));
+
+        let mut out = Vec::new();
+        let options = EncodeOptions {
+            compress_streams: true,
+            ..Default::default()
+        };
+        SimpleEncoder::write_with(&pdf, &mut out, options).unwrap();
+        assert!(out.len() < data.len() / 10, "{} bytes", out.len());
+
+        let (_, reparsed) = parse_complete(out[..].into()).unwrap();
+        let stream = reparsed.dereference(&Reference::new(5, 0)).unwrap().stream().unwrap();
+        assert_eq!(stream.filtered_data().unwrap(), data.into());
+    }
+
+    #[test]
+    fn deterministic() {
+        let mut fixture = Vec::new();
+        SimpleEncoder::write_with(&pdf(), &mut fixture, EncodeOptions::default()).unwrap();
+
+        // Each parse creates new hash maps with different iteration orders.
+        let encode = || {
+            let (_, parsed) = parse_complete(fixture[..].into()).unwrap();
+            let mut out = Vec::new();
+            SimpleEncoder::write_with(&parsed, &mut out, EncodeOptions::default()).unwrap();
+            out
+        };
+        let first = encode();
+        assert_eq!(first, encode());
+        assert_eq!(first, fixture);
+    }
+
+    #[test]
+    fn key_order() {
+        let mut out = Vec::new();
+        SimpleEncoder::write_with(&pdf(), &mut out, EncodeOptions::default()).unwrap();
+        let contains = |needle: &[u8]| out.windows(needle.len()).any(|w| w == needle);
+        assert!(contains(
+            b"<</Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 5 0 R>>"
+        ));
+        assert!(contains(b"<</Type /Pages /Kids [3 0 R] /Count 1>>"));
+    }
+
+    #[test]
+    fn missing_root() {
+        let mut pdf = pdf();
+        pdf.sections[0].trailer.root = Reference::new(42, 0);
+        let mut out = Vec::new();
+        assert_eq!(
+            SimpleEncoder::write_with(&pdf, &mut out, EncodeOptions::default()),
+            Err(EncodeError::MissingRoot(Reference::new(42, 0)))
+        );
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn missing_trailer() {
+        let mut pdf = pdf();
+        pdf.sections.clear();
+        assert_eq!(
+            SimpleEncoder::write_with(&pdf, &mut Vec::new(), EncodeOptions::default()),
+            Err(EncodeError::MissingTrailer)
+        );
+    }
+
+    #[test]
+    fn encrypted_object_streams() {
+        let mut pdf = pdf();
+        pdf.sections[0].trailer.encrypt = Some(Object::Reference(Reference::new(6, 0)));
+        pdf.sections[0].xref = Xref::new(vec![UsedCompressedObject {
+            number: 7,
+            containing_object: 8,
+            index: 0,
+        }
+        .into()]);
+        let mut out = Vec::new();
+        assert_eq!(
+            SimpleEncoder::write_with(&pdf, &mut out, EncodeOptions::default()),
+            Err(EncodeError::Encrypted)
+        );
+        assert!(out.is_empty());
+
+        // Without object streams the encrypted objects are written as they are.
+        pdf.sections[0].xref = Xref::new(Vec::new());
+        SimpleEncoder::write_with(&pdf, &mut out, EncodeOptions::default()).unwrap();
+    }
+
+    #[test]
+    fn empty_update_round_trip() {
+        let mut pdf = pdf();
+        let mut update = pdf.sections[0].clone();
+        update.objects.clear();
+        pdf.sections.insert(0, update);
+        let mut out = Vec::new();
+        let options = EncodeOptions {
+            xref: XrefStyle::Table,
+            ..Default::default()
+        };
+        SimpleEncoder::write_with(&pdf, &mut out, options).unwrap();
+        let text = String::from_utf8_lossy(&out);
+        assert!(text.contains("xref\n0 0\ntrailer\n"), "{}", text);
+
+        let strict = ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let (_, reparsed) = parse_complete_with(out[..].into(), &strict).unwrap();
+        assert_eq!(reparsed.sections.len(), 2);
+        assert!(reparsed.sections[0].objects.is_empty());
+        assert_eq!(reparsed.page_count(), Ok(1));
+    }
+
+    /// `pdf()` written with a xref table and an update that frees object 6.
+    fn freed_fixture() -> Vec<u8> {
+        let mut out = Vec::new();
+        let options = EncodeOptions {
+            xref: XrefStyle::Table,
+            ..Default::default()
+        };
+        SimpleEncoder::write_with(&pdf(), &mut out, options).unwrap();
+        let (_, startxref) = crate::parse::startxref(out[..].into(), &Default::default()).unwrap();
+        push_xref_table(
+            &mut out,
+            &[(0, &[(6, 65535, false)]), (6, &[(0, 1, false)])],
+            &format!("<</Size 7 /Root 1 0 R /Prev {}>>", startxref),
+        );
+        out
+    }
+
+    #[test]
+    fn freed_objects_round_trip() {
+        let (_, parsed) = parse_complete(freed_fixture()[..].into()).unwrap();
+        assert!(matches!(parsed.lookup(6), Some((_, XrefEntry::Free(_)))));
+
+        for xref in [XrefStyle::Table, XrefStyle::Stream] {
+            let mut out = Vec::new();
+            let options = EncodeOptions {
+                xref,
+                ..Default::default()
+            };
+            SimpleEncoder::write_with(&parsed, &mut out, options).unwrap();
+
+            let (_, reparsed) = parse_complete(out[..].into()).unwrap();
+            assert_eq!(reparsed.sections.len(), 2);
+            assert_eq!(
+                reparsed.lookup(6).map(|(_, entry)| entry),
+                Some(&XrefEntry::Free(FreeObject {
+                    number: 6,
+                    generation: 1,
+                    next_free: 0,
+                })),
+                "{:?}",
+                xref
+            );
+            assert!(reparsed.objects().all(|(number, _, _)| number != 6));
+            assert_eq!(reparsed.page_count(), Ok(1));
+        }
+    }
+
+    /// Pretends that `offset` bytes were written before the output.
+    struct OffsetWriter {
+        offset: usize,
+        out: Vec<u8>,
+    }
+
+    impl Writer for OffsetWriter {
+        fn write(&mut self, buf: &[u8]) {
+            self.out.extend(buf);
+        }
+
+        fn position(&self) -> usize {
+            self.offset + self.out.len()
+        }
+    }
+
+    #[test]
+    fn values_out_of_range() {
+        // Offsets beyond 10 digits don't fit into a xref table.
+        let mut out = OffsetWriter {
+            offset: 10_000_000_000,
+            out: Vec::new(),
+        };
+        let options = EncodeOptions {
+            xref: XrefStyle::Table,
+            ..Default::default()
+        };
+        assert!(matches!(
+            SimpleEncoder::write_with(&pdf(), &mut out, options),
+            Err(EncodeError::ValueOutOfRange(offset)) if offset > 10_000_000_000
+        ));
+
+        // `Prev` is an integer.
+        let mut out = Vec::new();
+        assert_eq!(
+            write_section(
+                &pdf().sections[0],
+                &mut out,
+                EncodeOptions::default(),
+                Some(3_000_000_000),
+                &mut 1,
+                None,
+                None
+            ),
+            Err(EncodeError::ValueOutOfRange(3_000_000_000))
+        );
+
+        // The length of a stream is an integer.
+        let too_large = i32::MAX as usize + 1;
+        assert_eq!(integer(too_large), Err(IntegerOutOfRange(too_large)));
+        let mut trailer = pdf().sections[0].trailer.clone();
+        trailer.size = too_large;
+        assert_eq!(Dictionary::try_from(trailer), Err(IntegerOutOfRange(too_large)));
+    }
+}
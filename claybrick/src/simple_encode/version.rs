@@ -0,0 +1,181 @@
+//! The PDF version of written documents.
+
+use crate::pdf::{
+    document::{catalog::catalog_version, K_VERSION},
+    Name, Object, RawPdf, Reference,
+};
+
+use super::{EncodeOptions, XrefStyle};
+
+/// Cross reference streams require PDF 1.5.
+pub(super) const XREF_STREAM_VERSION: (u8, u8) = (1, 5);
+
+/// The catalog with the `/Version` that [EncodeOptions::version] requests.
+/// It replaces the catalog of the newest section that contains it.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct VersionedCatalog {
+    /// The index of the section.
+    pub(super) section: usize,
+    pub(super) number: usize,
+    pub(super) object: Object,
+}
+
+impl VersionedCatalog {
+    /// Replace the catalog of `pdf`, a copy of the written document.
+    pub(super) fn apply(self, pdf: &mut RawPdf) {
+        pdf.sections[self.section].objects.insert(self.number, self.object);
+    }
+}
+
+/// The version for the header and, if the catalog has to change, the
+/// catalog with the `/Version` that [EncodeOptions::version] requests.
+///
+/// The header keeps the version of `pdf` unless the requested version is
+/// older. A newer version is written to the catalog, since readers use the
+/// newer of both. Xref streams are replaced by tables for versions before
+/// 1.5.
+pub(super) fn versioned(
+    pdf: &RawPdf,
+    root: Reference,
+    options: &mut EncodeOptions,
+) -> ((u8, u8), Option<VersionedCatalog>) {
+    let catalog = catalog_version(pdf, &root);
+    let (header, changed, effective) = match options.version {
+        None => (pdf.version, None, pdf.effective_version()),
+        Some(version) => {
+            let header = pdf.version.min(version);
+            let entry = (version > header).then_some(version);
+            let changed = match entry != catalog {
+                true => with_catalog_version(pdf, root, entry),
+                false => None,
+            };
+            (header, changed, version)
+        }
+    };
+
+    if effective < XREF_STREAM_VERSION && options.xref == XrefStyle::Stream {
+        log::warn!(
+            "PDF {}.{} doesn't support xref streams, writing tables",
+            effective.0,
+            effective.1
+        );
+        options.xref = XrefStyle::Table;
+    }
+    (header, changed)
+}
+
+/// A copy of the catalog with its `/Version` set to `version`, or removed for
+/// `None`. The catalog can be an indirect object or, if it was stored in an
+/// object stream, a dictionary.
+fn with_catalog_version(pdf: &RawPdf, root: Reference, version: Option<(u8, u8)>) -> Option<VersionedCatalog> {
+    let number = root.index as usize;
+    let (section, object) = pdf
+        .sections
+        .iter()
+        .enumerate()
+        .find_map(|(index, section)| Some((index, section.objects.get(&number)?)))?;
+    let mut object = object.clone();
+    let catalog = match &mut object {
+        Object::Indirect(indirect) => match &mut *indirect.object {
+            Object::Dictionary(dict) => dict,
+            _ => return None,
+        },
+        Object::Dictionary(dict) => dict,
+        _ => return None,
+    };
+    match version {
+        Some((major, minor)) => {
+            let name = Name::new(format!("{}.{}", major, minor).into_bytes());
+            catalog.insert(K_VERSION.to_vec().into(), Object::Name(name));
+        }
+        None => {
+            catalog.remove(K_VERSION);
+        }
+    }
+    Some(VersionedCatalog {
+        section,
+        number,
+        object,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        pdf::{document::catalog::parse_version, tests::parsed_pdf},
+        simple_encode::{EncodeOptions, SimpleEncoder},
+    };
+
+    use super::*;
+
+    fn document(version: (u8, u8), catalog: &[u8]) -> RawPdf {
+        let mut pdf = parsed_pdf(
+            Reference::new(1, 0),
+            &[catalog, b"2 0 obj <</Type /Pages /Kids [] /Count 0>> endobj"],
+        );
+        pdf.version = version;
+        pdf
+    }
+
+    fn write(pdf: &RawPdf, version: Option<(u8, u8)>) -> (Vec<u8>, RawPdf) {
+        let mut out = Vec::new();
+        let options = EncodeOptions {
+            version,
+            ..Default::default()
+        };
+        SimpleEncoder::write_with(pdf, &mut out, options).unwrap();
+        let written = crate::read_bytes(out.clone()).unwrap();
+        (out, written)
+    }
+
+    fn written_catalog_version(pdf: &RawPdf) -> Option<(u8, u8)> {
+        catalog_version(pdf, &pdf.sections[0].trailer.root)
+    }
+
+    #[test]
+    fn keep_version() {
+        let pdf = document((1, 4), b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj");
+        let (out, written) = write(&pdf, None);
+        assert!(out.starts_with(b"%PDF-1.4\n"));
+        assert_eq!(written.version, (1, 4));
+        assert_eq!(written_catalog_version(&written), None);
+        // PDF 1.4 has no xref streams
+        assert!(out.windows(6).any(|w| w == b"\nxref\n"));
+    }
+
+    #[test]
+    fn override_version() {
+        let pdf = document((1, 4), b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj");
+        let (out, written) = write(&pdf, Some((1, 7)));
+        assert!(out.starts_with(b"%PDF-1.4\n"));
+        assert_eq!(written_catalog_version(&written), Some((1, 7)));
+        assert!(!out.windows(6).any(|w| w == b"\nxref\n"));
+        // the document isn't changed
+        assert_eq!(written_catalog_version(&pdf), None);
+
+        // older versions replace the header and the catalog entry
+        let pdf = document((1, 7), b"1 0 obj <</Type /Catalog /Pages 2 0 R /Version /2.0>> endobj");
+        let (out, written) = write(&pdf, Some((1, 3)));
+        assert!(out.starts_with(b"%PDF-1.3\n"));
+        assert_eq!(written_catalog_version(&written), None);
+        assert!(out.windows(6).any(|w| w == b"\nxref\n"));
+    }
+
+    #[test]
+    fn compressed_catalog() {
+        // Objects from object streams are dictionaries without object number.
+        let mut pdf = document((1, 4), b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj");
+        let catalog = pdf.sections[0].objects[&1].indirect().unwrap().object().clone();
+        pdf.sections[0].objects.insert(1, catalog);
+        let (_, written) = write(&pdf, Some((1, 7)));
+        assert_eq!(written_catalog_version(&written), Some((1, 7)));
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version(b"1.7"), Some((1, 7)));
+        assert_eq!(parse_version(b"2.0"), Some((2, 0)));
+        assert_eq!(parse_version(b"17"), None);
+        assert_eq!(parse_version(b"1.x"), None);
+    }
+}
@@ -0,0 +1,202 @@
+use crate::{
+    pdf::{
+        xref::{FreeObject, Unsupported, UsedCompressedObject, UsedObject, XrefEntry},
+        Xref,
+    },
+    simple_encode::EncodeError,
+    writer::Writer,
+};
+
+/// Largest byte offset and generation number of a xref table entry.
+const MAX_TABLE_OFFSET: u64 = 9_999_999_999;
+const MAX_TABLE_GENERATION: usize = 99_999;
+
+/// The three fields of an entry in a xref stream.
+fn fields(entry: &XrefEntry) -> [usize; 3] {
+    match entry {
+        XrefEntry::Free(FreeObject {
+            next_free, generation, ..
+        }) => [entry.type_num(), *next_free, *generation],
+        XrefEntry::Used(UsedObject {
+            byte_offset,
+            generation,
+            ..
+        }) => [entry.type_num(), *byte_offset, *generation],
+        XrefEntry::UsedCompressed(UsedCompressedObject {
+            containing_object,
+            index,
+            ..
+        }) => [entry.type_num(), *containing_object, *index],
+        XrefEntry::Unsupported(Unsupported { type_num, w1, w2, .. }) => [*type_num, *w1, *w2],
+    }
+}
+
+/// Number of bytes needed to store `value`.
+fn byte_width(value: usize) -> usize {
+    (usize::BITS - value.leading_zeros()).div_ceil(8) as usize
+}
+
+/// The minimal byte widths (`W`) of the three fields of the xref stream
+/// entries. Each field is at least one byte wide.
+pub(crate) fn xref_stream_widths(xref: &Xref) -> [usize; 3] {
+    xref.entries().fold([1; 3], |widths, entry| {
+        let fields = fields(entry);
+        std::array::from_fn(|i| widths[i].max(byte_width(fields[i])))
+    })
+}
+
+/// Append `value` as big endian number with `width` bytes. The width must be
+/// large enough to hold the value, see [xref_stream_widths].
+fn encode_xref_entry(value: usize, width: usize, out: &mut Vec<u8>) {
+    debug_assert!(byte_width(value) <= width, "{} doesn't fit into {} bytes", value, width);
+    out.extend(
+        (0..width)
+            .rev()
+            .map(|i| value.checked_shr(8 * i as u32).unwrap_or(0) as u8),
+    );
+}
+
+/// Ranges of contiguous object numbers `(first, count)`.
+pub(crate) fn subsections(xref: &Xref) -> Vec<(usize, usize)> {
+    let mut subsections: Vec<(usize, usize)> = Vec::new();
+    for entry in xref.entries() {
+        match subsections.last_mut() {
+            Some((first, count)) if *first + *count == entry.number() => *count += 1,
+            _ => subsections.push((entry.number(), 1)),
+        }
+    }
+    subsections
+}
+
+/// Write a classic xref table starting with the `xref` keyword.
+///
+/// Each entry is exactly 20 bytes long. Fails if an offset or generation
+/// doesn't fit into its field.
+pub(crate) fn write_xref_table(xref: &Xref, writer: &mut dyn Writer) -> Result<(), EncodeError> {
+    writer.write(b"xref\n");
+    let mut entries = xref.entries();
+    let mut subsections = subsections(xref);
+    // Readers expect at least one subsection, even if it's empty.
+    if subsections.is_empty() {
+        subsections.push((0, 0));
+    }
+    for (first, count) in subsections {
+        writer.write(format!("{} {}\n", first, count).as_bytes());
+        for entry in entries.by_ref().take(count) {
+            let (offset, generation, kind) = match entry {
+                XrefEntry::Free(FreeObject {
+                    next_free, generation, ..
+                }) => (*next_free, *generation, 'f'),
+                XrefEntry::Used(UsedObject {
+                    byte_offset,
+                    generation,
+                    ..
+                }) => (*byte_offset, *generation, 'n'),
+                // Compressed objects can't be represented in a table.
+                XrefEntry::UsedCompressed(_) | XrefEntry::Unsupported(_) => (0, 0, 'f'),
+            };
+            if offset as u64 > MAX_TABLE_OFFSET || generation > MAX_TABLE_GENERATION {
+                log::error!("Xref entry {:?} doesn't fit into a xref table", entry);
+                return Err(EncodeError::ValueOutOfRange(offset.max(generation)));
+            }
+            writer.write(format!("{:010} {:05} {}\r\n", offset, generation, kind).as_bytes());
+        }
+    }
+    Ok(())
+}
+
+/// The data of a xref stream with the given field widths.
+pub(crate) fn xref_stream_data(xref: &Xref, widths: [usize; 3]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(xref.len() * widths.iter().sum::<usize>());
+    for entry in xref.entries() {
+        for (value, width) in fields(entry).into_iter().zip(widths) {
+            encode_xref_entry(value, width, &mut data);
+        }
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xref() -> Xref {
+        Xref::new(vec![
+            FreeObject {
+                number: 0,
+                generation: 65535,
+                next_free: 0,
+            }
+            .into(),
+            UsedObject {
+                number: 1,
+                byte_offset: 15,
+                generation: 0,
+            }
+            .into(),
+            UsedObject {
+                number: 2,
+                byte_offset: 1234,
+                generation: 1,
+            }
+            .into(),
+            UsedObject {
+                number: 7,
+                byte_offset: 99999,
+                generation: 0,
+            }
+            .into(),
+        ])
+    }
+
+    #[test]
+    fn table() {
+        let mut out = Vec::new();
+        write_xref_table(&xref(), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "xref\n0 3\n0000000000 65535 f\r\n0000000015 00000 n\r\n0000001234 00001 n\r\n\
+            7 1\n0000099999 00000 n\r\n"
+        );
+    }
+
+    #[test]
+    fn stream_data() {
+        let xref = xref();
+        let widths = xref_stream_widths(&xref);
+        // The largest offset 99999 needs 3 bytes, generation 65535 needs 2.
+        assert_eq!(widths, [1, 3, 2]);
+
+        let data = xref_stream_data(&xref, widths);
+        assert_eq!(data.len(), 4 * 6);
+        assert_eq!(&data[6..12], &[1, 0, 0, 15, 0, 0]);
+
+        // The parser numbers the entries consecutively, `Index` is not
+        // part of the data.
+        let (_, entries) = crate::parse::xref::xref_stream_data(widths, data[..].into()).unwrap();
+        assert_eq!(
+            entries.iter().map(fields).collect::<Vec<_>>(),
+            xref.entries().map(fields).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn small_offsets() {
+        let xref = Xref::new(vec![
+            UsedObject {
+                number: 1,
+                byte_offset: 65535,
+                generation: 0,
+            }
+            .into(),
+            UsedCompressedObject {
+                number: 2,
+                containing_object: 3,
+                index: 0,
+            }
+            .into(),
+        ]);
+        assert_eq!(xref_stream_widths(&xref), [1, 2, 1]);
+        assert_eq!(xref_stream_data(&xref, [1, 2, 1]), vec![1, 0xFF, 0xFF, 0, 2, 0, 3, 0]);
+    }
+}
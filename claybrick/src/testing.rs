@@ -0,0 +1,141 @@
+//! Assertions for tests of code that reads or writes documents.
+
+use std::fmt::Display;
+
+use crate::{
+    diff::{diff_with, Comparison, PdfDiff},
+    pdf::RawPdf,
+};
+
+/// The documents are not equivalent, see [assert_equivalent].
+#[derive(Debug, Clone, PartialEq)]
+pub struct EquivalenceError {
+    /// The object numbers are those of the compared documents.
+    pub diff: Box<PdfDiff>,
+}
+
+impl Display for EquivalenceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "The documents are not equivalent:\n{}", self.diff)
+    }
+}
+
+impl std::error::Error for EquivalenceError {}
+
+/// Check that both documents have the same content, e.g. a document and the
+/// result of writing and parsing it again.
+///
+/// The objects reachable from `Root` and `Info` of the newest trailers are
+/// matched like [diff()](crate::diff()) does, thus object numbers, xref
+/// sections and the order of dictionary entries don't matter. Numbers are equal
+/// within an epsilon, strings are compared by their bytes and streams by their
+/// decoded data.
+pub fn assert_equivalent(a: &RawPdf, b: &RawPdf) -> Result<(), EquivalenceError> {
+    let diff = diff_with(a, b, Comparison::Equivalent);
+    match diff.is_empty() {
+        true => Ok(()),
+        false => Err(EquivalenceError { diff: Box::new(diff) }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::{
+        build::{Info, PdfBuilder},
+        pdf::{tests::parsed_pdf, Reference},
+        simple_encode::{EncodeError, EncodeOptions, SimpleEncoder, XrefStyle},
+    };
+
+    use super::*;
+
+    /// Documents written by the builder and the parsable documents of the
+    /// fuzz corpus.
+    fn fixtures() -> Vec<(String, RawPdf)> {
+        let mut builder = PdfBuilder::new();
+        builder.set_info(Info {
+            title: Some("Round trip".to_string()),
+            ..Default::default()
+        });
+        for index in 0..3 {
+            let page = builder.add_page(612.0, 792.0 + index as f32 / 3.0);
+            builder.set_page_content(page, &b"BT /F1 12 Tf (Hello) Tj ET"[..]);
+        }
+        let mut fixtures = vec![("builder".to_string(), builder.finish().unwrap())];
+
+        let mut pdf = parsed_pdf(Reference::new(1, 0), &[
+            b"1 0 obj <</Type /Catalog /Pages 2 0 R /Odd#20Name /A#2FB#23 /Strings [(a\\)b\\\\c) <00FF> ()]>> endobj",
+            b"2 0 obj <</Type /Pages /Kids [3 0 R] /Count 1>> endobj",
+            b"3 0 obj <</Type /Page /Parent 2 0 R /MediaBox [0 0 612.5 -792.25] /Contents 4 0 R \
+              /Resources <<>> /Flags [true false null -0.5 .25 1000000]>> endobj",
+            b"4 0 obj <</Length 8 /Filter /ASCIIHexDecode>> stream\n48656C6C\nendstream endobj",
+            b"5 0 obj <</Title (Objects) /Producer <FEFF0041>>> endobj",
+        ]);
+        pdf.sections[0].trailer.info = Some(Reference::new(5, 0));
+        fixtures.push(("objects".to_string(), pdf));
+
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if let Ok(pdf) = crate::read_bytes(std::fs::read(&path).unwrap()) {
+                fixtures.push((path.file_name().unwrap().to_string_lossy().into_owned(), pdf));
+            }
+        }
+        fixtures
+    }
+
+    #[test]
+    fn round_trip_fixtures() {
+        let options = [
+            EncodeOptions::default(),
+            EncodeOptions {
+                xref: XrefStyle::Table,
+                ..Default::default()
+            },
+            EncodeOptions {
+                compress_streams: true,
+                use_object_streams: true,
+                ..Default::default()
+            },
+            EncodeOptions {
+                prune_unreferenced: true,
+                ..Default::default()
+            },
+        ];
+        for (name, pdf) in fixtures() {
+            for options in &options {
+                let mut out = Vec::new();
+                match SimpleEncoder::write_with(&pdf, &mut out, *options) {
+                    // some corpus documents have no catalog
+                    Err(EncodeError::MissingRoot(_)) => continue,
+                    result => result.unwrap(),
+                }
+                let written = crate::read_bytes(out).unwrap_or_else(|e| panic!("{} {:?}: {:?}", name, options, e));
+                if let Err(e) = assert_equivalent(&pdf, &written) {
+                    panic!("{} {:?}: {}", name, options, e);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn not_equivalent() {
+        let pdf = |pages: &[u8]| {
+            parsed_pdf(
+                Reference::new(1, 0),
+                &[b"1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj", pages],
+            )
+        };
+        let a = pdf(b"2 0 obj <</Type /Pages /Kids [] /Count 0 /Title (x) /Size 1.00001>> endobj");
+        let b = pdf(b"2 0 obj <</Size 1 /Title <78> /Type /Pages /Kids [] /Count 0>> endobj");
+        assert_eq!(assert_equivalent(&a, &b), Ok(()));
+
+        let c = pdf(b"2 0 obj <</Type /Pages /Kids [] /Count 0 /Title (y) /Size 1>> endobj");
+        let err = assert_equivalent(&a, &c).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The documents are not equivalent:\nObject 2:\n  /Title: (x) -> (y)\n"
+        );
+    }
+}
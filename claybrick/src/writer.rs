@@ -1,9 +1,19 @@
+use std::io;
+
+use crate::simple_encode::{EncodeError, DEFAULT_PRECISION};
+
 pub trait Writer {
     /// Write the buffer.
     fn write(&mut self, buf: &[u8]);
 
     /// Index of the next byte that will be written.
     fn position(&self) -> usize;
+
+    /// Number of decimal places of written real numbers, see
+    /// [EncodeOptions::real_precision](crate::simple_encode::EncodeOptions::real_precision).
+    fn real_precision(&self) -> usize {
+        DEFAULT_PRECISION
+    }
 }
 
 impl Writer for Vec<u8> {
@@ -16,6 +26,61 @@ impl Writer for Vec<u8> {
     }
 }
 
+/// Adapter that writes to an [io::Write] sink, e.g. a `BufWriter<File>`,
+/// without buffering the complete output.
+///
+/// [Writer::write] can't fail, so the first I/O error is recorded and
+/// returned by [IoWriter::into_result]. Everything written after an error is
+/// dropped.
+pub struct IoWriter<W: io::Write> {
+    inner: W,
+    position: usize,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> IoWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            position: 0,
+            error: None,
+        }
+    }
+
+    /// The first error that occurred while writing.
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    /// Flush the sink and return it, or the first error that occurred.
+    pub fn into_result(mut self) -> io::Result<W> {
+        match self.error {
+            Some(err) => Err(err),
+            None => {
+                self.inner.flush()?;
+                Ok(self.inner)
+            }
+        }
+    }
+}
+
+impl<W: io::Write> Writer for IoWriter<W> {
+    fn write(&mut self, buf: &[u8]) {
+        if self.error.is_none() {
+            if let Err(err) = self.inner.write_all(buf) {
+                log::error!("Error while writing at {}: {}", self.position, err);
+                self.error = Some(err);
+            }
+        }
+        // Keep counting so offsets stay consistent with the attempted output.
+        self.position += buf.len();
+    }
+
+    fn position(&self) -> usize {
+        self.position
+    }
+}
+
 struct DummyWriter {
     size: usize,
 }
@@ -47,11 +112,73 @@ impl Writer for DummyWriter {
 }
 
 pub trait Encoder<T> {
-    fn encoded_len(o: &T) -> usize {
+    fn encoded_len(o: &T) -> Result<usize, EncodeError> {
         let mut out = DummyWriter::new();
-        Self::write_to(o, &mut out);
-        out.len()
+        Self::write_to(o, &mut out)?;
+        Ok(out.len())
     }
 
-    fn write_to(o: &T, writer: &mut dyn Writer);
+    /// Write the encoded value. Fails if the value can't be represented,
+    /// e.g. because a number is out of range.
+    fn write_to(o: &T, writer: &mut dyn Writer) -> Result<(), EncodeError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        build::PdfBuilder,
+        simple_encode::{EncodeOptions, SimpleEncoder},
+    };
+
+    use super::*;
+
+    /// Fails after `limit` bytes were written.
+    #[derive(Debug)]
+    struct FailingWriter {
+        written: usize,
+        limit: usize,
+    }
+
+    impl io::Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.written + buf.len() > self.limit {
+                return Err(io::Error::other("disk full"));
+            }
+            self.written += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn document() -> crate::pdf::RawPdf {
+        let mut builder = PdfBuilder::new();
+        let page = builder.add_page(100.0, 100.0);
+        builder.set_page_content(page, &b"0 0 m 100 100 l S"[..]);
+        builder.finish().unwrap()
+    }
+
+    #[test]
+    fn io_writer() {
+        let pdf = document();
+        let mut expected = Vec::new();
+        SimpleEncoder::write_with(&pdf, &mut expected, EncodeOptions::default()).unwrap();
+
+        let mut out = IoWriter::new(io::BufWriter::new(Vec::new()));
+        SimpleEncoder::write_with(&pdf, &mut out, EncodeOptions::default()).unwrap();
+        assert_eq!(out.position(), expected.len());
+        let written = out.into_result().unwrap().into_inner().unwrap();
+        assert_eq!(written, expected);
+    }
+
+    #[test]
+    fn io_writer_error() {
+        let mut out = IoWriter::new(FailingWriter { written: 0, limit: 100 });
+        SimpleEncoder::write_with(&document(), &mut out, EncodeOptions::default()).unwrap();
+        assert_eq!(out.error().map(io::Error::to_string).as_deref(), Some("disk full"));
+        assert!(out.position() > 100);
+        assert_eq!(out.into_result().unwrap_err().kind(), io::ErrorKind::Other);
+    }
 }
@@ -0,0 +1,108 @@
+use std::sync::{Arc, Mutex};
+
+use claybrick::{
+    build::PdfBuilder,
+    parse::{ParseOptions, ParseWarning},
+    simple_encode::{EncodeOptions, SimpleEncoder, XrefStyle},
+};
+
+const CONTENTS: [&[u8]; 2] = [b"0 0 m 100 100 l S", b"BT ET"];
+
+/// A document whose `startxref` points nowhere and whose content streams
+/// declare wrong lengths.
+fn broken() -> Vec<u8> {
+    let mut builder = PdfBuilder::new();
+    for content in CONTENTS {
+        let page = builder.add_page(612.0, 792.0);
+        builder.set_page_content(page, content);
+    }
+    let mut out = Vec::new();
+    let options = EncodeOptions {
+        xref: XrefStyle::Table,
+        ..Default::default()
+    };
+    SimpleEncoder::write_with(&builder.finish().unwrap(), &mut out, options).unwrap();
+
+    let replace = |out: Vec<u8>, from: &[u8], to: &[u8]| {
+        let index = out.windows(from.len()).rposition(|w| w == from).unwrap();
+        [&out[..index], to, &out[index + from.len()..]].concat()
+    };
+    let out = replace(out, b"/Length 17", b"/Length 99");
+    let out = replace(out, b"/Length 5", b"/Length 2");
+    let startxref = out.windows(9).rposition(|w| w == b"startxref").unwrap();
+    [&out[..startxref], &b"startxref\n12\n%%EOF\n"[..]].concat()
+}
+
+#[test]
+fn repair_broken_document() {
+    let input = broken();
+    assert!(claybrick::read_bytes_with(
+        input.clone(),
+        &ParseOptions {
+            strict: true,
+            ..Default::default()
+        }
+    )
+    .is_err());
+
+    let (repaired, warnings) = claybrick::repair(&input).unwrap();
+    assert!(warnings.contains(&ParseWarning::XrefRebuilt), "{:?}", warnings);
+    let mismatches = warnings
+        .iter()
+        .filter(|warning| matches!(warning, ParseWarning::StreamLengthMismatch { .. }))
+        .count();
+    assert_eq!(mismatches, 2, "{:?}", warnings);
+
+    let warnings = Arc::new(Mutex::new(Vec::new()));
+    let collected = warnings.clone();
+    let options = ParseOptions {
+        warnings: Some(Arc::new(move |warning| collected.lock().unwrap().push(warning))),
+        strict: true,
+        ..Default::default()
+    };
+    let pdf = claybrick::read_bytes_with(repaired, &options).unwrap();
+    assert_eq!(*warnings.lock().unwrap(), vec![]);
+    assert_eq!(pdf.sections().len(), 1);
+
+    let catalog = pdf.catalog().unwrap();
+    assert_eq!(catalog.page_count(), Ok(2));
+    for (index, content) in CONTENTS.into_iter().enumerate() {
+        let page = catalog.page(index).unwrap();
+        let data = page.contents()[0].filtered_data().unwrap();
+        assert_eq!(&data[..], content);
+    }
+}
+
+#[cfg(feature = "std-fs")]
+#[test]
+fn repair_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("broken.pdf");
+    std::fs::write(&path, broken()).unwrap();
+    let (repaired, warnings) = claybrick::repair_file(&path).unwrap();
+    assert_eq!(warnings, claybrick::repair(&broken()).unwrap().1);
+    assert_eq!(claybrick::read_bytes(repaired).unwrap().page_count(), Ok(2));
+}
+
+#[cfg(feature = "encryption")]
+#[test]
+fn repair_encrypted() {
+    use claybrick::simple_encode::EncodeError;
+
+    let mut builder = PdfBuilder::new();
+    let page = builder.add_page(612.0, 792.0);
+    builder.set_page_content(page, CONTENTS[0]);
+    let mut out = Vec::new();
+    SimpleEncoder::write_encrypted(
+        &builder.finish().unwrap(),
+        &mut out,
+        EncodeOptions::default(),
+        &Default::default(),
+    )
+    .unwrap();
+
+    assert!(matches!(
+        claybrick::repair(&out),
+        Err(claybrick::CbError::Encode(EncodeError::Encrypted))
+    ));
+}
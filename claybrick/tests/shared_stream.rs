@@ -0,0 +1,47 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use claybrick::{build::PdfBuilder, simple_encode::SimpleEncoder};
+
+/// Counts the allocated bytes.
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATED.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn large_stream_is_not_copied() {
+    const SIZE: usize = 10 * 1024 * 1024;
+    let mut builder = PdfBuilder::new();
+    let page = builder.add_page(100.0, 100.0);
+    builder.set_page_content(page, vec![b' '; SIZE]);
+    let mut input = Vec::new();
+    SimpleEncoder::write_with(&builder.finish().unwrap(), &mut input, Default::default()).unwrap();
+
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let pdf = claybrick::read_bytes(input).unwrap();
+    let allocated = ALLOCATED.load(Ordering::Relaxed) - before;
+
+    assert_eq!(pdf.page(0).unwrap().contents()[0].data.len(), SIZE);
+    assert!(allocated < SIZE, "{} bytes allocated", allocated);
+}
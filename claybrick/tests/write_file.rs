@@ -0,0 +1,39 @@
+#![cfg(feature = "std-fs")]
+
+use claybrick::{
+    build::PdfBuilder,
+    simple_encode::{EncodeOptions, XrefStyle},
+    CbError,
+};
+
+#[test]
+fn write_and_read_file() {
+    let mut builder = PdfBuilder::new();
+    builder.add_page(612.0, 792.0);
+    builder.add_page(595.0, 842.0);
+    let pdf = builder.finish().unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("written.pdf");
+    let options = EncodeOptions {
+        xref: XrefStyle::Table,
+        sync_file: true,
+        ..Default::default()
+    };
+    claybrick::write_file(&pdf, &path, &options).unwrap();
+    let written = claybrick::read_file(&path).unwrap();
+    assert_eq!(written.page_count(), Ok(2));
+
+    let mut out = Vec::new();
+    claybrick::write_to(&written, &mut out, &options).unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), out);
+
+    let missing = dir.path().join("missing").join("written.pdf");
+    match claybrick::write_file(&pdf, &missing, &options) {
+        Err(CbError::Io { path, kind, .. }) => {
+            assert_eq!(path.as_deref(), Some(missing.as_path()));
+            assert_eq!(kind, std::io::ErrorKind::NotFound);
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+}
@@ -20,11 +20,11 @@ pub fn bench_decode(c: &mut Criterion) {
 
     c.bench_function("decode-example", |b| b.iter(|| decode(encoded)));
 
-    let decoded: Vec<u8> = (0..u8::MAX).into_iter().cycle().take(10 * 1024 * 1024).collect();
+    let decoded: Vec<u8> = (0..u8::MAX).cycle().take(10 * 1024 * 1024).collect();
     let encoded = aw_ascii85::encode(&decoded[..]);
     c.bench_function("decode-10mb", |b| b.iter(|| decode(&encoded[..])));
 
-    let decoded: Vec<u8> = iter::repeat(b'z').take(10 * 1024 * 1024).collect();
+    let decoded: Vec<u8> = iter::repeat_n(b'z', 10 * 1024 * 1024).collect();
     let encoded = aw_ascii85::encode(&decoded[..]);
     c.bench_function("decode-z", |b| b.iter(|| decode(&encoded[..])));
 }
@@ -34,10 +34,10 @@ pub fn bench_encode(c: &mut Criterion) {
 
     c.bench_function("encode-example", |b| b.iter(|| decode(&EXAMPLE_PLAIN[..])));
 
-    let decoded: Vec<u8> = (0..u8::MAX).into_iter().cycle().take(10 * 1024 * 1024).collect();
+    let decoded: Vec<u8> = (0..u8::MAX).cycle().take(10 * 1024 * 1024).collect();
     c.bench_function("encode-10mb", |b| b.iter(|| decode(&decoded[..])));
 
-    let decoded: Vec<u8> = iter::repeat(0_u8).take(10 * 1024 * 1024).collect();
+    let decoded: Vec<u8> = iter::repeat_n(0_u8, 10 * 1024 * 1024).collect();
     c.bench_function("encode-z", |b| b.iter(|| decode(&decoded[..])));
 }
 
@@ -92,7 +92,7 @@ pub fn decode(mut data: &[u8]) -> Result<Vec<u8>, DecodeError> {
 
     let mut stream = data.iter().filter(|b| !b.is_ascii_whitespace());
 
-    let mut out = Vec::with_capacity((data.len() + 4) / 5 * 4);
+    let mut out = Vec::with_capacity(data.len().div_ceil(5) * 4);
 
     loop {
         match stream.next() {
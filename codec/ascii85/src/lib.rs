@@ -46,7 +46,7 @@ mod tests {
     pub(crate) const EXAMPLE_PLAIN: &[u8; 269] = b"Man is distinguished, not only by his reason, but by this singular passion from other animals, which is a lust of the mind, that by a perseverance of delight in the continued and indefatigable generation of knowledge, exceeds the short vehemence of any carnal pleasure.";
 
     pub(crate) fn decode_samples() -> Vec<(&'static [u8], &'static str)> {
-        return vec![
+        vec![
             (&b""[..], "<~~>"),
             (&b""[..], "~>"),
             (&b""[..], "<~"),
@@ -61,11 +61,11 @@ mod tests {
             (&[0; 4], "<~z~>"),
             (&[0; 16], "zzzz"),
             (EXAMPLE_PLAIN, EXAMPLE_CODEC),
-        ];
+        ]
     }
 
     pub(crate) fn encode_samples() -> Vec<(&'static [u8], &'static str)> {
-        return vec![
+        vec![
             (&b""[..], "~>"),
             (&b"M"[..], "9`~>"),
             (&b"Ma"[..], "9jn~>"),
@@ -75,6 +75,6 @@ mod tests {
             (&[0; 4], "z~>"),
             (&[0; 16], "zzzz~>"),
             (EXAMPLE_PLAIN, EXAMPLE_CODEC),
-        ];
+        ]
     }
 }